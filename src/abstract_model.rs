@@ -1,20 +1,42 @@
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tracing::debug;
 
-use stateright::{Model, Property};
+use stateright::{Expectation, Fingerprint, Model, Property};
 
+use crate::api::patch::Patch;
 use crate::arbitrary_client::ArbitraryClient;
 use crate::arbitrary_client::ArbitraryClientAction;
+use crate::arbitrary_client::ArbitraryClientCfg;
 use crate::controller::util::get_node_condition;
-use crate::controller::{Controller, Controllers};
-use crate::resources::Node;
+use crate::controller::{deployment, job, node, nodelifecycle};
+use crate::controller::{Controller, ControllerStates, Controllers};
+use crate::coverage::ActionCoverage;
+use crate::events::{self, EventRecording};
+use crate::leader_election::{self, Election};
+use crate::node_faults;
+use crate::rbac::{Authorizer, Permission, Role, Verb};
+use crate::recording::ClientOperation;
 use crate::resources::{
-    ConditionStatus, ControllerRevision, Deployment, Job, NodeConditionType, PersistentVolumeClaim,
-    Pod, ReplicaSet, ResourceQuantities, StatefulSet,
+    ConditionStatus, ConfigMap, ControllerRevision, CronJob, Deployment, DeploymentRollback,
+    Endpoints, HorizontalPodAutoscaler, Job, Lease, Namespace, NodeConditionType, PersistentVolume,
+    PersistentVolumeClaim, Pod, ReplicaSet, ReplicationController, ResourceQuantities, Scale,
+    Secret, Service, StatefulSet, Subject,
 };
-use crate::state::RawState;
-use crate::state::{history::ConsistencySetup, revision::Revision, State};
+use crate::resources::{Meta, Metadata, Node, Spec};
+use crate::state::{
+    history::{eventual::ReplicaMerge, Compaction, ConsistencySetup, SessionGuarantee},
+    resources::Resources,
+    revision::Revision,
+    State,
+};
+use crate::state::{RawState, ResourceKind, StateView};
+use crate::user_properties::UserProperty;
+use crate::workqueue;
 
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
@@ -25,32 +47,966 @@ pub struct AbstractModelCfg {
     pub initial_state: RawState,
     /// The consistency level of the state.
     pub consistency_level: ConsistencySetup,
+    /// Alternative implementations that the controller at the given index can be upgraded to at
+    /// some point during the run.
+    pub upgrades: BTreeMap<usize, Controllers>,
+    /// Shadow implementations for the controller at the given index.
+    /// Shadows step alongside their primary on the same views but their actions are only compared
+    /// against the primary's, never applied.
+    pub shadows: BTreeMap<usize, Controllers>,
+    /// Whether controllers can have their caches rebuilt from a relist that is missing a kind of
+    /// resource.
+    pub relist_faults: bool,
+    /// Whether controllers read through watch caches that lag behind the state until they are
+    /// resynced, rather than reading any revision the consistency level allows.
+    pub watch_caches: bool,
+    /// Whether controllers send their actions to the API server as messages that can be delayed,
+    /// reordered, dropped and duplicated, with the number of times that they resend dropped ones.
+    /// When not given actions are applied as soon as they are taken.
+    pub message_retries: Option<u32>,
+    /// Whether controllers queue writes that fail to be retried after a delay growing with each
+    /// failure, rather than only retrying once they see a new revision.
+    pub rate_limited_retries: bool,
+    /// Whether nodes can be partitioned from the control plane.
+    pub node_partitions: bool,
+    /// Whether nodes can crash, losing the pods they run until they are restarted.
+    pub node_crashes: bool,
+    /// Whether controllers other than nodes can crash, losing their local state until they are
+    /// restarted.
+    pub controller_crashes: bool,
+    /// How many seconds the clock of the controller at each index runs ahead of the cluster's,
+    /// for the time that it sees and the creation timestamps of what it creates.
+    pub clock_skews: BTreeMap<usize, u64>,
+    /// Whether nodes can be drained of their pods and uncordoned again.
+    pub node_drains: bool,
+    /// Whether nodes can come under memory and disk pressure, evicting their pods.
+    pub node_pressure: bool,
+    /// Whether replicas of controllers elect a leader to act, rather than all acting at once.
+    pub leader_election: bool,
+    /// Roles restricting the actions of the controller at the given index.
+    /// Controllers without a role are unrestricted.
+    pub roles: BTreeMap<usize, Role>,
+    /// Identities that the controller at the given index acts under, its actions only being
+    /// allowed by the roles bound to them in the state.
+    pub identities: BTreeMap<usize, Subject>,
+    /// Whether controllers record events about their actions.
+    pub events: EventRecording,
+    /// The session guarantees of the controller at the given index.
+    /// Controllers without one only get monotonic reads, unless the consistency level is
+    /// read-your-writes.
+    pub sessions: BTreeMap<usize, SessionGuarantee>,
+    /// How replicas of an eventually consistent state merge their resources.
+    pub replica_merge: ReplicaMerge,
+    /// How much of the history of the state to keep.
+    pub compaction: Compaction,
+    /// Scopes narrowing the resources that the controller at the given index sees.
+    /// Controllers without a scope see everything.
+    pub scopes: BTreeMap<usize, ControllerScope>,
+    /// Kinds of actions that are left out of the exploration.
+    pub disabled_actions: BTreeSet<ActionKind>,
+    /// The indices of controllers that are weakly fair, those that stay enabled eventually
+    /// stepping.
+    pub fair_controllers: BTreeSet<usize>,
+    /// The number of steps that a weakly fair controller can stay enabled for without stepping,
+    /// after which it steps next.
+    pub fairness_bound: usize,
+    /// The names of deployments that have to keep enough replicas available while rolling out.
+    pub rollout_availability: BTreeSet<String>,
+    /// Recorded client operations that are taken in order, alongside the arbitrary client.
+    pub client_operations: Vec<ClientOperation>,
+    /// What the arbitrary client may change.
+    pub arbitrary_client: ArbitraryClientCfg,
+    /// Properties loaded at runtime, checked by the properties at the same positions.
+    pub user_properties: Vec<UserProperty>,
+    /// Which of the properties to check.
+    pub property_selection: PropertySelection,
     #[derivative(Debug = "ignore")]
     pub properties: Vec<Property<AbstractModel>>,
 }
 
-#[derive(derivative::Derivative)]
+#[derive(Clone, derivative::Derivative)]
 #[derivative(Debug)]
 pub struct AbstractModel {
     pub controllers: Vec<Controllers>,
+    pub upgrades: BTreeMap<usize, Controllers>,
+    pub shadows: BTreeMap<usize, Controllers>,
+    pub relist_faults: bool,
+    pub watch_caches: bool,
+    pub message_retries: Option<u32>,
+    pub rate_limited_retries: bool,
+    pub node_partitions: bool,
+    pub node_crashes: bool,
+    pub controller_crashes: bool,
+    pub clock_skews: BTreeMap<usize, u64>,
+    pub node_drains: bool,
+    pub node_pressure: bool,
+    pub leader_election: bool,
+    pub events: EventRecording,
+    pub sessions: BTreeMap<usize, SessionGuarantee>,
+    pub replica_merge: ReplicaMerge,
+    pub compaction: Compaction,
+    pub authorizer: Authorizer,
+    pub scopes: BTreeMap<usize, ControllerScope>,
+    pub disabled_actions: BTreeSet<ActionKind>,
+    pub fair_controllers: BTreeSet<usize>,
+    pub fairness_bound: usize,
+    pub rollout_availability: BTreeSet<String>,
+    pub client_operations: Vec<ClientOperation>,
+    pub arbitrary_client: ArbitraryClientCfg,
+    pub user_properties: Vec<UserProperty>,
+    pub property_selection: PropertySelection,
     pub initial_states: Vec<State>,
+    /// The resources that the model started from, which the arbitrary client creates again once
+    /// deleted.
+    pub initial_resources: RawState,
+    /// Fingerprints of states explored in an earlier run, which are skipped along with all that
+    /// follows them.
+    #[derivative(Debug = "ignore")]
+    pub explored: Arc<BTreeSet<Fingerprint>>,
+    /// Set to stop the check early, leaving every state after it unexplored so that the check
+    /// finishes and reports what it found so far.
+    #[derivative(Debug = "ignore")]
+    pub stop: Arc<AtomicBool>,
+    /// Where to record the actions that controllers take, if anywhere.
+    #[derivative(Debug = "ignore")]
+    pub coverage: Option<ActionCoverage>,
     #[derivative(Debug = "ignore")]
     pub properties: Vec<Property<Self>>,
 }
 
 impl AbstractModel {
     pub fn new(cfg: AbstractModelCfg) -> Self {
+        let mut sessions = cfg.sessions;
+        if cfg.consistency_level == ConsistencySetup::ReadYourWrites {
+            // the history only gives the guarantee to controllers that keep track of their writes
+            for i in 0..cfg.controllers.len() {
+                sessions
+                    .entry(i)
+                    .or_insert(SessionGuarantee::ReadYourWrites);
+            }
+        }
+        let initial_resources = cfg.initial_state.clone();
         let mut state = State::new(cfg.initial_state, cfg.consistency_level);
+        state.set_event_recording(cfg.events);
         for c in &cfg.controllers {
             state.add_controller(c.new_state());
         }
+        for (i, shadow) in &cfg.shadows {
+            state.update_shadow(*i, shadow.new_state());
+        }
         let initial_states = vec![state];
+        let roles = cfg
+            .roles
+            .into_iter()
+            .map(|(i, role)| (i, (cfg.controllers[i].name(), role)))
+            .collect();
         Self {
             controllers: cfg.controllers,
+            upgrades: cfg.upgrades,
+            shadows: cfg.shadows,
+            relist_faults: cfg.relist_faults,
+            watch_caches: cfg.watch_caches,
+            message_retries: cfg.message_retries,
+            rate_limited_retries: cfg.rate_limited_retries,
+            node_partitions: cfg.node_partitions,
+            node_crashes: cfg.node_crashes,
+            controller_crashes: cfg.controller_crashes,
+            clock_skews: cfg.clock_skews,
+            node_drains: cfg.node_drains,
+            node_pressure: cfg.node_pressure,
+            leader_election: cfg.leader_election,
+            events: cfg.events,
+            sessions,
+            replica_merge: cfg.replica_merge,
+            compaction: cfg.compaction,
+            authorizer: Authorizer::new(roles).with_identities(cfg.identities),
+            scopes: cfg.scopes,
+            disabled_actions: cfg.disabled_actions,
+            fair_controllers: cfg.fair_controllers,
+            fairness_bound: cfg.fairness_bound,
+            rollout_availability: cfg.rollout_availability,
+            client_operations: cfg.client_operations,
+            arbitrary_client: cfg.arbitrary_client,
+            user_properties: cfg.user_properties,
+            property_selection: cfg.property_selection,
             initial_states,
+            initial_resources,
+            explored: Default::default(),
+            stop: Default::default(),
+            coverage: None,
             properties: cfg.properties,
         }
     }
+
+    /// The controller currently running at the given index, taking upgrades into account.
+    pub fn controller(&self, state: &State, controller_index: usize) -> &Controllers {
+        if state.controller_upgraded(controller_index) {
+            &self.upgrades[&controller_index]
+        } else {
+            &self.controllers[controller_index]
+        }
+    }
+
+    /// The view at the given revision as seen by the controller at the given index, narrowed to
+    /// its scope if it has one and with the time on its own clock.
+    pub fn view_for<'a>(
+        &self,
+        state: &'a State,
+        revision: &Revision,
+        controller_index: usize,
+    ) -> Cow<'a, StateView> {
+        let view = state.view_at(revision);
+        let scope = self.scopes.get(&controller_index);
+        let skew = self.clock_skew(controller_index);
+        if scope.is_none() && skew == 0 {
+            return view;
+        }
+        let mut view = view.into_owned();
+        if let Some(scope) = scope {
+            for (kind, names) in &scope.0 {
+                view.retain_names(*kind, |name| names.contains(name));
+            }
+        }
+        view.clock += skew;
+        Cow::Owned(view)
+    }
+
+    /// How many seconds the clock of the controller at the given index runs ahead of the cluster's.
+    pub fn clock_skew(&self, controller_index: usize) -> u64 {
+        self.clock_skews
+            .get(&controller_index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Every property of the model, including those that the selection leaves out.
+    pub fn all_properties(&self) -> Vec<Property<Self>> {
+        let mut p = self.properties.clone();
+
+        p.push(Property::<Self>::always(
+            "all resources have unique names",
+            |_model, state| {
+                let state = state.latest();
+                all_unique(state.namespaces.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.nodes.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.pods.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.replicasets.iter().map(|n| &n.metadata.name))
+                    && all_unique(
+                        state
+                            .replication_controllers
+                            .iter()
+                            .map(|n| &n.metadata.name),
+                    )
+                    && all_unique(state.deployments.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.statefulsets.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.controller_revisions.iter().map(|n| &n.metadata.name))
+                    && all_unique(
+                        state
+                            .persistent_volume_claims
+                            .iter()
+                            .map(|n| &n.metadata.name),
+                    )
+                    && all_unique(state.persistent_volumes.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.storage_classes.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.priority_classes.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.leases.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.services.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.endpoints.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.config_maps.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.secrets.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.jobs.iter().map(|n| &n.metadata.name))
+                    && all_unique(state.cronjobs.iter().map(|n| &n.metadata.name))
+                    && all_unique(
+                        state
+                            .horizontal_pod_autoscalers
+                            .iter()
+                            .map(|n| &n.metadata.name),
+                    )
+            },
+        ));
+        p
+    }
+
+    /// Whether the property is discovered by the state alone, for checkers that judge the states
+    /// they visit one at a time.
+    ///
+    /// THEMELIOS: Only always and sometimes properties can be discovered this way. Eventually
+    /// properties need whole paths, which these checkers cut short or can't tell apart from
+    /// cycles, so are never discovered by them.
+    pub fn discovered_in(&self, property: &Property<Self>, state: &State) -> bool {
+        match property.expectation {
+            Expectation::Always => !(property.condition)(self, state),
+            Expectation::Sometimes => (property.condition)(self, state),
+            Expectation::Eventually => false,
+        }
+    }
+
+    /// Add the actions of the controller at the given index, stepping at or resyncing to the
+    /// revisions that it can see.
+    fn controller_actions(&self, state: &State, i: usize, actions: &mut Vec<Action>) {
+        if state.node_partitioned(i) || state.node_crashed(i) {
+            // can't see the api to take any steps
+            return;
+        }
+        if state.controller_crashed(i) {
+            // not running until it is restarted
+            return;
+        }
+        if state.awaiting_message(i) {
+            // blocked on the response to its last request
+            return;
+        }
+        let controller = self.controller(state, i);
+        let cstate = state.get_controller(i);
+        let min_revision = controller.min_revision_accepted(cstate);
+        if self.watch_caches {
+            // controllers only see as far as their cache has caught up
+            let cached = state.watch_cache(i);
+            if let Some(revision) = cached {
+                actions.push(Action::ControllerStep(revision.clone(), i));
+            }
+            for revision in
+                state.session_revisions(i, cached.or(min_revision), self.session_guarantee(i))
+            {
+                if Some(&revision) != cached {
+                    actions.push(Action::ControllerResync(revision, i));
+                }
+            }
+            return;
+        }
+        let revisions = state.session_revisions(i, min_revision, self.session_guarantee(i));
+        if revisions.is_empty() {
+            let now = state.latest().clock;
+            if state.workqueue(i).map_or(false, |q| q.has_due(now)) {
+                // work that it queued is due so it looks again without needing a new revision
+                let revision = min_revision
+                    .cloned()
+                    .unwrap_or_else(|| state.max_revision());
+                actions.push(Action::ControllerStep(revision, i));
+            }
+        }
+        for revision in revisions {
+            debug!(?revision, "Adding revision choice");
+            actions.push(Action::ControllerStep(revision, i));
+        }
+    }
+
+    /// A copy of the model for exploring states off to the side of the check, such as within a
+    /// property, recording nothing about the actions taken there as coverage or exercised grants.
+    pub fn probe(&self) -> Self {
+        Self {
+            authorizer: self.authorizer.unrecorded(),
+            coverage: None,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the controller at the given index has something to do, stepping at some revision
+    /// that it can see giving an action.
+    pub fn controller_enabled(&self, state: &State, controller_index: usize) -> bool {
+        let mut actions = Vec::new();
+        self.controller_actions(state, controller_index, &mut actions);
+        let controller = self.controller(state, controller_index);
+        actions.into_iter().any(|action| {
+            let Action::ControllerStep(revision, _) = action else {
+                return false;
+            };
+            let view = self.view_for(state, &revision, controller_index);
+            if matches!(self.elect(&view, controller_index), Some(Election::Follow)) {
+                return false;
+            }
+            let mut cstate = state.get_controller(controller_index).clone();
+            // requeues only wait on work to come due, changing nothing until then
+            controller.step(&view, &mut cstate).map_or(false, |a| {
+                !matches!(a, ControllerAction::RequeueDeployment(_, _))
+            })
+        })
+    }
+
+    /// The weakly fair controller that has been enabled for the fairness bound without stepping,
+    /// if any, so must step next.
+    fn starved_controller(&self, state: &State) -> Option<usize> {
+        self.fair_controllers.iter().copied().find(|i| {
+            state.starved_steps(*i) >= self.fairness_bound && self.controller_enabled(state, *i)
+        })
+    }
+
+    /// Count the steps that each weakly fair controller has been enabled for without stepping
+    /// itself, given which controller stepped to get from the last state to the next.
+    fn update_starvation(&self, last_state: &State, stepped: Option<usize>, next: &mut State) {
+        for &i in &self.fair_controllers {
+            let steps = if stepped == Some(i) || !self.controller_enabled(last_state, i) {
+                0
+            } else {
+                last_state.starved_steps(i) + 1
+            };
+            next.set_starved_steps(i, steps);
+        }
+    }
+
+    /// The session guarantees of the controller at the given index.
+    pub fn session_guarantee(&self, controller_index: usize) -> SessionGuarantee {
+        self.sessions
+            .get(&controller_index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Send the action taken by the controller at the revision to the API server, which handles it
+    /// straight away unless actions are sent as messages, when it waits to be delivered.
+    fn send_controller_action(
+        &self,
+        state: &mut State,
+        view: &StateView,
+        controller_index: usize,
+        revision: Revision,
+        action: ControllerAction,
+    ) {
+        if let Some(retries) = self.message_retries {
+            state.send_message(Message {
+                controller: controller_index,
+                revision,
+                operation: action,
+                retries,
+            });
+        } else {
+            self.handle_controller_action(state, view, controller_index, revision, action);
+        }
+    }
+
+    /// Apply the action taken by the controller if its role allows it, otherwise reject it as the
+    /// API server does.
+    fn handle_controller_action(
+        &self,
+        state: &mut State,
+        view: &StateView,
+        controller_index: usize,
+        revision: Revision,
+        action: ControllerAction,
+    ) {
+        if self
+            .authorizer
+            .authorize_in(state, controller_index, &action)
+        {
+            self.apply_controller_action(state, view, controller_index, revision, action);
+        } else {
+            let controller = self.controller(state, controller_index);
+            self.reject_controller_action(state, view, controller, &action);
+            state.record_unauthorized(controller_index);
+        }
+    }
+
+    /// Deliver the message to the API server, handling its action against the state as it is
+    /// now rather than as it was when the action was taken.
+    fn deliver_message(&self, state: &mut State, message: Message) {
+        let view = self
+            .view_for(state, &message.revision, message.controller)
+            .into_owned();
+        self.handle_controller_action(
+            state,
+            &view,
+            message.controller,
+            message.revision,
+            message.operation,
+        );
+    }
+
+    /// Apply the action taken by the controller, recording the events it emits for it and the
+    /// write for its session.
+    fn apply_controller_action(
+        &self,
+        state: &mut State,
+        view: &StateView,
+        controller_index: usize,
+        revision: Revision,
+        action: ControllerAction,
+    ) {
+        let mut action = action;
+        for write in action.writes_mut() {
+            if let Some(metadata) = write.created_metadata_mut() {
+                // THEMELIOS: each controller talks to an api server sharing its clock, which
+                // stamps what it creates, any creation timestamp set by the controller itself
+                // being ignored
+                metadata.creation_timestamp =
+                    (self.clock_skew(controller_index) > 0).then(|| view.now());
+            }
+        }
+        let before = state.max_revision();
+        let recorded = (self.events != EventRecording::Disabled).then(|| action.clone());
+        let key = action
+            .resource_namespace_and_name()
+            .map(|(namespace, name)| workqueue::key(namespace, name));
+        state.push_change(Change {
+            revision,
+            operation: action,
+        });
+        let applied = state.max_revision() != before;
+        if let (true, Some(key)) = (self.rate_limited_retries, key) {
+            let now = state.latest().clock;
+            state.update_workqueue(controller_index, |queue| {
+                if applied {
+                    queue.forget(&key);
+                } else {
+                    queue.add_rate_limited(&key, now);
+                }
+            });
+        }
+        if applied
+            && state
+                .view_at(&before)
+                .observed_generation_regressed(&state.latest())
+        {
+            state.record_regressed_observed_generation(controller_index);
+        }
+        if applied && self.session_guarantee(controller_index) == SessionGuarantee::ReadYourWrites {
+            state.record_write(controller_index, state.max_revision());
+        }
+        if let Some(action) = recorded {
+            let controller = self.controller(state, controller_index);
+            state.record_events(events::for_action(
+                &controller.name(),
+                view,
+                &action,
+                applied,
+            ));
+        }
+    }
+
+    /// Record the events for an action that the API server rejected.
+    fn reject_controller_action(
+        &self,
+        state: &mut State,
+        view: &StateView,
+        controller: &Controllers,
+        action: &ControllerAction,
+    ) {
+        if self.events != EventRecording::Disabled {
+            state.record_events(events::for_action(&controller.name(), view, action, false));
+        }
+    }
+
+    /// The outcome of leader election for the controller at the given index, none if it doesn't
+    /// take part in it.
+    ///
+    /// Nodes each manage their own pods so never elect a leader.
+    pub fn elect(&self, view: &RawState, controller_index: usize) -> Option<Election> {
+        self.elected_identity(controller_index)
+            .map(|(lease, identity)| leader_election::elect(view, &lease, &identity))
+    }
+
+    /// Whether the controller at the given index takes part in leader election but no longer holds
+    /// its lease.
+    fn deposed(&self, state: &State, controller_index: usize) -> bool {
+        self.elected_identity(controller_index)
+            .map_or(false, |(lease, identity)| {
+                !leader_election::holds_lease(&state.latest(), &lease, &identity)
+            })
+    }
+
+    /// The lease and identity that the controller at the given index elects a leader with.
+    ///
+    /// These come from the original controller so that upgrades keep holding the same lease.
+    pub fn elected_identity(&self, controller_index: usize) -> Option<(String, String)> {
+        let controller = &self.controllers[controller_index];
+        if !self.leader_election || matches!(controller, Controllers::Node(_)) {
+            return None;
+        }
+        let name = controller.name();
+        Some((
+            leader_election::lease_name(&name),
+            leader_election::identity(&name, controller_index),
+        ))
+    }
+
+    /// The state after taking the action, before its history is compacted.
+    fn next_state_uncompacted(&self, last_state: &State, action: Action) -> Option<State> {
+        match action {
+            Action::ControllerStep(revision, controller_index) => {
+                let controller = self.controller(last_state, controller_index);
+                let mut cstate = last_state.get_controller(controller_index).clone();
+                let view = &self.view_for(last_state, &revision, controller_index);
+                let mut state = last_state.clone();
+                match self.elect(view, controller_index) {
+                    None | Some(Election::Lead) => {}
+                    Some(Election::Lease(action)) => {
+                        // THEMELIOS: lease requests are made by the leader election library
+                        // rather than the controller so aren't checked against its role
+                        state.push_change(Change {
+                            revision,
+                            operation: action,
+                        });
+                        return Some(state);
+                    }
+                    // only the leader runs its control loop
+                    Some(Election::Follow) => return None,
+                }
+                let now = last_state.latest().clock;
+                state.update_workqueue(controller_index, |queue| {
+                    // the controller reconciles everything it sees, including whatever was due
+                    queue.take_due(now);
+                });
+                let action = controller.step(view, &mut cstate);
+                if let Some(shadow) = self.shadows.get(&controller_index) {
+                    let mut shadow_state = last_state.get_shadow(controller_index).clone();
+                    let shadow_action = shadow.step(view, &mut shadow_state);
+                    if shadow_action != action {
+                        state.mark_shadow_diverged(controller_index);
+                    }
+                    state.update_shadow(controller_index, shadow_state);
+                }
+                if let Some(ControllerAction::RequeueDeployment(deployment, after)) = &action {
+                    // requeues stay with the controller rather than going to the api server
+                    let metadata = &deployment.metadata;
+                    let key = workqueue::key(&metadata.namespace, &metadata.name);
+                    state.update_workqueue(controller_index, |queue| match after {
+                        Some(seconds) => queue.add_after(&key, now, *seconds),
+                        None => queue.add_rate_limited(&key, now),
+                    });
+                    state.update_controller(controller_index, cstate);
+                    return Some(state);
+                }
+                if let Some(action) = action {
+                    if let Some(coverage) = &self.coverage {
+                        coverage.record(view, &action);
+                    }
+                    if self.deposed(last_state, controller_index) {
+                        // acting on a stale view of its own lease
+                        state.record_deposed_leader(controller_index);
+                    }
+                    if action.changes_spec_through_status(view) {
+                        state.record_spec_through_status(controller_index);
+                    }
+                    self.send_controller_action(
+                        &mut state,
+                        view,
+                        controller_index,
+                        revision,
+                        action,
+                    );
+                }
+                state.update_controller(controller_index, cstate);
+                Some(state)
+            }
+            Action::ArbitraryStep(action) => {
+                let mut state = last_state.clone();
+                let controller_action = ArbitraryClient::controller_action(&state.latest(), action);
+                state.push_change(Change {
+                    revision: state.max_revision(),
+                    operation: controller_action,
+                });
+                Some(state)
+            }
+            Action::ClientOperation(i) => {
+                let mut state = last_state.clone();
+                let controller_action =
+                    self.client_operations[i].controller_action(&state.latest())?;
+                state.push_change(Change {
+                    revision: state.max_revision(),
+                    operation: controller_action,
+                });
+                state.take_client_operation();
+                Some(state)
+            }
+            Action::ControllerRestart(controller_index) => {
+                let mut state = last_state.clone();
+                let controller_state = self.controller(last_state, controller_index).new_state();
+                state.update_controller(controller_index, controller_state);
+                // the restarted controller starts a new session
+                state.reset_session(controller_index);
+                state.clear_watch_cache(controller_index);
+                state.clear_workqueue(controller_index);
+                state.recover_controller(controller_index);
+                if let Some(shadow) = self.shadows.get(&controller_index) {
+                    // the shadow restarts along with its primary
+                    state.update_shadow(controller_index, shadow.new_state());
+                }
+                Some(state)
+            }
+            Action::ControllerCrash(controller_index) => {
+                let mut state = last_state.clone();
+                // the controller loses its state as it goes down, only getting a fresh one once
+                // it is restarted
+                let controller_state = self.controller(last_state, controller_index).new_state();
+                state.update_controller(controller_index, controller_state);
+                state.reset_session(controller_index);
+                state.clear_watch_cache(controller_index);
+                state.clear_workqueue(controller_index);
+                state.crash_controller(controller_index);
+                if let Some(shadow) = self.shadows.get(&controller_index) {
+                    state.update_shadow(controller_index, shadow.new_state());
+                }
+                Some(state)
+            }
+            Action::NodeRestart(controller_index) => {
+                let mut state = last_state.clone();
+                let controller_state = self.controller(last_state, controller_index).new_state();
+                state.update_controller(controller_index, controller_state);
+                state.reset_session(controller_index);
+                state.clear_watch_cache(controller_index);
+                state.clear_workqueue(controller_index);
+                state.recover_node(controller_index);
+                if let Some(shadow) = self.shadows.get(&controller_index) {
+                    state.update_shadow(controller_index, shadow.new_state());
+                }
+                let s = state.latest();
+                if let Controllers::Node(n) = self.controller(last_state, controller_index) {
+                    if let Some(node) = s.nodes.get(&n.name) {
+                        state.push_change(Change {
+                            revision: s.revision.clone(),
+                            operation: ControllerAction::DeleteNode(node.clone()),
+                        });
+                    }
+                }
+                Some(state)
+            }
+            Action::NodePartition(controller_index) => {
+                let mut state = last_state.clone();
+                state.partition_node(controller_index);
+                let s = state.latest();
+                if let Controllers::Node(n) = self.controller(last_state, controller_index) {
+                    // a node that heartbeats is noticed by the node lifecycle controller instead
+                    if let (Some(node), None) = (s.nodes.get(&n.name), n.lease_duration_seconds) {
+                        state.push_change(Change {
+                            revision: s.revision.clone(),
+                            operation: ControllerAction::UpdateNode(node_faults::not_ready(node)),
+                        });
+                    }
+                }
+                Some(state)
+            }
+            Action::NodeCrash(controller_index) => {
+                let Controllers::Node(n) = self.controller(last_state, controller_index) else {
+                    return None;
+                };
+                let mut state = last_state.clone();
+                // the kubelet loses track of what it was running
+                state.update_controller(controller_index, n.new_state());
+                state.reset_session(controller_index);
+                state.clear_watch_cache(controller_index);
+                state.clear_workqueue(controller_index);
+                state.crash_node(controller_index);
+                let mut operations = node_faults::crash(&state.latest(), &n.name);
+                if n.lease_duration_seconds.is_some() {
+                    // the node lifecycle controller notices that it stopped heartbeating instead
+                    operations.retain(|op| !matches!(op, ControllerAction::UpdateNode(_)));
+                }
+                for operation in operations {
+                    state.push_change(Change {
+                        revision: state.max_revision(),
+                        operation,
+                    });
+                }
+                Some(state)
+            }
+            Action::NodeDrain(controller_index) => {
+                let Controllers::Node(n) = self.controller(last_state, controller_index) else {
+                    return None;
+                };
+                let mut state = last_state.clone();
+                let operations = node_faults::drain(&state.latest(), &n.name);
+                for operation in operations {
+                    state.push_change(Change {
+                        revision: state.max_revision(),
+                        operation,
+                    });
+                }
+                Some(state)
+            }
+            Action::NodeUncordon(controller_index) => {
+                let Controllers::Node(n) = self.controller(last_state, controller_index) else {
+                    return None;
+                };
+                let mut state = last_state.clone();
+                let operation = node_faults::uncordon(&state.latest(), &n.name)?;
+                state.push_change(Change {
+                    revision: state.max_revision(),
+                    operation,
+                });
+                Some(state)
+            }
+            Action::NodePressure(controller_index, condition) => {
+                let Controllers::Node(n) = self.controller(last_state, controller_index) else {
+                    return None;
+                };
+                let mut state = last_state.clone();
+                let operations = node_faults::pressure(&state.latest(), &n.name, &condition);
+                if let ControllerStates::Node(mut local) =
+                    state.get_controller(controller_index).clone()
+                {
+                    // the kubelet stops the containers of the pods that it evicts
+                    for operation in &operations {
+                        if let ControllerAction::UpdatePod(pod) = operation {
+                            node::forget_pod(&mut local, &pod.metadata.name);
+                        }
+                    }
+                    state.update_controller(controller_index, ControllerStates::Node(local));
+                }
+                for operation in operations {
+                    state.push_change(Change {
+                        revision: state.max_revision(),
+                        operation,
+                    });
+                }
+                Some(state)
+            }
+            Action::NodePressureRelieved(controller_index, condition) => {
+                let Controllers::Node(n) = self.controller(last_state, controller_index) else {
+                    return None;
+                };
+                let mut state = last_state.clone();
+                let operation = node_faults::relieve(&state.latest(), &n.name, &condition)?;
+                state.push_change(Change {
+                    revision: state.max_revision(),
+                    operation,
+                });
+                Some(state)
+            }
+            Action::ControllerUpgrade(controller_index) => {
+                let mut state = last_state.clone();
+                let controller_state = self.upgrades[&controller_index].new_state();
+                state.upgrade_controller(controller_index, controller_state);
+                if let Some(shadow) = self.shadows.get(&controller_index) {
+                    state.update_shadow(controller_index, shadow.new_state());
+                }
+                Some(state)
+            }
+            Action::ControllerRelist(revision, controller_index, kind) => {
+                let controller = self.controller(last_state, controller_index);
+                let full_view = self.view_for(last_state, &revision, controller_index);
+                if !matches!(
+                    self.elect(&full_view, controller_index),
+                    None | Some(Election::Lead)
+                ) {
+                    // only the leader runs the controller, and so has a cache to rebuild
+                    return None;
+                }
+                let mut relisted_view = (*full_view).clone();
+                relisted_view.clear_kind(kind);
+                // the cache is rebuilt from scratch so any local state is lost
+                let mut cstate = controller.new_state();
+                let mut state = last_state.clone();
+                if self.watch_caches {
+                    state.resync_watch_cache(controller_index, revision.clone());
+                }
+                if let Some(action) = controller.step(&relisted_view, &mut cstate) {
+                    if let Some(coverage) = &self.coverage {
+                        coverage.record(&relisted_view, &action);
+                    }
+                    if action.is_deletion() {
+                        let full_action = controller.step(&full_view, &mut controller.new_state());
+                        if full_action.as_ref() != Some(&action) {
+                            // only deleting because of the missing resources
+                            state.record_destructive_relist(controller_index);
+                        }
+                    }
+                    if action.changes_spec_through_status(&relisted_view) {
+                        state.record_spec_through_status(controller_index);
+                    }
+                    self.send_controller_action(
+                        &mut state,
+                        &full_view,
+                        controller_index,
+                        revision,
+                        action,
+                    );
+                }
+                state.update_controller(controller_index, cstate);
+                Some(state)
+            }
+            Action::ControllerResync(revision, controller_index) => {
+                let mut state = last_state.clone();
+                state.resync_watch_cache(controller_index, revision);
+                Some(state)
+            }
+            Action::DeliverMessage(index) => {
+                let mut state = last_state.clone();
+                let message = state.take_message(index);
+                self.deliver_message(&mut state, message);
+                Some(state)
+            }
+            Action::DropMessage(index) => {
+                let mut state = last_state.clone();
+                if !state.retry_message(index) {
+                    // out of retries, the controller gives up on it
+                    state.take_message(index);
+                }
+                Some(state)
+            }
+            Action::DuplicateMessage(index) => {
+                // the message is delivered but the response to it is lost, so the controller sends
+                // it again
+                let mut state = last_state.clone();
+                let message = state.messages()[index].clone();
+                if !state.retry_message(index) {
+                    return None;
+                }
+                self.deliver_message(&mut state, message);
+                Some(state)
+            }
+            Action::AntiEntropy(from, to) => {
+                let mut state = last_state.clone();
+                // merges that change nothing are left out to keep from growing the history
+                state
+                    .merge_replicas(from, to, &self.replica_merge)
+                    .then_some(state)
+            }
+            Action::StorePartition(member) => {
+                let mut state = last_state.clone();
+                state.partition_store(member).then_some(state)
+            }
+            Action::StoreHeal => {
+                let mut state = last_state.clone();
+                state.heal_store().then_some(state)
+            }
+            Action::AdvanceClock => {
+                let mut state = last_state.clone();
+                state.push_change(Change {
+                    revision: state.max_revision(),
+                    operation: ControllerAction::AdvanceClock(CLOCK_STEP_SECONDS),
+                });
+                Some(state)
+            }
+            Action::UpdateMetric(namespace, name, utilization) => {
+                let mut state = last_state.clone();
+                state.push_change(Change {
+                    revision: state.max_revision(),
+                    operation: ControllerAction::UpdateMetric(namespace, name, utilization),
+                });
+                Some(state)
+            }
+        }
+    }
+
+    /// Compact the history of the state, keeping every revision that a controller can still
+    /// observe so that compaction never changes what they do.
+    fn compact(&self, state: &mut State) {
+        let keep = match self.compaction {
+            Compaction::Disabled => return,
+            Compaction::KeepLast(keep) => keep,
+            Compaction::Referenced => 1,
+        };
+        let referenced = (0..self.controllers.len())
+            .filter_map(|i| {
+                self.controller(state, i)
+                    .min_revision_accepted(state.get_controller(i))
+                    .cloned()
+            })
+            .collect::<Vec<_>>();
+        state.compact(keep, &referenced);
+    }
+}
+
+/// An action that a controller has sent to the API server and that is yet to be delivered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Message {
+    /// The index of the controller that sent it.
+    pub controller: usize,
+    /// The revision of the state that the controller took the action at.
+    pub revision: Revision,
+    /// The action that the controller took.
+    pub operation: ControllerAction,
+    /// The number of times that the controller resends the message once it is dropped.
+    pub retries: u32,
 }
 
 /// Changes to a state.
@@ -67,16 +1023,28 @@ pub enum ControllerAction {
     /// Name and resources
     NodeJoin(String, ResourceQuantities),
     DeleteNode(Node),
+    UpdateNode(Node),
 
     // Pods
     CreatePod(Pod),
     SoftDeletePod(Pod),
     HardDeletePod(Pod),
     UpdatePod(Pod),
+    /// Namespace, name and patch
+    PatchPod(String, String, Patch),
 
     // Deployments
+    CreateDeployment(Deployment),
     UpdateDeployment(Deployment),
-    RequeueDeployment(Deployment),
+    /// Namespace, name and patch
+    PatchDeployment(String, String, Patch),
+    ScaleDeployment(Scale),
+    /// Namespace and rollback
+    RollbackDeployment(String, DeploymentRollback),
+    DeleteDeployment(Deployment),
+    /// Look at the deployment again after the given number of seconds, or after a rate limited
+    /// delay when not given, through the work queue of the controller.
+    RequeueDeployment(Deployment, Option<u64>),
     // Update just the status part of the resource, not triggering more reconciliations (I think)
     UpdateDeploymentStatus(Deployment),
 
@@ -84,14 +1052,23 @@ pub enum ControllerAction {
     CreateReplicaSet(ReplicaSet),
     UpdateReplicaSet(ReplicaSet),
     UpdateReplicaSetStatus(ReplicaSet),
-    // a batch update of multiple replicasets that should cause a new reconciliation if it fails to
-    // have this
-    UpdateReplicaSets(Vec<ReplicaSet>),
+    /// Namespace, name and patch
+    PatchReplicaSet(String, String, Patch),
+    ScaleReplicaSet(Scale),
     DeleteReplicaSet(ReplicaSet),
 
+    // ReplicationControllers
+    UpdateReplicationControllerStatus(ReplicationController),
+    DeleteReplicationController(ReplicationController),
+
     // StatefulSets
+    CreateStatefulSet(StatefulSet),
     UpdateStatefulSet(StatefulSet),
     UpdateStatefulSetStatus(StatefulSet),
+    /// Namespace, name and patch
+    PatchStatefulSet(String, String, Patch),
+    ScaleStatefulSet(Scale),
+    DeleteStatefulSet(StatefulSet),
 
     // ControllerRevisions
     CreateControllerRevision(ControllerRevision),
@@ -101,20 +1078,798 @@ pub enum ControllerAction {
     // PersistentVolumeClaims
     CreatePersistentVolumeClaim(PersistentVolumeClaim),
     UpdatePersistentVolumeClaim(PersistentVolumeClaim),
+    DeletePersistentVolumeClaim(PersistentVolumeClaim),
+    UpdatePersistentVolumeClaimStatus(PersistentVolumeClaim),
+
+    // PersistentVolumes
+    CreatePersistentVolume(PersistentVolume),
+    UpdatePersistentVolume(PersistentVolume),
+    UpdatePersistentVolumeStatus(PersistentVolume),
+
+    // Leases
+    CreateLease(Lease),
+    UpdateLease(Lease),
+
+    // Services
+    DeleteService(Service),
+
+    // Endpoints
+    CreateEndpoints(Endpoints),
+    UpdateEndpoints(Endpoints),
+    DeleteEndpoints(Endpoints),
+
+    // ConfigMaps
+    CreateConfigMap(ConfigMap),
+    UpdateConfigMap(ConfigMap),
+    DeleteConfigMap(ConfigMap),
+
+    // Secrets
+    CreateSecret(Secret),
+    UpdateSecret(Secret),
+    DeleteSecret(Secret),
 
     // Jobs
+    CreateJob(Job),
     UpdateJob(Job),
     UpdateJobStatus(Job),
+    /// Namespace, name and patch
+    PatchJob(String, String, Patch),
+    DeleteJob(Job),
+
+    // CronJobs
+    UpdateCronJobStatus(CronJob),
+    DeleteCronJob(CronJob),
+
+    // HorizontalPodAutoscalers
+    UpdateHorizontalPodAutoscalerStatus(HorizontalPodAutoscaler),
+    DeleteHorizontalPodAutoscaler(HorizontalPodAutoscaler),
+
+    // Namespaces
+    SoftDeleteNamespace(Namespace),
+    // Set the finalizers in the spec, removing the namespace once none remain
+    FinalizeNamespace(Namespace),
+
+    /// Writes that the API server applies together at a single revision, all of them failing if
+    /// any one does, whatever the consistency level.
+    ///
+    /// THEMELIOS: the writes share the revision of the transaction, so it can create at most one
+    /// resource from a generated name. Eventually consistent replicas merge resource by resource,
+    /// so a replica keeps its own newer versions of any of the resources that were written.
+    Transaction(Vec<ControllerAction>),
+
+    /// Advance the cluster clock by the given number of seconds.
+    AdvanceClock(u64),
+    /// Set the utilization reported by the metrics source for the autoscaler.
+    /// Namespace, name and utilization
+    UpdateMetric(String, String, u32),
+}
+
+impl ControllerAction {
+    /// The names of every kind of action, in the order they are declared.
+    pub const NAMES: [&'static str; 66] = [
+        "NodeJoin",
+        "DeleteNode",
+        "UpdateNode",
+        "CreatePod",
+        "SoftDeletePod",
+        "HardDeletePod",
+        "UpdatePod",
+        "PatchPod",
+        "CreateDeployment",
+        "UpdateDeployment",
+        "PatchDeployment",
+        "ScaleDeployment",
+        "RollbackDeployment",
+        "DeleteDeployment",
+        "RequeueDeployment",
+        "UpdateDeploymentStatus",
+        "CreateReplicaSet",
+        "UpdateReplicaSet",
+        "UpdateReplicaSetStatus",
+        "PatchReplicaSet",
+        "ScaleReplicaSet",
+        "DeleteReplicaSet",
+        "UpdateReplicationControllerStatus",
+        "DeleteReplicationController",
+        "CreateStatefulSet",
+        "UpdateStatefulSet",
+        "UpdateStatefulSetStatus",
+        "PatchStatefulSet",
+        "ScaleStatefulSet",
+        "DeleteStatefulSet",
+        "CreateControllerRevision",
+        "UpdateControllerRevision",
+        "DeleteControllerRevision",
+        "CreatePersistentVolumeClaim",
+        "UpdatePersistentVolumeClaim",
+        "DeletePersistentVolumeClaim",
+        "UpdatePersistentVolumeClaimStatus",
+        "CreatePersistentVolume",
+        "UpdatePersistentVolume",
+        "UpdatePersistentVolumeStatus",
+        "CreateLease",
+        "UpdateLease",
+        "DeleteService",
+        "CreateEndpoints",
+        "UpdateEndpoints",
+        "DeleteEndpoints",
+        "CreateConfigMap",
+        "UpdateConfigMap",
+        "DeleteConfigMap",
+        "CreateSecret",
+        "UpdateSecret",
+        "DeleteSecret",
+        "CreateJob",
+        "UpdateJob",
+        "UpdateJobStatus",
+        "PatchJob",
+        "DeleteJob",
+        "UpdateCronJobStatus",
+        "DeleteCronJob",
+        "UpdateHorizontalPodAutoscalerStatus",
+        "DeleteHorizontalPodAutoscaler",
+        "SoftDeleteNamespace",
+        "FinalizeNamespace",
+        "Transaction",
+        "AdvanceClock",
+        "UpdateMetric",
+    ];
+
+    /// The name of the kind of action, as in [`ControllerAction::NAMES`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControllerAction::NodeJoin(_, _) => "NodeJoin",
+            ControllerAction::DeleteNode(_) => "DeleteNode",
+            ControllerAction::UpdateNode(_) => "UpdateNode",
+            ControllerAction::CreatePod(_) => "CreatePod",
+            ControllerAction::SoftDeletePod(_) => "SoftDeletePod",
+            ControllerAction::HardDeletePod(_) => "HardDeletePod",
+            ControllerAction::UpdatePod(_) => "UpdatePod",
+            ControllerAction::PatchPod(_, _, _) => "PatchPod",
+            ControllerAction::CreateDeployment(_) => "CreateDeployment",
+            ControllerAction::UpdateDeployment(_) => "UpdateDeployment",
+            ControllerAction::PatchDeployment(_, _, _) => "PatchDeployment",
+            ControllerAction::ScaleDeployment(_) => "ScaleDeployment",
+            ControllerAction::RollbackDeployment(_, _) => "RollbackDeployment",
+            ControllerAction::DeleteDeployment(_) => "DeleteDeployment",
+            ControllerAction::RequeueDeployment(_, _) => "RequeueDeployment",
+            ControllerAction::UpdateDeploymentStatus(_) => "UpdateDeploymentStatus",
+            ControllerAction::CreateReplicaSet(_) => "CreateReplicaSet",
+            ControllerAction::UpdateReplicaSet(_) => "UpdateReplicaSet",
+            ControllerAction::UpdateReplicaSetStatus(_) => "UpdateReplicaSetStatus",
+            ControllerAction::PatchReplicaSet(_, _, _) => "PatchReplicaSet",
+            ControllerAction::ScaleReplicaSet(_) => "ScaleReplicaSet",
+            ControllerAction::DeleteReplicaSet(_) => "DeleteReplicaSet",
+            ControllerAction::UpdateReplicationControllerStatus(_) => {
+                "UpdateReplicationControllerStatus"
+            }
+            ControllerAction::DeleteReplicationController(_) => "DeleteReplicationController",
+            ControllerAction::CreateStatefulSet(_) => "CreateStatefulSet",
+            ControllerAction::UpdateStatefulSet(_) => "UpdateStatefulSet",
+            ControllerAction::UpdateStatefulSetStatus(_) => "UpdateStatefulSetStatus",
+            ControllerAction::PatchStatefulSet(_, _, _) => "PatchStatefulSet",
+            ControllerAction::ScaleStatefulSet(_) => "ScaleStatefulSet",
+            ControllerAction::DeleteStatefulSet(_) => "DeleteStatefulSet",
+            ControllerAction::CreateControllerRevision(_) => "CreateControllerRevision",
+            ControllerAction::UpdateControllerRevision(_) => "UpdateControllerRevision",
+            ControllerAction::DeleteControllerRevision(_) => "DeleteControllerRevision",
+            ControllerAction::CreatePersistentVolumeClaim(_) => "CreatePersistentVolumeClaim",
+            ControllerAction::UpdatePersistentVolumeClaim(_) => "UpdatePersistentVolumeClaim",
+            ControllerAction::DeletePersistentVolumeClaim(_) => "DeletePersistentVolumeClaim",
+            ControllerAction::UpdatePersistentVolumeClaimStatus(_) => {
+                "UpdatePersistentVolumeClaimStatus"
+            }
+            ControllerAction::CreatePersistentVolume(_) => "CreatePersistentVolume",
+            ControllerAction::UpdatePersistentVolume(_) => "UpdatePersistentVolume",
+            ControllerAction::UpdatePersistentVolumeStatus(_) => "UpdatePersistentVolumeStatus",
+            ControllerAction::CreateLease(_) => "CreateLease",
+            ControllerAction::UpdateLease(_) => "UpdateLease",
+            ControllerAction::DeleteService(_) => "DeleteService",
+            ControllerAction::CreateEndpoints(_) => "CreateEndpoints",
+            ControllerAction::UpdateEndpoints(_) => "UpdateEndpoints",
+            ControllerAction::DeleteEndpoints(_) => "DeleteEndpoints",
+            ControllerAction::CreateConfigMap(_) => "CreateConfigMap",
+            ControllerAction::UpdateConfigMap(_) => "UpdateConfigMap",
+            ControllerAction::DeleteConfigMap(_) => "DeleteConfigMap",
+            ControllerAction::CreateSecret(_) => "CreateSecret",
+            ControllerAction::UpdateSecret(_) => "UpdateSecret",
+            ControllerAction::DeleteSecret(_) => "DeleteSecret",
+            ControllerAction::CreateJob(_) => "CreateJob",
+            ControllerAction::UpdateJob(_) => "UpdateJob",
+            ControllerAction::UpdateJobStatus(_) => "UpdateJobStatus",
+            ControllerAction::PatchJob(_, _, _) => "PatchJob",
+            ControllerAction::DeleteJob(_) => "DeleteJob",
+            ControllerAction::UpdateCronJobStatus(_) => "UpdateCronJobStatus",
+            ControllerAction::DeleteCronJob(_) => "DeleteCronJob",
+            ControllerAction::UpdateHorizontalPodAutoscalerStatus(_) => {
+                "UpdateHorizontalPodAutoscalerStatus"
+            }
+            ControllerAction::DeleteHorizontalPodAutoscaler(_) => "DeleteHorizontalPodAutoscaler",
+            ControllerAction::SoftDeleteNamespace(_) => "SoftDeleteNamespace",
+            ControllerAction::FinalizeNamespace(_) => "FinalizeNamespace",
+            ControllerAction::Transaction(_) => "Transaction",
+            ControllerAction::AdvanceClock(_) => "AdvanceClock",
+            ControllerAction::UpdateMetric(_, _, _) => "UpdateMetric",
+        }
+    }
+
+    /// The writes that the action makes: those of a transaction, otherwise just the action itself.
+    pub fn writes_mut(&mut self) -> &mut [ControllerAction] {
+        match self {
+            ControllerAction::Transaction(writes) => writes.as_mut_slice(),
+            write => std::slice::from_mut(write),
+        }
+    }
+
+    /// The metadata of the resource that this action creates, if it creates one.
+    pub fn created_metadata_mut(&mut self) -> Option<&mut Metadata> {
+        Some(match self {
+            ControllerAction::CreatePod(r) => &mut r.metadata,
+            ControllerAction::CreateDeployment(r) => &mut r.metadata,
+            ControllerAction::CreateReplicaSet(r) => &mut r.metadata,
+            ControllerAction::CreateStatefulSet(r) => &mut r.metadata,
+            ControllerAction::CreateControllerRevision(r) => &mut r.metadata,
+            ControllerAction::CreatePersistentVolumeClaim(r) => &mut r.metadata,
+            ControllerAction::CreatePersistentVolume(r) => &mut r.metadata,
+            ControllerAction::CreateLease(r) => &mut r.metadata,
+            ControllerAction::CreateEndpoints(r) => &mut r.metadata,
+            ControllerAction::CreateConfigMap(r) => &mut r.metadata,
+            ControllerAction::CreateSecret(r) => &mut r.metadata,
+            ControllerAction::CreateJob(r) => &mut r.metadata,
+            _ => return None,
+        })
+    }
+
+    /// Whether this action removes (or starts removing) a resource.
+    pub fn is_deletion(&self) -> bool {
+        if let ControllerAction::Transaction(writes) = self {
+            return writes.iter().any(ControllerAction::is_deletion);
+        }
+        matches!(
+            self,
+            ControllerAction::DeleteNode(_)
+                | ControllerAction::SoftDeletePod(_)
+                | ControllerAction::HardDeletePod(_)
+                | ControllerAction::DeleteReplicaSet(_)
+                | ControllerAction::DeleteReplicationController(_)
+                | ControllerAction::DeleteControllerRevision(_)
+                | ControllerAction::DeleteJob(_)
+                | ControllerAction::DeleteDeployment(_)
+                | ControllerAction::DeleteStatefulSet(_)
+                | ControllerAction::DeletePersistentVolumeClaim(_)
+                | ControllerAction::DeleteCronJob(_)
+                | ControllerAction::DeleteHorizontalPodAutoscaler(_)
+                | ControllerAction::DeleteService(_)
+                | ControllerAction::DeleteEndpoints(_)
+                | ControllerAction::DeleteConfigMap(_)
+                | ControllerAction::DeleteSecret(_)
+                | ControllerAction::SoftDeleteNamespace(_)
+        )
+    }
+
+    /// Whether this is a status update that changes the spec of the resource from the version in
+    /// the view, which the status subresource would ignore.
+    pub fn changes_spec_through_status(&self, view: &StateView) -> bool {
+        fn changed<T: Meta + Spec + Clone>(resources: &Resources<T>, res: &T) -> bool {
+            resources
+                .get_in(&res.metadata().namespace, &res.metadata().name)
+                .map_or(false, |existing| existing.spec() != res.spec())
+        }
+        match self {
+            ControllerAction::Transaction(writes) => {
+                writes.iter().any(|w| w.changes_spec_through_status(view))
+            }
+            ControllerAction::UpdateDeploymentStatus(dep) => changed(&view.deployments, dep),
+            ControllerAction::UpdateReplicaSetStatus(rs) => changed(&view.replicasets, rs),
+            ControllerAction::UpdateReplicationControllerStatus(rc) => {
+                changed(&view.replication_controllers, rc)
+            }
+            ControllerAction::UpdateStatefulSetStatus(sts) => changed(&view.statefulsets, sts),
+            ControllerAction::UpdatePersistentVolumeClaimStatus(pvc) => {
+                changed(&view.persistent_volume_claims, pvc)
+            }
+            ControllerAction::UpdatePersistentVolumeStatus(pv) => {
+                changed(&view.persistent_volumes, pv)
+            }
+            ControllerAction::UpdateJobStatus(job) => changed(&view.jobs, job),
+            ControllerAction::UpdateCronJobStatus(cronjob) => changed(&view.cronjobs, cronjob),
+            ControllerAction::UpdateHorizontalPodAutoscalerStatus(hpa) => {
+                changed(&view.horizontal_pod_autoscalers, hpa)
+            }
+            _ => false,
+        }
+    }
+
+    /// The permission needed to take this action, if it is a request to the API server.
+    pub fn required_permission(&self) -> Option<Permission> {
+        let (verb, kind) = match self {
+            ControllerAction::NodeJoin(_, _) => (Verb::Create, ResourceKind::Nodes),
+            ControllerAction::DeleteNode(_) => (Verb::Delete, ResourceKind::Nodes),
+            ControllerAction::UpdateNode(_) => (Verb::Update, ResourceKind::Nodes),
+            ControllerAction::CreatePod(_) => (Verb::Create, ResourceKind::Pods),
+            ControllerAction::SoftDeletePod(_) | ControllerAction::HardDeletePod(_) => {
+                (Verb::Delete, ResourceKind::Pods)
+            }
+            ControllerAction::UpdatePod(_) => (Verb::Update, ResourceKind::Pods),
+            ControllerAction::PatchPod(_, _, _) => (Verb::Patch, ResourceKind::Pods),
+            ControllerAction::CreateDeployment(_) => (Verb::Create, ResourceKind::Deployments),
+            ControllerAction::UpdateDeployment(_)
+            | ControllerAction::ScaleDeployment(_)
+            | ControllerAction::RollbackDeployment(_, _)
+            | ControllerAction::UpdateDeploymentStatus(_) => {
+                (Verb::Update, ResourceKind::Deployments)
+            }
+            ControllerAction::PatchDeployment(_, _, _) => (Verb::Patch, ResourceKind::Deployments),
+            ControllerAction::DeleteDeployment(_) => (Verb::Delete, ResourceKind::Deployments),
+            // only requeues locally
+            ControllerAction::RequeueDeployment(_, _) => return None,
+            ControllerAction::CreateReplicaSet(_) => (Verb::Create, ResourceKind::ReplicaSets),
+            ControllerAction::UpdateReplicaSet(_)
+            | ControllerAction::UpdateReplicaSetStatus(_)
+            | ControllerAction::ScaleReplicaSet(_) => (Verb::Update, ResourceKind::ReplicaSets),
+            ControllerAction::PatchReplicaSet(_, _, _) => (Verb::Patch, ResourceKind::ReplicaSets),
+            ControllerAction::UpdateReplicationControllerStatus(_) => {
+                (Verb::Update, ResourceKind::ReplicationControllers)
+            }
+            ControllerAction::DeleteReplicationController(_) => {
+                (Verb::Delete, ResourceKind::ReplicationControllers)
+            }
+            ControllerAction::DeleteReplicaSet(_) => (Verb::Delete, ResourceKind::ReplicaSets),
+            ControllerAction::CreateStatefulSet(_) => (Verb::Create, ResourceKind::StatefulSets),
+            ControllerAction::UpdateStatefulSet(_)
+            | ControllerAction::UpdateStatefulSetStatus(_)
+            | ControllerAction::ScaleStatefulSet(_) => (Verb::Update, ResourceKind::StatefulSets),
+            ControllerAction::PatchStatefulSet(_, _, _) => {
+                (Verb::Patch, ResourceKind::StatefulSets)
+            }
+            ControllerAction::DeleteStatefulSet(_) => (Verb::Delete, ResourceKind::StatefulSets),
+            ControllerAction::CreateControllerRevision(_) => {
+                (Verb::Create, ResourceKind::ControllerRevisions)
+            }
+            ControllerAction::UpdateControllerRevision(_) => {
+                (Verb::Update, ResourceKind::ControllerRevisions)
+            }
+            ControllerAction::DeleteControllerRevision(_) => {
+                (Verb::Delete, ResourceKind::ControllerRevisions)
+            }
+            ControllerAction::CreatePersistentVolumeClaim(_) => {
+                (Verb::Create, ResourceKind::PersistentVolumeClaims)
+            }
+            ControllerAction::UpdatePersistentVolumeClaim(_)
+            | ControllerAction::UpdatePersistentVolumeClaimStatus(_) => {
+                (Verb::Update, ResourceKind::PersistentVolumeClaims)
+            }
+            ControllerAction::CreatePersistentVolume(_) => {
+                (Verb::Create, ResourceKind::PersistentVolumes)
+            }
+            ControllerAction::UpdatePersistentVolume(_)
+            | ControllerAction::UpdatePersistentVolumeStatus(_) => {
+                (Verb::Update, ResourceKind::PersistentVolumes)
+            }
+            ControllerAction::DeletePersistentVolumeClaim(_) => {
+                (Verb::Delete, ResourceKind::PersistentVolumeClaims)
+            }
+            ControllerAction::CreateLease(_) => (Verb::Create, ResourceKind::Leases),
+            ControllerAction::UpdateLease(_) => (Verb::Update, ResourceKind::Leases),
+            ControllerAction::DeleteService(_) => (Verb::Delete, ResourceKind::Services),
+            ControllerAction::CreateEndpoints(_) => (Verb::Create, ResourceKind::Endpoints),
+            ControllerAction::UpdateEndpoints(_) => (Verb::Update, ResourceKind::Endpoints),
+            ControllerAction::DeleteEndpoints(_) => (Verb::Delete, ResourceKind::Endpoints),
+            ControllerAction::CreateConfigMap(_) => (Verb::Create, ResourceKind::ConfigMaps),
+            ControllerAction::UpdateConfigMap(_) => (Verb::Update, ResourceKind::ConfigMaps),
+            ControllerAction::DeleteConfigMap(_) => (Verb::Delete, ResourceKind::ConfigMaps),
+            ControllerAction::CreateSecret(_) => (Verb::Create, ResourceKind::Secrets),
+            ControllerAction::UpdateSecret(_) => (Verb::Update, ResourceKind::Secrets),
+            ControllerAction::DeleteSecret(_) => (Verb::Delete, ResourceKind::Secrets),
+            ControllerAction::CreateJob(_) => (Verb::Create, ResourceKind::Jobs),
+            ControllerAction::UpdateJob(_) | ControllerAction::UpdateJobStatus(_) => {
+                (Verb::Update, ResourceKind::Jobs)
+            }
+            ControllerAction::PatchJob(_, _, _) => (Verb::Patch, ResourceKind::Jobs),
+            ControllerAction::DeleteJob(_) => (Verb::Delete, ResourceKind::Jobs),
+            ControllerAction::UpdateCronJobStatus(_) => (Verb::Update, ResourceKind::CronJobs),
+            ControllerAction::DeleteCronJob(_) => (Verb::Delete, ResourceKind::CronJobs),
+            ControllerAction::UpdateHorizontalPodAutoscalerStatus(_) => {
+                (Verb::Update, ResourceKind::HorizontalPodAutoscalers)
+            }
+            ControllerAction::DeleteHorizontalPodAutoscaler(_) => {
+                (Verb::Delete, ResourceKind::HorizontalPodAutoscalers)
+            }
+            ControllerAction::SoftDeleteNamespace(_) => (Verb::Delete, ResourceKind::Namespaces),
+            ControllerAction::FinalizeNamespace(_) => (Verb::Update, ResourceKind::Namespaces),
+            // stands in for the whole transaction, each write being authorized on its own
+            ControllerAction::Transaction(writes) => return writes.first()?.required_permission(),
+            // changes in the environment rather than requests
+            ControllerAction::AdvanceClock(_) | ControllerAction::UpdateMetric(_, _, _) => {
+                return None
+            }
+        };
+        Some(Permission::new(verb, kind))
+    }
+
+    /// The name of the resource that the action writes, or the prefix that it is generated from
+    /// for creates that leave the name to the API server.
+    pub fn resource_name(&self) -> Option<&str> {
+        self.resource_namespace_and_name().map(|(_, name)| name)
+    }
+
+    /// The namespace and name of the resource that the action writes, as for
+    /// [`resource_name`](Self::resource_name), the namespace being empty for cluster scoped
+    /// resources.
+    pub fn resource_namespace_and_name(&self) -> Option<(&str, &str)> {
+        fn name<T: Meta>(resource: &T) -> (&str, &str) {
+            let metadata = resource.metadata();
+            let name = if metadata.name.is_empty() {
+                &metadata.generate_name
+            } else {
+                &metadata.name
+            };
+            (&metadata.namespace, name)
+        }
+        Some(match self {
+            ControllerAction::NodeJoin(name, _) => ("", name.as_str()),
+            ControllerAction::PatchPod(namespace, name, _)
+            | ControllerAction::PatchDeployment(namespace, name, _)
+            | ControllerAction::PatchReplicaSet(namespace, name, _)
+            | ControllerAction::PatchStatefulSet(namespace, name, _)
+            | ControllerAction::PatchJob(namespace, name, _) => (namespace.as_str(), name.as_str()),
+            ControllerAction::DeleteNode(node) | ControllerAction::UpdateNode(node) => name(node),
+            ControllerAction::CreatePod(pod)
+            | ControllerAction::SoftDeletePod(pod)
+            | ControllerAction::HardDeletePod(pod)
+            | ControllerAction::UpdatePod(pod) => name(pod),
+            ControllerAction::CreateDeployment(dep)
+            | ControllerAction::UpdateDeployment(dep)
+            | ControllerAction::DeleteDeployment(dep)
+            | ControllerAction::UpdateDeploymentStatus(dep) => name(dep),
+            ControllerAction::ScaleDeployment(scale)
+            | ControllerAction::ScaleReplicaSet(scale)
+            | ControllerAction::ScaleStatefulSet(scale) => (
+                scale.metadata.namespace.as_str(),
+                scale.metadata.name.as_str(),
+            ),
+            ControllerAction::RollbackDeployment(namespace, rollback) => {
+                (namespace.as_str(), rollback.name.as_str())
+            }
+            ControllerAction::CreateReplicaSet(rs)
+            | ControllerAction::UpdateReplicaSet(rs)
+            | ControllerAction::UpdateReplicaSetStatus(rs)
+            | ControllerAction::DeleteReplicaSet(rs) => name(rs),
+            ControllerAction::UpdateReplicationControllerStatus(rc)
+            | ControllerAction::DeleteReplicationController(rc) => name(rc),
+            ControllerAction::CreateStatefulSet(sts)
+            | ControllerAction::UpdateStatefulSet(sts)
+            | ControllerAction::UpdateStatefulSetStatus(sts)
+            | ControllerAction::DeleteStatefulSet(sts) => name(sts),
+            ControllerAction::CreateControllerRevision(cr)
+            | ControllerAction::UpdateControllerRevision(cr)
+            | ControllerAction::DeleteControllerRevision(cr) => name(cr),
+            ControllerAction::CreatePersistentVolumeClaim(pvc)
+            | ControllerAction::UpdatePersistentVolumeClaim(pvc)
+            | ControllerAction::DeletePersistentVolumeClaim(pvc)
+            | ControllerAction::UpdatePersistentVolumeClaimStatus(pvc) => name(pvc),
+            ControllerAction::CreatePersistentVolume(pv)
+            | ControllerAction::UpdatePersistentVolume(pv)
+            | ControllerAction::UpdatePersistentVolumeStatus(pv) => name(pv),
+            ControllerAction::CreateLease(lease) | ControllerAction::UpdateLease(lease) => {
+                name(lease)
+            }
+            ControllerAction::DeleteService(svc) => name(svc),
+            ControllerAction::CreateEndpoints(ep)
+            | ControllerAction::UpdateEndpoints(ep)
+            | ControllerAction::DeleteEndpoints(ep) => name(ep),
+            ControllerAction::CreateConfigMap(cm)
+            | ControllerAction::UpdateConfigMap(cm)
+            | ControllerAction::DeleteConfigMap(cm) => name(cm),
+            ControllerAction::CreateSecret(secret)
+            | ControllerAction::UpdateSecret(secret)
+            | ControllerAction::DeleteSecret(secret) => name(secret),
+            ControllerAction::CreateJob(job)
+            | ControllerAction::UpdateJob(job)
+            | ControllerAction::UpdateJobStatus(job)
+            | ControllerAction::DeleteJob(job) => name(job),
+            ControllerAction::UpdateCronJobStatus(cronjob)
+            | ControllerAction::DeleteCronJob(cronjob) => name(cronjob),
+            ControllerAction::UpdateHorizontalPodAutoscalerStatus(hpa)
+            | ControllerAction::DeleteHorizontalPodAutoscaler(hpa) => name(hpa),
+            ControllerAction::SoftDeleteNamespace(ns) | ControllerAction::FinalizeNamespace(ns) => {
+                name(ns)
+            }
+            ControllerAction::Transaction(writes) => {
+                writes.first()?.resource_namespace_and_name()?
+            }
+            ControllerAction::RequeueDeployment(_, _)
+            | ControllerAction::AdvanceClock(_)
+            | ControllerAction::UpdateMetric(_, _, _) => return None,
+        })
+    }
 }
 
+/// How far the clock moves forward in a single [`Action::AdvanceClock`], matching the granularity
+/// of cron schedules.
+pub const CLOCK_STEP_SECONDS: u64 = 60;
+
+/// The utilization readings that the metrics source can report in an [`Action::UpdateMetric`].
+/// A small set keeps the state space bounded while still covering readings below, at and above a
+/// typical target.
+pub const METRIC_LEVELS: [u32; 3] = [25, 50, 100];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Action {
     ControllerStep(Revision, usize),
     ArbitraryStep(ArbitraryClientAction),
+    /// The recorded client operation at the given index is taken, the operations before it having
+    /// been taken already.
+    ClientOperation(usize),
 
     /// The controller at the given index restarts, losing its state.
     ControllerRestart(usize),
+    /// The controller at the given index crashes, losing its state and taking no steps until it
+    /// is restarted.
+    ControllerCrash(usize),
     NodeRestart(usize),
+    /// The controller at the given index is replaced by its upgraded implementation, starting
+    /// from a fresh state.
+    ControllerUpgrade(usize),
+    /// Time passes in the cluster, moving the clock forward by [`CLOCK_STEP_SECONDS`].
+    AdvanceClock,
+    /// The metrics source reports a new utilization for the autoscaler with the given namespace
+    /// and name.
+    UpdateMetric(String, String, u32),
+    /// The node at the given controller index is partitioned from the control plane.
+    /// Its kubelet keeps running what it has but can no longer reach the API.
+    NodePartition(usize),
+    /// The node at the given controller index crashes, stopping its pods until it is restarted.
+    NodeCrash(usize),
+    /// The node at the given controller index is cordoned and its pods evicted.
+    NodeDrain(usize),
+    /// The drained node at the given controller index has pods scheduled to it again.
+    NodeUncordon(usize),
+    /// The node at the given controller index comes under, or stays under, the given pressure
+    /// condition, its kubelet evicting a pod.
+    NodePressure(usize, NodeConditionType),
+    /// The given pressure condition of the node at the given controller index is relieved.
+    NodePressureRelieved(usize, NodeConditionType),
+    /// The controller at the given index rebuilds its cache from a relist at the given revision
+    /// that has not yet returned any resources of the given kind, then takes a step.
+    ControllerRelist(Revision, usize, ResourceKind),
+    /// The watch cache of the controller at the given index catches up to the given revision.
+    ControllerResync(Revision, usize),
+    /// The message at the given index is delivered to the API server.
+    DeliverMessage(usize),
+    /// The message at the given index is lost on its way to the API server, to be resent if the
+    /// controller has retries left for it.
+    DropMessage(usize),
+    /// The message at the given index is delivered to the API server but the response to it is
+    /// lost, so the controller resends it.
+    DuplicateMessage(usize),
+    /// The first replica of the state sends its latest state to the second, which merges it in.
+    AntiEntropy(usize, usize),
+    /// The given member of the store is partitioned from the rest, electing a new leader if it was
+    /// the leader.
+    StorePartition(usize),
+    /// The partitioned member of the store rejoins the rest and catches up.
+    StoreHeal,
+}
+
+impl Action {
+    /// The index of the controller that takes the action, if it is taken by one.
+    pub fn controller(&self) -> Option<usize> {
+        match self {
+            Action::ControllerStep(_, i)
+            | Action::ControllerRelist(_, i, _)
+            | Action::ControllerResync(_, i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            Action::ControllerStep(_, _) => ActionKind::ControllerStep,
+            Action::ArbitraryStep(_) => ActionKind::ArbitraryStep,
+            Action::ClientOperation(_) => ActionKind::ClientOperation,
+            Action::ControllerRestart(_) => ActionKind::ControllerRestart,
+            Action::ControllerCrash(_) => ActionKind::ControllerCrash,
+            Action::NodeRestart(_) => ActionKind::NodeRestart,
+            Action::ControllerUpgrade(_) => ActionKind::ControllerUpgrade,
+            Action::AdvanceClock => ActionKind::AdvanceClock,
+            Action::UpdateMetric(_, _, _) => ActionKind::UpdateMetric,
+            Action::NodePartition(_) => ActionKind::NodePartition,
+            Action::NodeCrash(_) => ActionKind::NodeCrash,
+            Action::NodeDrain(_) | Action::NodeUncordon(_) => ActionKind::NodeDrain,
+            Action::NodePressure(_, _) | Action::NodePressureRelieved(_, _) => {
+                ActionKind::NodePressure
+            }
+            Action::ControllerRelist(_, _, _) => ActionKind::ControllerRelist,
+            Action::ControllerResync(_, _) => ActionKind::ControllerResync,
+            Action::DeliverMessage(_) => ActionKind::DeliverMessage,
+            Action::DropMessage(_) | Action::DuplicateMessage(_) => ActionKind::MessageFault,
+            Action::AntiEntropy(_, _) => ActionKind::AntiEntropy,
+            Action::StorePartition(_) => ActionKind::StorePartition,
+            Action::StoreHeal => ActionKind::StoreHeal,
+        }
+    }
+}
+
+/// The kinds of [`Action`], for leaving some out of the exploration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ActionKind {
+    ControllerStep,
+    ArbitraryStep,
+    ClientOperation,
+    ControllerRestart,
+    ControllerCrash,
+    NodeRestart,
+    ControllerUpgrade,
+    AdvanceClock,
+    UpdateMetric,
+    NodePartition,
+    NodeCrash,
+    /// Draining nodes and uncordoning them again.
+    NodeDrain,
+    /// Nodes coming under pressure and having it relieved again.
+    NodePressure,
+    ControllerRelist,
+    ControllerResync,
+    DeliverMessage,
+    /// Messages being dropped and duplicated.
+    MessageFault,
+    AntiEntropy,
+    StorePartition,
+    StoreHeal,
+}
+
+impl ActionKind {
+    pub const ALL: [ActionKind; 20] = [
+        ActionKind::ControllerStep,
+        ActionKind::ArbitraryStep,
+        ActionKind::ClientOperation,
+        ActionKind::ControllerRestart,
+        ActionKind::ControllerCrash,
+        ActionKind::NodeRestart,
+        ActionKind::ControllerUpgrade,
+        ActionKind::AdvanceClock,
+        ActionKind::UpdateMetric,
+        ActionKind::NodePartition,
+        ActionKind::NodeCrash,
+        ActionKind::NodeDrain,
+        ActionKind::NodePressure,
+        ActionKind::ControllerRelist,
+        ActionKind::ControllerResync,
+        ActionKind::DeliverMessage,
+        ActionKind::MessageFault,
+        ActionKind::AntiEntropy,
+        ActionKind::StorePartition,
+        ActionKind::StoreHeal,
+    ];
+}
+
+impl FromStr for ActionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "controller-step" => ActionKind::ControllerStep,
+            "arbitrary-step" => ActionKind::ArbitraryStep,
+            "client-operation" => ActionKind::ClientOperation,
+            "controller-restart" => ActionKind::ControllerRestart,
+            "controller-crash" => ActionKind::ControllerCrash,
+            "node-restart" => ActionKind::NodeRestart,
+            "controller-upgrade" => ActionKind::ControllerUpgrade,
+            "advance-clock" => ActionKind::AdvanceClock,
+            "update-metric" => ActionKind::UpdateMetric,
+            "node-partition" => ActionKind::NodePartition,
+            "node-crash" => ActionKind::NodeCrash,
+            "node-drain" => ActionKind::NodeDrain,
+            "node-pressure" => ActionKind::NodePressure,
+            "controller-relist" => ActionKind::ControllerRelist,
+            "controller-resync" => ActionKind::ControllerResync,
+            "deliver-message" => ActionKind::DeliverMessage,
+            "message-fault" => ActionKind::MessageFault,
+            "anti-entropy" => ActionKind::AntiEntropy,
+            "store-partition" => ActionKind::StorePartition,
+            "store-heal" => ActionKind::StoreHeal,
+            _ => return Err(format!("unknown action kind {s:?}")),
+        })
+    }
+}
+
+impl ActionKind {
+    /// The class of the kind, for weighting the kinds against each other when simulating.
+    pub fn class(self) -> ActionClass {
+        match self {
+            ActionKind::ArbitraryStep | ActionKind::ClientOperation | ActionKind::UpdateMetric => {
+                ActionClass::Client
+            }
+            ActionKind::ControllerStep
+            | ActionKind::ControllerRelist
+            | ActionKind::ControllerResync
+            | ActionKind::DeliverMessage
+            | ActionKind::AntiEntropy
+            | ActionKind::AdvanceClock => ActionClass::Controller,
+            ActionKind::ControllerRestart
+            | ActionKind::ControllerCrash
+            | ActionKind::NodeRestart
+            | ActionKind::ControllerUpgrade
+            | ActionKind::NodePartition
+            | ActionKind::NodeCrash
+            | ActionKind::NodeDrain
+            | ActionKind::NodePressure
+            | ActionKind::MessageFault
+            | ActionKind::StorePartition
+            | ActionKind::StoreHeal => ActionClass::Fault,
+        }
+    }
+}
+
+/// Broad classes of [`ActionKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ActionClass {
+    /// Changes from outside of the cluster: clients and the metrics that they drive.
+    Client,
+    /// The cluster getting on with its work: controllers stepping and catching up, the store
+    /// replicating and time passing.
+    Controller,
+    /// Things going wrong: restarts, upgrades, partitions, crashes, drains and lost messages.
+    Fault,
+}
+
+impl FromStr for ActionClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "client" => ActionClass::Client,
+            "controller" => ActionClass::Controller,
+            "fault" => ActionClass::Fault,
+            _ => return Err(format!("unknown action class {s:?}")),
+        })
+    }
+}
+
+/// The resources that a controller sees, as the names it sees of each kind.
+/// Kinds that aren't listed are seen in full.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ControllerScope(pub BTreeMap<ResourceKind, BTreeSet<String>>);
+
+impl ControllerScope {
+    /// Only see the named resources of the given kind, along with any already in scope.
+    pub fn with(mut self, kind: ResourceKind, names: impl IntoIterator<Item = String>) -> Self {
+        self.0.entry(kind).or_default().extend(names);
+        self
+    }
+}
+
+/// Which of the properties to check, by name, and which of them only warn when they don't hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropertySelection {
+    /// The only properties to check, all of them when not given.
+    pub only: Option<BTreeSet<String>>,
+    /// Properties not to check.
+    pub skip: BTreeSet<String>,
+    /// Properties that are reported as warnings rather than failures when they don't hold.
+    pub warnings: BTreeSet<String>,
+}
+
+impl PropertySelection {
+    /// Whether the property is checked.
+    pub fn includes(&self, name: &str) -> bool {
+        self.only.as_ref().map_or(true, |only| only.contains(name)) && !self.skip.contains(name)
+    }
+
+    /// Whether the property only warns when it doesn't hold.
+    pub fn is_warning(&self, name: &str) -> bool {
+        self.warnings.contains(name)
+    }
+
+    /// All of the names that the selection refers to.
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.only
+            .iter()
+            .flatten()
+            .chain(&self.skip)
+            .chain(&self.warnings)
+    }
 }
 
 impl Model for AbstractModel {
@@ -127,30 +1882,184 @@ impl Model for AbstractModel {
     }
 
     fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
-        for (i, controller) in self.controllers.iter().enumerate() {
-            let cstate = state.get_controller(i);
-            let min_revision = controller.min_revision_accepted(cstate);
-            for revision in state.revisions(min_revision) {
-                debug!(?revision, "Adding revision choice");
-                actions.push(Action::ControllerStep(revision, i));
-            }
+        for i in 0..self.controllers.len() {
+            self.controller_actions(state, i, actions);
         }
 
         // arbitrary client
         let latest_view = state.latest();
-        let arbitrary_actions = ArbitraryClient::actions(&latest_view)
-            .into_iter()
-            .map(Action::ArbitraryStep);
+        let arbitrary_actions = ArbitraryClient::actions(
+            &latest_view,
+            &self.initial_resources,
+            &self.arbitrary_client,
+        )
+        .into_iter()
+        .map(Action::ArbitraryStep);
         actions.extend(arbitrary_actions);
 
-        for (i, controller) in self.controllers.iter().enumerate() {
+        // recorded client, taking its operations in order once their resources exist
+        let next_operation = state.client_operations();
+        if let Some(operation) = self.client_operations.get(next_operation) {
+            if operation.controller_action(&latest_view).is_some() {
+                actions.push(Action::ClientOperation(next_operation));
+            }
+        }
+
+        for i in 0..self.controllers.len() {
+            let controller = self.controller(state, i);
             if matches!(controller, Controllers::Node(_)) {
                 // skip nodes for now
                 continue;
             }
+            if state.controller_crashed(i) {
+                // crashed controllers only come back by restarting
+                actions.push(Action::ControllerRestart(i));
+                continue;
+            }
             if state.get_controller(i) != &controller.new_state() {
                 actions.push(Action::ControllerRestart(i));
             }
+            if self.controller_crashes {
+                actions.push(Action::ControllerCrash(i));
+            }
+        }
+
+        if self.relist_faults {
+            for i in 0..self.controllers.len() {
+                if matches!(self.controller(state, i), Controllers::Node(_))
+                    || state.controller_crashed(i)
+                {
+                    // nodes only watch their own pods, skip them like for restarts, and crashed
+                    // controllers have no cache to rebuild
+                    continue;
+                }
+                // relists can land on any revision still stored, even ones older than the
+                // controller has seen
+                for revision in state.retained_revisions() {
+                    for kind in ResourceKind::ALL {
+                        actions.push(Action::ControllerRelist(revision.clone(), i, kind));
+                    }
+                }
+            }
+        }
+
+        // messages can be delivered in any order, or lost
+        for (i, message) in state.messages().iter().enumerate() {
+            actions.push(Action::DeliverMessage(i));
+            actions.push(Action::DropMessage(i));
+            if message.retries > 0 {
+                actions.push(Action::DuplicateMessage(i));
+            }
+        }
+
+        for from in 0..state.replicas() {
+            for to in 0..state.replicas() {
+                if from != to {
+                    actions.push(Action::AntiEntropy(from, to));
+                }
+            }
+        }
+
+        if state.store_members() > 1 {
+            if state.partitioned_store_member().is_some() {
+                actions.push(Action::StoreHeal);
+            } else {
+                for member in 0..state.store_members() {
+                    actions.push(Action::StorePartition(member));
+                }
+            }
+        }
+
+        // only let time pass when something depends on it, to avoid growing the state space
+        // needlessly
+        if !latest_view.cronjobs.is_empty()
+            || (self.leader_election && !latest_view.leases.is_empty())
+            || (self
+                .controllers
+                .iter()
+                .any(|c| matches!(c, Controllers::NodeLifecycle(_)))
+                && nodelifecycle::evictions_pending(&latest_view))
+            || (self
+                .controllers
+                .iter()
+                .any(|c| matches!(c, Controllers::Node(n) if n.lease_duration_seconds.is_some()))
+                && !latest_view.nodes.is_empty())
+            || deployment::progress_deadlines_pending(&latest_view)
+            || job::active_deadlines_pending(&latest_view)
+            || (0..self.controllers.len()).any(|i| {
+                state
+                    .workqueue(i)
+                    .map_or(false, |q| q.has_waiting(latest_view.clock))
+            })
+        {
+            actions.push(Action::AdvanceClock);
+        }
+
+        // the metrics source is free to report any level for each autoscaler at any time
+        for hpa in latest_view.horizontal_pod_autoscalers.iter() {
+            let key = (hpa.metadata.namespace.clone(), hpa.metadata.name.clone());
+            let current = latest_view.metrics.get(&key);
+            for level in METRIC_LEVELS {
+                if current != Some(&level) {
+                    actions.push(Action::UpdateMetric(key.0.clone(), key.1.clone(), level));
+                }
+            }
+        }
+
+        for i in self.upgrades.keys() {
+            if !state.controller_upgraded(*i) {
+                actions.push(Action::ControllerUpgrade(*i));
+            }
+        }
+
+        if self.node_partitions {
+            for (i, controller) in self.controllers.iter().enumerate() {
+                if let Controllers::Node(n) = controller {
+                    if !state.node_partitioned(i) && latest_view.nodes.has(&n.name) {
+                        actions.push(Action::NodePartition(i));
+                    }
+                }
+            }
+        }
+
+        for (i, controller) in self.controllers.iter().enumerate() {
+            let Controllers::Node(n) = controller else {
+                continue;
+            };
+            let Some(node) = latest_view.nodes.get(&n.name) else {
+                continue;
+            };
+            if self.node_crashes && !state.node_crashed(i) && !state.node_partitioned(i) {
+                actions.push(Action::NodeCrash(i));
+            }
+            if state.node_crashed(i) {
+                // crashed nodes come back up by restarting
+                actions.push(Action::NodeRestart(i));
+            }
+            if self.node_drains {
+                if node.spec.unschedulable {
+                    actions.push(Action::NodeUncordon(i));
+                } else {
+                    actions.push(Action::NodeDrain(i));
+                }
+            }
+            if self.node_pressure && !state.node_crashed(i) && !state.node_partitioned(i) {
+                for condition in [
+                    NodeConditionType::MemoryPressure,
+                    NodeConditionType::DiskPressure,
+                ] {
+                    let under_pressure = node_faults::under_pressure(node, &condition);
+                    if under_pressure {
+                        actions.push(Action::NodePressureRelieved(i, condition.clone()));
+                    }
+                    // the pressure can keep up until there are no pods left to evict
+                    if !under_pressure
+                        || !node_faults::eviction_order(&latest_view, &n.name).is_empty()
+                    {
+                        actions.push(Action::NodePressure(i, condition));
+                    }
+                }
+            }
         }
 
         // at max revision as this isn't a controller event
@@ -171,78 +2080,36 @@ impl Model for AbstractModel {
                 }
             }
         }
-    }
 
-    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
-        match action {
-            Action::ControllerStep(revision, controller_index) => {
-                let controller = &self.controllers[controller_index];
-                let mut cstate = last_state.get_controller(controller_index).clone();
-                let view = &last_state.view_at(&revision);
-                let mut state = last_state.clone();
-                if let Some(action) = controller.step(view, &mut cstate) {
-                    state.push_change(Change {
-                        revision,
-                        operation: action,
-                    });
-                }
-                state.update_controller(controller_index, cstate);
-                Some(state)
-            }
-            Action::ArbitraryStep(action) => {
-                let mut state = last_state.clone();
-                let controller_action = ArbitraryClient::controller_action(&state.latest(), action);
-                state.push_change(Change {
-                    revision: state.max_revision(),
-                    operation: controller_action,
-                });
-                Some(state)
-            }
-            Action::ControllerRestart(controller_index) => {
-                let mut state = last_state.clone();
-                let controller_state = self.controllers[controller_index].new_state();
-                state.update_controller(controller_index, controller_state);
-                Some(state)
-            }
-            Action::NodeRestart(controller_index) => {
-                let mut state = last_state.clone();
-                let controller_state = self.controllers[controller_index].new_state();
-                state.update_controller(controller_index, controller_state);
-                let s = state.latest();
-                if let Controllers::Node(n) = &self.controllers[controller_index] {
-                    if let Some(node) = s.nodes.get(&n.name) {
-                        state.push_change(Change {
-                            revision: s.revision.clone(),
-                            operation: ControllerAction::DeleteNode(node.clone()),
-                        });
-                    }
-                }
-                Some(state)
+        if !self.disabled_actions.is_empty() {
+            actions.retain(|action| !self.disabled_actions.contains(&action.kind()));
+        }
+
+        if let Some(starved) = self.starved_controller(state) {
+            // the controller has waited long enough, it has to be the next to step
+            if actions.iter().any(|a| a.controller() == Some(starved)) {
+                actions.retain(|action| action.controller() == Some(starved));
             }
         }
     }
 
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let stepped = action.controller();
+        let mut state = self.next_state_uncompacted(last_state, action)?;
+        self.update_starvation(last_state, stepped, &mut state);
+        self.compact(&mut state);
+        Some(state)
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        !self.stop.load(Ordering::Relaxed)
+            && (self.explored.is_empty()
+                || !self.explored.contains(&stateright::fingerprint(state)))
+    }
+
     fn properties(&self) -> Vec<stateright::Property<Self>> {
-        let mut p = self.properties.clone();
-        p.append(&mut vec![Property::<Self>::always(
-            "all resources have unique names",
-            |_model, state| {
-                let state = state.latest();
-                all_unique(state.nodes.iter().map(|n| &n.metadata.name))
-                    && all_unique(state.pods.iter().map(|n| &n.metadata.name))
-                    && all_unique(state.replicasets.iter().map(|n| &n.metadata.name))
-                    && all_unique(state.deployments.iter().map(|n| &n.metadata.name))
-                    && all_unique(state.statefulsets.iter().map(|n| &n.metadata.name))
-                    && all_unique(state.controller_revisions.iter().map(|n| &n.metadata.name))
-                    && all_unique(
-                        state
-                            .persistent_volume_claims
-                            .iter()
-                            .map(|n| &n.metadata.name),
-                    )
-                    && all_unique(state.jobs.iter().map(|n| &n.metadata.name))
-            },
-        )]);
+        let mut p = self.all_properties();
+        p.retain(|p| self.property_selection.includes(p.name));
         p
     }
 
@@ -252,22 +2119,84 @@ impl Model for AbstractModel {
     {
         match action {
             Action::ControllerStep(rev, i) => {
-                let controller = &self.controllers[*i];
-                let view = last_state.view_at(rev);
+                let controller = self.controller(last_state, *i);
+                let view = self.view_for(last_state, rev, *i);
                 let mut cstate = last_state.get_controller(*i).clone();
-                let caction = controller
-                    .step(&view, &mut cstate)
-                    .map(|a| format!("{:?}", a))
-                    .unwrap_or_default();
-                let name = self.controllers[*i].name();
-                format!("{:?}: {} {}", action, name, caction)
+                let name = controller.name();
+                match self.elect(&view, *i) {
+                    None | Some(Election::Lead) => {}
+                    Some(Election::Lease(lease_action)) => {
+                        return format!("{:?}: {} {:?}", action, name, lease_action)
+                    }
+                    Some(Election::Follow) => return format!("{:?}: {} following", action, name),
+                }
+                let caction = controller.step(&view, &mut cstate);
+                let mut out = format!(
+                    "{:?}: {} {}",
+                    action,
+                    name,
+                    caction
+                        .as_ref()
+                        .map(|a| format!("{:?}", a))
+                        .unwrap_or_default()
+                );
+                if let Some(shadow) = self.shadows.get(i) {
+                    let mut shadow_state = last_state.get_shadow(*i).clone();
+                    let shadow_action = shadow.step(&view, &mut shadow_state);
+                    if shadow_action != caction {
+                        out.push_str(&format!(
+                            " (shadow {} diverged: {:?})",
+                            shadow.name(),
+                            shadow_action
+                        ));
+                    }
+                }
+                out
             }
             Action::ArbitraryStep(_) => format!("{:?}", action),
-            Action::ControllerRestart(i) => {
-                let name = self.controllers[*i].name();
+            Action::ClientOperation(i) => {
+                format!("{:?}: {:?}", action, self.client_operations[*i])
+            }
+            Action::ControllerRestart(i) | Action::ControllerCrash(i) => {
+                let name = self.controller(last_state, *i).name();
                 format!("{:?}: {}", action, name)
             }
+            Action::ControllerResync(_, i) => {
+                let name = self.controller(last_state, *i).name();
+                format!("{:?}: {}", action, name)
+            }
+            Action::DeliverMessage(i) | Action::DropMessage(i) | Action::DuplicateMessage(i) => {
+                let message = &last_state.messages()[*i];
+                let name = self.controller(last_state, message.controller).name();
+                format!("{:?}: {} {:?}", action, name, message.operation)
+            }
             Action::NodeRestart(_) => format!("{:?}", action),
+            Action::NodePartition(_) => format!("{:?}", action),
+            Action::NodeCrash(_) => format!("{:?}", action),
+            Action::NodeDrain(_) => format!("{:?}", action),
+            Action::NodeUncordon(_) => format!("{:?}", action),
+            Action::NodePressure(_, _) => format!("{:?}", action),
+            Action::NodePressureRelieved(_, _) => format!("{:?}", action),
+            Action::ControllerUpgrade(i) => {
+                let from = self.controller(last_state, *i).name();
+                let to = self.upgrades[i].name();
+                format!("{:?}: {} -> {}", action, from, to)
+            }
+            Action::AdvanceClock => format!("{:?}", action),
+            Action::AntiEntropy(_, _) => format!("{:?}", action),
+            Action::StorePartition(_) => format!("{:?}", action),
+            Action::StoreHeal => format!("{:?}", action),
+            Action::UpdateMetric(_, _, _) => format!("{:?}", action),
+            Action::ControllerRelist(rev, i, kind) => {
+                let controller = self.controller(last_state, *i);
+                let mut view = self.view_for(last_state, rev, *i).into_owned();
+                view.clear_kind(*kind);
+                let caction = controller
+                    .step(&view, &mut controller.new_state())
+                    .map(|a| format!("{:?}", a))
+                    .unwrap_or_default();
+                format!("{:?}: {} {}", action, controller.name(), caction)
+            }
         }
     }
 
@@ -1,13 +1,22 @@
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::APIResource;
+use k8s_openapi::ClusterResourceScope;
 use k8s_openapi::NamespaceResourceScope;
 use k8s_openapi::Resource;
 use serde::Serialize;
 
 use crate::resources::Deployment;
+use crate::resources::Job;
+use crate::resources::Meta;
+use crate::resources::Namespace;
 use crate::resources::Node;
 use crate::resources::Pod;
 use crate::resources::ReplicaSet;
+use crate::resources::ReplicationController;
 use crate::resources::Scale;
+use crate::resources::StatefulSet;
+
+pub mod patch;
+pub mod selector;
 
 pub trait APIObject: Resource {
     fn api_resource() -> APIResource;
@@ -36,7 +45,15 @@ impl_resource!(
     "v1",
     "pods"
 );
-// impl_resource!(Job, "JobList");
+impl_resource!(
+    Job,
+    NamespaceResourceScope,
+    "batch/v1",
+    "batch",
+    "Job",
+    "v1",
+    "jobs"
+);
 impl_resource!(
     Deployment,
     NamespaceResourceScope,
@@ -55,17 +72,53 @@ impl_resource!(
     "v1",
     "replicasets"
 );
-// impl_resource!(StatefulSet, "StatefulSetList");
+impl_resource!(
+    ReplicationController,
+    NamespaceResourceScope,
+    "v1",
+    "core",
+    "ReplicationController",
+    "v1",
+    "replicationcontrollers"
+);
+impl_resource!(
+    StatefulSet,
+    NamespaceResourceScope,
+    "apps/v1",
+    "apps",
+    "StatefulSet",
+    "v1",
+    "statefulsets"
+);
 // impl_resource!(PersistentVolumeClaim, "PersistentVolumeClaimList");
 impl_resource!(
     Node,
-    NamespaceResourceScope,
+    ClusterResourceScope,
     "v1",
     "core",
     "Node",
     "v1",
     "nodes"
 );
+impl_resource!(
+    Namespace,
+    ClusterResourceScope,
+    "v1",
+    "core",
+    "Namespace",
+    "v1",
+    "namespaces"
+);
+
+impl_resource!(
+    Scale,
+    NamespaceResourceScope,
+    "autoscaling/v1",
+    "autoscaling",
+    "Scale",
+    "v1",
+    "scale"
+);
 
 macro_rules! impl_listable {
     ($r:ident, $kind:expr) => {
@@ -76,12 +129,14 @@ macro_rules! impl_listable {
 }
 
 impl_listable!(Pod, "PodList");
-// impl_listable!(Job, "JobList");
+impl_listable!(Job, "JobList");
 impl_listable!(Deployment, "DeploymentList");
 impl_listable!(ReplicaSet, "ReplicaSetList");
-// impl_listable!(StatefulSet, "StatefulSetList");
+impl_listable!(ReplicationController, "ReplicationControllerList");
+impl_listable!(StatefulSet, "StatefulSetList");
 // impl_listable!(PersistentVolumeClaim, "PersistentVolumeClaimList");
 impl_listable!(Node, "NodeList");
+impl_listable!(Namespace, "NamespaceList");
 //
 macro_rules! impl_api_object {
     ($r:ident) => {
@@ -95,7 +150,7 @@ macro_rules! impl_api_object {
                     group: None,
                     kind: $r::KIND.to_owned(),
                     name: plural_name,
-                    namespaced: true,
+                    namespaced: <$r as Meta>::NAMESPACED,
                     short_names: None,
                     singular_name: $r::KIND.to_lowercase(),
                     storage_version_hash: None,
@@ -116,12 +171,14 @@ macro_rules! impl_api_object {
 }
 
 impl_api_object!(Pod);
-// impl_api_object!(Job);
+impl_api_object!(Job);
 impl_api_object!(Deployment);
 impl_api_object!(ReplicaSet);
-// impl_api_object!(StatefulSet);
+impl_api_object!(ReplicationController);
+impl_api_object!(StatefulSet);
 // impl_api_object!(PersistentVolumeClaim);
 impl_api_object!(Node);
+impl_api_object!(Namespace);
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -173,3 +230,14 @@ impl Scale {
         }
     }
 }
+
+/// The status subresource of a kind of resource, for discovery.
+pub fn status_api_resource<K: APIObject>() -> APIResource {
+    let resource = K::api_resource();
+    APIResource {
+        name: format!("{}/status", resource.name),
+        singular_name: "".to_owned(),
+        verbs: vec!["get".to_owned(), "patch".to_owned(), "update".to_owned()],
+        ..resource
+    }
+}
@@ -0,0 +1,317 @@
+//! Patches to resources, as accepted by the API server for `PATCH` requests.
+//!
+//! Patches are applied to the latest version of a resource rather than replacing it, so they only
+//! conflict with concurrent writes when they carry a resource version themselves.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A patch in one of the formats the API server accepts, holding its JSON body.
+///
+/// The body is kept as text so that patches can be part of hashed actions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Patch {
+    /// A list of operations, as in RFC 6902.
+    Json(String),
+    /// A partial resource merged into the existing one, as in RFC 7386.
+    Merge(String),
+    /// A merge patch that merges lists of objects by their key rather than replacing them.
+    ///
+    /// THEMELIOS: Only the merge keys of the fields on pods and workloads are known, along with
+    /// the `$patch` and `$deleteFromPrimitiveList` directives.
+    StrategicMerge(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The patch isn't valid for its format.
+    Invalid(String),
+    /// An operation referred to a location that doesn't exist.
+    PathNotFound(String),
+    /// A test operation didn't match.
+    TestFailed(String),
+    /// The patched resource is no longer a valid resource of its kind.
+    InvalidResult(String),
+}
+
+/// Fields of lists that are merged by the given key in strategic merge patches.
+const MERGE_KEYS: &[(&str, &str)] = &[
+    ("containers", "name"),
+    ("initContainers", "name"),
+    ("ephemeralContainers", "name"),
+    ("volumes", "name"),
+    ("volumeMounts", "mountPath"),
+    ("env", "name"),
+    ("ports", "containerPort"),
+    ("imagePullSecrets", "name"),
+    ("conditions", "type"),
+    ("ownerReferences", "uid"),
+    ("topologySpreadConstraints", "topologyKey"),
+];
+
+/// Fields of lists of plain values that are merged as sets in strategic merge patches.
+const MERGED_PRIMITIVE_LISTS: &[&str] = &["finalizers"];
+
+const DELETE_FROM_PRIMITIVE_LIST: &str = "$deleteFromPrimitiveList/";
+const SET_ELEMENT_ORDER: &str = "$setElementOrder/";
+const PATCH_DIRECTIVE: &str = "$patch";
+
+impl Patch {
+    pub fn json(operations: Value) -> Self {
+        Self::Json(operations.to_string())
+    }
+
+    pub fn merge(patch: Value) -> Self {
+        Self::Merge(patch.to_string())
+    }
+
+    pub fn strategic_merge(patch: Value) -> Self {
+        Self::StrategicMerge(patch.to_string())
+    }
+
+    /// The patch for the body of a request with the given content type, none if the content type
+    /// isn't one for patches.
+    pub fn from_content_type(content_type: &str, body: String) -> Option<Self> {
+        match content_type.split(';').next().unwrap_or_default().trim() {
+            "application/json-patch+json" => Some(Self::Json(body)),
+            "application/merge-patch+json" => Some(Self::Merge(body)),
+            "application/strategic-merge-patch+json" => Some(Self::StrategicMerge(body)),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json(_) => "application/json-patch+json",
+            Self::Merge(_) => "application/merge-patch+json",
+            Self::StrategicMerge(_) => "application/strategic-merge-patch+json",
+        }
+    }
+
+    /// Apply the patch to the resource, returning the patched copy.
+    pub fn apply<T: Serialize + DeserializeOwned>(&self, resource: &T) -> Result<T, PatchError> {
+        let mut target =
+            serde_json::to_value(resource).map_err(|e| PatchError::InvalidResult(e.to_string()))?;
+        let (Self::Json(body) | Self::Merge(body) | Self::StrategicMerge(body)) = self;
+        let patch: Value =
+            serde_json::from_str(body).map_err(|e| PatchError::Invalid(e.to_string()))?;
+        match self {
+            Self::Json(_) => json_patch(&mut target, &patch)?,
+            Self::Merge(_) => merge_patch(&mut target, &patch),
+            Self::StrategicMerge(_) => strategic_merge_patch(&mut target, &patch)?,
+        }
+        // deserialize from a reference so that resources can borrow strings from it
+        T::deserialize(&target).map_err(|e| PatchError::InvalidResult(e.to_string()))
+    }
+}
+
+fn json_patch(target: &mut Value, operations: &Value) -> Result<(), PatchError> {
+    let operations = operations
+        .as_array()
+        .ok_or_else(|| PatchError::Invalid("json patch must be a list of operations".to_owned()))?;
+    for operation in operations {
+        let field = |name: &str| {
+            operation
+                .get(name)
+                .ok_or_else(|| PatchError::Invalid(format!("operation is missing {name:?}")))
+        };
+        let pointer = |name: &str| {
+            field(name)?
+                .as_str()
+                .ok_or_else(|| PatchError::Invalid(format!("{name:?} must be a string")))
+        };
+        let path = pointer("path")?;
+        match field("op")?.as_str() {
+            Some("add") => add(target, path, field("value")?.clone())?,
+            Some("remove") => {
+                remove(target, path)?;
+            }
+            Some("replace") => {
+                remove(target, path)?;
+                add(target, path, field("value")?.clone())?;
+            }
+            Some("move") => {
+                let value = remove(target, pointer("from")?)?;
+                add(target, path, value)?;
+            }
+            Some("copy") => {
+                let from = pointer("from")?;
+                let value = target
+                    .pointer(from)
+                    .ok_or_else(|| PatchError::PathNotFound(from.to_owned()))?
+                    .clone();
+                add(target, path, value)?;
+            }
+            Some("test") => {
+                if target.pointer(path) != Some(field("value")?) {
+                    return Err(PatchError::TestFailed(path.to_owned()));
+                }
+            }
+            op => return Err(PatchError::Invalid(format!("unknown operation {op:?}"))),
+        }
+    }
+    Ok(())
+}
+
+/// Split a JSON pointer into the pointer to its parent and the unescaped last token.
+fn split_pointer(path: &str) -> Result<(&str, String), PatchError> {
+    let (parent, last) = path
+        .rsplit_once('/')
+        .ok_or_else(|| PatchError::Invalid(format!("invalid path {path:?}")))?;
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+fn add(target: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    let not_found = || PatchError::PathNotFound(path.to_owned());
+    let (parent, token) = split_pointer(path)?;
+    match target.pointer_mut(parent).ok_or_else(not_found)? {
+        Value::Object(map) => {
+            map.insert(token, value);
+        }
+        Value::Array(list) => {
+            let index = if token == "-" {
+                list.len()
+            } else {
+                token.parse().map_err(|_| not_found())?
+            };
+            if index > list.len() {
+                return Err(not_found());
+            }
+            list.insert(index, value);
+        }
+        _ => return Err(not_found()),
+    }
+    Ok(())
+}
+
+fn remove(target: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let not_found = || PatchError::PathNotFound(path.to_owned());
+    let (parent, token) = split_pointer(path)?;
+    match target.pointer_mut(parent).ok_or_else(not_found)? {
+        Value::Object(map) => map.remove(&token).ok_or_else(not_found),
+        Value::Array(list) => {
+            let index: usize = token.parse().map_err(|_| not_found())?;
+            if index >= list.len() {
+                return Err(not_found());
+            }
+            Ok(list.remove(index))
+        }
+        _ => Err(not_found()),
+    }
+}
+
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().unwrap();
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn strategic_merge_patch(target: &mut Value, patch: &Value) -> Result<(), PatchError> {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return Ok(());
+    };
+    match patch.get(PATCH_DIRECTIVE).and_then(Value::as_str) {
+        None | Some("merge") => {}
+        Some("replace") => {
+            *target = Value::Object(without_directives(patch));
+            return Ok(());
+        }
+        Some(directive) => {
+            return Err(PatchError::Invalid(format!(
+                "unsupported patch directive {directive:?}"
+            )))
+        }
+    }
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().unwrap();
+    for (key, value) in patch {
+        if let Some(field) = key.strip_prefix(DELETE_FROM_PRIMITIVE_LIST) {
+            let deleted = value.as_array().ok_or_else(|| {
+                PatchError::Invalid(format!("{key:?} must be a list of values to delete"))
+            })?;
+            if let Some(Value::Array(list)) = target.get_mut(field) {
+                list.retain(|v| !deleted.contains(v));
+            }
+        } else if key.starts_with(SET_ELEMENT_ORDER) || key == PATCH_DIRECTIVE {
+            // the order of merged lists isn't modelled
+        } else if key.starts_with('$') {
+            return Err(PatchError::Invalid(format!(
+                "unsupported patch directive {key:?}"
+            )));
+        } else if value.is_null() || is_deletion(value) {
+            target.remove(key);
+        } else if let (Value::Array(patch_list), Some(Value::Array(list))) =
+            (value, target.get_mut(key))
+        {
+            if let Some((_, merge_key)) = MERGE_KEYS.iter().find(|(field, _)| *field == key) {
+                merge_lists(list, patch_list, merge_key)?;
+            } else if MERGED_PRIMITIVE_LISTS.contains(&key.as_str()) {
+                for v in patch_list {
+                    if !list.contains(v) {
+                        list.push(v.clone());
+                    }
+                }
+            } else {
+                *list = patch_list.clone();
+            }
+        } else {
+            strategic_merge_patch(target.entry(key).or_insert(Value::Null), value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Merge the patched items into the list, matching them up by the key.
+fn merge_lists(list: &mut Vec<Value>, patch: &[Value], merge_key: &str) -> Result<(), PatchError> {
+    for item in patch {
+        let key = item.get(merge_key).ok_or_else(|| {
+            PatchError::Invalid(format!("list item is missing its merge key {merge_key:?}"))
+        })?;
+        let existing = list.iter().position(|v| v.get(merge_key) == Some(key));
+        match (existing, is_deletion(item)) {
+            (Some(i), true) => {
+                list.remove(i);
+            }
+            (None, true) => {}
+            (Some(i), false) => strategic_merge_patch(&mut list[i], item)?,
+            (None, false) => {
+                let mut new = Value::Null;
+                strategic_merge_patch(&mut new, item)?;
+                list.push(new);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the patch is for deleting the value it is merged into.
+fn is_deletion(patch: &Value) -> bool {
+    patch.get(PATCH_DIRECTIVE).and_then(Value::as_str) == Some("delete")
+}
+
+fn without_directives(patch: &Map<String, Value>) -> Map<String, Value> {
+    patch
+        .iter()
+        .filter(|(k, _)| !k.starts_with('$'))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
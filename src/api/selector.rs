@@ -0,0 +1,267 @@
+//! Label and field selectors of list requests, as given in the `labelSelector` and
+//! `fieldSelector` query parameters.
+//!
+//! Label selectors take both the equality-based (`tier=web,env!=prod`) and the set-based
+//! (`tier in (web,api),!canary`) requirements, field selectors only the equality-based ones.
+
+use std::collections::BTreeMap;
+
+use crate::resources::{
+    Deployment, Job, Meta, Namespace, Node, Pod, ReplicaSet, ReplicationController, StatefulSet,
+};
+
+/// How a requirement relates the value under its key to its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equals,
+    NotEquals,
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist,
+}
+
+/// A single requirement of a selector, all of which must hold for it to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub key: String,
+    pub operator: Operator,
+    pub values: Vec<String>,
+}
+
+impl Requirement {
+    /// Whether the values under the keys meet the requirement, those without the key only meet
+    /// the negative ones.
+    pub fn matches(&self, values: &BTreeMap<String, String>) -> bool {
+        let value = values.get(&self.key);
+        match self.operator {
+            Operator::Equals | Operator::In => value.map_or(false, |v| self.values.contains(v)),
+            Operator::NotEquals | Operator::NotIn => {
+                value.map_or(true, |v| !self.values.contains(v))
+            }
+            Operator::Exists => value.is_some(),
+            Operator::DoesNotExist => value.is_none(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError(pub String);
+
+impl std::fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid selector: {}", self.0)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// A selector made of requirements, the empty one matching everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selector(pub Vec<Requirement>);
+
+impl Selector {
+    /// Parse a label selector.
+    pub fn parse_labels(selector: &str) -> Result<Self, SelectorError> {
+        split(selector)?
+            .into_iter()
+            .map(parse_requirement)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Parse a field selector, which only takes equality-based requirements.
+    pub fn parse_fields(selector: &str) -> Result<Self, SelectorError> {
+        let selector = Self::parse_labels(selector)?;
+        if let Some(r) = selector
+            .0
+            .iter()
+            .find(|r| !matches!(r.operator, Operator::Equals | Operator::NotEquals))
+        {
+            return Err(SelectorError(format!(
+                "field selectors only support =, == and !=, found {:?} for {}",
+                r.operator, r.key
+            )));
+        }
+        Ok(selector)
+    }
+
+    pub fn matches(&self, values: &BTreeMap<String, String>) -> bool {
+        self.0.iter().all(|r| r.matches(values))
+    }
+}
+
+/// Split a selector into its requirements at the commas that aren't within a set of values.
+fn split(selector: &str) -> Result<Vec<&str>, SelectorError> {
+    let mut requirements = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in selector.char_indices() {
+        match c {
+            '(' if depth == 0 => depth += 1,
+            ')' if depth == 1 => depth -= 1,
+            '(' | ')' => {
+                return Err(SelectorError(format!(
+                    "unbalanced parentheses in {selector}"
+                )))
+            }
+            ',' if depth == 0 => {
+                requirements.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(SelectorError(format!(
+            "unbalanced parentheses in {selector}"
+        )));
+    }
+    requirements.push(&selector[start..]);
+    if requirements.len() == 1 && requirements[0].trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(requirements)
+}
+
+fn parse_requirement(requirement: &str) -> Result<Requirement, SelectorError> {
+    let requirement = requirement.trim();
+    let new = |key: &str, operator, values: Vec<&str>| {
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(SelectorError(format!("invalid key in {requirement:?}")));
+        }
+        let values = values.into_iter().map(str::trim).collect::<Vec<_>>();
+        if values.iter().any(|v| v.contains(char::is_whitespace)) {
+            return Err(SelectorError(format!("invalid value in {requirement:?}")));
+        }
+        Ok(Requirement {
+            key: key.to_owned(),
+            operator,
+            values: values.into_iter().map(str::to_owned).collect(),
+        })
+    };
+
+    if let Some(key) = requirement.strip_prefix('!') {
+        return new(key, Operator::DoesNotExist, Vec::new());
+    }
+    if let Some(open) = requirement.find('(') {
+        let (head, set) = requirement.split_at(open);
+        let Some(set) = set.strip_prefix('(').and_then(|set| set.strip_suffix(')')) else {
+            return Err(SelectorError(format!("invalid set in {requirement:?}")));
+        };
+        let words = head.split_whitespace().collect::<Vec<_>>();
+        let operator = match words.as_slice() {
+            [_, "in"] => Operator::In,
+            [_, "notin"] => Operator::NotIn,
+            _ => {
+                return Err(SelectorError(format!(
+                    "expected in or notin in {requirement:?}"
+                )))
+            }
+        };
+        let values = set.split(',').collect::<Vec<_>>();
+        if values.iter().any(|v| v.trim().is_empty()) {
+            return Err(SelectorError(format!("empty value in {requirement:?}")));
+        }
+        return new(words[0], operator, values);
+    }
+    for (symbol, operator) in [
+        ("!=", Operator::NotEquals),
+        ("==", Operator::Equals),
+        ("=", Operator::Equals),
+    ] {
+        if let Some((key, value)) = requirement.split_once(symbol) {
+            return new(key, operator, vec![value]);
+        }
+    }
+    new(requirement, Operator::Exists, Vec::new())
+}
+
+/// Resources that can be selected by their fields, as well as by their labels.
+pub trait FieldSelectable: Meta {
+    /// The fields, other than `metadata.name` and `metadata.namespace`, that can be selected on.
+    const FIELDS: &'static [&'static str] = &[];
+
+    /// The value of one of the [`FieldSelectable::FIELDS`].
+    fn field(&self, _field: &str) -> Option<String> {
+        None
+    }
+
+    /// The values of the fields that can be selected on.
+    fn fields(&self) -> BTreeMap<String, String> {
+        let mut fields = BTreeMap::from([
+            ("metadata.name".to_owned(), self.metadata().name.clone()),
+            (
+                "metadata.namespace".to_owned(),
+                self.metadata().namespace.clone(),
+            ),
+        ]);
+        for field in Self::FIELDS {
+            fields.insert((*field).to_owned(), self.field(field).unwrap_or_default());
+        }
+        fields
+    }
+}
+
+impl FieldSelectable for Pod {
+    const FIELDS: &'static [&'static str] =
+        &["spec.nodeName", "spec.schedulerName", "status.phase"];
+
+    fn field(&self, field: &str) -> Option<String> {
+        match field {
+            "spec.nodeName" => self.spec.node_name.clone(),
+            "spec.schedulerName" => self.spec.scheduler_name.clone(),
+            "status.phase" => Some(format!("{:?}", self.status.phase)),
+            _ => None,
+        }
+    }
+}
+
+impl FieldSelectable for Deployment {}
+impl FieldSelectable for ReplicaSet {}
+impl FieldSelectable for ReplicationController {}
+impl FieldSelectable for StatefulSet {}
+impl FieldSelectable for Job {}
+impl FieldSelectable for Node {}
+impl FieldSelectable for Namespace {}
+
+/// The selectors of a list request, for the resources of one kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListQuery {
+    pub labels: Selector,
+    pub fields: Selector,
+}
+
+impl ListQuery {
+    /// The query for the selectors given, failing if either is invalid or the field selector
+    /// names fields that can't be selected on for the kind.
+    pub fn new<T: FieldSelectable>(
+        label_selector: Option<&str>,
+        field_selector: Option<&str>,
+    ) -> Result<Self, SelectorError> {
+        let labels = label_selector
+            .map(Selector::parse_labels)
+            .transpose()?
+            .unwrap_or_default();
+        let fields = field_selector
+            .map(Selector::parse_fields)
+            .transpose()?
+            .unwrap_or_default();
+        if let Some(r) = fields.0.iter().find(|r| {
+            !matches!(r.key.as_str(), "metadata.name" | "metadata.namespace")
+                && !T::FIELDS.contains(&r.key.as_str())
+        }) {
+            return Err(SelectorError(format!(
+                "field label not supported: {}",
+                r.key
+            )));
+        }
+        Ok(Self { labels, fields })
+    }
+
+    pub fn matches<T: FieldSelectable>(&self, resource: &T) -> bool {
+        self.labels.matches(&resource.metadata().labels)
+            && (self.fields.0.is_empty() || self.fields.matches(&resource.fields()))
+    }
+}
@@ -1,160 +1,540 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use serde_json::json;
+
 use crate::{
     abstract_model::ControllerAction,
-    resources::{ContainerState, ContainerStateTerminated},
-    state::StateView,
+    api::patch::Patch,
+    controller::{deployment::DEPRECATED_ROLLBACK_TO, node::missing_config},
+    resources::{
+        ConfigMap, ContainerState, ContainerStateTerminated, Deployment, DeploymentRollback, Job,
+        Metadata, Namespace, ReplicaSet, RollbackConfig, Scale, Secret, StatefulSet,
+    },
+    state::{RawState, ResourceKind, StateView},
+    utils,
 };
 
+/// The finalizer that the arbitrary client holds back the deletion of workloads with.
+pub const CLIENT_FINALIZER: &str = "themelios/client";
+
+/// A kind of change that the arbitrary client makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Mutation {
+    /// Scale workloads up and down.
+    Scale,
+    /// Change the image of the first container in the templates of workloads.
+    Image,
+    /// Pause and resume deployments.
+    Pause,
+    /// Roll deployments back to their last revision.
+    Rollback,
+    /// Suspend and resume jobs.
+    Suspend,
+    /// Delete namespaces.
+    DeleteNamespace,
+    /// Create the config maps and secrets that pods are waiting on.
+    CreateConfig,
+    /// Delete workloads, which stay terminating while they have finalizers.
+    Delete,
+    /// Add and remove the client's finalizer on workloads.
+    Finalizer,
+    /// Create deleted workloads again, as they were in the initial state.
+    Recreate,
+}
+
+impl Mutation {
+    /// The changes made unless others are configured, leaving out those that remove workloads
+    /// and so cut short the checks of their controllers.
+    pub const DEFAULT: [Mutation; 7] = [
+        Mutation::Scale,
+        Mutation::Image,
+        Mutation::Pause,
+        Mutation::Rollback,
+        Mutation::Suspend,
+        Mutation::DeleteNamespace,
+        Mutation::CreateConfig,
+    ];
+}
+
+impl FromStr for Mutation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "scale" => Mutation::Scale,
+            "image" => Mutation::Image,
+            "pause" => Mutation::Pause,
+            "rollback" => Mutation::Rollback,
+            "suspend" => Mutation::Suspend,
+            "delete-namespace" => Mutation::DeleteNamespace,
+            "create-config" => Mutation::CreateConfig,
+            "delete" => Mutation::Delete,
+            "finalizer" => Mutation::Finalizer,
+            "recreate" => Mutation::Recreate,
+            _ => return Err(format!("unknown mutation {s:?}")),
+        })
+    }
+}
+
+/// What the arbitrary client may change, so that checks can focus on how operators really change
+/// the cluster.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArbitraryClientCfg {
+    /// The kinds of resources that the client may touch, all of them when none.
+    pub kinds: Option<BTreeSet<ResourceKind>>,
+    /// The changes that the client may make, those of [`Mutation::DEFAULT`] when none.
+    pub mutations: Option<BTreeSet<Mutation>>,
+    /// The fewest replicas that the client scales workloads down to.
+    pub min_replicas: u32,
+    /// The most replicas that the client scales workloads up to, unbounded when none.
+    pub max_replicas: Option<u32>,
+    /// The images that the client changes templates to, deriving a new one from the current image
+    /// when empty.
+    pub images: Vec<String>,
+}
+
+impl ArbitraryClientCfg {
+    /// Whether the client may make the change to resources of the kind.
+    pub fn allows(&self, kind: ResourceKind, mutation: Mutation) -> bool {
+        self.kinds.as_ref().map_or(true, |k| k.contains(&kind))
+            && self.mutations.as_ref().map_or_else(
+                || Mutation::DEFAULT.contains(&mutation),
+                |m| m.contains(&mutation),
+            )
+    }
+
+    /// The images to change a template with the image to.
+    fn images_from(&self, image: &str) -> Vec<String> {
+        if self.images.is_empty() {
+            vec![format!("{}1", image)]
+        } else {
+            self.images
+                .iter()
+                .filter(|i| *i != image)
+                .cloned()
+                .collect()
+        }
+    }
+}
+
 pub struct ArbitraryClient;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ArbitraryClientAction {
-    ScaleDeployment(String, i32),
-    ScaleStatefulSet(String, i32),
-    ScaleReplicaSet(String, i32),
+    /// Workloads are identified by their namespace and name.
+    ScaleDeployment(String, String, i32),
+    ScaleStatefulSet(String, String, i32),
+    ScaleReplicaSet(String, String, i32),
+
+    ChangeImageDeployment(String, String, String),
+    ChangeImageStatefulSet(String, String, String),
+    ChangeImageReplicaSet(String, String, String),
+
+    TogglePauseDeployment(String, String),
+    /// Roll the deployment back to its last revision.
+    RollbackDeployment(String, String),
+
+    ToggleSuspendJob(String, String),
 
-    ChangeImageDeployment(String, String),
-    ChangeImageStatefulSet(String, String),
-    ChangeImageReplicaSet(String, String),
+    /// Delete the workload, which only marks it as terminating while it has finalizers.
+    DeleteDeployment(String, String),
+    DeleteStatefulSet(String, String),
+    DeleteJob(String, String),
 
-    TogglePauseDeployment(String),
+    /// Add the client's finalizer to the workload, or remove it when already there.
+    ToggleFinalizerDeployment(String, String),
+    ToggleFinalizerStatefulSet(String, String),
+    ToggleFinalizerJob(String, String),
 
-    ToggleSuspendJob(String),
+    /// Create the workload again once it has been deleted.
+    RecreateDeployment(Deployment),
+    RecreateStatefulSet(StatefulSet),
+    RecreateJob(Job),
 
-    MarkSucceededContainer(String),
-    MarkFailedContainer(String),
+    MarkSucceededContainer(String, String),
+    MarkFailedContainer(String, String),
+
+    DeleteNamespace(String),
+
+    /// Create a config map that a pod is waiting on, by namespace and name, with the keys it
+    /// needs.
+    CreateConfigMap(String, String, BTreeSet<String>),
+    /// Create a secret that a pod is waiting on, by namespace and name, with the keys it needs.
+    CreateSecret(String, String, BTreeSet<String>),
 }
 
 impl ArbitraryClient {
-    pub fn actions(view: &StateView) -> Vec<ArbitraryClientAction> {
+    /// The actions that the client can take against the view, recreating the workloads of the
+    /// initial state once they have been deleted.
+    pub fn actions(
+        view: &StateView,
+        initial: &RawState,
+        cfg: &ArbitraryClientCfg,
+    ) -> Vec<ArbitraryClientAction> {
         let mut actions = Vec::new();
-        // scale resources up
-        macro_rules! scale_up {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    actions.push($update(res.metadata.name.clone(), 1));
+        // scale resources up and down, within the bounds
+        macro_rules! scale {
+            ($kind:ident, $resource_kind:expr, $replicas:expr, $update:expr) => {
+                if cfg.allows($resource_kind, Mutation::Scale) {
+                    for res in view.$kind.iter() {
+                        let replicas: u32 = $replicas(res);
+                        if cfg.max_replicas.map_or(true, |max| replicas < max) {
+                            actions.push($update(
+                                res.metadata.namespace.clone(),
+                                res.metadata.name.clone(),
+                                1,
+                            ));
+                        }
+                    }
+                    for res in view.$kind.iter() {
+                        let replicas: u32 = $replicas(res);
+                        if replicas > cfg.min_replicas {
+                            actions.push($update(
+                                res.metadata.namespace.clone(),
+                                res.metadata.name.clone(),
+                                -1,
+                            ));
+                        }
+                    }
                 }
             };
         }
-        scale_up!(deployments, ArbitraryClientAction::ScaleDeployment);
+        scale!(
+            deployments,
+            ResourceKind::Deployments,
+            |res: &Deployment| res.spec.replicas,
+            ArbitraryClientAction::ScaleDeployment
+        );
+        scale!(
+            statefulsets,
+            ResourceKind::StatefulSets,
+            |res: &StatefulSet| res.spec.replicas.unwrap(),
+            ArbitraryClientAction::ScaleStatefulSet
+        );
+        scale!(
+            replicasets,
+            ResourceKind::ReplicaSets,
+            |res: &ReplicaSet| res.spec.replicas.unwrap(),
+            ArbitraryClientAction::ScaleReplicaSet
+        );
 
-        macro_rules! scale_up_option {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    actions.push($update(res.metadata.name.clone(), 1));
+        // change image in templates
+        macro_rules! change_image {
+            ($kind:ident, $resource_kind:expr, $update:expr) => {
+                if cfg.allows($resource_kind, Mutation::Image) {
+                    for res in view.$kind.iter() {
+                        if res.spec.template.spec.containers.is_empty() {
+                            continue;
+                        }
+                        let image = &res.spec.template.spec.containers[0].image;
+                        for new_image in cfg.images_from(image) {
+                            actions.push($update(
+                                res.metadata.namespace.clone(),
+                                res.metadata.name.clone(),
+                                new_image,
+                            ));
+                        }
+                    }
                 }
             };
         }
-        scale_up_option!(statefulsets, ArbitraryClientAction::ScaleStatefulSet);
-        scale_up_option!(replicasets, ArbitraryClientAction::ScaleReplicaSet);
-
-        // scale resources down
-        macro_rules! scale_down {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    if res.spec.replicas > 0 {
-                        actions.push($update(res.metadata.name.clone(), -1));
+        change_image!(
+            deployments,
+            ResourceKind::Deployments,
+            ArbitraryClientAction::ChangeImageDeployment
+        );
+        change_image!(
+            statefulsets,
+            ResourceKind::StatefulSets,
+            ArbitraryClientAction::ChangeImageStatefulSet
+        );
+        change_image!(
+            replicasets,
+            ResourceKind::ReplicaSets,
+            ArbitraryClientAction::ChangeImageReplicaSet
+        );
+
+        // toggle deployments paused status
+        if cfg.allows(ResourceKind::Deployments, Mutation::Pause) {
+            for res in view.deployments.iter() {
+                actions.push(ArbitraryClientAction::TogglePauseDeployment(
+                    res.metadata.namespace.clone(),
+                    res.metadata.name.clone(),
+                ));
+            }
+        }
+
+        // roll deployments back, once they have an earlier revision to go back to
+        if cfg.allows(ResourceKind::Deployments, Mutation::Rollback) {
+            for res in view.deployments.iter() {
+                let pending = res
+                    .metadata
+                    .annotations
+                    .contains_key(DEPRECATED_ROLLBACK_TO);
+                let revisions = view.replicasets.for_controller(&res.metadata.uid).count();
+                if !pending && revisions > 1 {
+                    actions.push(ArbitraryClientAction::RollbackDeployment(
+                        res.metadata.namespace.clone(),
+                        res.metadata.name.clone(),
+                    ));
+                }
+            }
+        }
+
+        // toggle job suspension
+        if cfg.allows(ResourceKind::Jobs, Mutation::Suspend) {
+            for res in view.jobs.iter() {
+                actions.push(ArbitraryClientAction::ToggleSuspendJob(
+                    res.metadata.namespace.clone(),
+                    res.metadata.name.clone(),
+                ));
+            }
+        }
+
+        // delete workloads, which linger while they have finalizers
+        macro_rules! delete {
+            ($kind:ident, $resource_kind:expr, $update:expr) => {
+                if cfg.allows($resource_kind, Mutation::Delete) {
+                    for res in view.$kind.iter() {
+                        if res.metadata.deletion_timestamp.is_none() {
+                            actions.push($update(
+                                res.metadata.namespace.clone(),
+                                res.metadata.name.clone(),
+                            ));
+                        }
                     }
                 }
             };
         }
-        scale_down!(deployments, ArbitraryClientAction::ScaleDeployment);
+        delete!(
+            deployments,
+            ResourceKind::Deployments,
+            ArbitraryClientAction::DeleteDeployment
+        );
+        delete!(
+            statefulsets,
+            ResourceKind::StatefulSets,
+            ArbitraryClientAction::DeleteStatefulSet
+        );
+        delete!(jobs, ResourceKind::Jobs, ArbitraryClientAction::DeleteJob);
 
-        macro_rules! scale_down_option {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    if res.spec.replicas.unwrap() > 0 {
-                        actions.push($update(res.metadata.name.clone(), -1));
+        // hold back and release the deletion of workloads, finalizers can only be removed from
+        // those that are terminating
+        macro_rules! toggle_finalizer {
+            ($kind:ident, $resource_kind:expr, $update:expr) => {
+                if cfg.allows($resource_kind, Mutation::Finalizer) {
+                    for res in view.$kind.iter() {
+                        if has_client_finalizer(&res.metadata)
+                            || res.metadata.deletion_timestamp.is_none()
+                        {
+                            actions.push($update(
+                                res.metadata.namespace.clone(),
+                                res.metadata.name.clone(),
+                            ));
+                        }
                     }
                 }
             };
         }
-        scale_down_option!(statefulsets, ArbitraryClientAction::ScaleStatefulSet);
-        scale_down_option!(replicasets, ArbitraryClientAction::ScaleReplicaSet);
+        toggle_finalizer!(
+            deployments,
+            ResourceKind::Deployments,
+            ArbitraryClientAction::ToggleFinalizerDeployment
+        );
+        toggle_finalizer!(
+            statefulsets,
+            ResourceKind::StatefulSets,
+            ArbitraryClientAction::ToggleFinalizerStatefulSet
+        );
+        toggle_finalizer!(
+            jobs,
+            ResourceKind::Jobs,
+            ArbitraryClientAction::ToggleFinalizerJob
+        );
 
-        // change image in templates
-        macro_rules! change_image {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    if res.spec.template.spec.containers.is_empty() {
-                        continue;
+        // create the workloads that the client started with again, those made by controllers
+        // are left to them
+        macro_rules! recreate {
+            ($kind:ident, $resource_kind:expr, $update:expr) => {
+                if cfg.allows($resource_kind, Mutation::Recreate) {
+                    for res in initial.$kind.iter() {
+                        let owned = res.metadata.owner_references.iter().any(|o| o.controller);
+                        if !owned
+                            && !view
+                                .$kind
+                                .has_in(&res.metadata.namespace, &res.metadata.name)
+                        {
+                            let mut res = res.clone();
+                            res.metadata = recreated(&res.metadata);
+                            res.status = Default::default();
+                            actions.push($update(res));
+                        }
                     }
-                    let image = &res.spec.template.spec.containers[0].image;
-                    let new_image = format!("{}1", image);
-                    actions.push($update(res.metadata.name.clone(), new_image));
                 }
             };
         }
-        change_image!(deployments, ArbitraryClientAction::ChangeImageDeployment);
-        change_image!(statefulsets, ArbitraryClientAction::ChangeImageStatefulSet);
-        change_image!(replicasets, ArbitraryClientAction::ChangeImageReplicaSet);
+        recreate!(
+            deployments,
+            ResourceKind::Deployments,
+            ArbitraryClientAction::RecreateDeployment
+        );
+        recreate!(
+            statefulsets,
+            ResourceKind::StatefulSets,
+            ArbitraryClientAction::RecreateStatefulSet
+        );
+        recreate!(jobs, ResourceKind::Jobs, ArbitraryClientAction::RecreateJob);
 
-        // toggle deployments paused status
-        macro_rules! toggle_pause {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    actions.push($update(res.metadata.name.clone()));
+        // delete namespaces, cascading to their contents
+        if cfg.allows(ResourceKind::Namespaces, Mutation::DeleteNamespace) {
+            for ns in view.namespaces.iter() {
+                if ns.metadata.deletion_timestamp.is_none()
+                    && ns.metadata.name != Namespace::DEFAULT
+                {
+                    actions.push(ArbitraryClientAction::DeleteNamespace(
+                        ns.metadata.name.clone(),
+                    ));
                 }
-            };
+            }
         }
-        toggle_pause!(deployments, ArbitraryClientAction::TogglePauseDeployment);
 
-        // toggle job suspension
-        macro_rules! toggle_suspension {
-            ($kind:ident, $update:expr) => {
-                for res in view.$kind.iter() {
-                    actions.push($update(res.metadata.name.clone()));
+        // create the config that pods are held back waiting on
+        let config_maps = cfg.allows(ResourceKind::ConfigMaps, Mutation::CreateConfig);
+        let secrets = cfg.allows(ResourceKind::Secrets, Mutation::CreateConfig);
+        for pod in view.pods.iter() {
+            if missing_config(pod, view).is_none() {
+                continue;
+            }
+            let namespace = &pod.metadata.namespace;
+            if config_maps {
+                for (name, keys) in pod.config_map_references() {
+                    if view.config_maps.get_in(namespace, &name).is_none() {
+                        let action =
+                            ArbitraryClientAction::CreateConfigMap(namespace.clone(), name, keys);
+                        if !actions.contains(&action) {
+                            actions.push(action);
+                        }
+                    }
                 }
-            };
+            }
+            if secrets {
+                for (name, keys) in pod.secret_references() {
+                    if view.secrets.get_in(namespace, &name).is_none() {
+                        let action =
+                            ArbitraryClientAction::CreateSecret(namespace.clone(), name, keys);
+                        if !actions.contains(&action) {
+                            actions.push(action);
+                        }
+                    }
+                }
+            }
         }
-        toggle_suspension!(jobs, ArbitraryClientAction::ToggleSuspendJob);
 
         actions
     }
 
     pub fn controller_action(state: &StateView, action: ArbitraryClientAction) -> ControllerAction {
         match action {
-            ArbitraryClientAction::ScaleDeployment(name, by) => {
-                let mut res = state.deployments.get(&name).unwrap().clone();
-                res.spec.replicas = (res.spec.replicas as i32 + by) as u32;
-                ControllerAction::UpdateDeployment(res)
-            }
-            ArbitraryClientAction::ScaleStatefulSet(name, by) => {
-                let mut res = state.statefulsets.get(&name).unwrap().clone();
-                res.spec.replicas = Some((res.spec.replicas.unwrap_or(1) as i32 + by) as u32);
-                ControllerAction::UpdateStatefulSet(res)
-            }
-            ArbitraryClientAction::ScaleReplicaSet(name, by) => {
-                let mut res = state.replicasets.get(&name).unwrap().clone();
-                res.spec.replicas = Some((res.spec.replicas.unwrap_or(1) as i32 + by) as u32);
-                ControllerAction::UpdateReplicaSet(res)
-            }
-            ArbitraryClientAction::ChangeImageDeployment(name, image) => {
-                let mut res = state.deployments.get(&name).unwrap().clone();
-                res.spec.template.spec.containers[0].image = image;
-                ControllerAction::UpdateDeployment(res)
-            }
-            ArbitraryClientAction::ChangeImageStatefulSet(name, image) => {
-                let mut res = state.statefulsets.get(&name).unwrap().clone();
-                res.spec.template.spec.containers[0].image = image;
-                ControllerAction::UpdateStatefulSet(res)
-            }
-            ArbitraryClientAction::ChangeImageReplicaSet(name, image) => {
-                let mut res = state.replicasets.get(&name).unwrap().clone();
-                res.spec.template.spec.containers[0].image = image;
-                ControllerAction::UpdateReplicaSet(res)
-            }
-            ArbitraryClientAction::TogglePauseDeployment(name) => {
-                let mut res = state.deployments.get(&name).unwrap().clone();
-                res.spec.paused = !res.spec.paused;
-                ControllerAction::UpdateDeployment(res)
-            }
-            ArbitraryClientAction::ToggleSuspendJob(name) => {
-                let mut res = state.jobs.get(&name).unwrap().clone();
-                res.spec.suspend = !res.spec.suspend;
-                ControllerAction::UpdateJob(res)
-            }
-            ArbitraryClientAction::MarkSucceededContainer(name) => {
-                let mut res = state.pods.get(&name).unwrap().clone();
+            // scale through the scale subresource, as kubectl scale does
+            ArbitraryClientAction::ScaleDeployment(namespace, name, by) => {
+                let res = state.deployments.get_in(&namespace, &name).unwrap();
+                let replicas = (res.spec.replicas as i32 + by) as u32;
+                ControllerAction::ScaleDeployment(Scale::new(
+                    &res.metadata,
+                    replicas,
+                    res.status.replicas,
+                ))
+            }
+            ArbitraryClientAction::ScaleStatefulSet(namespace, name, by) => {
+                let res = state.statefulsets.get_in(&namespace, &name).unwrap();
+                let replicas = (res.spec.replicas.unwrap_or(1) as i32 + by) as u32;
+                ControllerAction::ScaleStatefulSet(Scale::new(
+                    &res.metadata,
+                    replicas,
+                    res.status.replicas,
+                ))
+            }
+            ArbitraryClientAction::ScaleReplicaSet(namespace, name, by) => {
+                let res = state.replicasets.get_in(&namespace, &name).unwrap();
+                let replicas = (res.spec.replicas.unwrap_or(1) as i32 + by) as u32;
+                ControllerAction::ScaleReplicaSet(Scale::new(
+                    &res.metadata,
+                    replicas,
+                    res.status.replicas,
+                ))
+            }
+            // patch the templates and flags, as kubectl set image and rollout pause do, so that
+            // the change isn't lost to a conflict with the controllers' status updates
+            ArbitraryClientAction::ChangeImageDeployment(namespace, name, image) => {
+                ControllerAction::PatchDeployment(namespace, name, change_image_patch(image))
+            }
+            ArbitraryClientAction::ChangeImageStatefulSet(namespace, name, image) => {
+                ControllerAction::PatchStatefulSet(namespace, name, change_image_patch(image))
+            }
+            ArbitraryClientAction::ChangeImageReplicaSet(namespace, name, image) => {
+                ControllerAction::PatchReplicaSet(namespace, name, change_image_patch(image))
+            }
+            ArbitraryClientAction::TogglePauseDeployment(namespace, name) => {
+                let res = state.deployments.get_in(&namespace, &name).unwrap();
+                let patch = Patch::merge(json!({ "spec": { "paused": !res.spec.paused } }));
+                ControllerAction::PatchDeployment(namespace, name, patch)
+            }
+            ArbitraryClientAction::RollbackDeployment(namespace, name) => {
+                ControllerAction::RollbackDeployment(
+                    namespace,
+                    DeploymentRollback {
+                        name,
+                        updated_annotations: Default::default(),
+                        rollback_to: RollbackConfig { revision: 0 },
+                    },
+                )
+            }
+            ArbitraryClientAction::ToggleSuspendJob(namespace, name) => {
+                let res = state.jobs.get_in(&namespace, &name).unwrap();
+                let patch = Patch::merge(json!({ "spec": { "suspend": !res.spec.suspend } }));
+                ControllerAction::PatchJob(namespace, name, patch)
+            }
+            ArbitraryClientAction::DeleteDeployment(namespace, name) => {
+                let res = state.deployments.get_in(&namespace, &name).unwrap().clone();
+                ControllerAction::DeleteDeployment(res)
+            }
+            ArbitraryClientAction::DeleteStatefulSet(namespace, name) => {
+                let res = state
+                    .statefulsets
+                    .get_in(&namespace, &name)
+                    .unwrap()
+                    .clone();
+                ControllerAction::DeleteStatefulSet(res)
+            }
+            ArbitraryClientAction::DeleteJob(namespace, name) => {
+                let res = state.jobs.get_in(&namespace, &name).unwrap().clone();
+                ControllerAction::DeleteJob(res)
+            }
+            ArbitraryClientAction::ToggleFinalizerDeployment(namespace, name) => {
+                let res = state.deployments.get_in(&namespace, &name).unwrap();
+                let patch = toggle_finalizer_patch(&res.metadata);
+                ControllerAction::PatchDeployment(namespace, name, patch)
+            }
+            ArbitraryClientAction::ToggleFinalizerStatefulSet(namespace, name) => {
+                let res = state.statefulsets.get_in(&namespace, &name).unwrap();
+                let patch = toggle_finalizer_patch(&res.metadata);
+                ControllerAction::PatchStatefulSet(namespace, name, patch)
+            }
+            ArbitraryClientAction::ToggleFinalizerJob(namespace, name) => {
+                let res = state.jobs.get_in(&namespace, &name).unwrap();
+                let patch = toggle_finalizer_patch(&res.metadata);
+                ControllerAction::PatchJob(namespace, name, patch)
+            }
+            ArbitraryClientAction::RecreateDeployment(res) => {
+                ControllerAction::CreateDeployment(res)
+            }
+            ArbitraryClientAction::RecreateStatefulSet(res) => {
+                ControllerAction::CreateStatefulSet(res)
+            }
+            ArbitraryClientAction::RecreateJob(res) => ControllerAction::CreateJob(res),
+            ArbitraryClientAction::MarkSucceededContainer(namespace, name) => {
+                let mut res = state.pods.get_in(&namespace, &name).unwrap().clone();
                 for cs in &mut res.status.container_statuses {
                     cs.last_state = cs.state.clone();
                     cs.state = ContainerState::Terminated(ContainerStateTerminated {
@@ -164,8 +544,8 @@ impl ArbitraryClient {
                 }
                 ControllerAction::UpdatePod(res)
             }
-            ArbitraryClientAction::MarkFailedContainer(name) => {
-                let mut res = state.pods.get(&name).unwrap().clone();
+            ArbitraryClientAction::MarkFailedContainer(namespace, name) => {
+                let mut res = state.pods.get_in(&namespace, &name).unwrap().clone();
                 for cs in &mut res.status.container_statuses {
                     cs.last_state = cs.state.clone();
                     cs.state = ContainerState::Terminated(ContainerStateTerminated {
@@ -175,6 +555,64 @@ impl ArbitraryClient {
                 }
                 ControllerAction::UpdatePod(res)
             }
+            ArbitraryClientAction::DeleteNamespace(name) => {
+                let res = state.namespaces.get(&name).unwrap().clone();
+                ControllerAction::SoftDeleteNamespace(res)
+            }
+            ArbitraryClientAction::CreateConfigMap(namespace, name, keys) => {
+                let mut metadata = utils::metadata(name);
+                metadata.namespace = namespace;
+                ControllerAction::CreateConfigMap(ConfigMap {
+                    metadata,
+                    data: keys.into_iter().map(|k| (k, String::new())).collect(),
+                })
+            }
+            ArbitraryClientAction::CreateSecret(namespace, name, keys) => {
+                let mut metadata = utils::metadata(name);
+                metadata.namespace = namespace;
+                ControllerAction::CreateSecret(Secret {
+                    metadata,
+                    data: keys.into_iter().map(|k| (k, String::new())).collect(),
+                    r#type: Some("Opaque".to_owned()),
+                })
+            }
         }
     }
 }
+
+/// Set the image of the first container in the template of a workload.
+fn change_image_patch(image: String) -> Patch {
+    Patch::json(json!([{
+        "op": "add",
+        "path": "/spec/template/spec/containers/0/image",
+        "value": image,
+    }]))
+}
+
+fn has_client_finalizer(metadata: &Metadata) -> bool {
+    metadata.finalizers.iter().any(|f| f == CLIENT_FINALIZER)
+}
+
+/// Add the client's finalizer to the resource, or remove it when already there.
+fn toggle_finalizer_patch(metadata: &Metadata) -> Patch {
+    let mut finalizers = metadata.finalizers.clone();
+    if has_client_finalizer(metadata) {
+        finalizers.retain(|f| f != CLIENT_FINALIZER);
+    } else {
+        finalizers.push(CLIENT_FINALIZER.to_owned());
+    }
+    Patch::merge(json!({ "metadata": { "finalizers": finalizers } }))
+}
+
+/// The metadata that a client would create the resource with again, without what the api server
+/// set on it.
+fn recreated(metadata: &Metadata) -> Metadata {
+    Metadata {
+        name: metadata.name.clone(),
+        namespace: metadata.namespace.clone(),
+        labels: metadata.labels.clone(),
+        annotations: metadata.annotations.clone(),
+        finalizers: metadata.finalizers.clone(),
+        ..Default::default()
+    }
+}
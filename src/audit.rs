@@ -0,0 +1,385 @@
+//! Conformance of the history of a real cluster to the model, from the audit log of its API
+//! server.
+//!
+//! The writes in the log are taken in order, those of clients as recorded operations and those of
+//! the controllers as steps that the model's controllers have to be able to take from a view that
+//! the consistency level allows, so that behaviour of the cluster that the model doesn't capture
+//! shows up as divergent writes.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use axum::http::Method;
+use serde::Deserialize;
+use serde_json::Value;
+use stateright::Model;
+
+use crate::abstract_model::{AbstractModel, Action, ControllerAction};
+use crate::controller::{Controller, Controllers};
+use crate::leader_election::Election;
+use crate::rbac::{self, Permission, Verb};
+use crate::recording::ClientOperation;
+use crate::serve_cluster::record::Target;
+use crate::state::revision::Revision;
+use crate::state::{ResourceKind, State};
+
+/// The most states that writes can have led to which are kept, dropping the rest.
+pub const MAX_CANDIDATE_STATES: usize = 64;
+
+/// The user that the controller manager acts as when it doesn't use a service account for each of
+/// its controllers.
+const CONTROLLER_MANAGER: &str = "system:kube-controller-manager";
+
+/// An event of the audit log, with only the fields needed to reconstruct the writes.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditEvent {
+    #[serde(default)]
+    stage: String,
+    #[serde(default)]
+    verb: String,
+    #[serde(default, rename = "requestURI")]
+    request_uri: String,
+    #[serde(default)]
+    user: UserInfo,
+    object_ref: Option<ObjectReference>,
+    response_status: Option<ResponseStatus>,
+    response_object: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct UserInfo {
+    #[serde(default)]
+    username: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ObjectReference {
+    #[serde(default)]
+    resource: String,
+    #[serde(default)]
+    namespace: String,
+    #[serde(default)]
+    name: String,
+    subresource: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ResponseStatus {
+    #[serde(default)]
+    code: u16,
+}
+
+/// Who made a write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Writer {
+    /// A client, making the recorded operation at the given index.
+    Client(usize),
+    /// A controller, as the user that it acts as.
+    Controller(String),
+}
+
+/// A write that the API server accepted, as recorded in its audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditedWrite {
+    pub writer: Writer,
+    pub permission: Permission,
+    pub namespace: String,
+    /// The name of the resource, or the prefix that it was generated from for creates.
+    pub name: String,
+    pub subresource: Option<String>,
+}
+
+impl AuditedWrite {
+    /// Whether the action makes this write.
+    ///
+    /// THEMELIOS: Updates and patches are taken to be the same, the model's controllers not always
+    /// writing the way that the real ones do, and only the resource that is written is compared,
+    /// not what is written to it.
+    pub fn made_by(&self, action: &ControllerAction) -> bool {
+        let Some(permission) = action.required_permission() else {
+            return false;
+        };
+        let same_verb = match (permission.verb, self.permission.verb) {
+            (Verb::Update | Verb::Patch, Verb::Update | Verb::Patch) => true,
+            (ours, theirs) => ours == theirs,
+        };
+        let same_name = action.resource_name().map_or(false, |name| {
+            // generated names only match on their prefix
+            if permission.verb == Verb::Create {
+                name.starts_with(&self.name)
+            } else {
+                name == self.name
+            }
+        });
+        same_verb && permission.kind == self.permission.kind && same_name
+    }
+}
+
+impl std::fmt::Display for AuditedWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}/{}", self.permission, self.namespace, self.name)?;
+        if let Some(subresource) = &self.subresource {
+            write!(f, "/{subresource}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditError {
+    /// The audit log couldn't be read.
+    Io(String),
+    /// The line, counting from one, was not an audit event.
+    InvalidLine(usize, String),
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditError::Io(error) => write!(f, "failed to read audit log: {error}"),
+            AuditError::InvalidLine(line, error) => {
+                write!(f, "invalid audit event on line {line}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+/// The writes of an audit log, in the order that they completed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditLog {
+    pub writes: Vec<AuditedWrite>,
+    /// The operations of the writes by clients, for the model to take.
+    pub operations: Vec<ClientOperation>,
+    /// The number of accepted writes that were left out, being to kinds that the model doesn't
+    /// have or by clients whose operations the model can't take.
+    pub skipped: usize,
+}
+
+impl AuditLog {
+    pub fn load(path: &Path) -> Result<Self, AuditError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| AuditError::Io(e.to_string()))?;
+        Self::parse(&contents)
+    }
+
+    /// Parse the audit log from its lines of events, as the API server writes them with the
+    /// `RequestResponse` level, skipping blank ones.
+    ///
+    /// THEMELIOS: Lease writes are left out, leader election not being checked against the log.
+    pub fn parse(contents: &str) -> Result<Self, AuditError> {
+        let mut log = Self::default();
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: AuditEvent = serde_json::from_str(line)
+                .map_err(|e| AuditError::InvalidLine(i + 1, e.to_string()))?;
+            let verb = match event.verb.as_str() {
+                "create" => Verb::Create,
+                "update" => Verb::Update,
+                "patch" => Verb::Patch,
+                "delete" => Verb::Delete,
+                _ => continue,
+            };
+            let accepted = event
+                .response_status
+                .as_ref()
+                .map_or(false, |s| (200..300).contains(&s.code));
+            if event.stage != "ResponseComplete" || !accepted {
+                continue;
+            }
+            let Some(object_ref) = &event.object_ref else {
+                continue;
+            };
+            let kind = ResourceKind::ALL
+                .into_iter()
+                .find(|k| rbac::api_resource(*k).1 == object_ref.resource);
+            let Some(kind) = kind.filter(|k| *k != ResourceKind::Leases) else {
+                log.skipped += 1;
+                continue;
+            };
+            let metadata = event
+                .response_object
+                .as_ref()
+                .map(|o| &o["metadata"])
+                .unwrap_or(&Value::Null);
+            let name = match metadata["generateName"].as_str() {
+                Some(prefix) if verb == Verb::Create && !prefix.is_empty() => prefix,
+                _ => metadata["name"].as_str().unwrap_or(&object_ref.name),
+            };
+            let writer = if is_controller(&event.user.username) {
+                Writer::Controller(event.user.username.clone())
+            } else {
+                let operation = client_operation(&event, verb);
+                let Some(operation) = operation else {
+                    log.skipped += 1;
+                    continue;
+                };
+                log.operations.push(operation);
+                Writer::Client(log.operations.len() - 1)
+            };
+            log.writes.push(AuditedWrite {
+                writer,
+                permission: Permission::new(verb, kind),
+                namespace: object_ref.namespace.clone(),
+                name: name.to_owned(),
+                subresource: object_ref.subresource.clone(),
+            });
+        }
+        Ok(log)
+    }
+}
+
+/// Whether the user is one that the built-in controllers act as.
+fn is_controller(username: &str) -> bool {
+    username == CONTROLLER_MANAGER
+        || username == "system:kube-scheduler"
+        || username.starts_with("system:node:")
+        || username.starts_with(&format!(
+            "system:serviceaccount:{}:",
+            rbac::CONTROLLER_NAMESPACE
+        ))
+}
+
+/// Whether the controller acts as the user, the controller manager acting for all of those with
+/// service accounts when they don't each use their own.
+fn acts_as(controller: &Controllers, user: &str) -> bool {
+    let identity = rbac::identity(controller);
+    identity.to_string() == user
+        || (user == CONTROLLER_MANAGER && identity.kind == "ServiceAccount")
+}
+
+/// The operation that a client made with the write, if the model can take it.
+fn client_operation(event: &AuditEvent, verb: Verb) -> Option<ClientOperation> {
+    let path = event.request_uri.split('?').next().unwrap_or_default();
+    let target = Target::parse(path)?;
+    let method = match verb {
+        Verb::Create => Method::POST,
+        Verb::Update => Method::PUT,
+        Verb::Patch => Method::PATCH,
+        Verb::Delete => Method::DELETE,
+    };
+    let body = serde_json::to_vec(event.response_object.as_ref().unwrap_or(&Value::Null)).ok()?;
+    target.operation(&method, &body).ok().flatten()
+}
+
+/// A controller write that the model's controllers couldn't have made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The index of the write in the log.
+    pub index: usize,
+    pub write: AuditedWrite,
+    /// The writes that the controllers could have made instead.
+    pub expected: BTreeSet<String>,
+}
+
+/// The outcome of checking the writes of an audit log against the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conformance {
+    /// The number of writes that were checked.
+    pub checked: usize,
+    /// Writes by controllers that the model has no controller acting as.
+    pub unattributed: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl Conformance {
+    pub fn conforms(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// The write that the controller makes stepping from the view at the revision, if any.
+fn step_write(
+    model: &AbstractModel,
+    state: &State,
+    revision: &Revision,
+    controller_index: usize,
+) -> Option<ControllerAction> {
+    let view = model.view_for(state, revision, controller_index);
+    match model.elect(&view, controller_index) {
+        None | Some(Election::Lead) => {}
+        Some(Election::Lease(action)) => return Some(action),
+        Some(Election::Follow) => return None,
+    }
+    let mut cstate = state.get_controller(controller_index).clone();
+    model
+        .controller(state, controller_index)
+        .step(&view, &mut cstate)
+}
+
+/// Check that the writes of the log could have been made by the model, with the recorded
+/// operations of the log's clients, keeping every state that they could have led to.
+///
+/// THEMELIOS: Controllers are taken to make their writes on their first step from a view, steps
+/// that write nothing not being in the log, and time doesn't pass. A divergent write is left out,
+/// so the writes after it are checked against the states without it.
+pub fn check(model: &AbstractModel, log: &AuditLog) -> Conformance {
+    let mut conformance = Conformance::default();
+    let mut states = model.init_states();
+    for (index, write) in log.writes.iter().enumerate() {
+        let (next, expected) = match &write.writer {
+            Writer::Client(operation) => {
+                let next = states
+                    .iter()
+                    .filter_map(|s| model.next_state(s, Action::ClientOperation(*operation)))
+                    .collect::<Vec<_>>();
+                (next, BTreeSet::new())
+            }
+            Writer::Controller(user) => {
+                let controllers = (0..model.controllers.len())
+                    .filter(|i| acts_as(&model.controllers[*i], user))
+                    .collect::<Vec<_>>();
+                if controllers.is_empty() {
+                    conformance.unattributed += 1;
+                    continue;
+                }
+                let mut next = Vec::new();
+                let mut expected = BTreeSet::new();
+                for state in &states {
+                    let mut actions = Vec::new();
+                    model.actions(state, &mut actions);
+                    for action in actions {
+                        let Action::ControllerStep(revision, i) = &action else {
+                            continue;
+                        };
+                        if !controllers.contains(i) {
+                            continue;
+                        }
+                        let Some(made) = step_write(model, state, revision, *i) else {
+                            continue;
+                        };
+                        if write.made_by(&made) {
+                            next.extend(model.next_state(state, action));
+                        } else if let Some(permission) = made.required_permission() {
+                            expected.insert(format!(
+                                "{} {}",
+                                permission,
+                                made.resource_name().unwrap_or_default()
+                            ));
+                        }
+                    }
+                }
+                (next, expected)
+            }
+        };
+        conformance.checked += 1;
+        if next.is_empty() {
+            conformance.divergences.push(Divergence {
+                index,
+                write: write.clone(),
+                expected,
+            });
+            continue;
+        }
+        let mut seen = BTreeSet::new();
+        states = next
+            .into_iter()
+            .filter(|s| seen.insert(stateright::fingerprint(s)))
+            .take(MAX_CANDIDATE_STATES)
+            .collect();
+    }
+    conformance
+}
@@ -0,0 +1,176 @@
+//! Checking with an approximate set of visited states, for models too big to keep the fingerprint
+//! of every state that they reach.
+//!
+//! Visited states are kept as bits in a Bloom filter of a fixed size, so some new states are taken
+//! to have been visited already and are never explored, trading completeness for reach.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use stateright::report::{ReportData, Reporter};
+use stateright::{Fingerprint, Model};
+
+use crate::abstract_model::AbstractModel;
+use crate::state::State;
+
+/// How often progress is reported while checking.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A Bloom filter over state fingerprints.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    /// The number of bits set for each fingerprint.
+    hashes: u32,
+    /// The number of bits that are set.
+    set: u64,
+}
+
+impl BloomFilter {
+    /// A filter taking up the given number of bytes, setting the given number of bits for each
+    /// fingerprint.
+    pub fn new(bytes: usize, hashes: u32) -> Self {
+        Self {
+            bits: vec![0; (bytes / 8).max(1)],
+            hashes: hashes.max(1),
+            set: 0,
+        }
+    }
+
+    fn len(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    /// Add the fingerprint, returning whether it wasn't already in the filter.
+    ///
+    /// Unlike a set, a fingerprint that was never added can be taken to have been.
+    pub fn insert(&mut self, fingerprint: Fingerprint) -> bool {
+        // double hashing, the fingerprint already being a hash of the state
+        let h1 = fingerprint.get();
+        let h2 = mix(h1) | 1;
+        let mut new = false;
+        for i in 0..u64::from(self.hashes) {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.len();
+            let word = &mut self.bits[(bit / 64) as usize];
+            let mask = 1 << (bit % 64);
+            if *word & mask == 0 {
+                *word |= mask;
+                self.set += 1;
+                new = true;
+            }
+        }
+        new
+    }
+
+    /// The chance that a fingerprint which was never added is taken to have been, given how full
+    /// the filter is now.
+    pub fn false_positive_rate(&self) -> f64 {
+        (self.set as f64 / self.len() as f64).powi(self.hashes as i32)
+    }
+}
+
+/// The finalizer of splitmix64, to get a second hash out of the fingerprint.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// The outcome of a bitstate check.
+#[derive(Debug, Clone, Default)]
+pub struct BitstateCheck {
+    pub total_states: usize,
+    /// The number of states that were taken to be new, and so explored.
+    pub unique_states: usize,
+    pub max_depth: usize,
+    pub duration: Duration,
+    /// The chance, by the end of the check, that a new state was taken to have been visited.
+    pub false_positive_rate: f64,
+    /// The fingerprints along the path to the first discovery of each property.
+    pub discoveries: BTreeMap<&'static str, Vec<Fingerprint>>,
+}
+
+impl BitstateCheck {
+    /// The path to the discovery of the property, encoded as for `tui`.
+    pub fn encoded_path(&self, property: &str) -> Option<String> {
+        self.discoveries.get(property).map(|path| {
+            path.iter()
+                .map(|fp| fp.to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+    }
+}
+
+/// Check the model depth first, keeping the states that have been visited in the filter.
+///
+/// THEMELIOS: Only always and sometimes properties are checked, see
+/// [`AbstractModel::discovered_in`].
+pub fn check_bitstate(
+    model: &AbstractModel,
+    filter: &mut BloomFilter,
+    max_depth: usize,
+    reporter: &mut dyn Reporter<AbstractModel>,
+) -> BitstateCheck {
+    let start = Instant::now();
+    let mut last_report = start;
+    let properties = model.properties();
+    let mut check = BitstateCheck::default();
+    let mut stack = Vec::<(State, usize)>::new();
+    for state in model.init_states() {
+        if filter.insert(stateright::fingerprint(&state)) {
+            check.unique_states += 1;
+            stack.push((state, 1));
+        }
+    }
+    // the fingerprints of the states leading to the one being visited
+    let mut path = Vec::new();
+    while let Some((state, depth)) = stack.pop() {
+        path.truncate(depth - 1);
+        path.push(stateright::fingerprint(&state));
+        check.total_states += 1;
+        check.max_depth = check.max_depth.max(depth);
+
+        for property in &properties {
+            if check.discoveries.contains_key(property.name) {
+                continue;
+            }
+            if model.discovered_in(property, &state) {
+                check.discoveries.insert(property.name, path.clone());
+            }
+        }
+
+        if max_depth == 0 || depth < max_depth {
+            let mut actions = Vec::new();
+            model.actions(&state, &mut actions);
+            for action in actions {
+                let Some(next) = model.next_state(&state, action) else {
+                    continue;
+                };
+                if model.within_boundary(&next) && filter.insert(stateright::fingerprint(&next)) {
+                    check.unique_states += 1;
+                    stack.push((next, depth + 1));
+                }
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            last_report = Instant::now();
+            reporter.report_checking(report_data(&check, start, false));
+        }
+    }
+    check.duration = start.elapsed();
+    check.false_positive_rate = filter.false_positive_rate();
+    reporter.report_checking(report_data(&check, start, true));
+    check
+}
+
+fn report_data(check: &BitstateCheck, start: Instant, done: bool) -> ReportData {
+    ReportData {
+        total_states: check.total_states,
+        unique_states: check.unique_states,
+        max_depth: check.max_depth,
+        duration: start.elapsed(),
+        done,
+    }
+}
@@ -0,0 +1,184 @@
+//! Checking with a bounded set of visited states, for models whose visited states don't fit in
+//! memory.
+//!
+//! Once the set is full the least recently seen fingerprints are evicted to make room, so states
+//! that were already explored can be explored again when they are next reached, trading time for
+//! memory.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use stateright::report::{ReportData, Reporter};
+use stateright::{Fingerprint, Model};
+
+use crate::abstract_model::AbstractModel;
+use crate::state::State;
+
+/// How often progress is reported while checking.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A set of state fingerprints holding at most a fixed number of them, evicting the least
+/// recently seen when full.
+#[derive(Debug, Clone)]
+pub struct VisitedCache {
+    capacity: usize,
+    /// When each fingerprint was last seen.
+    seen: HashMap<Fingerprint, u64>,
+    /// The fingerprints by when they were last seen, oldest first.
+    order: BTreeMap<u64, Fingerprint>,
+    tick: u64,
+    evictions: usize,
+}
+
+impl VisitedCache {
+    /// A cache holding up to the given number of fingerprints.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            seen: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Add the fingerprint, returning whether it wasn't already in the cache.
+    ///
+    /// Fingerprints that are already present count as seen again, keeping them from eviction for
+    /// longer.
+    pub fn insert(&mut self, fingerprint: Fingerprint) -> bool {
+        self.tick += 1;
+        if let Some(last) = self.seen.insert(fingerprint, self.tick) {
+            self.order.remove(&last);
+            self.order.insert(self.tick, fingerprint);
+            return false;
+        }
+        self.order.insert(self.tick, fingerprint);
+        if self.seen.len() > self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.seen.remove(&oldest);
+                self.evictions += 1;
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// The number of fingerprints evicted to make room for others.
+    pub fn evictions(&self) -> usize {
+        self.evictions
+    }
+}
+
+/// The outcome of a bounded check.
+#[derive(Debug, Clone, Default)]
+pub struct BoundedCheck {
+    pub total_states: usize,
+    /// The number of states that were taken to be new, including those explored again after
+    /// being evicted.
+    pub unique_states: usize,
+    pub max_depth: usize,
+    pub duration: Duration,
+    /// The number of fingerprints evicted from the visited set.
+    pub evictions: usize,
+    /// The fingerprints along the path to the first discovery of each property.
+    pub discoveries: BTreeMap<&'static str, Vec<Fingerprint>>,
+}
+
+impl BoundedCheck {
+    /// The path to the discovery of the property, encoded as for `tui`.
+    pub fn encoded_path(&self, property: &str) -> Option<String> {
+        self.discoveries.get(property).map(|path| {
+            path.iter()
+                .map(|fp| fp.to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+    }
+}
+
+/// Check the model depth first, keeping the states that have been visited in the cache.
+///
+/// THEMELIOS: Only always and sometimes properties are checked, see
+/// [`AbstractModel::discovered_in`].
+/// States on the path being explored are never explored again from it, so that evictions can't
+/// have the check go round a cycle forever.
+pub fn check_bounded(
+    model: &AbstractModel,
+    visited: &mut VisitedCache,
+    max_depth: usize,
+    reporter: &mut dyn Reporter<AbstractModel>,
+) -> BoundedCheck {
+    let start = Instant::now();
+    let mut last_report = start;
+    let properties = model.properties();
+    let mut check = BoundedCheck::default();
+    let mut stack = Vec::<(State, usize)>::new();
+    for state in model.init_states() {
+        if visited.insert(stateright::fingerprint(&state)) {
+            check.unique_states += 1;
+            stack.push((state, 1));
+        }
+    }
+    // the fingerprints of the states leading to the one being visited
+    let mut path = Vec::new();
+    while let Some((state, depth)) = stack.pop() {
+        path.truncate(depth - 1);
+        path.push(stateright::fingerprint(&state));
+        check.total_states += 1;
+        check.max_depth = check.max_depth.max(depth);
+
+        for property in &properties {
+            if check.discoveries.contains_key(property.name) {
+                continue;
+            }
+            if model.discovered_in(property, &state) {
+                check.discoveries.insert(property.name, path.clone());
+            }
+        }
+
+        if max_depth == 0 || depth < max_depth {
+            let mut actions = Vec::new();
+            model.actions(&state, &mut actions);
+            for action in actions {
+                let Some(next) = model.next_state(&state, action) else {
+                    continue;
+                };
+                let fingerprint = stateright::fingerprint(&next);
+                if model.within_boundary(&next)
+                    && !path.contains(&fingerprint)
+                    && visited.insert(fingerprint)
+                {
+                    check.unique_states += 1;
+                    stack.push((next, depth + 1));
+                }
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            last_report = Instant::now();
+            reporter.report_checking(report_data(&check, start, false));
+        }
+    }
+    check.duration = start.elapsed();
+    check.evictions = visited.evictions();
+    reporter.report_checking(report_data(&check, start, true));
+    check
+}
+
+fn report_data(check: &BoundedCheck, start: Instant, done: bool) -> ReportData {
+    ReportData {
+        total_states: check.total_states,
+        unique_states: check.unique_states,
+        max_depth: check.max_depth,
+        duration: start.elapsed(),
+        done,
+    }
+}
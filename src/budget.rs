@@ -0,0 +1,131 @@
+//! Budgets on how much a check may use, stopping it early once one runs out so that it still
+//! finishes by reporting what it found so far.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::Model;
+use sysinfo::{ProcessExt, System, SystemExt};
+use tracing::warn;
+
+/// Limits on a check, each of them unlimited when not given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// The number of states to visit.
+    pub max_states: Option<usize>,
+    /// How long to check for.
+    pub timeout: Option<Duration>,
+    /// The memory that the process may use, in bytes.
+    pub max_memory: Option<u64>,
+}
+
+/// The limit of a [`Budget`] that ran out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exhausted {
+    States,
+    Duration,
+    Memory,
+}
+
+impl Display for Exhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Exhausted::States => write!(f, "states"),
+            Exhausted::Duration => write!(f, "duration"),
+            Exhausted::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+impl Budget {
+    /// Whether any of the limits are set.
+    pub fn is_limited(&self) -> bool {
+        self.max_states.is_some() || self.timeout.is_some() || self.max_memory.is_some()
+    }
+
+    /// The first of the limits that the check has reached, having made the progress and using
+    /// the memory.
+    pub fn exhausted(&self, data: &ReportData, memory: u64) -> Option<Exhausted> {
+        if self
+            .max_states
+            .map_or(false, |max| data.total_states >= max)
+        {
+            Some(Exhausted::States)
+        } else if self.timeout.map_or(false, |max| data.duration >= max) {
+            Some(Exhausted::Duration)
+        } else if self.max_memory.map_or(false, |max| memory >= max) {
+            Some(Exhausted::Memory)
+        } else {
+            None
+        }
+    }
+}
+
+/// Stops the check once its budget runs out, by setting the flag that the model stops exploring
+/// on, and says which ran out when the check reports its results.
+///
+/// The budget is only checked as progress is reported, so a check can go over it by as much as it
+/// gets done between reports.
+pub struct BudgetReporter {
+    budget: Budget,
+    stop: Arc<AtomicBool>,
+    exhausted: Option<Exhausted>,
+    system: System,
+}
+
+impl BudgetReporter {
+    pub fn new(budget: Budget, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            budget,
+            stop,
+            exhausted: None,
+            system: System::new(),
+        }
+    }
+
+    /// The limit that ran out, if any did.
+    pub fn exhausted(&self) -> Option<Exhausted> {
+        self.exhausted
+    }
+
+    fn memory(&mut self) -> u64 {
+        if self.budget.max_memory.is_none() {
+            return 0;
+        }
+        let Ok(pid) = sysinfo::get_current_pid() else {
+            return 0;
+        };
+        self.system.refresh_process(pid);
+        self.system.process(pid).map_or(0, |p| p.memory())
+    }
+}
+
+impl<M: Model> Reporter<M> for BudgetReporter {
+    fn report_checking(&mut self, data: ReportData) {
+        if self.exhausted.is_some() || data.done {
+            return;
+        }
+        let memory = self.memory();
+        if let Some(exhausted) = self.budget.exhausted(&data, memory) {
+            warn!(%exhausted, "Stopping the check as its budget ran out");
+            self.exhausted = Some(exhausted);
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn report_discoveries(&mut self, _discoveries: BTreeMap<&'static str, ReportDiscovery<M>>)
+    where
+        M::Action: std::fmt::Debug,
+        M::State: std::fmt::Debug + std::hash::Hash,
+    {
+        if let Some(exhausted) = self.exhausted {
+            println!(
+                "Stopped early as the {exhausted} budget ran out, the results only cover the states checked so far"
+            );
+        }
+    }
+}
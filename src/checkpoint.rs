@@ -0,0 +1,157 @@
+//! Checkpoints of the states that a check has explored, so that the check can be interrupted and
+//! resumed, or skip what an earlier run of the same model already got through.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use stateright::{CheckerVisitor, Fingerprint, Model};
+use tracing::{info, warn};
+
+use crate::abstract_model::{AbstractModel, Action};
+use crate::state::State;
+
+/// How many states are visited between writes of the checkpoint.
+const CHECKPOINT_INTERVAL: usize = 100_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointFile {
+    /// The fingerprint of the model that the states were explored in.
+    model: Fingerprint,
+    explored: BTreeSet<Fingerprint>,
+}
+
+/// The states of a model that have been explored along with everything that follows them.
+///
+/// Visiting states during a check records their successors, and states are only taken as explored
+/// once all that they lead to has been visited too, so resuming never skips part of the state
+/// space that was queued when the check was stopped.
+///
+/// THEMELIOS: Discoveries are not kept, so properties that failed in explored states are only
+/// reported by the run that found them.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    path: PathBuf,
+    model: Fingerprint,
+    /// States explored in earlier runs.
+    explored: Arc<BTreeSet<Fingerprint>>,
+    /// The successors of each state visited in this run.
+    visited: Arc<Mutex<BTreeMap<Fingerprint, Vec<Fingerprint>>>>,
+    visits: Arc<AtomicUsize>,
+}
+
+impl Checkpoint {
+    /// Load the checkpoint at the path for the model, starting afresh if there is none yet or it
+    /// was written for a different model.
+    pub fn load(path: PathBuf, model: &AbstractModel) -> std::io::Result<Self> {
+        let fingerprint = model_fingerprint(model);
+        let explored = match File::open(&path) {
+            Ok(file) => {
+                let file: CheckpointFile = serde_json::from_reader(BufReader::new(file))?;
+                if file.model == fingerprint {
+                    info!(explored = file.explored.len(), "Resuming from checkpoint");
+                    file.explored
+                } else {
+                    warn!("Ignoring checkpoint written for a different model");
+                    BTreeSet::new()
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => BTreeSet::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(Self {
+            path,
+            model: fingerprint,
+            explored: Arc::new(explored),
+            visited: Default::default(),
+            visits: Default::default(),
+        })
+    }
+
+    /// The states explored in earlier runs, for the model to skip.
+    pub fn explored(&self) -> Arc<BTreeSet<Fingerprint>> {
+        Arc::clone(&self.explored)
+    }
+
+    /// Write the states explored so far, replacing the previous checkpoint only once the new one
+    /// is complete.
+    pub fn save(&self) -> std::io::Result<()> {
+        // hold on to the visits so that saves don't interleave
+        let visited = self.visited.lock().unwrap();
+        let file = CheckpointFile {
+            model: self.model,
+            explored: self.completed(&visited),
+        };
+        let partial = self.path.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        serde_json::to_writer(&mut writer, &file)?;
+        writer.flush()?;
+        std::fs::rename(partial, &self.path)
+    }
+
+    /// The states from earlier runs along with those visited in this one that only lead to
+    /// other explored states.
+    fn completed(
+        &self,
+        visited: &BTreeMap<Fingerprint, Vec<Fingerprint>>,
+    ) -> BTreeSet<Fingerprint> {
+        let mut predecessors = BTreeMap::<Fingerprint, Vec<Fingerprint>>::new();
+        let mut pending = Vec::new();
+        for (state, successors) in visited {
+            for successor in successors {
+                predecessors.entry(*successor).or_default().push(*state);
+            }
+            if successors
+                .iter()
+                .any(|s| !visited.contains_key(s) && !self.explored.contains(s))
+            {
+                pending.push(*state);
+            }
+        }
+        // anything that can reach a state yet to be visited is still to be explored
+        let mut unexplored = pending.iter().copied().collect::<BTreeSet<_>>();
+        while let Some(state) = pending.pop() {
+            for predecessor in predecessors.get(&state).into_iter().flatten() {
+                if unexplored.insert(*predecessor) {
+                    pending.push(*predecessor);
+                }
+            }
+        }
+        self.explored
+            .iter()
+            .chain(visited.keys().filter(|s| !unexplored.contains(*s)))
+            .copied()
+            .collect()
+    }
+}
+
+impl CheckerVisitor<AbstractModel> for Checkpoint {
+    fn visit(&self, model: &AbstractModel, path: stateright::Path<State, Action>) {
+        let state = path.last_state();
+        let mut actions = Vec::new();
+        model.actions(state, &mut actions);
+        let successors = actions
+            .into_iter()
+            .filter_map(|action| model.next_state(state, action))
+            .map(|next| stateright::fingerprint(&next))
+            .collect();
+        self.visited
+            .lock()
+            .unwrap()
+            .insert(stateright::fingerprint(state), successors);
+        if (self.visits.fetch_add(1, Ordering::Relaxed) + 1) % CHECKPOINT_INTERVAL == 0 {
+            if let Err(error) = self.save() {
+                warn!(?error, "Failed to write checkpoint");
+            }
+        }
+    }
+}
+
+/// Identifies the model by its configuration, which includes the initial states and controllers.
+fn model_fingerprint(model: &AbstractModel) -> Fingerprint {
+    stateright::fingerprint(&format!("{model:?}"))
+}
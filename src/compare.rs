@@ -0,0 +1,134 @@
+//! Checking the same model under several consistency levels, to see which of its properties only
+//! hold with the stronger ones.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use stateright::{Checker, Expectation, Model, Path};
+
+use crate::abstract_model::Action;
+use crate::model::OrchestrationModelCfg;
+use crate::report::property_holds;
+use crate::state::history::ConsistencySetup;
+use crate::state::State;
+
+/// The consistency levels that are compared unless others are given, from the strongest to the
+/// weakest.
+pub const LEVELS: [ConsistencySetup; 4] = [
+    ConsistencySetup::Synchronous,
+    ConsistencySetup::ResettableSession,
+    ConsistencySetup::OptimisticLinear,
+    ConsistencySetup::Causal,
+];
+
+/// What checking the model under one of the consistency levels found.
+#[derive(Debug, Clone)]
+pub struct LevelResult {
+    pub consistency_level: ConsistencySetup,
+    pub unique_states: usize,
+    /// The discovery of each property that has one, as the shortest path to it.
+    pub discoveries: BTreeMap<&'static str, Path<State, Action>>,
+}
+
+/// The results of checking the model under each of the consistency levels.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// The expectation of each property, by name.
+    pub properties: BTreeMap<&'static str, Expectation>,
+    pub levels: Vec<LevelResult>,
+}
+
+/// Check the model breadth first under each of the consistency levels in turn, so that the paths
+/// that it finds are as short as they can be.
+pub fn compare(
+    cfg: &OrchestrationModelCfg,
+    levels: &[ConsistencySetup],
+    threads: usize,
+    max_depth: usize,
+) -> Comparison {
+    let mut properties = BTreeMap::new();
+    let mut results = Vec::new();
+    for level in levels {
+        let mut cfg = cfg.clone();
+        cfg.consistency_level = level.clone();
+        let model = cfg.into_abstract_model();
+        properties.extend(
+            model
+                .properties()
+                .into_iter()
+                .map(|p| (p.name, p.expectation)),
+        );
+        let checker = model
+            .checker()
+            .threads(threads)
+            .target_max_depth(max_depth)
+            .spawn_bfs()
+            .join();
+        results.push(LevelResult {
+            consistency_level: level.clone(),
+            unique_states: checker.unique_state_count(),
+            discoveries: checker.discoveries().into_iter().collect(),
+        });
+    }
+    Comparison {
+        properties,
+        levels: results,
+    }
+}
+
+impl Comparison {
+    /// Whether the property holds under the consistency level at the index.
+    pub fn holds(&self, property: &str, level: usize) -> bool {
+        let discovered = self.levels[level].discoveries.contains_key(property);
+        self.properties
+            .get(property)
+            .map_or(true, |expectation| property_holds(expectation, discovered))
+    }
+
+    /// The paths that show each property not holding, with the index of the level that they are
+    /// under, leaving out properties that only fail by having no example.
+    pub fn failures(&self) -> Vec<(&'static str, usize, &Path<State, Action>)> {
+        let mut failures = Vec::new();
+        for name in self.properties.keys() {
+            for (i, level) in self.levels.iter().enumerate() {
+                if let Some(path) = level.discoveries.get(name) {
+                    if !self.holds(name, i) {
+                        failures.push((*name, i, path));
+                    }
+                }
+            }
+        }
+        failures
+    }
+
+    /// A table of whether each property holds under each of the levels, a row for each property
+    /// and a column for each level.
+    pub fn table(&self) -> String {
+        let headers = self
+            .levels
+            .iter()
+            .map(|l| format!("{:?}", l.consistency_level))
+            .collect::<Vec<_>>();
+        let names = self
+            .properties
+            .iter()
+            .map(|(name, expectation)| format!("{expectation:?} {name:?}"))
+            .collect::<Vec<_>>();
+        let width = names.iter().map(|n| n.len()).max().unwrap_or_default();
+        let mut table = format!("{:width$}", "Property");
+        for header in &headers {
+            write!(table, "  {header}").unwrap();
+        }
+        table.push('\n');
+        for (name, row) in self.properties.keys().zip(&names) {
+            write!(table, "{row:width$}").unwrap();
+            for (i, header) in headers.iter().enumerate() {
+                let status = if self.holds(name, i) { "OK" } else { "FAILED" };
+                write!(table, "  {status:w$}", w = header.len()).unwrap();
+            }
+            table.truncate(table.trim_end().len());
+            table.push('\n');
+        }
+        table
+    }
+}
@@ -0,0 +1,125 @@
+//! Layering of options from a config file and environment variables under those given on the
+//! command line, so that flags override the environment and the environment overrides the file.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+use clap::Command;
+
+/// Prefix for environment variables setting options, e.g. `THEMELIOS_NODES=2` for `--nodes 2`.
+pub const ENV_PREFIX: &str = "THEMELIOS_";
+
+/// Layer the values of options from the config file, keyed by their long flag, and the
+/// environment under the command line arguments, returning the arguments to parse.
+///
+/// Each option takes its values from the command line if given there, otherwise from its
+/// environment variable, otherwise from the file.
+/// Boolean flags are set by `true`, `1`, `yes` or `on`, and left unset by `false`, `0`, `no` or
+/// `off`.
+pub fn layer_args(
+    command: &Command,
+    args: Vec<OsString>,
+    file: BTreeMap<String, Vec<String>>,
+    env: &BTreeMap<String, String>,
+) -> Vec<OsString> {
+    let cli = args
+        .iter()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    let mut layered = file;
+    for (var, value) in env {
+        if let Some(key) = var.strip_prefix(ENV_PREFIX) {
+            let key = key.to_lowercase().replace('_', "-");
+            // other variables can share the prefix, only take those for options
+            if key != "config" && is_option(command, &key) {
+                layered.insert(key, vec![value.clone()]);
+            }
+        }
+    }
+
+    let mut args = args;
+    for (key, values) in layered {
+        let Some(arg) = command
+            .get_arguments()
+            .find(|a| a.get_long() == Some(key.as_str()))
+        else {
+            continue;
+        };
+        if given_on_cli(&cli, &key, arg.get_short()) {
+            continue;
+        }
+        let takes_value = arg.is_takes_value_set();
+        for value in values {
+            match (takes_value, parse_bool(&value)) {
+                (false, Some(true)) => args.push(format!("--{key}").into()),
+                (false, Some(false)) => {}
+                // leave anything else for the parser to reject
+                _ => {
+                    args.push(format!("--{key}").into());
+                    args.push(value.into());
+                }
+            }
+        }
+    }
+    args
+}
+
+/// Whether the command has an option with the given long flag.
+pub fn is_option(command: &Command, long: &str) -> bool {
+    command.get_arguments().any(|a| a.get_long() == Some(long))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// The value given for a long flag on the command line, in either `--flag value` or
+/// `--flag=value` form.
+pub fn flag_value(cli: &[String], long: &str) -> Option<String> {
+    let flag = format!("--{long}");
+    let prefix = format!("--{long}=");
+    cli.iter().enumerate().find_map(|(i, a)| {
+        if a == &flag {
+            cli.get(i + 1).cloned()
+        } else {
+            a.strip_prefix(&prefix).map(|v| v.to_owned())
+        }
+    })
+}
+
+fn given_on_cli(cli: &[String], long: &str, short: Option<char>) -> bool {
+    let flag = format!("--{long}");
+    let prefix = format!("--{long}=");
+    cli.iter().any(|a| {
+        a == &flag
+            || a.starts_with(&prefix)
+            || short.map_or(false, |s| {
+                !a.starts_with("--") && a.starts_with('-') && a[1..].starts_with(s)
+            })
+    })
+}
+
+/// Flatten a value from the config file into the values to give on the command line, repeating
+/// the flag for lists.
+pub fn config_values(key: &str, value: serde_yaml::Value) -> Result<Vec<String>, String> {
+    match value {
+        serde_yaml::Value::Bool(b) => Ok(vec![b.to_string()]),
+        serde_yaml::Value::Number(n) => Ok(vec![n.to_string()]),
+        serde_yaml::Value::String(s) => Ok(vec![s]),
+        serde_yaml::Value::Sequence(values) => {
+            let mut out = Vec::new();
+            for value in values {
+                out.extend(config_values(key, value)?);
+            }
+            Ok(out)
+        }
+        _ => Err(format!(
+            "unsupported value for option {key:?} in config file"
+        )),
+    }
+}
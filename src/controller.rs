@@ -10,19 +10,35 @@ pub use replicaset::ReplicaSetController;
 pub use scheduler::SchedulerController;
 pub use statefulset::StatefulSetController;
 
+pub use self::cronjob::{CronJobController, CronJobControllerState};
 pub use self::deployment::DeploymentControllerState;
+pub use self::endpoints::{EndpointsController, EndpointsControllerState};
+pub use self::hpa::{HPAController, HPAControllerState};
 pub use self::job::{JobController, JobControllerState};
+pub use self::namespace::{NamespaceController, NamespaceControllerState};
 pub use self::node::NodeControllerState;
+pub use self::nodelifecycle::{NodeLifecycleController, NodeLifecycleControllerState};
 pub use self::podgc::{PodGCController, PodGCControllerState};
+pub use self::provisioner::{ProvisionerController, ProvisionerControllerState};
+pub use self::pvbinder::{PersistentVolumeBinderController, PersistentVolumeBinderControllerState};
 pub use self::replicaset::ReplicaSetControllerState;
+pub use self::replication::{ReplicationManager, ReplicationManagerState};
 pub use self::scheduler::SchedulerControllerState;
 pub use self::statefulset::StatefulSetControllerState;
 
+pub mod cronjob;
 pub mod deployment;
+pub mod endpoints;
+pub mod hpa;
 pub mod job;
+pub mod namespace;
 pub mod node;
+pub mod nodelifecycle;
 pub mod podgc;
+pub mod provisioner;
+pub mod pvbinder;
 pub mod replicaset;
+pub mod replication;
 pub mod scheduler;
 pub mod statefulset;
 pub mod util;
@@ -51,9 +67,17 @@ pub enum Controllers {
     Node(NodeController),
     Scheduler(SchedulerController),
     ReplicaSet(ReplicaSetController),
+    ReplicationController(ReplicationManager),
     Deployment(DeploymentController),
     StatefulSet(StatefulSetController),
     Job(JobController),
+    CronJob(CronJobController),
+    HorizontalPodAutoscaler(HPAController),
+    NodeLifecycle(NodeLifecycleController),
+    Namespace(NamespaceController),
+    Endpoints(EndpointsController),
+    PersistentVolumeBinder(PersistentVolumeBinderController),
+    Provisioner(ProvisionerController),
     PodGC(PodGCController),
 }
 
@@ -62,9 +86,17 @@ pub enum ControllerStates {
     Node(NodeControllerState),
     Scheduler(SchedulerControllerState),
     ReplicaSet(ReplicaSetControllerState),
+    ReplicationController(ReplicationManagerState),
     Deployment(DeploymentControllerState),
     StatefulSet(StatefulSetControllerState),
     Job(JobControllerState),
+    CronJob(CronJobControllerState),
+    HorizontalPodAutoscaler(HPAControllerState),
+    NodeLifecycle(NodeLifecycleControllerState),
+    Namespace(NamespaceControllerState),
+    Endpoints(EndpointsControllerState),
+    PersistentVolumeBinder(PersistentVolumeBinderControllerState),
+    Provisioner(ProvisionerControllerState),
     PodGC(PodGCControllerState),
 }
 
@@ -94,6 +126,9 @@ impl Controller for Controllers {
             (Controllers::ReplicaSet(c), ControllerStates::ReplicaSet(s)) => {
                 c.step(global_state, s).map(|a| a.into())
             }
+            (Controllers::ReplicationController(c), ControllerStates::ReplicationController(s)) => {
+                c.step(global_state, s).map(|a| a.into())
+            }
             (Controllers::Deployment(c), ControllerStates::Deployment(s)) => {
                 c.step(global_state, s).map(|a| a.into())
             }
@@ -103,6 +138,29 @@ impl Controller for Controllers {
             (Controllers::Job(c), ControllerStates::Job(s)) => {
                 c.step(global_state, s).map(|a| a.into())
             }
+            (Controllers::CronJob(c), ControllerStates::CronJob(s)) => {
+                c.step(global_state, s).map(|a| a.into())
+            }
+            (
+                Controllers::HorizontalPodAutoscaler(c),
+                ControllerStates::HorizontalPodAutoscaler(s),
+            ) => c.step(global_state, s).map(|a| a.into()),
+            (Controllers::NodeLifecycle(c), ControllerStates::NodeLifecycle(s)) => {
+                c.step(global_state, s).map(|a| a.into())
+            }
+            (Controllers::Namespace(c), ControllerStates::Namespace(s)) => {
+                c.step(global_state, s).map(|a| a.into())
+            }
+            (Controllers::Endpoints(c), ControllerStates::Endpoints(s)) => {
+                c.step(global_state, s).map(|a| a.into())
+            }
+            (
+                Controllers::PersistentVolumeBinder(c),
+                ControllerStates::PersistentVolumeBinder(s),
+            ) => c.step(global_state, s).map(|a| a.into()),
+            (Controllers::Provisioner(c), ControllerStates::Provisioner(s)) => {
+                c.step(global_state, s).map(|a| a.into())
+            }
             (Controllers::PodGC(c), ControllerStates::PodGC(s)) => {
                 c.step(global_state, s).map(|a| a.into())
             }
@@ -127,6 +185,12 @@ impl Controller for Controllers {
                 .into_iter()
                 .map(ControllerStates::ReplicaSet)
                 .collect(),
+            (Controllers::ReplicationController(c), ControllerStates::ReplicationController(s)) => {
+                c.arbitrary_steps(s)
+                    .into_iter()
+                    .map(ControllerStates::ReplicationController)
+                    .collect()
+            }
             (Controllers::Deployment(c), ControllerStates::Deployment(s)) => c
                 .arbitrary_steps(s)
                 .into_iter()
@@ -142,6 +206,47 @@ impl Controller for Controllers {
                 .into_iter()
                 .map(ControllerStates::Job)
                 .collect(),
+            (Controllers::CronJob(c), ControllerStates::CronJob(s)) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::CronJob)
+                .collect(),
+            (
+                Controllers::HorizontalPodAutoscaler(c),
+                ControllerStates::HorizontalPodAutoscaler(s),
+            ) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::HorizontalPodAutoscaler)
+                .collect(),
+            (Controllers::NodeLifecycle(c), ControllerStates::NodeLifecycle(s)) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::NodeLifecycle)
+                .collect(),
+            (Controllers::Namespace(c), ControllerStates::Namespace(s)) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::Namespace)
+                .collect(),
+            (Controllers::Endpoints(c), ControllerStates::Endpoints(s)) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::Endpoints)
+                .collect(),
+            (
+                Controllers::PersistentVolumeBinder(c),
+                ControllerStates::PersistentVolumeBinder(s),
+            ) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::PersistentVolumeBinder)
+                .collect(),
+            (Controllers::Provisioner(c), ControllerStates::Provisioner(s)) => c
+                .arbitrary_steps(s)
+                .into_iter()
+                .map(ControllerStates::Provisioner)
+                .collect(),
             (Controllers::PodGC(c), ControllerStates::PodGC(s)) => c
                 .arbitrary_steps(s)
                 .into_iter()
@@ -156,9 +261,17 @@ impl Controller for Controllers {
             Controllers::Node(c) => c.name(),
             Controllers::Scheduler(c) => c.name(),
             Controllers::ReplicaSet(c) => c.name(),
+            Controllers::ReplicationController(c) => c.name(),
             Controllers::Deployment(c) => c.name(),
             Controllers::StatefulSet(c) => c.name(),
             Controllers::Job(c) => c.name(),
+            Controllers::CronJob(c) => c.name(),
+            Controllers::HorizontalPodAutoscaler(c) => c.name(),
+            Controllers::NodeLifecycle(c) => c.name(),
+            Controllers::Namespace(c) => c.name(),
+            Controllers::Endpoints(c) => c.name(),
+            Controllers::PersistentVolumeBinder(c) => c.name(),
+            Controllers::Provisioner(c) => c.name(),
             Controllers::PodGC(c) => c.name(),
         }
     }
@@ -172,6 +285,9 @@ impl Controller for Controllers {
             (Controllers::ReplicaSet(c), ControllerStates::ReplicaSet(s)) => {
                 c.min_revision_accepted(s)
             }
+            (Controllers::ReplicationController(c), ControllerStates::ReplicationController(s)) => {
+                c.min_revision_accepted(s)
+            }
             (Controllers::Deployment(c), ControllerStates::Deployment(s)) => {
                 c.min_revision_accepted(s)
             }
@@ -179,6 +295,27 @@ impl Controller for Controllers {
                 c.min_revision_accepted(s)
             }
             (Controllers::Job(c), ControllerStates::Job(s)) => c.min_revision_accepted(s),
+            (Controllers::CronJob(c), ControllerStates::CronJob(s)) => c.min_revision_accepted(s),
+            (
+                Controllers::HorizontalPodAutoscaler(c),
+                ControllerStates::HorizontalPodAutoscaler(s),
+            ) => c.min_revision_accepted(s),
+            (Controllers::NodeLifecycle(c), ControllerStates::NodeLifecycle(s)) => {
+                c.min_revision_accepted(s)
+            }
+            (Controllers::Namespace(c), ControllerStates::Namespace(s)) => {
+                c.min_revision_accepted(s)
+            }
+            (Controllers::Endpoints(c), ControllerStates::Endpoints(s)) => {
+                c.min_revision_accepted(s)
+            }
+            (
+                Controllers::PersistentVolumeBinder(c),
+                ControllerStates::PersistentVolumeBinder(s),
+            ) => c.min_revision_accepted(s),
+            (Controllers::Provisioner(c), ControllerStates::Provisioner(s)) => {
+                c.min_revision_accepted(s)
+            }
             (Controllers::PodGC(c), ControllerStates::PodGC(s)) => c.min_revision_accepted(s),
             _ => unreachable!(),
         }
@@ -195,6 +332,9 @@ impl Controllers {
             Controllers::ReplicaSet(_) => {
                 ControllerStates::ReplicaSet(ReplicaSetControllerState::default())
             }
+            Controllers::ReplicationController(_) => {
+                ControllerStates::ReplicationController(ReplicationManagerState::default())
+            }
             Controllers::Deployment(_) => {
                 ControllerStates::Deployment(DeploymentControllerState::default())
             }
@@ -202,6 +342,25 @@ impl Controllers {
                 ControllerStates::StatefulSet(StatefulSetControllerState::default())
             }
             Controllers::Job(_) => ControllerStates::Job(JobControllerState::default()),
+            Controllers::CronJob(_) => ControllerStates::CronJob(CronJobControllerState::default()),
+            Controllers::HorizontalPodAutoscaler(_) => {
+                ControllerStates::HorizontalPodAutoscaler(HPAControllerState::default())
+            }
+            Controllers::NodeLifecycle(_) => {
+                ControllerStates::NodeLifecycle(NodeLifecycleControllerState::default())
+            }
+            Controllers::Namespace(_) => {
+                ControllerStates::Namespace(NamespaceControllerState::default())
+            }
+            Controllers::Endpoints(_) => {
+                ControllerStates::Endpoints(EndpointsControllerState::default())
+            }
+            Controllers::PersistentVolumeBinder(_) => ControllerStates::PersistentVolumeBinder(
+                PersistentVolumeBinderControllerState::default(),
+            ),
+            Controllers::Provisioner(_) => {
+                ControllerStates::Provisioner(ProvisionerControllerState::default())
+            }
             Controllers::PodGC(_) => ControllerStates::PodGC(PodGCControllerState::default()),
         }
     }
@@ -0,0 +1,400 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    resources::{
+        ConcurrencyPolicy, ConditionStatus, CronJob, Job, JobConditionType, Metadata, Time,
+    },
+    state::{revision::Revision, StateView},
+};
+
+use super::{util::new_controller_ref, Controller};
+
+pub const CRONJOB_SCHEDULED_TIMESTAMP_ANNOTATION: &str =
+    "batch.kubernetes.io/cronjob-scheduled-timestamp";
+
+const DEFAULT_SUCCESSFUL_JOBS_HISTORY_LIMIT: u32 = 3;
+const DEFAULT_FAILED_JOBS_HISTORY_LIMIT: u32 = 1;
+
+#[derive(Clone, Debug)]
+pub struct CronJobController;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct CronJobControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
+pub enum CronJobControllerAction {
+    UpdateCronJobStatus(CronJob),
+
+    CreateJob(Job),
+    DeleteJob(Job),
+}
+
+impl From<CronJobControllerAction> for ControllerAction {
+    fn from(value: CronJobControllerAction) -> Self {
+        match value {
+            CronJobControllerAction::UpdateCronJobStatus(cj) => {
+                ControllerAction::UpdateCronJobStatus(cj)
+            }
+            CronJobControllerAction::CreateJob(job) => ControllerAction::CreateJob(job),
+            CronJobControllerAction::DeleteJob(job) => ControllerAction::DeleteJob(job),
+        }
+    }
+}
+
+impl Controller for CronJobController {
+    type State = CronJobControllerState;
+
+    type Action = CronJobControllerAction;
+
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        let now = global_state.now();
+        for cronjob in global_state.cronjobs.iter() {
+            let jobs = global_state
+                .jobs
+                .for_controller(&cronjob.metadata.uid)
+                .collect::<Vec<_>>();
+            if let Some(op) = sync_cronjob(cronjob, &jobs, now) {
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "CronJob".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+fn sync_cronjob(cronjob: &CronJob, jobs: &[&Job], now: Time) -> Option<CronJobControllerAction> {
+    let mut status = cronjob.status.clone();
+
+    // forget about any active jobs that no longer exist
+    let child_names = jobs
+        .iter()
+        .map(|j| &j.metadata.name)
+        .collect::<BTreeSet<_>>();
+    status.active.retain(|name| child_names.contains(name));
+
+    for job in jobs {
+        let in_active_list = status.active.contains(&job.metadata.name);
+        match job_finished(job) {
+            Some(finished) => {
+                if in_active_list {
+                    status.active.retain(|name| name != &job.metadata.name);
+                    if finished == JobConditionType::Complete {
+                        if let Some(completion_time) = job.status.completion_time {
+                            if status
+                                .last_successful_time
+                                .map_or(true, |t| t < completion_time)
+                            {
+                                status.last_successful_time = Some(completion_time);
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                if !in_active_list {
+                    // an unfinished job we own but were not tracking, adopt it
+                    status.active.push(job.metadata.name.clone());
+                }
+            }
+        }
+    }
+
+    if status != cronjob.status {
+        let mut cronjob = cronjob.clone();
+        cronjob.status = status;
+        return Some(CronJobControllerAction::UpdateCronJobStatus(cronjob));
+    }
+
+    if cronjob.metadata.deletion_timestamp.is_some() {
+        // the cronjob is being deleted, don't do anything more
+        return None;
+    }
+
+    if let Some(job) = cleanup_finished_jobs(cronjob, jobs) {
+        return Some(CronJobControllerAction::DeleteJob(job.clone()));
+    }
+
+    if cronjob.spec.suspend {
+        debug!("Not starting job because the cronjob is suspended");
+        return None;
+    }
+
+    let schedule = match Schedule::parse(&cronjob.spec.schedule) {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            debug!(?err, "Unparseable schedule");
+            return None;
+        }
+    };
+
+    let earliest = status
+        .last_schedule_time
+        .or(cronjob.metadata.creation_timestamp)?;
+    let scheduled_time = Time(schedule.most_recent_time(earliest.0, now.0)?);
+
+    if let Some(deadline) = cronjob.spec.starting_deadline_seconds {
+        if scheduled_time.0 + Duration::from_secs(deadline) < now.0 {
+            debug!("Missed starting window for the most recent scheduled time");
+            return None;
+        }
+    }
+
+    let job_name = job_name(cronjob, scheduled_time);
+    if child_names.contains(&job_name) {
+        // we already created the job for this schedule, just record it
+        let mut cronjob = cronjob.clone();
+        cronjob.status.last_schedule_time = Some(scheduled_time);
+        return Some(CronJobControllerAction::UpdateCronJobStatus(cronjob));
+    }
+
+    match cronjob.spec.concurrency_policy {
+        ConcurrencyPolicy::Allow => {}
+        ConcurrencyPolicy::Forbid => {
+            if !status.active.is_empty() {
+                debug!("Not starting job because a prior execution is still running and the concurrency policy is Forbid");
+                return None;
+            }
+        }
+        ConcurrencyPolicy::Replace => {
+            for name in &status.active {
+                if let Some(job) = jobs.iter().find(|j| &j.metadata.name == name) {
+                    return Some(CronJobControllerAction::DeleteJob((*job).clone()));
+                }
+            }
+        }
+    }
+
+    Some(CronJobControllerAction::CreateJob(get_job_from_template(
+        cronjob,
+        job_name,
+        scheduled_time,
+    )))
+}
+
+/// Find the oldest finished job that is beyond the history limits.
+fn cleanup_finished_jobs<'a>(cronjob: &CronJob, jobs: &[&'a Job]) -> Option<&'a Job> {
+    let successful_limit = cronjob
+        .spec
+        .successful_jobs_history_limit
+        .unwrap_or(DEFAULT_SUCCESSFUL_JOBS_HISTORY_LIMIT) as usize;
+    let failed_limit = cronjob
+        .spec
+        .failed_jobs_history_limit
+        .unwrap_or(DEFAULT_FAILED_JOBS_HISTORY_LIMIT) as usize;
+
+    let mut successful = Vec::new();
+    let mut failed = Vec::new();
+    for job in jobs {
+        match job_finished(job) {
+            Some(JobConditionType::Complete) => successful.push(*job),
+            Some(_) => failed.push(*job),
+            None => {}
+        }
+    }
+
+    for (mut finished, limit) in [(successful, successful_limit), (failed, failed_limit)] {
+        if finished.len() > limit {
+            finished.sort_by_key(|j| (j.status.start_time, j.metadata.name.clone()));
+            return finished.first().copied();
+        }
+    }
+    None
+}
+
+/// Whether the job has finished, and if so with which condition.
+fn job_finished(job: &Job) -> Option<JobConditionType> {
+    job.status
+        .conditions
+        .iter()
+        .find(|c| {
+            (c.r#type == JobConditionType::Complete || c.r#type == JobConditionType::Failed)
+                && c.status == ConditionStatus::True
+        })
+        .map(|c| c.r#type)
+}
+
+/// Jobs are named deterministically from the scheduled time so that a job is only created once per
+/// schedule.
+fn job_name(cronjob: &CronJob, scheduled_time: Time) -> String {
+    format!(
+        "{}-{}",
+        cronjob.metadata.name,
+        scheduled_time.0.unix_timestamp() / 60
+    )
+}
+
+fn get_job_from_template(cronjob: &CronJob, name: String, scheduled_time: Time) -> Job {
+    let template = &cronjob.spec.job_template;
+    let mut annotations = template.metadata.annotations.clone();
+    annotations.insert(
+        CRONJOB_SCHEDULED_TIMESTAMP_ANNOTATION.to_owned(),
+        scheduled_time.0.format(&Rfc3339).unwrap(),
+    );
+    Job {
+        metadata: Metadata {
+            name,
+            namespace: cronjob.metadata.namespace.clone(),
+            labels: template.metadata.labels.clone(),
+            annotations,
+            owner_references: vec![new_controller_ref(&cronjob.metadata, &CronJob::GVK)],
+            ..Default::default()
+        },
+        spec: template.spec.clone(),
+        status: Default::default(),
+    }
+}
+
+/// A standard cron schedule of minute, hour, day of month, month and day of week.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: BTreeSet<u8>,
+    hours: BTreeSet<u8>,
+    days_of_month: BTreeSet<u8>,
+    months: BTreeSet<u8>,
+    days_of_week: BTreeSet<u8>,
+    // When both day fields are restricted a day matches if either does, as in cron.
+    days_restricted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleParseError(pub String);
+
+impl Schedule {
+    pub fn parse(schedule: &str) -> Result<Self, ScheduleParseError> {
+        let schedule = match schedule.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            s => s,
+        };
+        let fields = schedule.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            return Err(ScheduleParseError(format!(
+                "expected 5 fields, found {}",
+                fields.len()
+            )));
+        }
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+        // both 0 and 7 are sunday
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days_of_month: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            days_of_week,
+            days_restricted: !fields[2].starts_with('*') && !fields[4].starts_with('*'),
+        })
+    }
+
+    pub fn matches(&self, time: OffsetDateTime) -> bool {
+        self.day_matches(time)
+            && self.minutes.contains(&time.minute())
+            && self.hours.contains(&time.hour())
+            && self.months.contains(&u8::from(time.month()))
+    }
+
+    fn day_matches(&self, time: OffsetDateTime) -> bool {
+        let day_of_month = self.days_of_month.contains(&time.day());
+        let day_of_week = self
+            .days_of_week
+            .contains(&time.weekday().number_days_from_sunday());
+        if self.days_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+
+    /// The most recent time that matches the schedule after `earliest` and no later than `now`.
+    ///
+    /// Steps back a field at a time, skipping over whole months, days and hours that don't match
+    /// rather than checking each of their minutes, so that even schedules that rarely match are
+    /// quick to search back through.
+    pub fn most_recent_time(
+        &self,
+        earliest: OffsetDateTime,
+        now: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        let minute = Duration::from_secs(60);
+        let midnight = |t: OffsetDateTime| t.replace_time(time::Time::MIDNIGHT);
+        let mut t = now.replace_second(0).ok()?.replace_nanosecond(0).ok()?;
+        while t > earliest {
+            t = if !self.months.contains(&u8::from(t.month())) {
+                // the last minute of the month before
+                midnight(t.replace_day(1).ok()?) - minute
+            } else if !self.day_matches(t) {
+                midnight(t) - minute
+            } else if !self.hours.contains(&t.hour()) {
+                match self.hours.range(..t.hour()).next_back() {
+                    Some(hour) => t.replace_hour(*hour).ok()?.replace_minute(59).ok()?,
+                    None => midnight(t) - minute,
+                }
+            } else if !self.minutes.contains(&t.minute()) {
+                match self.minutes.range(..t.minute()).next_back() {
+                    Some(m) => t.replace_minute(*m).ok()?,
+                    None => t.replace_minute(0).ok()? - minute,
+                }
+            } else {
+                return Some(t);
+            };
+        }
+        None
+    }
+}
+
+fn parse_field(field: &str, min: u8, max: u8) -> Result<BTreeSet<u8>, ScheduleParseError> {
+    let parse = |v: &str| {
+        v.parse::<u8>()
+            .map_err(|_| ScheduleParseError(format!("invalid value {v:?} in field {field:?}")))
+    };
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(parse(step)?)),
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse(start)?, parse(end)?)
+        } else {
+            let value = parse(range)?;
+            // a single value with a step runs until the end of the range
+            (value, if step.is_some() { max } else { value })
+        };
+        if start < min || end > max || start > end || step == Some(0) {
+            return Err(ScheduleParseError(format!(
+                "invalid range {part:?} in field {field:?}"
+            )));
+        }
+        values.extend((start..=end).step_by(step.unwrap_or(1) as usize));
+    }
+    Ok(values)
+}
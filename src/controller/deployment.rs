@@ -7,10 +7,9 @@ use crate::{
     resources::{
         ConditionStatus, Deployment, DeploymentCondition, DeploymentConditionType,
         DeploymentStatus, DeploymentStrategyType, LabelSelector, Pod, PodTemplateSpec, ReplicaSet,
-        ReplicaSetCondition, ReplicaSetConditionType,
+        ReplicaSetCondition, ReplicaSetConditionType, RollbackConfig, Time,
     },
-    state::{revision::Revision, StateView},
-    utils::now,
+    state::{revision::Revision, RawState, StateView},
 };
 use tracing::debug;
 
@@ -36,10 +35,13 @@ const NEW_RSAVAILABLE_REASON: &str = "NewReplicaSetAvailable";
 // within the given deadline (progressDeadlineSeconds).
 const TIMED_OUT_REASON: &str = "ProgressDeadlineExceeded";
 
+// The progress deadline of deployments that don't set their own.
+const DEFAULT_PROGRESS_DEADLINE_SECONDS: u32 = 600;
+
 // FoundNewRSReason is added in a deployment when it adopts an existing replica set.
 const FOUND_NEW_RSREASON: &str = "FoundNewReplicaSet";
 
-const DEPRECATED_ROLLBACK_TO: &str = "deprecated.deployment.rollback.to";
+pub const DEPRECATED_ROLLBACK_TO: &str = "deprecated.deployment.rollback.to";
 
 // const KUBE_CTL_PREFIX: &str = "kubectl.kubernetes.io/";
 // TODO: should use a const format thing with KUBE_CTL_PREFIX
@@ -51,7 +53,7 @@ pub const LAST_APPLIED_CONFIG_ANNOTATION: &str = "kubectl.kubernetes.io/last-app
 pub const DEFAULT_DEPLOYMENT_UNIQUE_LABEL_KEY: &str = "pod-template-hash";
 
 // RevisionAnnotation is the revision annotation of a deployment's replica sets which records its rollout sequence
-const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+pub const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
 
 // RevisionHistoryAnnotation maintains the history of all old revisions that a replica set has served for a deployment.
 const REVISION_HISTORY_ANNOTATION: &str = "deployment.kubernetes.io/revision-history";
@@ -59,7 +61,7 @@ const REVISION_HISTORY_ANNOTATION: &str = "deployment.kubernetes.io/revision-his
 // DesiredReplicasAnnotation is the desired replicas for a deployment recorded as an annotation
 // in its replica sets. Helps in separating scaling events from the rollout process and for
 // determining if the new replica set for a deployment is really saturated.
-const DESIRED_REPLICAS_ANNOTATION: &str = "deployment.kubernetes.io/desired-replicas";
+pub const DESIRED_REPLICAS_ANNOTATION: &str = "deployment.kubernetes.io/desired-replicas";
 
 // MaxReplicasAnnotation is the maximum replicas a deployment can have at a given point, which
 // is deployment.spec.replicas + maxSurge. Used by the underlying replica sets to estimate their
@@ -86,21 +88,24 @@ pub struct DeploymentControllerState {
 
 #[derive(Debug)]
 pub enum DeploymentControllerAction {
-    RequeueDeployment(Deployment),
+    /// Look at the deployment again after the given number of seconds, or after a rate limited
+    /// delay when not given.
+    RequeueDeployment(Deployment, Option<u64>),
     UpdateDeployment(Deployment),
     UpdateDeploymentStatus(Deployment),
 
     CreateReplicaSet(ReplicaSet),
     UpdateReplicaSet(ReplicaSet),
     DeleteReplicaSet(ReplicaSet),
+    /// Update the replicasets together, in a single transaction.
     UpdateReplicaSets(Vec<ReplicaSet>),
 }
 
 impl From<DeploymentControllerAction> for ControllerAction {
     fn from(value: DeploymentControllerAction) -> Self {
         match value {
-            DeploymentControllerAction::RequeueDeployment(d) => {
-                ControllerAction::RequeueDeployment(d)
+            DeploymentControllerAction::RequeueDeployment(d, after) => {
+                ControllerAction::RequeueDeployment(d, after)
             }
             DeploymentControllerAction::UpdateDeployment(d) => {
                 ControllerAction::UpdateDeployment(d)
@@ -117,9 +122,11 @@ impl From<DeploymentControllerAction> for ControllerAction {
             DeploymentControllerAction::DeleteReplicaSet(rs) => {
                 ControllerAction::DeleteReplicaSet(rs)
             }
-            DeploymentControllerAction::UpdateReplicaSets(rss) => {
-                ControllerAction::UpdateReplicaSets(rss)
-            }
+            DeploymentControllerAction::UpdateReplicaSets(rss) => ControllerAction::Transaction(
+                rss.into_iter()
+                    .map(ControllerAction::UpdateReplicaSet)
+                    .collect(),
+            ),
         }
     }
 }
@@ -137,15 +144,29 @@ impl Controller for DeploymentController {
         local_state: &mut Self::State,
     ) -> Option<DeploymentControllerAction> {
         local_state.revision = Some(global_state.revision.clone());
+        let mut requeue = None;
         for deployment in global_state.deployments.iter() {
-            let replicasets = global_state.replicasets.iter().collect::<Vec<_>>();
+            let replicasets = global_state
+                .replicasets
+                .in_namespace(&deployment.metadata.namespace)
+                .collect::<Vec<_>>();
             let pod_map = BTreeMap::new();
-            if let Some(op) = reconcile(deployment, &replicasets, &pod_map, &global_state.revision)
-            {
-                return Some(op);
+            match reconcile(
+                deployment,
+                &replicasets,
+                &pod_map,
+                &global_state.revision,
+                global_state.now(),
+            ) {
+                // requeues only matter once the other deployments have nothing to do
+                Some(op @ DeploymentControllerAction::RequeueDeployment(_, _)) => {
+                    requeue.get_or_insert(op);
+                }
+                Some(op) => return Some(op),
+                None => {}
             }
         }
-        None
+        requeue
     }
 
     fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
@@ -166,6 +187,7 @@ fn reconcile(
     all_replicasets: &[&ReplicaSet],
     pod_map: &BTreeMap<String, Vec<Pod>>,
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
     let everything = LabelSelector::default();
     if deployment.spec.selector == everything {
@@ -194,13 +216,14 @@ fn reconcile(
             &replicasets,
             all_replicasets,
             state_revision,
+            now,
         );
     }
 
     // Update deployment conditions with an Unknown condition when pausing/resuming
     // a deployment. In this way, we can be sure that we won't timeout when a user
     // resumes a Deployment with a set progressDeadlineSeconds.
-    if let Some(op) = check_paused_conditions(&mut deployment.clone()) {
+    if let Some(op) = check_paused_conditions(&mut deployment.clone(), now) {
         return Some(op);
     }
 
@@ -210,6 +233,7 @@ fn reconcile(
             &replicasets,
             all_replicasets,
             state_revision,
+            now,
         );
     }
 
@@ -217,10 +241,11 @@ fn reconcile(
     // revision so we should ensure that we won't proceed to update replica sets until we
     // make sure that the deployment has cleaned up its rollback spec in subsequent enqueues.
     if get_rollback_to(deployment).is_some() {
-        return rollback(&mut deployment.clone(), &replicasets, all_replicasets);
+        return rollback(&mut deployment.clone(), &replicasets, all_replicasets, now);
     }
 
-    let scaling_event = is_scaling_event(&mut deployment.clone(), &replicasets, all_replicasets);
+    let scaling_event =
+        is_scaling_event(&mut deployment.clone(), &replicasets, all_replicasets, now);
     let scaling_event = match scaling_event {
         ValOrOp::Resource(r) => r,
         ValOrOp::Op(op) => return Some(op),
@@ -231,6 +256,7 @@ fn reconcile(
             &replicasets,
             all_replicasets,
             state_revision,
+            now,
         );
     }
 
@@ -247,12 +273,14 @@ fn reconcile(
             all_replicasets,
             pod_map,
             state_revision,
+            now,
         ),
         DeploymentStrategyType::RollingUpdate => rollout_rolling(
             &mut deployment.clone(),
             &replicasets,
             all_replicasets,
             state_revision,
+            now,
         ),
     }
 }
@@ -328,9 +356,15 @@ fn sync_status_only(
     replicasets: &[&ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
-    let (new_replicaset, old_replicasets) =
-        get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, false);
+    let (new_replicaset, old_replicasets) = get_all_replicasets_and_sync_revision(
+        deployment,
+        replicasets,
+        replicasets_in_ns,
+        false,
+        now,
+    );
     let new_replicaset = match new_replicaset {
         Some(ValOrOp::Resource(r)) => Some(r),
         Some(ValOrOp::Op(op)) => return Some(op),
@@ -340,13 +374,16 @@ fn sync_status_only(
     if let Some(new_replicaset) = &new_replicaset {
         all_rss.push(new_replicaset);
     }
-    sync_deployment_status(&all_rss, &new_replicaset, deployment, state_revision)
+    sync_deployment_status(&all_rss, &new_replicaset, deployment, state_revision, now)
 }
 
 // checkPausedConditions checks if the given deployment is paused or not and adds an appropriate condition.
 // These conditions are needed so that we won't accidentally report lack of progress for resumed deployments
 // that were paused for longer than progressDeadlineSeconds.
-fn check_paused_conditions(deployment: &mut Deployment) -> Option<DeploymentControllerAction> {
+fn check_paused_conditions(
+    deployment: &mut Deployment,
+    now: Time,
+) -> Option<DeploymentControllerAction> {
     debug!("Checking paused conditions");
     if has_progress_deadline(deployment) {
         return None;
@@ -366,6 +403,7 @@ fn check_paused_conditions(deployment: &mut Deployment) -> Option<DeploymentCont
             ConditionStatus::Unknown,
             PAUSED_DEPLOY_REASON.to_owned(),
             "Deployment is paused".to_owned(),
+            now,
         );
         set_deployment_condition(&mut deployment.status, cond);
         Some(DeploymentControllerAction::UpdateDeploymentStatus(
@@ -377,6 +415,7 @@ fn check_paused_conditions(deployment: &mut Deployment) -> Option<DeploymentCont
             ConditionStatus::Unknown,
             RESUMED_DEPLOY_REASON.to_owned(),
             "Deployment is resumed".to_owned(),
+            now,
         );
         set_deployment_condition(&mut deployment.status, cond);
         Some(DeploymentControllerAction::UpdateDeploymentStatus(
@@ -393,10 +432,16 @@ fn sync(
     replicasets: &[&ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
     debug!("Syncing deployment");
-    let (new_replicaset, old_replicasets) =
-        get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, false);
+    let (new_replicaset, old_replicasets) = get_all_replicasets_and_sync_revision(
+        deployment,
+        replicasets,
+        replicasets_in_ns,
+        false,
+        now,
+    );
     let new_replicaset = match new_replicaset {
         Some(ValOrOp::Resource(r)) => Some(r),
         Some(ValOrOp::Op(op)) => return Some(op),
@@ -423,6 +468,7 @@ fn sync(
         &new_replicaset,
         deployment,
         state_revision,
+        now,
     ) {
         return Some(op);
     }
@@ -445,6 +491,7 @@ fn get_all_replicasets_and_sync_revision<'a>(
     replicasets: &[&'a ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
     create_if_not_existed: bool,
+    now: Time,
 ) -> (Option<ValOrOp<ReplicaSet>>, Vec<&'a ReplicaSet>) {
     debug!("getting all replicasets and sync revision");
     let (_, all_old_replicasets) = find_old_replicasets(deployment, replicasets);
@@ -455,6 +502,7 @@ fn get_all_replicasets_and_sync_revision<'a>(
         &all_old_replicasets,
         replicasets_in_ns,
         create_if_not_existed,
+        now,
     );
 
     (new_replicaset, all_old_replicasets)
@@ -515,6 +563,7 @@ fn get_new_replicaset(
     old_replicasets: &[&ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
     create_if_not_existed: bool,
+    now: Time,
 ) -> Option<ValOrOp<ReplicaSet>> {
     let existing_new_rs = find_new_replicaset(deployment, replicasets);
 
@@ -568,6 +617,7 @@ fn get_new_replicaset(
                 ConditionStatus::True,
                 FOUND_NEW_RSREASON.to_owned(),
                 message,
+                now,
             );
             set_deployment_condition(&mut deployment.status, condition);
             needs_update = true;
@@ -672,9 +722,16 @@ fn sync_deployment_status(
     new_replicaset: &Option<ReplicaSet>,
     deployment: &Deployment,
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
     debug!("Syncing deployment status");
-    let new_status = calculate_status(all_replicasets, new_replicaset, deployment, state_revision);
+    let new_status = calculate_status(
+        all_replicasets,
+        new_replicaset,
+        deployment,
+        state_revision,
+        now,
+    );
     if deployment.status != new_status {
         debug!("Setting new status");
         let mut new_deployment = deployment.clone();
@@ -693,6 +750,7 @@ fn calculate_status(
     new_replicaset: &Option<ReplicaSet>,
     deployment: &Deployment,
     state_revision: &Revision,
+    now: Time,
 ) -> DeploymentStatus {
     let available_replicas = get_available_replica_count_for_replicasets(all_replicasets);
     let total_replicas = get_replica_count_for_replicasets(all_replicasets);
@@ -726,6 +784,7 @@ fn calculate_status(
             ConditionStatus::True,
             MINIMUM_REPLICAS_AVAILABLE.to_owned(),
             "Deployment has minimum availability.".to_owned(),
+            now,
         );
         set_deployment_condition(&mut status, min_availability);
     } else {
@@ -738,6 +797,7 @@ fn calculate_status(
             ConditionStatus::False,
             MINIMUM_REPLICAS_UNAVAILABLE.to_owned(),
             "Deployment does not have minimum availability.".to_owned(),
+            now,
         );
         set_deployment_condition(&mut status, no_min_availability);
     }
@@ -884,7 +944,6 @@ fn scale(
                 }
             }
 
-            // TODO: Use transactions when we have them.
             if let Some(DeploymentControllerAction::UpdateReplicaSet(rs)) = scale_replicaset(
                 rs,
                 name_to_size.get(&rs.metadata.name).copied().unwrap_or(0),
@@ -1078,7 +1137,7 @@ fn max_surge(deployment: &Deployment) -> u32 {
     }
 }
 
-fn is_rolling_update(deployment: &Deployment) -> bool {
+pub(crate) fn is_rolling_update(deployment: &Deployment) -> bool {
     deployment
         .spec
         .strategy
@@ -1210,12 +1269,13 @@ fn new_deployment_condition(
     status: ConditionStatus,
     reason: String,
     message: String,
+    now: Time,
 ) -> DeploymentCondition {
     DeploymentCondition {
         r#type: cond_type,
         status,
-        last_update_time: Some(now()),
-        last_transition_time: Some(now()),
+        last_update_time: Some(now),
+        last_transition_time: Some(now),
         reason: Some(reason),
         message: Some(message),
     }
@@ -1452,10 +1512,6 @@ fn get_rollback_to(deployment: &Deployment) -> Option<RollbackConfig> {
     }
 }
 
-pub struct RollbackConfig {
-    revision: u64,
-}
-
 // GetProportion will estimate the proportion for the provided replica set using 1. the current size
 // of the parent deployment, 2. the replica count that needs be added on the replica sets of the
 // deployment, and 3. the total replicas added in the replica sets of the deployment so far.
@@ -1521,9 +1577,15 @@ fn rollback(
     deployment: &mut Deployment,
     replicasets: &[&ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
-    let (new_rs, all_old_rss) =
-        get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, true);
+    let (new_rs, all_old_rss) = get_all_replicasets_and_sync_revision(
+        deployment,
+        replicasets,
+        replicasets_in_ns,
+        true,
+        now,
+    );
 
     let new_rs = match new_rs {
         Some(ValOrOp::Resource(r)) => Some(r),
@@ -1648,9 +1710,15 @@ fn is_scaling_event(
     deployment: &mut Deployment,
     replicasets: &[&ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
+    now: Time,
 ) -> ValOrOp<bool> {
-    let (new_rs, old_rss) =
-        get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, false);
+    let (new_rs, old_rss) = get_all_replicasets_and_sync_revision(
+        deployment,
+        replicasets,
+        replicasets_in_ns,
+        false,
+        now,
+    );
     let new_rs = match new_rs {
         Some(ValOrOp::Resource(r)) => Some(r),
         Some(ValOrOp::Op(op)) => return ValOrOp::Op(op),
@@ -1708,7 +1776,7 @@ fn rollback_to_template(
     deployment: &mut Deployment,
     replicaset: &ReplicaSet,
 ) -> DeploymentControllerAction {
-    if equal_ignore_hash(&deployment.spec.template, &replicaset.spec.template) {
+    if !equal_ignore_hash(&deployment.spec.template, &replicaset.spec.template) {
         set_from_replicaset_template(deployment, &replicaset.spec.template);
         // set RS (the old RS we'll rolling back to) annotations back to the deployment;
         // otherwise, the deployment's current annotations (should be the same as current new RS) will be copied to the RS after the rollback.
@@ -1787,10 +1855,16 @@ fn rollout_rolling(
     replicasets: &[&ReplicaSet],
     replicasets_in_ns: &[&ReplicaSet],
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
     debug!("Rolling out an update");
-    let (new_replicaset, old_replicasets) =
-        get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, true);
+    let (new_replicaset, old_replicasets) = get_all_replicasets_and_sync_revision(
+        deployment,
+        replicasets,
+        replicasets_in_ns,
+        true,
+        now,
+    );
     let new_replicaset = match new_replicaset {
         Some(ValOrOp::Resource(r)) => r,
         Some(ValOrOp::Op(op)) => return Some(op),
@@ -1831,6 +1905,7 @@ fn rollout_rolling(
         &Some(new_replicaset.clone()),
         deployment,
         state_revision,
+        now,
     )
 }
 
@@ -1844,8 +1919,9 @@ fn sync_rollout_status(
     new_rs: &Option<ReplicaSet>,
     deployment: &Deployment,
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
-    let mut new_status = calculate_status(all_rss, new_rs, deployment, state_revision);
+    let mut new_status = calculate_status(all_rss, new_rs, deployment, state_revision, now);
     debug!("Checking new status");
 
     if !has_progress_deadline(deployment) {
@@ -1869,6 +1945,7 @@ fn sync_rollout_status(
                 ConditionStatus::True,
                 NEW_RSAVAILABLE_REASON.to_owned(),
                 msg,
+                now,
             );
             set_deployment_condition(&mut new_status, condition);
         } else if deployment_progressing(deployment, &new_status) {
@@ -1878,6 +1955,7 @@ fn sync_rollout_status(
                 ConditionStatus::True,
                 REPLICASET_UPDATED_REASON.to_owned(),
                 msg,
+                now,
             );
             if let Some(current_cond) = current_cond {
                 if current_cond.status == ConditionStatus::True {
@@ -1886,7 +1964,7 @@ fn sync_rollout_status(
                 remove_deployment_condition(&mut new_status, DeploymentConditionType::Progressing);
             }
             set_deployment_condition(&mut new_status, condition);
-        } else if deployment_timed_out(deployment, &new_status) {
+        } else if deployment_timed_out(deployment, &new_status, now) {
             let msg = format!(
                 "Deployment {} has timed out progressing.",
                 deployment.metadata.name
@@ -1896,6 +1974,7 @@ fn sync_rollout_status(
                 ConditionStatus::False,
                 TIMED_OUT_REASON.to_owned(),
                 msg,
+                now,
             );
             set_deployment_condition(&mut new_status, condition);
         }
@@ -1909,7 +1988,7 @@ fn sync_rollout_status(
     }
 
     if deployment.status == new_status {
-        return requeue_stuck_deployment(deployment, new_status);
+        return requeue_stuck_deployment(deployment, new_status, now);
     }
 
     debug!("Deployment status was different at the end, updating");
@@ -2130,10 +2209,16 @@ fn rollout_recreate(
     replicasets_in_ns: &[&ReplicaSet],
     pod_map: &BTreeMap<String, Vec<Pod>>,
     state_revision: &Revision,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
     // Don't create a new RS if not already existed, so that we avoid scaling up before scaling down.
-    let (new_replicaset, old_replicasets) =
-        get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, false);
+    let (new_replicaset, old_replicasets) = get_all_replicasets_and_sync_revision(
+        deployment,
+        replicasets,
+        replicasets_in_ns,
+        false,
+        now,
+    );
     let new_replicaset = match new_replicaset {
         Some(ValOrOp::Resource(r)) => Some(r),
         Some(ValOrOp::Op(op)) => return Some(op),
@@ -2158,15 +2243,20 @@ fn rollout_recreate(
 
     if old_pods_running(&new_replicaset, &old_replicasets, pod_map) {
         let all_rss = all_rss.iter().collect::<Vec<_>>();
-        return sync_rollout_status(&all_rss, &new_replicaset, deployment, state_revision);
+        return sync_rollout_status(&all_rss, &new_replicaset, deployment, state_revision, now);
     }
 
     // If we need to create a new RS, create it now.
     let (new_replicaset, old_replicasets) = if let Some(new_replicaset) = new_replicaset {
         (new_replicaset, old_replicasets)
     } else {
-        let (new_replicaset, old_replicasets) =
-            get_all_replicasets_and_sync_revision(deployment, replicasets, replicasets_in_ns, true);
+        let (new_replicaset, old_replicasets) = get_all_replicasets_and_sync_revision(
+            deployment,
+            replicasets,
+            replicasets_in_ns,
+            true,
+            now,
+        );
         let new_replicaset = match new_replicaset {
             Some(ValOrOp::Resource(r)) => r,
             Some(ValOrOp::Op(op)) => return Some(op),
@@ -2188,7 +2278,13 @@ fn rollout_recreate(
     }
 
     let all_rss = all_rss.iter().collect::<Vec<_>>();
-    sync_rollout_status(&all_rss, &Some(new_replicaset), deployment, state_revision)
+    sync_rollout_status(
+        &all_rss,
+        &Some(new_replicaset),
+        deployment,
+        state_revision,
+        now,
+    )
 }
 
 fn scale_down_old_replicasets_for_recreate(
@@ -2235,7 +2331,7 @@ fn deployment_progressing(deployment: &Deployment, new_status: &DeploymentStatus
         || new_status.available_replicas > old_status.available_replicas
 }
 
-fn deployment_timed_out(deployment: &Deployment, new_status: &DeploymentStatus) -> bool {
+fn deployment_timed_out(deployment: &Deployment, new_status: &DeploymentStatus, now: Time) -> bool {
     if !has_progress_deadline(deployment) {
         return false;
     }
@@ -2253,17 +2349,41 @@ fn deployment_timed_out(deployment: &Deployment, new_status: &DeploymentStatus)
     }
 
     let from = cond.last_update_time.unwrap();
-    let now = now();
     let delta = std::time::Duration::from_secs(
         deployment
             .spec
             .progress_deadline_seconds
-            .unwrap_or_default() as u64,
+            .unwrap_or(DEFAULT_PROGRESS_DEADLINE_SECONDS) as u64,
     );
 
     from.0 + delta < now.0
 }
 
+/// Whether any deployment is progressing towards a deadline that is still to come, so advancing
+/// the clock can time it out.
+///
+/// THEMELIOS: Only deadlines that are set explicitly count, so that the clock isn't advanced for
+/// every deployment through the default deadline.
+pub fn progress_deadlines_pending(view: &RawState) -> bool {
+    view.deployments.iter().any(|deployment| {
+        let Some(deadline) = deployment.spec.progress_deadline_seconds else {
+            return false;
+        };
+        let Some(cond) =
+            get_deployment_condition(&deployment.status, DeploymentConditionType::Progressing)
+        else {
+            return false;
+        };
+        let reason = cond.reason.as_deref();
+        if reason == Some(NEW_RSAVAILABLE_REASON) || reason == Some(TIMED_OUT_REASON) {
+            return false;
+        }
+        cond.last_update_time.map_or(false, |from| {
+            from.0 + std::time::Duration::from_secs(deadline as u64) >= view.now().0
+        })
+    })
+}
+
 fn get_replica_failures(
     all_replicasets: &[&ReplicaSet],
     new_rs: &Option<ReplicaSet>,
@@ -2316,6 +2436,7 @@ fn replicaset_to_deployment_condition(cond: ReplicaSetCondition) -> DeploymentCo
 fn requeue_stuck_deployment(
     deployment: &Deployment,
     new_status: DeploymentStatus,
+    now: Time,
 ) -> Option<DeploymentControllerAction> {
     let current_cond =
         get_deployment_condition(&deployment.status, DeploymentConditionType::Progressing);
@@ -2355,12 +2476,34 @@ fn requeue_stuck_deployment(
     // progressDeadlineSeconds: 600 (10 minutes)
     //
     // lastUpdated + progressDeadlineSeconds - now => 00:00:00 + 00:10:00 - 00:03:00 => 07:00
-    // TODO: could delay requeue but just do it for now, the rate limiting can handle that
-    // TODO: fix requeueing
-    None
-    // Some(DeploymentControllerAction::RequeueDeployment(
-    //     deployment.clone(),
-    // ))
+    let last_update = current_cond.unwrap().last_update_time?;
+    let deadline = std::time::Duration::from_secs(
+        deployment
+            .spec
+            .progress_deadline_seconds
+            .unwrap_or(DEFAULT_PROGRESS_DEADLINE_SECONDS) as u64,
+    );
+    let after = (last_update.0 + deadline - now.0).whole_seconds();
+    // If the remaining time is less than a second, then requeue the deployment immediately.
+    // Make it ratelimited so we stay on the safe side, eventually the Deployment should
+    // transition either to a Complete or to a TimedOut condition.
+    if after < 1 {
+        debug!("Queueing up deployment for a progress check now");
+        return Some(DeploymentControllerAction::RequeueDeployment(
+            deployment.clone(),
+            None,
+        ));
+    }
+    debug!(after, "Queueing up deployment for a progress check");
+    // Add a second to avoid milliseconds skew in AddAfter.
+    // See https://github.com/kubernetes/kubernetes/issues/39785#issuecomment-279959133 for more info.
+    //
+    // THEMELIOS: The clock moves in whole seconds so there is no skew, but the second is still
+    // needed as the deployment only times out once the clock is strictly past its deadline.
+    Some(DeploymentControllerAction::RequeueDeployment(
+        deployment.clone(),
+        Some(after as u64 + 1),
+    ))
 }
 
 fn old_pods_running(
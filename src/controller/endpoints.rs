@@ -0,0 +1,156 @@
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    resources::{
+        EndpointAddress, EndpointSubset, Endpoints, Metadata, ObjectReference, Pod, Service,
+    },
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::{
+    util::{is_pod_ready, subset},
+    Controller,
+};
+
+/// The endpoints controller, keeping the endpoints of each service in line with the pods that it
+/// selects.
+#[derive(Clone, Debug)]
+pub struct EndpointsController;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct EndpointsControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug)]
+pub enum EndpointsControllerAction {
+    CreateEndpoints(Endpoints),
+    UpdateEndpoints(Endpoints),
+    DeleteEndpoints(Endpoints),
+}
+
+impl From<EndpointsControllerAction> for ControllerAction {
+    fn from(value: EndpointsControllerAction) -> Self {
+        match value {
+            EndpointsControllerAction::CreateEndpoints(e) => ControllerAction::CreateEndpoints(e),
+            EndpointsControllerAction::UpdateEndpoints(e) => ControllerAction::UpdateEndpoints(e),
+            EndpointsControllerAction::DeleteEndpoints(e) => ControllerAction::DeleteEndpoints(e),
+        }
+    }
+}
+
+impl Controller for EndpointsController {
+    type State = EndpointsControllerState;
+
+    type Action = EndpointsControllerAction;
+
+    // https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/endpoint/endpoints_controller.go
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        for service in global_state.services.iter() {
+            // services without selectors have their endpoints managed externally
+            if service.spec.selector.is_empty() {
+                continue;
+            }
+            let subsets = desired_subsets(service, global_state);
+            match global_state
+                .endpoints
+                .get_in(&service.metadata.namespace, &service.metadata.name)
+            {
+                None => {
+                    debug!(service = service.metadata.name, "Creating endpoints");
+                    return Some(EndpointsControllerAction::CreateEndpoints(Endpoints {
+                        metadata: Metadata {
+                            name: service.metadata.name.clone(),
+                            namespace: service.metadata.namespace.clone(),
+                            labels: service.metadata.labels.clone(),
+                            ..Default::default()
+                        },
+                        subsets,
+                    }));
+                }
+                Some(endpoints) if endpoints.subsets != subsets => {
+                    debug!(service = service.metadata.name, "Updating endpoints");
+                    let mut endpoints = endpoints.clone();
+                    endpoints.subsets = subsets;
+                    return Some(EndpointsControllerAction::UpdateEndpoints(endpoints));
+                }
+                Some(_) => {}
+            }
+        }
+        for endpoints in global_state.endpoints.iter() {
+            if !global_state
+                .services
+                .has_in(&endpoints.metadata.namespace, &endpoints.metadata.name)
+            {
+                debug!(
+                    endpoints = endpoints.metadata.name,
+                    "Deleting endpoints of removed service"
+                );
+                return Some(EndpointsControllerAction::DeleteEndpoints(
+                    endpoints.clone(),
+                ));
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "Endpoints".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+/// The pods that the service routes to, whether ready or not.
+///
+/// THEMELIOS: Pods only get an address once they are scheduled since they have no IPs, and
+/// terminating pods are dropped as if `publishNotReadyAddresses` were unset.
+pub fn selected_pods<'a>(
+    service: &'a Service,
+    view: &'a RawState,
+) -> impl Iterator<Item = &'a Pod> {
+    view.pods
+        .in_namespace(&service.metadata.namespace)
+        .filter(|p| subset(&service.spec.selector, &p.metadata.labels))
+        .filter(|p| p.spec.node_name.is_some())
+        .filter(|p| p.metadata.deletion_timestamp.is_none())
+}
+
+/// The subsets that the endpoints for the service should have, with a single subset since ports
+/// are not modelled.
+pub fn desired_subsets(service: &Service, view: &RawState) -> Vec<EndpointSubset> {
+    let mut subset = EndpointSubset::default();
+    for pod in selected_pods(service, view) {
+        let address = EndpointAddress {
+            node_name: pod.spec.node_name.clone(),
+            target_ref: Some(ObjectReference {
+                kind: Pod::GVK.kind.to_owned(),
+                namespace: pod.metadata.namespace.clone(),
+                name: pod.metadata.name.clone(),
+                uid: pod.metadata.uid.clone(),
+            }),
+        };
+        if is_pod_ready(pod) {
+            subset.addresses.push(address);
+        } else {
+            subset.not_ready_addresses.push(address);
+        }
+    }
+    if subset.addresses.is_empty() && subset.not_ready_addresses.is_empty() {
+        Vec::new()
+    } else {
+        vec![subset]
+    }
+}
@@ -0,0 +1,195 @@
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    resources::{HorizontalPodAutoscaler, Scale},
+    state::{revision::Revision, StateView},
+};
+
+use super::Controller;
+
+/// The target utilization used when the autoscaler doesn't specify one.
+pub const DEFAULT_TARGET_CPU_UTILIZATION_PERCENTAGE: u32 = 80;
+
+/// Utilization within this percentage of the target is close enough to not scale.
+const TOLERANCE_PERCENTAGE: u32 = 10;
+
+#[derive(Clone, Debug)]
+pub struct HPAController;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct HPAControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
+pub enum HPAControllerAction {
+    UpdateHorizontalPodAutoscalerStatus(HorizontalPodAutoscaler),
+
+    ScaleDeployment(Scale),
+    ScaleReplicaSet(Scale),
+    ScaleStatefulSet(Scale),
+}
+
+impl From<HPAControllerAction> for ControllerAction {
+    fn from(value: HPAControllerAction) -> Self {
+        match value {
+            HPAControllerAction::UpdateHorizontalPodAutoscalerStatus(hpa) => {
+                ControllerAction::UpdateHorizontalPodAutoscalerStatus(hpa)
+            }
+            HPAControllerAction::ScaleDeployment(scale) => ControllerAction::ScaleDeployment(scale),
+            HPAControllerAction::ScaleReplicaSet(scale) => ControllerAction::ScaleReplicaSet(scale),
+            HPAControllerAction::ScaleStatefulSet(scale) => {
+                ControllerAction::ScaleStatefulSet(scale)
+            }
+        }
+    }
+}
+
+impl Controller for HPAController {
+    type State = HPAControllerState;
+
+    type Action = HPAControllerAction;
+
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        for hpa in global_state.horizontal_pod_autoscalers.iter() {
+            if let Some(op) = reconcile_autoscaler(hpa, global_state) {
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "HorizontalPodAutoscaler".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+fn reconcile_autoscaler(
+    hpa: &HorizontalPodAutoscaler,
+    view: &StateView,
+) -> Option<HPAControllerAction> {
+    let reference = &hpa.spec.scale_target_ref;
+    let scale = match get_scale(view, hpa) {
+        Some(scale) => scale,
+        None => {
+            debug!(?reference, "Scale target not found");
+            return None;
+        }
+    };
+
+    let current_replicas = scale.spec.replicas;
+    let key = (hpa.metadata.namespace.clone(), hpa.metadata.name.clone());
+    let utilization = view.metrics.get(&key).copied();
+    let min_replicas = hpa.min_replicas();
+    let max_replicas = hpa.spec.max_replicas;
+    let desired_replicas = if current_replicas == 0 && min_replicas != 0 {
+        // autoscaling is disabled for the target
+        0
+    } else if current_replicas > max_replicas {
+        max_replicas
+    } else if current_replicas < min_replicas {
+        min_replicas
+    } else {
+        match utilization {
+            Some(utilization) => {
+                let target = hpa
+                    .spec
+                    .target_cpu_utilization_percentage
+                    .unwrap_or(DEFAULT_TARGET_CPU_UTILIZATION_PERCENTAGE);
+                // THEMELIOS: no stabilization window or rate limiting of scaling
+                replicas_for_utilization(current_replicas, utilization, target)
+                    .max(min_replicas)
+                    .min(max_replicas)
+            }
+            None => {
+                debug!("No metrics available for the autoscaler");
+                current_replicas
+            }
+        }
+    };
+
+    if desired_replicas != current_replicas {
+        let mut scale = scale;
+        scale.spec.replicas = desired_replicas;
+        return match reference.kind.as_str() {
+            "Deployment" => Some(HPAControllerAction::ScaleDeployment(scale)),
+            "ReplicaSet" => Some(HPAControllerAction::ScaleReplicaSet(scale)),
+            "StatefulSet" => Some(HPAControllerAction::ScaleStatefulSet(scale)),
+            _ => None,
+        };
+    }
+
+    let mut status = hpa.status.clone();
+    status.observed_generation = hpa.metadata.generation;
+    status.current_replicas = current_replicas;
+    status.current_cpu_utilization_percentage = utilization;
+    if status.desired_replicas != desired_replicas {
+        // THEMELIOS: scaling and recording the status are separate steps here, so the scale time
+        // is when the new desired count is first reported
+        status.desired_replicas = desired_replicas;
+        status.last_scale_time = Some(view.now());
+    }
+
+    if status != hpa.status {
+        let mut hpa = hpa.clone();
+        hpa.status = status;
+        return Some(HPAControllerAction::UpdateHorizontalPodAutoscalerStatus(
+            hpa,
+        ));
+    }
+    None
+}
+
+/// The number of replicas needed to bring the average utilization back to the target, keeping the
+/// current count when the utilization is already within tolerance of it.
+pub fn replicas_for_utilization(current_replicas: u32, utilization: u32, target: u32) -> u32 {
+    if target == 0 || utilization.abs_diff(target) * 100 <= target * TOLERANCE_PERCENTAGE {
+        return current_replicas;
+    }
+    // round up so that we never leave the pods over the target
+    (current_replicas * utilization + target - 1) / target
+}
+
+/// Read the scale subresource of the autoscaler's target.
+pub fn get_scale(view: &StateView, hpa: &HorizontalPodAutoscaler) -> Option<Scale> {
+    let reference = &hpa.spec.scale_target_ref;
+    let namespace = &hpa.metadata.namespace;
+    let (metadata, replicas, status_replicas) = match reference.kind.as_str() {
+        "Deployment" => {
+            let d = view.deployments.get_in(namespace, &reference.name)?;
+            (&d.metadata, d.spec.replicas, d.status.replicas)
+        }
+        "ReplicaSet" => {
+            let rs = view.replicasets.get_in(namespace, &reference.name)?;
+            (
+                &rs.metadata,
+                rs.spec.replicas.unwrap_or(1),
+                rs.status.replicas,
+            )
+        }
+        "StatefulSet" => {
+            let sts = view.statefulsets.get_in(namespace, &reference.name)?;
+            (
+                &sts.metadata,
+                sts.spec.replicas.unwrap_or(1),
+                sts.status.replicas,
+            )
+        }
+        _ => return None,
+    };
+    Some(Scale::new(metadata, replicas, status_replicas))
+}
@@ -4,10 +4,12 @@ use std::{
     time::Duration,
 };
 
+use serde_json::json;
 use tracing::debug;
 
 use crate::{
     abstract_model::ControllerAction,
+    api::patch::Patch,
     resources::{
         ConditionStatus, Container, ContainerStatus, EnvVar, EnvVarSource, JobCompletionMode,
         JobCondition, JobConditionType, JobPodFailurePolicy, JobPodFailurePolicyRuleAction,
@@ -17,8 +19,7 @@ use crate::{
         PodCondition, PodPhase, PodRestartPolicy, PodStatus, PodTemplateSpec, Time,
     },
     resources::{Job, PodConditionType},
-    state::{revision::Revision, StateView},
-    utils::now,
+    state::{revision::Revision, RawState, StateView},
 };
 
 use super::{
@@ -28,10 +29,10 @@ use super::{
     Controller,
 };
 
-const JOB_COMPLETION_INDEX_ANNOTATION: &str = "batch.kubernetes.io/job-completion-index";
+pub const JOB_COMPLETION_INDEX_ANNOTATION: &str = "batch.kubernetes.io/job-completion-index";
 pub const JOB_TRACKING_FINALIZER: &str = "batch.kubernetes.io/job-tracking";
 
-const JOB_COMPLETION_INDEX_ENV_NAME: &str = "JOB_COMPLETION_INDEX";
+pub const JOB_COMPLETION_INDEX_ENV_NAME: &str = "JOB_COMPLETION_INDEX";
 
 const JOB_REASON_POD_FAILURE_POLICY: &str = "PodFailurePolicy";
 const JOB_REASON_BACKOFF_LIMIT_EXCEEDED: &str = "BackoffLimitExceeded";
@@ -57,7 +58,8 @@ pub enum JobControllerAction {
     UpdateJobStatus(Job),
 
     CreatePod(Pod),
-    UpdatePod(Pod),
+    /// Namespace, name and patch
+    PatchPod(String, String, Patch),
     DeletePod(Pod),
 }
 
@@ -74,7 +76,9 @@ impl From<JobControllerAction> for ControllerAction {
         match value {
             JobControllerAction::UpdateJobStatus(j) => ControllerAction::UpdateJobStatus(j),
             JobControllerAction::CreatePod(pod) => ControllerAction::CreatePod(pod),
-            JobControllerAction::UpdatePod(pod) => ControllerAction::UpdatePod(pod),
+            JobControllerAction::PatchPod(namespace, name, patch) => {
+                ControllerAction::PatchPod(namespace, name, patch)
+            }
             JobControllerAction::DeletePod(pod) => ControllerAction::SoftDeletePod(pod),
         }
     }
@@ -94,11 +98,18 @@ impl Controller for JobController {
         for job in global_state.jobs.iter() {
             let mut pods = global_state
                 .pods
-                .iter()
+                .in_namespace(&job.metadata.namespace)
                 .filter(|p| job.spec.selector.matches(&p.metadata.labels))
                 .collect::<Vec<_>>();
             let mut job = job.clone();
-            if let Some(op) = reconcile(&mut job, &mut pods, &global_state.revision).0 {
+            if let Some(op) = reconcile(
+                &mut job,
+                &mut pods,
+                &global_state.revision,
+                global_state.now(),
+            )
+            .0
+            {
                 return Some(op);
             }
         }
@@ -122,6 +133,7 @@ fn reconcile(
     job: &mut Job,
     pods: &mut [&Pod],
     state_revision: &Revision,
+    now: Time,
 ) -> OptionalJobControllerAction {
     let active_pods = util::filter_active_pods(pods);
     let active = active_pods.len();
@@ -138,7 +150,7 @@ fn reconcile(
 
     // Job first start. Set StartTime only if the job is not in the suspended state.
     if job.status.start_time.is_none() && !job.spec.suspend {
-        job.status.start_time = Some(now());
+        job.status.start_time = Some(now);
     }
 
     let exceeds_backoff_limit = failed > job.spec.backoff_limit.unwrap_or_default() as usize;
@@ -151,7 +163,7 @@ fn reconcile(
             ConditionStatus::True,
             failure_target_condition.reason.clone(),
             failure_target_condition.message.clone(),
-            now(),
+            now,
         ))
     } else if let Some(fail_job_message) = get_fail_job_message(job, pods) {
         // Prepare the interim FailureTarget condition to record the failure message before the finalizers (allowing removal of the pods) are removed.
@@ -160,7 +172,7 @@ fn reconcile(
             ConditionStatus::True,
             JOB_REASON_POD_FAILURE_POLICY.to_owned(),
             fail_job_message,
-            now(),
+            now,
         ))
     } else if exceeds_backoff_limit || past_backoff_limit_on_failure(job, pods) {
         // check if the number of pod restart exceeds backoff (for restart OnFailure only)
@@ -170,21 +182,19 @@ fn reconcile(
             ConditionStatus::True,
             JOB_REASON_BACKOFF_LIMIT_EXCEEDED.to_owned(),
             "Job has reached the specified backoff limit".to_owned(),
-            now(),
+            now,
         ))
-    } else if past_active_deadline(job) {
+    } else if past_active_deadline(job, now) {
         Some(new_condition(
             JobConditionType::Failed,
             ConditionStatus::True,
             JOB_REASON_DEADLINE_EXCEEDED.to_owned(),
             "Job was active longer than specified deadline".to_owned(),
-            now(),
+            now,
         ))
-    } else if job.spec.active_deadline_seconds.is_some() && !job.spec.suspend {
-        // let sync_duration = job.spec.active_deadline_seconds - (now() - job.status.start_time);
-        // TODO: requeue
-        todo!()
     } else {
+        // THEMELIOS: the job isn't requeued for when its deadline passes, the controller is
+        // stepped again once the clock has advanced
         None
     };
 
@@ -245,7 +255,7 @@ fn reconcile(
                 ConditionStatus::True,
                 String::new(),
                 String::new(),
-                now(),
+                now,
             ));
         } else if manage_job_called {
             debug!("Manage job called");
@@ -260,7 +270,7 @@ fn reconcile(
                     ConditionStatus::True,
                     "JobSuspended".to_owned(),
                     "Job suspended".to_owned(),
-                    now(),
+                    now,
                 ) {
                     job.status.conditions = new_conditions;
                     debug!("Suspend condition changed");
@@ -274,7 +284,7 @@ fn reconcile(
                     ConditionStatus::False,
                     "JobResumed".to_owned(),
                     "Job resumed".to_owned(),
-                    now(),
+                    now,
                 ) {
                     job.status.conditions = new_conditions;
                     debug!("Suspend condition changed");
@@ -285,7 +295,7 @@ fn reconcile(
                     // consistent with resuming a Job created in the suspended state.
                     // (ActiveDeadlineSeconds is interpreted as the number of seconds a
                     // Job is continuously active.)
-                    job.status.start_time = Some(now());
+                    job.status.start_time = Some(now);
                 }
             }
         }
@@ -312,6 +322,7 @@ fn reconcile(
         succeeded_indexes,
         prev_succeeded_indexes,
         finished_condition,
+        now,
     )
 }
 
@@ -616,19 +627,39 @@ fn past_backoff_limit_on_failure(job: &Job, pods: &[&Pod]) -> bool {
 // pastActiveDeadline checks if job has ActiveDeadlineSeconds field set and if
 // it is exceeded. If the job is currently suspended, the function will always
 // return false.
-fn past_active_deadline(job: &Job) -> bool {
+pub(crate) fn past_active_deadline(job: &Job, now: Time) -> bool {
     if job.spec.active_deadline_seconds.is_none()
         || job.status.start_time.is_none()
         || job.spec.suspend
     {
         return false;
     }
-    let duration = job.status.start_time.unwrap().0 - now().0;
+    let duration = now.0 - job.status.start_time.unwrap().0;
     let allowed_duration =
         Duration::from_secs(job.spec.active_deadline_seconds.unwrap_or_default());
     duration >= allowed_duration
 }
 
+/// Whether any unfinished job is running towards an active deadline that is still to come, so
+/// advancing the clock can fail it.
+pub fn active_deadlines_pending(view: &RawState) -> bool {
+    view.jobs.iter().any(|job| {
+        job.spec.active_deadline_seconds.is_some()
+            && job.status.start_time.is_some()
+            && !job.spec.suspend
+            && !is_job_finished(job)
+            && !past_active_deadline(job, view.now())
+    })
+}
+
+/// Whether the job has either completed or failed.
+pub fn is_job_finished(job: &Job) -> bool {
+    job.status.conditions.iter().any(|c| {
+        (c.r#type == JobConditionType::Complete || c.r#type == JobConditionType::Failed)
+            && c.status == ConditionStatus::True
+    })
+}
+
 // calculateSucceededIndexes returns the old and new list of succeeded indexes
 // in compressed format (intervals).
 // The old list is solely based off .status.completedIndexes, but returns an
@@ -731,6 +762,7 @@ fn track_job_status_and_remove_finalizers(
     mut succeeded_indexes: OrderedIntervals,
     prev_succeeded_indexes: OrderedIntervals,
     mut finished_condition: Option<JobCondition>,
+    now: Time,
 ) -> OptionalJobControllerAction {
     let is_indexed = job.spec.completion_mode == JobCompletionMode::Indexed;
 
@@ -869,7 +901,7 @@ fn track_job_status_and_remove_finalizers(
         // It is also used in the enactJobFinished function for reporting.
         finished_condition = Some(new_failed_condition_for_failure_target(
             &finished_condition.unwrap(),
-            now(),
+            now,
         ));
     }
 
@@ -885,7 +917,7 @@ fn track_job_status_and_remove_finalizers(
     }
 
     let job_finished =
-        !reached_max_uncounted_pods && enact_job_finished(&mut job.status, finished_condition);
+        !reached_max_uncounted_pods && enact_job_finished(&mut job.status, finished_condition, now);
     if job_finished {
         debug!("needs flush job finished");
         needs_flush = true;
@@ -1235,14 +1267,18 @@ fn remove_tracking_finalizer_patch(pod: &Pod) -> OptionalJobControllerAction {
         return None.into();
     }
 
-    let mut pod = pod.clone();
-    pod.metadata
-        .finalizers
-        .retain(|f| f != JOB_TRACKING_FINALIZER);
-
-    // TODO: this should be a patch operation
+    let patch = Patch::strategic_merge(json!({
+        "metadata": {
+            "$deleteFromPrimitiveList/finalizers": [JOB_TRACKING_FINALIZER],
+        },
+    }));
     debug!(pod = pod.metadata.name, "Removing tracking finalizer");
-    Some(JobControllerAction::UpdatePod(pod)).into()
+    Some(JobControllerAction::PatchPod(
+        pod.metadata.namespace.clone(),
+        pod.metadata.name.clone(),
+        patch,
+    ))
+    .into()
 }
 
 fn create_pod_with_generate_name(
@@ -1300,7 +1336,10 @@ fn add_completion_index_env_variable(container: &mut Container) {
     {
         return;
     }
-    let field_path = format!("metadata.labels['{}']", JOB_COMPLETION_INDEX_ANNOTATION);
+    let field_path = format!(
+        "metadata.annotations['{}']",
+        JOB_COMPLETION_INDEX_ANNOTATION
+    );
     container.env.push(EnvVar {
         name: JOB_COMPLETION_INDEX_ENV_NAME.to_owned(),
         value: None,
@@ -1309,6 +1348,7 @@ fn add_completion_index_env_variable(container: &mut Container) {
                 field_path,
                 api_version: None,
             }),
+            ..Default::default()
         }),
     })
 }
@@ -1372,6 +1412,7 @@ fn count_terminating_pods(pods: &[&Pod]) -> usize {
 fn enact_job_finished(
     job_status: &mut JobStatus,
     finished_condition: Option<JobCondition>,
+    now: Time,
 ) -> bool {
     if let Some(fc) = finished_condition {
         let uncounted = &job_status.uncounted_terminated_pods;
@@ -1385,7 +1426,7 @@ fn enact_job_finished(
             fc.status,
             fc.reason,
             fc.message,
-            now(),
+            now,
         );
         job_status.conditions = conditions.unwrap_or_default();
         if fc.r#type == JobConditionType::Complete {
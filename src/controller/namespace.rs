@@ -0,0 +1,220 @@
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    resources::{
+        ConfigMap, ControllerRevision, CronJob, Deployment, Endpoints, HorizontalPodAutoscaler,
+        Job, Namespace, PersistentVolumeClaim, Pod, ReplicaSet, ReplicationController, Secret,
+        Service, StatefulSet,
+    },
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::Controller;
+
+/// The namespace controller, removing the contents of namespaces that are being deleted before
+/// finalizing them.
+#[derive(Clone, Debug)]
+pub struct NamespaceController;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct NamespaceControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug)]
+pub enum NamespaceControllerAction {
+    DeleteCronJob(CronJob),
+    DeleteHorizontalPodAutoscaler(HorizontalPodAutoscaler),
+    DeleteDeployment(Deployment),
+    DeleteStatefulSet(StatefulSet),
+    DeleteJob(Job),
+    DeleteReplicaSet(ReplicaSet),
+    DeleteReplicationController(ReplicationController),
+    DeleteControllerRevision(ControllerRevision),
+    DeletePersistentVolumeClaim(PersistentVolumeClaim),
+    DeleteService(Service),
+    DeleteEndpoints(Endpoints),
+    DeleteConfigMap(ConfigMap),
+    DeleteSecret(Secret),
+    DeletePod(Pod),
+
+    FinalizeNamespace(Namespace),
+}
+
+impl From<NamespaceControllerAction> for ControllerAction {
+    fn from(value: NamespaceControllerAction) -> Self {
+        match value {
+            NamespaceControllerAction::DeleteCronJob(cj) => ControllerAction::DeleteCronJob(cj),
+            NamespaceControllerAction::DeleteHorizontalPodAutoscaler(hpa) => {
+                ControllerAction::DeleteHorizontalPodAutoscaler(hpa)
+            }
+            NamespaceControllerAction::DeleteDeployment(dep) => {
+                ControllerAction::DeleteDeployment(dep)
+            }
+            NamespaceControllerAction::DeleteStatefulSet(sts) => {
+                ControllerAction::DeleteStatefulSet(sts)
+            }
+            NamespaceControllerAction::DeleteJob(job) => ControllerAction::DeleteJob(job),
+            NamespaceControllerAction::DeleteReplicaSet(rs) => {
+                ControllerAction::DeleteReplicaSet(rs)
+            }
+            NamespaceControllerAction::DeleteReplicationController(rc) => {
+                ControllerAction::DeleteReplicationController(rc)
+            }
+            NamespaceControllerAction::DeleteControllerRevision(cr) => {
+                ControllerAction::DeleteControllerRevision(cr)
+            }
+            NamespaceControllerAction::DeletePersistentVolumeClaim(pvc) => {
+                ControllerAction::DeletePersistentVolumeClaim(pvc)
+            }
+            NamespaceControllerAction::DeleteService(service) => {
+                ControllerAction::DeleteService(service)
+            }
+            NamespaceControllerAction::DeleteEndpoints(endpoints) => {
+                ControllerAction::DeleteEndpoints(endpoints)
+            }
+            NamespaceControllerAction::DeleteConfigMap(config_map) => {
+                ControllerAction::DeleteConfigMap(config_map)
+            }
+            NamespaceControllerAction::DeleteSecret(secret) => {
+                ControllerAction::DeleteSecret(secret)
+            }
+            NamespaceControllerAction::DeletePod(pod) => ControllerAction::SoftDeletePod(pod),
+            NamespaceControllerAction::FinalizeNamespace(ns) => {
+                ControllerAction::FinalizeNamespace(ns)
+            }
+        }
+    }
+}
+
+impl Controller for NamespaceController {
+    type State = NamespaceControllerState;
+
+    type Action = NamespaceControllerAction;
+
+    // https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/namespace/deletion/namespaced_resources_deleter.go
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        for namespace in global_state.namespaces.iter() {
+            if namespace.metadata.deletion_timestamp.is_none() {
+                continue;
+            }
+            if let Some(op) = delete_contents(namespace, global_state) {
+                return Some(op);
+            }
+            if namespace_empty(&namespace.metadata.name, global_state)
+                && namespace
+                    .spec
+                    .finalizers
+                    .iter()
+                    .any(|f| f == Namespace::FINALIZER)
+            {
+                debug!(
+                    namespace = namespace.metadata.name,
+                    "Finalizing empty namespace"
+                );
+                let mut namespace = namespace.clone();
+                namespace
+                    .spec
+                    .finalizers
+                    .retain(|f| f != Namespace::FINALIZER);
+                return Some(NamespaceControllerAction::FinalizeNamespace(namespace));
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "Namespace".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+/// Delete the next resource in the namespace, workloads first so that they stop creating more.
+///
+/// Pods are only marked for deletion, removing them is left to their node.
+fn delete_contents(namespace: &Namespace, view: &StateView) -> Option<NamespaceControllerAction> {
+    let ns = &namespace.metadata.name;
+    macro_rules! delete_first {
+        ($kind:ident, $action:expr) => {
+            if let Some(res) = view.$kind.in_namespace(ns).next() {
+                return Some($action(res.clone()));
+            }
+        };
+    }
+    delete_first!(cronjobs, NamespaceControllerAction::DeleteCronJob);
+    delete_first!(
+        horizontal_pod_autoscalers,
+        NamespaceControllerAction::DeleteHorizontalPodAutoscaler
+    );
+    delete_first!(deployments, NamespaceControllerAction::DeleteDeployment);
+    delete_first!(statefulsets, NamespaceControllerAction::DeleteStatefulSet);
+    delete_first!(jobs, NamespaceControllerAction::DeleteJob);
+    delete_first!(replicasets, NamespaceControllerAction::DeleteReplicaSet);
+    delete_first!(
+        replication_controllers,
+        NamespaceControllerAction::DeleteReplicationController
+    );
+    delete_first!(
+        controller_revisions,
+        NamespaceControllerAction::DeleteControllerRevision
+    );
+    delete_first!(
+        persistent_volume_claims,
+        NamespaceControllerAction::DeletePersistentVolumeClaim
+    );
+    delete_first!(services, NamespaceControllerAction::DeleteService);
+    delete_first!(endpoints, NamespaceControllerAction::DeleteEndpoints);
+    delete_first!(config_maps, NamespaceControllerAction::DeleteConfigMap);
+    delete_first!(secrets, NamespaceControllerAction::DeleteSecret);
+    view.pods
+        .in_namespace(ns)
+        .find(|p| p.metadata.deletion_timestamp.is_none())
+        .map(|p| NamespaceControllerAction::DeletePod(p.clone()))
+}
+
+/// Whether no resources remain in the namespace.
+pub fn namespace_empty(namespace: &str, view: &RawState) -> bool {
+    view.pods.in_namespace(namespace).next().is_none()
+        && view.replicasets.in_namespace(namespace).next().is_none()
+        && view
+            .replication_controllers
+            .in_namespace(namespace)
+            .next()
+            .is_none()
+        && view.deployments.in_namespace(namespace).next().is_none()
+        && view.statefulsets.in_namespace(namespace).next().is_none()
+        && view
+            .controller_revisions
+            .in_namespace(namespace)
+            .next()
+            .is_none()
+        && view
+            .persistent_volume_claims
+            .in_namespace(namespace)
+            .next()
+            .is_none()
+        && view.services.in_namespace(namespace).next().is_none()
+        && view.endpoints.in_namespace(namespace).next().is_none()
+        && view.config_maps.in_namespace(namespace).next().is_none()
+        && view.secrets.in_namespace(namespace).next().is_none()
+        && view.jobs.in_namespace(namespace).next().is_none()
+        && view.cronjobs.in_namespace(namespace).next().is_none()
+        && view
+            .horizontal_pod_autoscalers
+            .in_namespace(namespace)
+            .next()
+            .is_none()
+}
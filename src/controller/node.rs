@@ -3,30 +3,166 @@ use std::collections::BTreeMap;
 use crate::abstract_model::ControllerAction;
 use crate::controller::Controller;
 use crate::resources::{
-    ConditionStatus, ContainerState, ContainerStateRunning, ContainerStateTerminated,
-    ContainerStateWaiting, ContainerStatus, Pod, PodCondition, PodConditionType, PodPhase,
-    ResourceQuantities,
+    ConditionStatus, Container, ContainerState, ContainerStateRunning, ContainerStateTerminated,
+    ContainerStateWaiting, ContainerStatus, Lease, LeaseSpec, Metadata, Node, NodeCondition,
+    NodeConditionType, Pod, PodCondition, PodConditionType, PodPhase, PodRestartPolicy,
+    ResourceQuantities, Time,
 };
 use crate::state::revision::Revision;
 use crate::state::StateView;
-use crate::utils::now;
 
-use super::util::is_pod_active;
+use super::util::{get_node_condition, is_pod_active};
+
+/// The namespace that nodes keep the leases that they renew as heartbeats in.
+pub const NODE_LEASE_NAMESPACE: &str = "kube-node-lease";
+
+/// The reason that containers are waiting while they back off from being restarted.
+pub const CRASH_LOOP_BACK_OFF: &str = "CrashLoopBackOff";
+
+/// The reason that containers are waiting when pulling their images has just failed.
+pub const ERR_IMAGE_PULL: &str = "ErrImagePull";
+
+/// The reason that containers are waiting while they back off from pulling their images again.
+pub const IMAGE_PULL_BACK_OFF: &str = "ImagePullBackOff";
 
 #[derive(Clone, Debug)]
 pub struct NodeController {
     pub name: String,
+    /// The number of times that the containers of a pod are restarted after exiting, after which
+    /// they are left backing off so that restarts don't grow the state space forever.
+    pub max_restarts: u32,
+    /// The duration of the lease that the node renews as its heartbeat, none if it doesn't
+    /// heartbeat.
+    pub lease_duration_seconds: Option<u64>,
+    /// Whether the node pulls the images of pods before starting them, the pulls possibly
+    /// failing.
+    pub image_pulls: bool,
 }
 
 #[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
 pub struct NodeControllerState {
     pub running: BTreeMap<String, ContainerState>,
+    /// The state of the init container currently running for each pod that is initializing.
+    pub initializing: BTreeMap<String, ContainerState>,
+    /// The latest probe results for the running containers, by pod and container name.
+    pub probes: BTreeMap<(String, String), ProbeResults>,
+    /// The number of times the containers of each pod have been restarted after exiting.
+    pub restarts: BTreeMap<String, u32>,
+    /// How pulling the images of each pod is going.
+    pub image_pulls: BTreeMap<String, ImagePull>,
+    /// The environment of each container that has been started, by pod and container name, as it
+    /// was resolved when the container started.
+    pub environments: BTreeMap<(String, String), BTreeMap<String, String>>,
     revision: Option<Revision>,
 }
 
+/// The outcomes of the probes of a container, each none when the container has no such probe.
+///
+/// THEMELIOS: Outcomes are only those that have crossed their thresholds, intermediate results
+/// are not modelled.
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct ProbeResults {
+    /// Whether the startup probe has succeeded.
+    pub startup: Option<bool>,
+    /// Whether the readiness probe is succeeding.
+    pub readiness: Option<bool>,
+    /// Whether the liveness probe is succeeding.
+    pub liveness: Option<bool>,
+}
+
+impl ProbeResults {
+    /// The results for a freshly started container: not yet started or ready until those probes
+    /// succeed, but live.
+    pub fn new(container: &Container) -> Self {
+        Self {
+            startup: container.startup_probe.as_ref().map(|_| false),
+            readiness: container.readiness_probe.as_ref().map(|_| false),
+            liveness: container.liveness_probe.as_ref().map(|_| true),
+        }
+    }
+
+    /// Whether the container has started, liveness and readiness are only probed after this.
+    pub fn started(&self) -> bool {
+        self.startup.unwrap_or(true)
+    }
+
+    pub fn ready(&self) -> bool {
+        self.started() && self.readiness.unwrap_or(true)
+    }
+
+    /// Whether the kubelet kills the container for it to be restarted.
+    pub fn failed(&self) -> bool {
+        self.liveness == Some(false)
+    }
+
+    /// The results that the probes could change to next.
+    fn next(&self) -> Vec<Self> {
+        let mut next = Vec::new();
+        if !self.started() {
+            // a startup probe either succeeds or fails enough to have the container killed
+            next.push(Self {
+                startup: Some(true),
+                ..self.clone()
+            });
+            next.push(Self {
+                liveness: Some(false),
+                ..self.clone()
+            });
+            return next;
+        }
+        if let Some(ready) = self.readiness {
+            next.push(Self {
+                readiness: Some(!ready),
+                ..self.clone()
+            });
+        }
+        if self.liveness == Some(true) {
+            next.push(Self {
+                liveness: Some(false),
+                ..self.clone()
+            });
+        }
+        next
+    }
+}
+
+/// How pulling the images of a pod is going.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ImagePull {
+    /// The images are being pulled.
+    Pulling,
+    /// Pulling the images failed, permanently if they can never be pulled.
+    Failed { permanent: bool },
+    /// Waiting to retry pulling the images after a failure.
+    BackOff { permanent: bool },
+    /// The images have been pulled.
+    Pulled,
+}
+
+impl ImagePull {
+    /// The states that the pull could move on to next.
+    fn next(self) -> Vec<Self> {
+        match self {
+            ImagePull::Pulling => vec![
+                ImagePull::Pulled,
+                ImagePull::Failed { permanent: false },
+                ImagePull::Failed { permanent: true },
+            ],
+            ImagePull::BackOff { permanent: false } => vec![ImagePull::Pulling],
+            ImagePull::Failed { .. }
+            | ImagePull::BackOff { permanent: true }
+            | ImagePull::Pulled => Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum NodeControllerAction {
     NodeJoin(String, ResourceQuantities),
+    UpdateNode(Node),
+
+    CreateLease(Lease),
+    RenewLease(Lease),
 
     UpdatePod(Pod),
     DeletePod(Pod),
@@ -36,6 +172,9 @@ impl From<NodeControllerAction> for ControllerAction {
     fn from(val: NodeControllerAction) -> Self {
         match val {
             NodeControllerAction::NodeJoin(id, q) => ControllerAction::NodeJoin(id, q),
+            NodeControllerAction::UpdateNode(node) => ControllerAction::UpdateNode(node),
+            NodeControllerAction::CreateLease(lease) => ControllerAction::CreateLease(lease),
+            NodeControllerAction::RenewLease(lease) => ControllerAction::UpdateLease(lease),
             NodeControllerAction::UpdatePod(pod) => ControllerAction::UpdatePod(pod),
             NodeControllerAction::DeletePod(pod) => ControllerAction::HardDeletePod(pod),
         }
@@ -53,7 +192,12 @@ impl Controller for NodeController {
         local_state: &mut Self::State,
     ) -> Option<NodeControllerAction> {
         local_state.revision = Some(global_state.revision.clone());
-        if let Some(_node) = global_state.nodes.get(&self.name) {
+        if let Some(node) = global_state.nodes.get(&self.name) {
+            if let Some(duration) = self.lease_duration_seconds {
+                if let Some(op) = self.heartbeat(node, global_state, duration) {
+                    return Some(op);
+                }
+            }
             let pods_for_this_node = global_state
                 .pods
                 .iter()
@@ -62,82 +206,91 @@ impl Controller for NodeController {
 
             for pod in pods_for_this_node {
                 if is_pod_active(pod) {
-                    if !local_state.running.contains_key(&pod.metadata.name) {
+                    let name = &pod.metadata.name;
+                    if !local_state.running.contains_key(name)
+                        && !local_state.initializing.contains_key(name)
+                    {
+                        // about to start the pod, which needs all of the config it refers to
+                        if let Some(waiting) = missing_config(pod, global_state) {
+                            if let Some(new_pod) = wait_for_config(pod, waiting) {
+                                return Some(NodeControllerAction::UpdatePod(new_pod));
+                            }
+                            continue;
+                        }
+                        if self.image_pulls {
+                            if let Some(update) = pull_images(pod, local_state) {
+                                if let Some(new_pod) = update {
+                                    return Some(NodeControllerAction::UpdatePod(new_pod));
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    if !is_initialized(pod) {
+                        if let Some(new_pod) =
+                            run_init_containers(pod, local_state, global_state.now())
+                        {
+                            return Some(NodeControllerAction::UpdatePod(new_pod));
+                        }
+                    } else if !local_state.running.contains_key(&pod.metadata.name) {
                         let cs = ContainerState::Running(ContainerStateRunning {
-                            started_at: Some(now()),
+                            started_at: Some(global_state.now()),
                         });
                         local_state
                             .running
                             .insert(pod.metadata.name.clone(), cs.clone());
                         let mut new_pod = pod.clone();
                         new_pod.status.container_statuses.clear();
+                        record_environments(local_state, pod, &pod.spec.containers);
                         for c in &new_pod.spec.containers {
+                            let probes = ProbeResults::new(c);
                             new_pod.status.container_statuses.push(ContainerStatus {
                                 name: c.name.clone(),
                                 state: cs.clone(),
                                 last_state: ContainerState::Waiting(
                                     ContainerStateWaiting::default(),
                                 ),
-                                ready: true,
+                                ready: probes.ready(),
                                 image: c.image.clone(),
-                                started: true,
+                                started: probes.started(),
                                 ..Default::default()
-                            })
+                            });
+                            local_state
+                                .probes
+                                .insert((pod.metadata.name.clone(), c.name.clone()), probes);
                         }
                         new_pod.status.phase = PodPhase::Running;
                         return Some(NodeControllerAction::UpdatePod(new_pod));
                     } else {
                         // already running it, monitor it
-                        let mut new_pod = pod.clone();
-                        if pod.status.container_statuses.iter().any(|cs| {
-                            matches!(
-                                cs.state,
-                                ContainerState::Terminated(ContainerStateTerminated { exit_code, .. }) if exit_code > 0
-                            )
-                        }) {
-                            new_pod.status.phase = PodPhase::Failed;
-                            new_pod.status.conditions.clear();
-                            local_state.running.remove(&pod.metadata.name);
-                            return Some(NodeControllerAction::UpdatePod(new_pod));
-                        } else if pod.status.container_statuses.iter().all(|cs| {
-                            matches!(
-                                cs.state,
-                                ContainerState::Terminated(ContainerStateTerminated {
-                                    exit_code: 0,
-                                    ..
-                                })
-                            )
-                        }) {
-                            new_pod.status.phase = PodPhase::Succeeded;
-                            new_pod.status.conditions.clear();
-                            local_state.running.remove(&pod.metadata.name);
-                            return Some(NodeControllerAction::UpdatePod(new_pod));
-                        }else if pod.status.phase == PodPhase::Running
-                            && !new_pod.status.conditions.iter().any(|c| {
-                                c.r#type == PodConditionType::Ready
-                                    && c.status == ConditionStatus::True
-                            })
-                        {
-                            // TODO: should have an arbitrary action to mark pods running, then this relies on
-                            // that.
-                            new_pod.status.conditions.push(PodCondition {
-                                status: ConditionStatus::True,
-                                r#type: PodConditionType::Ready,
-                                last_probe_time: None,
-                                last_transition_time: None,
-                                message: None,
-                                reason: None,
-                            });
+                        let now = global_state.now();
+                        let new_pod = match local_state.running.get(&pod.metadata.name).cloned() {
+                            Some(ContainerState::Terminated(terminated)) => {
+                                Some(exit_containers(pod, local_state, terminated, now))
+                            }
+                            Some(ContainerState::Running(_)) if is_backing_off(pod) => {
+                                Some(restart_containers(pod, local_state, now))
+                            }
+                            _ if pod.status.phase == PodPhase::Running => {
+                                apply_probe_results(pod, local_state, now)
+                            }
+                            _ => None,
+                        };
+                        if let Some(new_pod) = new_pod {
                             return Some(NodeControllerAction::UpdatePod(new_pod));
                         }
                     }
                 } else if pod.metadata.deletion_timestamp.is_some() {
-                    // TODO: ensure we mark containers as shutdown first?
-
-                    // pod has been marked for deletion and is running on this node, forget about
-                    // it locally and delete it for good in the API
-                    local_state.running.remove(&pod.metadata.name);
-                    return Some(NodeControllerAction::DeletePod(pod.clone()));
+                    // pod has been marked for deletion and is on this node, stop its containers
+                    // before deleting it for good in the API
+                    forget_pod(local_state, &pod.metadata.name);
+                    if let Some(new_pod) = stop_containers(pod, global_state.now()) {
+                        return Some(NodeControllerAction::UpdatePod(new_pod));
+                    }
+                    // already deleted, waiting on its finalizers
+                    if pod.metadata.deletion_grace_period_seconds != Some(0) {
+                        return Some(NodeControllerAction::DeletePod(pod.clone()));
+                    }
                 } else {
                     // suceeded or failed, not sure what to do here?
                 }
@@ -157,11 +310,16 @@ impl Controller for NodeController {
         let mut states = Vec::new();
         for (pod, state) in &local_state.running {
             match state {
+                // a container that is about to be restarted can't exit until the restart has
+                // been reported, when it gets its start time
+                ContainerState::Running(ContainerStateRunning { started_at: None }) => {}
                 ContainerState::Running(ContainerStateRunning { started_at }) => {
                     let term = ContainerStateTerminated {
                         exit_code: 0,
                         started_at: *started_at,
-                        finished_at: Some(now()),
+                        // the clock isn't known here, the finish time is filled in when the
+                        // termination is reported
+                        finished_at: None,
                         ..Default::default()
                     };
                     // a running container could fail
@@ -187,10 +345,61 @@ impl Controller for NodeController {
                 }
                 ContainerState::Terminated(_) => {}
                 ContainerState::Waiting(_) => {
-                    // TODO: move to running
+                    // the backoff could end, unless the containers have been restarted too often
+                    if local_state.restarts.get(pod).copied().unwrap_or_default()
+                        < self.max_restarts
+                    {
+                        let mut s = local_state.clone();
+                        s.running.insert(
+                            pod.clone(),
+                            ContainerState::Running(ContainerStateRunning { started_at: None }),
+                        );
+                        states.push(s);
+                    }
+                }
+            }
+        }
+        for (pod, state) in &local_state.initializing {
+            if let ContainerState::Running(ContainerStateRunning { started_at }) = state {
+                // a running init container could fail or succeed
+                for exit_code in [1, 0] {
+                    let mut s = local_state.clone();
+                    s.initializing.insert(
+                        pod.clone(),
+                        ContainerState::Terminated(ContainerStateTerminated {
+                            exit_code,
+                            started_at: *started_at,
+                            finished_at: None,
+                            ..Default::default()
+                        }),
+                    );
+                    states.push(s);
                 }
             }
         }
+        // pulls of images could finish, fail or be retried
+        for (pod, pull) in &local_state.image_pulls {
+            for next in pull.next() {
+                let mut s = local_state.clone();
+                s.image_pulls.insert(pod.clone(), next);
+                states.push(s);
+            }
+        }
+        // probes of running containers could change their outcomes
+        for (key, results) in &local_state.probes {
+            let running = matches!(
+                local_state.running.get(&key.0),
+                Some(ContainerState::Running(_))
+            );
+            if !running {
+                continue;
+            }
+            for next in results.next() {
+                let mut s = local_state.clone();
+                s.probes.insert(key.clone(), next);
+                states.push(s);
+            }
+        }
         states
     }
 
@@ -202,3 +411,571 @@ impl Controller for NodeController {
         state.revision.as_ref()
     }
 }
+
+impl NodeController {
+    /// Renew the node's lease once the clock has moved on since it was last renewed, and report
+    /// the node as ready again if it was marked otherwise while the kubelet was still running.
+    fn heartbeat(
+        &self,
+        node: &Node,
+        view: &StateView,
+        lease_duration_seconds: u64,
+    ) -> Option<NodeControllerAction> {
+        let now = view.now();
+        match view.leases.get_in(NODE_LEASE_NAMESPACE, &self.name) {
+            None => {
+                return Some(NodeControllerAction::CreateLease(Lease {
+                    metadata: Metadata {
+                        name: self.name.clone(),
+                        namespace: NODE_LEASE_NAMESPACE.to_owned(),
+                        ..Default::default()
+                    },
+                    spec: LeaseSpec {
+                        holder_identity: Some(self.name.clone()),
+                        lease_duration_seconds: Some(lease_duration_seconds),
+                        acquire_time: Some(now),
+                        renew_time: Some(now),
+                        lease_transitions: 0,
+                    },
+                }))
+            }
+            Some(lease) if lease.spec.renew_time.as_ref() != Some(&now) => {
+                let mut lease = lease.clone();
+                lease.spec.renew_time = Some(now);
+                return Some(NodeControllerAction::RenewLease(lease));
+            }
+            Some(_) => {}
+        }
+
+        let ready = get_node_condition(&node.status.conditions, NodeConditionType::Ready)
+            .map_or(false, |c| c.status == ConditionStatus::True);
+        if ready {
+            return None;
+        }
+        let mut node = node.clone();
+        node.status
+            .conditions
+            .retain(|c| c.r#type != NodeConditionType::Ready);
+        node.status.conditions.push(NodeCondition {
+            r#type: NodeConditionType::Ready,
+            status: ConditionStatus::True,
+            reason: "KubeletReady".to_owned(),
+            message: "kubelet is posting ready status".to_owned(),
+            last_transition_time: Some(now),
+            ..Default::default()
+        });
+        Some(NodeControllerAction::UpdateNode(node))
+    }
+}
+
+/// Stop tracking the containers of the pod, as once it has finished or been stopped.
+pub fn forget_pod(local_state: &mut NodeControllerState, pod_name: &str) {
+    local_state.running.remove(pod_name);
+    local_state.initializing.remove(pod_name);
+    local_state.probes.retain(|(pod, _), _| pod != pod_name);
+    local_state.restarts.remove(pod_name);
+    local_state.image_pulls.remove(pod_name);
+    local_state
+        .environments
+        .retain(|(pod, _), _| pod != pod_name);
+}
+
+/// The environment of the container in the pod, with the values of its variables that refer to
+/// fields of the pod resolved through the downward API.
+///
+/// THEMELIOS: Variables taken from config maps and secrets are left out, only whether they exist
+/// is modelled.
+pub fn environment(pod: &Pod, container: &Container) -> BTreeMap<String, String> {
+    container
+        .env
+        .iter()
+        .filter_map(|env| {
+            let value = match (&env.value, &env.value_from) {
+                (_, Some(source)) => pod.field_value(&source.field_ref.as_ref()?.field_path)?,
+                (value, None) => value.clone().unwrap_or_default(),
+            };
+            Some((env.name.clone(), value))
+        })
+        .collect()
+}
+
+/// Record the environments of the containers of the pod as they start.
+fn record_environments(local_state: &mut NodeControllerState, pod: &Pod, containers: &[Container]) {
+    for container in containers {
+        local_state.environments.insert(
+            (pod.metadata.name.clone(), container.name.clone()),
+            environment(pod, container),
+        );
+    }
+}
+
+/// Whether the pod's restart policy has its containers restarted after exiting with the code.
+fn restarts_after(pod: &Pod, exit_code: u32) -> bool {
+    match pod.spec.restart_policy.unwrap_or(PodRestartPolicy::Always) {
+        PodRestartPolicy::Always => true,
+        PodRestartPolicy::OnFailure => exit_code != 0,
+        PodRestartPolicy::Never => false,
+    }
+}
+
+/// Whether the containers of the pod are waiting out a backoff before being restarted.
+pub fn is_backing_off(pod: &Pod) -> bool {
+    pod.status.container_statuses.iter().any(
+        |cs| matches!(&cs.state, ContainerState::Waiting(w) if w.reason == CRASH_LOOP_BACK_OFF),
+    )
+}
+
+/// Report that the containers of the pod exited, either backing off to restart them if the pod's
+/// restart policy says to or finishing the pod with the exit code.
+///
+/// THEMELIOS: The containers of a pod exit together, and how long each backoff lasts is left to
+/// the model rather than doubling with every restart.
+fn exit_containers(
+    pod: &Pod,
+    local_state: &mut NodeControllerState,
+    mut terminated: ContainerStateTerminated,
+    now: Time,
+) -> Pod {
+    let name = &pod.metadata.name;
+    terminated.finished_at.get_or_insert(now);
+    terminated.reason = if terminated.exit_code == 0 {
+        "Completed"
+    } else {
+        "Error"
+    }
+    .to_owned();
+    let mut new_pod = pod.clone();
+    if !restarts_after(pod, terminated.exit_code) {
+        for status in &mut new_pod.status.container_statuses {
+            status.state = ContainerState::Terminated(terminated.clone());
+            status.ready = false;
+            status.started = false;
+        }
+        new_pod.status.phase = if terminated.exit_code == 0 {
+            PodPhase::Succeeded
+        } else {
+            PodPhase::Failed
+        };
+        new_pod.status.conditions.clear();
+        forget_pod(local_state, name);
+        return new_pod;
+    }
+
+    let waiting = ContainerState::Waiting(ContainerStateWaiting {
+        reason: CRASH_LOOP_BACK_OFF.to_owned(),
+        message: format!("back-off restarting failed container in pod {name}"),
+    });
+    for status in &mut new_pod.status.container_statuses {
+        status.last_state = ContainerState::Terminated(terminated.clone());
+        status.state = waiting.clone();
+        status.ready = false;
+        status.started = false;
+    }
+    local_state.running.insert(name.clone(), waiting);
+    local_state.probes.retain(|(pod, _), _| pod != name);
+    set_ready_condition(&mut new_pod, now);
+    new_pod
+}
+
+/// Restart the containers of the pod once their backoff has ended, as new containers with fresh
+/// probe results.
+fn restart_containers(pod: &Pod, local_state: &mut NodeControllerState, now: Time) -> Pod {
+    let name = &pod.metadata.name;
+    let running = ContainerState::Running(ContainerStateRunning {
+        started_at: Some(now),
+    });
+    let mut new_pod = pod.clone();
+    for status in &mut new_pod.status.container_statuses {
+        let Some(container) = pod.spec.containers.iter().find(|c| c.name == status.name) else {
+            continue;
+        };
+        let probes = ProbeResults::new(container);
+        status.state = running.clone();
+        status.restart_count += 1;
+        status.started = probes.started();
+        status.ready = probes.ready();
+        local_state
+            .probes
+            .insert((name.clone(), status.name.clone()), probes);
+    }
+    local_state.running.insert(name.clone(), running);
+    *local_state.restarts.entry(name.clone()).or_default() += 1;
+    set_ready_condition(&mut new_pod, now);
+    new_pod
+}
+
+/// Restart the containers of the pod that failed their probes and update the readiness of the
+/// containers and the pod from their probe results, none if the pod's status is already up to
+/// date.
+///
+/// THEMELIOS: Containers are restarted as if the restart policy were always, without any backoff.
+fn apply_probe_results(pod: &Pod, local_state: &mut NodeControllerState, now: Time) -> Option<Pod> {
+    let mut new_pod = pod.clone();
+    for status in &mut new_pod.status.container_statuses {
+        let key = (pod.metadata.name.clone(), status.name.clone());
+        let Some(results) = local_state.probes.get_mut(&key) else {
+            continue;
+        };
+        if results.failed() {
+            if let Some(container) = pod.spec.containers.iter().find(|c| c.name == status.name) {
+                *results = ProbeResults::new(container);
+            }
+            let started_at = match &status.state {
+                ContainerState::Running(running) => running.started_at,
+                _ => None,
+            };
+            status.last_state = ContainerState::Terminated(ContainerStateTerminated {
+                exit_code: 137,
+                reason: "Error".to_owned(),
+                started_at,
+                finished_at: Some(now),
+                ..Default::default()
+            });
+            status.state = ContainerState::Running(ContainerStateRunning {
+                started_at: Some(now),
+            });
+            status.restart_count += 1;
+        }
+        status.started = results.started();
+        status.ready = results.ready();
+    }
+    set_ready_condition(&mut new_pod, now);
+    (new_pod != *pod).then_some(new_pod)
+}
+
+/// Set the ready condition of the pod from whether all of its containers are ready.
+fn set_ready_condition(new_pod: &mut Pod, now: Time) {
+    let ready = if new_pod.status.container_statuses.iter().all(|cs| cs.ready) {
+        ConditionStatus::True
+    } else {
+        ConditionStatus::False
+    };
+    match new_pod
+        .status
+        .conditions
+        .iter_mut()
+        .find(|c| c.r#type == PodConditionType::Ready)
+    {
+        Some(condition) if condition.status == ready => {}
+        Some(condition) => {
+            condition.status = ready;
+            condition.last_transition_time = Some(now);
+        }
+        None => new_pod.status.conditions.push(PodCondition {
+            status: ready,
+            r#type: PodConditionType::Ready,
+            last_probe_time: None,
+            last_transition_time: Some(now),
+            message: None,
+            reason: None,
+        }),
+    }
+}
+
+/// Why the pod cannot be started, if it refers to a config map or secret (or a key of one) that
+/// does not exist and is not optional, or to a field of itself that the downward API doesn't
+/// expose.
+///
+/// THEMELIOS: References are all resolved before the pod starts rather than as each of its
+/// containers is created, so a missing reference also holds back the init containers.
+pub fn missing_config(pod: &Pod, view: &StateView) -> Option<ContainerStateWaiting> {
+    let namespace = &pod.metadata.namespace;
+    let config_map = |name: &str| view.config_maps.get_in(namespace, name);
+    let secret = |name: &str| view.secrets.get_in(namespace, name);
+    let required = |optional: Option<bool>| !optional.unwrap_or(false);
+    let not_found = |kind: &str, name: &str| format!("{kind} \"{name}\" not found");
+
+    for volume in &pod.spec.volumes {
+        let message = if let Some(source) = &volume.config_map {
+            (required(source.optional) && config_map(&source.name).is_none())
+                .then(|| not_found("configmap", &source.name))
+        } else if let Some(source) = &volume.secret {
+            (required(source.optional) && secret(&source.secret_name).is_none())
+                .then(|| not_found("secret", &source.secret_name))
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            return Some(ContainerStateWaiting {
+                reason: "ContainerCreating".to_owned(),
+                message: format!(
+                    "MountVolume.SetUp failed for volume {:?}: {message}",
+                    volume.name
+                ),
+            });
+        }
+    }
+
+    let config_error = |message: String| {
+        Some(ContainerStateWaiting {
+            reason: "CreateContainerConfigError".to_owned(),
+            message,
+        })
+    };
+    let containers = pod.spec.init_containers.iter().chain(&pod.spec.containers);
+    for container in containers {
+        for source in container
+            .env
+            .iter()
+            .filter_map(|env| env.value_from.as_ref())
+        {
+            if let Some(selector) = &source.field_ref {
+                if pod.field_value(&selector.field_path).is_none() {
+                    return config_error(format!("unsupported fieldPath: {}", selector.field_path));
+                }
+            }
+            if let Some(selector) = &source.config_map_key_ref {
+                if required(selector.optional) {
+                    match config_map(&selector.name) {
+                        None => return config_error(not_found("configmap", &selector.name)),
+                        Some(c) if !c.data.contains_key(&selector.key) => {
+                            return config_error(format!(
+                                "couldn't find key {} in ConfigMap {namespace}/{}",
+                                selector.key, selector.name
+                            ))
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            if let Some(selector) = &source.secret_key_ref {
+                if required(selector.optional) {
+                    match secret(&selector.name) {
+                        None => return config_error(not_found("secret", &selector.name)),
+                        Some(s) if !s.data.contains_key(&selector.key) => {
+                            return config_error(format!(
+                                "couldn't find key {} in Secret {namespace}/{}",
+                                selector.key, selector.name
+                            ))
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        for source in &container.env_from {
+            if let Some(source) = &source.config_map_ref {
+                if required(source.optional) && config_map(&source.name).is_none() {
+                    return config_error(not_found("configmap", &source.name));
+                }
+            }
+            if let Some(source) = &source.secret_ref {
+                if required(source.optional) && secret(&source.name).is_none() {
+                    return config_error(not_found("secret", &source.name));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Hold the containers of the pod in waiting until the config it needs exists.
+fn wait_for_config(pod: &Pod, waiting: ContainerStateWaiting) -> Option<Pod> {
+    let mut new_pod = pod.clone();
+    new_pod.status.phase = PodPhase::Pending;
+    new_pod.status.container_statuses = pod
+        .spec
+        .containers
+        .iter()
+        .map(|c| ContainerStatus {
+            name: c.name.clone(),
+            image: c.image.clone(),
+            state: ContainerState::Waiting(waiting.clone()),
+            ..Default::default()
+        })
+        .collect();
+    (new_pod != *pod).then_some(new_pod)
+}
+
+/// Pull the images of the pod before any of its containers start, none once they have been
+/// pulled and otherwise the update to the pod's status while waiting on them, if there is one.
+///
+/// THEMELIOS: The images of a pod are pulled together, and a failed pull is either retried until
+/// it succeeds or always fails again, as the model chooses.
+fn pull_images(pod: &Pod, local_state: &mut NodeControllerState) -> Option<Option<Pod>> {
+    let name = &pod.metadata.name;
+    let waiting = match local_state.image_pulls.get(name).copied() {
+        Some(ImagePull::Pulled) => return None,
+        None => {
+            local_state
+                .image_pulls
+                .insert(name.clone(), ImagePull::Pulling);
+            wait_for_images(pod, "ContainerCreating", |_| String::new())
+        }
+        // the status stays as it was while the images are pulled again
+        Some(ImagePull::Pulling) => None,
+        Some(ImagePull::Failed { permanent }) => {
+            local_state
+                .image_pulls
+                .insert(name.clone(), ImagePull::BackOff { permanent });
+            wait_for_images(pod, ERR_IMAGE_PULL, |image| {
+                format!("failed to pull image {image:?}")
+            })
+        }
+        Some(ImagePull::BackOff { .. }) => wait_for_images(pod, IMAGE_PULL_BACK_OFF, |image| {
+            format!("Back-off pulling image {image:?}")
+        }),
+    };
+    Some(waiting)
+}
+
+/// Hold the containers of the pod in waiting for their images, none if they are already waiting
+/// for the reason.
+fn wait_for_images(pod: &Pod, reason: &str, message: impl Fn(&str) -> String) -> Option<Pod> {
+    let mut new_pod = pod.clone();
+    new_pod.status.phase = PodPhase::Pending;
+    new_pod.status.container_statuses = pod
+        .spec
+        .containers
+        .iter()
+        .map(|c| ContainerStatus {
+            name: c.name.clone(),
+            image: c.image.clone(),
+            state: ContainerState::Waiting(ContainerStateWaiting {
+                reason: reason.to_owned(),
+                message: message(&c.image),
+            }),
+            ..Default::default()
+        })
+        .collect();
+    (new_pod != *pod).then_some(new_pod)
+}
+
+/// Whether all of the init containers of the pod have completed successfully.
+pub fn is_initialized(pod: &Pod) -> bool {
+    pod.status.init_container_statuses.len() == pod.spec.init_containers.len()
+        && pod
+            .status
+            .init_container_statuses
+            .iter()
+            .all(|cs| matches!(&cs.state, ContainerState::Terminated(t) if t.exit_code == 0))
+}
+
+/// Run the init containers of the pod one at a time, in order, before any of its containers start.
+///
+/// A failed init container is restarted unless the pod's restart policy is never, in which case
+/// the pod fails.
+fn run_init_containers(pod: &Pod, local_state: &mut NodeControllerState, now: Time) -> Option<Pod> {
+    let name = &pod.metadata.name;
+    let running = ContainerState::Running(ContainerStateRunning {
+        started_at: Some(now),
+    });
+    let mut new_pod = pod.clone();
+    let Some(state) = local_state.initializing.get(name).cloned() else {
+        // start the first init container, the rest wait for it
+        new_pod.status.phase = PodPhase::Pending;
+        new_pod.status.init_container_statuses = pod
+            .spec
+            .init_containers
+            .iter()
+            .enumerate()
+            .map(|(i, c)| ContainerStatus {
+                name: c.name.clone(),
+                state: if i == 0 {
+                    running.clone()
+                } else {
+                    ContainerState::default()
+                },
+                image: c.image.clone(),
+                ..Default::default()
+            })
+            .collect();
+        new_pod.status.container_statuses = pod
+            .spec
+            .containers
+            .iter()
+            .map(|c| ContainerStatus {
+                name: c.name.clone(),
+                image: c.image.clone(),
+                ..Default::default()
+            })
+            .collect();
+        set_initialized(&mut new_pod, ConditionStatus::False, now);
+        record_environments(local_state, pod, &pod.spec.init_containers);
+        local_state.initializing.insert(name.clone(), running);
+        return Some(new_pod);
+    };
+
+    let ContainerState::Terminated(mut terminated) = state else {
+        return None;
+    };
+    terminated.finished_at.get_or_insert(now);
+    let statuses = &mut new_pod.status.init_container_statuses;
+    let index = statuses
+        .iter()
+        .position(|cs| !matches!(&cs.state, ContainerState::Terminated(t) if t.exit_code == 0))?;
+    if terminated.exit_code == 0 {
+        statuses[index].state = ContainerState::Terminated(terminated);
+        if index + 1 < statuses.len() {
+            statuses[index + 1].state = running.clone();
+            local_state.initializing.insert(name.clone(), running);
+        } else {
+            // all done, the containers can start now
+            local_state.initializing.remove(name);
+            set_initialized(&mut new_pod, ConditionStatus::True, now);
+        }
+    } else if pod.spec.restart_policy == Some(PodRestartPolicy::Never) {
+        statuses[index].state = ContainerState::Terminated(terminated);
+        new_pod.status.phase = PodPhase::Failed;
+        forget_pod(local_state, name);
+    } else {
+        let status = &mut statuses[index];
+        status.last_state = ContainerState::Terminated(terminated);
+        status.state = running.clone();
+        status.restart_count += 1;
+        local_state.initializing.insert(name.clone(), running);
+    }
+    Some(new_pod)
+}
+
+fn set_initialized(pod: &mut Pod, status: ConditionStatus, now: Time) {
+    pod.status
+        .conditions
+        .retain(|c| c.r#type != PodConditionType::Initialized);
+    pod.status.conditions.push(PodCondition {
+        status,
+        r#type: PodConditionType::Initialized,
+        last_probe_time: None,
+        last_transition_time: Some(now),
+        message: None,
+        reason: None,
+    });
+}
+
+/// Stop the running containers of a terminating pod, none if they have all stopped already.
+///
+/// THEMELIOS: Containers always stop within their grace period, rather than being killed once it
+/// runs out.
+fn stop_containers(pod: &Pod, now: Time) -> Option<Pod> {
+    let mut new_pod = pod.clone();
+    let statuses = new_pod
+        .status
+        .init_container_statuses
+        .iter_mut()
+        .chain(new_pod.status.container_statuses.iter_mut());
+    let mut stopped = false;
+    for status in statuses {
+        if let ContainerState::Running(ContainerStateRunning { started_at }) = status.state {
+            status.state = ContainerState::Terminated(ContainerStateTerminated {
+                exit_code: 0,
+                reason: "Completed".to_owned(),
+                started_at,
+                finished_at: Some(now),
+                ..Default::default()
+            });
+            status.ready = false;
+            stopped = true;
+        }
+    }
+    if !stopped {
+        return None;
+    }
+    for condition in &mut new_pod.status.conditions {
+        if condition.r#type == PodConditionType::Ready && condition.status != ConditionStatus::False
+        {
+            condition.status = ConditionStatus::False;
+            condition.last_transition_time = Some(now);
+        }
+    }
+    Some(new_pod)
+}
@@ -0,0 +1,278 @@
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    node_faults,
+    resources::{
+        ConditionStatus, Node, NodeConditionType, Operator, Pod, PodConditionType, Taint,
+        TaintEffect, Time, Toleration,
+    },
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::{
+    node::NODE_LEASE_NAMESPACE,
+    util::{get_node_condition, is_pod_active},
+    Controller,
+};
+
+/// Taint added to nodes whose ready condition is false.
+pub const TAINT_NODE_NOT_READY: &str = "node.kubernetes.io/not-ready";
+
+/// Taint added to nodes whose ready condition is unknown, such as when the node stops reporting.
+pub const TAINT_NODE_UNREACHABLE: &str = "node.kubernetes.io/unreachable";
+
+/// The node lifecycle controller, along with its taint manager.
+///
+/// Nodes that are not ready get tainted based on their condition and pods that don't tolerate the
+/// NoExecute taints on their node are evicted, once any tolerations with a `toleration_seconds`
+/// have run out.
+///
+/// When given a grace period, nodes that have gone that long without renewing their lease have
+/// their ready condition marked as unknown first, and their pods as not ready.
+///
+/// THEMELIOS: Tolerations count down against the cluster clock, so the time until eviction only
+/// passes through explicit clock advances.
+#[derive(Clone, Debug, Default)]
+pub struct NodeLifecycleController {
+    /// Seconds after a node last renewed its lease that it is marked as not ready, none if nodes
+    /// don't heartbeat.
+    pub node_monitor_grace_period: Option<u64>,
+}
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct NodeLifecycleControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug)]
+pub enum NodeLifecycleControllerAction {
+    UpdateNode(Node),
+
+    UpdatePod(Pod),
+    EvictPod(Pod),
+}
+
+impl From<NodeLifecycleControllerAction> for ControllerAction {
+    fn from(value: NodeLifecycleControllerAction) -> Self {
+        match value {
+            NodeLifecycleControllerAction::UpdateNode(node) => ControllerAction::UpdateNode(node),
+            NodeLifecycleControllerAction::UpdatePod(pod) => ControllerAction::UpdatePod(pod),
+            NodeLifecycleControllerAction::EvictPod(pod) => ControllerAction::SoftDeletePod(pod),
+        }
+    }
+}
+
+impl Controller for NodeLifecycleController {
+    type State = NodeLifecycleControllerState;
+
+    type Action = NodeLifecycleControllerAction;
+
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        let now = global_state.now();
+        if let Some(grace) = self.node_monitor_grace_period {
+            for node in global_state.nodes.iter() {
+                if let Some(op) = monitor_node_health(node, global_state, grace) {
+                    return Some(op);
+                }
+            }
+        }
+        for node in global_state.nodes.iter() {
+            if let Some(op) = taint_node_by_condition(node, global_state) {
+                return Some(op);
+            }
+        }
+        for node in global_state.nodes.iter() {
+            for pod in global_state.pods_for_node(&node.metadata.name) {
+                if is_pod_active(pod) && !tolerates_no_execute_taints(pod, node, &now) {
+                    debug!(
+                        pod = pod.metadata.name,
+                        node = node.metadata.name,
+                        "Evicting pod that doesn't tolerate the node's taints"
+                    );
+                    return Some(NodeLifecycleControllerAction::EvictPod(pod.clone()));
+                }
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "NodeLifecycle".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+/// Whether the node has gone the grace period without renewing its lease.
+///
+/// THEMELIOS: Nodes are only monitored once they have created their lease.
+pub fn heartbeat_lost(node: &Node, view: &RawState, grace: u64) -> bool {
+    view.leases
+        .get_in(NODE_LEASE_NAMESPACE, &node.metadata.name)
+        .and_then(|lease| lease.spec.renew_time.as_ref())
+        .map_or(false, |renewed| {
+            renewed.0.unix_timestamp() as u64 + grace <= view.clock
+        })
+}
+
+/// Mark the ready condition of a node that has lost its heartbeat as unknown, then each of its
+/// ready pods as not ready.
+fn monitor_node_health(
+    node: &Node,
+    view: &StateView,
+    grace: u64,
+) -> Option<NodeLifecycleControllerAction> {
+    if !heartbeat_lost(node, view, grace) {
+        return None;
+    }
+    let status =
+        get_node_condition(&node.status.conditions, NodeConditionType::Ready).map(|c| &c.status);
+    if status != Some(&ConditionStatus::Unknown) {
+        debug!(node = node.metadata.name, "Node stopped renewing its lease");
+        return Some(NodeLifecycleControllerAction::UpdateNode(
+            node_faults::not_ready(node),
+        ));
+    }
+    for pod in view.pods_for_node(&node.metadata.name) {
+        let ready = pod.status.conditions.iter().position(|c| {
+            c.r#type == PodConditionType::Ready && c.status != ConditionStatus::False
+        });
+        if let Some(i) = ready {
+            let mut pod = pod.clone();
+            pod.status.conditions[i].status = ConditionStatus::False;
+            pod.status.conditions[i].last_transition_time = Some(view.now());
+            return Some(NodeLifecycleControllerAction::UpdatePod(pod));
+        }
+    }
+    None
+}
+
+/// Keep the lifecycle taints on the node in line with its ready condition.
+fn taint_node_by_condition(node: &Node, view: &StateView) -> Option<NodeLifecycleControllerAction> {
+    let desired = match get_node_condition(&node.status.conditions, NodeConditionType::Ready)
+        .map(|c| &c.status)
+    {
+        Some(ConditionStatus::True) => None,
+        Some(ConditionStatus::False) => Some(TAINT_NODE_NOT_READY),
+        Some(ConditionStatus::Unknown) | None => Some(TAINT_NODE_UNREACHABLE),
+    };
+
+    let is_lifecycle_taint = |t: &Taint| {
+        t.effect == TaintEffect::NoExecute
+            && (t.key == TAINT_NODE_NOT_READY || t.key == TAINT_NODE_UNREACHABLE)
+    };
+    let current = node
+        .spec
+        .taints
+        .iter()
+        .filter(|&t| is_lifecycle_taint(t))
+        .map(|t| t.key.as_str())
+        .collect::<Vec<_>>();
+    if current == desired.into_iter().collect::<Vec<_>>() {
+        return None;
+    }
+
+    let mut node = node.clone();
+    node.spec.taints.retain(|t| !is_lifecycle_taint(t));
+    if let Some(key) = desired {
+        node.spec.taints.push(Taint {
+            effect: TaintEffect::NoExecute,
+            key: key.to_owned(),
+            time_added: Some(view.now()),
+            value: String::new(),
+        });
+    }
+    Some(NodeLifecycleControllerAction::UpdateNode(node))
+}
+
+/// Whether the pod can keep running on the node at the given time, given its NoExecute taints.
+pub fn tolerates_no_execute_taints(pod: &Pod, node: &Node, now: &Time) -> bool {
+    no_execute_taints(node).all(|taint| match eviction_time(pod, taint) {
+        Eviction::Never => true,
+        Eviction::At(at) => now.0.unix_timestamp() < at,
+        Eviction::Now => false,
+    })
+}
+
+/// Whether any pod is tolerating a NoExecute taint only until some time still to come, so
+/// advancing the clock can lead to its eviction.
+pub fn evictions_pending(view: &RawState) -> bool {
+    let now = view.now().0.unix_timestamp();
+    view.nodes.iter().any(|node| {
+        view.pods_for_node(&node.metadata.name)
+            .into_iter()
+            .filter(|pod| is_pod_active(pod))
+            .any(|pod| {
+                no_execute_taints(node)
+                    .any(|taint| matches!(eviction_time(pod, taint), Eviction::At(at) if now < at))
+            })
+    })
+}
+
+/// When a pod gets evicted due to a taint on its node.
+enum Eviction {
+    Never,
+    /// The unix timestamp that the last of the tolerations of the taint run out.
+    At(i64),
+    Now,
+}
+
+fn no_execute_taints(node: &Node) -> impl Iterator<Item = &Taint> {
+    node.spec
+        .taints
+        .iter()
+        .filter(|t| t.effect == TaintEffect::NoExecute)
+}
+
+fn eviction_time(pod: &Pod, taint: &Taint) -> Eviction {
+    let mut tolerations = pod
+        .spec
+        .tolerations
+        .iter()
+        .filter(|toleration| tolerates(toleration, taint))
+        .peekable();
+    if tolerations.peek().is_none() {
+        return Eviction::Now;
+    }
+    // the longest toleration wins, one without a limit tolerates the taint forever
+    let Some(seconds) = tolerations
+        .map(|t| t.toleration_seconds)
+        .max_by_key(|s| s.map_or(u64::MAX, |s| s))
+        .flatten()
+    else {
+        return Eviction::Never;
+    };
+    // THEMELIOS: taints without a time added count from the start of the clock
+    let added = taint.time_added.map_or(0, |t| t.0.unix_timestamp());
+    Eviction::At(added + seconds as i64)
+}
+
+fn tolerates(toleration: &Toleration, taint: &Taint) -> bool {
+    if toleration
+        .effect
+        .as_ref()
+        .map_or(false, |effect| effect != &taint.effect)
+    {
+        return false;
+    }
+    match toleration.operator.as_ref().unwrap_or(&Operator::Equal) {
+        // an empty key with exists matches everything
+        Operator::Exists => toleration.key.is_empty() || toleration.key == taint.key,
+        Operator::Equal => {
+            toleration.key == taint.key
+                && toleration.value.as_deref().unwrap_or_default() == taint.value
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    resources::{
+        ClaimReference, Metadata, PersistentVolume, PersistentVolumeClaim,
+        PersistentVolumeClaimPhase, PersistentVolumeSpec, StorageClass,
+    },
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::{
+    pvbinder::{binding_delayed, find_matching_volume, ANNOTATION_SELECTED_NODE},
+    Controller,
+};
+
+/// Dynamic provisioning of volumes for claims that name a storage class.
+///
+/// Provisioned volumes are pre-bound to their claim, leaving the binder to finish the binding.
+/// Claims whose class waits for a consumer are only provisioned once the scheduler has selected
+/// a node, with the volume limited to that node.
+///
+/// THEMELIOS: A single provisioner stands in for the external provisioner of every class.
+#[derive(Clone, Debug)]
+pub struct ProvisionerController;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct ProvisionerControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug)]
+pub enum ProvisionerControllerAction {
+    CreatePersistentVolume(PersistentVolume),
+}
+
+impl From<ProvisionerControllerAction> for ControllerAction {
+    fn from(value: ProvisionerControllerAction) -> Self {
+        match value {
+            ProvisionerControllerAction::CreatePersistentVolume(pv) => {
+                ControllerAction::CreatePersistentVolume(pv)
+            }
+        }
+    }
+}
+
+impl Controller for ProvisionerController {
+    type State = ProvisionerControllerState;
+
+    type Action = ProvisionerControllerAction;
+
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        for pvc in global_state.persistent_volume_claims.iter() {
+            if let Some(class) = needs_provisioning(pvc, global_state) {
+                let pv = provision(pvc, class);
+                debug!(
+                    pv = pv.metadata.name,
+                    pvc = pvc.metadata.name,
+                    class = class.metadata.name,
+                    "Provisioning volume for claim"
+                );
+                return Some(ProvisionerControllerAction::CreatePersistentVolume(pv));
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "Provisioner".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+/// The name of the volume provisioned for the claim.
+pub fn provisioned_volume_name(pvc: &PersistentVolumeClaim) -> String {
+    format!("pvc-{}", pvc.metadata.uid)
+}
+
+/// The class to provision a volume for the claim with, if it needs one.
+fn needs_provisioning<'a>(
+    pvc: &PersistentVolumeClaim,
+    view: &'a RawState,
+) -> Option<&'a StorageClass> {
+    if pvc.metadata.deletion_timestamp.is_some()
+        || pvc.spec.volume_name.is_some()
+        || pvc.status.phase != PersistentVolumeClaimPhase::Pending
+    {
+        return None;
+    }
+    let class = view
+        .storage_classes
+        .get(pvc.spec.storage_class_name.as_ref()?)
+        .filter(|class| !class.provisioner.is_empty())?;
+    if binding_delayed(pvc, view)
+        || view.persistent_volumes.has(&provisioned_volume_name(pvc))
+        || find_matching_volume(pvc, view).is_some()
+    {
+        return None;
+    }
+    Some(class)
+}
+
+fn provision(pvc: &PersistentVolumeClaim, class: &StorageClass) -> PersistentVolume {
+    PersistentVolume {
+        metadata: Metadata {
+            name: provisioned_volume_name(pvc),
+            ..Default::default()
+        },
+        spec: PersistentVolumeSpec {
+            capacity: pvc.spec.resources.requests.clone().unwrap_or_default(),
+            access_modes: pvc.spec.access_modes.clone(),
+            claim_ref: Some(ClaimReference {
+                name: pvc.metadata.name.clone(),
+                namespace: pvc.metadata.namespace.clone(),
+                uid: pvc.metadata.uid.clone(),
+            }),
+            storage_class_name: Some(class.metadata.name.clone()),
+            node_affinity: pvc
+                .metadata
+                .annotations
+                .get(ANNOTATION_SELECTED_NODE)
+                .cloned(),
+        },
+        ..Default::default()
+    }
+}
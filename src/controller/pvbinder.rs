@@ -0,0 +1,306 @@
+use tracing::debug;
+
+use crate::{
+    abstract_model::ControllerAction,
+    resources::{
+        ClaimReference, PersistentVolume, PersistentVolumeClaim, PersistentVolumeClaimPhase,
+        PersistentVolumePhase, ResourceQuantities, VolumeBindingMode,
+    },
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::Controller;
+
+/// The name of the resource that volume capacity and claim requests are given in.
+const STORAGE: &str = "storage";
+
+/// Annotation on claims with delayed binding, set by the scheduler to the node it picked for the
+/// first pod using the claim.
+pub const ANNOTATION_SELECTED_NODE: &str = "volume.kubernetes.io/selected-node";
+
+/// The binding part of the persistent volume controller, matching claims to volumes.
+///
+/// Binding is done the same way round as in kubernetes: the volume gets its claim reference
+/// first, then the claim gets the volume name, with the phases of each following.
+///
+/// THEMELIOS: Volumes aren't provisioned dynamically and are always retained, so released
+/// volumes stay around without being reused.
+#[derive(Clone, Debug)]
+pub struct PersistentVolumeBinderController;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct PersistentVolumeBinderControllerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug)]
+pub enum PersistentVolumeBinderControllerAction {
+    UpdatePersistentVolume(PersistentVolume),
+    UpdatePersistentVolumeStatus(PersistentVolume),
+
+    UpdatePersistentVolumeClaim(PersistentVolumeClaim),
+    UpdatePersistentVolumeClaimStatus(PersistentVolumeClaim),
+}
+
+impl From<PersistentVolumeBinderControllerAction> for ControllerAction {
+    fn from(value: PersistentVolumeBinderControllerAction) -> Self {
+        match value {
+            PersistentVolumeBinderControllerAction::UpdatePersistentVolume(pv) => {
+                ControllerAction::UpdatePersistentVolume(pv)
+            }
+            PersistentVolumeBinderControllerAction::UpdatePersistentVolumeStatus(pv) => {
+                ControllerAction::UpdatePersistentVolumeStatus(pv)
+            }
+            PersistentVolumeBinderControllerAction::UpdatePersistentVolumeClaim(pvc) => {
+                ControllerAction::UpdatePersistentVolumeClaim(pvc)
+            }
+            PersistentVolumeBinderControllerAction::UpdatePersistentVolumeClaimStatus(pvc) => {
+                ControllerAction::UpdatePersistentVolumeClaimStatus(pvc)
+            }
+        }
+    }
+}
+
+impl Controller for PersistentVolumeBinderController {
+    type State = PersistentVolumeBinderControllerState;
+
+    type Action = PersistentVolumeBinderControllerAction;
+
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        for pv in global_state.persistent_volumes.iter() {
+            if let Some(op) = sync_volume(pv, global_state) {
+                return Some(op);
+            }
+        }
+        for pvc in global_state.persistent_volume_claims.iter() {
+            if let Some(op) = sync_claim(pvc, global_state) {
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "PersistentVolumeBinder".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+/// Keep the phase of the volume in line with the claim it references.
+fn sync_volume(
+    pv: &PersistentVolume,
+    view: &StateView,
+) -> Option<PersistentVolumeBinderControllerAction> {
+    let Some(claim_ref) = &pv.spec.claim_ref else {
+        return set_volume_phase(pv, PersistentVolumePhase::Available);
+    };
+    let claim = view
+        .persistent_volume_claims
+        .get_in(&claim_ref.namespace, &claim_ref.name)
+        .filter(|pvc| claim_ref_matches(claim_ref, pvc));
+    match claim {
+        // pre-bound by name, waiting for the claim to turn up
+        None if claim_ref.uid.is_empty() => set_volume_phase(pv, PersistentVolumePhase::Available),
+        None => set_volume_phase(pv, PersistentVolumePhase::Released),
+        Some(pvc) => match &pvc.spec.volume_name {
+            Some(name) if name != &pv.metadata.name => {
+                // the claim was bound to another volume, undo our half of the binding
+                debug!(
+                    pv = pv.metadata.name,
+                    pvc = pvc.metadata.name,
+                    "Unbinding volume from claim bound elsewhere"
+                );
+                let mut pv = pv.clone();
+                pv.spec.claim_ref = None;
+                Some(PersistentVolumeBinderControllerAction::UpdatePersistentVolume(pv))
+            }
+            _ if claim_ref.uid.is_empty() => None,
+            _ => set_volume_phase(pv, PersistentVolumePhase::Bound),
+        },
+    }
+}
+
+/// Bind the claim to a volume, or update its phase if it already is.
+fn sync_claim(
+    pvc: &PersistentVolumeClaim,
+    view: &StateView,
+) -> Option<PersistentVolumeBinderControllerAction> {
+    if pvc.metadata.deletion_timestamp.is_some() {
+        return None;
+    }
+    let Some(volume_name) = &pvc.spec.volume_name else {
+        // the volume gets bound first so finish the binding for any that already are
+        if let Some(pv) = view.persistent_volumes.iter().find(|pv| {
+            pv.spec
+                .claim_ref
+                .as_ref()
+                .map_or(false, |r| !r.uid.is_empty() && claim_ref_matches(r, pvc))
+        }) {
+            let mut pvc = pvc.clone();
+            pvc.spec.volume_name = Some(pv.metadata.name.clone());
+            return Some(PersistentVolumeBinderControllerAction::UpdatePersistentVolumeClaim(pvc));
+        }
+        if binding_delayed(pvc, view) {
+            return None;
+        }
+        let pv = find_matching_volume(pvc, view)?;
+        debug!(
+            pv = pv.metadata.name,
+            pvc = pvc.metadata.name,
+            "Binding volume to claim"
+        );
+        return Some(bind_volume(pv, pvc));
+    };
+
+    let Some(pv) = view.persistent_volumes.get(volume_name) else {
+        return set_claim_lost(pvc);
+    };
+    match &pv.spec.claim_ref {
+        // pre-bound to the volume by name
+        None => Some(bind_volume(pv, pvc)),
+        Some(r) if claim_ref_matches(r, pvc) => {
+            if r.uid.is_empty() {
+                Some(bind_volume(pv, pvc))
+            } else {
+                set_claim_bound(pvc, pv)
+            }
+        }
+        // the volume belongs to someone else
+        Some(_) => set_claim_lost(pvc),
+    }
+}
+
+/// The smallest available volume that satisfies the claim, preferring those pre-bound to it.
+pub fn find_matching_volume<'a>(
+    pvc: &PersistentVolumeClaim,
+    view: &'a RawState,
+) -> Option<&'a PersistentVolume> {
+    let requested = storage(pvc.spec.resources.requests.as_ref());
+    let storage_class = pvc.spec.storage_class_name.as_deref().unwrap_or_default();
+    let selected_node = pvc.metadata.annotations.get(ANNOTATION_SELECTED_NODE);
+    view.persistent_volumes
+        .iter()
+        .filter(|pv| pv.metadata.deletion_timestamp.is_none())
+        .filter(|pv| {
+            pv.spec
+                .claim_ref
+                .as_ref()
+                .map_or(true, |r| r.uid.is_empty() && claim_ref_matches(r, pvc))
+        })
+        .filter(|pv| {
+            pvc.spec
+                .access_modes
+                .iter()
+                .all(|mode| pv.spec.access_modes.contains(mode))
+        })
+        .filter(|pv| pv.spec.storage_class_name.as_deref().unwrap_or_default() == storage_class)
+        .filter(|pv| storage(Some(&pv.spec.capacity)) >= requested)
+        .filter(|pv| pvc.spec.selector.matches(&pv.metadata.labels))
+        .filter(|pv| match (&pv.spec.node_affinity, selected_node) {
+            (Some(node), Some(selected)) => node == selected,
+            _ => true,
+        })
+        .min_by_key(|pv| {
+            (
+                pv.spec.claim_ref.is_none(),
+                storage(Some(&pv.spec.capacity)),
+            )
+        })
+}
+
+/// Whether the claim's class waits for a consumer and the scheduler hasn't picked a node for one yet.
+pub fn binding_delayed(pvc: &PersistentVolumeClaim, view: &RawState) -> bool {
+    waits_for_first_consumer(pvc, view)
+        && !pvc
+            .metadata
+            .annotations
+            .contains_key(ANNOTATION_SELECTED_NODE)
+}
+
+/// Whether the claim's class delays binding until a pod using it is scheduled.
+pub fn waits_for_first_consumer(pvc: &PersistentVolumeClaim, view: &RawState) -> bool {
+    pvc.spec
+        .storage_class_name
+        .as_ref()
+        .and_then(|name| view.storage_classes.get(name))
+        .map_or(false, |class| {
+            class.volume_binding_mode == VolumeBindingMode::WaitForFirstConsumer
+        })
+}
+
+/// The amount of storage in the quantities, zero if none is given.
+///
+/// THEMELIOS: Invalid quantities would be rejected by the API server so are also counted as none.
+pub fn storage(quantities: Option<&ResourceQuantities>) -> u64 {
+    quantities
+        .and_then(|q| q.others.get(STORAGE))
+        .map_or(0, |q| q.value().unwrap_or_default())
+}
+
+/// Whether the reference is to the claim, references without a uid match any claim of that name.
+pub fn claim_ref_matches(claim_ref: &ClaimReference, pvc: &PersistentVolumeClaim) -> bool {
+    claim_ref.name == pvc.metadata.name
+        && claim_ref.namespace == pvc.metadata.namespace
+        && (claim_ref.uid.is_empty() || claim_ref.uid == pvc.metadata.uid)
+}
+
+fn bind_volume(
+    pv: &PersistentVolume,
+    pvc: &PersistentVolumeClaim,
+) -> PersistentVolumeBinderControllerAction {
+    let mut pv = pv.clone();
+    pv.spec.claim_ref = Some(ClaimReference {
+        name: pvc.metadata.name.clone(),
+        namespace: pvc.metadata.namespace.clone(),
+        uid: pvc.metadata.uid.clone(),
+    });
+    PersistentVolumeBinderControllerAction::UpdatePersistentVolume(pv)
+}
+
+fn set_volume_phase(
+    pv: &PersistentVolume,
+    phase: PersistentVolumePhase,
+) -> Option<PersistentVolumeBinderControllerAction> {
+    if pv.status.phase == phase {
+        return None;
+    }
+    let mut pv = pv.clone();
+    pv.status.phase = phase;
+    Some(PersistentVolumeBinderControllerAction::UpdatePersistentVolumeStatus(pv))
+}
+
+fn set_claim_bound(
+    pvc: &PersistentVolumeClaim,
+    pv: &PersistentVolume,
+) -> Option<PersistentVolumeBinderControllerAction> {
+    let mut bound = pvc.clone();
+    bound.status.phase = PersistentVolumeClaimPhase::Bound;
+    bound.status.capacity = pv.spec.capacity.clone();
+    bound.status.access_modes = pv.spec.access_modes.clone();
+    if &bound == pvc {
+        return None;
+    }
+    Some(PersistentVolumeBinderControllerAction::UpdatePersistentVolumeClaimStatus(bound))
+}
+
+/// Claims that were bound lose their volume, those that weren't stay pending.
+fn set_claim_lost(pvc: &PersistentVolumeClaim) -> Option<PersistentVolumeBinderControllerAction> {
+    if pvc.status.phase != PersistentVolumeClaimPhase::Bound {
+        return None;
+    }
+    let mut pvc = pvc.clone();
+    pvc.status.phase = PersistentVolumeClaimPhase::Lost;
+    Some(PersistentVolumeBinderControllerAction::UpdatePersistentVolumeClaimStatus(pvc))
+}
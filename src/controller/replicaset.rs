@@ -10,12 +10,11 @@ use crate::controller::util::new_controller_ref;
 use crate::controller::Controller;
 use crate::resources::ConditionStatus;
 use crate::resources::{
-    LabelSelector, Pod, PodConditionType, ReplicaSet, ReplicaSetCondition, ReplicaSetConditionType,
-    ReplicaSetStatus, Time,
+    GroupVersionKind, LabelSelector, Pod, PodConditionType, ReplicaSet, ReplicaSetCondition,
+    ReplicaSetConditionType, ReplicaSetStatus, Time,
 };
 use crate::state::revision::Revision;
 use crate::state::StateView;
-use crate::utils::now;
 
 use super::util;
 use super::util::get_pod_from_template;
@@ -65,8 +64,17 @@ impl Controller for ReplicaSetController {
     ) -> Option<Self::Action> {
         local_state.revision = Some(global_state.revision.clone());
         for replicaset in global_state.replicasets.iter() {
-            let pods = global_state.pods.iter().collect::<Vec<_>>();
-            if let Some(op) = reconcile(replicaset, &pods, &global_state.revision) {
+            let pods = global_state
+                .pods
+                .in_namespace(&replicaset.metadata.namespace)
+                .collect::<Vec<_>>();
+            if let Some(op) = reconcile(
+                replicaset,
+                &pods,
+                &global_state.revision,
+                global_state.now(),
+                &ReplicaSet::GVK,
+            ) {
                 return Some(op);
             }
         }
@@ -86,13 +94,17 @@ impl Controller for ReplicaSetController {
     }
 }
 
-fn reconcile(
+/// Reconcile the pods of a replicaset, or of another kind of resource that has been converted to
+/// one, setting the given kind in the owner references of its pods.
+pub(crate) fn reconcile(
     replicaset: &ReplicaSet,
     all_pods: &[&Pod],
     state_revision: &Revision,
+    now: Time,
+    owner_kind: &GroupVersionKind,
 ) -> Option<ReplicaSetControllerAction> {
     let filtered_pods = util::filter_active_pods(all_pods);
-    let filtered_pods = claim_pods(replicaset, &filtered_pods);
+    let filtered_pods = claim_pods(replicaset, &filtered_pods, owner_kind);
 
     let filtered_pods = match filtered_pods {
         ValOrOp::Resource(r) => r,
@@ -100,12 +112,12 @@ fn reconcile(
     };
 
     if replicaset.metadata.deletion_timestamp.is_none() {
-        if let Some(op) = manage_replicas(&filtered_pods, replicaset) {
+        if let Some(op) = manage_replicas(&filtered_pods, replicaset, owner_kind) {
             return Some(op);
         }
     }
 
-    let new_status = calculate_status(replicaset, &filtered_pods);
+    let new_status = calculate_status(replicaset, &filtered_pods, now);
     if let Some(op) = update_replicaset_status(replicaset, new_status, state_revision) {
         return Some(op);
     }
@@ -116,6 +128,7 @@ fn reconcile(
 fn claim_pods<'a>(
     replicaset: &ReplicaSet,
     filtered_pods: &[&'a Pod],
+    owner_kind: &GroupVersionKind,
 ) -> ValOrOp<Vec<&'a Pod>, ReplicaSetControllerAction> {
     for pod in filtered_pods {
         if replicaset.spec.selector.matches(&pod.metadata.labels) {
@@ -161,7 +174,7 @@ fn claim_pods<'a>(
             } else {
                 pod.metadata
                     .owner_references
-                    .push(new_controller_ref(&replicaset.metadata, &ReplicaSet::GVK));
+                    .push(new_controller_ref(&replicaset.metadata, owner_kind));
             }
             return ValOrOp::Op(ReplicaSetControllerAction::UpdatePod(pod));
         }
@@ -179,7 +192,7 @@ fn claim_pods<'a>(
     ValOrOp::Resource(pods)
 }
 
-fn calculate_status(replicaset: &ReplicaSet, pods: &[&Pod]) -> ReplicaSetStatus {
+fn calculate_status(replicaset: &ReplicaSet, pods: &[&Pod], now: Time) -> ReplicaSetStatus {
     let mut new_status = replicaset.status.clone();
 
     // Count the number of pods that have labels matching the labels of the pod
@@ -199,7 +212,7 @@ fn calculate_status(replicaset: &ReplicaSet, pods: &[&Pod]) -> ReplicaSetStatus
         }
         if is_pod_ready(pod) {
             ready_replicas_count += 1;
-            if is_pod_available(pod, replicaset.spec.min_ready_seconds, now()) {
+            if is_pod_available(pod, replicaset.spec.min_ready_seconds, now) {
                 available_replicas_count += 1;
             }
         }
@@ -244,7 +257,7 @@ fn remove_condition(status: &mut ReplicaSetStatus, cond_type: ReplicaSetConditio
     status.conditions.retain(|c| c.r#type != cond_type)
 }
 
-fn is_pod_available(pod: &Pod, min_ready_seconds: u32, now: Time) -> bool {
+pub(crate) fn is_pod_available(pod: &Pod, min_ready_seconds: u32, now: Time) -> bool {
     if let Some(c) = pod
         .status
         .conditions
@@ -292,6 +305,7 @@ fn update_replicaset_status(
 fn manage_replicas(
     filtered_pods: &[&Pod],
     replicaset: &ReplicaSet,
+    owner_kind: &GroupVersionKind,
 ) -> Option<ReplicaSetControllerAction> {
     match filtered_pods
         .len()
@@ -311,11 +325,8 @@ fn manage_replicas(
             // after one of its pods fails.  Conveniently, this also prevents the
             // event spam that those failures would generate.
             // TODO: batch size??
-            let pod = get_pod_from_template(
-                &replicaset.metadata,
-                &replicaset.spec.template,
-                &ReplicaSet::GVK,
-            );
+            let pod =
+                get_pod_from_template(&replicaset.metadata, &replicaset.spec.template, owner_kind);
             Some(ReplicaSetControllerAction::CreatePod(pod))
         }
         Ordering::Greater => {
@@ -0,0 +1,125 @@
+use crate::abstract_model::ControllerAction;
+use crate::controller::Controller;
+use crate::resources::{LabelSelector, Pod, ReplicaSet, ReplicaSetSpec, ReplicationController};
+use crate::state::revision::Revision;
+use crate::state::StateView;
+
+use super::replicaset::{self, ReplicaSetControllerAction};
+
+/// The controller for legacy replication controllers.
+///
+/// As in kubernetes, this shares its logic with the replicaset controller by converting each
+/// replication controller to a replicaset and back again.
+#[derive(Clone, Debug)]
+pub struct ReplicationManager;
+
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct ReplicationManagerState {
+    revision: Option<Revision>,
+}
+
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
+pub enum ReplicationManagerAction {
+    CreatePod(Pod),
+    UpdatePod(Pod),
+    DeletePod(Pod),
+
+    UpdateReplicationControllerStatus(ReplicationController),
+}
+
+impl From<ReplicationManagerAction> for ControllerAction {
+    fn from(value: ReplicationManagerAction) -> Self {
+        match value {
+            ReplicationManagerAction::CreatePod(p) => ControllerAction::CreatePod(p),
+            ReplicationManagerAction::UpdatePod(p) => ControllerAction::UpdatePod(p),
+            ReplicationManagerAction::DeletePod(p) => ControllerAction::SoftDeletePod(p),
+            ReplicationManagerAction::UpdateReplicationControllerStatus(rc) => {
+                ControllerAction::UpdateReplicationControllerStatus(rc)
+            }
+        }
+    }
+}
+
+impl Controller for ReplicationManager {
+    type State = ReplicationManagerState;
+    type Action = ReplicationManagerAction;
+
+    fn step(
+        &self,
+        global_state: &StateView,
+        local_state: &mut Self::State,
+    ) -> Option<Self::Action> {
+        local_state.revision = Some(global_state.revision.clone());
+        for rc in global_state.replication_controllers.iter() {
+            let Some(replicaset) = to_replicaset(rc) else {
+                continue;
+            };
+            let pods = global_state
+                .pods
+                .in_namespace(&rc.metadata.namespace)
+                .collect::<Vec<_>>();
+            if let Some(op) = replicaset::reconcile(
+                &replicaset,
+                &pods,
+                &global_state.revision,
+                global_state.now(),
+                &ReplicationController::GVK,
+            ) {
+                return Some(from_replicaset_action(rc, op));
+            }
+        }
+        None
+    }
+
+    fn arbitrary_steps(&self, _local_state: &Self::State) -> Vec<Self::State> {
+        Vec::new()
+    }
+
+    fn name(&self) -> String {
+        "ReplicationManager".to_owned()
+    }
+
+    fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
+        state.revision.as_ref()
+    }
+}
+
+/// The replicaset equivalent of the replication controller, none if it has no template to create
+/// pods from.
+///
+/// An empty selector is defaulted to the labels of the template and missing replicas to one, as
+/// the API server would.
+pub fn to_replicaset(rc: &ReplicationController) -> Option<ReplicaSet> {
+    let template = rc.spec.template.clone()?;
+    let match_labels = if rc.spec.selector.is_empty() {
+        template.metadata.labels.clone()
+    } else {
+        rc.spec.selector.clone()
+    };
+    Some(ReplicaSet {
+        metadata: rc.metadata.clone(),
+        spec: ReplicaSetSpec {
+            selector: LabelSelector { match_labels },
+            template,
+            replicas: Some(rc.spec.replicas.unwrap_or(1)),
+            min_ready_seconds: rc.spec.min_ready_seconds,
+        },
+        status: rc.status.clone(),
+    })
+}
+
+fn from_replicaset_action(
+    rc: &ReplicationController,
+    action: ReplicaSetControllerAction,
+) -> ReplicationManagerAction {
+    match action {
+        ReplicaSetControllerAction::CreatePod(p) => ReplicationManagerAction::CreatePod(p),
+        ReplicaSetControllerAction::UpdatePod(p) => ReplicationManagerAction::UpdatePod(p),
+        ReplicaSetControllerAction::DeletePod(p) => ReplicationManagerAction::DeletePod(p),
+        ReplicaSetControllerAction::UpdateReplicaSetStatus(rs) => {
+            let mut rc = rc.clone();
+            rc.status = rs.status;
+            ReplicationManagerAction::UpdateReplicationControllerStatus(rc)
+        }
+    }
+}
@@ -1,15 +1,113 @@
+use std::cmp::Reverse;
+use std::str::FromStr;
+
 use tracing::debug;
 
 use crate::abstract_model::ControllerAction;
 use crate::controller::Controller;
-use crate::resources::{Node, PersistentVolumeClaim, Pod, ResourceQuantities};
+use crate::resources::{
+    Node, PersistentVolumeClaim, PersistentVolumeClaimPhase, Pod, PodPhase, PreemptionPolicy,
+    ResourceQuantities,
+};
 use crate::state::revision::Revision;
-use crate::state::StateView;
+use crate::state::{RawState, StateView};
 
+use super::pvbinder::{binding_delayed, ANNOTATION_SELECTED_NODE};
 use super::util::is_pod_active;
 
-#[derive(Clone, Debug)]
-pub struct SchedulerController;
+/// The scheduler that pods without a `scheduler_name` are scheduled by.
+pub const DEFAULT_SCHEDULER_NAME: &str = "default-scheduler";
+
+/// Annotation naming the group of pods that a pod is gang scheduled with.
+pub const ANNOTATION_POD_GROUP: &str = "scheduling.x-k8s.io/pod-group";
+
+/// Annotation for the number of pods in the group that have to be scheduled together, defaulting
+/// to the whole group.
+pub const ANNOTATION_POD_GROUP_MIN_MEMBER: &str = "scheduling.x-k8s.io/pod-group-min-member";
+
+/// A scheduler, only placing the pods that name its profile.
+#[derive(Clone, Debug, Default)]
+pub struct SchedulerController {
+    pub profile: SchedulerProfile,
+}
+
+impl SchedulerController {
+    pub fn new(profile: SchedulerProfile) -> Self {
+        Self { profile }
+    }
+}
+
+/// The configuration of a scheduler, modelled on kube-scheduler profiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchedulerProfile {
+    /// The name that pods select this scheduler by.
+    pub scheduler_name: String,
+    /// How feasible nodes are ordered for placing pods on.
+    pub scoring: ScoringStrategy,
+    /// Whether lower priority pods get preempted for pods that don't fit anywhere.
+    pub preemption: bool,
+    /// Whether pods in a pod group are only bound once enough of the group fits.
+    pub gang_scheduling: bool,
+}
+
+impl Default for SchedulerProfile {
+    fn default() -> Self {
+        Self {
+            scheduler_name: DEFAULT_SCHEDULER_NAME.to_owned(),
+            scoring: ScoringStrategy::default(),
+            preemption: true,
+            gang_scheduling: false,
+        }
+    }
+}
+
+impl SchedulerProfile {
+    /// Whether this profile should schedule the pod.
+    pub fn responsible_for(&self, pod: &Pod) -> bool {
+        pod.spec
+            .scheduler_name
+            .as_deref()
+            .unwrap_or(DEFAULT_SCHEDULER_NAME)
+            == self.scheduler_name
+    }
+}
+
+/// Parses profiles in the form `name[:option]...`, where the options are a scoring strategy,
+/// `no-preemption` or `gang`.
+impl FromStr for SchedulerProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let mut profile = SchedulerProfile {
+            scheduler_name: parts.next().unwrap_or_default().to_owned(),
+            ..Default::default()
+        };
+        if profile.scheduler_name.is_empty() {
+            return Err(format!("scheduler profile {s:?} has no name"));
+        }
+        for option in parts {
+            match option {
+                "least-allocated" => profile.scoring = ScoringStrategy::LeastAllocated,
+                "most-allocated" => profile.scoring = ScoringStrategy::MostAllocated,
+                "no-preemption" => profile.preemption = false,
+                "gang" => profile.gang_scheduling = true,
+                _ => return Err(format!("unknown scheduler profile option {option:?}")),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// The order that a scheduler tries feasible nodes in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScoringStrategy {
+    /// Spread pods out by preferring the least loaded nodes.
+    #[default]
+    LeastAllocated,
+    /// Pack pods together by preferring the most loaded nodes.
+    MostAllocated,
+}
 
 #[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
 pub struct SchedulerControllerState {
@@ -19,12 +117,22 @@ pub struct SchedulerControllerState {
 #[derive(Debug)]
 pub enum SchedulerControllerAction {
     UpdatePod(Pod),
+
+    /// Select a node for a claim that waits for its first consumer.
+    UpdatePersistentVolumeClaim(PersistentVolumeClaim),
+
+    /// Evict a lower priority pod to make space for a higher priority one.
+    PreemptPod(Pod),
 }
 
 impl From<SchedulerControllerAction> for ControllerAction {
     fn from(value: SchedulerControllerAction) -> Self {
         match value {
             SchedulerControllerAction::UpdatePod(p) => ControllerAction::UpdatePod(p),
+            SchedulerControllerAction::UpdatePersistentVolumeClaim(pvc) => {
+                ControllerAction::UpdatePersistentVolumeClaim(pvc)
+            }
+            SchedulerControllerAction::PreemptPod(pod) => ControllerAction::SoftDeletePod(pod),
         }
     }
 }
@@ -45,21 +153,26 @@ impl Controller for SchedulerController {
             .iter()
             .map(|v| (v, global_state.pods_for_node(&v.metadata.name)))
             .collect::<Vec<_>>();
-        // TODO: sort nodes by load
-        nodes.sort_by_key(|(_, pods)| pods.len());
+        // THEMELIOS: load is approximated by the number of pods on the node
+        match self.profile.scoring {
+            ScoringStrategy::LeastAllocated => nodes.sort_by_key(|(_, pods)| pods.len()),
+            ScoringStrategy::MostAllocated => nodes.sort_by_key(|(_, pods)| Reverse(pods.len())),
+        }
 
-        let pods_to_schedule = global_state
+        let mut pods_to_schedule = global_state
             .pods
             .iter()
-            .filter(|p| p.spec.node_name.is_none() && is_pod_active(p));
-
-        let pvcs = global_state
-            .persistent_volume_claims
-            .iter()
+            .filter(|p| p.spec.node_name.is_none() && is_pod_active(p))
+            .filter(|p| self.profile.responsible_for(p))
             .collect::<Vec<_>>();
+        // highest priority first, as in the scheduling queue
+        pods_to_schedule.sort_by_key(|p| Reverse(pod_priority(p)));
 
         for pod in pods_to_schedule {
-            if let Some(op) = schedule(pod, &nodes, &pvcs) {
+            if self.profile.gang_scheduling && !gang_fits(pod, &nodes, global_state) {
+                continue;
+            }
+            if let Some(op) = schedule(&self.profile, pod, &nodes, global_state) {
                 return Some(op);
             }
         }
@@ -71,7 +184,11 @@ impl Controller for SchedulerController {
     }
 
     fn name(&self) -> String {
-        "Scheduler".to_owned()
+        if self.profile.scheduler_name == DEFAULT_SCHEDULER_NAME {
+            "Scheduler".to_owned()
+        } else {
+            format!("Scheduler-{}", self.profile.scheduler_name)
+        }
     }
 
     fn min_revision_accepted<'a>(&self, state: &'a Self::State) -> Option<&'a Revision> {
@@ -80,26 +197,28 @@ impl Controller for SchedulerController {
 }
 
 fn schedule(
+    profile: &SchedulerProfile,
     pod: &Pod,
     nodes: &[(&Node, Vec<&Pod>)],
-    pvcs: &[&PersistentVolumeClaim],
+    view: &RawState,
 ) -> Option<SchedulerControllerAction> {
-    // try to find a node suitable
-    for (node, pods) in nodes {
-        debug!(node = node.metadata.name, "Seeing if node fits");
+    let Some(claims) = pod_claims(pod, view) else {
+        debug!(
+            pod = pod.metadata.name,
+            "Pod requires volumes that don't exist"
+        );
+        return None;
+    };
 
-        if node.spec.unschedulable {
-            debug!("Node is not schedulable");
-            continue;
-        }
+    // nodes preferred by the pod's affinity first, keeping the scoring order between equals
+    let mut candidates = nodes.iter().collect::<Vec<_>>();
+    candidates.sort_by_key(|(node, _)| Reverse(preferred_weight(pod, node)));
 
-        if !tolerates_taints(pod, node) {
-            debug!("Pod doesn't tolerate node's taints");
-            continue;
-        }
+    // try to find a node suitable
+    for (node, pods) in candidates {
+        debug!(node = node.metadata.name, "Seeing if node fits");
 
-        if !volumes_exist(pod, pvcs) {
-            debug!("Pod requires volumes that don't exist");
+        if !node_feasible(pod, node, &claims, view) {
             continue;
         }
 
@@ -108,66 +227,344 @@ fn schedule(
             continue;
         }
 
+        // claims waiting for a consumer get provisioned or bound for this node before the pod
+        // can be bound to it
+        if let Some(claim) = claims.iter().find(|c| binding_delayed(c, view)) {
+            debug!(
+                pvc = claim.metadata.name,
+                node = node.metadata.name,
+                "Selecting node for claim"
+            );
+            let mut claim = (*claim).clone();
+            claim.metadata.annotations.insert(
+                ANNOTATION_SELECTED_NODE.to_owned(),
+                node.metadata.name.clone(),
+            );
+            return Some(SchedulerControllerAction::UpdatePersistentVolumeClaim(
+                claim,
+            ));
+        }
+
         let mut pod = pod.clone();
         pod.spec.node_name = Some(node.metadata.name.clone());
         return Some(SchedulerControllerAction::UpdatePod(pod));
     }
-    None
+    if !profile.preemption {
+        return None;
+    }
+    preempt(pod, nodes, &claims, view)
 }
 
-fn tolerates_taints(pod: &Pod, node: &Node) -> bool {
-    for taint in &node.spec.taints {
-        if pod.spec.tolerations.iter().any(|t| t.key == taint.key) {
-            // this pod tolerates this taint and so is immune to its effects
-        } else {
-            // this pod does not tolerate this taint, apply the effect
-            return false;
+/// The group that the pod is gang scheduled with, if any.
+pub fn pod_group(pod: &Pod) -> Option<&str> {
+    pod.metadata
+        .annotations
+        .get(ANNOTATION_POD_GROUP)
+        .map(String::as_str)
+}
+
+/// The active pods in the same group as the pod, including itself.
+pub fn group_members<'a>(pod: &Pod, view: &'a RawState) -> Vec<&'a Pod> {
+    let group = pod_group(pod);
+    view.pods
+        .iter()
+        .filter(|p| p.metadata.namespace == pod.metadata.namespace)
+        .filter(|p| is_pod_active(p) && group.is_some() && pod_group(p) == group)
+        .collect()
+}
+
+/// The number of pods in the group that have to be scheduled together.
+pub fn min_member(pod: &Pod, group_size: usize) -> usize {
+    pod.metadata
+        .annotations
+        .get(ANNOTATION_POD_GROUP_MIN_MEMBER)
+        .and_then(|m| m.parse().ok())
+        .unwrap_or(group_size)
+}
+
+/// Whether the pod can be bound without leaving its group short of its minimum members, modelled
+/// on the coscheduling plugin.
+///
+/// Pods outside of a group, and groups that already have enough members bound, schedule as
+/// normal. Otherwise the pod and enough of its pending group members to reach the minimum must
+/// all fit on the nodes together.
+///
+/// THEMELIOS: The group is placed greedily at the view, its pods are still bound one at a time
+/// rather than being held until the whole group is permitted, so other pods can claim the space
+/// before the rest of the group gets bound.
+fn gang_fits(pod: &Pod, nodes: &[(&Node, Vec<&Pod>)], view: &RawState) -> bool {
+    if pod_group(pod).is_none() {
+        return true;
+    }
+    let members = group_members(pod, view);
+    let min_member = min_member(pod, members.len());
+    if members.len() < min_member {
+        debug!(
+            pod = pod.metadata.name,
+            "Pod group doesn't have enough members yet"
+        );
+        return false;
+    }
+    let bound = members
+        .iter()
+        .filter(|p| p.spec.node_name.is_some())
+        .count();
+    let Some(mut needed) = min_member.checked_sub(bound).filter(|n| *n > 0) else {
+        return true;
+    };
+
+    let pending = members
+        .iter()
+        .copied()
+        .filter(|p| p.spec.node_name.is_none() && p.metadata.name != pod.metadata.name);
+    let mut placed = nodes.to_vec();
+    for member in std::iter::once(pod).chain(pending) {
+        let Some(claims) = pod_claims(member, view) else {
+            continue;
+        };
+        let Some((_, pods)) = placed.iter_mut().find(|(node, pods)| {
+            node_feasible(member, node, &claims, view) && fits_resources(member, node, pods)
+        }) else {
+            if member.metadata.name == pod.metadata.name {
+                break;
+            }
+            continue;
+        };
+        pods.push(member);
+        needed -= 1;
+        if needed == 0 {
+            return true;
         }
     }
+    debug!(
+        pod = pod.metadata.name,
+        "Not enough of the pod group fits to schedule it"
+    );
+    false
+}
+
+/// Whether the pod could run on the node, other than for the resources it has available.
+fn node_feasible(
+    pod: &Pod,
+    node: &Node,
+    claims: &[&PersistentVolumeClaim],
+    view: &RawState,
+) -> bool {
+    if node.spec.unschedulable {
+        debug!("Node is not schedulable");
+        return false;
+    }
+
+    if !tolerates_taints(pod, node) {
+        debug!("Pod doesn't tolerate node's taints");
+        return false;
+    }
+
+    if !matches_node_affinity(pod, node) {
+        debug!("Node doesn't match the pod's node selector or affinity");
+        return false;
+    }
+
+    if !volumes_fit(claims, node, view) {
+        debug!("Pod requires volumes that aren't bound or aren't available on the node");
+        return false;
+    }
     true
 }
 
-fn volumes_exist(pod: &Pod, pvcs: &[&PersistentVolumeClaim]) -> bool {
-    for volume in &pod.spec.volumes {
-        if !pvcs.iter().any(|pvc| pvc.metadata.name == volume.name) {
-            return false;
+/// Evict lower priority pods from a node so that the pod fits there, when it doesn't fit anywhere.
+///
+/// Victims are evicted one at a time, lowest priority first. Pods that are already terminating
+/// count as gone so that the pod waits for them to stop rather than preempting any more.
+///
+/// THEMELIOS: The first node that can be made to fit is picked, rather than scoring the nodes
+/// by their victims.
+fn preempt(
+    pod: &Pod,
+    nodes: &[(&Node, Vec<&Pod>)],
+    claims: &[&PersistentVolumeClaim],
+    view: &RawState,
+) -> Option<SchedulerControllerAction> {
+    if pod.spec.preemption_policy == Some(PreemptionPolicy::Never) {
+        return None;
+    }
+    let priority = pod_priority(pod);
+    let requests = pod_requests(pod);
+    for (node, pods) in nodes {
+        if !node_feasible(pod, node, claims, view) {
+            continue;
+        }
+
+        let allocatable = allocatable(node);
+        let remaining_pods = pods
+            .iter()
+            .filter(|p| p.metadata.deletion_timestamp.is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        let mut used = node_requests(&remaining_pods);
+        if (used.clone() + requests.clone()).fits_within(&allocatable) {
+            debug!(
+                pod = pod.metadata.name,
+                node = node.metadata.name,
+                "Waiting for terminating pods to make space"
+            );
+            return None;
+        }
+
+        let mut victims = remaining_pods
+            .into_iter()
+            .filter(|p| pod_priority(p) < priority)
+            .collect::<Vec<_>>();
+        victims.sort_by_key(|p| pod_priority(p));
+        for victim in &victims {
+            used -= pod_requests(victim);
+            if (used.clone() + requests.clone()).fits_within(&allocatable) {
+                debug!(
+                    pod = pod.metadata.name,
+                    node = node.metadata.name,
+                    victim = victims[0].metadata.name,
+                    "Preempting lower priority pod"
+                );
+                return Some(SchedulerControllerAction::PreemptPod(victims[0].clone()));
+            }
         }
     }
-    true
+    None
 }
 
-fn fits_resources(pod: &Pod, node: &Node, pods_for_node: &[&Pod]) -> bool {
-    let requests = pod
+/// The priority of the pod, pods without one get the default of zero.
+pub fn pod_priority(pod: &Pod) -> i32 {
+    pod.spec.priority.unwrap_or_default()
+}
+
+/// The effective requests of the pod: its containers run together but its init containers run
+/// one at a time before them, so it needs the most of either.
+pub fn pod_requests(pod: &Pod) -> ResourceQuantities {
+    let containers: ResourceQuantities = pod
         .spec
         .containers
         .iter()
         .filter_map(|c| c.resources.requests.as_ref())
         .sum();
+    pod.spec
+        .init_containers
+        .iter()
+        .filter_map(|c| c.resources.requests.as_ref())
+        .fold(containers, |acc, r| acc.max(r.clone()))
+}
 
-    // use allocatable from node status, or capacity if it is missing
-    let mut remaining_allocatable = node
-        .status
+/// The total requests of the pods on a node, excluding those that have finished and so no longer
+/// hold their resources.
+pub fn node_requests(pods_for_node: &[&Pod]) -> ResourceQuantities {
+    pods_for_node
+        .iter()
+        .filter(|p| !matches!(p.status.phase, PodPhase::Succeeded | PodPhase::Failed))
+        .map(|p| pod_requests(p))
+        .fold(ResourceQuantities::default(), |acc, r| acc + r)
+}
+
+/// The allocatable resources of the node, or its capacity if it is missing.
+pub fn allocatable(node: &Node) -> ResourceQuantities {
+    node.status
         .allocatable
         .as_ref()
         .unwrap_or(&node.status.capacity)
-        .clone();
+        .clone()
+}
 
-    for running_pod in pods_for_node {
-        let requests: ResourceQuantities = running_pod
-            .spec
-            .containers
-            .iter()
-            .filter_map(|c| c.resources.requests.as_ref())
-            .sum();
-        remaining_allocatable -= requests.clone();
+fn tolerates_taints(pod: &Pod, node: &Node) -> bool {
+    for taint in &node.spec.taints {
+        if pod.spec.tolerations.iter().any(|t| t.key == taint.key) {
+            // this pod tolerates this taint and so is immune to its effects
+        } else {
+            // this pod does not tolerate this taint, apply the effect
+            return false;
+        }
     }
+    true
+}
 
-    debug!(
-        ?remaining_allocatable,
-        ?requests,
-        "Checking if node has space"
-    );
-    if remaining_allocatable >= requests {
+/// Whether the node matches both the node selector and the required node affinity of the pod.
+pub fn matches_node_affinity(pod: &Pod, node: &Node) -> bool {
+    let selector_matches = pod
+        .spec
+        .node_selector
+        .iter()
+        .all(|(k, v)| node.metadata.labels.get(k) == Some(v));
+    let affinity_matches = pod
+        .spec
+        .affinity
+        .as_ref()
+        .and_then(|a| a.node_affinity.as_ref())
+        .and_then(|a| {
+            a.required_during_scheduling_ignored_during_execution
+                .as_ref()
+        })
+        .map_or(true, |s| s.matches(node));
+    selector_matches && affinity_matches
+}
+
+/// The sum of the weights of the pod's preferred node affinity terms that the node matches.
+fn preferred_weight(pod: &Pod, node: &Node) -> u32 {
+    pod.spec
+        .affinity
+        .as_ref()
+        .and_then(|a| a.node_affinity.as_ref())
+        .map_or(0, |a| {
+            a.preferred_during_scheduling_ignored_during_execution
+                .iter()
+                .filter(|t| t.preference.matches(node))
+                .map(|t| t.weight)
+                .sum()
+        })
+}
+
+/// The claims used by the pod's volumes, none if any of them don't exist.
+fn pod_claims<'a>(pod: &Pod, view: &'a RawState) -> Option<Vec<&'a PersistentVolumeClaim>> {
+    pod.spec
+        .volumes
+        .iter()
+        .filter_map(|volume| volume.persistent_volume_claim.as_ref())
+        .map(|source| {
+            view.persistent_volume_claims
+                .get_in(&pod.metadata.namespace, &source.claim_name)
+        })
+        .collect()
+}
+
+/// Whether the claims are ready for a pod on the node: bound to volumes it can access, or waiting
+/// for a node to be selected.
+fn volumes_fit(claims: &[&PersistentVolumeClaim], node: &Node, view: &RawState) -> bool {
+    // THEMELIOS: states without any volumes or classes don't model binding so claims only need
+    // to exist
+    if view.persistent_volumes.is_empty() && view.storage_classes.is_empty() {
+        return true;
+    }
+    claims.iter().all(|claim| {
+        if claim.status.phase != PersistentVolumeClaimPhase::Bound {
+            return binding_delayed(claim, view);
+        }
+        claim
+            .spec
+            .volume_name
+            .as_ref()
+            .and_then(|name| view.persistent_volumes.get(name))
+            .map_or(false, |pv| {
+                pv.spec
+                    .node_affinity
+                    .as_ref()
+                    .map_or(true, |n| n == &node.metadata.name)
+            })
+    })
+}
+
+fn fits_resources(pod: &Pod, node: &Node, pods_for_node: &[&Pod]) -> bool {
+    let requests = pod_requests(pod);
+    let allocatable = allocatable(node);
+    let used = node_requests(pods_for_node);
+
+    debug!(?allocatable, ?used, ?requests, "Checking if node has space");
+    if (used + requests).fits_within(&allocatable) {
         debug!(
             pod = pod.metadata.name,
             node = node.metadata.name,
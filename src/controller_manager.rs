@@ -22,6 +22,7 @@ use crate::{
     controller::{job::JobController, Controller, DeploymentController, ReplicaSetController},
     state::revision::Revision,
     state::StateView,
+    utils,
 };
 
 type AppState = Arc<Mutex<StateView>>;
@@ -72,7 +73,7 @@ pub async fn run() -> (Arc<AtomicBool>, Vec<JoinHandle<()>>) {
                                 .unwrap();
                                 state.revision = std::cmp::max(state.revision.clone(), revision);
                                 // TODO: should map the given resource to our types and use that
-                                let r = state.$field.get(dep.metadata.name.as_ref().unwrap()).unwrap().clone();
+                                let r = state.$field.get_in(dep.metadata.namespace.as_deref().unwrap_or_default(), dep.metadata.name.as_ref().unwrap()).unwrap().clone();
                                 state.$field.remove(&r);
                             }
                             Event::Restarted(deps) => {
@@ -155,12 +156,15 @@ async fn controller_loop<C: Controller>(
 
         tokio::time::sleep(rate_limit).await;
 
-        let s = state.lock().await;
+        let mut s = state.lock().await;
 
         if s.revision == last_revision {
             continue;
         }
 
+        // the mirrored state only sees time pass through the wall clock
+        s.clock = utils::wall_clock();
+
         debug!(name = controller.name(), "Checking for steps");
         if let Some(operation) = controller.step(&s, &mut cstate) {
             info!(name = controller.name(), "Got operation to perform");
@@ -178,6 +182,7 @@ async fn handle_action(action: ControllerAction, client: Client) {
     match action {
         ControllerAction::NodeJoin(_, _) => todo!(),
         ControllerAction::DeleteNode(_) => todo!(),
+        ControllerAction::UpdateNode(_) => todo!(),
         ControllerAction::CreatePod(mut pod) => {
             if pod.metadata.namespace.is_empty() {
                 pod.metadata.namespace = "default".to_owned();
@@ -193,6 +198,11 @@ async fn handle_action(action: ControllerAction, client: Client) {
         ControllerAction::SoftDeletePod(_) => todo!(),
         ControllerAction::HardDeletePod(_) => todo!(),
         ControllerAction::UpdatePod(_) => todo!(),
+        ControllerAction::PatchPod(_, _, _) => todo!(),
+        ControllerAction::PatchDeployment(_, _, _) => todo!(),
+        ControllerAction::PatchReplicaSet(_, _, _) => todo!(),
+        ControllerAction::PatchStatefulSet(_, _, _) => todo!(),
+        ControllerAction::PatchJob(_, _, _) => todo!(),
         ControllerAction::UpdateDeployment(mut dep) => {
             if dep.metadata.namespace.is_empty() {
                 dep.metadata.namespace = "default".to_owned();
@@ -211,7 +221,11 @@ async fn handle_action(action: ControllerAction, client: Client) {
             .await
             .unwrap();
         }
-        ControllerAction::RequeueDeployment(_) => todo!(),
+        ControllerAction::CreateDeployment(_) => todo!(),
+        ControllerAction::RequeueDeployment(_, _) => todo!(),
+        ControllerAction::ScaleDeployment(_) => todo!(),
+        ControllerAction::RollbackDeployment(_, _) => todo!(),
+        ControllerAction::DeleteDeployment(_) => todo!(),
         ControllerAction::UpdateDeploymentStatus(mut dep) => {
             if dep.metadata.namespace.is_empty() {
                 dep.metadata.namespace = "default".to_owned();
@@ -276,16 +290,64 @@ async fn handle_action(action: ControllerAction, client: Client) {
             .await
             .unwrap();
         }
-        ControllerAction::UpdateReplicaSets(_) => todo!(),
+        ControllerAction::ScaleReplicaSet(_) => todo!(),
         ControllerAction::DeleteReplicaSet(_) => todo!(),
+        ControllerAction::UpdateReplicationControllerStatus(mut rc) => {
+            if rc.metadata.namespace.is_empty() {
+                rc.metadata.namespace = "default".to_owned();
+            }
+            let api = Api::<k8s_openapi::api::core::v1::ReplicationController>::namespaced(
+                client,
+                &rc.metadata.namespace,
+            );
+            api.replace_status(
+                &rc.metadata.name.clone(),
+                &PostParams::default(),
+                serde_json::to_vec(&rc).unwrap(),
+            )
+            .await
+            .unwrap();
+        }
+        ControllerAction::DeleteReplicationController(_) => todo!(),
         ControllerAction::UpdateStatefulSet(_) => todo!(),
         ControllerAction::UpdateStatefulSetStatus(_) => todo!(),
+        ControllerAction::ScaleStatefulSet(_) => todo!(),
+        ControllerAction::CreateStatefulSet(_) => todo!(),
+        ControllerAction::DeleteStatefulSet(_) => todo!(),
         ControllerAction::CreateControllerRevision(_) => todo!(),
         ControllerAction::UpdateControllerRevision(_) => todo!(),
         ControllerAction::DeleteControllerRevision(_) => todo!(),
         ControllerAction::CreatePersistentVolumeClaim(_) => todo!(),
         ControllerAction::UpdatePersistentVolumeClaim(_) => todo!(),
+        ControllerAction::DeletePersistentVolumeClaim(_) => todo!(),
+        ControllerAction::UpdatePersistentVolumeClaimStatus(_) => todo!(),
+        ControllerAction::CreatePersistentVolume(_) => todo!(),
+        ControllerAction::UpdatePersistentVolume(_) => todo!(),
+        ControllerAction::UpdatePersistentVolumeStatus(_) => todo!(),
+        ControllerAction::CreateLease(_) => todo!(),
+        ControllerAction::UpdateLease(_) => todo!(),
+        ControllerAction::DeleteService(_) => todo!(),
+        ControllerAction::CreateEndpoints(_) => todo!(),
+        ControllerAction::UpdateEndpoints(_) => todo!(),
+        ControllerAction::DeleteEndpoints(_) => todo!(),
+        ControllerAction::CreateConfigMap(_) => todo!(),
+        ControllerAction::UpdateConfigMap(_) => todo!(),
+        ControllerAction::DeleteConfigMap(_) => todo!(),
+        ControllerAction::CreateSecret(_) => todo!(),
+        ControllerAction::UpdateSecret(_) => todo!(),
+        ControllerAction::DeleteSecret(_) => todo!(),
+        ControllerAction::CreateJob(_) => todo!(),
         ControllerAction::UpdateJob(_) => todo!(),
         ControllerAction::UpdateJobStatus(_) => todo!(),
+        ControllerAction::DeleteJob(_) => todo!(),
+        ControllerAction::UpdateCronJobStatus(_) => todo!(),
+        ControllerAction::DeleteCronJob(_) => todo!(),
+        ControllerAction::UpdateHorizontalPodAutoscalerStatus(_) => todo!(),
+        ControllerAction::DeleteHorizontalPodAutoscaler(_) => todo!(),
+        ControllerAction::SoftDeleteNamespace(_) => todo!(),
+        ControllerAction::FinalizeNamespace(_) => todo!(),
+        ControllerAction::Transaction(_) => todo!(),
+        ControllerAction::AdvanceClock(_) => todo!(),
+        ControllerAction::UpdateMetric(_, _, _) => todo!(),
     }
 }
@@ -3,19 +3,40 @@ use stateright::{Expectation, Property};
 use crate::{
     abstract_model::AbstractModel,
     controller::{
-        job::JobController, podgc::PodGCController, Controllers, DeploymentController,
-        NodeController, ReplicaSetController, SchedulerController, StatefulSetController,
+        job::JobController, podgc::PodGCController, Controllers, CronJobController,
+        DeploymentController, EndpointsController, HPAController, NamespaceController,
+        NodeController, NodeLifecycleController, PersistentVolumeBinderController,
+        ProvisionerController, ReplicaSetController, ReplicationManager, SchedulerController,
+        StatefulSetController,
     },
     state::State,
 };
 
+pub mod cronjob;
 pub mod deployment;
+pub mod endpoints;
+pub mod generation;
+pub mod hpa;
 pub mod job;
+pub mod leader_election;
+pub mod namespace;
 pub mod node;
+pub mod nodelifecycle;
+pub mod ownership;
+pub mod partition;
 pub mod podgc;
+pub mod provisioner;
+pub mod pvbinder;
+pub mod quiescence;
+pub mod rbac;
+pub mod relist;
 pub mod replicaset;
+pub mod replication;
 pub mod scheduler;
+pub mod shadow;
 pub mod statefulset;
+pub mod status;
+pub mod upgrade;
 
 pub trait ControllerProperties {
     fn properties() -> Properties;
@@ -27,14 +48,70 @@ impl ControllerProperties for Controllers {
         properties.append(&mut NodeController::properties());
         properties.append(&mut SchedulerController::properties());
         properties.append(&mut ReplicaSetController::properties());
+        properties.append(&mut ReplicationManager::properties());
         properties.append(&mut DeploymentController::properties());
         properties.append(&mut StatefulSetController::properties());
         properties.append(&mut JobController::properties());
+        properties.append(&mut CronJobController::properties());
+        properties.append(&mut HPAController::properties());
+        properties.append(&mut NodeLifecycleController::properties());
+        properties.append(&mut NamespaceController::properties());
+        properties.append(&mut EndpointsController::properties());
+        properties.append(&mut PersistentVolumeBinderController::properties());
+        properties.append(&mut ProvisionerController::properties());
         properties.append(&mut PodGCController::properties());
         properties
     }
 }
 
+/// The names of the controllers that have a bundle of properties, as given by
+/// [`Controller::name`](crate::controller::Controller::name).
+pub const BUNDLES: [&str; 15] = [
+    "Node",
+    "Scheduler",
+    "ReplicaSet",
+    "ReplicationManager",
+    "Deployment",
+    "StatefulSet",
+    "Job",
+    "CronJob",
+    "HorizontalPodAutoscaler",
+    "NodeLifecycle",
+    "Namespace",
+    "Endpoints",
+    "PersistentVolumeBinder",
+    "Provisioner",
+    "PodGC",
+];
+
+/// The standard safety and liveness properties for the controller with the given name, such as
+/// `Deployment`, so that they can be checked without assembling them by hand.
+pub fn for_controller(name: &str) -> Option<Properties> {
+    let properties = match name {
+        "Node" => NodeController::properties(),
+        "Scheduler" => SchedulerController::properties(),
+        "ReplicaSet" => ReplicaSetController::properties(),
+        "ReplicationManager" => ReplicationManager::properties(),
+        "Deployment" => {
+            let mut properties = DeploymentController::properties();
+            properties.append(&mut deployment::availability_properties());
+            properties
+        }
+        "StatefulSet" => StatefulSetController::properties(),
+        "Job" => JobController::properties(),
+        "CronJob" => CronJobController::properties(),
+        "HorizontalPodAutoscaler" => HPAController::properties(),
+        "NodeLifecycle" => NodeLifecycleController::properties(),
+        "Namespace" => NamespaceController::properties(),
+        "Endpoints" => EndpointsController::properties(),
+        "PersistentVolumeBinder" => PersistentVolumeBinderController::properties(),
+        "Provisioner" => ProvisionerController::properties(),
+        "PodGC" => PodGCController::properties(),
+        _ => return None,
+    };
+    Some(properties)
+}
+
 #[derive(Default)]
 pub struct Properties(Vec<Property<AbstractModel>>);
 
@@ -0,0 +1,58 @@
+use stateright::Expectation;
+
+use crate::controller::CronJobController;
+use crate::resources::{ConcurrencyPolicy, ConditionStatus, JobConditionType};
+
+use super::{ControllerProperties, Properties};
+
+impl ControllerProperties for CronJobController {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Sometimes,
+            "cronjob: every cronjob has scheduled a job",
+            |_m, s| {
+                let s = s.latest();
+                s.cronjobs
+                    .iter()
+                    .all(|cj| cj.status.last_schedule_time.is_some())
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "cronjob: last schedule time is never in the future",
+            |_m, s| {
+                let s = s.latest();
+                let now = s.now();
+                s.cronjobs
+                    .iter()
+                    .all(|cj| cj.status.last_schedule_time.map_or(true, |t| t <= now))
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "cronjob: forbid concurrency policy never runs jobs concurrently",
+            |_m, s| {
+                let s = s.latest();
+                s.cronjobs
+                    .iter()
+                    .filter(|cj| cj.spec.concurrency_policy == ConcurrencyPolicy::Forbid)
+                    .all(|cj| {
+                        let running = s
+                            .jobs
+                            .for_controller(&cj.metadata.uid)
+                            .filter(|j| {
+                                !j.status.conditions.iter().any(|c| {
+                                    (c.r#type == JobConditionType::Complete
+                                        || c.r#type == JobConditionType::Failed)
+                                        && c.status == ConditionStatus::True
+                                })
+                            })
+                            .count();
+                        running <= 1
+                    })
+            },
+        );
+        properties
+    }
+}
@@ -1,11 +1,17 @@
 use crate::controller::deployment::deployment_complete;
 use crate::controller::deployment::find_old_replicasets;
+use crate::controller::deployment::is_rolling_update;
 use crate::controller::deployment::skip_copy_annotation;
 use crate::controller::deployment::DEFAULT_DEPLOYMENT_UNIQUE_LABEL_KEY;
+use crate::controller::deployment::DESIRED_REPLICAS_ANNOTATION;
+use crate::controller::replicaset::is_pod_available;
 use crate::controller::util::subset;
+use crate::resources::Deployment;
+use crate::resources::IntOrString;
 use crate::resources::Pod;
 use crate::resources::ReplicaSet;
 use crate::state::revision::Revision;
+use crate::state::StateView;
 use crate::utils::LogicalBoolExt;
 use stateright::Expectation;
 
@@ -111,6 +117,24 @@ impl ControllerProperties for DeploymentController {
                     })
             },
         );
+        properties.add(
+            Expectation::Always,
+            "dep: replicas stay within replicas + maxSurge and above replicas - maxUnavailable during rolling updates",
+            |_model, state| {
+                // THEMELIOS: The replicas that the replicasets are asked to run are bounded rather
+                // than their pods, as replicaset controllers can create pods for the new
+                // replicaset before others have deleted those of the old ones.
+                let s = state.latest();
+                s.deployments
+                    .iter()
+                    // scaling the deployment changes the bounds before the replicasets follow
+                    .filter(|d| is_rolling_update(d) && rolling_out(&s, d) && scaled(&s, d))
+                    .all(|d| {
+                        let total = total_replicas(&s, d);
+                        min_available(d) <= total && total <= max_total(d)
+                    })
+            },
+        );
         properties.add(
             Expectation::Always,
             "dep: no replicaset is created when a deployment is paused",
@@ -130,6 +154,106 @@ impl ControllerProperties for DeploymentController {
     }
 }
 
+/// Properties checking that the deployments named in the model keep enough replicas available
+/// while they roll out.
+pub fn availability_properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "dep: available replicas stay above replicas - maxUnavailable during rolling updates",
+        |model, state| {
+            let s = state.latest();
+            s.deployments
+                .iter()
+                .filter(|d| model.rollout_availability.contains(&d.metadata.name))
+                .all(|d| {
+                    !is_rolling_update(d)
+                        || !rolling_out(&s, d)
+                        || available_pods(&s, d) >= min_available(d)
+                })
+        },
+    );
+    properties
+}
+
+/// The number of replicas that a rolling update has to keep available, the desired replicas less
+/// the most that can be unavailable.
+pub fn min_available(deployment: &Deployment) -> u32 {
+    let (_, max_unavailable) = fenceposts(deployment);
+    deployment.spec.replicas.saturating_sub(max_unavailable)
+}
+
+/// The most replicas that a rolling update can run at once, the desired replicas plus the most
+/// that can surge above them.
+pub fn max_total(deployment: &Deployment) -> u32 {
+    let (max_surge, _) = fenceposts(deployment);
+    deployment.spec.replicas + max_surge
+}
+
+/// The maxSurge and maxUnavailable of the deployment, resolved against its desired replicas.
+fn fenceposts(deployment: &Deployment) -> (u32, u32) {
+    let replicas = deployment.spec.replicas;
+    let rolling_update = deployment
+        .spec
+        .strategy
+        .as_ref()
+        .and_then(|s| s.rolling_update.as_ref());
+    let default = IntOrString::Str("25%".to_owned());
+    let max_surge = rolling_update
+        .and_then(|r| r.max_surge.as_ref())
+        .unwrap_or(&default)
+        .scaled_value(replicas, true);
+    let mut max_unavailable = rolling_update
+        .and_then(|r| r.max_unavailable.as_ref())
+        .unwrap_or(&default)
+        .scaled_value(replicas, false);
+    if max_surge == 0 && max_unavailable == 0 {
+        // as the controller resolves them, so that the rollout can make progress
+        max_unavailable = 1;
+    }
+    (max_surge, max_unavailable)
+}
+
+/// Whether the deployment controller has finished scaling the replicasets of the deployment to
+/// its current replicas, recording them in the desired replicas annotation of each one.
+fn scaled(view: &StateView, deployment: &Deployment) -> bool {
+    let desired = deployment.spec.replicas.to_string();
+    view.replicasets
+        .for_controller(&deployment.metadata.uid)
+        .filter(|rs| rs.spec.replicas.map_or(false, |r| r > 0))
+        .all(|rs| rs.metadata.annotations.get(DESIRED_REPLICAS_ANNOTATION) == Some(&desired))
+}
+
+/// The number of replicas that the replicasets of the deployment are asked to run.
+fn total_replicas(view: &StateView, deployment: &Deployment) -> u32 {
+    view.replicasets
+        .for_controller(&deployment.metadata.uid)
+        .filter_map(|rs| rs.spec.replicas)
+        .sum()
+}
+
+/// Whether the deployment has old replicasets that are still to be scaled down.
+fn rolling_out(view: &StateView, deployment: &Deployment) -> bool {
+    let replicasets = view
+        .replicasets
+        .for_controller(&deployment.metadata.uid)
+        .collect::<Vec<_>>();
+    let (old_with_replicas, _) = find_old_replicasets(deployment, &replicasets);
+    !old_with_replicas.is_empty()
+}
+
+/// The number of available pods across all of the replicasets of the deployment.
+fn available_pods(view: &StateView, deployment: &Deployment) -> u32 {
+    view.replicasets
+        .for_controller(&deployment.metadata.uid)
+        .flat_map(|rs| view.pods.for_controller(&rs.metadata.uid))
+        .filter(|p| {
+            p.metadata.deletion_timestamp.is_none()
+                && is_pod_available(p, deployment.spec.min_ready_seconds, view.now())
+        })
+        .count() as u32
+}
+
 fn check_rs_hash_labels(rs: &ReplicaSet) -> bool {
     let hash = rs.metadata.labels.get(DEFAULT_DEPLOYMENT_UNIQUE_LABEL_KEY);
     let selector_hash = rs
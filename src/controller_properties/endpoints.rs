@@ -0,0 +1,54 @@
+use stateright::Expectation;
+
+use crate::{
+    controller::{endpoints::selected_pods, util::is_pod_ready, EndpointsController},
+    resources::Service,
+    routing::routable_backends,
+    state::RawState,
+    utils::LogicalBoolExt,
+};
+
+use super::{ControllerProperties, Properties};
+
+impl ControllerProperties for EndpointsController {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Always,
+            "endpoints: when current, services with ready pods have a routable backend",
+            |_model, state| {
+                let s = state.latest();
+                s.services
+                    .iter()
+                    .filter(|service| !service.spec.selector.is_empty())
+                    .all(|service| {
+                        let current = s
+                            .endpoints
+                            .get_in(&service.metadata.namespace, &service.metadata.name)
+                            .map_or(false, |e| s.resource_current(e));
+                        current.implies(available(&s, service))
+                    })
+            },
+        );
+        properties.add(
+            Expectation::Eventually,
+            "endpoints: services with ready pods have a routable backend",
+            |_model, state| {
+                let s = state.latest();
+                s.services
+                    .iter()
+                    .filter(|service| !service.spec.selector.is_empty())
+                    .all(|service| available(&s, service))
+            },
+        );
+        properties
+    }
+}
+
+/// Whether traffic to the service reaches some backend, if the workload behind it has any ready
+/// replicas.
+fn available(s: &RawState, service: &Service) -> bool {
+    let ready = selected_pods(service, s).any(is_pod_ready);
+    let backends = routable_backends(s, &service.metadata.namespace, &service.metadata.name);
+    ready.implies(!backends.is_empty())
+}
@@ -0,0 +1,20 @@
+use stateright::Expectation;
+
+use super::Properties;
+
+/// Properties checking that the observed generation of resources only moves forward, whatever
+/// the consistency of the store that controllers read from and write to.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "generation: observed generations never decrease",
+        |_model, state| state.regressed_observed_generations().is_empty(),
+    );
+    properties.add(
+        Expectation::Always,
+        "generation: observed generations never exceed the generation",
+        |_model, state| state.latest().observed_generations_bounded(),
+    );
+    properties
+}
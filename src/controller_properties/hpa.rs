@@ -0,0 +1,53 @@
+use stateright::Expectation;
+
+use crate::{
+    controller::{hpa::get_scale, HPAController},
+    utils::LogicalBoolExt,
+};
+
+use super::{ControllerProperties, Properties};
+
+impl ControllerProperties for HPAController {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Sometimes,
+            "hpa: every autoscaler is stable",
+            |_m, s| {
+                let s = s.latest();
+                s.horizontal_pod_autoscalers
+                    .iter()
+                    .all(|hpa| s.resource_stable(hpa))
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "hpa: stable autoscalers have their target at the desired replicas",
+            |_m, s| {
+                let s = s.latest();
+                s.horizontal_pod_autoscalers.iter().all(|hpa| {
+                    let replicas = get_scale(&s, hpa).map(|scale| scale.spec.replicas);
+                    s.resource_stable(hpa)
+                        .implies(replicas.map_or(true, |r| r == hpa.status.desired_replicas))
+                })
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "hpa: desired replicas are within the autoscaler bounds",
+            |_m, s| {
+                let s = s.latest();
+                s.horizontal_pod_autoscalers
+                    .iter()
+                    .filter(|hpa| hpa.status.observed_generation > 0)
+                    .all(|hpa| {
+                        let desired = hpa.status.desired_replicas;
+                        // a target scaled to zero has autoscaling disabled
+                        desired == 0
+                            || (hpa.min_replicas() <= desired && desired <= hpa.spec.max_replicas)
+                    })
+            },
+        );
+        properties
+    }
+}
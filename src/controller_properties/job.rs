@@ -1,12 +1,15 @@
-use crate::controller::job::JOB_TRACKING_FINALIZER;
+use crate::controller::job::{
+    is_job_finished, past_active_deadline, JOB_COMPLETION_INDEX_ANNOTATION,
+    JOB_COMPLETION_INDEX_ENV_NAME, JOB_TRACKING_FINALIZER,
+};
 use crate::controller::util::is_pod_active;
 use crate::controller::util::is_pod_ready;
-use crate::resources::PodPhase;
+use crate::resources::{Job, PodPhase, Time};
 use crate::state::revision::Revision;
 use crate::utils::LogicalBoolExt;
 use stateright::Expectation;
 
-use crate::controller::JobController;
+use crate::controller::{ControllerStates, Controllers, JobController};
 
 use super::ControllerProperties;
 use super::Properties;
@@ -111,6 +114,63 @@ impl ControllerProperties for JobController {
                     })
             },
         );
+        properties.add(
+            Expectation::Eventually,
+            "job: jobs with finite completions eventually complete or fail",
+            |_model, state| {
+                let s = state.latest();
+                s.jobs
+                    .iter()
+                    .all(|job| due_to_finish(job, s.now()).implies(is_job_finished(job)))
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "job: indexed pods see their completion index in their environment",
+            |model, state| {
+                let s = state.latest();
+                model.controllers.iter().enumerate().all(|(c, controller)| {
+                    let (Controllers::Node(node), ControllerStates::Node(n)) =
+                        (controller, state.get_controller(c))
+                    else {
+                        return true;
+                    };
+                    n.environments.iter().all(|((pod, _), env)| {
+                        let Some(index) = env.get(JOB_COMPLETION_INDEX_ENV_NAME) else {
+                            return true;
+                        };
+                        // nodes keep their pods by name, the one bound to the node is theirs
+                        s.pods
+                            .iter()
+                            .filter(|p| &p.metadata.name == pod)
+                            .filter(|p| p.spec.node_name.as_ref() == Some(&node.name))
+                            .all(|pod| {
+                                pod.metadata
+                                    .annotations
+                                    .get(JOB_COMPLETION_INDEX_ANNOTATION)
+                                    == Some(index)
+                            })
+                    })
+                })
+            },
+        );
         properties
     }
 }
+
+/// Whether the job has to finish, having succeeded enough pods, failed more than its backoff limit
+/// allows or run past its active deadline.
+///
+/// THEMELIOS: Pods only finish when a client marks their containers as done, so jobs whose pods
+/// keep running only have to finish once they pass their deadline.
+fn due_to_finish(job: &Job, now: Time) -> bool {
+    let Some(completions) = job.spec.completions else {
+        return false;
+    };
+    if job.spec.suspend || job.metadata.deletion_timestamp.is_some() {
+        return false;
+    }
+    job.status.succeeded >= completions
+        || job.status.failed > job.spec.backoff_limit.unwrap_or_default()
+        || past_active_deadline(job, now)
+}
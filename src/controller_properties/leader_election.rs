@@ -0,0 +1,30 @@
+use stateright::Expectation;
+
+use crate::leader_election;
+
+use super::Properties;
+
+/// Properties checking that replicated controllers only act while leading.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "leader election: controllers only act while holding their lease",
+        |_m, state| state.deposed_leaders().is_empty(),
+    );
+    properties.add(
+        Expectation::Eventually,
+        "leader election: every replicated controller has a leader",
+        |model, state| {
+            let s = state.latest();
+            (0..model.controllers.len())
+                .filter_map(|i| model.elected_identity(i))
+                .all(|(lease, _)| {
+                    s.leases
+                        .get_in(leader_election::LEASE_NAMESPACE, &lease)
+                        .map_or(false, |lease| !leader_election::expired(&s, lease))
+                })
+        },
+    );
+    properties
+}
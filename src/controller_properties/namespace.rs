@@ -0,0 +1,29 @@
+use stateright::Expectation;
+
+use crate::controller::namespace::namespace_empty;
+use crate::controller::NamespaceController;
+
+use super::ControllerProperties;
+use super::Properties;
+
+impl ControllerProperties for NamespaceController {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Always,
+            "namespace: deleted namespaces leave no resources behind",
+            |model, state| {
+                let s = state.latest();
+                model.initial_states.iter().all(|initial| {
+                    initial
+                        .latest()
+                        .namespaces
+                        .iter()
+                        .filter(|ns| !s.namespaces.has(&ns.metadata.name))
+                        .all(|ns| namespace_empty(&ns.metadata.name, &s))
+                })
+            },
+        );
+        properties
+    }
+}
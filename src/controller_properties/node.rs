@@ -2,7 +2,10 @@ use std::collections::BTreeSet;
 
 use stateright::Expectation;
 
+use crate::controller::node::is_backing_off;
+use crate::controller::util::is_pod_ready;
 use crate::controller::{ControllerStates, NodeController};
+use crate::utils::LogicalBoolExt;
 
 use super::{ControllerProperties, Properties};
 
@@ -27,6 +30,16 @@ impl ControllerProperties for NodeController {
                 true
             },
         );
+        properties.add(
+            Expectation::Always,
+            "node: pods with containers backing off from restarting are not ready",
+            |_model, state| {
+                let s = state.latest();
+                s.pods
+                    .iter()
+                    .all(|pod| is_backing_off(pod).implies(!is_pod_ready(pod)))
+            },
+        );
         properties
     }
 }
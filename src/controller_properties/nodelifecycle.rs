@@ -0,0 +1,9 @@
+use crate::controller::NodeLifecycleController;
+
+use super::{ControllerProperties, Properties};
+
+impl ControllerProperties for NodeLifecycleController {
+    fn properties() -> Properties {
+        Properties::default()
+    }
+}
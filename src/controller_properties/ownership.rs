@@ -0,0 +1,98 @@
+use stateright::Expectation;
+
+use crate::resources::{LabelSelector, Meta, Pod, PodPhase};
+use crate::state::RawState;
+
+use super::Properties;
+
+/// Properties checking that controllers don't orphan the pods they own, whichever controllers run.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "ownership: pods with a controller point at an existing owner",
+        |_model, state| {
+            let s = state.latest();
+            s.pods
+                .iter()
+                .filter(|pod| collectable(&s, pod))
+                .all(|pod| owner_exists(&s, pod))
+        },
+    );
+    properties.add(
+        Expectation::Always,
+        "ownership: replicaset and job pods keep their owner while they match it",
+        |_model, state| {
+            let s = state.latest();
+            let replicasets = s
+                .replicasets
+                .iter()
+                .map(|rs| (rs.metadata(), &rs.spec.selector));
+            let jobs = s
+                .jobs
+                .iter()
+                .map(|job| (job.metadata(), &job.spec.selector));
+            replicasets.chain(jobs).all(|(owner, selector)| {
+                owner.deletion_timestamp.is_some()
+                    || !disowned_pods(&s, &owner.name, &owner.namespace, selector)
+            })
+        },
+    );
+    properties
+}
+
+/// Whether the pod is one that the garbage collector would clean up after its owner was deleted.
+///
+/// THEMELIOS: There is no garbage collector, so pods that are finished, being deleted or in a
+/// namespace being deleted can be left pointing at owners that have gone.
+fn collectable(s: &RawState, pod: &Pod) -> bool {
+    pod.metadata.deletion_timestamp.is_none()
+        && !matches!(pod.status.phase, PodPhase::Succeeded | PodPhase::Failed)
+        && s.namespace_accepts_creates(&pod.metadata.namespace)
+}
+
+/// Whether the controller of the pod, if it has one, exists with the uid that the pod refers to.
+pub fn owner_exists(s: &RawState, pod: &Pod) -> bool {
+    let Some(owner) = pod.metadata.owner_references.iter().find(|o| o.controller) else {
+        return true;
+    };
+    // owners are always in the namespace of what they own
+    let namespace = &pod.metadata.namespace;
+    let uid = match owner.kind.as_str() {
+        "ReplicaSet" => s
+            .replicasets
+            .get_in(namespace, &owner.name)
+            .map(|r| &r.metadata.uid),
+        "ReplicationController" => s
+            .replication_controllers
+            .get_in(namespace, &owner.name)
+            .map(|r| &r.metadata.uid),
+        "StatefulSet" => s
+            .statefulsets
+            .get_in(namespace, &owner.name)
+            .map(|r| &r.metadata.uid),
+        "Job" => s
+            .jobs
+            .get_in(namespace, &owner.name)
+            .map(|r| &r.metadata.uid),
+        "Node" => s.nodes.get(&owner.name).map(|r| &r.metadata.uid),
+        // owners that aren't modelled can't be checked
+        _ => return true,
+    };
+    uid == Some(&owner.uid)
+}
+
+/// Whether any pod that the named owner created still matches its selector but has no controller.
+///
+/// Controllers only release pods that stop matching their selector, so those that still match
+/// have lost their owner reference some other way.
+/// Pods are taken to be created by the owner when their generated name starts with its name.
+fn disowned_pods(s: &RawState, name: &str, namespace: &str, selector: &LabelSelector) -> bool {
+    let prefix = format!("{name}-");
+    s.pods.matching(selector).any(|pod| {
+        pod.metadata.namespace == namespace
+            && pod.metadata.generate_name.starts_with(&prefix)
+            && pod.metadata.deletion_timestamp.is_none()
+            && !pod.metadata.owner_references.iter().any(|o| o.controller)
+    })
+}
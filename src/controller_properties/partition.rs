@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+
+use stateright::Expectation;
+
+use crate::{
+    controller::{
+        util::{get_node_condition, is_pod_active},
+        ControllerStates, Controllers,
+    },
+    resources::{ConditionStatus, NodeConditionType},
+    state::StateView,
+};
+
+use super::Properties;
+
+/// Properties checking that workloads respond correctly to nodes becoming not ready, which needs
+/// the node lifecycle controller's taints and evictions, the workload controllers and pod
+/// termination to work together.
+///
+/// ReplicaSet pods are fungible so should be replaced as soon as they are evicted, but StatefulSet
+/// pods have a stable identity so must not be replaced until the old pod is known to have
+/// terminated, otherwise two copies could end up running at once.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Eventually,
+        "partition: replicaset pods on not ready nodes are eventually replaced",
+        |_model, state| replicaset_pods_replaced(&state.latest()),
+    );
+    properties.add(
+        Expectation::Sometimes,
+        "partition: replicaset pods are replaced after a node becomes not ready",
+        |_model, state| {
+            let s = state.latest();
+            !not_ready_nodes(&s).is_empty() && replicaset_pods_replaced(&s)
+        },
+    );
+    properties.add(
+        Expectation::Always,
+        "partition: statefulset pods are not replaced until the old pod has terminated",
+        |model, state| {
+            let s = state.latest();
+            model.controllers.iter().enumerate().all(|(i, controller)| {
+                match (controller, state.get_controller(i)) {
+                    (Controllers::Node(node), ControllerStates::Node(node_state)) => {
+                        // anything still running on a node must not have been recreated
+                        // elsewhere
+                        //
+                        // THEMELIOS: Nodes keep their pods by name alone, so the pods of that
+                        // name in every namespace are checked.
+                        node_state.running.keys().all(|name| {
+                            s.pods
+                                .iter()
+                                .filter(|pod| &pod.metadata.name == name)
+                                .all(|pod| {
+                                    let replaced = pod.spec.node_name.as_ref() != Some(&node.name);
+                                    let stateful = pod
+                                        .metadata
+                                        .owner_references
+                                        .iter()
+                                        .any(|o| o.controller && o.kind == "StatefulSet");
+                                    !(stateful && replaced)
+                                })
+                        })
+                    }
+                    _ => true,
+                }
+            })
+        },
+    );
+    properties
+}
+
+/// The names of nodes whose ready condition isn't true.
+fn not_ready_nodes(view: &StateView) -> BTreeSet<&String> {
+    view.nodes
+        .iter()
+        .filter(|n| {
+            get_node_condition(&n.status.conditions, NodeConditionType::Ready)
+                .map_or(true, |c| c.status != ConditionStatus::True)
+        })
+        .map(|n| &n.metadata.name)
+        .collect()
+}
+
+/// Whether every replicaset has its desired number of active pods, none of which are on nodes
+/// that aren't ready.
+fn replicaset_pods_replaced(view: &StateView) -> bool {
+    let not_ready = not_ready_nodes(view);
+    view.replicasets.iter().all(|rs| {
+        let active = view
+            .pods
+            .for_controller(&rs.metadata.uid)
+            .filter(|p| is_pod_active(p))
+            .collect::<Vec<_>>();
+        let on_not_ready = active.iter().any(|p| {
+            p.spec
+                .node_name
+                .as_ref()
+                .map_or(false, |n| not_ready.contains(n))
+        });
+        !on_not_ready && active.len() as u32 >= rs.spec.replicas.unwrap_or(1)
+    })
+}
@@ -0,0 +1,77 @@
+use stateright::Expectation;
+
+use crate::controller::pvbinder::{binding_delayed, ANNOTATION_SELECTED_NODE};
+use crate::controller::ProvisionerController;
+use crate::resources::PersistentVolumeClaimPhase;
+
+use super::ControllerProperties;
+use super::Properties;
+
+impl ControllerProperties for ProvisionerController {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Always,
+            "provisioner: pods only run on nodes that their volumes are available on",
+            |_model, state| {
+                let s = state.latest();
+                s.pods.iter().all(|pod| {
+                    let Some(node) = &pod.spec.node_name else {
+                        return true;
+                    };
+                    pod.spec
+                        .volumes
+                        .iter()
+                        .filter_map(|v| v.persistent_volume_claim.as_ref())
+                        .filter_map(|source| {
+                            s.persistent_volume_claims
+                                .get_in(&pod.metadata.namespace, &source.claim_name)
+                        })
+                        .filter_map(|pvc| pvc.spec.volume_name.as_ref())
+                        .filter_map(|name| s.persistent_volumes.get(name))
+                        .all(|pv| pv.spec.node_affinity.as_ref().map_or(true, |n| n == node))
+                })
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "provisioner: volumes for delayed claims are provisioned on the selected node",
+            |_model, state| {
+                let s = state.latest();
+                s.persistent_volumes.iter().all(|pv| {
+                    pv.spec
+                        .claim_ref
+                        .as_ref()
+                        .and_then(|r| s.persistent_volume_claims.get_in(&r.namespace, &r.name))
+                        .and_then(|pvc| pvc.metadata.annotations.get(ANNOTATION_SELECTED_NODE))
+                        .map_or(true, |selected| {
+                            pv.spec
+                                .node_affinity
+                                .as_ref()
+                                .map_or(true, |n| n == selected)
+                        })
+                })
+            },
+        );
+        properties.add(
+            Expectation::Eventually,
+            "provisioner: claims of provisioned classes get bound",
+            |_model, state| {
+                let s = state.latest();
+                s.persistent_volume_claims
+                    .iter()
+                    .filter(|pvc| pvc.metadata.deletion_timestamp.is_none())
+                    .filter(|pvc| !binding_delayed(pvc, &s))
+                    .filter(|pvc| {
+                        pvc.spec
+                            .storage_class_name
+                            .as_ref()
+                            .and_then(|name| s.storage_classes.get(name))
+                            .map_or(false, |class| !class.provisioner.is_empty())
+                    })
+                    .all(|pvc| pvc.status.phase == PersistentVolumeClaimPhase::Bound)
+            },
+        );
+        properties
+    }
+}
@@ -0,0 +1,74 @@
+use stateright::Expectation;
+
+use crate::controller::pvbinder::{binding_delayed, find_matching_volume, storage};
+use crate::controller::PersistentVolumeBinderController;
+use crate::resources::PersistentVolumeClaimPhase;
+
+use super::ControllerProperties;
+use super::Properties;
+
+impl ControllerProperties for PersistentVolumeBinderController {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Always,
+            "pvbinder: volumes are bound to at most one claim",
+            |_model, state| {
+                let s = state.latest();
+                let mut bound = s
+                    .persistent_volume_claims
+                    .iter()
+                    .filter(|pvc| pvc.status.phase == PersistentVolumeClaimPhase::Bound)
+                    .filter_map(|pvc| pvc.spec.volume_name.as_ref())
+                    .collect::<Vec<_>>();
+                let count = bound.len();
+                bound.sort();
+                bound.dedup();
+                bound.len() == count
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "pvbinder: bound claims are satisfied by their volume",
+            |_model, state| {
+                let s = state.latest();
+                s.persistent_volume_claims
+                    .iter()
+                    .filter(|pvc| pvc.status.phase == PersistentVolumeClaimPhase::Bound)
+                    .all(|pvc| {
+                        let Some(pv) = pvc
+                            .spec
+                            .volume_name
+                            .as_ref()
+                            .and_then(|name| s.persistent_volumes.get(name))
+                        else {
+                            // waiting to be marked as lost
+                            return true;
+                        };
+                        pvc.spec
+                            .access_modes
+                            .iter()
+                            .all(|mode| pv.spec.access_modes.contains(mode))
+                            && pv.spec.storage_class_name.as_deref().unwrap_or_default()
+                                == pvc.spec.storage_class_name.as_deref().unwrap_or_default()
+                            && storage(Some(&pv.spec.capacity))
+                                >= storage(pvc.spec.resources.requests.as_ref())
+                    })
+            },
+        );
+        properties.add(
+            Expectation::Eventually,
+            "pvbinder: claims with a matching volume get bound",
+            |_model, state| {
+                let s = state.latest();
+                s.persistent_volume_claims
+                    .iter()
+                    .filter(|pvc| pvc.metadata.deletion_timestamp.is_none())
+                    .filter(|pvc| pvc.status.phase == PersistentVolumeClaimPhase::Pending)
+                    .filter(|pvc| !binding_delayed(pvc, &s))
+                    .all(|pvc| find_matching_volume(pvc, &s).is_none())
+            },
+        );
+        properties
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::{HashSet, VecDeque};
+
+use stateright::{Expectation, Model};
+
+use crate::{
+    abstract_model::{AbstractModel, Action},
+    state::State,
+};
+
+use super::Properties;
+
+/// The number of states to explore from each state looking for one where the controllers have
+/// settled, beyond which they are taken not to settle.
+pub const QUIESCENCE_STATE_BOUND: usize = 1000;
+
+/// Properties checking that controllers settle down once clients stop changing things, rather
+/// than reconciling forever, such as by updating the status of a resource back and forth.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "quiescence: controllers can settle once clients stop acting",
+        |model, state| can_quiesce(model, state),
+    );
+    properties
+}
+
+/// Whether no controller has anything left to do, nor any messages waiting to be delivered.
+pub fn quiescent(model: &AbstractModel, state: &State) -> bool {
+    state.messages().is_empty()
+        && (0..model.controllers.len()).all(|i| !model.controller_enabled(state, i))
+}
+
+/// Whether a quiescent state can be reached from the state by controllers alone, without any
+/// clients or faults acting.
+///
+/// THEMELIOS: Only up to [`QUIESCENCE_STATE_BOUND`] states are explored, so controllers that take
+/// longer than that to settle are reported as never settling.
+/// They are explored with a [probe](AbstractModel::probe) of the model, so that the coverage and
+/// grants of the check only count the states that it explores itself.
+pub fn can_quiesce(model: &AbstractModel, state: &State) -> bool {
+    if quiescent(model, state) {
+        return true;
+    }
+    let model = &model.probe();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([state.clone()]);
+    while let Some(state) = queue.pop_front() {
+        if !seen.insert(stateright::fingerprint(&state)) {
+            continue;
+        }
+        if quiescent(model, &state) {
+            return true;
+        }
+        if seen.len() >= QUIESCENCE_STATE_BOUND {
+            return false;
+        }
+        let mut actions = Vec::new();
+        model.actions(&state, &mut actions);
+        for action in actions {
+            // time passing, messages arriving and the state replicating keep happening without
+            // any clients
+            let settling = matches!(
+                action,
+                Action::ControllerStep(_, _)
+                    | Action::ControllerResync(_, _)
+                    | Action::DeliverMessage(_)
+                    | Action::AntiEntropy(_, _)
+                    | Action::StoreHeal
+                    | Action::AdvanceClock
+            );
+            if settling {
+                if let Some(next) = model.next_state(&state, action) {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,15 @@
+use stateright::Expectation;
+
+use super::Properties;
+
+/// Properties checking that controllers stay within the roles they have been given, and those
+/// bound to their identities.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "rbac: controllers only take actions allowed by their role",
+        |_m, state| state.unauthorized_controllers().is_empty(),
+    );
+    properties
+}
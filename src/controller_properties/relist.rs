@@ -0,0 +1,15 @@
+use stateright::Expectation;
+
+use super::Properties;
+
+/// Properties checking that controllers behave safely when their caches are rebuilt from partial
+/// relists.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "relist: controllers do not delete resources based on an incomplete relist",
+        |_m, state| state.destructive_relists().is_empty(),
+    );
+    properties
+}
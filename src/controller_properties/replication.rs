@@ -0,0 +1,48 @@
+use stateright::Expectation;
+
+use crate::{
+    controller::{util::is_pod_active, ReplicationManager},
+    state::revision::Revision,
+    utils::LogicalBoolExt,
+};
+
+use super::{ControllerProperties, Properties};
+
+impl ControllerProperties for ReplicationManager {
+    fn properties() -> Properties {
+        let mut properties = Properties::default();
+        properties.add(
+            Expectation::Always,
+            "rc: when stable, status.replicas == count(active_pods)",
+            |_model, state| {
+                let s = state.latest();
+                s.replication_controllers
+                    .iter()
+                    .filter(|r| r.status.observed_revision != Revision::default())
+                    .all(|r| {
+                        let observed = state.view_at(&r.status.observed_revision);
+                        let pod_count = observed
+                            .pods
+                            .for_controller(&r.metadata.uid)
+                            .filter(|p| is_pod_active(p))
+                            .count();
+                        s.resource_stable(r)
+                            .implies(pod_count as u32 == r.status.replicas)
+                    })
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "rc: when stable, status replicas == spec replicas",
+            |_model, state| {
+                let s = state.latest();
+                s.replication_controllers.iter().all(|r| {
+                    let stable = s.resource_stable(r);
+                    let replicas_equal = r.spec.replicas.unwrap_or(1) == r.status.replicas;
+                    stable.implies(replicas_equal)
+                })
+            },
+        );
+        properties
+    }
+}
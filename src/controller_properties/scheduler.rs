@@ -1,10 +1,16 @@
+use stateright::Expectation;
+
+use crate::controller::scheduler::{
+    allocatable, group_members, min_member, node_requests, pod_group, pod_priority,
+};
+use crate::controller::util::is_pod_active;
 use crate::controller::SchedulerController;
 
 use super::{ControllerProperties, Properties};
 
 impl ControllerProperties for SchedulerController {
     fn properties() -> Properties {
-        Properties::default()
+        let mut properties = Properties::default();
         // properties.add(
         //     Expectation::Eventually,
         //     "sched: every pod gets scheduled",
@@ -13,5 +19,48 @@ impl ControllerProperties for SchedulerController {
         //         state.pods.iter().all(|pod| pod.spec.node_name.is_some())
         //     },
         // );
+        properties.add(
+            Expectation::Always,
+            "sched: requests on a node never exceed its allocatable",
+            |_model, state| {
+                let state = state.latest();
+                state.nodes.iter().all(|node| {
+                    let pods = state.pods_for_node(&node.metadata.name);
+                    node_requests(&pods).fits_within(&allocatable(node))
+                })
+            },
+        );
+        properties.add(
+            Expectation::Eventually,
+            "sched: pods with a raised priority get scheduled",
+            |_model, state| {
+                let state = state.latest();
+                state
+                    .pods
+                    .iter()
+                    .filter(|pod| is_pod_active(pod) && pod_priority(pod) > 0)
+                    .all(|pod| pod.spec.node_name.is_some())
+            },
+        );
+        properties.add(
+            Expectation::Eventually,
+            "sched: pod groups are not left partially scheduled",
+            |_model, state| {
+                let state = state.latest();
+                state
+                    .pods
+                    .iter()
+                    .filter(|pod| is_pod_active(pod) && pod_group(pod).is_some())
+                    .all(|pod| {
+                        let members = group_members(pod, &state);
+                        let bound = members
+                            .iter()
+                            .filter(|p| p.spec.node_name.is_some())
+                            .count();
+                        bound == 0 || bound >= min_member(pod, members.len())
+                    })
+            },
+        );
+        properties
     }
 }
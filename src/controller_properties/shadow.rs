@@ -0,0 +1,14 @@
+use stateright::Expectation;
+
+use super::Properties;
+
+/// Properties comparing controllers against their shadow implementations.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "shadow: shadow controllers take the same actions as their primary",
+        |_m, state| state.diverged_shadows().is_empty(),
+    );
+    properties
+}
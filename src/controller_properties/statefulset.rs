@@ -1,12 +1,16 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
 use stateright::Expectation;
 
 use crate::{
     controller::{
-        statefulset::{get_ordinal, pod_in_ordinal_range},
+        statefulset::{get_ordinal, get_start_ordinal, is_running_and_ready, pod_in_ordinal_range},
         util::is_pod_ready,
         StatefulSetController,
     },
-    state::revision::Revision,
+    resources::{Pod, PodManagementPolicyType, PodPhase, StatefulSet},
+    state::{revision::Revision, State, StateView},
     utils::LogicalBoolExt,
 };
 
@@ -101,6 +105,102 @@ impl ControllerProperties for StatefulSetController {
                     })
             },
         );
+        properties.add(
+            Expectation::Always,
+            "sts: at most one pod per ordinal",
+            |_model, state| {
+                let s = state.latest();
+                s.statefulsets.iter().all(|sts| {
+                    let mut ordinals = BTreeSet::new();
+                    s.pods
+                        .for_controller(&sts.metadata.uid)
+                        .filter_map(get_ordinal)
+                        .all(|o| ordinals.insert(o))
+                })
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "sts: pods are created in order under OrderedReady",
+            |_model, state| {
+                // point one from https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#deployment-and-scaling-guarantees
+                let s = state.latest();
+                s.statefulsets.iter().filter(|sts| ordered(sts)).all(|sts| {
+                    s.pods.for_controller(&sts.metadata.uid).all(|pod| {
+                        let Some(created_from) = created_from(state, pod) else {
+                            return true;
+                        };
+                        let Some(ordinal) = get_ordinal(pod) else {
+                            return true;
+                        };
+                        let present = created_from
+                            .pods
+                            .for_controller(&sts.metadata.uid)
+                            .filter(|p| p.metadata.deletion_timestamp.is_none())
+                            .filter_map(get_ordinal)
+                            .collect::<BTreeSet<_>>();
+                        (get_start_ordinal(sts)..ordinal).all(|o| present.contains(&o))
+                    })
+                })
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "sts: pods are deleted in reverse order under OrderedReady",
+            |_model, state| {
+                // point two from https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#deployment-and-scaling-guarantees
+                let s = state.latest();
+                s.statefulsets
+                    .iter()
+                    .filter(|sts| ordered(sts))
+                    .filter(|sts| s.namespace_accepts_creates(&sts.metadata.namespace))
+                    .all(|sts| {
+                        let pods = s.pods.for_controller(&sts.metadata.uid).collect::<Vec<_>>();
+                        let condemned_terminating = pods.iter().filter(|p| {
+                            p.metadata.deletion_timestamp.is_some()
+                                && p.status.phase != PodPhase::Failed
+                                && !pod_in_ordinal_range(p, sts)
+                        });
+                        condemned_terminating
+                            .filter_map(|p| get_ordinal(p))
+                            .all(|o| {
+                                // all of its successors should have already been shut down
+                                pods.iter()
+                                    .filter_map(|p| get_ordinal(p))
+                                    .all(|successor| successor <= o)
+                            })
+                    })
+            },
+        );
+        properties.add(
+            Expectation::Always,
+            "sts: pods only run once their predecessor is ready under OrderedReady",
+            |_model, state| {
+                let s = state.latest();
+                s.statefulsets.iter().filter(|sts| ordered(sts)).all(|sts| {
+                    s.pods
+                        .for_controller(&sts.metadata.uid)
+                        .filter(|pod| pod.status.phase == PodPhase::Running)
+                        .all(|pod| {
+                            let Some(created_from) = created_from(state, pod) else {
+                                return true;
+                            };
+                            let Some(ordinal) = get_ordinal(pod) else {
+                                return true;
+                            };
+                            if ordinal <= get_start_ordinal(sts) {
+                                return true;
+                            }
+                            created_from
+                                .pods
+                                .for_controller(&sts.metadata.uid)
+                                .any(|p| {
+                                    get_ordinal(p) == Some(ordinal - 1) && is_running_and_ready(p)
+                                })
+                        })
+                })
+            },
+        );
         // properties.add(
         //     Expectation::Always,
         //     "sts: when stable, statefulsets always have consecutive pods",
@@ -129,3 +229,19 @@ impl ControllerProperties for StatefulSetController {
         properties
     }
 }
+
+fn ordered(sts: &StatefulSet) -> bool {
+    sts.spec.pod_management_policy == PodManagementPolicyType::OrderedReady
+}
+
+/// The view of the state that the pod was created from, if it was created after the initial state
+/// and that view hasn't since been compacted away.
+///
+/// THEMELIOS: Pods take their uid from the revision that their creation was applied to, so this is
+/// the view that the creating controller's write landed on.
+fn created_from<'a>(state: &'a State, pod: &Pod) -> Option<Cow<'a, StateView>> {
+    let revision = Revision::try_from(&pod.metadata.uid).ok()?;
+    let view = state.view_at(&revision);
+    let existed = view.pods.iter().any(|p| p.metadata.uid == pod.metadata.uid);
+    (view.revision == revision && !existed).then_some(view)
+}
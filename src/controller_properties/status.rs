@@ -0,0 +1,15 @@
+use stateright::Expectation;
+
+use super::Properties;
+
+/// Properties checking that controllers keep to the status subresource when writing status,
+/// leaving the spec to the clients that own it.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Always,
+        "controllers never change specs through status updates",
+        |_model, state| state.spec_through_status().is_empty(),
+    );
+    properties
+}
@@ -0,0 +1,37 @@
+use stateright::Expectation;
+
+use crate::controller::deployment::deployment_complete;
+
+use super::Properties;
+
+/// Properties checking that in-flight work still converges once controllers have been swapped
+/// for their upgraded implementations.
+///
+/// Upgrades can be taken until they have happened, so every complete path has one to converge
+/// after.
+pub fn properties() -> Properties {
+    let mut properties = Properties::default();
+    properties.add(
+        Expectation::Eventually,
+        "upgrade: deployments complete after a controller upgrade",
+        |_m, state| {
+            let s = state.latest();
+            state.any_controller_upgraded()
+                && s.deployments
+                    .iter()
+                    .all(|d| deployment_complete(d, &d.status))
+        },
+    );
+    properties.add(
+        Expectation::Eventually,
+        "upgrade: replicasets are stable after a controller upgrade",
+        |_m, state| {
+            let s = state.latest();
+            state.any_controller_upgraded()
+                && s.replicasets.iter().all(|r| {
+                    s.resource_stable(r) && r.status.replicas == r.spec.replicas.unwrap_or_default()
+                })
+        },
+    );
+    properties
+}
@@ -0,0 +1,191 @@
+//! Which actions the controllers took during a check, for telling whether a model configuration
+//! reaches the code paths of interest.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::Model;
+
+use crate::abstract_model::ControllerAction;
+use crate::controller::deployment::DEPRECATED_ROLLBACK_TO;
+use crate::resources::{DeploymentStrategyType, ReplicaSet};
+use crate::state::StateView;
+
+/// Branches of the controllers that are told apart from the actions they lead to.
+pub const BRANCHES: [&str; 4] = [
+    "deployment: rollback",
+    "deployment: recreate rollout",
+    "deployment: rolling update rollout",
+    "deployment: hash collision",
+];
+
+/// Counts of the actions taken by controllers, shared between the model recording them and the
+/// reporter summarising them.
+#[derive(Debug, Clone, Default)]
+pub struct ActionCoverage(Arc<Mutex<CoverageData>>);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageData {
+    /// Times each kind of action was taken, by [`ControllerAction::name`].
+    pub actions: BTreeMap<&'static str, u64>,
+    /// Times each of the [`BRANCHES`] was taken.
+    pub branches: BTreeMap<&'static str, u64>,
+}
+
+impl CoverageData {
+    /// The kinds of action that were never taken.
+    pub fn missed_actions(&self) -> Vec<&'static str> {
+        ControllerAction::NAMES
+            .into_iter()
+            .filter(|name| !self.actions.contains_key(name))
+            .collect()
+    }
+
+    /// The branches that were never taken.
+    pub fn missed_branches(&self) -> Vec<&'static str> {
+        BRANCHES
+            .into_iter()
+            .filter(|name| !self.branches.contains_key(name))
+            .collect()
+    }
+}
+
+impl ActionCoverage {
+    /// Record the action that a controller took from the view.
+    pub fn record(&self, view: &StateView, action: &ControllerAction) {
+        let mut data = self.0.lock().unwrap();
+        *data.actions.entry(action.name()).or_default() += 1;
+        for branch in branches(view, action) {
+            *data.branches.entry(branch).or_default() += 1;
+        }
+    }
+
+    /// The counts recorded so far.
+    pub fn data(&self) -> CoverageData {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// A summary of what was and wasn't covered.
+    pub fn summary(&self) -> String {
+        let data = self.data();
+        let mut out = format!(
+            "Controller action coverage: {}/{} actions, {}/{} branches\n",
+            data.actions.len(),
+            ControllerAction::NAMES.len(),
+            data.branches.len(),
+            BRANCHES.len()
+        );
+        for (name, count) in data.actions.iter().chain(&data.branches) {
+            out.push_str(&format!("  {name}: {count}\n"));
+        }
+        for name in data
+            .missed_actions()
+            .into_iter()
+            .chain(data.missed_branches())
+        {
+            out.push_str(&format!("  {name}: never\n"));
+        }
+        out
+    }
+}
+
+/// The branches that the action shows were taken from the view.
+///
+/// THEMELIOS: Rollouts are told apart by the strategy of the deployment owning the replicasets
+/// that the action changes, so scaling a deployment also counts towards the rollout of its
+/// strategy.
+fn branches(view: &StateView, action: &ControllerAction) -> Vec<&'static str> {
+    let mut branches = Vec::new();
+    match action {
+        ControllerAction::UpdateDeployment(d) => {
+            let rolling_back = view
+                .deployments
+                .get_in(&d.metadata.namespace, &d.metadata.name)
+                .map_or(false, |old| {
+                    old.metadata
+                        .annotations
+                        .get(DEPRECATED_ROLLBACK_TO)
+                        .map_or(false, |r| !r.is_empty())
+                });
+            if rolling_back && !d.metadata.annotations.contains_key(DEPRECATED_ROLLBACK_TO) {
+                branches.push("deployment: rollback");
+            }
+        }
+        ControllerAction::UpdateDeploymentStatus(d) => {
+            let collided = view
+                .deployments
+                .get_in(&d.metadata.namespace, &d.metadata.name)
+                .map_or(false, |old| {
+                    d.status.collision_count > old.status.collision_count
+                });
+            if collided {
+                branches.push("deployment: hash collision");
+            }
+        }
+        ControllerAction::CreateReplicaSet(rs) | ControllerAction::UpdateReplicaSet(rs) => {
+            branches.extend(rollout(view, rs));
+        }
+        ControllerAction::Transaction(writes) => {
+            branches.extend(writes.iter().flat_map(|w| self::branches(view, w)));
+            // the same rollout can be behind several of the writes
+            branches.sort();
+            branches.dedup();
+        }
+        _ => {}
+    }
+    branches
+}
+
+/// The rollout branch for a change to a replicaset, from the strategy of the deployment owning
+/// it.
+fn rollout(view: &StateView, rs: &ReplicaSet) -> Option<&'static str> {
+    let owner = rs
+        .metadata
+        .owner_references
+        .iter()
+        .find(|o| o.controller && o.kind == "Deployment")?;
+    let deployment = view
+        .deployments
+        .get_in(&rs.metadata.namespace, &owner.name)?;
+    let strategy = deployment
+        .spec
+        .strategy
+        .as_ref()
+        .map(|s| s.r#type)
+        .unwrap_or_default();
+    Some(match strategy {
+        DeploymentStrategyType::Recreate => "deployment: recreate rollout",
+        DeploymentStrategyType::RollingUpdate => "deployment: rolling update rollout",
+    })
+}
+
+/// Prints a summary of the controller actions taken once the check is done.
+///
+/// THEMELIOS: Actions are recorded as the model generates them, so those taken from states that
+/// are generated more than once are counted for each time.
+#[derive(Debug, Clone)]
+pub struct CoverageReporter {
+    coverage: ActionCoverage,
+}
+
+impl CoverageReporter {
+    pub fn new(coverage: ActionCoverage) -> Self {
+        Self { coverage }
+    }
+}
+
+impl<M> Reporter<M> for CoverageReporter
+where
+    M: Model,
+{
+    fn report_checking(&mut self, _data: ReportData) {}
+
+    fn report_discoveries(&mut self, _discoveries: BTreeMap<&'static str, ReportDiscovery<M>>)
+    where
+        <M as Model>::Action: std::fmt::Debug,
+        <M as Model>::State: std::fmt::Debug + std::hash::Hash,
+    {
+        print!("{}", self.coverage.summary());
+    }
+}
@@ -0,0 +1,615 @@
+//! Differential testing of the model against a real cluster, such as one run with kind or k3s.
+//!
+//! A scripted [`Scenario`] is run against both, letting each converge after every step, and the
+//! [`Summary`] of the states that they converge to is compared step by step. This checks that the
+//! ported controllers behave as those upstream do.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use k8s_openapi::api::apps::v1 as apps;
+use k8s_openapi::api::batch::v1 as batch;
+use k8s_openapi::api::core::v1 as core;
+use kube::api::{DeleteParams, ListParams, Patch, PatchParams, PostParams};
+use kube::{Api, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use stateright::Model;
+use tracing::info;
+
+use crate::abstract_model::{AbstractModel, Action};
+use crate::controller_properties::quiescence::quiescent;
+use crate::recording::{ClientOperation, RecordedResource, Recording};
+use crate::resources::{
+    Container, Deployment, DeploymentSpec, LabelSelector, Metadata, PodSpec, PodTemplateSpec,
+};
+use crate::state::{RawState, ResourceKind, State};
+use crate::utils;
+
+/// The number of actions that the model takes to converge after each step, beyond which it is
+/// taken not to converge.
+pub const MODEL_STEP_BOUND: usize = 1000;
+
+/// How often the cluster is polled while waiting for it to converge.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The number of polls in a row that have to see the same summary for the cluster to have
+/// converged.
+const SETTLED_POLLS: usize = 3;
+
+/// A step of a scenario, taken by a client of the cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScenarioStep {
+    /// Create a deployment running a single container of the image.
+    CreateDeployment {
+        name: String,
+        image: String,
+        replicas: u32,
+    },
+    /// Scale the deployment through its scale subresource.
+    Scale { name: String, replicas: u32 },
+    /// Roll out a new image for the containers of the deployment.
+    UpdateImage { name: String, image: String },
+    /// Remove the node at the index from the cluster, counting the nodes that can run pods in
+    /// order of their names.
+    ///
+    /// THEMELIOS: Nodes are killed by deleting them, the kubelet of a real node may register it
+    /// again later.
+    KillNode { node: usize },
+}
+
+/// The steps to run against both the model and the cluster, in a namespace of the cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scenario {
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    pub steps: Vec<ScenarioStep>,
+}
+
+fn default_namespace() -> String {
+    "default".to_owned()
+}
+
+impl Default for Scenario {
+    /// Create a deployment, scale it, update its image and then kill a node.
+    fn default() -> Self {
+        let name = "web".to_owned();
+        Self {
+            namespace: default_namespace(),
+            steps: vec![
+                ScenarioStep::CreateDeployment {
+                    name: name.clone(),
+                    image: "nginx:1.25".to_owned(),
+                    replicas: 2,
+                },
+                ScenarioStep::Scale {
+                    name: name.clone(),
+                    replicas: 4,
+                },
+                ScenarioStep::UpdateImage {
+                    name,
+                    image: "nginx:1.26".to_owned(),
+                },
+                ScenarioStep::KillNode { node: 0 },
+            ],
+        }
+    }
+}
+
+impl Scenario {
+    /// Load a scenario from a YAML file.
+    pub fn load(path: &Path) -> Result<Self, DifferentialError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| DifferentialError::Scenario(e.to_string()))?;
+        serde_yaml::from_str(&contents).map_err(|e| DifferentialError::Scenario(e.to_string()))
+    }
+
+    /// The operations of the clients taking the steps, killing nodes from those given.
+    pub fn recording(&self, nodes: &[String]) -> Result<Recording, DifferentialError> {
+        let mut deployments = BTreeMap::<&str, Deployment>::new();
+        let mut operations = Vec::new();
+        for step in &self.steps {
+            let operation = match step {
+                ScenarioStep::CreateDeployment {
+                    name,
+                    image,
+                    replicas,
+                } => {
+                    let deployment = deployment(&self.namespace, name, image, *replicas);
+                    deployments.insert(name, deployment.clone());
+                    ClientOperation::Create(RecordedResource::Deployment(deployment))
+                }
+                ScenarioStep::Scale { name, replicas } => ClientOperation::Scale {
+                    kind: ResourceKind::Deployments,
+                    namespace: self.namespace.clone(),
+                    name: name.clone(),
+                    replicas: *replicas,
+                },
+                ScenarioStep::UpdateImage { name, image } => {
+                    let deployment = deployments.get_mut(name.as_str()).ok_or_else(|| {
+                        DifferentialError::Scenario(format!("no deployment named {name}"))
+                    })?;
+                    for container in &mut deployment.spec.template.spec.containers {
+                        container.image = image.clone();
+                    }
+                    ClientOperation::Update(RecordedResource::Deployment(deployment.clone()))
+                }
+                ScenarioStep::KillNode { node } => {
+                    let name = nodes.get(*node).ok_or_else(|| {
+                        DifferentialError::Scenario(format!(
+                            "no node {node}, only {} can run pods",
+                            nodes.len()
+                        ))
+                    })?;
+                    ClientOperation::Delete {
+                        kind: ResourceKind::Nodes,
+                        namespace: String::new(),
+                        name: name.clone(),
+                    }
+                }
+            };
+            operations.push(operation);
+        }
+        Ok(Recording { operations })
+    }
+}
+
+/// A deployment of the image, with the defaults that the API server would give it.
+fn deployment(namespace: &str, name: &str, image: &str, replicas: u32) -> Deployment {
+    let labels = BTreeMap::from([("app".to_owned(), name.to_owned())]);
+    let mut metadata = utils::metadata(name.to_owned());
+    metadata.namespace = namespace.to_owned();
+    Deployment {
+        metadata,
+        spec: DeploymentSpec {
+            replicas,
+            selector: LabelSelector {
+                match_labels: labels.clone(),
+            },
+            template: PodTemplateSpec {
+                metadata: Metadata {
+                    labels,
+                    ..Default::default()
+                },
+                spec: PodSpec {
+                    containers: vec![Container {
+                        name: name.to_owned(),
+                        image: image.to_owned(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            },
+            progress_deadline_seconds: Some(600),
+            revision_history_limit: 10,
+            ..Default::default()
+        },
+        status: Default::default(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DifferentialError {
+    /// The scenario couldn't be read or refers to something that doesn't exist.
+    Scenario(String),
+    /// The model couldn't take a step or didn't converge.
+    Model(String),
+    /// A request to the cluster failed or it didn't converge in time.
+    Cluster(String),
+}
+
+impl std::fmt::Display for DifferentialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DifferentialError::Scenario(error) => write!(f, "invalid scenario: {error}"),
+            DifferentialError::Model(error) => write!(f, "model failed: {error}"),
+            DifferentialError::Cluster(error) => write!(f, "cluster failed: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DifferentialError {}
+
+fn cluster_error(error: impl std::fmt::Display) -> DifferentialError {
+    DifferentialError::Cluster(error.to_string())
+}
+
+/// What the states of the model and the cluster are compared on, leaving out what differs between
+/// any two runs such as generated names, uids and times.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub deployments: BTreeMap<String, DeploymentSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentSummary {
+    pub replicas: u32,
+    /// Whether the controller has observed the latest generation of the deployment.
+    pub observed: bool,
+    pub status_replicas: u32,
+    pub updated_replicas: u32,
+    pub ready_replicas: u32,
+    pub available_replicas: u32,
+    /// The status and reason of each condition, by its type.
+    pub conditions: BTreeMap<String, String>,
+    /// The replicas of the replica sets of the deployment, by the images that they run.
+    pub replicasets: BTreeMap<String, u32>,
+    /// The number of pods of the deployment that aren't being deleted, by their images and phase.
+    pub pods: BTreeMap<String, usize>,
+}
+
+/// Summarize the resources of the state in the namespace.
+pub fn summarize(state: &RawState, namespace: &str) -> Summary {
+    let owned_by = |metadata: &Metadata, owner: &Metadata| {
+        metadata.owner_references.iter().any(|r| r.uid == owner.uid)
+    };
+    let images = |spec: &PodSpec| {
+        spec.containers
+            .iter()
+            .map(|c| c.image.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    let mut summary = Summary::default();
+    for deployment in state
+        .deployments
+        .iter()
+        .filter(|d| d.metadata.namespace == namespace)
+    {
+        let replicasets = state
+            .replicasets
+            .iter()
+            .filter(|rs| owned_by(&rs.metadata, &deployment.metadata))
+            .collect::<Vec<_>>();
+        let mut deployment_summary = DeploymentSummary {
+            replicas: deployment.spec.replicas,
+            observed: deployment.status.observed_generation >= deployment.metadata.generation,
+            status_replicas: deployment.status.replicas,
+            updated_replicas: deployment.status.updated_replicas,
+            ready_replicas: deployment.status.ready_replicas,
+            available_replicas: deployment.status.available_replicas,
+            ..Default::default()
+        };
+        for condition in &deployment.status.conditions {
+            deployment_summary.conditions.insert(
+                format!("{:?}", condition.r#type),
+                format!(
+                    "{:?} {}",
+                    condition.status,
+                    condition.reason.as_deref().unwrap_or_default()
+                ),
+            );
+        }
+        for rs in &replicasets {
+            *deployment_summary
+                .replicasets
+                .entry(images(&rs.spec.template.spec))
+                .or_default() += rs.spec.replicas.unwrap_or_default();
+        }
+        for pod in state.pods.iter().filter(|p| {
+            p.metadata.deletion_timestamp.is_none()
+                && replicasets
+                    .iter()
+                    .any(|rs| owned_by(&p.metadata, &rs.metadata))
+        }) {
+            let key = format!("{} {:?}", images(&pod.spec), pod.status.phase);
+            *deployment_summary.pods.entry(key).or_default() += 1;
+        }
+        summary
+            .deployments
+            .insert(deployment.metadata.name.clone(), deployment_summary);
+    }
+    summary
+}
+
+/// The differences between the summaries of the model and the cluster after each step, as
+/// unified diffs from the model to the cluster.
+pub fn differences(model: &[Summary], cluster: &[Summary]) -> Vec<(usize, String)> {
+    let render = |summary: Option<&Summary>| {
+        summary.map_or_else(String::new, |s| serde_yaml::to_string(s).unwrap())
+    };
+    (0..model.len().max(cluster.len()))
+        .filter(|i| model.get(*i) != cluster.get(*i))
+        .map(|i| {
+            let (model, cluster) = (render(model.get(i)), render(cluster.get(i)));
+            let textdiff = similar::TextDiff::from_lines(&model, &cluster);
+            let diff = similar::udiff::UnifiedDiff::from_text_diff(&textdiff)
+                .header("model", "cluster")
+                .to_string();
+            (i, diff)
+        })
+        .collect()
+}
+
+/// Take the client operations of the model in order, letting its controllers converge before
+/// each, and summarize the state that they converge to before the first operation and after
+/// each.
+pub fn converge_model(
+    model: &AbstractModel,
+    namespace: &str,
+) -> Result<Vec<Summary>, DifferentialError> {
+    let mut state = model
+        .init_states()
+        .pop()
+        .ok_or_else(|| DifferentialError::Model("no initial state".to_owned()))?;
+    let mut summaries = Vec::new();
+    loop {
+        state = converge(model, state)?;
+        summaries.push(summarize(&state.latest(), namespace));
+        let next = state.client_operations();
+        if next >= model.client_operations.len() {
+            return Ok(summaries);
+        }
+        state = model
+            .next_state(&state, Action::ClientOperation(next))
+            .ok_or_else(|| {
+                DifferentialError::Model(format!("client operation {next} couldn't be taken"))
+            })?;
+    }
+}
+
+/// Step the first controller that can until none can, so that the model runs one way through
+/// rather than exploring every interleaving.
+fn converge(model: &AbstractModel, mut state: State) -> Result<State, DifferentialError> {
+    for _ in 0..MODEL_STEP_BOUND {
+        if quiescent(model, &state) {
+            return Ok(state);
+        }
+        let mut actions = Vec::new();
+        model.actions(&state, &mut actions);
+        let next = actions
+            .into_iter()
+            .filter(|a| matches!(a, Action::ControllerStep(_, _)))
+            .find_map(|a| model.next_state(&state, a));
+        match next {
+            Some(next) => state = next,
+            None => return Ok(state),
+        }
+    }
+    Err(DifferentialError::Model(format!(
+        "controllers didn't converge within {MODEL_STEP_BOUND} actions"
+    )))
+}
+
+/// The operations grouped into the steps that the model converges between: the leading creates,
+/// which it starts from, and then each of the rest.
+fn steps(mut operations: Vec<ClientOperation>) -> Vec<Vec<ClientOperation>> {
+    let setup = operations
+        .iter()
+        .take_while(|o| matches!(o, ClientOperation::Create(_)))
+        .count();
+    let rest = operations.split_off(setup);
+    std::iter::once(operations)
+        .chain(rest.into_iter().map(|o| vec![o]))
+        .collect()
+}
+
+/// The names of the nodes of the cluster that can run pods, in order, leaving out the control
+/// plane.
+pub async fn cluster_nodes(client: &Client) -> Result<Vec<String>, DifferentialError> {
+    let nodes = Api::<core::Node>::all(client.clone())
+        .list(&ListParams::default())
+        .await
+        .map_err(cluster_error)?;
+    let mut names = nodes
+        .items
+        .into_iter()
+        .filter(|n| {
+            !n.metadata.labels.as_ref().map_or(false, |l| {
+                l.contains_key("node-role.kubernetes.io/control-plane")
+            })
+        })
+        .filter_map(|n| n.metadata.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/// Run the scenario against the cluster of the kubeconfig, waiting up to the timeout for it to
+/// converge after each step, and summarize the states that it converges to as for
+/// [`converge_model`].
+///
+/// THEMELIOS: The cluster is taken to have converged once its summary stops changing for
+/// [`SETTLED_POLLS`] polls, so controllers that are slower than that to react are missed.
+pub async fn run_cluster(
+    scenario: &Scenario,
+    timeout: Duration,
+) -> Result<Vec<Summary>, DifferentialError> {
+    let client = Client::try_default().await.map_err(cluster_error)?;
+    let nodes = cluster_nodes(&client).await?;
+    let recording = scenario.recording(&nodes)?;
+    let mut summaries = Vec::new();
+    for (i, step) in steps(recording.operations).into_iter().enumerate() {
+        for operation in &step {
+            info!(step = i, ?operation, "Applying operation to the cluster");
+            apply(&client, operation).await?;
+        }
+        summaries.push(converge_cluster(&client, &scenario.namespace, timeout).await?);
+    }
+    Ok(summaries)
+}
+
+/// Poll the cluster until its summary settles.
+async fn converge_cluster(
+    client: &Client,
+    namespace: &str,
+    timeout: Duration,
+) -> Result<Summary, DifferentialError> {
+    let started = tokio::time::Instant::now();
+    let mut last = None;
+    let mut settled = 0;
+    while started.elapsed() < timeout {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let summary = summarize(&cluster_state(client, namespace).await?, namespace);
+        if last.as_ref() == Some(&summary) {
+            settled += 1;
+            if settled + 1 >= SETTLED_POLLS {
+                return Ok(summary);
+            }
+        } else {
+            settled = 0;
+        }
+        last = Some(summary);
+    }
+    Err(DifferentialError::Cluster(format!(
+        "didn't converge within {}s",
+        timeout.as_secs()
+    )))
+}
+
+/// The resources of the namespace that summaries are made from, as the model's.
+async fn cluster_state(client: &Client, namespace: &str) -> Result<RawState, DifferentialError> {
+    async fn list<K, T>(client: &Client, namespace: &str) -> Result<Vec<T>, DifferentialError>
+    where
+        K: kube::Resource<Scope = k8s_openapi::NamespaceResourceScope>
+            + Clone
+            + std::fmt::Debug
+            + Serialize
+            + serde::de::DeserializeOwned,
+        <K as kube::Resource>::DynamicType: Default,
+        T: serde::de::DeserializeOwned,
+    {
+        let list = Api::<K>::namespaced(client.clone(), namespace)
+            .list(&ListParams::default())
+            .await
+            .map_err(cluster_error)?;
+        list.items
+            .into_iter()
+            .map(|r| {
+                serde_json::to_value(r)
+                    .and_then(serde_json::from_value)
+                    .map_err(cluster_error)
+            })
+            .collect()
+    }
+    Ok(RawState::default()
+        .with_deployments(list::<apps::Deployment, _>(client, namespace).await?)
+        .with_replicasets(list::<apps::ReplicaSet, _>(client, namespace).await?)
+        .with_pods(list::<core::Pod, _>(client, namespace).await?))
+}
+
+/// Run the body with the api of the kind, failing for kinds that can't be applied.
+macro_rules! with_api {
+    ($client:expr, $kind:expr, $namespace:expr, |$api:ident| $body:expr) => {
+        match $kind {
+            ResourceKind::Nodes => {
+                let $api = Api::<core::Node>::all($client.clone());
+                $body
+            }
+            ResourceKind::Pods => {
+                let $api = Api::<core::Pod>::namespaced($client.clone(), $namespace);
+                $body
+            }
+            ResourceKind::Deployments => {
+                let $api = Api::<apps::Deployment>::namespaced($client.clone(), $namespace);
+                $body
+            }
+            ResourceKind::ReplicaSets => {
+                let $api = Api::<apps::ReplicaSet>::namespaced($client.clone(), $namespace);
+                $body
+            }
+            ResourceKind::ReplicationControllers => {
+                let $api =
+                    Api::<core::ReplicationController>::namespaced($client.clone(), $namespace);
+                $body
+            }
+            ResourceKind::StatefulSets => {
+                let $api = Api::<apps::StatefulSet>::namespaced($client.clone(), $namespace);
+                $body
+            }
+            ResourceKind::Jobs => {
+                let $api = Api::<batch::Job>::namespaced($client.clone(), $namespace);
+                $body
+            }
+            kind => {
+                return Err(DifferentialError::Cluster(format!(
+                    "{kind:?} can't be applied to the cluster"
+                )))
+            }
+        }
+    };
+}
+
+/// The resource without the fields of its metadata, or that of its pod template, that the cluster
+/// fills in.
+fn for_cluster(resource: &RecordedResource) -> Result<Value, DifferentialError> {
+    let mut value = resource.to_value().map_err(cluster_error)?;
+    for pointer in ["/metadata", "/spec/template/metadata"] {
+        if let Some(metadata) = value.pointer_mut(pointer).and_then(Value::as_object_mut) {
+            for field in ["uid", "resourceVersion", "creationTimestamp", "generation"] {
+                metadata.remove(field);
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Apply the operation of a client to the cluster.
+///
+/// Updates only write the spec, labels and annotations of the resource, as they are replayed
+/// by the model.
+pub async fn apply(client: &Client, operation: &ClientOperation) -> Result<(), DifferentialError> {
+    match operation {
+        ClientOperation::Create(resource) => {
+            let value = for_cluster(resource)?;
+            let namespace = value["metadata"]["namespace"]
+                .as_str()
+                .unwrap_or("default")
+                .to_owned();
+            with_api!(client, resource.kind(), &namespace, |api| {
+                let object = serde_json::from_value(value).map_err(cluster_error)?;
+                api.create(&PostParams::default(), &object)
+                    .await
+                    .map_err(cluster_error)?;
+            })
+        }
+        ClientOperation::Update(resource) => {
+            let value = for_cluster(resource)?;
+            let metadata = &value["metadata"];
+            let name = metadata["name"].as_str().unwrap_or_default();
+            let namespace = metadata["namespace"].as_str().unwrap_or("default");
+            let patch = json!({
+                "metadata": {
+                    "labels": metadata.get("labels").cloned().unwrap_or(json!({})),
+                    "annotations": metadata.get("annotations").cloned().unwrap_or(json!({})),
+                },
+                "spec": value["spec"],
+            });
+            with_api!(client, resource.kind(), namespace, |api| {
+                api.patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+                    .await
+                    .map_err(cluster_error)?;
+            })
+        }
+        ClientOperation::Scale {
+            kind,
+            namespace,
+            name,
+            replicas,
+        } => {
+            let patch = json!({"spec": {"replicas": replicas}});
+            with_api!(client, *kind, namespace, |api| {
+                api.patch_scale(name, &PatchParams::default(), &Patch::Merge(&patch))
+                    .await
+                    .map_err(cluster_error)?;
+            })
+        }
+        ClientOperation::Delete {
+            kind,
+            namespace,
+            name,
+        } => {
+            with_api!(client, *kind, namespace, |api| {
+                api.delete(name, &DeleteParams::default())
+                    .await
+                    .map_err(cluster_error)?;
+            })
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,280 @@
+//! Events emitted by the controllers about the actions they take, as the event recorders in the
+//! kubernetes controllers do.
+//!
+//! THEMELIOS: Events are kept alongside the history of the state rather than in it. Controllers
+//! never read them, so they don't need to be viewed at different revisions, and leaving them out
+//! of the fingerprint keeps them from multiplying the states explored.
+
+use std::hash::{Hash, Hasher};
+
+use crate::abstract_model::ControllerAction;
+use crate::resources::{Event, EventType, Metadata, ObjectReference, ReplicaSet};
+use crate::state::StateView;
+
+/// Whether events are recorded, and whether they distinguish otherwise identical states.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EventRecording {
+    #[default]
+    Disabled,
+    /// Recorded for traces but left out of the fingerprint, so states that only differ in their
+    /// events are explored once.
+    Recorded,
+    /// Recorded and part of the fingerprint, for properties over the events.
+    Fingerprinted,
+}
+
+/// The events recorded so far.
+///
+/// These only take part in comparing and hashing states when they are fingerprinted.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    recording: EventRecording,
+    events: Vec<Event>,
+}
+
+impl EventLog {
+    pub fn new(recording: EventRecording) -> Self {
+        Self {
+            recording,
+            events: Vec::new(),
+        }
+    }
+
+    /// Record the event, counting it against an earlier one for the same object, reason and
+    /// message as the event correlator does.
+    pub fn record(&mut self, mut event: Event) {
+        if self.recording == EventRecording::Disabled {
+            return;
+        }
+        if let Some(existing) = self.events.iter_mut().find(|e| {
+            e.involved_object == event.involved_object
+                && e.reason == event.reason
+                && e.message == event.message
+        }) {
+            existing.count += 1;
+            existing.last_timestamp = event.last_timestamp;
+            return;
+        }
+        event.metadata.name = format!("{}.{}", event.involved_object.name, self.events.len());
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+}
+
+impl PartialEq for EventLog {
+    fn eq(&self, other: &Self) -> bool {
+        self.recording != EventRecording::Fingerprinted || self.events == other.events
+    }
+}
+
+impl Eq for EventLog {}
+
+impl Hash for EventLog {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if self.recording == EventRecording::Fingerprinted {
+            self.events.hash(state);
+        }
+    }
+}
+
+/// The events that the controller emits for taking the action on the given view, depending on
+/// whether the API server accepted it.
+pub fn for_action(
+    controller: &str,
+    view: &StateView,
+    action: &ControllerAction,
+    applied: bool,
+) -> Vec<Event> {
+    let now = view.now();
+    let event = |involved_object: ObjectReference,
+                 r#type: EventType,
+                 reason: &str,
+                 message: String| Event {
+        metadata: Metadata {
+            namespace: involved_object.namespace.clone(),
+            ..Default::default()
+        },
+        involved_object,
+        reason: reason.to_owned(),
+        message,
+        r#type,
+        reporting_controller: controller.to_owned(),
+        count: 1,
+        first_timestamp: Some(now),
+        last_timestamp: Some(now),
+    };
+    let mut events = Vec::new();
+    match action {
+        ControllerAction::CreatePod(pod) => {
+            if let Some(owner) = owner(&pod.metadata) {
+                let name = name_or_prefix(&pod.metadata);
+                events.push(if applied {
+                    event(
+                        owner,
+                        EventType::Normal,
+                        "SuccessfulCreate",
+                        format!("Created pod: {name}"),
+                    )
+                } else {
+                    event(
+                        owner,
+                        EventType::Warning,
+                        "FailedCreate",
+                        format!("Error creating: pod {name}"),
+                    )
+                });
+            }
+        }
+        ControllerAction::SoftDeletePod(pod) => {
+            if let Some(owner) = owner(&pod.metadata) {
+                let name = &pod.metadata.name;
+                events.push(if applied {
+                    event(
+                        owner,
+                        EventType::Normal,
+                        "SuccessfulDelete",
+                        format!("Deleted pod: {name}"),
+                    )
+                } else {
+                    event(
+                        owner,
+                        EventType::Warning,
+                        "FailedDelete",
+                        format!("Error deleting: pod {name}"),
+                    )
+                });
+            }
+        }
+        ControllerAction::UpdatePod(pod) if applied => {
+            let scheduled = view
+                .pods
+                .get_in(&pod.metadata.namespace, &pod.metadata.name)
+                .map_or(false, |p| p.spec.node_name.is_none());
+            if let (true, Some(node)) = (scheduled, &pod.spec.node_name) {
+                let m = &pod.metadata;
+                events.push(event(
+                    reference("Pod", m),
+                    EventType::Normal,
+                    "Scheduled",
+                    format!("Successfully assigned {}/{} to {node}", m.namespace, m.name),
+                ));
+            }
+        }
+        ControllerAction::CreateReplicaSet(rs) => {
+            if let Some(owner) = owner(&rs.metadata) {
+                let name = name_or_prefix(&rs.metadata);
+                events.push(if applied {
+                    let replicas = rs.spec.replicas.unwrap_or(1);
+                    event(
+                        owner,
+                        EventType::Normal,
+                        "ScalingReplicaSet",
+                        format!("Scaled up replica set {name} to {replicas}"),
+                    )
+                } else {
+                    event(
+                        owner,
+                        EventType::Warning,
+                        "FailedCreate",
+                        format!("Failed to create new replica set {name}"),
+                    )
+                });
+            }
+        }
+        ControllerAction::UpdateReplicaSet(rs) if applied => {
+            events.extend(scaled_replicaset(view, rs, &event));
+        }
+        ControllerAction::Transaction(writes) => {
+            for write in writes {
+                events.extend(for_action(controller, view, write, applied));
+            }
+        }
+        ControllerAction::CreateJob(job) => {
+            if let Some(owner) = owner(&job.metadata) {
+                let name = name_or_prefix(&job.metadata);
+                events.push(if applied {
+                    event(
+                        owner,
+                        EventType::Normal,
+                        "SuccessfulCreate",
+                        format!("Created job {name}"),
+                    )
+                } else {
+                    event(
+                        owner,
+                        EventType::Warning,
+                        "FailedCreate",
+                        format!("Error creating job: {name}"),
+                    )
+                });
+            }
+        }
+        _ => {}
+    }
+    events
+}
+
+/// The event for the deployment scaling one of its replicasets, if the update changed its
+/// replicas.
+fn scaled_replicaset(
+    view: &StateView,
+    rs: &ReplicaSet,
+    event: &impl Fn(ObjectReference, EventType, &str, String) -> Event,
+) -> Option<Event> {
+    let owner = owner(&rs.metadata)?;
+    let from = view
+        .replicasets
+        .get_in(&rs.metadata.namespace, &rs.metadata.name)?
+        .spec
+        .replicas;
+    let to = rs.spec.replicas;
+    if from == to {
+        return None;
+    }
+    let (from, to) = (from.unwrap_or(1), to.unwrap_or(1));
+    let direction = if to > from { "up" } else { "down" };
+    Some(event(
+        owner,
+        EventType::Normal,
+        "ScalingReplicaSet",
+        format!(
+            "Scaled {direction} replica set {} from {from} to {to}",
+            rs.metadata.name
+        ),
+    ))
+}
+
+/// The controller of the resource, which events about its creation are reported against.
+fn owner(metadata: &Metadata) -> Option<ObjectReference> {
+    metadata
+        .owner_references
+        .iter()
+        .find(|r| r.controller)
+        .map(|r| ObjectReference {
+            kind: r.kind.clone(),
+            namespace: metadata.namespace.clone(),
+            name: r.name.clone(),
+            uid: r.uid.clone(),
+        })
+}
+
+fn reference(kind: &str, metadata: &Metadata) -> ObjectReference {
+    ObjectReference {
+        kind: kind.to_owned(),
+        namespace: metadata.namespace.clone(),
+        name: metadata.name.clone(),
+        uid: metadata.uid.clone(),
+    }
+}
+
+/// The name of a resource being created, falling back to the prefix it is generated from.
+fn name_or_prefix(metadata: &Metadata) -> &str {
+    if metadata.name.is_empty() {
+        &metadata.generate_name
+    } else {
+        &metadata.name
+    }
+}
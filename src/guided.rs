@@ -0,0 +1,255 @@
+//! Checking guided by a heuristic, for hunting bugs in models too big to explore exhaustively.
+//!
+//! Successor states are scored by a [`Heuristic`] and the highest scoring state seen so far is
+//! always explored next, so branches that the heuristic deems interesting are reached before
+//! those that plain depth or breadth first search would get to first.
+
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+
+use stateright::report::{ReportData, Reporter};
+use stateright::{Expectation, Fingerprint, Model};
+
+use crate::abstract_model::AbstractModel;
+use crate::resources::{Meta, Spec};
+use crate::state::resources::Resources;
+use crate::state::{RawState, State};
+
+/// How often progress is reported while checking.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Scores the step from a state to one of its successors, higher scores being explored first.
+pub type Heuristic = fn(&AbstractModel, &State, &State) -> u64;
+
+/// Prefer steps that change more resources, whether creating, deleting or writing to their spec or
+/// status, over those that only change the local state of controllers.
+pub fn resource_changes(_model: &AbstractModel, last: &State, next: &State) -> u64 {
+    changed_resources(&last.latest().state, &next.latest().state) as u64
+}
+
+/// The number of resources that differ between the two states.
+pub fn changed_resources(before: &RawState, after: &RawState) -> usize {
+    fn changed<T: Meta + Spec + Clone>(before: &Resources<T>, after: &Resources<T>) -> usize {
+        let version = |r: &T| r.metadata().resource_version.clone();
+        let changed = after
+            .iter()
+            .filter(|r| {
+                let m = r.metadata();
+                before.get_in(&m.namespace, &m.name).map(version) != Some(version(r))
+            })
+            .count();
+        let removed = before
+            .iter()
+            .filter(|r| !after.has_in(&r.metadata().namespace, &r.metadata().name))
+            .count();
+        changed + removed
+    }
+
+    macro_rules! sum_changed {
+        ($($field:ident),*) => {
+            0 $(+ changed(&before.$field, &after.$field))*
+        };
+    }
+
+    sum_changed!(
+        nodes,
+        pods,
+        replicasets,
+        replication_controllers,
+        deployments,
+        statefulsets,
+        controller_revisions,
+        persistent_volume_claims,
+        persistent_volumes,
+        storage_classes,
+        priority_classes,
+        leases,
+        services,
+        endpoints,
+        config_maps,
+        secrets,
+        jobs,
+        cronjobs,
+        horizontal_pod_autoscalers,
+        namespaces,
+        roles,
+        role_bindings
+    )
+}
+
+/// A state waiting to be explored.
+struct Queued {
+    score: u64,
+    /// When the state was queued, so that equal scores are explored depth first.
+    order: u64,
+    depth: usize,
+    state: State,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        (self.score, self.order) == (other.score, other.order)
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.score, self.order).cmp(&(other.score, other.order))
+    }
+}
+
+/// The outcome of a guided check.
+#[derive(Debug, Clone, Default)]
+pub struct GuidedCheck {
+    pub total_states: usize,
+    pub unique_states: usize,
+    pub max_depth: usize,
+    pub duration: Duration,
+    /// The fingerprints along the path to the first discovery of each property.
+    pub discoveries: BTreeMap<&'static str, Vec<Fingerprint>>,
+    /// The number of states explored before each property was discovered.
+    pub states_to_discovery: BTreeMap<&'static str, usize>,
+}
+
+impl GuidedCheck {
+    /// The path to the discovery of the property, encoded as for `tui`.
+    pub fn encoded_path(&self, property: &str) -> Option<String> {
+        self.discoveries.get(property).map(|path| {
+            path.iter()
+                .map(|fp| fp.to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+    }
+}
+
+/// Check the model best first by the heuristic, stopping after visiting the given number of
+/// states (0 is no limit) or once every property has been discovered.
+///
+/// THEMELIOS: Only always and sometimes properties are checked, see
+/// [`AbstractModel::discovered_in`].
+pub fn check_guided(
+    model: &AbstractModel,
+    heuristic: Heuristic,
+    max_states: usize,
+    max_depth: usize,
+    reporter: &mut dyn Reporter<AbstractModel>,
+) -> GuidedCheck {
+    let start = Instant::now();
+    let mut last_report = start;
+    let properties = model
+        .properties()
+        .into_iter()
+        .filter(|p| !matches!(p.expectation, Expectation::Eventually))
+        .collect::<Vec<_>>();
+    let mut check = GuidedCheck::default();
+    // the state that each visited state was first reached from
+    let mut parents = HashMap::<Fingerprint, Option<Fingerprint>>::new();
+    let mut queue = BinaryHeap::new();
+    let mut order = 0;
+    for state in model.init_states() {
+        if parents
+            .insert(stateright::fingerprint(&state), None)
+            .is_none()
+        {
+            check.unique_states += 1;
+            queue.push(Queued {
+                score: 0,
+                order,
+                depth: 1,
+                state,
+            });
+            order += 1;
+        }
+    }
+    while let Some(Queued { depth, state, .. }) = queue.pop() {
+        if max_states != 0 && check.total_states >= max_states {
+            break;
+        }
+        let fingerprint = stateright::fingerprint(&state);
+        check.total_states += 1;
+        check.max_depth = check.max_depth.max(depth);
+
+        for property in &properties {
+            if check.discoveries.contains_key(property.name) {
+                continue;
+            }
+            if model.discovered_in(property, &state) {
+                check
+                    .discoveries
+                    .insert(property.name, path_to(&parents, fingerprint));
+                check
+                    .states_to_discovery
+                    .insert(property.name, check.total_states);
+            }
+        }
+        if !properties.is_empty() && check.discoveries.len() == properties.len() {
+            break;
+        }
+
+        if max_depth == 0 || depth < max_depth {
+            let mut actions = Vec::new();
+            model.actions(&state, &mut actions);
+            for action in actions {
+                let Some(next) = model.next_state(&state, action) else {
+                    continue;
+                };
+                if !model.within_boundary(&next) {
+                    continue;
+                }
+                let next_fingerprint = stateright::fingerprint(&next);
+                if parents.contains_key(&next_fingerprint) {
+                    continue;
+                }
+                parents.insert(next_fingerprint, Some(fingerprint));
+                check.unique_states += 1;
+                queue.push(Queued {
+                    score: heuristic(model, &state, &next),
+                    order,
+                    depth: depth + 1,
+                    state: next,
+                });
+                order += 1;
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            last_report = Instant::now();
+            reporter.report_checking(report_data(&check, start, false));
+        }
+    }
+    check.duration = start.elapsed();
+    reporter.report_checking(report_data(&check, start, true));
+    check
+}
+
+/// The fingerprints from an initial state to the given one.
+fn path_to(
+    parents: &HashMap<Fingerprint, Option<Fingerprint>>,
+    fingerprint: Fingerprint,
+) -> Vec<Fingerprint> {
+    let mut path = vec![fingerprint];
+    while let Some(Some(parent)) = parents.get(path.last().unwrap()) {
+        path.push(*parent);
+    }
+    path.reverse();
+    path
+}
+
+fn report_data(check: &GuidedCheck, start: Instant, done: bool) -> ReportData {
+    ReportData {
+        total_states: check.total_states,
+        unique_states: check.unique_states,
+        max_depth: check.max_depth,
+        duration: start.elapsed(),
+        done,
+    }
+}
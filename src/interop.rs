@@ -0,0 +1,105 @@
+//! Conversions between the resources of the model and those of `k8s-openapi`, for reusing fixtures
+//! written for real clusters and sending what the model produces through `kube` clients.
+//!
+//! Resources are converted through their JSON form, so fields that only one side has are dropped
+//! and values that the other side can't represent, such as conditions that the model doesn't know
+//! of, fail the conversion.
+
+use k8s_openapi::api::{
+    apps::v1 as apps, autoscaling::v2 as autoscaling, batch::v1 as batch,
+    coordination::v1 as coordination, core::v1 as core, rbac::v1 as rbac,
+    scheduling::v1 as scheduling, storage::v1 as storage,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::resources::{
+    ConfigMap, ControllerRevision, CronJob, Deployment, Endpoints, HorizontalPodAutoscaler, Job,
+    Lease, Metadata, Namespace, Node, PersistentVolume, PersistentVolumeClaim, Pod, PriorityClass,
+    ReplicaSet, ReplicationController, Role, RoleBinding, Secret, Service, StatefulSet,
+    StorageClass,
+};
+
+/// A resource that couldn't be converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError(pub String);
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to convert resource: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Convert through the JSON text of the value, which the revisions of the model need to be read
+/// from.
+fn convert<T: Serialize, U: DeserializeOwned>(value: &T) -> Result<U, ConversionError> {
+    let json = serde_json::to_string(value).map_err(|e| ConversionError(e.to_string()))?;
+    serde_json::from_str(&json).map_err(|e| ConversionError(e.to_string()))
+}
+
+macro_rules! conversions {
+    ($($ours:ty => $theirs:ty),* $(,)?) => {
+        $(
+            impl TryFrom<&$ours> for $theirs {
+                type Error = ConversionError;
+
+                fn try_from(value: &$ours) -> Result<Self, Self::Error> {
+                    convert(value)
+                }
+            }
+
+            impl TryFrom<$ours> for $theirs {
+                type Error = ConversionError;
+
+                fn try_from(value: $ours) -> Result<Self, Self::Error> {
+                    convert(&value)
+                }
+            }
+
+            impl TryFrom<&$theirs> for $ours {
+                type Error = ConversionError;
+
+                fn try_from(value: &$theirs) -> Result<Self, Self::Error> {
+                    convert(value)
+                }
+            }
+
+            impl TryFrom<$theirs> for $ours {
+                type Error = ConversionError;
+
+                fn try_from(value: $theirs) -> Result<Self, Self::Error> {
+                    convert(&value)
+                }
+            }
+        )*
+    };
+}
+
+conversions!(
+    Metadata => ObjectMeta,
+    Node => core::Node,
+    Pod => core::Pod,
+    ReplicaSet => apps::ReplicaSet,
+    ReplicationController => core::ReplicationController,
+    Deployment => apps::Deployment,
+    StatefulSet => apps::StatefulSet,
+    ControllerRevision => apps::ControllerRevision,
+    PersistentVolumeClaim => core::PersistentVolumeClaim,
+    PersistentVolume => core::PersistentVolume,
+    StorageClass => storage::StorageClass,
+    PriorityClass => scheduling::PriorityClass,
+    Lease => coordination::Lease,
+    Service => core::Service,
+    Endpoints => core::Endpoints,
+    ConfigMap => core::ConfigMap,
+    Secret => core::Secret,
+    Job => batch::Job,
+    CronJob => batch::CronJob,
+    HorizontalPodAutoscaler => autoscaling::HorizontalPodAutoscaler,
+    Namespace => core::Namespace,
+    Role => rbac::Role,
+    RoleBinding => rbac::RoleBinding,
+);
@@ -0,0 +1,34 @@
+//! Stopping checks early when interrupted, so that a long run still finishes by reporting what it
+//! found and writing its checkpoint rather than losing it all.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tracing::{error, warn};
+
+/// Set `stop` on the first Ctrl-C, letting the check wind down, and exit straight away on the
+/// second for when winding down takes too long.
+pub fn stop_on_interrupt(stop: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(err) => {
+                error!(%err, "Failed to listen for interrupts");
+                return;
+            }
+        };
+        rt.block_on(async {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            warn!("Interrupted, stopping the check to report what it found so far, interrupt again to exit now");
+            stop.store(true, Ordering::Relaxed);
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        });
+    });
+}
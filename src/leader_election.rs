@@ -0,0 +1,101 @@
+//! Leader election between replicas of a controller, modelled on client-go's leaderelection
+//! package.
+//!
+//! Replicas of a controller contend for a [`Lease`] named after it and only the holder runs its
+//! control loop, the others only try to acquire the lease. The holder renews the lease whenever
+//! the clock has moved on since its last renewal and another replica can take it over once it has
+//! gone unrenewed for its duration.
+
+use crate::abstract_model::{ControllerAction, CLOCK_STEP_SECONDS};
+use crate::resources::{Lease, LeaseSpec, Metadata};
+use crate::state::RawState;
+
+/// The namespace that controllers keep their leases in.
+pub const LEASE_NAMESPACE: &str = "kube-system";
+
+/// How long a lease is held for after being renewed.
+///
+/// THEMELIOS: This is a single clock step, so a holder that doesn't renew before the clock next
+/// advances loses the lease.
+pub const LEASE_DURATION_SECONDS: u64 = CLOCK_STEP_SECONDS;
+
+/// What a replica can do given the lease at its view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Election {
+    /// The replica holds the lease so runs its control loop.
+    Lead,
+    /// The replica has to acquire or renew the lease before it can lead.
+    Lease(ControllerAction),
+    /// Another replica holds the lease.
+    Follow,
+}
+
+/// The name of the lease for a controller.
+pub fn lease_name(controller_name: &str) -> String {
+    controller_name.to_lowercase()
+}
+
+/// The identity a replica holds its lease under, unique between replicas of the same controller.
+pub fn identity(controller_name: &str, controller_index: usize) -> String {
+    format!("{}-{}", lease_name(controller_name), controller_index)
+}
+
+/// Decide what the replica with the given identity does about the named lease.
+pub fn elect(view: &RawState, lease_name: &str, identity: &str) -> Election {
+    let now = view.now();
+    let Some(lease) = view.leases.get_in(LEASE_NAMESPACE, lease_name) else {
+        return Election::Lease(ControllerAction::CreateLease(Lease {
+            metadata: Metadata {
+                name: lease_name.to_owned(),
+                namespace: LEASE_NAMESPACE.to_owned(),
+                ..Default::default()
+            },
+            spec: LeaseSpec {
+                holder_identity: Some(identity.to_owned()),
+                lease_duration_seconds: Some(LEASE_DURATION_SECONDS),
+                acquire_time: Some(now),
+                renew_time: Some(now),
+                lease_transitions: 0,
+            },
+        }));
+    };
+
+    let held = lease.spec.holder_identity.as_deref() == Some(identity);
+    if held {
+        if lease.spec.renew_time.as_ref() == Some(&now) {
+            return Election::Lead;
+        }
+        let mut lease = lease.clone();
+        lease.spec.renew_time = Some(now);
+        return Election::Lease(ControllerAction::UpdateLease(lease));
+    }
+
+    if !expired(view, lease) {
+        return Election::Follow;
+    }
+    let mut lease = lease.clone();
+    lease.spec.holder_identity = Some(identity.to_owned());
+    lease.spec.lease_duration_seconds = Some(LEASE_DURATION_SECONDS);
+    lease.spec.acquire_time = Some(now);
+    lease.spec.renew_time = Some(now);
+    lease.spec.lease_transitions += 1;
+    Election::Lease(ControllerAction::UpdateLease(lease))
+}
+
+/// Whether the replica with the given identity holds the named lease and it has not expired.
+pub fn holds_lease(view: &RawState, lease_name: &str, identity: &str) -> bool {
+    view.leases
+        .get_in(LEASE_NAMESPACE, lease_name)
+        .map_or(false, |lease| {
+            lease.spec.holder_identity.as_deref() == Some(identity) && !expired(view, lease)
+        })
+}
+
+/// Whether the lease has gone unrenewed for its duration, or has no holder to renew it.
+pub fn expired(view: &RawState, lease: &Lease) -> bool {
+    let (Some(_), Some(renew_time)) = (&lease.spec.holder_identity, &lease.spec.renew_time) else {
+        return true;
+    };
+    let duration = lease.spec.lease_duration_seconds.unwrap_or_default();
+    renew_time.0.unix_timestamp() as u64 + duration <= view.clock
+}
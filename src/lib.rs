@@ -1,14 +1,43 @@
 pub mod abstract_model;
 pub mod api;
 pub mod arbitrary_client;
+pub mod audit;
+pub mod bitstate;
+pub mod bounded;
+pub mod budget;
+pub mod checkpoint;
+pub mod compare;
+pub mod config;
 pub mod controller;
 pub mod controller_manager;
 pub mod controller_properties;
+pub mod coverage;
+pub mod differential;
+pub mod events;
+pub mod guided;
 pub mod hasher;
+#[cfg(feature = "openapi")]
+pub mod interop;
+pub mod interrupt;
+pub mod leader_election;
+pub mod manifests;
+pub mod metrics;
 pub mod model;
+pub mod node_faults;
+pub mod progress;
+pub mod rbac;
+pub mod recording;
 pub mod report;
 pub mod resources;
+pub mod routing;
 pub mod serve_cluster;
 pub mod serve_test;
+pub mod shrink;
+pub mod simulation;
+pub mod snapshot;
 pub mod state;
+pub mod trace;
+pub mod tui;
+pub mod user_properties;
 pub mod utils;
+pub mod workqueue;
@@ -1,11 +1,40 @@
 use std::collections::BTreeMap;
 use std::io::IsTerminal;
+use std::time::Duration;
 
-use clap::Parser;
+use kube::Client;
 use stateright::Checker;
 use stateright::Model;
-use stateright::UniformChooser;
+use themelios::abstract_model::AbstractModel;
+use themelios::abstract_model::ControllerScope;
+use themelios::abstract_model::PropertySelection;
+use themelios::arbitrary_client::ArbitraryClientCfg;
+use themelios::audit::{self, AuditLog};
+use themelios::bitstate::{check_bitstate, BloomFilter};
+use themelios::bounded::{check_bounded, VisitedCache};
+use themelios::budget::BudgetReporter;
+use themelios::checkpoint::Checkpoint;
+use themelios::compare;
+use themelios::coverage::{ActionCoverage, CoverageReporter};
+use themelios::differential::{self, Scenario};
+use themelios::events::EventRecording;
+use themelios::guided::{check_guided, resource_changes};
+use themelios::interrupt;
+use themelios::manifests;
+use themelios::metrics::{self, Metrics, MetricsReporter};
 use themelios::model;
+use themelios::progress::ProgressReporter;
+use themelios::rbac;
+use themelios::recording::{Recorder, Recording};
+use themelios::report::DeepestPath;
+use themelios::report::DepthHistogram;
+use themelios::report::HtmlReporter;
+use themelios::report::JointReporter;
+use themelios::report::JointTerminalVisitor;
+use themelios::report::JsonReporter;
+use themelios::report::RolloutTracker;
+use themelios::report::SeedTracker;
+use themelios::report::SqliteReporter;
 use themelios::report::StdoutReporter;
 use themelios::resources::Deployment;
 use themelios::resources::DeploymentSpec;
@@ -24,13 +53,21 @@ use themelios::resources::ReplicaSetStatus;
 use themelios::resources::StatefulSet;
 use themelios::resources::StatefulSetSpec;
 use themelios::resources::StatefulSetStatus;
-use themelios::state::history::ConsistencySetup;
+use themelios::serve_cluster::replicas::ReplicaReads;
+use themelios::shrink::shrink;
+use themelios::simulation::{simulate_seeds, simulate_swarm, SeedLimits, WeightedChooser};
+use themelios::snapshot::Snapshot;
+use themelios::state::history::{Compaction, ConsistencySetup};
 use themelios::state::RawState;
+use themelios::trace::{self, Trace};
+use themelios::tui;
+use themelios::user_properties;
 use themelios::utils;
 use tokio::runtime::Runtime;
 use tower_http::trace::TraceLayer;
 use tracing::info;
 use tracing::metadata::LevelFilter;
+use tracing::warn;
 use tracing_subscriber::fmt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -39,7 +76,7 @@ use tracing_subscriber::EnvFilter;
 pub mod opts;
 
 fn main() {
-    let opts = opts::Opts::parse();
+    let opts = opts::Opts::load();
 
     let is_terminal = std::io::stdout().is_terminal();
     let log_filter = EnvFilter::builder()
@@ -50,7 +87,7 @@ fn main() {
         .with(log_filter)
         .init();
 
-    let initial_state = RawState::default()
+    let mut initial_state = RawState::default()
         .with_pods((0..opts.initial_pods).map(|i| Pod {
             metadata: utils::metadata(format!("pod-{i}")),
             spec: PodSpec {
@@ -66,6 +103,9 @@ fn main() {
                 subdomain: String::new(),
                 tolerations: Vec::new(),
                 node_selector: BTreeMap::new(),
+                priority: None,
+                priority_class_name: None,
+                preemption_policy: None,
             },
             status: PodStatus::default(),
         }))
@@ -88,6 +128,9 @@ fn main() {
                         subdomain: String::new(),
                         tolerations: Vec::new(),
                         node_selector: BTreeMap::new(),
+                        priority: None,
+                        priority_class_name: None,
+                        preemption_policy: None,
                     },
                 },
                 min_ready_seconds: 0,
@@ -116,6 +159,9 @@ fn main() {
                         subdomain: String::new(),
                         tolerations: Vec::new(),
                         node_selector: BTreeMap::new(),
+                        priority: None,
+                        priority_class_name: None,
+                        preemption_policy: None,
                     },
                 },
                 min_ready_seconds: 0,
@@ -146,44 +192,393 @@ fn main() {
             status: NodeStatus::default(),
         }));
 
+    let snapshot = if let Some(path) = &opts.snapshot {
+        Some(Snapshot::load(path).expect("Failed to load the snapshot"))
+    } else if opts.snapshot_cluster {
+        let rt = Runtime::new().unwrap();
+        Some(
+            rt.block_on(async {
+                let client = Client::try_default().await.map_err(|e| e.to_string())?;
+                Snapshot::from_cluster(&client)
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+            .expect("Failed to take a snapshot of the cluster"),
+        )
+    } else if let Some(dir) = &opts.manifests {
+        Some(Snapshot::load_manifests(dir).expect("Failed to load the manifests"))
+    } else {
+        opts.resources
+            .as_ref()
+            .map(|resources| Snapshot::from_values(resources.iter().cloned()))
+    };
+    if let Some(snapshot) = snapshot {
+        for (kind, count) in &snapshot.skipped {
+            warn!(%kind, count, "Left out resources of a kind the model doesn't have");
+        }
+        for warning in &snapshot.warnings {
+            warn!(%warning, "Left out part of the snapshot");
+        }
+        // THEMELIOS: The nodes of the snapshot, manifests or config file have no kubelets in the
+        // model, so keep those of the model for pods to run on.
+        let nodes = initial_state.nodes.iter().cloned().collect::<Vec<_>>();
+        let mut state = snapshot.state;
+        state.nodes = Default::default();
+        initial_state = state.with_nodes(nodes);
+    }
+
+    let mut client_operations = Vec::new();
+    if let Some(path) = &opts.replay {
+        let replay = Recording::load(path)
+            .and_then(Recording::into_replay)
+            .expect("Failed to load the recording");
+        // the recording doesn't create nodes, so keep those of the model
+        let nodes = initial_state.nodes.iter().cloned().collect::<Vec<_>>();
+        initial_state = replay.initial_state.with_nodes(nodes);
+        client_operations = replay.operations;
+    }
+
+    let scenario = match &opts.command {
+        opts::SubCmd::Differential { scenario, .. } => Some(match scenario {
+            Some(path) => Scenario::load(path).expect("Failed to load the scenario"),
+            None => Scenario::default(),
+        }),
+        _ => None,
+    };
+    if let Some(scenario) = &scenario {
+        // the scenario kills nodes by their index, so keep those of the model
+        let nodes = initial_state.nodes.iter().cloned().collect::<Vec<_>>();
+        let names = nodes
+            .iter()
+            .map(|n| n.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let replay = scenario
+            .recording(&names)
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.into_replay().map_err(|e| e.to_string()))
+            .expect("Failed to take the scenario through the model");
+        initial_state = replay.initial_state.with_nodes(nodes);
+        client_operations = replay.operations;
+    }
+
+    let audit_log = match &opts.command {
+        opts::SubCmd::Conformance { audit_log } => {
+            let log = AuditLog::load(audit_log).expect("Failed to load the audit log");
+            client_operations = log.operations.clone();
+            Some(log)
+        }
+        _ => None,
+    };
+
     let consistency_level = if opts.session {
         ConsistencySetup::ResettableSession
+    } else if opts.read_your_writes {
+        ConsistencySetup::ReadYourWrites
     } else if opts.optimistic_linear {
         ConsistencySetup::OptimisticLinear
     } else if opts.causal {
         ConsistencySetup::Causal
+    } else if let Some(replicas) = opts.eventual {
+        ConsistencySetup::Eventual(replicas)
+    } else if let Some(members) = opts.partitioned {
+        ConsistencySetup::Partitioned(members)
     } else {
         // default to synchronous
         ConsistencySetup::Synchronous
     };
+    let mut controller_scopes = BTreeMap::<String, ControllerScope>::new();
+    for scope in &opts.controller_scope {
+        let entry = controller_scopes
+            .entry(scope.controller.clone())
+            .or_default();
+        entry
+            .0
+            .entry(scope.kind)
+            .or_default()
+            .extend(scope.names.iter().cloned());
+    }
     let model = model::OrchestrationModelCfg {
         initial_state,
         consistency_level,
         schedulers: opts.schedulers,
+        scheduler_profiles: opts.scheduler_profiles,
         nodes: opts.nodes,
+        container_restarts: opts.container_restarts,
+        image_pulls: opts.image_pulls,
         replicaset_controllers: opts.replicaset_controllers,
+        replicationcontroller_controllers: opts.replicationcontroller_controllers,
         deployment_controllers: opts.deployment_controllers,
         statefulset_controllers: opts.statefulset_controllers,
         job_controllers: opts.job_controllers,
+        cronjob_controllers: opts.cronjob_controllers,
+        hpa_controllers: opts.hpa_controllers,
+        nodelifecycle_controllers: opts.nodelifecycle_controllers,
+        namespace_controllers: opts.namespace_controllers,
+        endpoints_controllers: opts.endpoints_controllers,
+        pvbinder_controllers: opts.pvbinder_controllers,
+        provisioner_controllers: opts.provisioner_controllers,
         podgc_controllers: opts.podgc_controllers,
+        // upgrades and shadows pair controllers with other implementations, only done through
+        // the library
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: if opts.rbac {
+            Some(rbac::default_role)
+        } else {
+            None
+        },
+        rbac_bindings: opts.rbac_bindings,
+        controller_sessions: None,
+        disabled_controllers: opts.disable_controller.iter().cloned().collect(),
+        controller_scopes,
+        disabled_actions: opts.disable_action.iter().copied().collect(),
+        fair_controllers: opts.fair_controller.iter().cloned().collect(),
+        fairness_bound: opts.fairness_bound,
+        replica_merge: Default::default(),
+        compaction: match opts.compact_history {
+            None => Compaction::Disabled,
+            Some(0) => Compaction::Referenced,
+            Some(keep) => Compaction::KeepLast(keep),
+        },
+        relist_faults: opts.relist_faults,
+        watch_caches: opts.watch_caches,
+        message_retries: opts.message_retries,
+        rate_limited_retries: opts.rate_limited_retries,
+        node_monitor_grace_period: opts.node_monitor_grace_period,
+        node_partitions: opts.node_partitions,
+        node_crashes: opts.node_crashes,
+        controller_crashes: opts.controller_crashes,
+        clock_skew: opts.clock_skew,
+        node_drains: opts.node_drains,
+        node_pressure: opts.node_pressure,
+        leader_election: opts.leader_election,
+        events: match (opts.events, opts.fingerprint_events) {
+            (false, _) => EventRecording::Disabled,
+            (true, false) => EventRecording::Recorded,
+            (true, true) => EventRecording::Fingerprinted,
+        },
+        quiescence: opts.quiescence,
+        status_writes: opts.status_writes,
+        ownership: opts.ownership,
+        generations: opts.generations,
+        rollout_availability: Default::default(),
+        client_operations,
+        arbitrary_client: ArbitraryClientCfg {
+            kinds: (!opts.client_kind.is_empty())
+                .then(|| opts.client_kind.iter().copied().collect()),
+            mutations: (!opts.client_mutation.is_empty())
+                .then(|| opts.client_mutation.iter().copied().collect()),
+            min_replicas: opts.client_min_replicas,
+            max_replicas: opts.client_max_replicas,
+            images: opts.client_image.clone(),
+        },
+        user_properties: opts
+            .properties
+            .as_ref()
+            .map(|path| user_properties::load(path).expect("Failed to load the properties"))
+            .unwrap_or_default()
+            .into_iter()
+            .chain(
+                user_properties::parse(&opts.config_properties.join("\n"))
+                    .expect("Failed to parse the properties of the config file"),
+            )
+            .collect(),
+        property_bundles: (!opts.property_bundle.is_empty())
+            .then(|| opts.property_bundle.iter().cloned().collect()),
+        property_selection: PropertySelection {
+            only: (!opts.property.is_empty()).then(|| opts.property.iter().cloned().collect()),
+            skip: opts.skip_property.iter().cloned().collect(),
+            warnings: opts.warn_property.iter().cloned().collect(),
+        },
         properties: Vec::new(),
     };
-    run(opts, model.into_abstract_model())
+    if let opts::SubCmd::CompareConsistency = &opts.command {
+        let threads = opts.threads.unwrap_or_else(num_cpus::get);
+        let comparison = compare::compare(&model, &compare::LEVELS, threads, opts.max_depth);
+        print!("{}", comparison.table());
+        for (name, level, path) in comparison.failures() {
+            let level = &comparison.levels[level].consistency_level;
+            print!("Property {name:?} fails under {level:?}, {path}");
+            println!(
+                "To explore this path try re-running with that consistency level and `explore {}`",
+                path.encode()
+            );
+        }
+        return;
+    }
+    let model = model.into_abstract_model();
+    if let opts::SubCmd::Tui { fingerprint_path } = &opts.command {
+        let trace = Trace::replay(&model, fingerprint_path).expect("Failed to replay the trace");
+        tui::run(trace).unwrap();
+        return;
+    }
+    if let opts::SubCmd::Export {
+        fingerprint_path,
+        output,
+    } = &opts.command
+    {
+        let fingerprints = trace::parse_path(fingerprint_path).expect("Failed to parse the path");
+        let states =
+            trace::replay_states(&model, &fingerprints).expect("Failed to replay the trace");
+        let state = states.last().unwrap().latest();
+        let paths = manifests::export(&state, output).expect("Failed to export the manifests");
+        println!(
+            "Wrote {} manifests to {}, to reproduce try `kubectl apply -f {}`",
+            paths.len(),
+            output.display(),
+            output.display()
+        );
+        return;
+    }
+    if let Some(log) = &audit_log {
+        let conformance = audit::check(&model, log);
+        for divergence in &conformance.divergences {
+            println!(
+                "Write {} diverges: {:?} made {}",
+                divergence.index, divergence.write.writer, divergence.write
+            );
+            for expected in &divergence.expected {
+                println!("  the model could have made {expected}");
+            }
+        }
+        println!(
+            "{} of {} writes diverge from the model, {} by controllers it doesn't have and {} left out",
+            conformance.divergences.len(),
+            conformance.checked,
+            conformance.unattributed,
+            log.skipped
+        );
+        if !conformance.conforms() {
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let opts::SubCmd::Shrink {
+        property,
+        fingerprint_path,
+    } = &opts.command
+    {
+        let shrunk = shrink(&model, property, fingerprint_path).expect("Failed to shrink the path");
+        for action in &shrunk.actions {
+            println!("{:?}", action);
+        }
+        println!(
+            "Shrunk from {} to {} steps, to step through it try re-running with `tui {}`",
+            shrunk.original_len,
+            shrunk.actions.len(),
+            shrunk.encoded_path()
+        );
+        return;
+    }
+    let authorizer = model.authorizer.clone();
+    run(opts, model, scenario);
+    for (controller, permission) in authorizer.unexercised_grants() {
+        println!("Unexercised grant for {}: {}", controller, permission);
+    }
 }
 
-fn run<M>(opts: opts::Opts, model: M)
-where
-    M: Model + Send + Sync + 'static,
-    M::State: Send + Sync + std::hash::Hash + std::fmt::Debug + Clone,
-    M::Action: Send + Sync + std::hash::Hash + std::fmt::Debug + Clone,
-{
+fn run(opts: opts::Opts, mut model: AbstractModel, scenario: Option<Scenario>) {
     println!("Running with config {:?}", opts);
-    let mut reporter = StdoutReporter::new(&model);
+    let checkpoint = match (&opts.checkpoint_path, &opts.command) {
+        (Some(path), opts::SubCmd::CheckDfs { .. } | opts::SubCmd::CheckBfs { .. }) => {
+            let checkpoint =
+                Checkpoint::load(path.clone(), &model).expect("Failed to load the checkpoint");
+            model.explored = checkpoint.explored();
+            Some(checkpoint)
+        }
+        _ => None,
+    };
+    let rollouts = RolloutTracker::default();
+    let depths = DepthHistogram::default();
+    let deepest = DeepestPath::default();
+    let tracker = SeedTracker::default();
+    let mut stdout = StdoutReporter::new(&model)
+        .with_rollouts(rollouts.clone())
+        .with_warnings(model.property_selection.warnings.clone());
+    if matches!(
+        opts.command,
+        opts::SubCmd::CheckSimulations { .. } | opts::SubCmd::Swarm { .. }
+    ) {
+        stdout = stdout.with_seeds(tracker.clone());
+    }
+    let mut reporter = JointReporter {
+        reporters: if opts.progress_tui {
+            vec![Box::new(ProgressReporter::new(
+                &model,
+                model.stop.clone(),
+                depths.clone(),
+                stdout,
+            ))]
+        } else {
+            vec![Box::new(stdout)]
+        },
+    };
+    if let Some(path) = &opts.json_report {
+        reporter.reporters.push(Box::new(JsonReporter::new(
+            &model,
+            path,
+            model.consistency_level.clone(),
+            opts.max_depth,
+            model.controllers.len(),
+            format!("{:?}", opts.command),
+        )));
+    }
+    if let Some(path) = &opts.sqlite_report {
+        let sqlite = SqliteReporter::new(
+            &model,
+            path,
+            model.consistency_level.clone(),
+            opts.max_depth,
+            model.controllers.len(),
+            format!("{:?}", opts.command),
+        )
+        .expect("Failed to open the sqlite report")
+        .with_rollouts(rollouts.clone());
+        reporter.reporters.push(Box::new(sqlite));
+    }
+    if let Some(path) = &opts.html_report {
+        reporter
+            .reporters
+            .push(Box::new(HtmlReporter::new(&model, path)));
+    }
+    if let Some(port) = opts.metrics_port {
+        let metrics = Metrics::new(&model);
+        metrics::serve(metrics.clone(), port);
+        reporter
+            .reporters
+            .push(Box::new(MetricsReporter::new(metrics)));
+    }
+    if opts.coverage {
+        let coverage = ActionCoverage::default();
+        model.coverage = Some(coverage.clone());
+        reporter
+            .reporters
+            .push(Box::new(CoverageReporter::new(coverage)));
+    }
+    if let Some(budget) = opts.command.budget().filter(|b| b.is_limited()) {
+        reporter
+            .reporters
+            .push(Box::new(BudgetReporter::new(budget, model.stop.clone())));
+    }
     let threads = opts.threads.unwrap_or_else(num_cpus::get);
-    let checker = model
+    let mut checker = model
+        .clone()
         .checker()
+        .terminal_visitor(JointTerminalVisitor {
+            visitors: vec![
+                Box::new(rollouts),
+                Box::new(depths),
+                Box::new(deepest.clone()),
+            ],
+        })
         .target_max_depth(opts.max_depth)
         .threads(threads);
+    if let Some(checkpoint) = &checkpoint {
+        checker = checker.visitor(checkpoint.clone());
+    }
+    if opts.command.is_check() {
+        interrupt::stop_on_interrupt(model.stop.clone());
+    }
 
     match opts.command {
         opts::SubCmd::Explore {
@@ -196,19 +591,121 @@ where
             println!("Serving web ui on http://127.0.0.1:{}{}", port, path);
             checker.serve(("127.0.0.1", port));
         }
-        opts::SubCmd::CheckDfs => {
+        opts::SubCmd::CheckDfs { .. } => {
             checker.spawn_dfs().report(&mut reporter).join();
         }
-        opts::SubCmd::CheckBfs => {
+        opts::SubCmd::CheckBfs { .. } => {
             checker.spawn_bfs().report(&mut reporter).join();
         }
-        opts::SubCmd::CheckSimulation { seed } => {
+        opts::SubCmd::CheckSimulation { seed, weight, .. } => {
             let seed = seed.unwrap_or(0);
+            let chooser =
+                WeightedChooser::new(weight.into_iter().map(|w| (w.class, w.weight)).collect());
             checker
-                .spawn_simulation(seed, UniformChooser)
+                .spawn_simulation(seed, chooser)
                 .report(&mut reporter)
                 .join();
         }
+        opts::SubCmd::CheckSimulations {
+            seeds,
+            first_seed,
+            states_per_seed,
+            ..
+        } => {
+            simulate_seeds(
+                &model,
+                first_seed..first_seed + seeds,
+                threads,
+                SeedLimits {
+                    states: states_per_seed,
+                    max_depth: opts.max_depth,
+                },
+                &tracker,
+                &mut reporter,
+            );
+        }
+        opts::SubCmd::Swarm {
+            members,
+            first_seed,
+            states_per_member,
+            ..
+        } => {
+            simulate_swarm(
+                &model,
+                first_seed..first_seed + members,
+                threads,
+                SeedLimits {
+                    states: states_per_member,
+                    max_depth: opts.max_depth,
+                },
+                &tracker,
+                &mut reporter,
+            );
+        }
+        opts::SubCmd::CheckBitstate {
+            memory_mb, hashes, ..
+        } => {
+            let mut filter = BloomFilter::new(memory_mb << 20, hashes);
+            let check = check_bitstate(&model, &mut filter, opts.max_depth, &mut reporter);
+            for property in model.properties() {
+                if let Some(path) = check.encoded_path(property.name) {
+                    println!(
+                        "Property {:?} {:?} discovered, to step through it try re-running with `tui {}`",
+                        property.expectation, property.name, path
+                    );
+                }
+            }
+            println!(
+                "Estimated chance of having skipped a new state: {:.6}",
+                check.false_positive_rate
+            );
+        }
+        opts::SubCmd::CheckBounded { max_visited, .. } => {
+            let mut visited = VisitedCache::new(max_visited);
+            let check = check_bounded(&model, &mut visited, opts.max_depth, &mut reporter);
+            for property in model.properties() {
+                if let Some(path) = check.encoded_path(property.name) {
+                    println!(
+                        "Property {:?} {:?} discovered, to step through it try re-running with `tui {}`",
+                        property.expectation, property.name, path
+                    );
+                }
+            }
+            println!("Evicted {} visited states", check.evictions);
+        }
+        opts::SubCmd::CheckGuided { budget } => {
+            let check = check_guided(
+                &model,
+                resource_changes,
+                budget.max_states,
+                opts.max_depth,
+                &mut reporter,
+            );
+            for property in model.properties() {
+                if let Some(path) = check.encoded_path(property.name) {
+                    println!(
+                        "Property {:?} {:?} discovered after {} states, to step through it try re-running with `tui {}`",
+                        property.expectation,
+                        property.name,
+                        check.states_to_discovery[property.name],
+                        path
+                    );
+                }
+            }
+        }
+        opts::SubCmd::Tui { .. } => unreachable!("the tui replays the model without a checker"),
+        opts::SubCmd::CompareConsistency => {
+            unreachable!("comparing consistency levels checks its own models")
+        }
+        opts::SubCmd::Shrink { .. } => {
+            unreachable!("shrinking replays the model without a checker")
+        }
+        opts::SubCmd::Export { .. } => {
+            unreachable!("exporting replays the model without a checker")
+        }
+        opts::SubCmd::Conformance { .. } => {
+            unreachable!("conformance replays the audit log without a checker")
+        }
         opts::SubCmd::ServeTest { port } => {
             let rt = Runtime::new().unwrap();
             rt.block_on(async {
@@ -220,12 +717,24 @@ where
                 axum::serve(listener, app).await.unwrap();
             });
         }
-        opts::SubCmd::ServeCluster { port } => {
+        opts::SubCmd::ServeCluster {
+            port,
+            record,
+            replicas,
+        } => {
+            let recorder = match record {
+                Some(path) => Recorder::create(&path).expect("Failed to create the recording"),
+                None => Recorder::default(),
+            };
+            let reads = ReplicaReads::new(&model.consistency_level);
             let rt = Runtime::new().unwrap();
             rt.block_on(async {
-                let address = format!("127.0.0.1:{port}");
-                info!("Serving cluster API on {address}");
-                let (shutdown, handles) = themelios::serve_cluster::run(address).await;
+                let addresses = (0..replicas.max(1))
+                    .map(|i| format!("127.0.0.1:{}", port + i))
+                    .collect::<Vec<_>>();
+                info!(?addresses, ?reads, "Serving cluster API");
+                let (shutdown, handles) =
+                    themelios::serve_cluster::run(addresses, reads, recorder).await;
                 tokio::signal::ctrl_c().await.unwrap();
                 shutdown.store(true, std::sync::atomic::Ordering::Relaxed);
                 for handle in handles {
@@ -233,6 +742,31 @@ where
                 }
             });
         }
+        opts::SubCmd::Differential { timeout, .. } => {
+            let scenario = scenario.expect("the scenario is loaded for differential runs");
+            let model_summaries = differential::converge_model(&model, &scenario.namespace)
+                .expect("Failed to run the scenario through the model");
+            let rt = Runtime::new().unwrap();
+            let cluster_summaries = rt
+                .block_on(differential::run_cluster(
+                    &scenario,
+                    Duration::from_secs(timeout),
+                ))
+                .expect("Failed to run the scenario against the cluster");
+            let differences = differential::differences(&model_summaries, &cluster_summaries);
+            for (step, diff) in &differences {
+                println!("Step {step} differs:");
+                println!("{diff}");
+            }
+            println!(
+                "{} of {} steps differ between the model and the cluster",
+                differences.len(),
+                model_summaries.len().max(cluster_summaries.len())
+            );
+            if !differences.is_empty() {
+                std::process::exit(1);
+            }
+        }
         opts::SubCmd::ControllerManager {} => {
             let rt = Runtime::new().unwrap();
             rt.block_on(async {
@@ -246,4 +780,16 @@ where
             });
         }
     }
+    if model.stop.load(std::sync::atomic::Ordering::Relaxed) {
+        if let Some(path) = deepest.encoded_path() {
+            println!(
+                "Stopped early with paths {} steps deep, to step through the deepest try re-running with `tui {}`",
+                deepest.depth(),
+                path
+            );
+        }
+    }
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.save().expect("Failed to write the checkpoint");
+    }
 }
@@ -0,0 +1,147 @@
+//! Export of states of the model as manifests, so that a configuration the checker found a
+//! problem with can be applied to a test cluster to reproduce it.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::resources::{GroupVersionKind, Meta};
+use crate::state::RawState;
+
+/// Fields of the metadata that the api server sets, which it won't take from a client.
+const SERVER_FIELDS: [&str; 8] = [
+    "uid",
+    "resourceVersion",
+    "generation",
+    "creationTimestamp",
+    "deletionTimestamp",
+    "deletionGracePeriodSeconds",
+    "managedFields",
+    "ownerReferences",
+];
+
+/// A resource of the state as a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub kind: &'static str,
+    pub namespace: String,
+    pub name: String,
+    pub yaml: String,
+}
+
+impl Manifest {
+    /// The name of the file for the manifest, prefixed by its position in the order to apply
+    /// them in.
+    pub fn file_name(&self, index: usize) -> String {
+        let kind = self.kind.to_lowercase();
+        if self.namespace.is_empty() {
+            format!("{index:03}-{kind}-{}.yaml", self.name)
+        } else {
+            format!("{index:03}-{kind}-{}-{}.yaml", self.namespace, self.name)
+        }
+    }
+}
+
+/// Remove the fields that the api server sets from the metadata in the value, including that of
+/// the templates within it.
+fn strip_server_fields(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Object(metadata)) = object.get_mut("metadata") {
+                for field in SERVER_FIELDS {
+                    metadata.remove(field);
+                }
+            }
+            object.values_mut().for_each(strip_server_fields);
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_server_fields),
+        _ => {}
+    }
+}
+
+fn manifest<T: Meta + Serialize>(gvk: GroupVersionKind, resource: &T) -> Option<Manifest> {
+    let metadata = resource.metadata();
+    // the controllers of the cluster create what the model's controllers did
+    if metadata.owner_references.iter().any(|o| o.controller) {
+        return None;
+    }
+    let mut value = serde_json::to_value(resource).ok()?;
+    strip_server_fields(&mut value);
+    let Value::Object(object) = value else {
+        return None;
+    };
+    let mut manifest = serde_json::Map::new();
+    manifest.insert("apiVersion".to_owned(), gvk.api_version().into());
+    manifest.insert("kind".to_owned(), gvk.kind.into());
+    manifest.extend(object.into_iter().filter(|(field, _)| field != "status"));
+    Some(Manifest {
+        kind: gvk.kind,
+        namespace: metadata.namespace.clone(),
+        name: metadata.name.clone(),
+        yaml: serde_yaml::to_string(&manifest).ok()?,
+    })
+}
+
+/// The resources of the state as manifests, in an order that they can be applied in.
+///
+/// THEMELIOS: Nodes, leases and controller revisions are left out, being made by the kubelets and
+/// controllers of the cluster, as are resources with a controlling owner, which the controllers
+/// will recreate from their owners. Statuses and the fields of the metadata that the api server
+/// sets are dropped, and pods lose the nodes they were bound to so that the cluster's scheduler
+/// can place them.
+pub fn manifests(state: &RawState) -> Vec<Manifest> {
+    let mut out = Vec::new();
+    macro_rules! add {
+        ($($field:ident: $kind:ident),* $(,)?) => {
+            $(
+                out.extend(
+                    state
+                        .$field
+                        .iter()
+                        .filter_map(|r| manifest(crate::resources::$kind::GVK, r)),
+                );
+            )*
+        };
+    }
+    add!(
+        namespaces: Namespace,
+        priority_classes: PriorityClass,
+        storage_classes: StorageClass,
+        roles: Role,
+        role_bindings: RoleBinding,
+        config_maps: ConfigMap,
+        secrets: Secret,
+        services: Service,
+        endpoints: Endpoints,
+        persistent_volumes: PersistentVolume,
+        persistent_volume_claims: PersistentVolumeClaim,
+        deployments: Deployment,
+        statefulsets: StatefulSet,
+        replicasets: ReplicaSet,
+        replication_controllers: ReplicationController,
+        cronjobs: CronJob,
+        jobs: Job,
+    );
+    out.extend(state.pods.iter().filter_map(|pod| {
+        let mut pod = pod.clone();
+        pod.spec.node_name = None;
+        manifest(crate::resources::Pod::GVK, &pod)
+    }));
+    add!(horizontal_pod_autoscalers: HorizontalPodAutoscaler);
+    out
+}
+
+/// Write the resources of the state as manifests to files in the directory, creating it if
+/// needed, returning the paths written in the order to apply them in.
+pub fn export(state: &RawState, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for (index, manifest) in manifests(state).into_iter().enumerate() {
+        let path = dir.join(manifest.file_name(index));
+        std::fs::write(&path, manifest.yaml)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
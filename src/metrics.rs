@@ -0,0 +1,168 @@
+//! Progress of a check exposed as Prometheus metrics over HTTP, for monitoring long running
+//! checks.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use axum::routing::get;
+use axum::Router;
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::{Expectation, Model};
+use tokio::runtime::Runtime;
+use tracing::info;
+
+use crate::report::property_holds;
+
+/// The latest progress of a check, shared between the reporter updating it and the server
+/// exposing it.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Mutex<MetricsData>>);
+
+#[derive(Debug, Clone, Default)]
+struct MetricsData {
+    data: Option<ReportData>,
+    properties: BTreeMap<&'static str, Expectation>,
+    discoveries: BTreeSet<&'static str>,
+}
+
+impl Metrics {
+    /// Metrics for checking the model, with every property yet to be discovered.
+    pub fn new<M: Model>(model: &M) -> Self {
+        let properties = model
+            .properties()
+            .iter()
+            .map(|p| (p.name, p.expectation.clone()))
+            .collect();
+        Self(Arc::new(Mutex::new(MetricsData {
+            data: None,
+            properties,
+            discoveries: BTreeSet::new(),
+        })))
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let metrics = self.0.lock().unwrap();
+        let data = metrics.data.as_ref();
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, value: f64| {
+            writeln!(out, "# HELP {name} {help}").unwrap();
+            writeln!(out, "# TYPE {name} gauge").unwrap();
+            writeln!(out, "{name} {value}").unwrap();
+        };
+        gauge(
+            "themelios_states",
+            "States generated so far, including repeats.",
+            data.map_or(0., |d| d.total_states as f64),
+        );
+        gauge(
+            "themelios_unique_states",
+            "Unique states generated so far.",
+            data.map_or(0., |d| d.unique_states as f64),
+        );
+        gauge(
+            "themelios_max_depth",
+            "Deepest state reached so far.",
+            data.map_or(0., |d| d.max_depth as f64),
+        );
+        gauge(
+            "themelios_duration_seconds",
+            "Time spent checking so far.",
+            data.map_or(0., |d| d.duration.as_secs_f64()),
+        );
+        gauge(
+            "themelios_done",
+            "Whether the check has finished.",
+            if data.map_or(false, |d| d.done) {
+                1.
+            } else {
+                0.
+            },
+        );
+        gauge(
+            "themelios_discoveries",
+            "Properties with a discovery so far.",
+            metrics.discoveries.len() as f64,
+        );
+
+        writeln!(
+            out,
+            "# HELP themelios_property_holds Whether the property holds given the discoveries so far."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE themelios_property_holds gauge").unwrap();
+        for (name, expectation) in &metrics.properties {
+            let holds = property_holds(expectation, metrics.discoveries.contains(name));
+            writeln!(
+                out,
+                "themelios_property_holds{{property=\"{}\",expectation=\"{:?}\"}} {}",
+                escape_label(name),
+                expectation,
+                u8::from(holds)
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+/// Escape a label value for the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Updates the metrics from the reports of the check, properties holding as in
+/// [`holds_until_done`](crate::report::holds_until_done) until it is done.
+#[derive(Debug, Clone)]
+pub struct MetricsReporter {
+    metrics: Metrics,
+}
+
+impl MetricsReporter {
+    pub fn new(metrics: Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<M> Reporter<M> for MetricsReporter
+where
+    M: Model,
+{
+    fn report_checking(&mut self, data: ReportData) {
+        self.metrics.0.lock().unwrap().data = Some(data);
+    }
+
+    fn report_discoveries(&mut self, discoveries: BTreeMap<&'static str, ReportDiscovery<M>>)
+    where
+        <M as Model>::Action: std::fmt::Debug,
+        <M as Model>::State: std::fmt::Debug + std::hash::Hash,
+    {
+        self.metrics.0.lock().unwrap().discoveries = discoveries.into_keys().collect();
+    }
+}
+
+/// Serve the metrics at `/metrics` on the port from a background thread, for as long as the
+/// process runs.
+pub fn serve(metrics: Metrics, port: u16) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let app = Router::new().route(
+                "/metrics",
+                get(move || {
+                    let metrics = metrics.clone();
+                    async move { metrics.render() }
+                }),
+            );
+            let address = format!("127.0.0.1:{port}");
+            info!("Serving metrics on {address}");
+            let listener = tokio::net::TcpListener::bind(address).await.unwrap();
+            axum::serve(listener, app).await.unwrap();
+        });
+    })
+}
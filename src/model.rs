@@ -1,15 +1,37 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use stateright::{Expectation, Property};
 
 use crate::{
-    abstract_model::{AbstractModel, AbstractModelCfg},
+    abstract_model::{
+        AbstractModel, AbstractModelCfg, ActionKind, ControllerScope, PropertySelection,
+    },
+    arbitrary_client::ArbitraryClientCfg,
     controller::{
-        job::JobController, podgc::PodGCController, Controllers, DeploymentController,
-        NodeController, ReplicaSetController, SchedulerController, StatefulSetController,
+        job::JobController, podgc::PodGCController, scheduler::SchedulerProfile, Controller,
+        Controllers, CronJobController, DeploymentController, EndpointsController, HPAController,
+        NamespaceController, NodeController, NodeLifecycleController,
+        PersistentVolumeBinderController, ProvisionerController, ReplicaSetController,
+        ReplicationManager, SchedulerController, StatefulSetController,
+    },
+    controller_properties::{
+        self, deployment, generation, leader_election, ownership, partition, quiescence, rbac,
+        relist, shadow, status, upgrade,
+    },
+    events::EventRecording,
+    rbac::Role,
+    recording::ClientOperation,
+    state::{
+        history::{eventual::ReplicaMerge, Compaction, ConsistencySetup, SessionGuarantee},
+        RawState, State,
     },
-    controller_properties::ControllerProperties,
-    state::{history::ConsistencySetup, RawState, State},
+    user_properties::{self, UserProperty},
 };
 
+/// The number of steps that fair controllers can be enabled for before they have to step, unless
+/// configured otherwise.
+pub const DEFAULT_FAIRNESS_BOUND: usize = 3;
+
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
 #[derive(Clone, Default)]
@@ -20,14 +42,121 @@ pub struct OrchestrationModelCfg {
     pub consistency_level: ConsistencySetup,
     /// The number of schedulers to run.
     pub schedulers: usize,
+    /// Additional schedulers to run, one for each profile, alongside the default ones.
+    pub scheduler_profiles: Vec<SchedulerProfile>,
     /// The number of nodes to run.
     pub nodes: usize,
+    /// The number of times that each node restarts the containers of a pod after they exit.
+    pub container_restarts: u32,
+    /// Whether nodes pull the images of pods before starting them, the pulls possibly failing.
+    pub image_pulls: bool,
     /// The number of replicaset controllers to run.
     pub replicaset_controllers: usize,
+    pub replicationcontroller_controllers: usize,
     pub deployment_controllers: usize,
     pub statefulset_controllers: usize,
     pub job_controllers: usize,
+    pub cronjob_controllers: usize,
+    pub hpa_controllers: usize,
+    pub nodelifecycle_controllers: usize,
+    pub namespace_controllers: usize,
+    pub endpoints_controllers: usize,
+    pub pvbinder_controllers: usize,
+    pub provisioner_controllers: usize,
     pub podgc_controllers: usize,
+    /// Map each controller to the implementation it can be upgraded to mid-run, if any.
+    /// Only available through the library, as there is no way to name another implementation on
+    /// the command line.
+    #[derivative(Debug = "ignore")]
+    pub controller_upgrade: Option<fn(&Controllers) -> Option<Controllers>>,
+    /// Map each controller to a shadow implementation to compare its actions against, if any.
+    #[derivative(Debug = "ignore")]
+    pub controller_shadow: Option<fn(&Controllers) -> Option<Controllers>>,
+    /// Map each controller to the role restricting its actions, if any.
+    #[derivative(Debug = "ignore")]
+    pub controller_roles: Option<fn(&Controllers) -> Option<Role>>,
+    /// Have controllers act under their [`crate::rbac::identity`], authorized by the roles bound
+    /// to it in the state.
+    /// A role and binding is added to the initial state for each identity that doesn't have one,
+    /// from the role of the controller or its [`crate::rbac::default_role`].
+    pub rbac_bindings: bool,
+    /// Map each controller to the guarantees of its session, if stronger than monotonic reads.
+    #[derivative(Debug = "ignore")]
+    pub controller_sessions: Option<fn(&Controllers) -> Option<SessionGuarantee>>,
+    /// Names of the controllers not to run, even when they are counted above.
+    pub disabled_controllers: BTreeSet<String>,
+    /// The scope of the resources seen by the controllers with each name, those without one see
+    /// them all.
+    /// Scopes that leave out the lease of a controller keep it from electing a leader.
+    pub controller_scopes: BTreeMap<String, ControllerScope>,
+    /// Kinds of actions not to explore, such as node restarts.
+    pub disabled_actions: BTreeSet<ActionKind>,
+    /// Names of the controllers that are weakly fair, so that liveness properties don't fail just
+    /// because they are never scheduled.
+    pub fair_controllers: BTreeSet<String>,
+    /// The number of steps that a fair controller can be enabled for before it has to step.
+    pub fairness_bound: usize,
+    /// How replicas merge their resources when the state is eventually consistent.
+    pub replica_merge: ReplicaMerge,
+    /// How much of the history of the state to keep.
+    pub compaction: Compaction,
+    /// Whether to inject relists that are missing a kind of resource into controllers.
+    pub relist_faults: bool,
+    /// Whether controllers read through watch caches that are only brought up to date by resyncs.
+    pub watch_caches: bool,
+    /// Whether controllers send their actions to the API server as messages that can be delayed,
+    /// reordered, dropped and duplicated, with the number of times that they resend dropped ones.
+    /// When not given actions are applied as soon as they are taken.
+    pub message_retries: Option<u32>,
+    /// Whether controllers retry writes that the API server rejects through their work queues,
+    /// with delays growing with each failure.
+    pub rate_limited_retries: bool,
+    /// Seconds after a node last renewed its lease, as it does each time the clock advances, that
+    /// the node lifecycle controller marks it as not ready.
+    /// When not given nodes don't heartbeat and are marked as soon as they fail.
+    pub node_monitor_grace_period: Option<u64>,
+    /// Whether nodes can be partitioned from the control plane.
+    pub node_partitions: bool,
+    /// Whether nodes can crash and be restarted.
+    pub node_crashes: bool,
+    /// Whether controllers other than nodes can crash, losing their local state, and be restarted
+    /// later.
+    pub controller_crashes: bool,
+    /// Seconds that the clock of each controller runs ahead of that of the controller before it,
+    /// so that no two of them agree on the time.
+    pub clock_skew: u64,
+    /// Whether nodes can be drained, as for maintenance, and uncordoned again.
+    pub node_drains: bool,
+    /// Whether nodes can come under memory and disk pressure, evicting their pods.
+    pub node_pressure: bool,
+    /// Whether replicas of controllers elect a leader through a lease to act.
+    pub leader_election: bool,
+    /// Whether controllers record events, and whether they are part of the fingerprint.
+    pub events: EventRecording,
+    /// Whether to check that controllers can always settle once clients stop acting.
+    pub quiescence: bool,
+    /// Whether to check that controllers never change specs through status updates.
+    pub status_writes: bool,
+    /// Whether to check that controllers don't orphan the pods they own.
+    pub ownership: bool,
+    /// Whether to check that observed generations only move forward.
+    pub generations: bool,
+    /// Names of deployments to check keep `replicas - maxUnavailable` replicas available during
+    /// rolling updates.
+    pub rollout_availability: BTreeSet<String>,
+    /// Recorded client operations to take in order, such as those replayed from a
+    /// [`crate::recording::Recording`].
+    pub client_operations: Vec<ClientOperation>,
+    /// What the arbitrary client may change.
+    pub arbitrary_client: ArbitraryClientCfg,
+    /// Properties loaded at runtime, written in the language of [`user_properties`].
+    pub user_properties: Vec<UserProperty>,
+    /// Names of the controllers whose bundles of properties to check, from
+    /// [`controller_properties::BUNDLES`].
+    /// Those of the controllers that run are checked when not given.
+    pub property_bundles: Option<BTreeSet<String>>,
+    /// Which of the properties to check, and which only warn when they don't hold.
+    pub property_selection: PropertySelection,
 
     #[derivative(Debug = "ignore")]
     pub properties: Vec<Property<AbstractModel>>,
@@ -43,12 +172,58 @@ impl OrchestrationModelCfg {
             initial_state,
             consistency_level,
             schedulers: controllers,
+            scheduler_profiles: Vec::new(),
             nodes: controllers,
+            container_restarts: 1,
+            image_pulls: false,
             replicaset_controllers: controllers,
+            replicationcontroller_controllers: controllers,
             deployment_controllers: controllers,
             statefulset_controllers: controllers,
             job_controllers: controllers,
+            cronjob_controllers: controllers,
+            hpa_controllers: controllers,
+            nodelifecycle_controllers: controllers,
+            namespace_controllers: controllers,
+            endpoints_controllers: controllers,
+            pvbinder_controllers: controllers,
+            provisioner_controllers: controllers,
             podgc_controllers: controllers,
+            controller_upgrade: None,
+            controller_shadow: None,
+            controller_roles: None,
+            rbac_bindings: false,
+            controller_sessions: None,
+            disabled_controllers: BTreeSet::new(),
+            controller_scopes: BTreeMap::new(),
+            disabled_actions: BTreeSet::new(),
+            fair_controllers: BTreeSet::new(),
+            fairness_bound: DEFAULT_FAIRNESS_BOUND,
+            replica_merge: ReplicaMerge::default(),
+            compaction: Compaction::Disabled,
+            relist_faults: false,
+            watch_caches: false,
+            message_retries: None,
+            rate_limited_retries: false,
+            node_monitor_grace_period: None,
+            node_partitions: false,
+            node_crashes: false,
+            controller_crashes: false,
+            clock_skew: 0,
+            node_drains: false,
+            node_pressure: false,
+            leader_election: false,
+            events: EventRecording::Disabled,
+            quiescence: false,
+            status_writes: false,
+            ownership: false,
+            generations: false,
+            rollout_availability: BTreeSet::new(),
+            client_operations: Vec::new(),
+            arbitrary_client: ArbitraryClientCfg::default(),
+            user_properties: Vec::new(),
+            property_bundles: None,
+            property_selection: PropertySelection::default(),
             properties: Vec::new(),
         }
     }
@@ -60,18 +235,56 @@ impl OrchestrationModelCfg {
             controllers: Vec::new(),
             initial_state: self.initial_state,
             consistency_level: self.consistency_level,
+            upgrades: BTreeMap::new(),
+            shadows: BTreeMap::new(),
+            relist_faults: self.relist_faults,
+            watch_caches: self.watch_caches,
+            message_retries: self.message_retries,
+            rate_limited_retries: self.rate_limited_retries,
+            node_partitions: self.node_partitions,
+            node_crashes: self.node_crashes,
+            controller_crashes: self.controller_crashes,
+            clock_skews: BTreeMap::new(),
+            node_drains: self.node_drains,
+            node_pressure: self.node_pressure,
+            leader_election: self.leader_election,
+            roles: BTreeMap::new(),
+            identities: BTreeMap::new(),
+            events: self.events,
+            sessions: BTreeMap::new(),
+            replica_merge: self.replica_merge,
+            compaction: self.compaction,
+            scopes: BTreeMap::new(),
+            disabled_actions: self.disabled_actions,
+            fair_controllers: BTreeSet::new(),
+            fairness_bound: self.fairness_bound,
+            rollout_availability: self.rollout_availability,
+            client_operations: self.client_operations,
+            arbitrary_client: self.arbitrary_client,
+            user_properties: self.user_properties,
+            property_selection: self.property_selection,
             properties: self.properties,
         };
 
         for i in 0..self.nodes {
             cfg.controllers.push(Controllers::Node(NodeController {
                 name: format!("node-{i}"),
+                max_restarts: self.container_restarts,
+                lease_duration_seconds: self.node_monitor_grace_period,
+                image_pulls: self.image_pulls,
             }));
         }
 
         for _ in 0..self.schedulers {
             cfg.controllers
-                .push(Controllers::Scheduler(SchedulerController));
+                .push(Controllers::Scheduler(SchedulerController::default()));
+        }
+
+        for profile in &self.scheduler_profiles {
+            cfg.controllers
+                .push(Controllers::Scheduler(SchedulerController::new(
+                    profile.clone(),
+                )));
         }
 
         for _ in 0..self.replicaset_controllers {
@@ -79,6 +292,11 @@ impl OrchestrationModelCfg {
                 .push(Controllers::ReplicaSet(ReplicaSetController));
         }
 
+        for _ in 0..self.replicationcontroller_controllers {
+            cfg.controllers
+                .push(Controllers::ReplicationController(ReplicationManager));
+        }
+
         for _ in 0..self.deployment_controllers {
             cfg.controllers
                 .push(Controllers::Deployment(DeploymentController));
@@ -93,11 +311,138 @@ impl OrchestrationModelCfg {
             cfg.controllers.push(Controllers::Job(JobController));
         }
 
+        for _ in 0..self.cronjob_controllers {
+            cfg.controllers
+                .push(Controllers::CronJob(CronJobController));
+        }
+
+        for _ in 0..self.hpa_controllers {
+            cfg.controllers
+                .push(Controllers::HorizontalPodAutoscaler(HPAController));
+        }
+
+        for _ in 0..self.nodelifecycle_controllers {
+            cfg.controllers
+                .push(Controllers::NodeLifecycle(NodeLifecycleController {
+                    node_monitor_grace_period: self.node_monitor_grace_period,
+                }));
+        }
+
+        for _ in 0..self.namespace_controllers {
+            cfg.controllers
+                .push(Controllers::Namespace(NamespaceController));
+        }
+
+        for _ in 0..self.endpoints_controllers {
+            cfg.controllers
+                .push(Controllers::Endpoints(EndpointsController));
+        }
+
+        for _ in 0..self.pvbinder_controllers {
+            cfg.controllers.push(Controllers::PersistentVolumeBinder(
+                PersistentVolumeBinderController,
+            ));
+        }
+
+        for _ in 0..self.provisioner_controllers {
+            cfg.controllers
+                .push(Controllers::Provisioner(ProvisionerController));
+        }
+
         for _ in 0..self.podgc_controllers {
             cfg.controllers.push(Controllers::PodGC(PodGCController));
         }
 
-        AbstractModel::new(cfg)
+        cfg.controllers
+            .retain(|c| !self.disabled_controllers.contains(&c.name()));
+
+        if let Some(upgrade) = self.controller_upgrade {
+            for (i, controller) in cfg.controllers.iter().enumerate() {
+                if let Some(upgraded) = upgrade(controller) {
+                    cfg.upgrades.insert(i, upgraded);
+                }
+            }
+        }
+
+        if let Some(shadow) = self.controller_shadow {
+            for (i, controller) in cfg.controllers.iter().enumerate() {
+                if let Some(shadowed) = shadow(controller) {
+                    cfg.shadows.insert(i, shadowed);
+                }
+            }
+        }
+
+        if let Some(role) = self.controller_roles {
+            for (i, controller) in cfg.controllers.iter().enumerate() {
+                if let Some(role) = role(controller) {
+                    cfg.roles.insert(i, role);
+                }
+            }
+        }
+
+        if self.rbac_bindings {
+            for (i, controller) in cfg.controllers.iter().enumerate() {
+                let subject = crate::rbac::identity(controller);
+                let role = cfg
+                    .roles
+                    .get(&i)
+                    .cloned()
+                    .or_else(|| crate::rbac::default_role(controller));
+                if let Some(role) = role {
+                    let (role, binding) = crate::rbac::bind(&subject, &role);
+                    let state = &mut cfg.initial_state;
+                    if !state
+                        .roles
+                        .has_in(&role.metadata.namespace, &role.metadata.name)
+                    {
+                        state.set_roles([role]);
+                    }
+                    if !state
+                        .role_bindings
+                        .has_in(&binding.metadata.namespace, &binding.metadata.name)
+                    {
+                        state.set_role_bindings([binding]);
+                    }
+                }
+                cfg.identities.insert(i, subject);
+            }
+        }
+
+        if let Some(session) = self.controller_sessions {
+            for (i, controller) in cfg.controllers.iter().enumerate() {
+                if let Some(guarantee) = session(controller) {
+                    cfg.sessions.insert(i, guarantee);
+                }
+            }
+        }
+
+        if self.clock_skew > 0 {
+            for i in 1..cfg.controllers.len() {
+                cfg.clock_skews.insert(i, i as u64 * self.clock_skew);
+            }
+        }
+
+        for (i, controller) in cfg.controllers.iter().enumerate() {
+            if let Some(scope) = self.controller_scopes.get(&controller.name()) {
+                cfg.scopes.insert(i, scope.clone());
+            }
+            if self.fair_controllers.contains(&controller.name()) {
+                cfg.fair_controllers.insert(i);
+            }
+        }
+
+        let model = AbstractModel::new(cfg);
+        let names = model
+            .all_properties()
+            .iter()
+            .map(|p| p.name)
+            .collect::<BTreeSet<_>>();
+        for name in model.property_selection.names() {
+            if !names.contains(name.as_str()) {
+                panic!("No property named {name:?}");
+            }
+        }
+        model
     }
 
     pub fn add_property(
@@ -113,34 +458,108 @@ impl OrchestrationModelCfg {
         })
     }
 
+    /// Add the properties, skipping any with the same name as one already added so that bundles
+    /// can overlap.
     pub fn add_properties(
         &mut self,
         properties: impl IntoIterator<Item = Property<AbstractModel>>,
     ) {
-        self.properties.extend(properties)
+        for property in properties {
+            if !self.properties.iter().any(|p| p.name == property.name) {
+                self.properties.push(property)
+            }
+        }
+    }
+
+    /// Whether the given number of controllers with the name run, rather than being disabled.
+    fn runs(&self, count: usize, name: &str) -> bool {
+        count > 0 && !self.disabled_controllers.contains(name)
+    }
+
+    /// The names of the controllers that run, other than schedulers.
+    fn running_controllers(&self) -> Vec<String> {
+        [
+            (self.replicaset_controllers, ReplicaSetController.name()),
+            (
+                self.replicationcontroller_controllers,
+                ReplicationManager.name(),
+            ),
+            (self.deployment_controllers, DeploymentController.name()),
+            (self.statefulset_controllers, StatefulSetController.name()),
+            (self.job_controllers, JobController.name()),
+            (self.cronjob_controllers, CronJobController.name()),
+            (self.hpa_controllers, HPAController.name()),
+            (
+                self.nodelifecycle_controllers,
+                NodeLifecycleController::default().name(),
+            ),
+            (self.namespace_controllers, NamespaceController.name()),
+            (self.endpoints_controllers, EndpointsController.name()),
+            (
+                self.pvbinder_controllers,
+                PersistentVolumeBinderController.name(),
+            ),
+            (self.provisioner_controllers, ProvisionerController.name()),
+            (self.podgc_controllers, PodGCController.name()),
+            (self.nodes, "Node".to_owned()),
+        ]
+        .into_iter()
+        .filter(|(count, name)| self.runs(*count, name))
+        .map(|(_, name)| name)
+        .collect()
     }
 
     fn auto_add_properties(&mut self) {
-        if self.replicaset_controllers > 0 {
-            self.add_properties(ReplicaSetController::properties())
+        let bundles: Vec<String> = match &self.property_bundles {
+            Some(bundles) => bundles.iter().cloned().collect(),
+            None => {
+                let mut bundles = self.running_controllers();
+                if self.schedulers > 0 || !self.scheduler_profiles.is_empty() {
+                    bundles.push("Scheduler".to_owned());
+                }
+                bundles
+            }
+        };
+        for name in bundles {
+            let bundle = controller_properties::for_controller(&name)
+                .unwrap_or_else(|| panic!("No property bundle for the controller {name:?}"));
+            self.add_properties(bundle)
+        }
+        if self.controller_upgrade.is_some() {
+            self.add_properties(upgrade::properties())
+        }
+        if self.controller_shadow.is_some() {
+            self.add_properties(shadow::properties())
+        }
+        if self.relist_faults {
+            self.add_properties(relist::properties())
+        }
+        if self.node_partitions {
+            self.add_properties(partition::properties())
+        }
+        if self.leader_election {
+            self.add_properties(leader_election::properties())
+        }
+        if self.controller_roles.is_some() || self.rbac_bindings {
+            self.add_properties(rbac::properties())
         }
-        if self.deployment_controllers > 0 {
-            self.add_properties(DeploymentController::properties())
+        if self.quiescence {
+            self.add_properties(quiescence::properties())
         }
-        if self.statefulset_controllers > 0 {
-            self.add_properties(StatefulSetController::properties())
+        if self.status_writes {
+            self.add_properties(status::properties())
         }
-        if self.job_controllers > 0 {
-            self.add_properties(JobController::properties())
+        if self.ownership {
+            self.add_properties(ownership::properties())
         }
-        if self.podgc_controllers > 0 {
-            self.add_properties(PodGCController::properties())
+        if self.generations {
+            self.add_properties(generation::properties())
         }
-        if self.nodes > 0 {
-            self.add_properties(NodeController::properties())
+        if !self.rollout_availability.is_empty() {
+            self.add_properties(deployment::availability_properties())
         }
-        if self.schedulers > 0 {
-            self.add_properties(SchedulerController::properties())
+        if !self.user_properties.is_empty() {
+            self.add_properties(user_properties::properties(&self.user_properties))
         }
     }
 }
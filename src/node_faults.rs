@@ -0,0 +1,224 @@
+//! Nodes failing and being taken out of service, as the changes that they make to the state.
+
+use crate::{
+    abstract_model::ControllerAction,
+    controller::util::is_pod_active,
+    resources::{
+        ConditionStatus, Node, NodeCondition, NodeConditionType, Pod, PodCondition,
+        PodConditionType, PodPhase, PodRestartPolicy, Taint, TaintEffect, Time,
+    },
+    state::StateView,
+};
+
+/// The node with its ready condition unknown, as once it stops posting its status.
+///
+/// THEMELIOS: unless nodes heartbeat through their leases, the node lifecycle controller noticing
+/// that they've stopped is folded into the node going away itself.
+pub fn not_ready(node: &Node) -> Node {
+    let mut node = node.clone();
+    node.status
+        .conditions
+        .retain(|c| c.r#type != NodeConditionType::Ready);
+    node.status.conditions.push(NodeCondition {
+        r#type: NodeConditionType::Ready,
+        status: ConditionStatus::Unknown,
+        reason: "NodeStatusUnknown".to_owned(),
+        message: "Kubelet stopped posting node status.".to_owned(),
+        ..Default::default()
+    });
+    node
+}
+
+/// The active pods bound to the node.
+fn pods_on<'a>(view: &'a StateView, node: &'a str) -> impl Iterator<Item = &'a Pod> + 'a {
+    view.pods
+        .iter()
+        .filter(move |p| p.spec.node_name.as_deref() == Some(node) && is_pod_active(p))
+}
+
+/// The changes from the node crashing, taking its kubelet and the containers it ran down with it.
+///
+/// Pods that won't be restarted fail, the rest have an unknown phase and aren't ready until the
+/// node comes back and starts them again.
+///
+/// THEMELIOS: the kubelet can't report anything once it has crashed, so the statuses that its pods
+/// would end up with are set as it goes down.
+pub fn crash(view: &StateView, node: &str) -> Vec<ControllerAction> {
+    let mut actions = Vec::new();
+    if let Some(n) = view.nodes.get(node) {
+        actions.push(ControllerAction::UpdateNode(not_ready(n)));
+    }
+    for pod in pods_on(view, node) {
+        let mut pod = pod.clone();
+        if pod.spec.restart_policy == Some(PodRestartPolicy::Never) {
+            pod.status.phase = PodPhase::Failed;
+            pod.status.conditions.clear();
+        } else {
+            pod.status.phase = PodPhase::Unknown;
+            for condition in &mut pod.status.conditions {
+                if condition.r#type == PodConditionType::Ready {
+                    condition.status = ConditionStatus::False;
+                }
+            }
+        }
+        for status in &mut pod.status.container_statuses {
+            status.ready = false;
+        }
+        actions.push(ControllerAction::UpdatePod(pod));
+    }
+    actions
+}
+
+/// The changes from draining the node as `kubectl drain --force` does, cordoning it so that no
+/// more pods are scheduled to it and then evicting those that it runs.
+///
+/// THEMELIOS: there are no disruption budgets for evictions to respect, so they are plain deletes
+/// with the grace period of the pod.
+pub fn drain(view: &StateView, node: &str) -> Vec<ControllerAction> {
+    let Some(n) = view.nodes.get(node) else {
+        return Vec::new();
+    };
+    let mut cordoned = n.clone();
+    cordoned.spec.unschedulable = true;
+    let mut actions = vec![ControllerAction::UpdateNode(cordoned)];
+    actions.extend(pods_on(view, node).map(|pod| ControllerAction::SoftDeletePod(pod.clone())));
+    actions
+}
+
+/// The change letting pods be scheduled to the node again after it was drained.
+pub fn uncordon(view: &StateView, node: &str) -> Option<ControllerAction> {
+    let mut node = view.nodes.get(node)?.clone();
+    node.spec.unschedulable = false;
+    Some(ControllerAction::UpdateNode(node))
+}
+
+/// What the node is short of under a pressure condition.
+struct Pressure {
+    /// The resource that the node is low on.
+    resource: &'static str,
+    /// The taint that keeps pods from being scheduled to the node meanwhile.
+    taint: &'static str,
+    /// The reasons for the condition when it is true and when it is false.
+    reasons: [&'static str; 2],
+}
+
+/// The pressure that the node condition is about, none for conditions that aren't pressure.
+fn pressure_of(condition: &NodeConditionType) -> Option<Pressure> {
+    match condition {
+        NodeConditionType::MemoryPressure => Some(Pressure {
+            resource: "memory",
+            taint: "node.kubernetes.io/memory-pressure",
+            reasons: ["KubeletHasInsufficientMemory", "KubeletHasSufficientMemory"],
+        }),
+        NodeConditionType::DiskPressure => Some(Pressure {
+            resource: "ephemeral-storage",
+            taint: "node.kubernetes.io/disk-pressure",
+            reasons: ["KubeletHasDiskPressure", "KubeletHasNoDiskPressure"],
+        }),
+        _ => None,
+    }
+}
+
+/// Whether the node has the pressure condition.
+pub fn under_pressure(node: &Node, condition: &NodeConditionType) -> bool {
+    node.status
+        .conditions
+        .iter()
+        .any(|c| &c.r#type == condition && c.status == ConditionStatus::True)
+}
+
+/// The active pods on the node in the order that the kubelet evicts them when it is under
+/// pressure: by their quality of service class, then their priority.
+///
+/// THEMELIOS: the resource usage of pods isn't modelled, so their class stands in for whether
+/// they use more than they request.
+pub fn eviction_order<'a>(view: &'a StateView, node: &'a str) -> Vec<&'a Pod> {
+    let mut pods = pods_on(view, node).collect::<Vec<_>>();
+    pods.sort_by_key(|p| {
+        let qos_class = p.status.qos_class.unwrap_or_else(|| p.qos_class());
+        (qos_class, p.spec.priority.unwrap_or_default())
+    });
+    pods
+}
+
+/// The changes from the node coming under the pressure condition, marking it as such and evicting
+/// the first of its pods in [`eviction_order`].
+/// Each time the pressure persists another pod is evicted.
+///
+/// THEMELIOS: the taint for the condition is added along with it, rather than by the node
+/// lifecycle controller. Evictions by the kubelet ignore disruption budgets, which aren't modelled
+/// anyway.
+pub fn pressure(
+    view: &StateView,
+    node: &str,
+    condition: &NodeConditionType,
+) -> Vec<ControllerAction> {
+    let (Some(n), Some(pressure)) = (view.nodes.get(node), pressure_of(condition)) else {
+        return Vec::new();
+    };
+    let mut actions = Vec::new();
+    if !under_pressure(n, condition) {
+        let mut n = n.clone();
+        n.status.conditions.retain(|c| &c.r#type != condition);
+        n.status.conditions.push(NodeCondition {
+            r#type: condition.clone(),
+            status: ConditionStatus::True,
+            reason: pressure.reasons[0].to_owned(),
+            message: format!("kubelet is low on {}", pressure.resource),
+            last_transition_time: Some(view.now()),
+            ..Default::default()
+        });
+        n.spec.taints.push(Taint {
+            effect: TaintEffect::NoSchedule,
+            key: pressure.taint.to_owned(),
+            time_added: Some(view.now()),
+            value: String::new(),
+        });
+        actions.push(ControllerAction::UpdateNode(n));
+    }
+    if let Some(pod) = eviction_order(view, node).first() {
+        let pod = evicted(pod, pressure.resource, view.now());
+        actions.push(ControllerAction::UpdatePod(pod));
+    }
+    actions
+}
+
+/// The pod having been evicted by its kubelet for the node running low on the resource.
+fn evicted(pod: &Pod, resource: &str, now: Time) -> Pod {
+    let mut pod = pod.clone();
+    pod.status.phase = PodPhase::Failed;
+    pod.status.conditions.clear();
+    pod.status.conditions.push(PodCondition {
+        status: ConditionStatus::True,
+        r#type: PodConditionType::DisruptionTarget,
+        last_probe_time: None,
+        last_transition_time: Some(now),
+        message: Some(format!("The node was low on resource: {resource}. ")),
+        reason: Some("TerminationByKubelet".to_owned()),
+    });
+    for status in &mut pod.status.container_statuses {
+        status.ready = false;
+    }
+    pod
+}
+
+/// The change from the pressure condition going away from the node, letting pods be scheduled to
+/// it again.
+pub fn relieve(
+    view: &StateView,
+    node: &str,
+    condition: &NodeConditionType,
+) -> Option<ControllerAction> {
+    let pressure = pressure_of(condition)?;
+    let mut node = view.nodes.get(node)?.clone();
+    for c in &mut node.status.conditions {
+        if &c.r#type == condition {
+            c.status = ConditionStatus::False;
+            c.reason = pressure.reasons[1].to_owned();
+            c.message = String::new();
+            c.last_transition_time = Some(view.now());
+        }
+    }
+    node.spec.taints.retain(|t| t.key != pressure.taint);
+    Some(ControllerAction::UpdateNode(node))
+}
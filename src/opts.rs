@@ -1,10 +1,39 @@
-use clap::Parser;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{CommandFactory, ErrorKind, Parser};
+use themelios::abstract_model::{ActionClass, ActionKind};
+use themelios::arbitrary_client::Mutation;
+use themelios::budget::Budget;
+use themelios::config::{config_values, flag_value, is_option, layer_args};
+use themelios::controller::scheduler::SchedulerProfile;
+use themelios::state::ResourceKind;
 
 #[derive(Parser, Debug)]
 pub struct Opts {
     #[clap(subcommand)]
     pub command: SubCmd,
 
+    /// YAML file of global options, keyed by their long flag (e.g. `nodes: 2`), can also be set with
+    /// `THEMELIOS_CONFIG`, or TOML when it ends in `.toml`.
+    /// Environment variables (e.g. `THEMELIOS_NODES`) override the file, and flags override both.
+    /// The file can also list the `resources` of the initial state, as manifests, and the
+    /// `properties` to check, as the lines of a properties file.
+    #[clap(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// The resources of the initial state from the config file, replacing those made from the
+    /// counts of the options.
+    #[clap(skip)]
+    pub resources: Option<Vec<serde_json::Value>>,
+
+    /// The properties listed in the config file.
+    #[clap(skip)]
+    pub config_properties: Vec<String>,
+
     /// The number of threads to run.
     /// Defaults to the number of CPUs the machine has, as reported by `num_cpus`.
     #[clap(long, short, global = true)]
@@ -22,6 +51,9 @@ pub struct Opts {
     #[clap(long, global = true, default_value = "1")]
     pub replicaset_controllers: usize,
 
+    #[clap(long, global = true, default_value = "0")]
+    pub replicationcontroller_controllers: usize,
+
     #[clap(long, global = true, default_value = "1")]
     pub deployments: u32,
 
@@ -40,23 +72,95 @@ pub struct Opts {
     #[clap(long, global = true, default_value = "1")]
     pub job_controllers: usize,
 
+    #[clap(long, global = true, default_value = "0")]
+    pub cronjob_controllers: usize,
+
+    #[clap(long, global = true, default_value = "0")]
+    pub hpa_controllers: usize,
+
+    #[clap(long, global = true, default_value = "0")]
+    pub nodelifecycle_controllers: usize,
+
+    #[clap(long, global = true, default_value = "0")]
+    pub namespace_controllers: usize,
+
+    #[clap(long, global = true, default_value = "0")]
+    pub endpoints_controllers: usize,
+
+    #[clap(long, global = true, default_value = "0")]
+    pub pvbinder_controllers: usize,
+
+    #[clap(long, global = true, default_value = "0")]
+    pub provisioner_controllers: usize,
+
     #[clap(long, global = true, default_value = "1")]
     pub podgc_controllers: usize,
 
     #[clap(long, short, global = true, default_value = "1")]
     pub schedulers: usize,
 
+    /// Additional schedulers to run, as `name[:least-allocated|most-allocated][:no-preemption][:gang]`.
+    #[clap(long, global = true)]
+    pub scheduler_profiles: Vec<SchedulerProfile>,
+
     #[clap(long, short, global = true, default_value = "1")]
     pub nodes: usize,
 
+    /// Times that nodes restart the containers of a pod after they exit, before leaving them in
+    /// CrashLoopBackOff.
+    #[clap(long, global = true, default_value = "1")]
+    pub container_restarts: u32,
+
+    /// Have nodes pull the images of pods before starting them, with pulls that can fail for a
+    /// while or for good.
+    #[clap(long, global = true)]
+    pub image_pulls: bool,
+
     /// Max depth for the check run, 0 is no limit.
     #[clap(long, global = true, default_value = "0")]
     pub max_depth: usize,
 
+    /// File to checkpoint the states explored by `check-dfs` and `check-bfs` to, resuming from it
+    /// when it was written for the same model.
+    #[clap(long, global = true)]
+    pub checkpoint_path: Option<PathBuf>,
+
+    /// File to write the results of the check to as JSON, including the properties and their
+    /// discoveries.
+    #[clap(long, global = true)]
+    pub json_report: Option<PathBuf>,
+
+    /// File to write the discoveries of the check to as HTML, with the actions and resource
+    /// changes along the path to each.
+    #[clap(long, global = true)]
+    pub html_report: Option<PathBuf>,
+
+    /// SQLite database to record the run in, alongside the other runs recorded there.
+    #[clap(long, global = true)]
+    pub sqlite_report: Option<PathBuf>,
+
+    /// Port to serve the progress of the check on as Prometheus metrics, at `/metrics`.
+    #[clap(long, global = true)]
+    pub metrics_port: Option<u16>,
+
+    /// Show the progress of the check in a terminal UI rather than printing it, where pressing
+    /// `q` stops the check early and still writes the reports.
+    #[clap(long, global = true)]
+    pub progress_tui: bool,
+
+    /// Print which kinds of controller action, and which branches of the controllers, were taken
+    /// during the check once it is done.
+    #[clap(long, global = true)]
+    pub coverage: bool,
+
     /// Model session consistency for the state.
     #[clap(long, global = true)]
     pub session: bool,
 
+    /// Model read-your-writes consistency for the state, without monotonic reads.
+    #[clap(long, global = true)]
+    pub read_your_writes: bool,
+
     /// Model optimistic linear consistency for the state.
     #[clap(long, global = true)]
     pub optimistic_linear: bool,
@@ -64,6 +168,268 @@ pub struct Opts {
     /// Model causal consistency for the state.
     #[clap(long, global = true)]
     pub causal: bool,
+
+    /// Model eventual consistency for the state, spread over the given number of replicas.
+    #[clap(long, global = true)]
+    pub eventual: Option<usize>,
+
+    /// Model a store replicated over the given number of members, one of which can be partitioned
+    /// from the rest.
+    #[clap(long, global = true)]
+    pub partitioned: Option<usize>,
+
+    /// Compact the history of the state, keeping the given number of latest revisions along with
+    /// any that controllers still refer to, or only those when zero.
+    #[clap(long, global = true)]
+    pub compact_history: Option<usize>,
+
+    /// Inject relists that are missing a kind of resource into controllers.
+    #[clap(long, global = true)]
+    pub relist_faults: bool,
+
+    /// Have controllers read through watch caches that lag behind the state until resynced.
+    #[clap(long, global = true)]
+    pub watch_caches: bool,
+
+    /// Have controllers send their actions to the API server as messages that can be delayed,
+    /// reordered, dropped and duplicated, resending dropped ones up to this many times.
+    #[clap(long, global = true)]
+    pub message_retries: Option<u32>,
+
+    /// Have controllers retry writes that fail after a delay that doubles with each failure.
+    #[clap(long, global = true)]
+    pub rate_limited_retries: bool,
+
+    /// Let nodes be partitioned from the control plane.
+    #[clap(long, global = true)]
+    pub node_partitions: bool,
+
+    /// Have nodes heartbeat by renewing their leases, being marked as not ready by the node
+    /// lifecycle controller once they have gone this many seconds without.
+    #[clap(long, global = true)]
+    pub node_monitor_grace_period: Option<u64>,
+
+    /// Let nodes crash, failing or losing track of their pods until they are restarted.
+    #[clap(long, global = true)]
+    pub node_crashes: bool,
+
+    /// Let controllers crash, losing their local state and taking no steps until restarted.
+    #[clap(long, global = true)]
+    pub controller_crashes: bool,
+
+    /// Let nodes be drained of their pods, as `kubectl drain` does, and uncordoned again.
+    #[clap(long, global = true)]
+    pub node_drains: bool,
+
+    /// Let nodes come under memory and disk pressure, their kubelets evicting pods by their QoS
+    /// class until it is relieved.
+    #[clap(long, global = true)]
+    pub node_pressure: bool,
+
+    /// Seconds that the clock of each controller runs ahead of that of the one before it, for the
+    /// time that it sees and the creation timestamps of what it creates.
+    #[clap(long, global = true, default_value = "0")]
+    pub clock_skew: u64,
+
+    /// Have replicas of controllers elect a leader through a lease, only the leader acting.
+    #[clap(long, global = true)]
+    pub leader_election: bool,
+
+    /// Restrict controllers to the default role for their kind, reporting grants that go unused.
+    #[clap(long, global = true)]
+    pub rbac: bool,
+
+    /// Have controllers act under service accounts, authorized by the roles bound to them in the
+    /// state, seeding the default ones.
+    #[clap(long, global = true)]
+    pub rbac_bindings: bool,
+
+    /// Record the events emitted by controllers, for inspecting traces.
+    #[clap(long, global = true)]
+    pub events: bool,
+
+    /// Make recorded events part of the fingerprint, distinguishing states that only differ in
+    /// their events.
+    #[clap(long, global = true, requires = "events")]
+    pub fingerprint_events: bool,
+
+    /// Check that controllers can always settle once clients stop acting, catching controllers
+    /// that reconcile forever.
+    #[clap(long, global = true)]
+    pub quiescence: bool,
+
+    /// Check that controllers never change the spec of a resource through its status subresource.
+    #[clap(long, global = true)]
+    pub status_writes: bool,
+
+    /// Check that controllers don't orphan the pods they own, whichever controllers run.
+    #[clap(long, global = true)]
+    pub ownership: bool,
+
+    /// Check that the observed generations of resources only move forward and never pass the
+    /// generation.
+    #[clap(long, global = true)]
+    pub generations: bool,
+
+    /// File of client operations recorded by `serve-cluster --record` to take in order, the
+    /// resources that it creates first replacing the generated ones other than nodes.
+    #[clap(long, global = true)]
+    pub replay: Option<PathBuf>,
+
+    /// File of resources from `kubectl get -o json` to start from, replacing the generated ones
+    /// other than nodes.
+    #[clap(long, global = true, conflicts_with = "snapshot_cluster")]
+    pub snapshot: Option<PathBuf>,
+
+    /// Start from the resources in the cluster of the kubeconfig, replacing the generated ones
+    /// other than nodes.
+    #[clap(long, global = true)]
+    pub snapshot_cluster: bool,
+
+    /// Directory of Kubernetes manifests, as given to `kubectl apply -f`, to start from, replacing
+    /// the generated resources other than nodes.
+    #[clap(long, global = true, conflicts_with_all = &["snapshot", "snapshot_cluster"])]
+    pub manifests: Option<PathBuf>,
+
+    /// Kinds of actions to leave out of the exploration, such as `node-restart`.
+    #[clap(long, global = true)]
+    pub disable_action: Vec<ActionKind>,
+
+    /// Kinds of resources that the arbitrary client may touch, such as `deployments`, instead of
+    /// all of them.
+    #[clap(long, global = true)]
+    pub client_kind: Vec<ResourceKind>,
+
+    /// Changes that the arbitrary client may make, such as `scale` or `delete`, instead of the
+    /// default ones, which leave out deleting and recreating workloads.
+    #[clap(long, global = true)]
+    pub client_mutation: Vec<Mutation>,
+
+    /// The fewest replicas that the arbitrary client scales workloads down to.
+    #[clap(long, global = true, default_value = "0")]
+    pub client_min_replicas: u32,
+
+    /// The most replicas that the arbitrary client scales workloads up to.
+    #[clap(long, global = true)]
+    pub client_max_replicas: Option<u32>,
+
+    /// Images that the arbitrary client changes templates to, instead of deriving new ones.
+    #[clap(long, global = true)]
+    pub client_image: Vec<String>,
+
+    /// Names of controllers not to run, such as `Deployment`.
+    #[clap(long, global = true)]
+    pub disable_controller: Vec<String>,
+
+    /// Only let the named controller see some resources of a kind, as
+    /// `controller=kind:name[,name...]`, e.g. `Deployment=deployments:dep-1`.
+    #[clap(long, global = true)]
+    pub controller_scope: Vec<ScopeOpt>,
+
+    /// Names of controllers to treat as weakly fair, such as `Deployment`, so that they can't stay
+    /// enabled forever without stepping.
+    #[clap(long, global = true)]
+    pub fair_controller: Vec<String>,
+
+    /// The number of steps that a fair controller can be enabled for before it has to step.
+    #[clap(long, global = true, default_value = "3")]
+    pub fairness_bound: usize,
+
+    /// File of extra properties to check, one per line as `always "name": count(pods) <= 4`.
+    /// See `themelios::user_properties` for the expressions that can be written.
+    #[clap(long, global = true)]
+    pub properties: Option<PathBuf>,
+
+    /// Names of controllers whose standard properties to check, such as `Deployment`, instead of
+    /// those of the controllers that run.
+    #[clap(long, global = true)]
+    pub property_bundle: Vec<String>,
+
+    /// Names of the only properties to check, such as `all resources have unique names`, instead
+    /// of all of them.
+    #[clap(long, global = true)]
+    pub property: Vec<String>,
+
+    /// Names of properties not to check.
+    #[clap(long, global = true)]
+    pub skip_property: Vec<String>,
+
+    /// Names of properties to report as warnings rather than failures when they don't hold.
+    #[clap(long, global = true)]
+    pub warn_property: Vec<String>,
+}
+
+/// The resources of a kind that a controller is scoped to.
+#[derive(Debug, Clone)]
+pub struct ScopeOpt {
+    pub controller: String,
+    pub kind: ResourceKind,
+    pub names: Vec<String>,
+}
+
+impl FromStr for ScopeOpt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (controller, resources) = s
+            .split_once('=')
+            .ok_or_else(|| format!("controller scope {s:?} has no resources"))?;
+        let (kind, names) = resources
+            .split_once(':')
+            .ok_or_else(|| format!("controller scope {s:?} has no resource names"))?;
+        Ok(ScopeOpt {
+            controller: controller.to_owned(),
+            kind: kind.parse()?,
+            names: names.split(',').map(|n| n.to_owned()).collect(),
+        })
+    }
+}
+
+/// The weight of a class of actions when simulating.
+#[derive(Debug, Clone)]
+pub struct WeightOpt {
+    pub class: ActionClass,
+    pub weight: u32,
+}
+
+impl FromStr for WeightOpt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, weight) = s
+            .split_once('=')
+            .ok_or_else(|| format!("action weight {s:?} has no weight"))?;
+        Ok(WeightOpt {
+            class: class.parse()?,
+            weight: weight
+                .parse()
+                .map_err(|e| format!("action weight {s:?} is invalid: {e}"))?,
+        })
+    }
+}
+
+/// Limits on a check, which stops early and reports what it found so far once one runs out.
+#[derive(clap::Args, Debug, Clone, Copy)]
+pub struct BudgetOpts {
+    /// Number of states to visit, 0 is no limit.
+    #[clap(long, default_value = "0")]
+    pub max_states: usize,
+    /// Seconds to check for, 0 is no limit.
+    #[clap(long, default_value = "0")]
+    pub timeout: u64,
+    /// Megabytes of memory that the checker may use, 0 is no limit.
+    #[clap(long, default_value = "0")]
+    pub max_memory: u64,
+}
+
+impl From<BudgetOpts> for Budget {
+    fn from(opts: BudgetOpts) -> Self {
+        Budget {
+            max_states: (opts.max_states > 0).then_some(opts.max_states),
+            timeout: (opts.timeout > 0).then(|| Duration::from_secs(opts.timeout)),
+            max_memory: (opts.max_memory > 0).then(|| opts.max_memory << 20),
+        }
+    }
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -75,11 +441,108 @@ pub enum SubCmd {
         #[clap(long, default_value = "8080")]
         port: u16,
     },
-    CheckDfs,
-    CheckBfs,
+    CheckDfs {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+    },
+    CheckBfs {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+    },
     CheckSimulation {
+        #[clap(flatten)]
+        budget: BudgetOpts,
         #[clap(long)]
         seed: Option<u64>,
+        /// How often to choose actions of a class compared to the others, as `class=weight`, with
+        /// the classes being `client`, `controller` and `fault`, e.g. `fault=5`.
+        /// Classes default to a weight of 1.
+        #[clap(long)]
+        weight: Vec<WeightOpt>,
+    },
+    /// Simulate with many seeds in parallel, a seed on each thread at a time.
+    CheckSimulations {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+        /// The number of seeds to simulate with.
+        #[clap(long, default_value = "8")]
+        seeds: u64,
+        /// The seed to start from, the others following on from it.
+        #[clap(long, default_value = "0")]
+        first_seed: u64,
+        /// Number of states to visit with each seed, 0 is no limit.
+        #[clap(long, default_value = "100000")]
+        states_per_seed: usize,
+    },
+    /// Simulate a swarm of variations of the model in parallel, each leaving out some kinds of
+    /// actions and going to its own depth, merging what they discover.
+    Swarm {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+        /// The number of members in the swarm, each with its own seed.
+        #[clap(long, default_value = "64")]
+        members: u64,
+        /// The seed of the first member, the others following on from it.
+        #[clap(long, default_value = "0")]
+        first_seed: u64,
+        /// Number of states for each member to visit, 0 is no limit.
+        #[clap(long, default_value = "10000")]
+        states_per_member: usize,
+    },
+    /// Check depth first, keeping visited states in a Bloom filter rather than a set, so that
+    /// bigger models fit in memory at the cost of missing some states.
+    CheckBitstate {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+        /// Memory to use for the visited states, in megabytes.
+        #[clap(long, default_value = "1024")]
+        memory_mb: usize,
+        /// Number of bits set in the filter for each state.
+        #[clap(long, default_value = "3")]
+        hashes: u32,
+    },
+    /// Check depth first, keeping at most a fixed number of visited states and evicting the least
+    /// recently seen when full, so that bigger models fit in memory at the cost of exploring some
+    /// states again.
+    CheckBounded {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+        /// Number of visited states to keep.
+        #[clap(long, default_value = "1000000")]
+        max_visited: usize,
+    },
+    /// Check best first, exploring the states that change the most resources before the rest, to
+    /// find bugs sooner than depth or breadth first search would.
+    CheckGuided {
+        #[clap(flatten)]
+        budget: BudgetOpts,
+    },
+    /// Check breadth first under the synchronous, resettable session, optimistic linear and causal
+    /// consistency levels in turn, reporting which properties hold under each and the shortest
+    /// paths to those that don't.
+    /// The consistency level options are ignored, each level being checked in turn.
+    CompareConsistency,
+    /// Step through a trace in the terminal.
+    Tui {
+        /// Path to a state, as printed for discoveries.
+        fingerprint_path: String,
+    },
+    /// Write the resources of the last state along a path as manifests, to apply to a test
+    /// cluster for reproducing it.
+    Export {
+        /// Path to a state, as printed for discoveries.
+        fingerprint_path: String,
+        /// Directory to write the manifests to.
+        #[clap(long, default_value = "manifests")]
+        output: PathBuf,
+    },
+    /// Shrink the path to a discovery, removing and reordering its actions while the property is
+    /// still discovered.
+    Shrink {
+        /// Name of the discovered property.
+        property: String,
+        /// Path to the discovery, as printed for discoveries.
+        fingerprint_path: String,
     },
     /// Serve an integration test suitable API.
     ServeTest {
@@ -90,7 +553,133 @@ pub enum SubCmd {
     ServeCluster {
         #[clap(long, default_value = "8080")]
         port: u16,
+        /// File to record the writes that the cluster accepts to, for replaying with `--replay`.
+        #[clap(long)]
+        record: Option<PathBuf>,
+        /// Number of API server replicas to serve, on consecutive ports from `port`, their reads
+        /// being as stale as the consistency level allows.
+        #[clap(long, default_value = "1")]
+        replicas: u16,
     },
     /// Deploy as controller-manager.
     ControllerManager {},
+    /// Run a scenario against both the model and the cluster of the kubeconfig, such as one from
+    /// kind or k3s, and diff the states that they converge to after each step.
+    Differential {
+        /// YAML file of the scenario to run, creating a deployment, scaling it, updating its image
+        /// and killing a node when not given.
+        #[clap(long)]
+        scenario: Option<PathBuf>,
+        /// Seconds to wait for the cluster to converge after each step.
+        #[clap(long, default_value = "300")]
+        timeout: u64,
+    },
+    /// Check that the writes in the audit log of a real cluster's API server could have been made
+    /// by the model's controllers under the consistency level, taking the writes of other clients
+    /// as recorded operations.
+    Conformance {
+        /// Audit log, written with the `RequestResponse` level.
+        audit_log: PathBuf,
+    },
+}
+
+impl SubCmd {
+    /// The budget of the check that the subcommand runs, if it runs one.
+    pub fn budget(&self) -> Option<Budget> {
+        match self {
+            SubCmd::CheckDfs { budget }
+            | SubCmd::CheckBfs { budget }
+            | SubCmd::CheckSimulation { budget, .. }
+            | SubCmd::CheckSimulations { budget, .. }
+            | SubCmd::Swarm { budget, .. }
+            | SubCmd::CheckBitstate { budget, .. }
+            | SubCmd::CheckBounded { budget, .. }
+            | SubCmd::CheckGuided { budget } => Some((*budget).into()),
+            _ => None,
+        }
+    }
+
+    /// Whether the subcommand runs a check.
+    pub fn is_check(&self) -> bool {
+        self.budget().is_some()
+    }
+}
+
+impl Opts {
+    /// Parse the options from the command line, layered over environment variables and the config
+    /// file.
+    pub fn load() -> Self {
+        let args = std::env::args_os().collect::<Vec<_>>();
+        let env = std::env::vars().collect::<BTreeMap<_, _>>();
+        Self::load_from(args, &env).unwrap_or_else(|err| err.exit())
+    }
+
+    fn load_from(args: Vec<OsString>, env: &BTreeMap<String, String>) -> Result<Self, clap::Error> {
+        let cli = args
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        let command = Self::command();
+
+        let mut layered = BTreeMap::new();
+        let mut resources = None;
+        let mut config_properties = Vec::new();
+        let config = flag_value(&cli, "config").or_else(|| env.get("THEMELIOS_CONFIG").cloned());
+        if let Some(path) = config {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|err| invalid(format!("failed to read config file {path}: {err}")))?;
+            let file: BTreeMap<String, serde_yaml::Value> = if path.ends_with(".toml") {
+                toml::from_str(&contents).map_err(|err| err.to_string())
+            } else {
+                serde_yaml::from_str(&contents).map_err(|err| err.to_string())
+            }
+            .map_err(|err| invalid(format!("failed to parse config file {path}: {err}")))?;
+            for (key, value) in file {
+                match (key.as_str(), value) {
+                    ("resources", serde_yaml::Value::Sequence(values)) => {
+                        let values = values
+                            .iter()
+                            .map(serde_json::to_value)
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|err| {
+                                invalid(format!("invalid resources in config file {path}: {err}"))
+                            })?;
+                        resources = Some(values);
+                    }
+                    // a single value is still the path of a properties file
+                    ("properties", serde_yaml::Value::Sequence(lines)) => {
+                        for line in lines {
+                            let serde_yaml::Value::String(line) = line else {
+                                return Err(invalid(format!(
+                                    "properties in config file {path} must be strings"
+                                )));
+                            };
+                            config_properties.push(line);
+                        }
+                    }
+                    (_, value) => {
+                        let values = config_values(&key, value).map_err(invalid)?;
+                        let key = key.replace('_', "-");
+                        if !is_option(&command, &key) {
+                            return Err(invalid(format!(
+                                "unknown option {key:?} in config file {path}"
+                            )));
+                        }
+                        layered.insert(key, values);
+                    }
+                }
+            }
+        }
+
+        let args = layer_args(&command, args, layered, env);
+        let mut opts = Self::try_parse_from(args)?;
+        opts.resources = resources;
+        opts.config_properties = config_properties;
+        Ok(opts)
+    }
+}
+
+fn invalid(message: String) -> clap::Error {
+    Opts::command().error(ErrorKind::InvalidValue, message)
 }
@@ -0,0 +1,264 @@
+//! A terminal UI following the progress of a check as it runs, for checks that run for too long
+//! to follow from the lines printed by [`StdoutReporter`](crate::report::StdoutReporter).
+
+use std::collections::BTreeMap;
+use std::io::{self, Stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{BarChart, Block, Borders, List, ListItem, Paragraph};
+use ratatui::{Frame, Terminal};
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::{Expectation, Model};
+use sysinfo::{ProcessExt, System, SystemExt};
+use tracing::warn;
+
+use crate::report::{holds_until_done, DepthHistogram};
+
+/// Shows the progress of the check in the terminal while it runs, handing the final results to
+/// another reporter once it is done.
+///
+/// Pressing `q`, or Ctrl-C as the terminal doesn't send interrupts while it is shown, stops the check
+/// early, which still finishes by reporting what it found so far.
+pub struct ProgressReporter<R> {
+    terminal: Option<Terminal<CrosstermBackend<Stdout>>>,
+    stop: Arc<AtomicBool>,
+    depths: DepthHistogram,
+    properties: BTreeMap<&'static str, Expectation>,
+    last: Option<ReportData>,
+    /// States checked per second since the previous report.
+    rate: f64,
+    stopping: bool,
+    system: System,
+    inner: R,
+}
+
+impl<R> ProgressReporter<R> {
+    /// Show the progress of checking the model, setting `stop` when asked to stop early.
+    pub fn new<M: Model>(
+        model: &M,
+        stop: Arc<AtomicBool>,
+        depths: DepthHistogram,
+        inner: R,
+    ) -> Self {
+        let properties = model
+            .properties()
+            .iter()
+            .map(|p| (p.name, p.expectation.clone()))
+            .collect();
+        Self {
+            terminal: None,
+            stop,
+            depths,
+            properties,
+            last: None,
+            rate: 0.,
+            stopping: false,
+            system: System::new(),
+            inner,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.terminal.is_none() {
+            enable_raw_mode()?;
+            io::stdout().execute(EnterAlternateScreen)?;
+            self.terminal = Some(Terminal::new(CrosstermBackend::new(io::stdout()))?);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.terminal.take().is_some() {
+            disable_raw_mode()?;
+            io::stdout().execute(LeaveAlternateScreen)?;
+        }
+        Ok(())
+    }
+
+    /// Handle any keys pressed since the last report, without waiting for more.
+    fn handle_keys(&mut self) -> io::Result<()> {
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                let interrupted =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press
+                    && (interrupted || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc))
+                {
+                    self.stopping = true;
+                    self.stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn memory(&mut self) -> u64 {
+        let Ok(pid) = sysinfo::get_current_pid() else {
+            return 0;
+        };
+        self.system.refresh_process(pid);
+        self.system.process(pid).map_or(0, |p| p.memory())
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let memory = self.memory();
+        let depths = self.depths.counts();
+        let Some(terminal) = &mut self.terminal else {
+            return Ok(());
+        };
+        let view = View {
+            data: self.last.as_ref(),
+            rate: self.rate,
+            memory,
+            stopping: self.stopping,
+            depths: &depths,
+            properties: &self.properties,
+        };
+        terminal.draw(|frame| view.draw(frame))?;
+        Ok(())
+    }
+}
+
+/// What is shown on each redraw.
+struct View<'a> {
+    data: Option<&'a ReportData>,
+    rate: f64,
+    memory: u64,
+    stopping: bool,
+    depths: &'a BTreeMap<usize, u64>,
+    properties: &'a BTreeMap<&'static str, Expectation>,
+}
+
+impl View<'_> {
+    fn draw(&self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Percentage(50),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .split(frame.size());
+
+        let (total, unique, max_depth, duration) =
+            self.data.map_or((0, 0, 0, Duration::ZERO), |d| {
+                (d.total_states, d.unique_states, d.max_depth, d.duration)
+            });
+        let stats = vec![
+            Line::from(format!(
+                "states={total} unique={unique} rate={:.0}/s",
+                self.rate
+            )),
+            Line::from(format!(
+                "max_depth={max_depth} memory_bytes={} duration={duration:?}",
+                self.memory
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(stats).block(Block::default().borders(Borders::ALL).title("Progress")),
+            rows[0],
+        );
+
+        let labels = self
+            .depths
+            .keys()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        let bars = labels
+            .iter()
+            .zip(self.depths.values())
+            .map(|(label, count)| (label.as_str(), *count))
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            BarChart::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Terminal paths by depth"),
+                )
+                .bar_width(3)
+                .data(&bars),
+            rows[1],
+        );
+
+        let properties = self
+            .properties
+            .iter()
+            .map(|(name, expectation)| {
+                let (status, color) = if holds_until_done(expectation) {
+                    ("no counterexample yet", Color::Green)
+                } else {
+                    ("no example yet", Color::Yellow)
+                };
+                ListItem::new(format!("{expectation:?} {name:?}: {status}"))
+                    .style(Style::default().fg(color))
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            List::new(properties).block(Block::default().borders(Borders::ALL).title("Properties")),
+            rows[2],
+        );
+
+        let help = if self.stopping {
+            "stopping, finishing the states already queued"
+        } else {
+            "q: stop early and report"
+        };
+        frame.render_widget(Paragraph::new(help), rows[3]);
+    }
+}
+
+impl<M, R> Reporter<M> for ProgressReporter<R>
+where
+    M: Model,
+    R: Reporter<M>,
+{
+    fn report_checking(&mut self, data: ReportData) {
+        if let Some(last) = &self.last {
+            let elapsed = data.duration.saturating_sub(last.duration).as_secs_f64();
+            if elapsed > 0. {
+                self.rate = (data.total_states - last.total_states) as f64 / elapsed;
+            }
+        }
+        self.last = Some(data.clone());
+        let result = self
+            .start()
+            .and_then(|()| self.handle_keys())
+            .and_then(|()| self.draw());
+        if let Err(error) = result {
+            warn!(%error, "Failed to show the progress");
+        }
+        if data.done {
+            self.inner.report_checking(data);
+        }
+    }
+
+    fn report_discoveries(&mut self, discoveries: BTreeMap<&'static str, ReportDiscovery<M>>)
+    where
+        M::Action: std::fmt::Debug,
+        M::State: std::fmt::Debug + std::hash::Hash,
+    {
+        if let Err(error) = self.finish() {
+            warn!(%error, "Failed to restore the terminal");
+        }
+        self.inner.report_discoveries(discoveries);
+    }
+}
+
+impl<R> Drop for ProgressReporter<R> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -0,0 +1,388 @@
+//! Scoped permissions for controllers, modelled on the kubernetes RBAC authorizer.
+//!
+//! Each restricted controller is given a [`Role`] of verbs per resource kind, actions outside of
+//! it are rejected as the API server would.
+//! Controllers can also act under an identity, their actions then being authorized by the roles
+//! bound to it through the [`resources::Role`] and [`RoleBinding`] resources in the state.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+use crate::abstract_model::ControllerAction;
+use crate::controller::Controllers;
+use crate::resources::{self, PolicyRule, RoleBinding, RoleRef, Subject};
+use crate::state::{RawState, ResourceKind, State};
+use crate::utils;
+
+/// The namespace of the service accounts that the built-in controllers act under.
+pub const CONTROLLER_NAMESPACE: &str = "kube-system";
+
+/// The verbs that can be granted on a kind of resource.
+///
+/// THEMELIOS: Reads are not modelled and subresources (status, scale) are covered by the update
+/// verb on their parent kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Verb {
+    Create,
+    Update,
+    Patch,
+    Delete,
+}
+
+impl Verb {
+    /// The name of the verb in policy rules.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verb::Create => "create",
+            Verb::Update => "update",
+            Verb::Patch => "patch",
+            Verb::Delete => "delete",
+        }
+    }
+}
+
+/// A verb granted on a kind of resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Permission {
+    pub verb: Verb,
+    pub kind: ResourceKind,
+}
+
+impl Permission {
+    pub fn new(verb: Verb, kind: ResourceKind) -> Self {
+        Self { verb, kind }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {:?}", self.verb, self.kind)
+    }
+}
+
+/// The set of permissions granted to a controller.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Role {
+    grants: BTreeSet<Permission>,
+}
+
+impl Role {
+    pub fn new(grants: impl IntoIterator<Item = Permission>) -> Self {
+        Self {
+            grants: grants.into_iter().collect(),
+        }
+    }
+
+    /// Grant all of the verbs on the given kind.
+    pub fn with(mut self, kind: ResourceKind, verbs: impl IntoIterator<Item = Verb>) -> Self {
+        self.grants
+            .extend(verbs.into_iter().map(|verb| Permission::new(verb, kind)));
+        self
+    }
+
+    /// Remove a single grant.
+    pub fn without(mut self, verb: Verb, kind: ResourceKind) -> Self {
+        self.grants.remove(&Permission::new(verb, kind));
+        self
+    }
+
+    pub fn allows(&self, permission: &Permission) -> bool {
+        self.grants.contains(permission)
+    }
+
+    pub fn grants(&self) -> impl Iterator<Item = &Permission> {
+        self.grants.iter()
+    }
+
+    /// The policy rules making up the role, one for each kind that it grants verbs on.
+    pub fn rules(&self) -> Vec<PolicyRule> {
+        let mut verbs = BTreeMap::<ResourceKind, Vec<String>>::new();
+        for grant in &self.grants {
+            verbs
+                .entry(grant.kind)
+                .or_default()
+                .push(grant.verb.name().to_owned());
+        }
+        verbs
+            .into_iter()
+            .map(|(kind, verbs)| {
+                let (group, resource) = api_resource(kind);
+                PolicyRule {
+                    api_groups: vec![group.to_owned()],
+                    resources: vec![resource.to_owned()],
+                    verbs,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The api group and resource name of the kind, as policy rules refer to it.
+pub fn api_resource(kind: ResourceKind) -> (&'static str, &'static str) {
+    use ResourceKind::*;
+    match kind {
+        Nodes => ("", "nodes"),
+        Pods => ("", "pods"),
+        ReplicaSets => ("apps", "replicasets"),
+        ReplicationControllers => ("", "replicationcontrollers"),
+        Deployments => ("apps", "deployments"),
+        StatefulSets => ("apps", "statefulsets"),
+        ControllerRevisions => ("apps", "controllerrevisions"),
+        PersistentVolumeClaims => ("", "persistentvolumeclaims"),
+        PersistentVolumes => ("", "persistentvolumes"),
+        StorageClasses => ("storage.k8s.io", "storageclasses"),
+        PriorityClasses => ("scheduling.k8s.io", "priorityclasses"),
+        Leases => ("coordination.k8s.io", "leases"),
+        Services => ("", "services"),
+        Endpoints => ("", "endpoints"),
+        ConfigMaps => ("", "configmaps"),
+        Secrets => ("", "secrets"),
+        Jobs => ("batch", "jobs"),
+        CronJobs => ("batch", "cronjobs"),
+        HorizontalPodAutoscalers => ("autoscaling", "horizontalpodautoscalers"),
+        Namespaces => ("", "namespaces"),
+        Roles => ("rbac.authorization.k8s.io", "roles"),
+        RoleBindings => ("rbac.authorization.k8s.io", "rolebindings"),
+    }
+}
+
+/// Whether the rule allows the permission, `*` matching any group, resource or verb.
+pub fn rule_allows(rule: &PolicyRule, permission: &Permission) -> bool {
+    let (group, resource) = api_resource(permission.kind);
+    let matches = |values: &[String], value: &str| values.iter().any(|v| v == "*" || v == value);
+    matches(&rule.api_groups, group)
+        && matches(&rule.resources, resource)
+        && matches(&rule.verbs, permission.verb.name())
+}
+
+/// Whether any of the roles bound to the subject in the state allows the permission.
+pub fn bindings_allow(state: &RawState, subject: &Subject, permission: &Permission) -> bool {
+    state
+        .role_bindings
+        .iter()
+        .filter(|binding| binding.subjects.contains(subject))
+        .filter_map(|binding| {
+            state
+                .roles
+                .get_in(&binding.metadata.namespace, &binding.role_ref.name)
+        })
+        .any(|role| role.rules.iter().any(|rule| rule_allows(rule, permission)))
+}
+
+/// The identity that the controller acts under, named as those of the built-in controllers: the
+/// nodes and scheduler as users and the rest as service accounts in [`CONTROLLER_NAMESPACE`].
+pub fn identity(controller: &Controllers) -> Subject {
+    let account = match controller {
+        Controllers::Node(node) => return Subject::user(&format!("system:node:{}", node.name)),
+        Controllers::Scheduler(_) => return Subject::user("system:kube-scheduler"),
+        Controllers::ReplicaSet(_) => "replicaset-controller",
+        Controllers::ReplicationController(_) => "replication-controller",
+        Controllers::Deployment(_) => "deployment-controller",
+        Controllers::StatefulSet(_) => "statefulset-controller",
+        Controllers::Job(_) => "job-controller",
+        Controllers::CronJob(_) => "cronjob-controller",
+        Controllers::HorizontalPodAutoscaler(_) => "horizontal-pod-autoscaler",
+        Controllers::NodeLifecycle(_) => "node-controller",
+        Controllers::Namespace(_) => "namespace-controller",
+        Controllers::Endpoints(_) => "endpoint-controller",
+        Controllers::PersistentVolumeBinder(_) => "persistent-volume-binder",
+        Controllers::Provisioner(_) => "persistent-volume-provisioner",
+        Controllers::PodGC(_) => "pod-garbage-collector",
+    };
+    Subject::service_account(CONTROLLER_NAMESPACE, account)
+}
+
+/// The role resource with the grants of the role and the binding of it to the subject, named as
+/// the API server names those of the built-in controllers.
+pub fn bind(subject: &Subject, role: &Role) -> (resources::Role, RoleBinding) {
+    let name = if subject.kind == "ServiceAccount" {
+        format!("system:controller:{}", subject.name)
+    } else {
+        subject.name.clone()
+    };
+    let mut metadata = utils::metadata(name.clone());
+    metadata.namespace = CONTROLLER_NAMESPACE.to_owned();
+    let role = resources::Role {
+        metadata: metadata.clone(),
+        rules: role.rules(),
+    };
+    let binding = RoleBinding {
+        metadata,
+        subjects: vec![subject.clone()],
+        role_ref: RoleRef {
+            api_group: resources::Role::GVK.group.to_owned(),
+            kind: resources::Role::GVK.kind.to_owned(),
+            name,
+        },
+    };
+    (role, binding)
+}
+
+/// The least-privilege role for each of the built-in controllers, covering every action they can
+/// take.
+pub fn default_role(controller: &Controllers) -> Option<Role> {
+    use ResourceKind::*;
+    use Verb::*;
+    let role = Role::default();
+    let role = match controller {
+        Controllers::Node(_) => role
+            .with(Nodes, [Create, Update])
+            .with(Pods, [Update, Delete])
+            .with(Leases, [Create, Update]),
+        Controllers::Scheduler(_) => role
+            .with(Pods, [Update, Delete])
+            .with(PersistentVolumeClaims, [Update]),
+        Controllers::ReplicaSet(_) => role
+            .with(Pods, [Create, Update, Delete])
+            .with(ReplicaSets, [Update]),
+        Controllers::ReplicationController(_) => role
+            .with(Pods, [Create, Update, Delete])
+            .with(ReplicationControllers, [Update]),
+        Controllers::Deployment(_) => role
+            .with(ReplicaSets, [Create, Update, Delete])
+            .with(Deployments, [Update]),
+        Controllers::StatefulSet(_) => role
+            .with(Pods, [Create, Update, Delete])
+            .with(ControllerRevisions, [Create, Update, Delete])
+            .with(PersistentVolumeClaims, [Create, Update])
+            .with(StatefulSets, [Update]),
+        Controllers::Job(_) => role
+            .with(Pods, [Create, Patch, Delete])
+            .with(Jobs, [Update]),
+        Controllers::CronJob(_) => role.with(Jobs, [Create, Delete]).with(CronJobs, [Update]),
+        Controllers::HorizontalPodAutoscaler(_) => role
+            .with(Deployments, [Update])
+            .with(ReplicaSets, [Update])
+            .with(StatefulSets, [Update])
+            .with(HorizontalPodAutoscalers, [Update]),
+        Controllers::NodeLifecycle(_) => role.with(Nodes, [Update]).with(Pods, [Update, Delete]),
+        Controllers::Namespace(_) => role
+            .with(Pods, [Delete])
+            .with(ReplicaSets, [Delete])
+            .with(ReplicationControllers, [Delete])
+            .with(Deployments, [Delete])
+            .with(StatefulSets, [Delete])
+            .with(ControllerRevisions, [Delete])
+            .with(PersistentVolumeClaims, [Delete])
+            .with(Jobs, [Delete])
+            .with(CronJobs, [Delete])
+            .with(HorizontalPodAutoscalers, [Delete])
+            .with(Services, [Delete])
+            .with(Endpoints, [Delete])
+            .with(ConfigMaps, [Delete])
+            .with(Secrets, [Delete])
+            .with(Namespaces, [Update]),
+        Controllers::Endpoints(_) => role.with(Endpoints, [Create, Update, Delete]),
+        Controllers::PersistentVolumeBinder(_) => role
+            .with(PersistentVolumes, [Update])
+            .with(PersistentVolumeClaims, [Update]),
+        Controllers::Provisioner(_) => role.with(PersistentVolumes, [Create]),
+        Controllers::PodGC(_) => role.with(Pods, [Delete]),
+    };
+    Some(role)
+}
+
+/// Checks the actions of controllers against their roles, keeping track of which grants get used.
+///
+/// Usage is shared between clones, and so between checker threads, rather than being part of the
+/// state so that it doesn't split otherwise identical states.
+#[derive(Debug, Clone, Default)]
+pub struct Authorizer {
+    /// The name and role of each restricted controller, keyed by its index.
+    roles: BTreeMap<usize, (String, Role)>,
+    /// The identity of each controller that is authorized by the roles bound to it in the state,
+    /// keyed by its index.
+    identities: BTreeMap<usize, Subject>,
+    exercised: Arc<Mutex<BTreeSet<(usize, Permission)>>>,
+}
+
+impl Authorizer {
+    pub fn new(roles: BTreeMap<usize, (String, Role)>) -> Self {
+        Self {
+            roles,
+            identities: BTreeMap::new(),
+            exercised: Arc::default(),
+        }
+    }
+
+    /// A copy of the authorizer whose use of grants is not recorded with this one's.
+    pub fn unrecorded(&self) -> Self {
+        Self {
+            roles: self.roles.clone(),
+            identities: self.identities.clone(),
+            exercised: Arc::default(),
+        }
+    }
+
+    /// Also authorize the controllers with identities by the roles bound to them.
+    pub fn with_identities(mut self, identities: BTreeMap<usize, Subject>) -> Self {
+        self.identities = identities;
+        self
+    }
+
+    /// Whether the controller at the given index may take the action, by its role and by the
+    /// roles bound to its identity in the latest view of the state, as the API server sees it.
+    pub fn authorize_in(
+        &self,
+        state: &State,
+        controller: usize,
+        action: &ControllerAction,
+    ) -> bool {
+        if let ControllerAction::Transaction(writes) = action {
+            return writes
+                .iter()
+                .all(|w| self.authorize_in(state, controller, w));
+        }
+        if !self.authorize(controller, action) {
+            return false;
+        }
+        match (
+            self.identities.get(&controller),
+            action.required_permission(),
+        ) {
+            (Some(subject), Some(permission)) => {
+                bindings_allow(&state.latest(), subject, &permission)
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the controller at the given index may take the action.
+    /// Controllers without a role are unrestricted.
+    /// A transaction needs permission for each of its writes.
+    pub fn authorize(&self, controller: usize, action: &ControllerAction) -> bool {
+        if let ControllerAction::Transaction(writes) = action {
+            return writes.iter().all(|w| self.authorize(controller, w));
+        }
+        let role = match self.roles.get(&controller) {
+            Some((_, role)) => role,
+            None => return true,
+        };
+        match action.required_permission() {
+            Some(permission) if role.allows(&permission) => {
+                self.exercised
+                    .lock()
+                    .unwrap()
+                    .insert((controller, permission));
+                true
+            }
+            Some(_) => false,
+            None => true,
+        }
+    }
+
+    /// The grants that have not been used by their controller in any state explored so far.
+    pub fn unexercised_grants(&self) -> Vec<(String, Permission)> {
+        let exercised = self.exercised.lock().unwrap();
+        self.roles
+            .iter()
+            .flat_map(|(i, (name, role))| {
+                role.grants()
+                    .filter(|p| !exercised.contains(&(*i, **p)))
+                    .map(|p| (name.clone(), *p))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,397 @@
+//! Recordings of the operations that clients make against the served cluster, and their replay
+//! through the model.
+//!
+//! A recording is a file of JSON lines, one [`ClientOperation`] for each write that the API
+//! accepted.
+//! Replaying it takes the resources created before any other operation as the initial state and
+//! the rest of the operations as actions that the model takes in order, interleaved with the
+//! controllers, so that the checker explores the workload that was run against the cluster.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::abstract_model::ControllerAction;
+use crate::resources::{
+    Deployment, Job, Meta, Pod, ReplicaSet, ReplicationController, Scale, StatefulSet,
+};
+use crate::state::revision::Revision;
+use crate::state::{RawState, ResourceKind, StateView};
+
+/// A resource in a recorded operation, as the API returned it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RecordedResource {
+    Pod(Pod),
+    Deployment(Deployment),
+    ReplicaSet(ReplicaSet),
+    ReplicationController(ReplicationController),
+    StatefulSet(StatefulSet),
+    Job(Job),
+}
+
+impl RecordedResource {
+    /// Parse a resource of the kind from the body of a response.
+    pub fn parse(kind: ResourceKind, body: &[u8]) -> serde_json::Result<Self> {
+        match kind {
+            ResourceKind::Pods => serde_json::from_slice(body).map(Self::Pod),
+            ResourceKind::Deployments => serde_json::from_slice(body).map(Self::Deployment),
+            ResourceKind::ReplicaSets => serde_json::from_slice(body).map(Self::ReplicaSet),
+            ResourceKind::ReplicationControllers => {
+                serde_json::from_slice(body).map(Self::ReplicationController)
+            }
+            ResourceKind::StatefulSets => serde_json::from_slice(body).map(Self::StatefulSet),
+            ResourceKind::Jobs => serde_json::from_slice(body).map(Self::Job),
+            kind => Err(serde::de::Error::custom(format!(
+                "{kind:?} are not recorded"
+            ))),
+        }
+    }
+
+    pub fn kind(&self) -> ResourceKind {
+        match self {
+            RecordedResource::Pod(_) => ResourceKind::Pods,
+            RecordedResource::Deployment(_) => ResourceKind::Deployments,
+            RecordedResource::ReplicaSet(_) => ResourceKind::ReplicaSets,
+            RecordedResource::ReplicationController(_) => ResourceKind::ReplicationControllers,
+            RecordedResource::StatefulSet(_) => ResourceKind::StatefulSets,
+            RecordedResource::Job(_) => ResourceKind::Jobs,
+        }
+    }
+
+    /// The resource on its own, without the kind that it is tagged with.
+    pub fn to_value(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            RecordedResource::Pod(r) => serde_json::to_value(r),
+            RecordedResource::Deployment(r) => serde_json::to_value(r),
+            RecordedResource::ReplicaSet(r) => serde_json::to_value(r),
+            RecordedResource::ReplicationController(r) => serde_json::to_value(r),
+            RecordedResource::StatefulSet(r) => serde_json::to_value(r),
+            RecordedResource::Job(r) => serde_json::to_value(r),
+        }
+    }
+}
+
+/// A write that a client made, and the API accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ClientOperation {
+    /// The resource was created.
+    Create(RecordedResource),
+    /// The resource was updated or patched, ending up as the one given.
+    Update(RecordedResource),
+    /// The workload was scaled through its scale subresource.
+    Scale {
+        kind: ResourceKind,
+        namespace: String,
+        name: String,
+        replicas: u32,
+    },
+    /// The resource was deleted, with an empty namespace for nodes.
+    Delete {
+        kind: ResourceKind,
+        namespace: String,
+        name: String,
+    },
+}
+
+/// The current resource with the spec, labels and annotations of the recorded one, keeping the
+/// status and the rest of the metadata that the cluster has moved on since.
+macro_rules! updated {
+    ($current:expr, $recorded:expr) => {{
+        let mut resource = $current.clone();
+        resource.spec = $recorded.spec.clone();
+        resource.metadata.labels = $recorded.metadata.labels.clone();
+        resource.metadata.annotations = $recorded.metadata.annotations.clone();
+        resource
+    }};
+}
+
+impl ClientOperation {
+    /// Whether the model has an action that takes the operation.
+    ///
+    /// THEMELIOS: Clients in the model can only create the kinds of resources that controllers
+    /// create, and can't update replication controllers, so recordings doing so can't be
+    /// replayed past their initial creates.
+    pub fn replayable(&self) -> bool {
+        match self {
+            ClientOperation::Create(resource) => matches!(
+                resource,
+                RecordedResource::Pod(_)
+                    | RecordedResource::ReplicaSet(_)
+                    | RecordedResource::Job(_)
+            ),
+            ClientOperation::Update(resource) => {
+                !matches!(resource, RecordedResource::ReplicationController(_))
+            }
+            ClientOperation::Scale { kind, .. } => matches!(
+                kind,
+                ResourceKind::Deployments | ResourceKind::ReplicaSets | ResourceKind::StatefulSets
+            ),
+            ClientOperation::Delete { kind, .. } => matches!(
+                kind,
+                ResourceKind::Nodes
+                    | ResourceKind::Pods
+                    | ResourceKind::Deployments
+                    | ResourceKind::ReplicaSets
+                    | ResourceKind::ReplicationControllers
+                    | ResourceKind::StatefulSets
+                    | ResourceKind::Jobs
+            ),
+        }
+    }
+
+    /// The action taking the operation against the latest view of the state, none while the
+    /// resource that it targets doesn't exist.
+    ///
+    /// Updates and scales are taken against the current version of the resource, as the
+    /// controllers will have written to it in between, rather than conflicting on the version
+    /// that was recorded.
+    pub fn controller_action(&self, view: &StateView) -> Option<ControllerAction> {
+        match self {
+            ClientOperation::Create(resource) => match resource.clone() {
+                RecordedResource::Pod(pod) => Some(ControllerAction::CreatePod(fresh(pod))),
+                RecordedResource::ReplicaSet(rs) => {
+                    Some(ControllerAction::CreateReplicaSet(fresh(rs)))
+                }
+                RecordedResource::Job(job) => Some(ControllerAction::CreateJob(fresh(job))),
+                _ => None,
+            },
+            ClientOperation::Update(resource) => match resource {
+                RecordedResource::Pod(r) => {
+                    let current = view.pods.get_in(&r.metadata.namespace, &r.metadata.name)?;
+                    Some(ControllerAction::UpdatePod(updated!(current, r)))
+                }
+                RecordedResource::Deployment(r) => {
+                    let current = view
+                        .deployments
+                        .get_in(&r.metadata.namespace, &r.metadata.name)?;
+                    Some(ControllerAction::UpdateDeployment(updated!(current, r)))
+                }
+                RecordedResource::ReplicaSet(r) => {
+                    let current = view
+                        .replicasets
+                        .get_in(&r.metadata.namespace, &r.metadata.name)?;
+                    Some(ControllerAction::UpdateReplicaSet(updated!(current, r)))
+                }
+                RecordedResource::StatefulSet(r) => {
+                    let current = view
+                        .statefulsets
+                        .get_in(&r.metadata.namespace, &r.metadata.name)?;
+                    Some(ControllerAction::UpdateStatefulSet(updated!(current, r)))
+                }
+                RecordedResource::Job(r) => {
+                    let current = view.jobs.get_in(&r.metadata.namespace, &r.metadata.name)?;
+                    Some(ControllerAction::UpdateJob(updated!(current, r)))
+                }
+                RecordedResource::ReplicationController(_) => None,
+            },
+            ClientOperation::Scale {
+                kind,
+                namespace,
+                name,
+                replicas,
+            } => match kind {
+                ResourceKind::Deployments => {
+                    let d = view.deployments.get_in(namespace, name)?;
+                    Some(ControllerAction::ScaleDeployment(Scale::new(
+                        &d.metadata,
+                        *replicas,
+                        d.status.replicas,
+                    )))
+                }
+                ResourceKind::ReplicaSets => {
+                    let rs = view.replicasets.get_in(namespace, name)?;
+                    Some(ControllerAction::ScaleReplicaSet(Scale::new(
+                        &rs.metadata,
+                        *replicas,
+                        rs.status.replicas,
+                    )))
+                }
+                ResourceKind::StatefulSets => {
+                    let sts = view.statefulsets.get_in(namespace, name)?;
+                    Some(ControllerAction::ScaleStatefulSet(Scale::new(
+                        &sts.metadata,
+                        *replicas,
+                        sts.status.replicas,
+                    )))
+                }
+                _ => None,
+            },
+            ClientOperation::Delete {
+                kind,
+                namespace,
+                name,
+            } => match kind {
+                ResourceKind::Nodes => view
+                    .nodes
+                    .get(name)
+                    .map(|r| ControllerAction::DeleteNode(r.clone())),
+                ResourceKind::Pods => view
+                    .pods
+                    .get_in(namespace, name)
+                    .map(|r| ControllerAction::SoftDeletePod(r.clone())),
+                ResourceKind::Deployments => view
+                    .deployments
+                    .get_in(namespace, name)
+                    .map(|r| ControllerAction::DeleteDeployment(r.clone())),
+                ResourceKind::ReplicaSets => view
+                    .replicasets
+                    .get_in(namespace, name)
+                    .map(|r| ControllerAction::DeleteReplicaSet(r.clone())),
+                ResourceKind::ReplicationControllers => view
+                    .replication_controllers
+                    .get_in(namespace, name)
+                    .map(|r| ControllerAction::DeleteReplicationController(r.clone())),
+                ResourceKind::StatefulSets => view
+                    .statefulsets
+                    .get_in(namespace, name)
+                    .map(|r| ControllerAction::DeleteStatefulSet(r.clone())),
+                ResourceKind::Jobs => view
+                    .jobs
+                    .get_in(namespace, name)
+                    .map(|r| ControllerAction::DeleteJob(r.clone())),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The resource without the version and creation time given to it by the cluster it was recorded
+/// from, for the model to give its own.
+fn fresh<T: Meta>(mut resource: T) -> T {
+    resource.metadata_mut().resource_version = Revision::default();
+    resource.metadata_mut().creation_timestamp = None;
+    resource
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingError {
+    /// The recording couldn't be read.
+    Io(String),
+    /// The line, counting from one, was not an operation.
+    InvalidLine(usize, String),
+    /// The operation at the given index has no action in the model to take it.
+    Unsupported(usize),
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::Io(error) => write!(f, "failed to read recording: {error}"),
+            RecordingError::InvalidLine(line, error) => {
+                write!(f, "invalid operation on line {line}: {error}")
+            }
+            RecordingError::Unsupported(index) => {
+                write!(f, "operation {index} can't be replayed by the model")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+/// The operations of a recording, in the order that they were accepted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    pub operations: Vec<ClientOperation>,
+}
+
+/// A recording split up for the model to replay.
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    /// The resources created before any other operation.
+    pub initial_state: RawState,
+    /// The operations that follow, for the model to take in order.
+    pub operations: Vec<ClientOperation>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| RecordingError::Io(e.to_string()))?;
+        Self::parse(&contents)
+    }
+
+    /// Parse a recording from its lines, skipping blank ones.
+    pub fn parse(contents: &str) -> Result<Self, RecordingError> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| RecordingError::InvalidLine(i + 1, e.to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(|operations| Self { operations })
+    }
+
+    /// Split the recording into its initial state and the operations to replay, failing on the
+    /// first operation that the model can't take.
+    pub fn into_replay(mut self) -> Result<Replay, RecordingError> {
+        let setup = self
+            .operations
+            .iter()
+            .take_while(|o| matches!(o, ClientOperation::Create(_)))
+            .count();
+        let operations = self.operations.split_off(setup);
+        if let Some(i) = operations.iter().position(|o| !o.replayable()) {
+            return Err(RecordingError::Unsupported(setup + i));
+        }
+
+        let mut initial_state = RawState::default();
+        for operation in self.operations {
+            let ClientOperation::Create(resource) = operation else {
+                unreachable!("only creates are taken for the initial state");
+            };
+            match resource {
+                RecordedResource::Pod(r) => initial_state.set_pods([fresh(r)]),
+                RecordedResource::Deployment(r) => initial_state.set_deployments([fresh(r)]),
+                RecordedResource::ReplicaSet(r) => initial_state.set_replicasets([fresh(r)]),
+                RecordedResource::ReplicationController(r) => {
+                    initial_state.set_replication_controllers([fresh(r)])
+                }
+                RecordedResource::StatefulSet(r) => initial_state.set_statefulsets([fresh(r)]),
+                RecordedResource::Job(r) => initial_state.set_jobs([fresh(r)]),
+            };
+        }
+        Ok(Replay {
+            initial_state,
+            operations,
+        })
+    }
+}
+
+/// Where the served cluster records the operations that it accepts, recording nothing by default.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    out: Option<Arc<Mutex<BufWriter<File>>>>,
+}
+
+impl Recorder {
+    /// Record to the file at the path, replacing anything already there.
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            out: Some(Arc::new(Mutex::new(BufWriter::new(file)))),
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Append the operation to the recording, flushing it so that the recording is complete
+    /// whenever the cluster is stopped.
+    pub fn record(&self, operation: &ClientOperation) -> std::io::Result<()> {
+        let Some(out) = &self.out else {
+            return Ok(());
+        };
+        let mut out = out.lock().unwrap();
+        serde_json::to_writer(&mut *out, operation)?;
+        writeln!(out)?;
+        out.flush()
+    }
+}
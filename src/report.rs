@@ -1,16 +1,26 @@
+use crate::abstract_model::AbstractModel;
+use crate::controller::deployment::REVISION_ANNOTATION;
 use crate::state::history::ConsistencySetup;
+use crate::state::State;
+use crate::trace::{replay_states, Trace};
+use serde::Serialize;
 use stateright::report::Reporter;
-use std::collections::BTreeMap;
+use stateright::CheckerTerminalVisitor;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
-use std::path::Path;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use sysinfo::ProcessExt;
 use sysinfo::System;
 use sysinfo::SystemExt;
+use tracing::warn;
 
 use stateright::{Expectation, Model};
 
 pub struct JointReporter<M> {
-    pub reporters: Vec<Box<dyn Reporter<M>>>,
+    pub reporters: Vec<Box<dyn Reporter<M> + Send>>,
 }
 
 impl<M> Reporter<M> for JointReporter<M>
@@ -41,6 +51,9 @@ pub struct StdoutReporter {
     last_total: usize,
     last_unique: usize,
     properties: BTreeMap<&'static str, Expectation>,
+    warnings: BTreeSet<String>,
+    rollouts: Option<RolloutTracker>,
+    seeds: Option<SeedTracker>,
 }
 
 impl StdoutReporter {
@@ -55,8 +68,30 @@ impl StdoutReporter {
             last_total: 0,
             last_unique: 0,
             properties,
+            warnings: BTreeSet::new(),
+            rollouts: None,
+            seeds: None,
         }
     }
+
+    /// Report the named properties as warnings rather than failures when they don't hold.
+    pub fn with_warnings(mut self, warnings: BTreeSet<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Also print the rollout summaries gathered by the tracker once checking is done.
+    pub fn with_rollouts(mut self, rollouts: RolloutTracker) -> Self {
+        self.rollouts = Some(rollouts);
+        self
+    }
+
+    /// Also print the statistics of each simulated seed gathered by the tracker once checking is
+    /// done.
+    pub fn with_seeds(mut self, seeds: SeedTracker) -> Self {
+        self.seeds = Some(seeds);
+        self
+    }
 }
 
 impl<M> Reporter<M> for StdoutReporter
@@ -108,14 +143,19 @@ where
         <M as Model>::Action: std::fmt::Debug,
         <M as Model>::State: std::fmt::Debug + std::hash::Hash,
     {
-        let (success, failure): (Vec<_>, Vec<_>) =
+        let (success, unsuccessful): (Vec<_>, Vec<_>) =
             self.properties.iter().partition(|(name, expectation)| {
                 property_holds(expectation, discoveries.get(*name).is_some())
             });
+        let (warning, failure): (Vec<_>, Vec<_>) = unsuccessful
+            .into_iter()
+            .partition(|(name, _)| self.warnings.contains(**name));
 
         for (name, expectation) in &self.properties {
             let status = if property_holds(expectation, discoveries.get(name).is_some()) {
                 "OK"
+            } else if self.warnings.contains(*name) {
+                "WARNING"
             } else {
                 "FAILED"
             };
@@ -130,14 +170,44 @@ where
         }
 
         println!(
-            "Properties checked. {} succeeded, {} failed",
+            "Properties checked. {} succeeded, {} failed, {} warned",
             success.len(),
-            failure.len()
+            failure.len(),
+            warning.len()
         );
+
+        if let Some(rollouts) = &self.rollouts {
+            for (name, summary) in rollouts.summaries() {
+                println!(
+                    "Rollout {:?} paths={} replicasets_created(mean={:.2}, max={}) max_concurrent_replicasets={} peak_pods={} max_revision={}",
+                    name,
+                    summary.paths,
+                    summary.mean_replicasets_created(),
+                    summary.max_replicasets_created,
+                    summary.max_concurrent_replicasets,
+                    summary.peak_pods,
+                    summary.max_revision,
+                );
+            }
+        }
+
+        if let Some(seeds) = &self.seeds {
+            for stats in seeds.seeds() {
+                println!(
+                    "Seed {} states={} unique={} max_depth={} duration={:?} discoveries={:?}",
+                    stats.seed,
+                    stats.total_states,
+                    stats.unique_states,
+                    stats.max_depth,
+                    stats.duration,
+                    stats.discoveries,
+                );
+            }
+        }
     }
 }
 
-fn property_holds(expectation: &Expectation, discovery: bool) -> bool {
+pub(crate) fn property_holds(expectation: &Expectation, discovery: bool) -> bool {
     match (expectation, discovery) {
         // counter-example
         (Expectation::Always, true) => false,
@@ -154,12 +224,21 @@ fn property_holds(expectation: &Expectation, discovery: bool) -> bool {
     }
 }
 
+/// Whether the property holds while the check is still running.
+///
+/// THEMELIOS: Checkers only report their discoveries once they are done, so until then each
+/// property is shown as it would be without any.
+pub(crate) fn holds_until_done(expectation: &Expectation) -> bool {
+    property_holds(expectation, false)
+}
+
 pub struct CSVReporter {
     writer: csv::Writer<File>,
     consistency: ConsistencySetup,
     max_depth: usize,
     controllers: usize,
     function: String,
+    rollouts: Option<(RolloutTracker, csv::Writer<File>)>,
 }
 
 impl CSVReporter {
@@ -190,8 +269,32 @@ impl CSVReporter {
             max_depth,
             controllers,
             function,
+            rollouts: None,
         }
     }
+
+    /// Also write the rollout summaries gathered by the tracker to the given path once checking
+    /// is done.
+    pub fn with_rollouts(mut self, rollouts: RolloutTracker, path: &Path) -> Self {
+        let mut writer = csv::Writer::from_path(path).unwrap();
+        writer
+            .write_record([
+                "deployment",
+                "paths",
+                "mean_replicasets_created",
+                "max_replicasets_created",
+                "max_concurrent_replicasets",
+                "peak_pods",
+                "max_revision",
+                "consistency",
+                "max_depth",
+                "controllers",
+                "function",
+            ])
+            .unwrap();
+        self.rollouts = Some((rollouts, writer));
+        self
+    }
 }
 
 impl<M> Reporter<M> for CSVReporter
@@ -222,5 +325,705 @@ where
         <M as Model>::Action: std::fmt::Debug,
         <M as Model>::State: std::fmt::Debug + std::hash::Hash,
     {
+        if let Some((rollouts, writer)) = &mut self.rollouts {
+            for (name, summary) in rollouts.summaries() {
+                writer
+                    .write_record([
+                        name,
+                        summary.paths.to_string(),
+                        format!("{:.2}", summary.mean_replicasets_created()),
+                        summary.max_replicasets_created.to_string(),
+                        summary.max_concurrent_replicasets.to_string(),
+                        summary.peak_pods.to_string(),
+                        summary.max_revision.to_string(),
+                        self.consistency.to_string(),
+                        self.max_depth.to_string(),
+                        self.controllers.to_string(),
+                        self.function.to_owned(),
+                    ])
+                    .unwrap();
+            }
+            writer.flush().unwrap();
+        }
+    }
+}
+
+/// The schema of the database written by [`SqliteReporter`], created if it doesn't exist yet.
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    function TEXT NOT NULL,
+    consistency TEXT NOT NULL,
+    max_depth INTEGER NOT NULL,
+    controllers INTEGER NOT NULL,
+    started_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS progress (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    total_states INTEGER NOT NULL,
+    unique_states INTEGER NOT NULL,
+    max_depth_reached INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    done INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS depths (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    depth INTEGER NOT NULL,
+    count INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS properties (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    name TEXT NOT NULL,
+    expectation TEXT NOT NULL,
+    holds INTEGER NOT NULL,
+    classification TEXT,
+    path TEXT,
+    trace TEXT
+);
+CREATE TABLE IF NOT EXISTS rollouts (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    deployment TEXT NOT NULL,
+    paths INTEGER NOT NULL,
+    mean_replicasets_created REAL NOT NULL,
+    max_replicasets_created INTEGER NOT NULL,
+    max_concurrent_replicasets INTEGER NOT NULL,
+    peak_pods INTEGER NOT NULL,
+    max_revision INTEGER NOT NULL
+);
+";
+
+/// Records runs in an SQLite database, so that the results of many runs can be queried and
+/// joined together.
+///
+/// Each reporter adds a run to the database, with its progress, property results and the traces
+/// of its discoveries.
+/// Clones share the connection, so one can be kept to record the depths once checking is done.
+#[derive(Clone)]
+pub struct SqliteReporter {
+    connection: Arc<Mutex<rusqlite::Connection>>,
+    run_id: i64,
+    properties: BTreeMap<&'static str, Expectation>,
+    rollouts: Option<RolloutTracker>,
+}
+
+impl SqliteReporter {
+    pub fn new<M: Model>(
+        model: &M,
+        path: &Path,
+        consistency: ConsistencySetup,
+        max_depth: usize,
+        controllers: usize,
+        function: String,
+    ) -> rusqlite::Result<Self> {
+        let connection = rusqlite::Connection::open(path)?;
+        // test runs write to the same database in parallel
+        connection.busy_timeout(Duration::from_secs(30))?;
+        connection.execute_batch(SQLITE_SCHEMA)?;
+        let started_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        connection.execute(
+            "INSERT INTO runs (function, consistency, max_depth, controllers, started_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                function,
+                consistency.to_string(),
+                max_depth,
+                controllers,
+                started_at
+            ],
+        )?;
+        let run_id = connection.last_insert_rowid();
+        let properties = model
+            .properties()
+            .iter()
+            .map(|p| (p.name, p.expectation.clone()))
+            .collect();
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            run_id,
+            properties,
+            rollouts: None,
+        })
+    }
+
+    /// Also record the rollout summaries gathered by the tracker once checking is done.
+    pub fn with_rollouts(mut self, rollouts: RolloutTracker) -> Self {
+        self.rollouts = Some(rollouts);
+        self
+    }
+
+    /// The id of the run in the `runs` table.
+    pub fn run_id(&self) -> i64 {
+        self.run_id
+    }
+
+    /// Record the number of states seen at each depth.
+    pub fn record_depths(&self, depths: impl IntoIterator<Item = (usize, u64)>) {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction().unwrap();
+        for (depth, count) in depths {
+            tx.execute(
+                "INSERT INTO depths (run_id, depth, count) VALUES (?1, ?2, ?3)",
+                rusqlite::params![self.run_id, depth, count],
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+    }
+}
+
+impl<M> Reporter<M> for SqliteReporter
+where
+    M: Model,
+{
+    fn report_checking(&mut self, data: stateright::report::ReportData) {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO progress (run_id, total_states, unique_states, max_depth_reached, duration_ms, done) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    self.run_id,
+                    data.total_states,
+                    data.unique_states,
+                    data.max_depth,
+                    data.duration.as_millis() as u64,
+                    data.done
+                ],
+            )
+            .unwrap();
+    }
+
+    fn report_discoveries(
+        &mut self,
+        discoveries: BTreeMap<&'static str, stateright::report::ReportDiscovery<M>>,
+    ) where
+        <M as Model>::Action: std::fmt::Debug,
+        <M as Model>::State: std::fmt::Debug + std::hash::Hash,
+    {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction().unwrap();
+        for (name, expectation) in &self.properties {
+            let discovery = discoveries.get(name);
+            tx.execute(
+                "INSERT INTO properties (run_id, name, expectation, holds, classification, path, trace) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    self.run_id,
+                    name,
+                    format!("{:?}", expectation),
+                    property_holds(expectation, discovery.is_some()),
+                    discovery.map(|d| d.classification.to_string()),
+                    discovery.map(|d| d.path.encode()),
+                    discovery.map(|d| d.path.to_string()),
+                ],
+            )
+            .unwrap();
+        }
+        if let Some(rollouts) = &self.rollouts {
+            for (name, summary) in rollouts.summaries() {
+                tx.execute(
+                    "INSERT INTO rollouts (run_id, deployment, paths, mean_replicasets_created, max_replicasets_created, max_concurrent_replicasets, peak_pods, max_revision) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    rusqlite::params![
+                        self.run_id,
+                        name,
+                        summary.paths,
+                        summary.mean_replicasets_created(),
+                        summary.max_replicasets_created,
+                        summary.max_concurrent_replicasets,
+                        summary.peak_pods,
+                        summary.max_revision
+                    ],
+                )
+                .unwrap();
+            }
+        }
+        tx.commit().unwrap();
+    }
+}
+
+/// Writes the results of a check to a JSON file, for tools to read without parsing the other
+/// reports.
+///
+/// The file is rewritten with everything known so far each time the check reports, so it holds
+/// the final results once the check is done.
+pub struct JsonReporter {
+    path: PathBuf,
+    config: JsonConfig,
+    properties: BTreeMap<&'static str, Expectation>,
+    data: Option<stateright::report::ReportData>,
+    discoveries: BTreeMap<&'static str, JsonDiscovery>,
+}
+
+/// The configuration of the check that the results are for.
+#[derive(Debug, Clone, Serialize)]
+struct JsonConfig {
+    consistency: String,
+    max_depth: usize,
+    controllers: usize,
+    function: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JsonReport<'a> {
+    config: &'a JsonConfig,
+    total_states: usize,
+    unique_states: usize,
+    max_depth_reached: usize,
+    duration_ms: u128,
+    done: bool,
+    properties: Vec<JsonProperty<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JsonProperty<'a> {
+    name: &'static str,
+    expectation: String,
+    holds: bool,
+    discovery: Option<&'a JsonDiscovery>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JsonDiscovery {
+    classification: String,
+    /// The encoded path to the discovery, to explore it with.
+    path: String,
+}
+
+impl JsonReporter {
+    pub fn new<M: Model>(
+        model: &M,
+        path: &Path,
+        consistency: ConsistencySetup,
+        max_depth: usize,
+        controllers: usize,
+        function: String,
+    ) -> Self {
+        let properties = model
+            .properties()
+            .iter()
+            .map(|p| (p.name, p.expectation.clone()))
+            .collect();
+        Self {
+            path: path.to_owned(),
+            config: JsonConfig {
+                consistency: consistency.to_string(),
+                max_depth,
+                controllers,
+                function,
+            },
+            properties,
+            data: None,
+            discoveries: BTreeMap::new(),
+        }
+    }
+
+    fn write(&self) {
+        let data = self.data.clone().unwrap_or(stateright::report::ReportData {
+            total_states: 0,
+            unique_states: 0,
+            max_depth: 0,
+            duration: Duration::ZERO,
+            done: false,
+        });
+        let properties = self
+            .properties
+            .iter()
+            .map(|(&name, expectation)| {
+                let discovery = self.discoveries.get(name);
+                JsonProperty {
+                    name,
+                    expectation: format!("{:?}", expectation),
+                    holds: property_holds(expectation, discovery.is_some()),
+                    discovery,
+                }
+            })
+            .collect();
+        let report = JsonReport {
+            config: &self.config,
+            total_states: data.total_states,
+            unique_states: data.unique_states,
+            max_depth_reached: data.max_depth,
+            duration_ms: data.duration.as_millis(),
+            done: data.done,
+            properties,
+        };
+        let file = File::create(&self.path).unwrap();
+        serde_json::to_writer_pretty(file, &report).unwrap();
+    }
+}
+
+impl<M> Reporter<M> for JsonReporter
+where
+    M: Model,
+{
+    fn report_checking(&mut self, data: stateright::report::ReportData) {
+        self.data = Some(data);
+        self.write();
+    }
+
+    fn report_discoveries(
+        &mut self,
+        discoveries: BTreeMap<&'static str, stateright::report::ReportDiscovery<M>>,
+    ) where
+        <M as Model>::Action: std::fmt::Debug,
+        <M as Model>::State: std::fmt::Debug + std::hash::Hash,
+    {
+        self.discoveries = discoveries
+            .into_iter()
+            .map(|(name, discovery)| {
+                let discovery = JsonDiscovery {
+                    classification: discovery.classification.to_string(),
+                    path: discovery.path.encode(),
+                };
+                (name, discovery)
+            })
+            .collect();
+        self.write();
+    }
+}
+
+/// Writes the discoveries of a check to a self-contained HTML file, each as a table of the
+/// actions taken along its path and the changes they made to the resources.
+///
+/// THEMELIOS: Discoveries are replayed through the model to render them, so this only works for
+/// checks of the same model that it was created with.
+pub struct HtmlReporter {
+    model: AbstractModel,
+    path: PathBuf,
+    data: Option<stateright::report::ReportData>,
+}
+
+impl HtmlReporter {
+    pub fn new(model: &AbstractModel, path: &Path) -> Self {
+        Self {
+            model: model.clone(),
+            path: path.to_owned(),
+            data: None,
+        }
+    }
+}
+
+impl Reporter<AbstractModel> for HtmlReporter {
+    fn report_checking(&mut self, data: stateright::report::ReportData) {
+        self.data = Some(data);
+    }
+
+    fn report_discoveries(
+        &mut self,
+        discoveries: BTreeMap<&'static str, stateright::report::ReportDiscovery<AbstractModel>>,
+    ) {
+        let mut html = String::from(HTML_HEADER);
+        if let Some(data) = &self.data {
+            html.push_str(&format!(
+                "<p>states={} unique={} max_depth={} duration={:?}</p>\n",
+                data.total_states, data.unique_states, data.max_depth, data.duration
+            ));
+        }
+        if discoveries.is_empty() {
+            html.push_str("<p>No discoveries.</p>\n");
+        }
+        for (name, discovery) in discoveries {
+            let path = discovery.path.encode();
+            html.push_str(&format!(
+                "<h2>{}: {}</h2>\n<p>To explore this path try re-running with <code>tui {}</code></p>\n",
+                discovery.classification,
+                escape_html(name),
+                path
+            ));
+            match Trace::replay(&self.model, &path) {
+                Ok(trace) => html.push_str(&trace_table(&trace)),
+                Err(error) => {
+                    warn!(?error, "Failed to replay path for the html report");
+                    html.push_str(&format!(
+                        "<p>Failed to replay the path: {}</p>\n",
+                        escape_html(&format!("{:?}", error))
+                    ));
+                }
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        std::fs::write(&self.path, html).unwrap();
+    }
+}
+
+const HTML_HEADER: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Themelios discoveries</title>
+<style>
+body { font-family: sans-serif; }
+table { border-collapse: collapse; width: 100%; }
+td, th { border: 1px solid #ccc; padding: 4px; text-align: left; vertical-align: top; }
+pre { margin: 0; }
+.add { color: #22863a; }
+.remove { color: #cb2431; }
+.hunk { color: #6f42c1; }
+</style>
+</head>
+<body>
+<h1>Themelios discoveries</h1>
+"#;
+
+/// A table of the steps of the trace, with the action taken and the resources it changed.
+pub fn trace_table(trace: &Trace) -> String {
+    let mut html = String::from("<table>\n<tr><th>Step</th><th>Action</th><th>Diff</th></tr>\n");
+    for (i, step) in trace.steps.iter().enumerate() {
+        let action = step.action.as_deref().unwrap_or("initial state");
+        let diff = trace
+            .diff(i)
+            .lines()
+            .map(|line| {
+                let class = if line.starts_with("@@") {
+                    "hunk"
+                } else if line.starts_with('+') {
+                    "add"
+                } else if line.starts_with('-') {
+                    "remove"
+                } else {
+                    ""
+                };
+                format!("<span class=\"{}\">{}</span>\n", class, escape_html(line))
+            })
+            .collect::<String>();
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>\n",
+            i,
+            escape_html(action),
+            diff
+        ));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// How the rollout of a single deployment went along one path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RolloutStats {
+    /// The number of distinct replicasets owned by the deployment at some point.
+    pub replicasets_created: usize,
+    /// The most replicasets owned by the deployment at once.
+    pub max_concurrent_replicasets: usize,
+    /// The most pods owned by the deployment's replicasets at once.
+    pub peak_pods: usize,
+    /// The highest revision of the deployment's replicasets.
+    pub max_revision: u64,
+}
+
+/// The rollout stats for each deployment, by name, over the states of a path.
+pub fn rollout_stats(states: &[State]) -> BTreeMap<String, RolloutStats> {
+    let mut stats = BTreeMap::<String, RolloutStats>::new();
+    let mut seen = BTreeMap::<String, BTreeSet<String>>::new();
+    for state in states {
+        let view = state.latest();
+        for deployment in view.deployments.iter() {
+            let name = &deployment.metadata.name;
+            let stats = stats.entry(name.clone()).or_default();
+            let seen = seen.entry(name.clone()).or_default();
+            let replicasets = view
+                .replicasets
+                .for_controller(&deployment.metadata.uid)
+                .collect::<Vec<_>>();
+            let pods = replicasets
+                .iter()
+                .map(|rs| view.pods.for_controller(&rs.metadata.uid).count())
+                .sum::<usize>();
+            seen.extend(replicasets.iter().map(|rs| rs.metadata.uid.clone()));
+            stats.replicasets_created = seen.len();
+            stats.max_concurrent_replicasets =
+                stats.max_concurrent_replicasets.max(replicasets.len());
+            stats.peak_pods = stats.peak_pods.max(pods);
+            let revision = replicasets
+                .iter()
+                .filter_map(|rs| rs.metadata.annotations.get(REVISION_ANNOTATION))
+                .filter_map(|r| r.parse::<u64>().ok())
+                .max()
+                .unwrap_or_default();
+            stats.max_revision = stats.max_revision.max(revision);
+        }
+    }
+    stats
+}
+
+/// The rollout stats of a deployment aggregated over all of the terminal paths checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RolloutSummary {
+    pub paths: u64,
+    pub total_replicasets_created: u64,
+    pub max_replicasets_created: usize,
+    pub max_concurrent_replicasets: usize,
+    pub peak_pods: usize,
+    pub max_revision: u64,
+}
+
+impl RolloutSummary {
+    pub fn add(&mut self, stats: &RolloutStats) {
+        self.paths += 1;
+        self.total_replicasets_created += stats.replicasets_created as u64;
+        self.max_replicasets_created = self.max_replicasets_created.max(stats.replicasets_created);
+        self.max_concurrent_replicasets = self
+            .max_concurrent_replicasets
+            .max(stats.max_concurrent_replicasets);
+        self.peak_pods = self.peak_pods.max(stats.peak_pods);
+        self.max_revision = self.max_revision.max(stats.max_revision);
+    }
+
+    pub fn mean_replicasets_created(&self) -> f64 {
+        if self.paths == 0 {
+            return 0.0;
+        }
+        self.total_replicasets_created as f64 / self.paths as f64
+    }
+}
+
+/// Summarises the deployment rollouts seen along each terminal path of a check.
+///
+/// Summaries are shared between clones so the tracker can be handed to the checker and the
+/// reporters alike.
+#[derive(Debug, Clone, Default)]
+pub struct RolloutTracker {
+    summaries: Arc<Mutex<BTreeMap<String, RolloutSummary>>>,
+}
+
+impl RolloutTracker {
+    pub fn summaries(&self) -> BTreeMap<String, RolloutSummary> {
+        self.summaries.lock().unwrap().clone()
+    }
+}
+
+impl CheckerTerminalVisitor<AbstractModel> for RolloutTracker {
+    fn visit(&self, model: &AbstractModel, path: &[NonZeroU64]) {
+        // deployments are only ever in the initial state, so skip replaying when there are none
+        if model
+            .initial_states
+            .iter()
+            .all(|s| s.latest().deployments.is_empty())
+        {
+            return;
+        }
+        let fingerprints = path.iter().map(|fp| fp.get()).collect::<Vec<_>>();
+        let states = match replay_states(model, &fingerprints) {
+            Ok(states) => states,
+            Err(error) => {
+                warn!(?error, "Failed to replay path for the rollout summary");
+                return;
+            }
+        };
+        let mut summaries = self.summaries.lock().unwrap();
+        for (name, stats) in rollout_stats(&states) {
+            summaries.entry(name).or_default().add(&stats);
+        }
+    }
+}
+
+/// Passes each terminal path on to all of the visitors.
+pub struct JointTerminalVisitor {
+    pub visitors: Vec<Box<dyn CheckerTerminalVisitor<AbstractModel> + Send + Sync>>,
+}
+
+impl CheckerTerminalVisitor<AbstractModel> for JointTerminalVisitor {
+    fn visit(&self, model: &AbstractModel, path: &[NonZeroU64]) {
+        for visitor in &self.visitors {
+            visitor.visit(model, path);
+        }
+    }
+}
+
+/// Counts the terminal paths of a check by their length.
+///
+/// Counts are shared between clones so the histogram can be handed to the checker and the
+/// reporters alike.
+#[derive(Debug, Clone, Default)]
+pub struct DepthHistogram {
+    counts: Arc<Mutex<BTreeMap<usize, u64>>>,
+}
+
+impl DepthHistogram {
+    /// The number of terminal paths of each length seen so far.
+    pub fn counts(&self) -> BTreeMap<usize, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+}
+
+impl CheckerTerminalVisitor<AbstractModel> for DepthHistogram {
+    fn visit(&self, _model: &AbstractModel, path: &[NonZeroU64]) {
+        *self.counts.lock().unwrap().entry(path.len()).or_default() += 1;
+    }
+}
+
+/// Keeps the longest terminal path of a check, which includes those of the states that were still
+/// to be explored when a check is stopped early, as they have no successors left to explore.
+///
+/// The path is shared between clones so the visitor can be handed to the checker and still be read
+/// afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct DeepestPath {
+    path: Arc<Mutex<Vec<NonZeroU64>>>,
+}
+
+impl DeepestPath {
+    /// The length of the longest path seen so far.
+    pub fn depth(&self) -> usize {
+        self.path.lock().unwrap().len()
+    }
+
+    /// The longest path seen so far, encoded as for `explore` and `tui`.
+    pub fn encoded_path(&self) -> Option<String> {
+        let path = self.path.lock().unwrap();
+        (!path.is_empty()).then(|| {
+            path.iter()
+                .map(|fp| fp.to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+    }
+}
+
+impl CheckerTerminalVisitor<AbstractModel> for DeepestPath {
+    fn visit(&self, _model: &AbstractModel, path: &[NonZeroU64]) {
+        let mut deepest = self.path.lock().unwrap();
+        if path.len() > deepest.len() {
+            *deepest = path.to_vec();
+        }
+    }
+}
+
+/// How much of the state space a simulation with one seed covered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeedStats {
+    pub seed: u64,
+    pub total_states: usize,
+    pub unique_states: usize,
+    pub max_depth: usize,
+    pub duration: Duration,
+    /// The properties that the seed found a discovery for.
+    pub discoveries: Vec<&'static str>,
+}
+
+/// Gathers the statistics of each seed of a simulation over many seeds.
+///
+/// Statistics are shared between clones so the tracker can be handed to the simulation and the
+/// reporters alike.
+#[derive(Debug, Clone, Default)]
+pub struct SeedTracker {
+    seeds: Arc<Mutex<BTreeMap<u64, SeedStats>>>,
+}
+
+impl SeedTracker {
+    pub fn add(&self, stats: SeedStats) {
+        self.seeds.lock().unwrap().insert(stats.seed, stats);
+    }
+
+    /// The statistics of the seeds that have finished, in order of seed.
+    pub fn seeds(&self) -> Vec<SeedStats> {
+        self.seeds.lock().unwrap().values().cloned().collect()
     }
 }
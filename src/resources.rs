@@ -1,18 +1,24 @@
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::Display,
     iter::Sum,
-    ops::{Add, AddAssign, Sub, SubAssign},
+    ops::{Add, Sub, SubAssign},
 };
 
 use crate::state::revision::Revision;
 
 pub trait Meta {
+    /// Whether resources of this kind live in a namespace, rather than being cluster-scoped.
+    const NAMESPACED: bool = true;
+
     fn metadata(&self) -> &Metadata;
     fn metadata_mut(&mut self) -> &mut Metadata;
 }
 
+/// Kinds of resource that are cluster-scoped, so are unique by their name alone.
+pub trait ClusterScoped: Meta {}
+
 macro_rules! impl_meta {
     ($r:ident) => {
         impl Meta for $r {
@@ -24,16 +30,45 @@ macro_rules! impl_meta {
             }
         }
     };
+    ($r:ident, cluster) => {
+        impl Meta for $r {
+            const NAMESPACED: bool = false;
+
+            fn metadata(&self) -> &Metadata {
+                &self.metadata
+            }
+            fn metadata_mut(&mut self) -> &mut Metadata {
+                &mut self.metadata
+            }
+        }
+
+        impl ClusterScoped for $r {}
+    };
 }
 
 impl_meta!(Pod);
 impl_meta!(Job);
+impl_meta!(CronJob);
 impl_meta!(Deployment);
 impl_meta!(ReplicaSet);
+impl_meta!(ReplicationController);
 impl_meta!(StatefulSet);
 impl_meta!(ControllerRevision);
 impl_meta!(PersistentVolumeClaim);
-impl_meta!(Node);
+impl_meta!(PersistentVolume, cluster);
+impl_meta!(StorageClass, cluster);
+impl_meta!(PriorityClass, cluster);
+impl_meta!(Lease);
+impl_meta!(Service);
+impl_meta!(Endpoints);
+impl_meta!(ConfigMap);
+impl_meta!(Secret);
+impl_meta!(Role);
+impl_meta!(RoleBinding);
+impl_meta!(Event);
+impl_meta!(Node, cluster);
+impl_meta!(HorizontalPodAutoscaler);
+impl_meta!(Namespace, cluster);
 
 pub trait ObservedGeneration {
     fn observed_generation(&self) -> u64;
@@ -53,7 +88,9 @@ macro_rules! impl_observed_generation {
 impl_observed_generation!(Job);
 impl_observed_generation!(Deployment);
 impl_observed_generation!(ReplicaSet);
+impl_observed_generation!(ReplicationController);
 impl_observed_generation!(StatefulSet);
+impl_observed_generation!(HorizontalPodAutoscaler);
 // impl_observed_generation!(ControllerRevision);
 // impl_observed_generation!(PersistentVolumeClaim);
 // impl_observed_generation!(Node);
@@ -83,6 +120,7 @@ macro_rules! impl_observed_revision {
 impl_observed_revision!(Job);
 impl_observed_revision!(Deployment);
 impl_observed_revision!(ReplicaSet);
+impl_observed_revision!(ReplicationController);
 impl_observed_revision!(StatefulSet);
 // impl_observed_revision!(ControllerRevision);
 // impl_observed_revision!(PersistentVolumeClaim);
@@ -107,11 +145,52 @@ macro_rules! impl_spec {
 
 impl_spec!(Pod, PodSpec);
 impl_spec!(Job, JobSpec);
+impl_spec!(CronJob, CronJobSpec);
 impl_spec!(Deployment, DeploymentSpec);
 impl_spec!(ReplicaSet, ReplicaSetSpec);
+impl_spec!(ReplicationController, ReplicationControllerSpec);
 impl_spec!(StatefulSet, StatefulSetSpec);
 impl_spec!(PersistentVolumeClaim, PersistentVolumeClaimSpec);
+impl_spec!(PersistentVolume, PersistentVolumeSpec);
 impl_spec!(Node, NodeSpec);
+impl_spec!(HorizontalPodAutoscaler, HorizontalPodAutoscalerSpec);
+impl_spec!(Namespace, NamespaceSpec);
+impl_spec!(Lease, LeaseSpec);
+impl_spec!(Service, ServiceSpec);
+
+/// Resources with a status subresource, whose writes only change the status and metadata.
+pub trait StatusSubresource: Spec {
+    /// Keep the spec of the existing version of the resource, as the status subresource does.
+    fn reset_spec(&mut self, existing: &Self);
+
+    /// Keep the status of the existing version of the resource, as writes to the resource itself
+    /// do.
+    fn reset_status(&mut self, existing: &Self);
+}
+
+macro_rules! impl_status_subresource {
+    ($r:ident) => {
+        impl StatusSubresource for $r {
+            fn reset_spec(&mut self, existing: &Self) {
+                self.spec = existing.spec.clone();
+            }
+
+            fn reset_status(&mut self, existing: &Self) {
+                self.status = existing.status.clone();
+            }
+        }
+    };
+}
+
+impl_status_subresource!(Job);
+impl_status_subresource!(CronJob);
+impl_status_subresource!(Deployment);
+impl_status_subresource!(ReplicaSet);
+impl_status_subresource!(ReplicationController);
+impl_status_subresource!(StatefulSet);
+impl_status_subresource!(PersistentVolumeClaim);
+impl_status_subresource!(PersistentVolume);
+impl_status_subresource!(HorizontalPodAutoscaler);
 
 impl Spec for ControllerRevision {
     type Spec = ();
@@ -120,6 +199,48 @@ impl Spec for ControllerRevision {
     }
 }
 
+impl Spec for StorageClass {
+    type Spec = ();
+    fn spec(&self) -> &Self::Spec {
+        &()
+    }
+}
+
+impl Spec for PriorityClass {
+    type Spec = ();
+    fn spec(&self) -> &Self::Spec {
+        &()
+    }
+}
+
+impl Spec for ConfigMap {
+    type Spec = ();
+    fn spec(&self) -> &Self::Spec {
+        &()
+    }
+}
+
+impl Spec for Secret {
+    type Spec = ();
+    fn spec(&self) -> &Self::Spec {
+        &()
+    }
+}
+
+impl Spec for Role {
+    type Spec = ();
+    fn spec(&self) -> &Self::Spec {
+        &()
+    }
+}
+
+impl Spec for RoleBinding {
+    type Spec = ();
+    fn spec(&self) -> &Self::Spec {
+        &()
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Metadata {
@@ -181,7 +302,7 @@ pub struct Metadata {
     pub generation: u64,
 
     // An opaque value that represents the internal version of this object that can be used by clients to determine when objects have changed. May be used for optimistic concurrency, change detection, and the watch operation on a resource or set of resources. Clients must treat these values as opaque and passed unmodified back to the server. They may only be valid for a particular resource or set of resources.
-    #[serde(default)]
+    #[serde(default = "Revision::empty")]
     pub resource_version: Revision,
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -254,6 +375,162 @@ impl Pod {
         version: "v1",
         kind: "Pod",
     };
+
+    /// The config maps that the pod refers to, from its volumes and the environment of its
+    /// containers, with the keys that it needs from each.
+    pub fn config_map_references(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut references = BTreeMap::<_, BTreeSet<_>>::new();
+        for volume in &self.spec.volumes {
+            if let Some(source) = &volume.config_map {
+                references.entry(source.name.clone()).or_default();
+            }
+        }
+        for container in self
+            .spec
+            .init_containers
+            .iter()
+            .chain(&self.spec.containers)
+        {
+            for env in &container.env {
+                let selector = env
+                    .value_from
+                    .as_ref()
+                    .and_then(|s| s.config_map_key_ref.as_ref());
+                if let Some(selector) = selector {
+                    references
+                        .entry(selector.name.clone())
+                        .or_default()
+                        .insert(selector.key.clone());
+                }
+            }
+            for source in container
+                .env_from
+                .iter()
+                .filter_map(|s| s.config_map_ref.as_ref())
+            {
+                references.entry(source.name.clone()).or_default();
+            }
+        }
+        references
+    }
+
+    /// The secrets that the pod refers to, from its volumes and the environment of its
+    /// containers, with the keys that it needs from each.
+    pub fn secret_references(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut references = BTreeMap::<_, BTreeSet<_>>::new();
+        for volume in &self.spec.volumes {
+            if let Some(source) = &volume.secret {
+                references.entry(source.secret_name.clone()).or_default();
+            }
+        }
+        for container in self
+            .spec
+            .init_containers
+            .iter()
+            .chain(&self.spec.containers)
+        {
+            for env in &container.env {
+                let selector = env
+                    .value_from
+                    .as_ref()
+                    .and_then(|s| s.secret_key_ref.as_ref());
+                if let Some(selector) = selector {
+                    references
+                        .entry(selector.name.clone())
+                        .or_default()
+                        .insert(selector.key.clone());
+                }
+            }
+            for source in container
+                .env_from
+                .iter()
+                .filter_map(|s| s.secret_ref.as_ref())
+            {
+                references.entry(source.name.clone()).or_default();
+            }
+        }
+        references
+    }
+
+    /// The value of the field of the pod at the path, as the downward API exposes it to the pod's
+    /// containers, none if the path isn't one that it supports.
+    ///
+    /// Labels and annotations that the pod doesn't have are empty, as are unset fields.
+    pub fn field_value(&self, field_path: &str) -> Option<String> {
+        let subscript = |prefix: &str| {
+            field_path
+                .strip_prefix(prefix)?
+                .strip_prefix("['")?
+                .strip_suffix("']")
+        };
+        if let Some(key) = subscript("metadata.labels") {
+            return Some(self.metadata.labels.get(key).cloned().unwrap_or_default());
+        }
+        if let Some(key) = subscript("metadata.annotations") {
+            return Some(
+                self.metadata
+                    .annotations
+                    .get(key)
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        match field_path {
+            "metadata.name" => Some(self.metadata.name.clone()),
+            "metadata.namespace" => Some(self.metadata.namespace.clone()),
+            "metadata.uid" => Some(self.metadata.uid.clone()),
+            "spec.nodeName" => Some(self.spec.node_name.clone().unwrap_or_default()),
+            _ => None,
+        }
+    }
+
+    /// The quality of service class of the pod, computed from the cpu and memory requests and
+    /// limits of its containers rather than read from its status, which records this class when
+    /// the pod is created.
+    pub fn qos_class(&self) -> PodQosClass {
+        let quantity = |quantities: &Option<ResourceQuantities>, resource: &str| {
+            quantities
+                .as_ref()
+                .and_then(|q| q.others.get(resource))
+                .and_then(|q| q.milli_value().ok())
+                .filter(|&q| q > 0)
+        };
+        let mut best_effort = true;
+        let mut guaranteed = true;
+        for container in self
+            .spec
+            .init_containers
+            .iter()
+            .chain(&self.spec.containers)
+        {
+            for resource in ["cpu", "memory"] {
+                let request = quantity(&container.resources.requests, resource);
+                let limit = quantity(&container.resources.limits, resource);
+                if request.is_some() || limit.is_some() {
+                    best_effort = false;
+                }
+                // requests default to the limits when not given
+                if limit.is_none() || request.map_or(false, |r| Some(r) != limit) {
+                    guaranteed = false;
+                }
+            }
+        }
+        if best_effort {
+            PodQosClass::BestEffort
+        } else if guaranteed {
+            PodQosClass::Guaranteed
+        } else {
+            PodQosClass::Burstable
+        }
+    }
+}
+
+/// The quality of service classes of pods, ordered from the first to be evicted to the last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PodQosClass {
+    BestEffort,
+    Burstable,
+    Guaranteed,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -291,6 +568,26 @@ pub struct PodSpec {
 
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub node_selector: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affinity: Option<Affinity>,
+
+    // The priority of the pod, filled in from its priority class when it is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority_class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preemption_policy: Option<PreemptionPolicy>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum PreemptionPolicy {
+    #[default]
+    PreemptLowerPriority,
+    Never,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -321,6 +618,126 @@ pub enum Operator {
     Exists,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Affinity {
+    pub node_affinity: Option<NodeAffinity>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeAffinity {
+    // If the affinity requirements specified by this field are not met at scheduling time, the pod will not be scheduled onto the node. If they cease to be met at some point during pod execution (e.g. due to an update), the system may or may not try to eventually evict the pod from its node.
+    pub required_during_scheduling_ignored_during_execution: Option<NodeSelector>,
+    // The scheduler will prefer to schedule pods to nodes that satisfy the affinity expressions specified by this field, but it may choose a node that violates one or more of the expressions.
+    #[serde(default)]
+    pub preferred_during_scheduling_ignored_during_execution: Vec<PreferredSchedulingTerm>,
+}
+
+/// A node selector represents the union of the results of one or more label queries over a set
+/// of nodes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSelector {
+    // Required. A list of node selector terms. The terms are ORed.
+    #[serde(default)]
+    pub node_selector_terms: Vec<NodeSelectorTerm>,
+}
+
+impl NodeSelector {
+    pub fn matches(&self, node: &Node) -> bool {
+        self.node_selector_terms.iter().any(|t| t.matches(node))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSelectorTerm {
+    // A list of node selector requirements by node's labels.
+    #[serde(default)]
+    pub match_expressions: Vec<NodeSelectorRequirement>,
+    // A list of node selector requirements by node's fields, only `metadata.name` is supported.
+    #[serde(default)]
+    pub match_fields: Vec<NodeSelectorRequirement>,
+}
+
+impl NodeSelectorTerm {
+    /// Whether the node meets all of the requirements, an empty term matches no nodes.
+    pub fn matches(&self, node: &Node) -> bool {
+        if self.match_expressions.is_empty() && self.match_fields.is_empty() {
+            return false;
+        }
+        let fields = BTreeMap::from([("metadata.name".to_owned(), node.metadata.name.clone())]);
+        self.match_expressions
+            .iter()
+            .all(|r| r.matches(&node.metadata.labels))
+            && self.match_fields.iter().all(|r| r.matches(&fields))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSelectorRequirement {
+    // The label key that the selector applies to.
+    pub key: String,
+    // Represents a key's relationship to a set of values.
+    pub operator: NodeSelectorOperator,
+    // An array of string values. If the operator is In or NotIn, the values array must be non-empty. If the operator is Exists or DoesNotExist, the values array must be empty. If the operator is Gt or Lt, the values array must have a single element, which will be interpreted as an integer.
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+impl NodeSelectorRequirement {
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        let value = labels.get(&self.key);
+        match self.operator {
+            NodeSelectorOperator::In => value.map_or(false, |v| self.values.contains(v)),
+            NodeSelectorOperator::NotIn => value.map_or(true, |v| !self.values.contains(v)),
+            NodeSelectorOperator::Exists => value.is_some(),
+            NodeSelectorOperator::DoesNotExist => value.is_none(),
+            NodeSelectorOperator::Gt | NodeSelectorOperator::Lt => {
+                // both sides must be integers, anything else doesn't match
+                let value = value.and_then(|v| v.parse::<i64>().ok());
+                let bound = match self.values.as_slice() {
+                    [bound] => bound.parse::<i64>().ok(),
+                    _ => None,
+                };
+                let (Some(value), Some(bound)) = (value, bound) else {
+                    return false;
+                };
+                if self.operator == NodeSelectorOperator::Gt {
+                    value > bound
+                } else {
+                    value < bound
+                }
+            }
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum NodeSelectorOperator {
+    #[default]
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist,
+    Gt,
+    Lt,
+}
+
+/// An empty preferred scheduling term matches all objects with implicit weight 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreferredSchedulingTerm {
+    // Weight associated with matching the corresponding nodeSelectorTerm, in the range 1-100.
+    pub weight: u32,
+    // A node selector term, associated with the corresponding weight.
+    pub preference: NodeSelectorTerm,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TaintEffect {
     NoSchedule,
@@ -333,6 +750,10 @@ pub enum TaintEffect {
 pub struct Volume {
     pub name: String,
     pub persistent_volume_claim: Option<PersistentVolumeClaimVolumeSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_map: Option<ConfigMapVolumeSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<SecretVolumeSource>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -343,16 +764,59 @@ pub struct PersistentVolumeClaimVolumeSource {
     pub read_only: bool,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMapVolumeSource {
+    pub name: String,
+    // Whether the volume can be mounted without the config map existing.
+    pub optional: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretVolumeSource {
+    pub secret_name: String,
+    // Whether the volume can be mounted without the secret existing.
+    pub optional: Option<bool>,
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Container {
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub name: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub image: String,
     #[serde(default, skip_serializing_if = "is_default")]
     pub resources: ResourceRequirements,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub env: Vec<EnvVar>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_from: Vec<EnvFromSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liveness_probe: Option<Probe>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readiness_probe: Option<Probe>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_probe: Option<Probe>,
+}
+
+/// A periodic check of a container by the kubelet.
+///
+/// THEMELIOS: Probe outcomes are chosen by the model rather than by running a handler, so the
+/// handler is not modelled and the timings are only kept for reference.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Probe {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_delay_seconds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period_seconds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success_threshold: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<u32>,
 }
 
 fn is_default<D: Default + PartialEq>(val: &D) -> bool {
@@ -367,13 +831,60 @@ pub struct EnvVar {
     pub value_from: Option<EnvVarSource>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EnvVarSource {
-    // pub config_map_key_ref: Option<ConfigMapKeySelector>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_map_key_ref: Option<ConfigMapKeySelector>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub field_ref: Option<ObjectFieldSelector>,
     // pub resource_field_ref: Option<ResourceFieldSelector>,
-    // pub secret_key_ref: Option<SecretKeySelector>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key_ref: Option<SecretKeySelector>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMapKeySelector {
+    pub name: String,
+    pub key: String,
+    // Whether the container can start without the config map or its key existing.
+    pub optional: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretKeySelector {
+    pub name: String,
+    pub key: String,
+    // Whether the container can start without the secret or its key existing.
+    pub optional: Option<bool>,
+}
+
+/// Populates the environment of a container with all of the keys of a config map or secret.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvFromSource {
+    // Prepended to each key to give the environment variable names.
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_map_ref: Option<ConfigMapEnvSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_ref: Option<SecretEnvSource>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMapEnvSource {
+    pub name: String,
+    pub optional: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretEnvSource {
+    pub name: String,
+    pub optional: Option<bool>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -390,6 +901,11 @@ pub struct PodStatus {
     #[serde(default)]
     pub phase: PodPhase,
 
+    // The Quality of Service (QOS) classification assigned to the pod based on resource
+    // requirements, set when the pod is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos_class: Option<PodQosClass>,
+
     #[serde(default)]
     pub conditions: Vec<PodCondition>,
 
@@ -531,18 +1047,62 @@ pub struct ResourceQuantities {
     pub others: BTreeMap<String, Quantity>,
 }
 
-impl Add<ResourceQuantities> for ResourceQuantities {
-    type Output = ResourceQuantities;
+impl ResourceQuantities {
+    /// Whether each of these quantities is no more than the corresponding available one, resources
+    /// that aren't available count as having none.
+    ///
+    /// Invalid quantities never fit.
+    pub fn fits_within(&self, available: &ResourceQuantities) -> bool {
+        self.others.iter().all(|(res, q)| {
+            let available = available
+                .others
+                .get(res)
+                .map_or(Ok(0), Quantity::milli_value);
+            matches!((q.milli_value(), available), (Ok(q), Ok(a)) if q <= a)
+        })
+    }
 
-    fn add(self, rhs: ResourceQuantities) -> Self::Output {
+    /// The larger of each of the quantities between these and the others.
+    pub fn max(self, other: ResourceQuantities) -> ResourceQuantities {
+        let mut others = self.others;
+        for (res, q) in other.others {
+            let current = others.entry(res).or_default();
+            if q.milli_value().ok() > current.milli_value().ok() {
+                *current = q;
+            }
+        }
+        Self { others }
+    }
+
+    /// Combine each of the quantities with the corresponding one in the others, keeping an invalid
+    /// quantity in place of the result so that it is never mistaken for a valid one.
+    fn combine(
+        self,
+        rhs: ResourceQuantities,
+        op: impl Fn(&Quantity, &Quantity) -> Result<Quantity, QuantityError>,
+    ) -> ResourceQuantities {
         let mut others = self.others;
         for (res, q) in rhs.others {
-            *others.entry(res).or_default() += q;
+            let current = others.entry(res).or_default();
+            match op(current, &q) {
+                Ok(result) => *current = result,
+                Err(_) if current.milli_value().is_ok() => *current = q,
+                Err(_) => {}
+            }
         }
         Self { others }
     }
 }
 
+impl Add<ResourceQuantities> for ResourceQuantities {
+    type Output = ResourceQuantities;
+
+    /// Saturating addition of each of the quantities.
+    fn add(self, rhs: ResourceQuantities) -> Self::Output {
+        self.combine(rhs, Quantity::saturating_add)
+    }
+}
+
 impl<'a> Sum<&'a ResourceQuantities> for ResourceQuantities {
     fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
         iter.fold(ResourceQuantities::default(), |acc, v| acc + v.clone())
@@ -557,14 +1117,9 @@ pub struct ResourceClaim {
 impl Sub for ResourceQuantities {
     type Output = Self;
 
+    /// Saturating subtraction of each of the quantities.
     fn sub(self, rhs: Self) -> Self::Output {
-        let mut others = self.others;
-        for (res, q) in rhs.others {
-            *others.entry(res).or_default() -= q;
-        }
-        Self {
-            others: BTreeMap::new(),
-        }
+        self.combine(rhs, Quantity::saturating_sub)
     }
 }
 
@@ -739,37 +1294,139 @@ pub struct UncountedTerminatedPods {
     pub succeeded: Vec<String>,
 }
 
-#[derive(Default, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct ReplicaSet {
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CronJob {
     pub metadata: Metadata,
-    pub spec: ReplicaSetSpec,
-    pub status: ReplicaSetStatus,
+    pub spec: CronJobSpec,
+    pub status: CronJobStatus,
 }
 
-impl ReplicaSet {
+impl CronJob {
     pub const GVK: GroupVersionKind = GroupVersionKind {
-        group: "apps",
+        group: "batch",
         version: "v1",
-        kind: "ReplicaSet",
+        kind: "CronJob",
     };
-
-    pub fn pods(&self) -> Vec<String> {
-        (0..self.status.replicas)
-            .map(|i| format!("{}-{}", self.metadata.name, i))
-            .collect()
-    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ReplicaSetSpec {
-    // Label selector for pods. Existing ReplicaSets whose pods are selected by this will be the ones affected by this deployment. It must match the pod template's labels.
-    pub selector: LabelSelector,
-    pub template: PodTemplateSpec,
-    pub replicas: Option<u32>,
-    // Minimum number of seconds for which a newly created pod should be ready without any of its container crashing, for it to be considered available. Defaults to 0 (pod will be considered available as soon as it is ready)
+pub struct CronJobSpec {
+    // The schedule in Cron format, see https://en.wikipedia.org/wiki/Cron.
+    pub schedule: String,
+    // Optional deadline in seconds for starting the job if it misses scheduled time for any reason. Missed jobs executions will be counted as failed ones.
+    pub starting_deadline_seconds: Option<u64>,
+    // Specifies how to treat concurrent executions of a Job.
     #[serde(default)]
-    pub min_ready_seconds: u32,
+    pub concurrency_policy: ConcurrencyPolicy,
+    // This flag tells the controller to suspend subsequent executions, it does not apply to already started executions. Defaults to false.
+    #[serde(default)]
+    pub suspend: bool,
+    // Specifies the job that will be created when executing a CronJob.
+    pub job_template: JobTemplateSpec,
+    // The number of successful finished jobs to retain. Value must be non-negative integer. Defaults to 3.
+    pub successful_jobs_history_limit: Option<u32>,
+    // The number of failed finished jobs to retain. Value must be non-negative integer. Defaults to 1.
+    pub failed_jobs_history_limit: Option<u32>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum ConcurrencyPolicy {
+    // allows CronJobs to run concurrently.
+    #[default]
+    Allow,
+    // forbids concurrent runs, skipping next run if previous hasn't finished yet.
+    Forbid,
+    // cancels currently running job and replaces it with a new one.
+    Replace,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobTemplateSpec {
+    #[serde(default)]
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub spec: JobSpec,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CronJobStatus {
+    // A list of pointers to currently running jobs.
+    // THEMELIOS: references are just the names of the jobs.
+    #[serde(default)]
+    pub active: Vec<String>,
+    // Information when was the last time the job was successfully scheduled.
+    pub last_schedule_time: Option<Time>,
+    // Information when was the last time the job successfully completed.
+    pub last_successful_time: Option<Time>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ReplicaSet {
+    pub metadata: Metadata,
+    pub spec: ReplicaSetSpec,
+    pub status: ReplicaSetStatus,
+}
+
+impl ReplicaSet {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "apps",
+        version: "v1",
+        kind: "ReplicaSet",
+    };
+
+    pub fn pods(&self) -> Vec<String> {
+        (0..self.status.replicas)
+            .map(|i| format!("{}-{}", self.metadata.name, i))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaSetSpec {
+    // Label selector for pods. Existing ReplicaSets whose pods are selected by this will be the ones affected by this deployment. It must match the pod template's labels.
+    pub selector: LabelSelector,
+    pub template: PodTemplateSpec,
+    pub replicas: Option<u32>,
+    // Minimum number of seconds for which a newly created pod should be ready without any of its container crashing, for it to be considered available. Defaults to 0 (pod will be considered available as soon as it is ready)
+    #[serde(default)]
+    pub min_ready_seconds: u32,
+}
+
+/// The legacy predecessor of the [`ReplicaSet`], selecting pods by equality on labels only.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ReplicationController {
+    pub metadata: Metadata,
+    pub spec: ReplicationControllerSpec,
+    // The status has the same fields as that of a replicaset.
+    #[serde(default)]
+    pub status: ReplicaSetStatus,
+}
+
+impl ReplicationController {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "ReplicationController",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationControllerSpec {
+    // Selector is a label query over pods that should match the Replicas count. If Selector is empty, it is defaulted to the labels present on the Pod template.
+    #[serde(default)]
+    pub selector: BTreeMap<String, String>,
+    pub template: Option<PodTemplateSpec>,
+    pub replicas: Option<u32>,
+    #[serde(default)]
+    pub min_ready_seconds: u32,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -1014,6 +1671,14 @@ pub struct ControllerRevision {
     pub data: String,
 }
 
+impl ControllerRevision {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "apps",
+        version: "v1",
+        kind: "ControllerRevision",
+    };
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StatefulSet {
@@ -1153,6 +1818,14 @@ pub struct PersistentVolumeClaim {
     pub status: PersistentVolumeClaimStatus,
 }
 
+impl PersistentVolumeClaim {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "PersistentVolumeClaim",
+    };
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PersistentVolumeClaimSpec {
@@ -1174,6 +1847,401 @@ pub struct PersistentVolumeClaimSpec {
 pub struct PersistentVolumeClaimStatus {
     #[serde(default)]
     pub access_modes: Vec<String>,
+    // The actual resources of the volume backing the claim, once bound.
+    #[serde(default)]
+    pub capacity: ResourceQuantities,
+    #[serde(default)]
+    pub phase: PersistentVolumeClaimPhase,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum PersistentVolumeClaimPhase {
+    #[default]
+    Pending,
+    Bound,
+    /// The volume the claim was bound to has gone.
+    Lost,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistentVolume {
+    pub metadata: Metadata,
+    pub spec: PersistentVolumeSpec,
+    #[serde(default)]
+    pub status: PersistentVolumeStatus,
+}
+
+impl PersistentVolume {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "PersistentVolume",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistentVolumeSpec {
+    #[serde(default)]
+    pub capacity: ResourceQuantities,
+    #[serde(default)]
+    pub access_modes: Vec<String>,
+    // The claim this volume is bound to, set by the binder.
+    pub claim_ref: Option<ClaimReference>,
+    pub storage_class_name: Option<String>,
+    // THEMELIOS: The node that the volume can be accessed from, none if it is available everywhere.
+    // This stands in for the full node affinity.
+    pub node_affinity: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimReference {
+    pub name: String,
+    #[serde(default)]
+    pub namespace: String,
+    // Empty when the volume was pre-bound to a claim by name only.
+    #[serde(default)]
+    pub uid: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistentVolumeStatus {
+    #[serde(default)]
+    pub phase: PersistentVolumePhase,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum PersistentVolumePhase {
+    #[default]
+    Available,
+    Bound,
+    /// The claim the volume was bound to has been deleted.
+    Released,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageClass {
+    pub metadata: Metadata,
+    // The provisioner that creates volumes for claims of this class.
+    pub provisioner: String,
+    #[serde(default)]
+    pub volume_binding_mode: VolumeBindingMode,
+}
+
+impl StorageClass {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "storage.k8s.io",
+        version: "v1",
+        kind: "StorageClass",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriorityClass {
+    pub metadata: Metadata,
+    // The priority given to pods of this class, higher values are scheduled first and can preempt
+    // lower ones.
+    pub value: i32,
+    // Whether this class applies to pods that don't name one.
+    #[serde(default)]
+    pub global_default: bool,
+    #[serde(default)]
+    pub preemption_policy: PreemptionPolicy,
+}
+
+impl PriorityClass {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "scheduling.k8s.io",
+        version: "v1",
+        kind: "PriorityClass",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lease {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub spec: LeaseSpec,
+}
+
+impl Lease {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "coordination.k8s.io",
+        version: "v1",
+        kind: "Lease",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaseSpec {
+    // The identity of the current holder of the lease.
+    pub holder_identity: Option<String>,
+    // How long the holder has the lease for after its last renewal, measured against the renew
+    // time.
+    pub lease_duration_seconds: Option<u64>,
+    pub acquire_time: Option<Time>,
+    pub renew_time: Option<Time>,
+    // The number of times the lease has changed holders.
+    #[serde(default)]
+    pub lease_transitions: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub spec: ServiceSpec,
+}
+
+impl Service {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "Service",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSpec {
+    // Route service traffic to pods with label keys and values matching this selector. If empty,
+    // the service is assumed to have external endpoints that are not managed by the endpoints
+    // controller.
+    #[serde(default)]
+    pub selector: BTreeMap<String, String>,
+}
+
+/// The backends of the service with the same name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoints {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub subsets: Vec<EndpointSubset>,
+}
+
+impl Endpoints {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "Endpoints",
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointSubset {
+    // Addresses which are ready to receive traffic.
+    #[serde(default)]
+    pub addresses: Vec<EndpointAddress>,
+    // Addresses of pods that are not yet ready, so are not routed to.
+    #[serde(default)]
+    pub not_ready_addresses: Vec<EndpointAddress>,
+}
+
+/// THEMELIOS: Pods are not given IPs so addresses are identified by the pod that they target.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointAddress {
+    pub node_name: Option<String>,
+    pub target_ref: Option<ObjectReference>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectReference {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    pub uid: String,
+}
+
+/// A report of something that happened to an object, such as a controller acting on it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub metadata: Metadata,
+    // The object that this event is about.
+    pub involved_object: ObjectReference,
+    // A short, machine understandable string giving the reason for the event, e.g. FailedCreate.
+    pub reason: String,
+    // A human-readable description of the event.
+    pub message: String,
+    pub r#type: EventType,
+    // The controller that emitted the event.
+    pub reporting_controller: String,
+    // The number of times the event has occurred.
+    pub count: u32,
+    pub first_timestamp: Option<Time>,
+    pub last_timestamp: Option<Time>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum EventType {
+    #[default]
+    Normal,
+    Warning,
+}
+
+/// Configuration for pods to consume as environment variables or files in a volume.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMap {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub data: BTreeMap<String, String>,
+}
+
+impl ConfigMap {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "ConfigMap",
+    };
+}
+
+/// Sensitive configuration for pods, consumed in the same ways as a config map.
+///
+/// THEMELIOS: Values are held as plain strings rather than base64 encoded bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Secret {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub data: BTreeMap<String, String>,
+    // Used to facilitate programmatic handling of secret data, e.g. Opaque.
+    pub r#type: Option<String>,
+}
+
+impl Secret {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "Secret",
+    };
+}
+
+/// Permissions on kinds of resources, granted to subjects through role bindings.
+///
+/// THEMELIOS: Roles grant their rules in every namespace, standing in for the cluster roles that
+/// the built-in controllers are given, so rules can also cover cluster-scoped kinds.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Role {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Role {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "rbac.authorization.k8s.io",
+        version: "v1",
+        kind: "Role",
+    };
+}
+
+/// The verbs allowed on the resources of the api groups, `*` matching any.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    #[serde(default)]
+    pub api_groups: Vec<String>,
+    #[serde(default)]
+    pub resources: Vec<String>,
+    pub verbs: Vec<String>,
+}
+
+/// Grants the rules of a role to the subjects.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleBinding {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub subjects: Vec<Subject>,
+    pub role_ref: RoleRef,
+}
+
+impl RoleBinding {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "rbac.authorization.k8s.io",
+        version: "v1",
+        kind: "RoleBinding",
+    };
+}
+
+/// An identity that requests are made under, such as a service account.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subject {
+    /// `ServiceAccount` or `User`.
+    pub kind: String,
+    pub name: String,
+    /// The namespace of a service account, empty for users.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub namespace: String,
+}
+
+impl Subject {
+    pub fn service_account(namespace: &str, name: &str) -> Self {
+        Self {
+            kind: "ServiceAccount".to_owned(),
+            name: name.to_owned(),
+            namespace: namespace.to_owned(),
+        }
+    }
+
+    pub fn user(name: &str) -> Self {
+        Self {
+            kind: "User".to_owned(),
+            name: name.to_owned(),
+            namespace: String::new(),
+        }
+    }
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.kind == "ServiceAccount" {
+            write!(f, "system:serviceaccount:{}:{}", self.namespace, self.name)
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+/// The role that a binding grants.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleRef {
+    pub api_group: String,
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum VolumeBindingMode {
+    /// Bind and provision as soon as the claim is created.
+    #[default]
+    Immediate,
+    /// Delay binding and provisioning until a pod using the claim gets a node selected.
+    WaitForFirstConsumer,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -1183,6 +2251,14 @@ pub struct Node {
     pub status: NodeStatus,
 }
 
+impl Node {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "Node",
+    };
+}
+
 #[derive(Clone, Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NodeSpec {
@@ -1251,31 +2327,153 @@ impl Default for Quantity {
     }
 }
 
-fn split_quantity(s: &str) -> (String, String) {
-    if let Some(alpha_pos) = s.chars().position(char::is_alphabetic) {
-        (
-            s.chars().take(alpha_pos).collect(),
-            s.chars().skip(alpha_pos).collect(),
-        )
-    } else {
-        (s.to_owned(), String::new())
+/// Why a quantity couldn't be used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuantityError {
+    /// The string isn't in the quantity format.
+    Invalid(String),
+    /// The value is too large to be represented.
+    Overflow,
+    /// The value would be negative.
+    Underflow,
+}
+
+/// The multiplier for a quantity suffix, as a fraction.
+fn suffix_scale(suffix: &str) -> Option<(u128, u128)> {
+    let scale = match suffix {
+        "Ki" => (1 << 10, 1),
+        "Mi" => (1 << 20, 1),
+        "Gi" => (1 << 30, 1),
+        "Ti" => (1 << 40, 1),
+        "Pi" => (1 << 50, 1),
+        "Ei" => (1 << 60, 1),
+        "n" => (1, 1_000_000_000),
+        "u" => (1, 1_000_000),
+        "m" => (1, 1_000),
+        "" => (1, 1),
+        "k" => (1_000, 1),
+        "M" => (1_000_000, 1),
+        "G" => (1_000_000_000, 1),
+        "T" => (1_000_000_000_000, 1),
+        "P" => (1_000_000_000_000_000, 1),
+        "E" => (1_000_000_000_000_000_000, 1),
+        _ => {
+            // a decimal exponent, such as 1e3
+            let exponent = suffix.strip_prefix(['e', 'E'])?.parse::<i32>().ok()?;
+            let power = 10u128.checked_pow(exponent.unsigned_abs())?;
+            if exponent < 0 {
+                (1, power)
+            } else {
+                (power, 1)
+            }
+        }
+    };
+    Some(scale)
+}
+
+/// Parse the quantity in thousandths of its unit, rounding up any finer precision as kubernetes
+/// does.
+fn parse_milli_value(s: &str) -> Result<u128, QuantityError> {
+    let invalid = || QuantityError::Invalid(s.to_owned());
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '+' | '-')))
+        .unwrap_or(s.len());
+    let (number, suffix) = s.split_at(split);
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
     }
+    let (scale_num, scale_den) = suffix_scale(suffix).ok_or_else(invalid)?;
+
+    let mut digits = 0u128;
+    for c in whole.chars().chain(fraction.chars()) {
+        digits = digits
+            .checked_mul(10)
+            .and_then(|d| d.checked_add(u128::from(c.to_digit(10).unwrap())))
+            .ok_or(QuantityError::Overflow)?;
+    }
+    let fraction_len = u32::try_from(fraction.len()).map_err(|_| QuantityError::Overflow)?;
+    let numerator = digits
+        .checked_mul(scale_num)
+        .and_then(|n| n.checked_mul(1000))
+        .ok_or(QuantityError::Overflow)?;
+    let denominator = 10u128
+        .checked_pow(fraction_len)
+        .and_then(|d| d.checked_mul(scale_den))
+        .ok_or(QuantityError::Overflow)?;
+    let milli = numerator.div_ceil(denominator);
+    if negative && milli > 0 {
+        return Err(QuantityError::Underflow);
+    }
+    Ok(milli)
 }
 
 impl Quantity {
-    pub fn to_num(&self) -> u64 {
+    /// The quantity in thousandths of its unit, such as millicores for cpu.
+    pub fn milli_value(&self) -> Result<u128, QuantityError> {
         match self {
-            Quantity::Str(s) => {
-                let (digit, unit) = split_quantity(s);
-                let num: u64 = digit.parse().unwrap();
-                match unit.as_str() {
-                    "" => num,
-                    "m" => num / 1000,
-                    "k" => num * 1000,
-                    u => panic!("unhandled unit {u:?} when splitting {s:?}"),
-                }
-            }
-            Quantity::Num(i) => *i,
+            Quantity::Str(s) => parse_milli_value(s),
+            Quantity::Num(n) => Ok(u128::from(*n) * 1000),
+        }
+    }
+
+    /// The quantity in whole units, rounded up.
+    pub fn value(&self) -> Result<u64, QuantityError> {
+        u64::try_from(self.milli_value()?.div_ceil(1000)).map_err(|_| QuantityError::Overflow)
+    }
+
+    /// The quantity with the given value in thousandths of its unit, as a plain number when it
+    /// is whole.
+    pub fn from_milli_value(milli: u128) -> Result<Self, QuantityError> {
+        if milli % 1000 == 0 {
+            u64::try_from(milli / 1000)
+                .map(Quantity::Num)
+                .map_err(|_| QuantityError::Overflow)
+        } else {
+            Ok(Quantity::Str(format!("{milli}m")))
+        }
+    }
+
+    pub fn checked_add(&self, rhs: &Quantity) -> Result<Quantity, QuantityError> {
+        let sum = self
+            .milli_value()?
+            .checked_add(rhs.milli_value()?)
+            .ok_or(QuantityError::Overflow)?;
+        Self::from_milli_value(sum)
+    }
+
+    pub fn checked_sub(&self, rhs: &Quantity) -> Result<Quantity, QuantityError> {
+        let difference = self
+            .milli_value()?
+            .checked_sub(rhs.milli_value()?)
+            .ok_or(QuantityError::Underflow)?;
+        Self::from_milli_value(difference)
+    }
+
+    /// Add the quantities, clamping at the largest quantity rather than overflowing.
+    /// Only fails if either isn't a valid quantity.
+    pub fn saturating_add(&self, rhs: &Quantity) -> Result<Quantity, QuantityError> {
+        match self.checked_add(rhs) {
+            Err(QuantityError::Overflow) => Ok(Quantity::Num(u64::MAX)),
+            result => result,
+        }
+    }
+
+    /// Subtract the quantities, clamping at zero rather than going negative.
+    /// Only fails if either isn't a valid quantity.
+    pub fn saturating_sub(&self, rhs: &Quantity) -> Result<Quantity, QuantityError> {
+        match self.checked_sub(rhs) {
+            Err(QuantityError::Underflow) => Ok(Quantity::Num(0)),
+            result => result,
         }
     }
 }
@@ -1302,32 +2500,6 @@ impl From<u64> for Quantity {
     }
 }
 
-impl Add<Quantity> for Quantity {
-    type Output = Quantity;
-    fn add(self, rhs: Quantity) -> Self::Output {
-        (self.to_num() + rhs.to_num()).into()
-    }
-}
-
-impl AddAssign<Quantity> for Quantity {
-    fn add_assign(&mut self, rhs: Quantity) {
-        *self = self.clone() + rhs;
-    }
-}
-
-impl Sub<Quantity> for Quantity {
-    type Output = Quantity;
-    fn sub(self, rhs: Quantity) -> Self::Output {
-        (self.to_num() - rhs.to_num()).into()
-    }
-}
-
-impl SubAssign<Quantity> for Quantity {
-    fn sub_assign(&mut self, rhs: Quantity) {
-        *self = self.clone() - rhs;
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum IntOrString {
@@ -1436,3 +2608,158 @@ pub struct ScaleStatus {
     #[serde(default)]
     pub replicas: u32,
 }
+
+impl Scale {
+    /// The scale subresource of a resource with the given metadata and replica counts.
+    ///
+    /// The resource version is kept so that writes through the scale conflict with newer writes to
+    /// the resource itself.
+    pub fn new(metadata: &Metadata, replicas: u32, status_replicas: u32) -> Self {
+        Self {
+            metadata: Metadata {
+                name: metadata.name.clone(),
+                namespace: metadata.namespace.clone(),
+                uid: metadata.uid.clone(),
+                resource_version: metadata.resource_version.clone(),
+                ..Default::default()
+            },
+            spec: ScaleSpec { replicas },
+            status: ScaleStatus {
+                replicas: status_replicas,
+            },
+        }
+    }
+}
+
+/// A request to roll a deployment back to an earlier revision, through its rollback subresource.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentRollback {
+    pub name: String,
+    #[serde(default)]
+    pub updated_annotations: BTreeMap<String, String>,
+    pub rollback_to: RollbackConfig,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RollbackConfig {
+    /// The revision to roll back to, zero for the last revision.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HorizontalPodAutoscaler {
+    pub metadata: Metadata,
+    pub spec: HorizontalPodAutoscalerSpec,
+    #[serde(default)]
+    pub status: HorizontalPodAutoscalerStatus,
+}
+
+impl HorizontalPodAutoscaler {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "autoscaling",
+        version: "v1",
+        kind: "HorizontalPodAutoscaler",
+    };
+
+    /// The lower limit for the number of replicas, defaulting to 1.
+    pub fn min_replicas(&self) -> u32 {
+        self.spec.min_replicas.unwrap_or(1)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HorizontalPodAutoscalerSpec {
+    // reference to scaled resource; horizontal pod autoscaler will learn the current resource consumption and will set the desired number of pods by using its Scale subresource.
+    pub scale_target_ref: CrossVersionObjectReference,
+    // minReplicas is the lower limit for the number of replicas to which the autoscaler can scale down. It defaults to 1 pod.
+    pub min_replicas: Option<u32>,
+    // maxReplicas is the upper limit for the number of pods that can be set by the autoscaler; cannot be smaller than MinReplicas.
+    pub max_replicas: u32,
+    // targetCPUUtilizationPercentage is the target average CPU utilization (represented as a percentage of requested CPU) over all the pods; if not specified the default autoscaling policy will be used.
+    #[serde(rename = "targetCPUUtilizationPercentage")]
+    pub target_cpu_utilization_percentage: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossVersionObjectReference {
+    // kind is the kind of the referent.
+    pub kind: String,
+    // name is the name of the referent.
+    pub name: String,
+    // apiVersion is the API version of the referent.
+    #[serde(default)]
+    pub api_version: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HorizontalPodAutoscalerStatus {
+    // observedGeneration is the most recent generation observed by this autoscaler.
+    #[serde(default)]
+    pub observed_generation: u64,
+    // lastScaleTime is the last time the HorizontalPodAutoscaler scaled the number of pods; used by the autoscaler to control how often the number of pods is changed.
+    pub last_scale_time: Option<Time>,
+    // currentReplicas is the current number of replicas of pods managed by this autoscaler.
+    #[serde(default)]
+    pub current_replicas: u32,
+    // desiredReplicas is the desired number of replicas of pods managed by this autoscaler.
+    #[serde(default)]
+    pub desired_replicas: u32,
+    // currentCPUUtilizationPercentage is the current average CPU utilization over all pods, represented as a percentage of requested CPU.
+    #[serde(rename = "currentCPUUtilizationPercentage")]
+    pub current_cpu_utilization_percentage: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Namespace {
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub spec: NamespaceSpec,
+    #[serde(default)]
+    pub status: NamespaceStatus,
+}
+
+impl Namespace {
+    pub const GVK: GroupVersionKind = GroupVersionKind {
+        group: "",
+        version: "v1",
+        kind: "Namespace",
+    };
+
+    /// The finalizer that keeps a namespace around until all of the resources in it are removed.
+    pub const FINALIZER: &'static str = "kubernetes";
+
+    /// The namespace for resources that don't specify one, it can't be deleted.
+    pub const DEFAULT: &'static str = "default";
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceSpec {
+    // Finalizers is an opaque list of values that must be empty to permanently remove object from storage.
+    #[serde(default)]
+    pub finalizers: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceStatus {
+    // Phase is the current lifecycle phase of the namespace.
+    #[serde(default)]
+    pub phase: NamespacePhase,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum NamespacePhase {
+    #[default]
+    Active,
+    Terminating,
+}
@@ -0,0 +1,72 @@
+//! A minimal model of the data plane, modelled on kube-proxy.
+//!
+//! Each node programs its routes from the ready addresses in the [`Endpoints`] of each service,
+//! so traffic only follows the endpoints and not the pods themselves. A backend taking that
+//! traffic is routable only if the pod it targets is still around and able to serve it, which
+//! can lag behind the endpoints in either direction.
+
+use std::collections::BTreeMap;
+
+use crate::controller::util::{get_node_condition, is_pod_ready};
+use crate::resources::{ConditionStatus, Endpoints, NodeConditionType, Pod};
+use crate::state::RawState;
+
+/// The backends that each service routes to, keyed by the name of the service.
+pub type RoutingTable = BTreeMap<String, Vec<String>>;
+
+/// The routes for every service, as the names of the pods that its endpoints list as ready.
+pub fn routing_table(view: &RawState) -> RoutingTable {
+    view.services
+        .iter()
+        .map(|service| {
+            let routes = view
+                .endpoints
+                .get_in(&service.metadata.namespace, &service.metadata.name)
+                .map(routes)
+                .unwrap_or_default();
+            (service.metadata.name.clone(), routes)
+        })
+        .collect()
+}
+
+fn routes(endpoints: &Endpoints) -> Vec<String> {
+    endpoints
+        .subsets
+        .iter()
+        .flat_map(|s| &s.addresses)
+        .filter_map(|a| a.target_ref.as_ref())
+        .map(|r| r.name.clone())
+        .collect()
+}
+
+/// The pods that traffic to the service, by namespace and name, reaches and that can serve it.
+///
+/// A routed pod must still exist as the same pod, be ready and be running on a node that hasn't
+/// been found to be not ready.
+pub fn routable_backends<'a>(view: &'a RawState, namespace: &str, service: &str) -> Vec<&'a Pod> {
+    let Some(endpoints) = view.endpoints.get_in(namespace, service) else {
+        return Vec::new();
+    };
+    endpoints
+        .subsets
+        .iter()
+        .flat_map(|s| &s.addresses)
+        .filter_map(|a| a.target_ref.as_ref())
+        .filter_map(|r| {
+            view.pods
+                .get_in(&r.namespace, &r.name)
+                .filter(|p| p.metadata.uid == r.uid)
+        })
+        .filter(|p| is_pod_ready(p))
+        .filter(|p| {
+            p.spec
+                .node_name
+                .as_ref()
+                .and_then(|n| view.nodes.get(n))
+                .map_or(false, |node| {
+                    get_node_condition(&node.status.conditions, NodeConditionType::Ready)
+                        .map_or(true, |c| c.status != ConditionStatus::False)
+                })
+        })
+        .collect()
+}
@@ -3,58 +3,103 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::api::patch::Patch;
+use crate::api::selector::{FieldSelectable, ListQuery, SelectorError};
+use crate::api::status_api_resource;
 use crate::api::APIObject;
 use crate::api::SerializableResource;
 use crate::controller::job::JobController;
 use crate::controller::podgc::PodGCController;
 use crate::controller::Controller;
 use crate::controller::DeploymentController;
+use crate::controller::NamespaceController;
 use crate::controller::NodeController;
 use crate::controller::ReplicaSetController;
+use crate::controller::ReplicationManager;
 use crate::controller::SchedulerController;
 use crate::controller::StatefulSetController;
+use crate::recording::Recorder;
 use crate::resources::Deployment;
+use crate::resources::Job;
+use crate::resources::Meta;
+use crate::resources::Metadata;
+use crate::resources::Namespace;
 use crate::resources::Node;
 use crate::resources::Pod;
 use crate::resources::ReplicaSet;
+use crate::resources::ReplicationController;
 use crate::resources::Scale;
+use crate::resources::Spec;
+use crate::resources::StatefulSet;
+use crate::resources::StatusSubresource;
+use crate::state::apply;
+use crate::state::resources::Resources;
 use crate::state::StateView;
+use crate::utils;
 use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::Request;
 use axum::extract::State;
+use axum::middleware;
+use axum::response::IntoResponse;
+use axum::response::Response;
 use axum::routing::delete;
 use axum::routing::patch;
 use axum::routing::put;
 use axum::{
-    http::{Method, StatusCode, Uri},
+    http::{header::CONTENT_TYPE, HeaderMap, Method, StatusCode, Uri},
     routing::get,
     routing::post,
-    Json, Router,
+    Extension, Json, Router,
 };
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::APIGroup;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::APIGroupList;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::APIVersions;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::GroupVersionForDiscovery;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{APIResourceList, ListMeta};
 use k8s_openapi::List;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
+use tower::ServiceExt;
 use tower_http::trace::TraceLayer;
 use tracing::{debug, info, warn};
 
+pub mod faults;
+pub mod openapi;
+pub mod record;
+pub mod replicas;
+pub mod watch;
+
+use self::faults::Faults;
+use self::replicas::{Replica, ReplicaReads, Replicas};
+use self::watch::{ListParams, Watches};
+
 type AppState = Arc<Mutex<StateView>>;
 
-pub async fn run(address: String) -> (Arc<AtomicBool>, Vec<JoinHandle<()>>) {
-    let trace_layer = TraceLayer::new_for_http();
+/// Serve the cluster through a replica of the API server at each of the addresses, reading as
+/// the reads of replicas are configured and recording the writes that it accepts to the recorder.
+pub async fn run(
+    addresses: Vec<String>,
+    reads: ReplicaReads,
+    recorder: Recorder,
+) -> (Arc<AtomicBool>, Vec<JoinHandle<()>>) {
     let state = Arc::new(Mutex::new(StateView::default()));
     let shutdown = Arc::new(AtomicBool::new(false));
+    let faults = Faults::default();
     let mut handles = Vec::new();
 
     macro_rules! run_controller {
-        ($cont:ident) => {
+        ($cont:expr) => {
             let state2 = Arc::clone(&state);
+            let faults2 = faults.clone();
             let sd = Arc::clone(&shutdown);
+            let controller = $cont;
+            faults.register_controller(controller.name());
             handles.push(tokio::spawn(async move {
-                controller_loop(state2, $cont, sd).await;
+                controller_loop(state2, controller, None, faults2, sd).await;
             }));
         };
     }
@@ -63,43 +108,88 @@ pub async fn run(address: String) -> (Arc<AtomicBool>, Vec<JoinHandle<()>>) {
     run_controller!(StatefulSetController);
     run_controller!(JobController);
     run_controller!(ReplicaSetController);
-    run_controller!(SchedulerController);
+    run_controller!(ReplicationManager);
+    run_controller!(SchedulerController::default());
     run_controller!(PodGCController);
+    run_controller!(NamespaceController);
 
     let state2 = Arc::clone(&state);
+    let faults2 = faults.clone();
     let sd = Arc::clone(&shutdown);
+    let node = "node1".to_owned();
+    faults.register_node(node.clone());
     handles.push(tokio::spawn(async move {
         controller_loop(
             state2,
             NodeController {
-                name: "node1".to_owned(),
+                name: node.clone(),
+                max_restarts: 0,
+                lease_duration_seconds: None,
+                image_pulls: false,
             },
+            Some(node),
+            faults2,
             sd,
         )
         .await;
     }));
 
-    let app = app(state).layer(trace_layer);
-    let listener = tokio::net::TcpListener::bind(address).await.unwrap();
-    let sd = Arc::clone(&shutdown);
-    handles.push(tokio::spawn(async move {
-        axum::serve(listener, app)
-            .with_graceful_shutdown(async move {
-                loop {
-                    tokio::time::sleep(Duration::from_millis(500)).await;
-                    if sd.load(Ordering::Relaxed) {
-                        break;
+    let watches = Watches::new(Arc::clone(&shutdown));
+    handles.push(tokio::spawn(watch::record_history(
+        Arc::clone(&state),
+        watches.clone(),
+    )));
+
+    let replicas = Replicas::new(addresses.len(), reads);
+    handles.push(tokio::spawn(replicas::sync_caches(
+        replicas.clone(),
+        watches.clone(),
+        Arc::clone(&shutdown),
+    )));
+
+    for (index, address) in addresses.into_iter().enumerate() {
+        let replica = Replica {
+            index,
+            replicas: replicas.clone(),
+        };
+        let app = app(
+            Arc::clone(&state),
+            watches.clone(),
+            faults.clone(),
+            recorder.clone(),
+            replica,
+        )
+        .layer(TraceLayer::new_for_http());
+        let listener = tokio::net::TcpListener::bind(&address).await.unwrap();
+        info!(index, address, "Serving api replica");
+        let sd = Arc::clone(&shutdown);
+        handles.push(tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    loop {
+                        tokio::time::sleep(Duration::from_millis(500)).await;
+                        if sd.load(Ordering::Relaxed) {
+                            break;
+                        }
                     }
-                }
-                info!("Stopping serving api");
-            })
-            .await
-            .unwrap()
-    }));
+                    info!(index, "Stopping serving api");
+                })
+                .await
+                .unwrap()
+        }));
+    }
     (shutdown, handles)
 }
 
-async fn controller_loop<C: Controller>(state: AppState, controller: C, shutdown: Arc<AtomicBool>) {
+/// Run the controller against the cluster until shut down, stopping while it is paused or the
+/// node that it runs is killed.
+async fn controller_loop<C: Controller>(
+    state: AppState,
+    controller: C,
+    node: Option<String>,
+    faults: Faults,
+    shutdown: Arc<AtomicBool>,
+) {
     info!(name = controller.name(), "Starting controller");
     let mut cstate = C::State::default();
     let mut last_revision = state.lock().await.revision.clone();
@@ -113,10 +203,21 @@ async fn controller_loop<C: Controller>(state: AppState, controller: C, shutdown
 
         let mut s = state.lock().await;
 
+        if node.as_ref().map_or(false, |n| faults.killed(n)) {
+            // the controller restarts along with its node
+            cstate = C::State::default();
+            continue;
+        }
+        if faults.paused(&controller.name()) {
+            continue;
+        }
+
         if s.revision == last_revision {
             continue;
         }
 
+        sync_clock(&mut s);
+
         debug!(name = controller.name(), "Checking for steps");
         if let Some(operation) = controller.step(&s, &mut cstate) {
             info!(name = controller.name(), "Got operation to perform");
@@ -131,13 +232,58 @@ async fn controller_loop<C: Controller>(state: AppState, controller: C, shutdown
     info!(name = controller.name(), "Stopping controller");
 }
 
-fn app(state: AppState) -> Router {
+/// Move the cluster clock up to the wall clock, time passing on its own when serving rather than
+/// through explicit model actions.
+fn sync_clock(s: &mut StateView) {
+    s.clock = utils::wall_clock();
+}
+
+fn app(
+    state: AppState,
+    watches: Watches,
+    faults: Faults,
+    recorder: Recorder,
+    replica: Replica,
+) -> Router {
+    api()
+        .layer(middleware::from_fn(record::record))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            replicas::replica_reads,
+        ))
+        .layer(middleware::from_fn(faults::stale_reads))
+        .layer(middleware::from_fn(faults::drop_writes))
+        .nest("/admin", faults::router())
+        .fallback(fallback)
+        .layer(Extension(watches))
+        .layer(Extension(faults))
+        .layer(Extension(recorder))
+        .layer(Extension(replica))
+        .with_state(state)
+}
+
+/// Serve the request from the view of the cluster rather than the latest state.
+async fn serve_view(view: StateView, watches: Watches, request: Request) -> Response {
+    let view = Arc::new(Mutex::new(view));
+    api()
+        .layer(Extension(watches))
+        .with_state(view)
+        .oneshot(request)
+        .await
+        .into_response()
+}
+
+/// The routes that clients of the cluster use, without the faults injected into them.
+fn api() -> Router<AppState> {
     Router::new()
+        .route("/version", get(version))
+        .route("/api", get(api_versions))
         .route("/apis", get(api_groups))
+        .route("/apis/apps", get(apps_api_group))
+        .route("/apis/batch", get(batch_api_group))
         .nest("/apis", apis())
         .nest("/api", apis())
-        .fallback(fallback)
-        .with_state(state)
+        .nest("/openapi", openapi::router())
 }
 
 fn apis() -> Router<AppState> {
@@ -146,28 +292,31 @@ fn apis() -> Router<AppState> {
         .nest("/v1", core_v1())
         .route("/apps/v1", get(list_apps_v1))
         .nest("/apps/v1", apps_v1())
+        .route("/batch/v1", get(list_batch_v1))
+        .nest("/batch/v1", batch_v1())
 }
 
 fn core_v1() -> Router<AppState> {
     Router::new()
-        // .route("/namespaces", get(list_namespaces))
-        .nest("/namespaces", namespaces_core_v1())
-}
-
-fn namespaces_core_v1() -> Router<AppState> {
-    Router::new().nest("/default", resources_core_v1())
+        .route("/pods", get(list_pods))
+        .route("/replicationcontrollers", get(list_replication_controllers))
+        .route("/namespaces", get(list_namespaces))
+        .route("/namespaces/:namespace", get(get_namespace))
+        .nest("/namespaces/:namespace", resources_core_v1())
+        .nest("/nodes", nodes_router())
 }
 
 fn resources_core_v1() -> Router<AppState> {
     Router::new()
         .nest("/pods", pods_router())
-        .nest("/nodes", nodes_router())
+        .nest("/replicationcontrollers", replication_controllers_router())
 }
 
 fn pods_router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_pods))
         .route("/:name", get(get_pod))
+        .route("/:name", patch(patch_pod))
         .route("/:name", delete(delete_pod))
 }
 fn nodes_router() -> Router<AppState> {
@@ -178,18 +327,141 @@ fn nodes_router() -> Router<AppState> {
 
 fn apps_v1() -> Router<AppState> {
     Router::new()
-        // .route("/namespaces", get(list_namespaces))
-        .nest("/namespaces", namespaces_apps_v1())
+        .route("/deployments", get(list_deployments))
+        .route("/replicasets", get(list_replicasets))
+        .route("/statefulsets", get(list_statefulsets))
+        .nest("/namespaces/:namespace", resources_apps_v1())
+}
+
+/// The resources selected by the query in the namespace from the request path, or in every
+/// namespace for requests to the cluster-wide path.
+fn selected<'a, T: FieldSelectable + Spec + Clone>(
+    resources: &'a Resources<T>,
+    namespace: &'a Option<String>,
+    query: &'a ListQuery,
+) -> impl Iterator<Item = &'a T> + 'a {
+    resources.iter().filter(move |r| {
+        namespace
+            .as_ref()
+            .map_or(true, |n| &r.metadata().namespace == n)
+            && query.matches(*r)
+    })
+}
+
+/// The response to a request with selectors that can't be used.
+fn bad_request(error: SelectorError) -> (StatusCode, Json<Status>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(Status {
+            code: Some(400),
+            details: None,
+            message: Some(error.to_string()),
+            metadata: ListMeta::default(),
+            reason: Some("BadRequest".to_owned()),
+            status: Some("Failure".to_owned()),
+        }),
+    )
+}
+
+/// Default the namespace of a resource in a request body to the one from the request path,
+/// rejecting any mismatch.
+fn with_namespace<T: Meta>(mut resource: T, namespace: &str) -> Result<T, StatusCode> {
+    if resource.metadata().namespace.is_empty() {
+        resource.metadata_mut().namespace = namespace.to_owned();
+    }
+    if resource.metadata().namespace != namespace {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(resource)
+}
+
+/// Target a scale request at the resource from the request path.
+///
+/// A scale without a resource version is applied unconditionally, as it is by the API server for
+/// `kubectl scale` without `--resource-version`.
+fn scale_request(mut scale: Scale, current: &Metadata) -> Scale {
+    scale.metadata.name = current.name.clone();
+    scale.metadata.namespace = current.namespace.clone();
+    if scale.metadata.resource_version.is_empty() {
+        scale.metadata.resource_version = current.resource_version.clone();
+    }
+    scale
+}
+
+fn deployment_scale(d: &Deployment) -> Scale {
+    Scale::new(&d.metadata, d.spec.replicas, d.status.replicas)
+}
+
+fn replicaset_scale(rs: &ReplicaSet) -> Scale {
+    Scale::new(
+        &rs.metadata,
+        rs.spec.replicas.unwrap_or(1),
+        rs.status.replicas,
+    )
 }
 
-fn namespaces_apps_v1() -> Router<AppState> {
-    Router::new().nest("/default", resources_apps_v1())
+fn statefulset_scale(sts: &StatefulSet) -> Scale {
+    Scale::new(
+        &sts.metadata,
+        sts.spec.replicas.unwrap_or(1),
+        sts.status.replicas,
+    )
+}
+
+/// The patch in the body of a request, in the format given by its content type.
+fn patch_request(headers: &HeaderMap, body: String) -> Result<Patch, StatusCode> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    Patch::from_content_type(content_type, body).ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+}
+
+/// The resource in the body of a write to the status subresource of the current one, either a
+/// whole resource for updates or a patch to the current one.
+fn status_request<T: Meta + Serialize + DeserializeOwned>(
+    headers: &HeaderMap,
+    body: String,
+    current: &T,
+) -> Result<T, StatusCode> {
+    if let Ok(patch) = patch_request(headers, body.clone()) {
+        return apply::patched(Some(current), &patch).map_err(|_| StatusCode::CONFLICT);
+    }
+    let resource: T = serde_json::from_str(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if resource.metadata().name != current.metadata().name {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    with_namespace(resource, &current.metadata().namespace)
+}
+
+/// The scale in the body of a write to the scale subresource, either a whole scale for updates or
+/// a patch to the current one.
+fn scale_body(headers: &HeaderMap, body: String, current: Scale) -> Result<Scale, StatusCode> {
+    let scale = match patch_request(headers, body.clone()) {
+        Ok(patch) => patch
+            .apply(&current)
+            .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?,
+        Err(_) => serde_json::from_str(&body).map_err(|_| StatusCode::BAD_REQUEST)?,
+    };
+    Ok(scale_request(scale, &current.metadata))
+}
+
+fn success() -> Json<Status> {
+    Json(Status {
+        code: None,
+        details: None,
+        message: None,
+        metadata: ListMeta::default(),
+        reason: None,
+        status: Some("Success".to_owned()),
+    })
 }
 
 fn resources_apps_v1() -> Router<AppState> {
     Router::new()
         .nest("/deployments", deployments_router())
         .nest("/replicasets", replicasets_router())
+        .nest("/statefulsets", statefulsets_router())
 }
 
 fn deployments_router() -> Router<AppState> {
@@ -198,20 +470,40 @@ fn deployments_router() -> Router<AppState> {
         .route("/:name", get(get_deployment))
         .route("/", post(create_deployment))
         .route("/:name", put(update_deployment))
-        .route("/:name/scale", patch(scale_deployment))
+        .route("/:name/status", get(get_deployment))
+        .route("/:name/status", put(update_deployment_status))
+        .route("/:name/status", patch(update_deployment_status))
+        .route("/:name", patch(patch_deployment))
         .route("/:name", delete(delete_deployment))
+        .route("/:name/scale", get(get_deployment_scale))
+        .route("/:name/scale", put(scale_deployment))
+        .route("/:name/scale", patch(scale_deployment))
 }
 
 #[tracing::instrument(skip_all)]
 async fn list_deployments(
     State(state): State<AppState>,
-) -> (StatusCode, Json<List<SerializableResource<Deployment>>>) {
+    namespace: Option<Path<String>>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
     info!("Got list request for deployments");
+    let namespace = namespace.map(|Path(namespace)| namespace);
+    let query = match params.query::<Deployment>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.deployments, &namespace, &query)
+                .cloned()
+                .collect()
+        })
+        .await;
+    }
     let state = state.lock().await;
     let deployments = List {
-        items: state
-            .deployments
-            .iter()
+        items: selected(&state.deployments, &namespace, &query)
             .map(|d| SerializableResource::new(d.clone()))
             .collect(),
         metadata: ListMeta {
@@ -221,17 +513,17 @@ async fn list_deployments(
             self_link: None,
         },
     };
-    (StatusCode::OK, Json(deployments))
+    (StatusCode::OK, Json(deployments)).into_response()
 }
 
 #[tracing::instrument(skip_all)]
 async fn get_deployment(
     State(state): State<AppState>,
-    Path(name): Path<String>,
+    Path((namespace, name)): Path<(String, String)>,
 ) -> (StatusCode, Json<SerializableResource<Deployment>>) {
     info!("Got get request for deployment");
     let state = state.lock().await;
-    if let Some(deployment) = state.deployments.get(&name) {
+    if let Some(deployment) = state.deployments.get_in(&namespace, &name) {
         (
             StatusCode::OK,
             Json(SerializableResource::new(deployment.clone())),
@@ -247,71 +539,156 @@ async fn get_deployment(
 #[tracing::instrument(skip_all)]
 async fn create_deployment(
     State(state): State<AppState>,
+    Path(namespace): Path<String>,
     Json(deployment): Json<Deployment>,
-) -> (StatusCode, Json<SerializableResource<Deployment>>) {
+) -> Result<(StatusCode, Json<SerializableResource<Deployment>>), StatusCode> {
     info!("Got create request for deployment");
+    let mut deployment = with_namespace(deployment, &namespace)?;
     let mut s = state.lock().await;
+    if !s.namespace_accepts_creates(&namespace) {
+        return Err(StatusCode::FORBIDDEN);
+    }
     s.revision = s.revision.clone().increment();
     let revision = s.revision.clone();
     let deployment_name = deployment.metadata.name.clone();
-    s.deployments.create(deployment, revision).unwrap();
-    let deployment = s.deployments.get(&deployment_name).unwrap().clone();
-    (StatusCode::OK, Json(SerializableResource::new(deployment)))
+    sync_clock(&mut s);
+    deployment.metadata.creation_timestamp = Some(s.now());
+    s.deployments
+        .create(deployment, revision)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    let deployment = s
+        .deployments
+        .get_in(&namespace, &deployment_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(deployment))))
 }
 
 #[tracing::instrument(skip_all)]
 async fn update_deployment(
     State(state): State<AppState>,
+    Path((namespace, _name)): Path<(String, String)>,
     Json(deployment): Json<Deployment>,
-) -> (StatusCode, Json<SerializableResource<Deployment>>) {
+) -> Result<(StatusCode, Json<SerializableResource<Deployment>>), StatusCode> {
     info!("Got create request for deployment");
+    let mut deployment = with_namespace(deployment, &namespace)?;
     let mut s = state.lock().await;
-    s.revision = s.revision.clone().increment();
-    let revision = s.revision.clone();
+    let revision = s.revision.clone().increment();
     let deployment_name = deployment.metadata.name.clone();
-    s.deployments.update(deployment, revision).unwrap();
-    let deployment = s.deployments.get(&deployment_name).unwrap().clone();
-    (StatusCode::OK, Json(SerializableResource::new(deployment)))
+    if let Some(current) = s.deployments.get_in(&namespace, &deployment_name) {
+        deployment.reset_status(current);
+    }
+    s.deployments
+        .update(deployment, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let deployment = s
+        .deployments
+        .get_in(&namespace, &deployment_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(deployment))))
+}
+
+/// Update the status of the deployment, any changes to its spec are ignored.
+#[tracing::instrument(skip_all)]
+async fn update_deployment_status(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Deployment>>), StatusCode> {
+    info!("Got status update request for deployment");
+    let mut s = state.lock().await;
+    let current = s
+        .deployments
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let deployment = status_request(&headers, body, current)?;
+    let revision = s.revision.clone().increment();
+    apply::deployments::update_status(&mut s, deployment, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let deployment = s.deployments.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(deployment))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn patch_deployment(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Deployment>>), StatusCode> {
+    info!("Got patch request for deployment");
+    let patch = patch_request(&headers, body)?;
+    let mut s = state.lock().await;
+    let current = s
+        .deployments
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut patched = apply::patched(Some(current), &patch).map_err(|_| StatusCode::CONFLICT)?;
+    patched.reset_status(current);
+    let revision = s.revision.clone().increment();
+    apply::deployments::update(&mut s, patched, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let deployment = s.deployments.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(deployment))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_deployment_scale(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<SerializableResource<Scale>>), StatusCode> {
+    info!("Got get scale request for deployment");
+    let s = state.lock().await;
+    let d = s
+        .deployments
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let scale = deployment_scale(d);
+    Ok((StatusCode::OK, Json(SerializableResource::new(scale))))
 }
 
 #[tracing::instrument(skip_all)]
 async fn scale_deployment(
     State(state): State<AppState>,
-    Path(name): Path<String>,
-    Json(scale): Json<Scale>,
-) -> (StatusCode, Json<SerializableResource<Deployment>>) {
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Scale>>), StatusCode> {
     info!("Got scale request for deployment");
     let mut s = state.lock().await;
-    s.revision = s.revision.clone().increment();
-    let revision = s.revision.clone();
-    let mut deployment = s.deployments.get(&name).unwrap().clone();
-    deployment.spec.replicas = scale.spec.replicas;
-    s.deployments.update(deployment, revision).unwrap();
-    let deployment = s.deployments.get(&name).unwrap().clone();
-    (StatusCode::OK, Json(SerializableResource::new(deployment)))
+    let d = s
+        .deployments
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let scale = scale_body(&headers, body, deployment_scale(d))?;
+    let revision = s.revision.clone().increment();
+    apply::deployments::scale(&mut s, scale, revision.clone()).map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let d = s.deployments.get_in(&namespace, &name).unwrap();
+    let scale = deployment_scale(d);
+    Ok((StatusCode::OK, Json(SerializableResource::new(scale))))
 }
 
 #[tracing::instrument(skip_all)]
 async fn delete_deployment(
     State(state): State<AppState>,
-    Path(name): Path<String>,
-) -> (StatusCode, Json<Status>) {
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<Status>), StatusCode> {
     info!("Got create request for deployment");
     let mut s = state.lock().await;
+    let deployment = s
+        .deployments
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .clone();
     s.revision = s.revision.clone().increment();
-    let deployment = s.deployments.get(&name).unwrap().clone();
     s.deployments.remove(&deployment);
-    (
-        StatusCode::OK,
-        Json(Status {
-            code: None,
-            details: None,
-            message: None,
-            metadata: ListMeta::default(),
-            reason: None,
-            status: Some("Success".to_owned()),
-        }),
-    )
+    Ok((StatusCode::OK, success()))
 }
 
 fn replicasets_router() -> Router<AppState> {
@@ -320,19 +697,40 @@ fn replicasets_router() -> Router<AppState> {
         .route("/:name", get(get_replicaset))
         .route("/", post(create_replicaset))
         .route("/:name", put(update_replicaset))
+        .route("/:name/status", get(get_replicaset))
+        .route("/:name/status", put(update_replicaset_status))
+        .route("/:name/status", patch(update_replicaset_status))
+        .route("/:name", patch(patch_replicaset))
         .route("/:name", delete(delete_replicaset))
+        .route("/:name/scale", get(get_replicaset_scale))
+        .route("/:name/scale", put(scale_replicaset))
+        .route("/:name/scale", patch(scale_replicaset))
 }
 
 #[tracing::instrument(skip_all)]
 async fn list_replicasets(
     State(state): State<AppState>,
-) -> (StatusCode, Json<List<SerializableResource<ReplicaSet>>>) {
+    namespace: Option<Path<String>>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
     info!("Got list request for replicasets");
+    let namespace = namespace.map(|Path(namespace)| namespace);
+    let query = match params.query::<ReplicaSet>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.replicasets, &namespace, &query)
+                .cloned()
+                .collect()
+        })
+        .await;
+    }
     let state = state.lock().await;
     let replicasets = List {
-        items: state
-            .replicasets
-            .iter()
+        items: selected(&state.replicasets, &namespace, &query)
             .map(|d| SerializableResource::new(d.clone()))
             .collect(),
         metadata: ListMeta {
@@ -342,17 +740,17 @@ async fn list_replicasets(
             self_link: None,
         },
     };
-    (StatusCode::OK, Json(replicasets))
+    (StatusCode::OK, Json(replicasets)).into_response()
 }
 
 #[tracing::instrument(skip_all)]
 async fn get_replicaset(
     State(state): State<AppState>,
-    Path(name): Path<String>,
+    Path((namespace, name)): Path<(String, String)>,
 ) -> (StatusCode, Json<SerializableResource<ReplicaSet>>) {
     info!("Got get request for replicaset");
     let state = state.lock().await;
-    if let Some(replicaset) = state.replicasets.get(&name) {
+    if let Some(replicaset) = state.replicasets.get_in(&namespace, &name) {
         (
             StatusCode::OK,
             Json(SerializableResource::new(replicaset.clone())),
@@ -368,90 +766,713 @@ async fn get_replicaset(
 #[tracing::instrument(skip_all)]
 async fn create_replicaset(
     State(state): State<AppState>,
+    Path(namespace): Path<String>,
     Json(replicaset): Json<ReplicaSet>,
-) -> (StatusCode, Json<ReplicaSet>) {
+) -> Result<(StatusCode, Json<ReplicaSet>), StatusCode> {
     info!("Got create request for replicaset");
+    let mut replicaset = with_namespace(replicaset, &namespace)?;
     let mut s = state.lock().await;
+    if !s.namespace_accepts_creates(&namespace) {
+        return Err(StatusCode::FORBIDDEN);
+    }
     s.revision = s.revision.clone().increment();
     let revision = s.revision.clone();
     let replicaset_name = replicaset.metadata.name.clone();
-    s.replicasets.create(replicaset, revision).unwrap();
-    let replicaset = s.replicasets.get(&replicaset_name).unwrap().clone();
-    (StatusCode::OK, Json(replicaset))
+    sync_clock(&mut s);
+    replicaset.metadata.creation_timestamp = Some(s.now());
+    s.replicasets
+        .create(replicaset, revision)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    let replicaset = s
+        .replicasets
+        .get_in(&namespace, &replicaset_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(replicaset)))
 }
 
 #[tracing::instrument(skip_all)]
 async fn update_replicaset(
     State(state): State<AppState>,
+    Path((namespace, _name)): Path<(String, String)>,
     Json(replicaset): Json<ReplicaSet>,
-) -> (StatusCode, Json<ReplicaSet>) {
+) -> Result<(StatusCode, Json<ReplicaSet>), StatusCode> {
     info!("Got create request for replicaset");
+    let mut replicaset = with_namespace(replicaset, &namespace)?;
     let mut s = state.lock().await;
-    s.revision = s.revision.clone().increment();
-    let revision = s.revision.clone();
+    let revision = s.revision.clone().increment();
     let replicaset_name = replicaset.metadata.name.clone();
-    s.replicasets.update(replicaset, revision).unwrap();
-    let replicaset = s.replicasets.get(&replicaset_name).unwrap().clone();
-    (StatusCode::OK, Json(replicaset))
+    if let Some(current) = s.replicasets.get_in(&namespace, &replicaset_name) {
+        replicaset.reset_status(current);
+    }
+    s.replicasets
+        .update(replicaset, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let replicaset = s
+        .replicasets
+        .get_in(&namespace, &replicaset_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(replicaset)))
+}
+
+/// Update the status of the replicaset, any changes to its spec are ignored.
+#[tracing::instrument(skip_all)]
+async fn update_replicaset_status(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<ReplicaSet>), StatusCode> {
+    info!("Got status update request for replicaset");
+    let mut s = state.lock().await;
+    let current = s
+        .replicasets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let replicaset = status_request(&headers, body, current)?;
+    let revision = s.revision.clone().increment();
+    apply::replicasets::update_status(&mut s, replicaset, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let replicaset = s.replicasets.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(replicaset)))
+}
+
+#[tracing::instrument(skip_all)]
+async fn patch_replicaset(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<ReplicaSet>>), StatusCode> {
+    info!("Got patch request for replicaset");
+    let patch = patch_request(&headers, body)?;
+    let mut s = state.lock().await;
+    let current = s
+        .replicasets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut patched = apply::patched(Some(current), &patch).map_err(|_| StatusCode::CONFLICT)?;
+    patched.reset_status(current);
+    let revision = s.revision.clone().increment();
+    apply::replicasets::update(&mut s, patched, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let replicaset = s.replicasets.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(replicaset))))
 }
 
 #[tracing::instrument(skip_all)]
 async fn delete_replicaset(
     State(state): State<AppState>,
-    Path(name): Path<String>,
-) -> (StatusCode, Json<Status>) {
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<Status>), StatusCode> {
     info!("Got create request for replicaset");
     let mut s = state.lock().await;
+    let replicaset = s
+        .replicasets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .clone();
     s.revision = s.revision.clone().increment();
-    let replicaset = s.replicasets.get(&name).unwrap().clone();
     s.replicasets.remove(&replicaset);
-    (
-        StatusCode::OK,
-        Json(Status {
-            code: None,
-            details: None,
-            message: None,
-            metadata: ListMeta::default(),
-            reason: None,
-            status: Some("Success".to_owned()),
-        }),
-    )
+    Ok((StatusCode::OK, success()))
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_replicaset_scale(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<SerializableResource<Scale>>), StatusCode> {
+    info!("Got get scale request for replicaset");
+    let s = state.lock().await;
+    let rs = s
+        .replicasets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let scale = replicaset_scale(rs);
+    Ok((StatusCode::OK, Json(SerializableResource::new(scale))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn scale_replicaset(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Scale>>), StatusCode> {
+    info!("Got scale request for replicaset");
+    let mut s = state.lock().await;
+    let rs = s
+        .replicasets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let scale = scale_body(&headers, body, replicaset_scale(rs))?;
+    let revision = s.revision.clone().increment();
+    apply::replicasets::scale(&mut s, scale, revision.clone()).map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let rs = s.replicasets.get_in(&namespace, &name).unwrap();
+    let scale = replicaset_scale(rs);
+    Ok((StatusCode::OK, Json(SerializableResource::new(scale))))
+}
+
+fn statefulsets_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_statefulsets))
+        .route("/:name", get(get_statefulset))
+        .route("/:name", put(update_statefulset))
+        .route("/:name", patch(patch_statefulset))
+        .route("/:name/status", get(get_statefulset))
+        .route("/:name/status", put(update_statefulset_status))
+        .route("/:name/status", patch(update_statefulset_status))
+        .route("/:name/scale", get(get_statefulset_scale))
+        .route("/:name/scale", put(scale_statefulset))
+        .route("/:name/scale", patch(scale_statefulset))
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_statefulsets(
+    State(state): State<AppState>,
+    namespace: Option<Path<String>>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
+    info!("Got list request for statefulsets");
+    let namespace = namespace.map(|Path(namespace)| namespace);
+    let query = match params.query::<StatefulSet>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.statefulsets, &namespace, &query)
+                .cloned()
+                .collect()
+        })
+        .await;
+    }
+    let state = state.lock().await;
+    let statefulsets = List {
+        items: selected(&state.statefulsets, &namespace, &query)
+            .map(|sts| SerializableResource::new(sts.clone()))
+            .collect(),
+        metadata: ListMeta {
+            continue_: None,
+            remaining_item_count: None,
+            resource_version: Some(state.revision.to_string()),
+            self_link: None,
+        },
+    };
+    (StatusCode::OK, Json(statefulsets)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_statefulset(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<SerializableResource<StatefulSet>>), StatusCode> {
+    info!("Got get request for statefulset");
+    let state = state.lock().await;
+    let sts = state
+        .statefulsets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok((StatusCode::OK, Json(SerializableResource::new(sts.clone()))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn patch_statefulset(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<StatefulSet>>), StatusCode> {
+    info!("Got patch request for statefulset");
+    let patch = patch_request(&headers, body)?;
+    let mut s = state.lock().await;
+    let current = s
+        .statefulsets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut patched = apply::patched(Some(current), &patch).map_err(|_| StatusCode::CONFLICT)?;
+    patched.reset_status(current);
+    let revision = s.revision.clone().increment();
+    apply::statefulsets::update(&mut s, patched, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let statefulset = s.statefulsets.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(statefulset))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_statefulset(
+    State(state): State<AppState>,
+    Path((namespace, _name)): Path<(String, String)>,
+    Json(statefulset): Json<StatefulSet>,
+) -> Result<(StatusCode, Json<SerializableResource<StatefulSet>>), StatusCode> {
+    info!("Got update request for statefulset");
+    let mut statefulset = with_namespace(statefulset, &namespace)?;
+    let mut s = state.lock().await;
+    let revision = s.revision.clone().increment();
+    let statefulset_name = statefulset.metadata.name.clone();
+    if let Some(current) = s.statefulsets.get_in(&namespace, &statefulset_name) {
+        statefulset.reset_status(current);
+    }
+    apply::statefulsets::update(&mut s, statefulset, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let statefulset = s
+        .statefulsets
+        .get_in(&namespace, &statefulset_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(statefulset))))
+}
+
+/// Update the status of the statefulset, any changes to its spec are ignored.
+#[tracing::instrument(skip_all)]
+async fn update_statefulset_status(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<StatefulSet>>), StatusCode> {
+    info!("Got status update request for statefulset");
+    let mut s = state.lock().await;
+    let current = s
+        .statefulsets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let statefulset = status_request(&headers, body, current)?;
+    let revision = s.revision.clone().increment();
+    apply::statefulsets::update_status(&mut s, statefulset, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let statefulset = s.statefulsets.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(statefulset))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_statefulset_scale(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<SerializableResource<Scale>>), StatusCode> {
+    info!("Got get scale request for statefulset");
+    let s = state.lock().await;
+    let sts = s
+        .statefulsets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let scale = statefulset_scale(sts);
+    Ok((StatusCode::OK, Json(SerializableResource::new(scale))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn scale_statefulset(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Scale>>), StatusCode> {
+    info!("Got scale request for statefulset");
+    let mut s = state.lock().await;
+    let sts = s
+        .statefulsets
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let scale = scale_body(&headers, body, statefulset_scale(sts))?;
+    let revision = s.revision.clone().increment();
+    apply::statefulsets::scale(&mut s, scale, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let sts = s.statefulsets.get_in(&namespace, &name).unwrap();
+    let scale = statefulset_scale(sts);
+    Ok((StatusCode::OK, Json(SerializableResource::new(scale))))
 }
 
+fn batch_v1() -> Router<AppState> {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .nest("/namespaces/:namespace/jobs", jobs_router())
+}
+
+fn jobs_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs))
+        .route("/:name", get(get_job))
+        .route("/", post(create_job))
+        .route("/:name", put(update_job))
+        .route("/:name", patch(patch_job))
+        .route("/:name", delete(delete_job))
+        .route("/:name/status", get(get_job))
+        .route("/:name/status", put(update_job_status))
+        .route("/:name/status", patch(update_job_status))
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_jobs(
+    State(state): State<AppState>,
+    namespace: Option<Path<String>>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
+    info!("Got list request for jobs");
+    let namespace = namespace.map(|Path(namespace)| namespace);
+    let query = match params.query::<Job>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.jobs, &namespace, &query).cloned().collect()
+        })
+        .await;
+    }
+    let state = state.lock().await;
+    let jobs = List {
+        items: selected(&state.jobs, &namespace, &query)
+            .map(|job| SerializableResource::new(job.clone()))
+            .collect(),
+        metadata: ListMeta {
+            continue_: None,
+            remaining_item_count: None,
+            resource_version: Some(state.revision.to_string()),
+            self_link: None,
+        },
+    };
+    (StatusCode::OK, Json(jobs)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_job(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<SerializableResource<Job>>), StatusCode> {
+    info!("Got get request for job");
+    let state = state.lock().await;
+    let job = state
+        .jobs
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok((StatusCode::OK, Json(SerializableResource::new(job.clone()))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn create_job(
+    State(state): State<AppState>,
+    Path(namespace): Path<String>,
+    Json(job): Json<Job>,
+) -> Result<(StatusCode, Json<SerializableResource<Job>>), StatusCode> {
+    info!("Got create request for job");
+    let mut job = with_namespace(job, &namespace)?;
+    let mut s = state.lock().await;
+    if !s.namespace_accepts_creates(&namespace) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    s.revision = s.revision.clone().increment();
+    let revision = s.revision.clone();
+    let job_name = job.metadata.name.clone();
+    sync_clock(&mut s);
+    job.metadata.creation_timestamp = Some(s.now());
+    s.jobs
+        .create(job, revision)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    let job = s.jobs.get_in(&namespace, &job_name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(job))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_job(
+    State(state): State<AppState>,
+    Path((namespace, _name)): Path<(String, String)>,
+    Json(job): Json<Job>,
+) -> Result<(StatusCode, Json<SerializableResource<Job>>), StatusCode> {
+    info!("Got update request for job");
+    let mut job = with_namespace(job, &namespace)?;
+    let mut s = state.lock().await;
+    let revision = s.revision.clone().increment();
+    let job_name = job.metadata.name.clone();
+    if let Some(current) = s.jobs.get_in(&namespace, &job_name) {
+        job.reset_status(current);
+    }
+    apply::jobs::update(&mut s, job, revision.clone()).map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let job = s.jobs.get_in(&namespace, &job_name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(job))))
+}
+
+/// Update the status of the job, any changes to its spec are ignored.
+#[tracing::instrument(skip_all)]
+async fn update_job_status(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Job>>), StatusCode> {
+    info!("Got status update request for job");
+    let mut s = state.lock().await;
+    let current = s
+        .jobs
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let job = status_request(&headers, body, current)?;
+    let revision = s.revision.clone().increment();
+    apply::jobs::update_status(&mut s, job, revision.clone()).map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let job = s.jobs.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(job))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn patch_job(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Job>>), StatusCode> {
+    info!("Got patch request for job");
+    let patch = patch_request(&headers, body)?;
+    let mut s = state.lock().await;
+    let current = s
+        .jobs
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut patched = apply::patched(Some(current), &patch).map_err(|_| StatusCode::CONFLICT)?;
+    patched.reset_status(current);
+    let revision = s.revision.clone().increment();
+    apply::jobs::update(&mut s, patched, revision.clone()).map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let job = s.jobs.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(job))))
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_job(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<Status>), StatusCode> {
+    info!("Got delete request for job");
+    let mut s = state.lock().await;
+    let job = s
+        .jobs
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .clone();
+    s.revision = s.revision.clone().increment();
+    s.jobs.remove(&job);
+    Ok((StatusCode::OK, success()))
+}
+
+fn replication_controllers_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_replication_controllers))
+        .route("/:name", get(get_replication_controller))
+        .route("/", post(create_replication_controller))
+        .route("/:name", put(update_replication_controller))
+        .route("/:name", delete(delete_replication_controller))
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_replication_controllers(
+    State(state): State<AppState>,
+    namespace: Option<Path<String>>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
+    info!("Got list request for replicationcontrollers");
+    let namespace = namespace.map(|Path(namespace)| namespace);
+    let query = match params.query::<ReplicationController>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.replication_controllers, &namespace, &query)
+                .cloned()
+                .collect()
+        })
+        .await;
+    }
+    let state = state.lock().await;
+    let replication_controllers = List {
+        items: selected(&state.replication_controllers, &namespace, &query)
+            .map(|rc| SerializableResource::new(rc.clone()))
+            .collect(),
+        metadata: ListMeta {
+            continue_: None,
+            remaining_item_count: None,
+            resource_version: Some(state.revision.to_string()),
+            self_link: None,
+        },
+    };
+    (StatusCode::OK, Json(replication_controllers)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_replication_controller(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> (
+    StatusCode,
+    Json<SerializableResource<ReplicationController>>,
+) {
+    info!("Got get request for replicationcontroller");
+    let state = state.lock().await;
+    if let Some(rc) = state.replication_controllers.get_in(&namespace, &name) {
+        (StatusCode::OK, Json(SerializableResource::new(rc.clone())))
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(SerializableResource::new(ReplicationController::default())),
+        )
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn create_replication_controller(
+    State(state): State<AppState>,
+    Path(namespace): Path<String>,
+    Json(rc): Json<ReplicationController>,
+) -> Result<(StatusCode, Json<ReplicationController>), StatusCode> {
+    info!("Got create request for replicationcontroller");
+    let mut rc = with_namespace(rc, &namespace)?;
+    let mut s = state.lock().await;
+    if !s.namespace_accepts_creates(&namespace) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    s.revision = s.revision.clone().increment();
+    let revision = s.revision.clone();
+    let rc_name = rc.metadata.name.clone();
+    sync_clock(&mut s);
+    rc.metadata.creation_timestamp = Some(s.now());
+    s.replication_controllers
+        .create(rc, revision)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    let rc = s
+        .replication_controllers
+        .get_in(&namespace, &rc_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(rc)))
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_replication_controller(
+    State(state): State<AppState>,
+    Path((namespace, _name)): Path<(String, String)>,
+    Json(rc): Json<ReplicationController>,
+) -> Result<(StatusCode, Json<ReplicationController>), StatusCode> {
+    info!("Got update request for replicationcontroller");
+    let rc = with_namespace(rc, &namespace)?;
+    let mut s = state.lock().await;
+    let revision = s.revision.clone().increment();
+    let rc_name = rc.metadata.name.clone();
+    s.replication_controllers
+        .update(rc, revision.clone())
+        .map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let rc = s
+        .replication_controllers
+        .get_in(&namespace, &rc_name)
+        .unwrap()
+        .clone();
+    Ok((StatusCode::OK, Json(rc)))
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_replication_controller(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<Status>), StatusCode> {
+    info!("Got delete request for replicationcontroller");
+    let mut s = state.lock().await;
+    let rc = s
+        .replication_controllers
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .clone();
+    s.revision = s.revision.clone().increment();
+    s.replication_controllers.remove(&rc);
+    Ok((StatusCode::OK, success()))
+}
+
+#[tracing::instrument(skip_all)]
+async fn version() -> (StatusCode, Json<serde_json::Value>) {
+    info!("Got request for version");
+    let version = serde_json::json!({
+        "major": "1",
+        "minor": "26",
+        "gitVersion": openapi::GIT_VERSION,
+        "gitCommit": "",
+        "gitTreeState": "clean",
+        "buildDate": "",
+        "goVersion": "",
+        "compiler": "",
+        "platform": "",
+    });
+    (StatusCode::OK, Json(version))
+}
+
+/// The versions of the core group, served under `/api` rather than `/apis`.
+#[tracing::instrument(skip_all)]
+async fn api_versions() -> (StatusCode, Json<APIVersions>) {
+    info!("Got request for api versions");
+    let versions = APIVersions {
+        server_address_by_client_cidrs: Vec::new(),
+        versions: vec!["v1".to_owned()],
+    };
+    (StatusCode::OK, Json(versions))
+}
+
+/// A named group, served at its only version.
+fn api_group(name: &str) -> APIGroup {
+    let version = GroupVersionForDiscovery {
+        group_version: format!("{name}/v1"),
+        version: "v1".to_owned(),
+    };
+    APIGroup {
+        name: name.to_owned(),
+        preferred_version: Some(version.clone()),
+        server_address_by_client_cidrs: None,
+        versions: vec![version],
+    }
+}
+
+/// The named groups, which leave out the core group.
 #[tracing::instrument(skip_all)]
 async fn api_groups() -> (StatusCode, Json<APIGroupList>) {
     info!("Got request for api groups");
     let apiversions = APIGroupList {
-        groups: vec![
-            APIGroup {
-                name: "".to_owned(),
-                preferred_version: None,
-                server_address_by_client_cidrs: None,
-                versions: vec![GroupVersionForDiscovery {
-                    group_version: "v1".to_owned(),
-                    version: "v1".to_owned(),
-                }],
-            },
-            APIGroup {
-                name: "apps".to_owned(),
-                preferred_version: None,
-                server_address_by_client_cidrs: None,
-                versions: vec![GroupVersionForDiscovery {
-                    group_version: "apps/v1".to_owned(),
-                    version: "v1".to_owned(),
-                }],
-            },
-        ],
+        groups: vec![api_group("apps"), api_group("batch")],
     };
     (StatusCode::OK, Json(apiversions))
 }
 
+#[tracing::instrument(skip_all)]
+async fn apps_api_group() -> (StatusCode, Json<APIGroup>) {
+    info!("Got request for api group apps");
+    (StatusCode::OK, Json(api_group("apps")))
+}
+
+#[tracing::instrument(skip_all)]
+async fn batch_api_group() -> (StatusCode, Json<APIGroup>) {
+    info!("Got request for api group batch");
+    (StatusCode::OK, Json(api_group("batch")))
+}
+
 #[tracing::instrument(skip_all)]
 async fn list_core_v1() -> (StatusCode, Json<APIResourceList>) {
     info!("Got request for api v1 versions");
     let apiversions = APIResourceList {
         group_version: "v1".to_owned(),
-        resources: vec![Pod::api_resource(), Node::api_resource()],
+        resources: vec![
+            Pod::api_resource(),
+            ReplicationController::api_resource(),
+            Node::api_resource(),
+            Namespace::api_resource(),
+        ],
     };
     (StatusCode::OK, Json(apiversions))
 }
@@ -463,43 +1484,71 @@ async fn list_apps_v1() -> (StatusCode, Json<APIResourceList>) {
         group_version: "apps/v1".to_owned(),
         resources: vec![
             Deployment::api_resource(),
+            status_api_resource::<Deployment>(),
             Scale::api_resource::<Deployment>(),
             ReplicaSet::api_resource(),
+            status_api_resource::<ReplicaSet>(),
+            Scale::api_resource::<ReplicaSet>(),
+            StatefulSet::api_resource(),
+            status_api_resource::<StatefulSet>(),
+            Scale::api_resource::<StatefulSet>(),
         ],
     };
     (StatusCode::OK, Json(apiversions))
 }
 
+#[tracing::instrument(skip_all)]
+async fn list_batch_v1() -> (StatusCode, Json<APIResourceList>) {
+    info!("Got request for api batch/v1 versions");
+    let apiversions = APIResourceList {
+        group_version: "batch/v1".to_owned(),
+        resources: vec![Job::api_resource(), status_api_resource::<Job>()],
+    };
+    (StatusCode::OK, Json(apiversions))
+}
+
 #[tracing::instrument(skip_all)]
 async fn list_pods(
     State(state): State<AppState>,
-) -> (StatusCode, Json<List<SerializableResource<Pod>>>) {
+    namespace: Option<Path<String>>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
     info!("Got list request for pods");
+    let namespace = namespace.map(|Path(namespace)| namespace);
+    let query = match params.query::<Pod>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.pods, &namespace, &query).cloned().collect()
+        })
+        .await;
+    }
     let state = state.lock().await;
     let pods = List {
-        items: state
-            .pods
-            .iter()
+        items: selected(&state.pods, &namespace, &query)
             .map(|p| SerializableResource::new(p.clone()))
             .collect(),
         metadata: ListMeta {
             continue_: None,
             remaining_item_count: None,
-            resource_version: None,
+            resource_version: Some(state.revision.to_string()),
             self_link: None,
         },
     };
-    (StatusCode::OK, Json(pods))
+    (StatusCode::OK, Json(pods)).into_response()
 }
 
 #[tracing::instrument(skip_all)]
 async fn get_pod(
     State(state): State<AppState>,
-    Path(name): Path<String>,
+    Path((namespace, name)): Path<(String, String)>,
 ) -> (StatusCode, Json<SerializableResource<Pod>>) {
     info!("Got get request for pods");
     let state = state.lock().await;
-    if let Some(pod) = state.pods.get(&name) {
+    if let Some(pod) = state.pods.get_in(&namespace, &name) {
         (StatusCode::OK, Json(SerializableResource::new(pod.clone())))
     } else {
         (
@@ -509,49 +1558,127 @@ async fn get_pod(
     }
 }
 
+#[tracing::instrument(skip_all)]
+async fn patch_pod(
+    State(state): State<AppState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<SerializableResource<Pod>>), StatusCode> {
+    info!("Got patch request for pod");
+    let patch = patch_request(&headers, body)?;
+    let mut s = state.lock().await;
+    let current = s
+        .pods
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let patched = apply::patched(Some(current), &patch).map_err(|_| StatusCode::CONFLICT)?;
+    let revision = s.revision.clone().increment();
+    apply::pods::update(&mut s, patched, revision.clone()).map_err(|_| StatusCode::CONFLICT)?;
+    s.revision = revision;
+    let pod = s.pods.get_in(&namespace, &name).unwrap().clone();
+    Ok((StatusCode::OK, Json(SerializableResource::new(pod))))
+}
+
 #[tracing::instrument(skip_all)]
 async fn delete_pod(
     State(state): State<AppState>,
-    Path(name): Path<String>,
-) -> (StatusCode, Json<Status>) {
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<Status>), StatusCode> {
     info!("Got delete request for pods");
     let mut state = state.lock().await;
+    let pod = state
+        .pods
+        .get_in(&namespace, &name)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .clone();
     state.revision = state.revision.clone().increment();
-    let pod = state.pods.get(&name).unwrap().clone();
     state.pods.remove(&pod);
-    (
-        StatusCode::OK,
-        Json(Status {
-            code: None,
-            details: None,
-            message: None,
-            metadata: ListMeta::default(),
-            reason: None,
-            status: Some("Success".to_owned()),
-        }),
-    )
+    Ok((StatusCode::OK, success()))
+}
+
+#[tracing::instrument(skip_all)]
+async fn list_namespaces(
+    State(state): State<AppState>,
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
+    info!("Got list request for namespaces");
+    let query = match params.query::<Namespace>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.namespaces, &None, &query).cloned().collect()
+        })
+        .await;
+    }
+    let state = state.lock().await;
+    let namespaces = List {
+        items: selected(&state.namespaces, &None, &query)
+            .map(|n| SerializableResource::new(n.clone()))
+            .collect(),
+        metadata: ListMeta {
+            continue_: None,
+            remaining_item_count: None,
+            resource_version: Some(state.revision.to_string()),
+            self_link: None,
+        },
+    };
+    (StatusCode::OK, Json(namespaces)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_namespace(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<SerializableResource<Namespace>>) {
+    info!("Got get request for namespaces");
+    let state = state.lock().await;
+    if let Some(namespace) = state.namespaces.get(&name) {
+        (
+            StatusCode::OK,
+            Json(SerializableResource::new(namespace.clone())),
+        )
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(SerializableResource::new(Namespace::default())),
+        )
+    }
 }
 
 #[tracing::instrument(skip_all)]
 async fn list_nodes(
     State(state): State<AppState>,
-) -> (StatusCode, Json<List<SerializableResource<Node>>>) {
+    Query(params): Query<ListParams>,
+    Extension(watches): Extension<Watches>,
+) -> Response {
     info!("Got list request for nodes");
+    let query = match params.query::<Node>() {
+        Ok(query) => query,
+        Err(error) => return bad_request(error).into_response(),
+    };
+    if params.watch {
+        return watch::watch(state, watches, params, move |s| {
+            selected(&s.nodes, &None, &query).cloned().collect()
+        })
+        .await;
+    }
     let state = state.lock().await;
     let nodes = List {
-        items: state
-            .nodes
-            .iter()
+        items: selected(&state.nodes, &None, &query)
             .map(|p| SerializableResource::new(p.clone()))
             .collect(),
         metadata: ListMeta {
             continue_: None,
             remaining_item_count: None,
-            resource_version: None,
+            resource_version: Some(state.revision.to_string()),
             self_link: None,
         },
     };
-    (StatusCode::OK, Json(nodes))
+    (StatusCode::OK, Json(nodes)).into_response()
 }
 
 #[tracing::instrument(skip_all)]
@@ -0,0 +1,297 @@
+//! Faults injected into the served cluster through its admin endpoints, so that tests against it
+//! can script the failures that the model checker explores: nodes failing, writes being lost,
+//! stale reads and controllers making no progress.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Extension, Json, Router};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ListMeta, Status};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::state::apply;
+
+use super::watch::{ListParams, Watches};
+use super::AppState;
+
+/// The faults currently injected into the cluster.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultConfig {
+    /// Nodes that have been removed from the cluster, along with the controllers running them.
+    pub killed_nodes: BTreeSet<String>,
+    /// Controllers that are not taking any steps.
+    pub paused_controllers: BTreeSet<String>,
+    /// The percentage of writes to the api that are dropped.
+    pub drop_writes_percent: u8,
+    /// How many states behind the latest one that reads are served from.
+    pub read_staleness: usize,
+}
+
+/// The faults injected into the cluster, shared by the requests and controllers.
+#[derive(Debug, Clone, Default)]
+pub struct Faults {
+    config: Arc<Mutex<FaultConfig>>,
+    controllers: Arc<Mutex<BTreeSet<String>>>,
+    nodes: Arc<Mutex<BTreeSet<String>>>,
+    dropped_writes: Arc<Mutex<u32>>,
+}
+
+impl Faults {
+    /// Allow the controller with the name to be paused.
+    pub fn register_controller(&self, name: String) {
+        self.controllers.lock().unwrap().insert(name);
+    }
+
+    /// Allow the node with the name to be killed.
+    pub fn register_node(&self, name: String) {
+        self.nodes.lock().unwrap().insert(name);
+    }
+
+    pub fn config(&self) -> FaultConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Pause the controller, failing if there is no controller with the name.
+    pub fn pause(&self, controller: &str) -> bool {
+        if !self.controllers.lock().unwrap().contains(controller) {
+            return false;
+        }
+        let mut config = self.config.lock().unwrap();
+        config.paused_controllers.insert(controller.to_owned());
+        true
+    }
+
+    pub fn resume(&self, controller: &str) {
+        let mut config = self.config.lock().unwrap();
+        config.paused_controllers.remove(controller);
+    }
+
+    pub fn paused(&self, controller: &str) -> bool {
+        self.config
+            .lock()
+            .unwrap()
+            .paused_controllers
+            .contains(controller)
+    }
+
+    /// Kill the node, failing if there is no node with the name.
+    pub fn kill(&self, node: &str) -> bool {
+        if !self.nodes.lock().unwrap().contains(node) {
+            return false;
+        }
+        let mut config = self.config.lock().unwrap();
+        config.killed_nodes.insert(node.to_owned());
+        true
+    }
+
+    pub fn restore(&self, node: &str) {
+        let mut config = self.config.lock().unwrap();
+        config.killed_nodes.remove(node);
+    }
+
+    pub fn killed(&self, node: &str) -> bool {
+        self.config.lock().unwrap().killed_nodes.contains(node)
+    }
+
+    /// Drop the given percentage of writes, capped at all of them.
+    pub fn set_drop_writes(&self, percent: u8) {
+        self.config.lock().unwrap().drop_writes_percent = percent.min(100);
+        *self.dropped_writes.lock().unwrap() = 0;
+    }
+
+    /// Whether to drop the next write.
+    ///
+    /// Writes are dropped evenly rather than at random, so that scripted scenarios are
+    /// reproducible: with 25% dropped, every fourth write is.
+    pub fn drop_write(&self) -> bool {
+        let percent = u32::from(self.config.lock().unwrap().drop_writes_percent);
+        let mut dropped = self.dropped_writes.lock().unwrap();
+        *dropped += percent;
+        if *dropped >= 100 {
+            *dropped -= 100;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_read_staleness(&self, states: usize) {
+        self.config.lock().unwrap().read_staleness = states;
+    }
+
+    pub fn read_staleness(&self) -> usize {
+        self.config.lock().unwrap().read_staleness
+    }
+
+    /// Remove all of the faults, restoring any killed nodes.
+    pub fn clear(&self) {
+        *self.config.lock().unwrap() = FaultConfig::default();
+        *self.dropped_writes.lock().unwrap() = 0;
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/faults", get(get_faults))
+        .route("/faults", delete(clear_faults))
+        .route("/nodes/:name/kill", post(kill_node))
+        .route("/nodes/:name/restore", post(restore_node))
+        .route("/controllers/:name/pause", post(pause_controller))
+        .route("/controllers/:name/resume", post(resume_controller))
+        .route("/writes/drop", put(drop_writes_percent))
+        .route("/reads/staleness", put(read_staleness))
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_faults(Extension(faults): Extension<Faults>) -> Json<FaultConfig> {
+    info!("Got request for faults");
+    Json(faults.config())
+}
+
+#[tracing::instrument(skip_all)]
+async fn clear_faults(Extension(faults): Extension<Faults>) -> Json<FaultConfig> {
+    info!("Got request to clear faults");
+    faults.clear();
+    Json(faults.config())
+}
+
+/// Kill the node, removing it from the cluster and stopping the controller running it until it is
+/// restored.
+#[tracing::instrument(skip_all)]
+async fn kill_node(
+    State(state): State<AppState>,
+    Extension(faults): Extension<Faults>,
+    Path(name): Path<String>,
+) -> Result<Json<FaultConfig>, StatusCode> {
+    info!(name, "Got request to kill node");
+    let mut s = state.lock().await;
+    if !faults.kill(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if let Some(node) = s.nodes.get(&name).cloned() {
+        s.revision = s.revision.clone().increment();
+        apply::nodes::delete(&mut s, node).map_err(|_| StatusCode::CONFLICT)?;
+    }
+    Ok(Json(faults.config()))
+}
+
+/// Restore the node, its controller starting afresh and joining it back to the cluster.
+#[tracing::instrument(skip_all)]
+async fn restore_node(
+    Extension(faults): Extension<Faults>,
+    Path(name): Path<String>,
+) -> Json<FaultConfig> {
+    info!(name, "Got request to restore node");
+    faults.restore(&name);
+    Json(faults.config())
+}
+
+#[tracing::instrument(skip_all)]
+async fn pause_controller(
+    Extension(faults): Extension<Faults>,
+    Path(name): Path<String>,
+) -> Result<Json<FaultConfig>, StatusCode> {
+    info!(name, "Got request to pause controller");
+    if !faults.pause(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(faults.config()))
+}
+
+#[tracing::instrument(skip_all)]
+async fn resume_controller(
+    Extension(faults): Extension<Faults>,
+    Path(name): Path<String>,
+) -> Json<FaultConfig> {
+    info!(name, "Got request to resume controller");
+    faults.resume(&name);
+    Json(faults.config())
+}
+
+#[derive(Debug, Deserialize)]
+struct DropWrites {
+    percent: u8,
+}
+
+#[tracing::instrument(skip_all)]
+async fn drop_writes_percent(
+    Extension(faults): Extension<Faults>,
+    Json(request): Json<DropWrites>,
+) -> Json<FaultConfig> {
+    info!(percent = request.percent, "Got request to drop writes");
+    faults.set_drop_writes(request.percent);
+    Json(faults.config())
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadStaleness {
+    states: usize,
+}
+
+#[tracing::instrument(skip_all)]
+async fn read_staleness(
+    Extension(faults): Extension<Faults>,
+    Json(request): Json<ReadStaleness>,
+) -> Json<FaultConfig> {
+    info!(states = request.states, "Got request for stale reads");
+    faults.set_read_staleness(request.states);
+    Json(faults.config())
+}
+
+/// Drop writes to the api before they are handled, as configured.
+pub async fn drop_writes(
+    Extension(faults): Extension<Faults>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let write = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    if write && faults.drop_write() {
+        info!(method = %request.method(), uri = %request.uri(), "Dropping write");
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(Status {
+                code: Some(503),
+                details: None,
+                message: Some("write dropped by fault injection".to_owned()),
+                metadata: ListMeta::default(),
+                reason: Some("ServiceUnavailable".to_owned()),
+                status: Some("Failure".to_owned()),
+            }),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Serve reads of the api from an older state of the cluster, as configured.
+///
+/// THEMELIOS: Older states are taken from the history kept for watches, which only holds the
+/// states seen as it polls the cluster, so a staleness of one state may skip several writes.
+/// Watches always start from the latest state.
+pub async fn stale_reads(
+    Extension(faults): Extension<Faults>,
+    Extension(watches): Extension<Watches>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let staleness = faults.read_staleness();
+    let watching = Query::<ListParams>::try_from_uri(request.uri())
+        .map_or(false, |Query(params)| params.watch);
+    if staleness == 0 || request.method() != Method::GET || watching {
+        return next.run(request).await;
+    }
+    let Some(view) = watches.behind(staleness) else {
+        return next.run(request).await;
+    };
+    super::serve_view(view, watches, request).await
+}
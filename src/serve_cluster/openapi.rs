@@ -0,0 +1,147 @@
+//! Minimal OpenAPI documents for the served cluster, enough for `kubectl` to find the kinds of the
+//! resources it is given.
+
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use k8s_openapi::Resource;
+use serde_json::{json, Map, Value};
+use tracing::info;
+
+use crate::resources::{
+    Deployment, Job, Namespace, Node, Pod, ReplicaSet, ReplicationController, StatefulSet,
+};
+
+use super::AppState;
+
+/// The kubernetes version that the served api follows.
+pub const GIT_VERSION: &str = "v1.26.0";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/v2", get(openapi_v2))
+        .route("/v3", get(openapi_v3))
+        .route("/v3/api/v1", get(openapi_v3_core_v1))
+        .route("/v3/apis/apps/v1", get(openapi_v3_apps_v1))
+        .route("/v3/apis/batch/v1", get(openapi_v3_batch_v1))
+}
+
+/// The name of the definition of the resource, as the api server names them.
+fn definition_name<T: Resource>() -> String {
+    let group = if T::GROUP.is_empty() {
+        "core"
+    } else {
+        T::GROUP
+    };
+    format!("io.k8s.api.{group}.{}.{}", T::VERSION, T::KIND)
+}
+
+/// A schema for the resource that accepts any fields, tagged with its group, version and kind.
+///
+/// THEMELIOS: Only the kinds are described rather than the fields of each resource, so clients
+/// can't validate requests against the schemas.
+fn definition<T: Resource>() -> (String, Value) {
+    let schema = json!({
+        "type": "object",
+        "x-kubernetes-preserve-unknown-fields": true,
+        "x-kubernetes-group-version-kind": [{
+            "group": T::GROUP,
+            "version": T::VERSION,
+            "kind": T::KIND,
+        }],
+    });
+    (definition_name::<T>(), schema)
+}
+
+fn core_v1_definitions() -> Map<String, Value> {
+    [
+        definition::<Pod>(),
+        definition::<ReplicationController>(),
+        definition::<Node>(),
+        definition::<Namespace>(),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn apps_v1_definitions() -> Map<String, Value> {
+    [
+        definition::<Deployment>(),
+        definition::<ReplicaSet>(),
+        definition::<StatefulSet>(),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn batch_v1_definitions() -> Map<String, Value> {
+    [definition::<Job>()].into_iter().collect()
+}
+
+fn info() -> Value {
+    json!({"title": "Kubernetes", "version": GIT_VERSION})
+}
+
+#[tracing::instrument(skip_all)]
+async fn openapi_v2() -> (StatusCode, Json<Value>) {
+    info!("Got request for openapi v2");
+    let mut definitions = core_v1_definitions();
+    definitions.extend(apps_v1_definitions());
+    definitions.extend(batch_v1_definitions());
+    let document = json!({
+        "swagger": "2.0",
+        "info": info(),
+        "paths": {},
+        "definitions": definitions,
+    });
+    (StatusCode::OK, Json(document))
+}
+
+#[tracing::instrument(skip_all)]
+async fn openapi_v3() -> (StatusCode, Json<Value>) {
+    info!("Got request for openapi v3");
+    let document = json!({
+        "paths": {
+            "api/v1": {"serverRelativeURL": "/openapi/v3/api/v1"},
+            "apis/apps/v1": {"serverRelativeURL": "/openapi/v3/apis/apps/v1"},
+            "apis/batch/v1": {"serverRelativeURL": "/openapi/v3/apis/batch/v1"},
+        },
+    });
+    (StatusCode::OK, Json(document))
+}
+
+fn openapi_v3_document(schemas: Map<String, Value>) -> Value {
+    json!({
+        "openapi": "3.0.0",
+        "info": info(),
+        "paths": {},
+        "components": {"schemas": schemas},
+    })
+}
+
+#[tracing::instrument(skip_all)]
+async fn openapi_v3_core_v1() -> (StatusCode, Json<Value>) {
+    info!("Got request for openapi v3 api/v1");
+    (
+        StatusCode::OK,
+        Json(openapi_v3_document(core_v1_definitions())),
+    )
+}
+
+#[tracing::instrument(skip_all)]
+async fn openapi_v3_apps_v1() -> (StatusCode, Json<Value>) {
+    info!("Got request for openapi v3 apis/apps/v1");
+    (
+        StatusCode::OK,
+        Json(openapi_v3_document(apps_v1_definitions())),
+    )
+}
+
+#[tracing::instrument(skip_all)]
+async fn openapi_v3_batch_v1() -> (StatusCode, Json<Value>) {
+    info!("Got request for openapi v3 apis/batch/v1");
+    (
+        StatusCode::OK,
+        Json(openapi_v3_document(batch_v1_definitions())),
+    )
+}
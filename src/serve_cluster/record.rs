@@ -0,0 +1,129 @@
+//! Recording of the writes that the served cluster accepts, for replaying through the model with
+//! [`crate::recording`].
+
+use axum::body::{self, Body};
+use axum::extract::Request;
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use tracing::warn;
+
+use crate::recording::{ClientOperation, RecordedResource, Recorder};
+use crate::resources::Scale;
+use crate::state::ResourceKind;
+
+/// The resource that a request is made to, from its path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub kind: ResourceKind,
+    pub namespace: String,
+    /// The name of the resource, none for requests to the collection.
+    pub name: Option<String>,
+    pub subresource: Option<String>,
+}
+
+impl Target {
+    /// The target of a request to a path within a namespace, such as
+    /// `/apis/apps/v1/namespaces/default/deployments/web/scale`, none for paths to kinds that
+    /// aren't recorded.
+    pub fn parse(path: &str) -> Option<Self> {
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+        let start = segments.iter().position(|s| *s == "namespaces")? + 1;
+        let (namespace, resource, name, subresource) = match segments[start..] {
+            [namespace, resource] => (namespace, resource, None, None),
+            [namespace, resource, name] => (namespace, resource, Some(name), None),
+            [namespace, resource, name, subresource] => {
+                (namespace, resource, Some(name), Some(subresource))
+            }
+            _ => return None,
+        };
+        let kind = match resource {
+            "pods" => ResourceKind::Pods,
+            "deployments" => ResourceKind::Deployments,
+            "replicasets" => ResourceKind::ReplicaSets,
+            "replicationcontrollers" => ResourceKind::ReplicationControllers,
+            "statefulsets" => ResourceKind::StatefulSets,
+            "jobs" => ResourceKind::Jobs,
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            namespace: namespace.to_owned(),
+            name: name.map(str::to_owned),
+            subresource: subresource.map(str::to_owned),
+        })
+    }
+
+    /// The operation made by a successful request to the target, from the body of its response,
+    /// none for requests that aren't recorded.
+    ///
+    /// THEMELIOS: Writes to the status subresource aren't recorded, statuses being left to the
+    /// controllers in the model.
+    pub fn operation(
+        &self,
+        method: &Method,
+        body: &[u8],
+    ) -> serde_json::Result<Option<ClientOperation>> {
+        let operation = match (method, &self.name, self.subresource.as_deref()) {
+            (&Method::POST, None, None) => {
+                ClientOperation::Create(RecordedResource::parse(self.kind, body)?)
+            }
+            (&Method::PUT | &Method::PATCH, Some(_), None) => {
+                ClientOperation::Update(RecordedResource::parse(self.kind, body)?)
+            }
+            (&Method::PUT | &Method::PATCH, Some(name), Some("scale")) => {
+                let scale: Scale = serde_json::from_slice(body)?;
+                ClientOperation::Scale {
+                    kind: self.kind,
+                    namespace: self.namespace.clone(),
+                    name: name.clone(),
+                    replicas: scale.spec.replicas,
+                }
+            }
+            (&Method::DELETE, Some(name), None) => ClientOperation::Delete {
+                kind: self.kind,
+                namespace: self.namespace.clone(),
+                name: name.clone(),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(operation))
+    }
+}
+
+/// Record the writes to the api that succeed, once they have been handled.
+pub async fn record(
+    Extension(recorder): Extension<Recorder>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let write = matches!(
+        method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let target = Target::parse(request.uri().path());
+    let Some(target) = target.filter(|_| write && recorder.enabled()) else {
+        return next.run(request).await;
+    };
+
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let Ok(bytes) = body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    match target.operation(&method, &bytes) {
+        Ok(Some(operation)) => {
+            if let Err(error) = recorder.record(&operation) {
+                warn!(%error, "Failed to record operation");
+            }
+        }
+        Ok(None) => {}
+        Err(error) => warn!(%error, ?target, "Failed to parse response to record"),
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
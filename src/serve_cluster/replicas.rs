@@ -0,0 +1,170 @@
+//! Replicas of the API server that the cluster is served through, each on its own listener, whose
+//! reads are as stale as the consistency level being checked allows so that clients of the served
+//! cluster see what the controllers in the model do.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Query, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Extension;
+
+use crate::state::history::{ConsistencySetup, SessionGuarantee};
+use crate::state::revision::Revision;
+
+use super::watch::{ListParams, Watches};
+use super::AppState;
+
+/// How often the watch cache of the first replica catches up with the store, the others taking
+/// proportionally longer.
+pub const REPLICA_SYNC_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How the replicas serve reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicaReads {
+    /// Reads go through to the store, as quorum reads do.
+    Linearizable,
+    /// Reads come from the watch cache of the replica, which only catches up with the store
+    /// periodically, with the guarantees of the session.
+    Cached(SessionGuarantee),
+}
+
+impl ReplicaReads {
+    /// The reads of replicas under the consistency level.
+    ///
+    /// THEMELIOS: Writes stay linearizable through the single store that the replicas share, so
+    /// the levels with weaker writes only get the stale reads of sessions and a partitioned store
+    /// is left to the model. The watch cache of a replica never goes back, so its reads are
+    /// monotonic even without sessions.
+    pub fn new(consistency_level: &ConsistencySetup) -> Self {
+        match consistency_level {
+            ConsistencySetup::Synchronous | ConsistencySetup::Partitioned(_) => Self::Linearizable,
+            ConsistencySetup::ReadYourWrites => Self::Cached(SessionGuarantee::ReadYourWrites),
+            ConsistencySetup::MonotonicSession
+            | ConsistencySetup::ResettableSession
+            | ConsistencySetup::OptimisticLinear
+            | ConsistencySetup::Causal
+            | ConsistencySetup::Eventual(_) => Self::Cached(SessionGuarantee::MonotonicReads),
+        }
+    }
+}
+
+/// The revisions that the watch caches of the replicas are at, shared by their listeners.
+#[derive(Debug, Clone)]
+pub struct Replicas {
+    reads: ReplicaReads,
+    caches: Arc<Mutex<Vec<Revision>>>,
+}
+
+impl Replicas {
+    pub fn new(replicas: usize, reads: ReplicaReads) -> Self {
+        Self {
+            reads,
+            caches: Arc::new(Mutex::new(vec![Revision::default(); replicas])),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.caches.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reads(&self) -> ReplicaReads {
+        self.reads
+    }
+
+    /// The revision that the watch cache of the replica is at.
+    pub fn revision(&self, replica: usize) -> Revision {
+        self.caches.lock().unwrap()[replica].clone()
+    }
+
+    /// Bring the watch cache of the replica up to the revision, never moving it back.
+    pub fn sync(&self, replica: usize, revision: Revision) {
+        let mut caches = self.caches.lock().unwrap();
+        if caches[replica] < revision {
+            caches[replica] = revision;
+        }
+    }
+
+    /// Record a write through the replica that the store applied at the revision, its watch cache
+    /// catching up to include it when reads are read-your-writes.
+    pub fn record_write(&self, replica: usize, revision: Revision) {
+        if self.reads == ReplicaReads::Cached(SessionGuarantee::ReadYourWrites) {
+            self.sync(replica, revision);
+        }
+    }
+}
+
+/// The replica that a listener serves.
+#[derive(Debug, Clone)]
+pub struct Replica {
+    pub index: usize,
+    pub replicas: Replicas,
+}
+
+/// Bring the watch caches of the replicas up to the latest state kept for watches, the replica
+/// at index `i` every `i + 1` intervals, until shut down.
+pub async fn sync_caches(replicas: Replicas, watches: Watches, shutdown: Arc<AtomicBool>) {
+    let mut ticks = 0usize;
+    loop {
+        if shutdown.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Some(latest) = watches.behind(0) {
+            for replica in 0..replicas.len() {
+                if ticks % (replica + 1) == 0 {
+                    replicas.sync(replica, latest.revision.clone());
+                }
+            }
+        }
+        ticks = ticks.wrapping_add(1);
+        tokio::time::sleep(REPLICA_SYNC_INTERVAL).await;
+    }
+}
+
+/// Serve reads through the replica from its watch cache, and catch it up with the writes through
+/// it as its reads require.
+///
+/// THEMELIOS: Watches always start from the store, only lists and gets being served from the
+/// cache. The controllers of the served cluster act on the store directly.
+pub async fn replica_reads(
+    State(state): State<AppState>,
+    Extension(replica): Extension<Replica>,
+    Extension(watches): Extension<Watches>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let write = matches!(
+        method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    if write {
+        let response = next.run(request).await;
+        if response.status().is_success() {
+            let s = state.lock().await;
+            // keep the written state so that the cache can serve it
+            watches.record(&s);
+            replica
+                .replicas
+                .record_write(replica.index, s.revision.clone());
+        }
+        return response;
+    }
+
+    let watching = Query::<ListParams>::try_from_uri(request.uri())
+        .map_or(false, |Query(params)| params.watch);
+    if replica.replicas.reads() == ReplicaReads::Linearizable || method != Method::GET || watching {
+        return next.run(request).await;
+    }
+    match watches.at(&replica.replicas.revision(replica.index)) {
+        Some(view) => super::serve_view(view, watches, request).await,
+        None => next.run(request).await,
+    }
+}
@@ -0,0 +1,268 @@
+//! Watches of the resources served by the cluster, streaming the changes to them as they happen
+//! so that controllers and `kubectl get --watch` can follow the cluster.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ListMeta, Status};
+use k8s_openapi::Resource;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api::selector::{FieldSelectable, ListQuery, SelectorError};
+use crate::api::SerializableResource;
+use crate::resources::Meta;
+use crate::state::revision::Revision;
+use crate::state::StateView;
+
+use super::AppState;
+
+/// How many past states are kept to start watches from, older resource versions are gone.
+pub const WATCH_HISTORY: usize = 100;
+
+/// How often watches and the history check for changes to the cluster.
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The query parameters of list requests, selecting the resources and whether to watch them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListParams {
+    pub label_selector: Option<String>,
+    pub field_selector: Option<String>,
+    #[serde(default)]
+    pub watch: bool,
+    pub resource_version: Option<String>,
+    #[serde(default)]
+    pub allow_watch_bookmarks: bool,
+    pub timeout_seconds: Option<u64>,
+}
+
+impl ListParams {
+    /// The selectors of the request, for resources of the kind listed.
+    pub fn query<T: FieldSelectable>(&self) -> Result<ListQuery, SelectorError> {
+        ListQuery::new::<T>(
+            self.label_selector.as_deref(),
+            self.field_selector.as_deref(),
+        )
+    }
+}
+
+/// The recent states of the cluster that watches can start from, shared by the requests.
+#[derive(Debug, Clone, Default)]
+pub struct Watches {
+    history: Arc<std::sync::Mutex<VecDeque<StateView>>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Watches {
+    /// Watches that finish once the server is shut down.
+    pub fn new(shutdown: Arc<AtomicBool>) -> Self {
+        Self {
+            history: Default::default(),
+            shutdown,
+        }
+    }
+
+    /// Keep the state in the history if it has changed since the last one kept.
+    pub fn record(&self, view: &StateView) {
+        let mut history = self.history.lock().unwrap();
+        if history
+            .back()
+            .map_or(false, |last| last.revision == view.revision)
+        {
+            return;
+        }
+        history.push_back(view.clone());
+        while history.len() > WATCH_HISTORY {
+            history.pop_front();
+        }
+    }
+
+    /// The latest state kept at or before the revision, none if it is older than any kept.
+    pub fn at(&self, revision: &Revision) -> Option<StateView> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|view| &view.revision <= revision)
+            .cloned()
+    }
+
+    /// The state kept the given number of states before the latest, or the oldest one kept.
+    pub fn behind(&self, states: usize) -> Option<StateView> {
+        let history = self.history.lock().unwrap();
+        history
+            .iter()
+            .rev()
+            .nth(states)
+            .or_else(|| history.front())
+            .cloned()
+    }
+}
+
+/// Keep the states of the cluster in the history as it changes, until shut down.
+pub async fn record_history(state: AppState, watches: Watches) {
+    loop {
+        if watches.shutdown.load(Ordering::Relaxed) {
+            break;
+        }
+        watches.record(&*state.lock().await);
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+/// The type of a watch event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl EventType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EventType::Added => "ADDED",
+            EventType::Modified => "MODIFIED",
+            EventType::Deleted => "DELETED",
+        }
+    }
+}
+
+/// The events that take the resources from before to after, by namespace and name.
+///
+/// A resource that is replaced by another of the same name is deleted and then added again.
+pub fn events<T: Meta + Clone>(before: &[T], after: &[T]) -> Vec<(EventType, T)> {
+    let before = before
+        .iter()
+        .map(|r| ((&r.metadata().namespace, &r.metadata().name), r))
+        .collect::<BTreeMap<_, _>>();
+    let after = after
+        .iter()
+        .map(|r| ((&r.metadata().namespace, &r.metadata().name), r))
+        .collect::<BTreeMap<_, _>>();
+    let mut events = Vec::new();
+    for (key, old) in &before {
+        match after.get(key) {
+            Some(new) if new.metadata().uid == old.metadata().uid => {}
+            _ => events.push((EventType::Deleted, (*old).clone())),
+        }
+    }
+    for (key, new) in &after {
+        match before.get(key) {
+            Some(old) if old.metadata().uid == new.metadata().uid => {
+                if old.metadata().resource_version != new.metadata().resource_version {
+                    events.push((EventType::Modified, (*new).clone()));
+                }
+            }
+            _ => events.push((EventType::Added, (*new).clone())),
+        }
+    }
+    events
+}
+
+/// Stream the changes to the resources picked out of the state, as a response to a list request
+/// with `watch=true`.
+///
+/// Watches without a resource version, or with `0`, start with every current resource being
+/// added. Those with a resource version older than the history respond with `410 Gone`.
+///
+/// THEMELIOS: Changes are found by comparing states as the watch notices them, so several changes
+/// to a resource between them are seen as one and bookmarks are sent whenever the cluster changed
+/// without any changes to the watched resources.
+pub async fn watch<T, F>(state: AppState, watches: Watches, params: ListParams, pick: F) -> Response
+where
+    T: Resource + Meta + Clone + serde::Serialize + Send + 'static,
+    F: Fn(&StateView) -> Vec<T> + Send + Sync + 'static,
+{
+    watches.record(&*state.lock().await);
+    let start = match params.resource_version.as_deref() {
+        None | Some("") | Some("0") => StateView::default(),
+        Some(version) => {
+            let Ok(revision) = Revision::try_from(version) else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+            match watches.at(&revision) {
+                Some(view) => view,
+                None => return gone(version).into_response(),
+            }
+        }
+    };
+    let timeout = params.timeout_seconds.map(Duration::from_secs);
+    let started = tokio::time::Instant::now();
+    let bookmarks = params.allow_watch_bookmarks;
+    let pick = Arc::new(pick);
+
+    let seen = (pick(&start), start.revision);
+    let stream = futures::stream::unfold(seen, move |(mut last, mut revision)| {
+        let state = state.clone();
+        let watches = watches.clone();
+        let pick = Arc::clone(&pick);
+        async move {
+            loop {
+                if watches.shutdown.load(Ordering::Relaxed)
+                    || timeout.map_or(false, |t| started.elapsed() >= t)
+                {
+                    return None;
+                }
+                let s = state.lock().await;
+                if s.revision != revision {
+                    let current = pick(&s);
+                    let mut chunk = String::new();
+                    for (event_type, object) in events(&last, &current) {
+                        let event = json!({
+                            "type": event_type.as_str(),
+                            "object": SerializableResource::new(object),
+                        });
+                        chunk.push_str(&format!("{event}\n"));
+                    }
+                    if chunk.is_empty() && bookmarks {
+                        let event = json!({
+                            "type": "BOOKMARK",
+                            "object": {
+                                "apiVersion": T::API_VERSION,
+                                "kind": T::KIND,
+                                "metadata": {"resourceVersion": s.revision.to_string()},
+                            },
+                        });
+                        chunk.push_str(&format!("{event}\n"));
+                    }
+                    last = current;
+                    revision = s.revision.clone();
+                    if !chunk.is_empty() {
+                        return Some((Ok::<_, Infallible>(chunk), (last, revision)));
+                    }
+                }
+                drop(s);
+                tokio::time::sleep(WATCH_INTERVAL).await;
+            }
+        }
+    });
+    Response::builder()
+        .header("content-type", "application/json")
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+/// The response to watching from a resource version that is no longer kept.
+fn gone(version: &str) -> (StatusCode, Json<Status>) {
+    (
+        StatusCode::GONE,
+        Json(Status {
+            code: Some(410),
+            details: None,
+            message: Some(format!("too old resource version: {version}")),
+            metadata: ListMeta::default(),
+            reason: Some("Expired".to_owned()),
+            status: Some("Failure".to_owned()),
+        }),
+    )
+}
@@ -6,6 +6,7 @@ use serde_json::json;
 use tracing::debug;
 
 use crate::abstract_model::ControllerAction;
+use crate::api::patch::Patch;
 use crate::controller::deployment::DeploymentControllerAction;
 use crate::controller::job::{JobController, JobControllerAction, JobControllerState};
 use crate::controller::replicaset::ReplicaSetControllerAction;
@@ -58,13 +59,28 @@ struct DeploymentRequest {
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "action", rename_all = "camelCase")]
 enum DeploymentResponse {
-    UpdateDeployment { deployment: Deployment },
-    RequeueDeployment { deployment: Deployment },
-    UpdateDeploymentStatus { deployment: Deployment },
-    CreateReplicaSet { replicaset: ReplicaSet },
-    UpdateReplicaSet { replicaset: ReplicaSet },
-    DeleteReplicaSet { replicaset: ReplicaSet },
-    UpdateReplicaSets { replicasets: Vec<ReplicaSet> },
+    UpdateDeployment {
+        deployment: Deployment,
+    },
+    RequeueDeployment {
+        deployment: Deployment,
+        after: Option<u64>,
+    },
+    UpdateDeploymentStatus {
+        deployment: Deployment,
+    },
+    CreateReplicaSet {
+        replicaset: ReplicaSet,
+    },
+    UpdateReplicaSet {
+        replicaset: ReplicaSet,
+    },
+    DeleteReplicaSet {
+        replicaset: ReplicaSet,
+    },
+    UpdateReplicaSets {
+        replicasets: Vec<ReplicaSet>,
+    },
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -139,10 +155,20 @@ struct JobRequest {
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "action", rename_all = "camelCase")]
 enum JobResponse {
-    UpdateJobStatus { job: Job },
-    CreatePod { pod: Pod },
-    UpdatePod { pod: Pod },
-    DeletePod { pod: Pod },
+    UpdateJobStatus {
+        job: Job,
+    },
+    CreatePod {
+        pod: Pod,
+    },
+    PatchPod {
+        namespace: String,
+        name: String,
+        patch: Patch,
+    },
+    DeletePod {
+        pod: Pod,
+    },
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -187,7 +213,7 @@ impl IntoResponse for ErrorResponse {
 async fn scheduler(
     Json(payload): Json<SchedulerRequest>,
 ) -> Result<Json<SchedulerResponse>, ErrorResponse> {
-    let s = SchedulerController;
+    let s = SchedulerController::default();
     debug!("Got scheduler request");
     let mut pods = payload.bound_pods;
     pods.push(payload.pod);
@@ -209,6 +235,7 @@ async fn scheduler(
                 node_name: pod.spec.node_name.unwrap(),
             }))
         }
+        Some(op) => Err(ErrorResponse::InvalidOperationReturned(op.into())),
         None => Err(ErrorResponse::NoOperation),
     }
 }
@@ -236,9 +263,10 @@ async fn deployment(
                 deployment: dep,
             }))
         }
-        Some(DeploymentControllerAction::RequeueDeployment(dep)) => {
+        Some(DeploymentControllerAction::RequeueDeployment(dep, after)) => {
             Ok(Json(DeploymentResponse::RequeueDeployment {
                 deployment: dep,
+                after,
             }))
         }
         Some(DeploymentControllerAction::UpdateDeploymentStatus(dep)) => {
@@ -396,7 +424,13 @@ async fn job(Json(payload): Json<JobRequest>) -> Result<Json<JobResponse>, Error
             Ok(Json(JobResponse::UpdateJobStatus { job }))
         }
         Some(JobControllerAction::CreatePod(pod)) => Ok(Json(JobResponse::CreatePod { pod })),
-        Some(JobControllerAction::UpdatePod(pod)) => Ok(Json(JobResponse::UpdatePod { pod })),
+        Some(JobControllerAction::PatchPod(namespace, name, patch)) => {
+            Ok(Json(JobResponse::PatchPod {
+                namespace,
+                name,
+                patch,
+            }))
+        }
         Some(JobControllerAction::DeletePod(pod)) => Ok(Json(JobResponse::DeletePod { pod })),
         None => Err(ErrorResponse::NoOperation),
     }
@@ -0,0 +1,194 @@
+//! Shrinking the paths to discoveries, removing and reordering their actions while the property
+//! is still discovered, so that what went wrong isn't buried among steps that didn't matter.
+
+use stateright::{Expectation, Model};
+
+use crate::abstract_model::{AbstractModel, Action};
+use crate::state::revision::Revision;
+use crate::state::State;
+use crate::trace::{parse_path, replay_actions, ReplayError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShrinkError {
+    /// The model has no property with the given name.
+    UnknownProperty(String),
+    /// Eventually properties are discovered along whole paths rather than at a single state.
+    EventuallyProperty,
+    /// The path couldn't be replayed through the model.
+    Replay(ReplayError),
+    /// The property isn't discovered along the path.
+    NotDiscovered,
+}
+
+impl From<ReplayError> for ShrinkError {
+    fn from(error: ReplayError) -> Self {
+        ShrinkError::Replay(error)
+    }
+}
+
+/// A shorter path to a discovery.
+#[derive(Debug, Clone)]
+pub struct Shrunk {
+    /// The number of actions along the original path.
+    pub original_len: usize,
+    /// The actions taken along the shrunk path.
+    pub actions: Vec<Action>,
+    /// The fingerprints of the states along the shrunk path, starting with the initial state.
+    pub fingerprints: Vec<u64>,
+}
+
+impl Shrunk {
+    /// The shrunk path, encoded as for `tui`.
+    pub fn encoded_path(&self) -> String {
+        self.fingerprints
+            .iter()
+            .map(|fp| fp.to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Shrink the path to the discovery of the property, returning the shortest path found that still
+/// discovers it.
+///
+/// Runs of actions are removed, halving the length of the runs down to single actions, then
+/// neighbouring actions are swapped, keeping any change that reaches the discovery sooner, until
+/// neither finds a shorter path.
+pub fn shrink(
+    model: &AbstractModel,
+    property: &str,
+    encoded_path: &str,
+) -> Result<Shrunk, ShrinkError> {
+    let property = model
+        .properties()
+        .into_iter()
+        .find(|p| p.name == property)
+        .ok_or_else(|| ShrinkError::UnknownProperty(property.to_owned()))?;
+    if matches!(property.expectation, Expectation::Eventually) {
+        return Err(ShrinkError::EventuallyProperty);
+    }
+    let discovered = |state: &State| match property.expectation {
+        Expectation::Always => !(property.condition)(model, state),
+        Expectation::Sometimes => (property.condition)(model, state),
+        Expectation::Eventually => false,
+    };
+
+    let (initial, actions) = replay_actions(model, &parse_path(encoded_path)?)?;
+    let original_len = actions.len();
+    let run = |actions: &[Action]| run_until(model, &initial, actions, &discovered);
+    let (mut actions, mut states) = run(&actions).ok_or(ShrinkError::NotDiscovered)?;
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        let mut chunk = actions.len() / 2;
+        while chunk > 0 {
+            let mut i = 0;
+            while i < actions.len() {
+                let mut candidate = actions.clone();
+                candidate.drain(i..(i + chunk).min(actions.len()));
+                match run(&candidate) {
+                    Some((shorter, shorter_states)) if shorter.len() < actions.len() => {
+                        actions = shorter;
+                        states = shorter_states;
+                        improved = true;
+                    }
+                    _ => i += chunk,
+                }
+            }
+            chunk /= 2;
+        }
+
+        for i in 1..actions.len() {
+            let mut candidate = actions.clone();
+            candidate.swap(i - 1, i);
+            if let Some((shorter, shorter_states)) = run(&candidate) {
+                if shorter.len() < actions.len() {
+                    actions = shorter;
+                    states = shorter_states;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(Shrunk {
+        original_len,
+        actions,
+        fingerprints: states
+            .iter()
+            .map(|s| stateright::fingerprint(s).get())
+            .collect(),
+    })
+}
+
+/// Take the actions from the initial state until one reaches a state where the property is
+/// discovered, returning the actions taken and the states along the way.
+///
+/// Actions that are no longer enabled are replaced by an enabled one of the same kind, if any.
+fn run_until(
+    model: &AbstractModel,
+    initial: &State,
+    actions: &[Action],
+    discovered: &impl Fn(&State) -> bool,
+) -> Option<(Vec<Action>, Vec<State>)> {
+    let mut states = vec![initial.clone()];
+    let mut taken = Vec::new();
+    if discovered(initial) {
+        return Some((taken, states));
+    }
+    for action in actions {
+        let state = states.last().unwrap();
+        let mut enabled = Vec::new();
+        model.actions(state, &mut enabled);
+        let Some(action) = substitute(action, enabled) else {
+            continue;
+        };
+        let Some(next) = model.next_state(state, action.clone()) else {
+            continue;
+        };
+        if !model.within_boundary(&next) {
+            return None;
+        }
+        let found = discovered(&next);
+        taken.push(action);
+        states.push(next);
+        if found {
+            return Some((taken, states));
+        }
+    }
+    None
+}
+
+/// The action if it is enabled, otherwise an enabled one that does the same but from another
+/// revision.
+///
+/// THEMELIOS: Removing earlier actions changes the revisions that controllers can read from, so
+/// the latest revision is taken in place of one that no longer exists.
+fn substitute(action: &Action, enabled: Vec<Action>) -> Option<Action> {
+    if enabled.contains(action) {
+        return Some(action.clone());
+    }
+    enabled
+        .into_iter()
+        .filter(|a| match (action, a) {
+            (Action::ControllerStep(_, i), Action::ControllerStep(_, j))
+            | (Action::ControllerResync(_, i), Action::ControllerResync(_, j)) => i == j,
+            (Action::ControllerRelist(_, i, k), Action::ControllerRelist(_, j, l)) => {
+                i == j && k == l
+            }
+            _ => false,
+        })
+        .max_by(|a, b| revision(a).cmp(&revision(b)))
+}
+
+fn revision(action: &Action) -> Option<&Revision> {
+    match action {
+        Action::ControllerStep(r, _)
+        | Action::ControllerResync(r, _)
+        | Action::ControllerRelist(r, _, _) => Some(r),
+        _ => None,
+    }
+}
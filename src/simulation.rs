@@ -0,0 +1,292 @@
+//! Simulations of a model with many seeds at once.
+//!
+//! A swarm goes further, varying the model that each seed simulates as well, so that the seeds
+//! spread out over more of the state space than they would with the same setup.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use stateright::report::{DiscoveryClassification, ReportData, ReportDiscovery, Reporter};
+use stateright::{Checker, Chooser, Expectation, Model, UniformChooser};
+
+use crate::abstract_model::{AbstractModel, ActionClass, ActionKind};
+use crate::report::{SeedStats, SeedTracker};
+
+/// Limits on the simulation run with each seed.
+#[derive(Debug, Clone, Copy)]
+pub struct SeedLimits {
+    /// The number of states to visit, 0 is no limit.
+    pub states: usize,
+    /// The depth to simulate paths to, 0 is no limit.
+    pub max_depth: usize,
+}
+
+/// Simulate the model with each of the seeds, running a seed on each of the threads at a time,
+/// and report the discoveries that any of them found.
+///
+/// Each seed runs until it hits its limits or has a discovery for every property, so seeds
+/// without limits only finish once they have found them all.
+///
+/// When more than one seed finds a discovery for a property the one from the lowest seed is
+/// reported, so that the results don't depend on how the seeds were scheduled.
+pub fn simulate_seeds(
+    model: &AbstractModel,
+    seeds: Range<u64>,
+    threads: usize,
+    limits: SeedLimits,
+    tracker: &SeedTracker,
+    reporter: &mut (dyn Reporter<AbstractModel> + Send),
+) -> BTreeMap<&'static str, ReportDiscovery<AbstractModel>> {
+    simulate_each(
+        model,
+        seeds,
+        threads,
+        |_| (model.clone(), limits),
+        tracker,
+        reporter,
+    )
+}
+
+/// Simulate a swarm of variations of the model, one for each seed, and report the discoveries that
+/// any of them found, as for [`simulate_seeds`].
+///
+/// Each member of the swarm leaves out a random set of the kinds of actions, on top of those the
+/// model already does, and simulates to a random depth, both chosen from its seed.
+/// Leaving actions out only narrows the paths that a member can take, so its discoveries are paths
+/// of the full model too.
+///
+/// The depth of each member is up to the maximum depth of the limits, or one of
+/// [`SWARM_DEPTHS`] when that is 0.
+pub fn simulate_swarm(
+    model: &AbstractModel,
+    seeds: Range<u64>,
+    threads: usize,
+    limits: SeedLimits,
+    tracker: &SeedTracker,
+    reporter: &mut (dyn Reporter<AbstractModel> + Send),
+) -> BTreeMap<&'static str, ReportDiscovery<AbstractModel>> {
+    simulate_each(
+        model,
+        seeds,
+        threads,
+        |seed| swarm_member(model, seed, limits),
+        tracker,
+        reporter,
+    )
+}
+
+/// The depths that swarm members choose between when they aren't given a maximum, 0 being no
+/// limit.
+pub const SWARM_DEPTHS: [usize; 4] = [25, 50, 100, 0];
+
+/// The variation of the model, and the limits, that the swarm member with the seed simulates.
+pub fn swarm_member(
+    model: &AbstractModel,
+    seed: u64,
+    limits: SeedLimits,
+) -> (AbstractModel, SeedLimits) {
+    let mut bits = mix(seed);
+    let mut member = model.clone();
+    for kind in ActionKind::ALL {
+        // controllers always get to step, otherwise little happens at all
+        if kind != ActionKind::ControllerStep && bits & 1 == 1 {
+            member.disabled_actions.insert(kind);
+        }
+        bits >>= 1;
+    }
+    let max_depth = if limits.max_depth == 0 {
+        SWARM_DEPTHS[bits as usize % SWARM_DEPTHS.len()]
+    } else {
+        // at least half the depth so that members still get somewhere
+        let min_depth = limits.max_depth.div_ceil(2);
+        min_depth + bits as usize % (limits.max_depth - min_depth + 1)
+    };
+    (
+        member,
+        SeedLimits {
+            states: limits.states,
+            max_depth,
+        },
+    )
+}
+
+/// The finalizer of splitmix64, spreading consecutive seeds over different variations.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Chooses actions with a chance in proportion to the weight of their class, so that a
+/// simulation can spend more of its steps on, say, faults than it would choosing uniformly.
+///
+/// Classes without a weight have a weight of 1, and a weight of 0 leaves the class out unless
+/// nothing else can be chosen.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedChooser {
+    weights: BTreeMap<ActionClass, u32>,
+}
+
+impl WeightedChooser {
+    pub fn new(weights: BTreeMap<ActionClass, u32>) -> Self {
+        Self { weights }
+    }
+
+    /// The weight of actions of the class.
+    pub fn weight(&self, class: ActionClass) -> u32 {
+        self.weights.get(&class).copied().unwrap_or(1)
+    }
+}
+
+impl Chooser<AbstractModel> for WeightedChooser {
+    /// The splitmix64 state of the run.
+    type State = u64;
+
+    fn new_state(&self, seed: u64) -> Self::State {
+        seed
+    }
+
+    fn choose_initial_state(
+        &self,
+        state: &mut Self::State,
+        initial_states: &[<AbstractModel as Model>::State],
+    ) -> usize {
+        (next_random(state) % initial_states.len() as u64) as usize
+    }
+
+    fn choose_action(
+        &self,
+        state: &mut Self::State,
+        _current_state: &<AbstractModel as Model>::State,
+        actions: &[<AbstractModel as Model>::Action],
+    ) -> usize {
+        let weights = actions
+            .iter()
+            .map(|a| u64::from(self.weight(a.kind().class())))
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<u64>();
+        if total == 0 {
+            return (next_random(state) % actions.len() as u64) as usize;
+        }
+        let mut target = next_random(state) % total;
+        for (i, weight) in weights.into_iter().enumerate() {
+            if target < weight {
+                return i;
+            }
+            target -= weight;
+        }
+        unreachable!("target is below the total weight")
+    }
+}
+
+/// The next number from the splitmix64 generator with the state.
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    mix(*state)
+}
+
+/// Simulate the model that each seed is given, with its limits.
+fn simulate_each(
+    model: &AbstractModel,
+    seeds: Range<u64>,
+    threads: usize,
+    setup: impl Fn(u64) -> (AbstractModel, SeedLimits) + Sync,
+    tracker: &SeedTracker,
+    reporter: &mut (dyn Reporter<AbstractModel> + Send),
+) -> BTreeMap<&'static str, ReportDiscovery<AbstractModel>> {
+    let start = Instant::now();
+    let expectations = model
+        .properties()
+        .into_iter()
+        .map(|p| (p.name, p.expectation))
+        .collect::<BTreeMap<_, _>>();
+    let next_seed = AtomicU64::new(seeds.start);
+    let discoveries = Mutex::new(BTreeMap::new());
+    let reporter = Mutex::new(reporter);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let seed = next_seed.fetch_add(1, Ordering::Relaxed);
+                if seed >= seeds.end {
+                    break;
+                }
+                let seed_start = Instant::now();
+                let (model, limits) = setup(seed);
+                let checker = model
+                    .checker()
+                    .threads(1)
+                    .target_state_count(limits.states)
+                    .target_max_depth(limits.max_depth)
+                    .spawn_simulation(seed, UniformChooser)
+                    .join();
+                let found = checker.discoveries();
+                let mut discovered = found.keys().copied().collect::<Vec<_>>();
+                discovered.sort();
+                tracker.add(SeedStats {
+                    seed,
+                    total_states: checker.state_count(),
+                    unique_states: checker.unique_state_count(),
+                    max_depth: checker.max_depth(),
+                    duration: seed_start.elapsed(),
+                    discoveries: discovered,
+                });
+                {
+                    let mut discoveries = discoveries.lock().unwrap();
+                    for (name, path) in found {
+                        match discoveries.get(name) {
+                            Some((earlier, _)) if *earlier < seed => {}
+                            _ => {
+                                discoveries.insert(name, (seed, path));
+                            }
+                        }
+                    }
+                }
+                reporter
+                    .lock()
+                    .unwrap()
+                    .report_checking(report_data(tracker, start, false));
+            });
+        }
+    });
+    let mut reporter = reporter.into_inner().unwrap();
+    reporter.report_checking(report_data(tracker, start, true));
+    let discoveries = discoveries
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|(name, (_, path))| {
+            let classification = match expectations[&name] {
+                Expectation::Sometimes => DiscoveryClassification::Example,
+                Expectation::Always | Expectation::Eventually => {
+                    DiscoveryClassification::Counterexample
+                }
+            };
+            (
+                name,
+                ReportDiscovery {
+                    path,
+                    classification,
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    reporter.report_discoveries(discoveries.clone());
+    discoveries
+}
+
+/// The totals over the seeds that have finished so far.
+///
+/// THEMELIOS: Seeds can visit the same states, so unique states are only unique within each seed.
+fn report_data(tracker: &SeedTracker, start: Instant, done: bool) -> ReportData {
+    let seeds = tracker.seeds();
+    ReportData {
+        total_states: seeds.iter().map(|s| s.total_states).sum(),
+        unique_states: seeds.iter().map(|s| s.unique_states).sum(),
+        max_depth: seeds.iter().map(|s| s.max_depth).max().unwrap_or_default(),
+        duration: start.elapsed(),
+        done,
+    }
+}
@@ -0,0 +1,286 @@
+//! Snapshots of a real cluster as the initial state of the model, so that the checker explores what
+//! could happen next from a production state.
+//!
+//! A snapshot is read from the output of `kubectl get -o json`, as single resources, lists of them
+//! or several such documents one after the other, from a directory of the manifests that would be
+//! given to `kubectl apply -f`, or straight from the cluster of the kubeconfig.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use k8s_openapi::api::{
+    apps::v1 as apps, autoscaling::v2 as autoscaling, batch::v1 as batch,
+    coordination::v1 as coordination, core::v1 as core, rbac::v1 as rbac,
+    scheduling::v1 as scheduling, storage::v1 as storage,
+};
+use kube::api::ListParams;
+use kube::{Api, Client};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::resources::{Meta, Spec, Time};
+use crate::state::resources::Resources;
+use crate::state::revision::Revision;
+use crate::state::RawState;
+use crate::utils;
+
+/// The state taken from a snapshot, along with what of it the model couldn't take.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub state: RawState,
+    /// The number of resources of each kind that the model doesn't have, which were left out.
+    pub skipped: BTreeMap<String, usize>,
+    /// Problems with resources of kinds that the model has, which were left out or only taken in
+    /// part.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot couldn't be read.
+    Io(String),
+    /// The snapshot wasn't JSON.
+    Json(String),
+    /// A manifest wasn't YAML.
+    Yaml(String),
+    /// The cluster couldn't be listed.
+    Cluster(String),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "failed to read snapshot: {error}"),
+            SnapshotError::Json(error) => write!(f, "invalid snapshot: {error}"),
+            SnapshotError::Yaml(error) => write!(f, "invalid manifest: {error}"),
+            SnapshotError::Cluster(error) => write!(f, "failed to list cluster: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Add the resource in the value to the resources, as the model's type of it.
+///
+/// Fields that the model doesn't have are ignored, and when the status can't be read, such as when
+/// it has a condition that the model doesn't know of or is missing, the resource is taken with an
+/// empty one for the controllers to fill in again.
+fn add<T>(resources: &mut Resources<T>, mut value: Value, warnings: &mut Vec<String>)
+where
+    T: Meta + Spec + Clone + DeserializeOwned,
+{
+    let name = format!(
+        "{} {}/{}",
+        value["kind"].as_str().unwrap_or_default(),
+        value["metadata"]["namespace"].as_str().unwrap_or_default(),
+        value["metadata"]["name"].as_str().unwrap_or_default()
+    );
+    // the model gives resources its own versions
+    if let Some(metadata) = value["metadata"].as_object_mut() {
+        metadata.remove("resourceVersion");
+    }
+    let resource = match serde_json::from_value::<T>(value.clone()) {
+        Ok(resource) => resource,
+        Err(error) => {
+            let had_status = value
+                .as_object_mut()
+                .and_then(|v| v.insert("status".to_owned(), Value::Object(Default::default())))
+                .is_some();
+            match serde_json::from_value::<T>(value) {
+                Ok(resource) => {
+                    if had_status {
+                        warnings.push(format!("left out the status of {name}: {error}"));
+                    }
+                    resource
+                }
+                Err(_) => {
+                    warnings.push(format!("left out {name}: {error}"));
+                    return;
+                }
+            }
+        }
+    };
+    if resources.create(resource, Revision::default()).is_err() {
+        warnings.push(format!("left out {name}: it is in the snapshot twice"));
+    }
+}
+
+/// Fill in the fields of the manifest that the api server defaults when it is applied, which
+/// resources from a cluster already have.
+///
+/// THEMELIOS: The api server selects the pods of a job by the uid of the job, which it doesn't have
+/// until the model creates it, so the name of the job stands in for it.
+fn default_manifest(value: &mut Value) {
+    if value["kind"] == "Job"
+        && value["spec"]["selector"].is_null()
+        && value["spec"]["template"].is_object()
+    {
+        let name = value["metadata"]["name"].clone();
+        value["spec"]["template"]["metadata"]["labels"]["job-name"] = name.clone();
+        value["spec"]["selector"] = serde_json::json!({ "matchLabels": { "job-name": name } });
+    }
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| SnapshotError::Io(e.to_string()))?;
+        Self::parse(&contents)
+    }
+
+    /// Parse a snapshot from the JSON documents, one after the other.
+    ///
+    /// The clock of the state starts at the latest creation time of any resource in it, the time
+    /// that the snapshot was taken at not being in it.
+    pub fn parse(contents: &str) -> Result<Self, SnapshotError> {
+        let values = serde_json::Deserializer::from_str(contents)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SnapshotError::Json(e.to_string()))?;
+        Ok(Self::from_values(values))
+    }
+
+    /// Load the manifests in the directory, the YAML or JSON files directly in it being read in
+    /// order of their names, each with any number of documents.
+    pub fn load_manifests(dir: &Path) -> Result<Self, SnapshotError> {
+        let mut paths = std::fs::read_dir(dir)
+            .map_err(|e| SnapshotError::Io(e.to_string()))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SnapshotError::Io(e.to_string()))?;
+        paths.retain(|p| {
+            p.is_file()
+                && p.extension()
+                    .map_or(false, |e| e == "yaml" || e == "yml" || e == "json")
+        });
+        paths.sort();
+        let mut values = Vec::new();
+        for path in paths {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| SnapshotError::Io(format!("{}: {e}", path.display())))?;
+            values.extend(
+                Self::parse_manifests(&contents)
+                    .map_err(|e| SnapshotError::Yaml(format!("{}: {e}", path.display())))?,
+            );
+        }
+        Ok(Self::from_values(values))
+    }
+
+    /// The resources in the YAML documents, leaving out empty ones.
+    fn parse_manifests(contents: &str) -> Result<Vec<Value>, serde_yaml::Error> {
+        let mut values = Vec::new();
+        for document in serde_yaml::Deserializer::from_str(contents) {
+            let mut value = Value::deserialize(document)?;
+            if !value.is_null() {
+                default_manifest(&mut value);
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// A snapshot of the resources, or lists of them, as their JSON values.
+    pub fn from_values(values: impl IntoIterator<Item = Value>) -> Self {
+        let mut snapshot = Self::default();
+        for value in values {
+            snapshot.add(value);
+        }
+        snapshot
+    }
+
+    /// Take a snapshot of the cluster of the kubeconfig, with the clock at the current time.
+    pub async fn from_cluster(client: &Client) -> Result<Self, SnapshotError> {
+        let mut snapshot = Self::default();
+        macro_rules! list {
+            ($($kind:ty),* $(,)?) => {
+                $(
+                    let list = Api::<$kind>::all(client.clone())
+                        .list(&ListParams::default())
+                        .await
+                        .map_err(|e| SnapshotError::Cluster(e.to_string()))?;
+                    for item in list.items {
+                        let value = serde_json::to_value(item)
+                            .map_err(|e| SnapshotError::Json(e.to_string()))?;
+                        snapshot.add(value);
+                    }
+                )*
+            };
+        }
+        list!(
+            core::Node,
+            core::Pod,
+            core::ReplicationController,
+            core::PersistentVolumeClaim,
+            core::PersistentVolume,
+            core::Service,
+            core::Endpoints,
+            core::ConfigMap,
+            core::Secret,
+            core::Namespace,
+            apps::ReplicaSet,
+            apps::Deployment,
+            apps::StatefulSet,
+            apps::ControllerRevision,
+            batch::Job,
+            batch::CronJob,
+            storage::StorageClass,
+            scheduling::PriorityClass,
+            coordination::Lease,
+            autoscaling::HorizontalPodAutoscaler,
+            rbac::Role,
+            rbac::RoleBinding,
+        );
+        snapshot.state.clock = utils::wall_clock();
+        Ok(snapshot)
+    }
+
+    /// Add the resource, or those of a list, to the state.
+    pub fn add(&mut self, value: Value) {
+        let kind = value["kind"].as_str().unwrap_or_default().to_owned();
+        if kind.ends_with("List") {
+            if let Some(Value::Array(items)) = value.get("items") {
+                for item in items.clone() {
+                    self.add(item);
+                }
+            }
+            return;
+        }
+
+        if let Ok(Time(created)) =
+            serde_json::from_value::<Time>(value["metadata"]["creationTimestamp"].clone())
+        {
+            self.state.clock = self.state.clock.max(created.unix_timestamp() as u64);
+        }
+
+        let state = &mut self.state;
+        let warnings = &mut self.warnings;
+        match kind.as_str() {
+            "Node" => add(&mut state.nodes, value, warnings),
+            "Pod" => add(&mut state.pods, value, warnings),
+            "ReplicaSet" => add(&mut state.replicasets, value, warnings),
+            "ReplicationController" => add(&mut state.replication_controllers, value, warnings),
+            "Deployment" => add(&mut state.deployments, value, warnings),
+            "StatefulSet" => add(&mut state.statefulsets, value, warnings),
+            "ControllerRevision" => add(&mut state.controller_revisions, value, warnings),
+            "PersistentVolumeClaim" => add(&mut state.persistent_volume_claims, value, warnings),
+            "PersistentVolume" => add(&mut state.persistent_volumes, value, warnings),
+            "StorageClass" => add(&mut state.storage_classes, value, warnings),
+            "PriorityClass" => add(&mut state.priority_classes, value, warnings),
+            "Lease" => add(&mut state.leases, value, warnings),
+            "Service" => add(&mut state.services, value, warnings),
+            "Endpoints" => add(&mut state.endpoints, value, warnings),
+            "ConfigMap" => add(&mut state.config_maps, value, warnings),
+            "Secret" => add(&mut state.secrets, value, warnings),
+            "Job" => add(&mut state.jobs, value, warnings),
+            "CronJob" => add(&mut state.cronjobs, value, warnings),
+            "HorizontalPodAutoscaler" => {
+                add(&mut state.horizontal_pod_autoscalers, value, warnings)
+            }
+            "Namespace" => add(&mut state.namespaces, value, warnings),
+            "Role" => add(&mut state.roles, value, warnings),
+            "RoleBinding" => add(&mut state.role_bindings, value, warnings),
+            _ => *self.skipped.entry(kind).or_default() += 1,
+        }
+    }
+}
@@ -1,21 +1,32 @@
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 use crate::controller::ControllerStates;
+use crate::events::{EventLog, EventRecording};
 use crate::resources::{
-    ConditionStatus, ControllerRevision, Job, Meta, NodeCondition, NodeConditionType,
-    ObservedGeneration, PersistentVolumeClaim,
+    ConfigMap, ControllerRevision, CronJob, Endpoints, Event, HorizontalPodAutoscaler, Job, Lease,
+    Meta, Namespace, NamespacePhase, ObservedGeneration, PersistentVolume, PersistentVolumeClaim,
+    PriorityClass, Role, RoleBinding, Secret, Service, Spec, StorageClass, Time,
 };
-use crate::utils::{self, now};
 use crate::{
-    abstract_model::{Change, ControllerAction},
-    resources::{Deployment, Node, Pod, ReplicaSet, StatefulSet},
+    abstract_model::{Change, ControllerAction, Message},
+    resources::{Deployment, Node, Pod, ReplicaSet, ReplicationController, StatefulSet},
+    workqueue::WorkQueue,
 };
 
-use self::history::{ConsistencySetup, History, StateHistory};
+use self::apply::{ApplyError, ApplyResult};
+use self::history::eventual::ReplicaMerge;
+use self::history::{ConsistencySetup, History, SessionGuarantee, StateHistory};
 use self::resources::Resources;
 use self::revision::Revision;
 
+pub mod apply;
 pub mod history;
 pub mod resources;
 pub mod revision;
@@ -27,6 +38,64 @@ pub struct State {
     states: StateHistory,
 
     controller_states: Vec<ControllerStates>,
+
+    /// The indices of controllers that have been upgraded to their alternative implementation.
+    upgraded_controllers: BTreeSet<usize>,
+
+    /// The states of shadow controllers, keyed by the index of the controller they shadow.
+    shadow_states: BTreeMap<usize, ControllerStates>,
+
+    /// The indices of controllers whose shadow has taken a different action to them.
+    diverged_shadows: BTreeSet<usize>,
+
+    /// The indices of controllers that deleted resources based on an incomplete relist.
+    destructive_relists: BTreeSet<usize>,
+
+    /// The indices of controllers that have attempted actions outside of their role.
+    unauthorized_controllers: BTreeSet<usize>,
+
+    /// The indices of controllers that acted after having lost their lease.
+    deposed_leaders: BTreeSet<usize>,
+
+    /// The indices of controllers that changed the spec of a resource in a status update.
+    spec_through_status: BTreeSet<usize>,
+
+    /// The indices of controllers whose writes lowered the observed generation of a resource.
+    regressed_observed_generations: BTreeSet<usize>,
+
+    /// The revision of the last successful write of controllers whose sessions guarantee reading
+    /// their own writes.
+    last_writes: BTreeMap<usize, Revision>,
+
+    /// The revision that the watch cache of each controller has caught up to, for controllers
+    /// that read through one.
+    watch_caches: BTreeMap<usize, Revision>,
+
+    /// The work queue of each controller that has queued anything, left out when empty.
+    workqueues: BTreeMap<usize, WorkQueue>,
+
+    /// The indices of node controllers that have been partitioned from the control plane.
+    partitioned_nodes: BTreeSet<usize>,
+
+    /// The indices of node controllers that have crashed and not yet been restarted.
+    crashed_nodes: BTreeSet<usize>,
+
+    /// The indices of other controllers that have crashed and not yet been restarted.
+    crashed_controllers: BTreeSet<usize>,
+
+    /// The number of steps that each weakly fair controller has been enabled for without taking
+    /// one itself, left out when zero.
+    starved_steps: BTreeMap<usize, usize>,
+
+    /// The events emitted by controllers, only fingerprinted when configured to be.
+    events: EventLog,
+
+    /// The number of recorded client operations that have been taken, in order.
+    client_operations: usize,
+
+    /// The actions that controllers have sent to the API server and that are yet to be delivered,
+    /// in the order that they were sent.
+    messages: Vec<Message>,
 }
 
 impl State {
@@ -34,6 +103,24 @@ impl State {
         Self {
             states: StateHistory::new(consistency_level, initial_state),
             controller_states: Vec::new(),
+            upgraded_controllers: BTreeSet::new(),
+            shadow_states: BTreeMap::new(),
+            diverged_shadows: BTreeSet::new(),
+            destructive_relists: BTreeSet::new(),
+            unauthorized_controllers: BTreeSet::new(),
+            deposed_leaders: BTreeSet::new(),
+            spec_through_status: BTreeSet::new(),
+            regressed_observed_generations: BTreeSet::new(),
+            last_writes: BTreeMap::new(),
+            watch_caches: BTreeMap::new(),
+            workqueues: BTreeMap::new(),
+            partitioned_nodes: BTreeSet::new(),
+            crashed_nodes: BTreeSet::new(),
+            crashed_controllers: BTreeSet::new(),
+            starved_steps: BTreeMap::new(),
+            events: EventLog::default(),
+            client_operations: 0,
+            messages: Vec::new(),
         }
     }
 
@@ -52,11 +139,123 @@ impl State {
         self.states.state_at(revision)
     }
 
+    /// The number of replicas that the state is spread over.
+    pub fn replicas(&self) -> usize {
+        self.states.replicas()
+    }
+
+    /// Merge the latest state of one replica into another, returning whether it changed anything.
+    pub fn merge_replicas(&mut self, from: usize, to: usize, merge: &ReplicaMerge) -> bool {
+        self.states.merge_replicas(from, to, merge)
+    }
+
+    /// The number of members of the store that can be partitioned from each other.
+    pub fn store_members(&self) -> usize {
+        self.states.store_members()
+    }
+
+    /// The member of the store that is partitioned from the rest, if any.
+    pub fn partitioned_store_member(&self) -> Option<usize> {
+        self.states.partitioned_store_member()
+    }
+
+    /// Partition the member of the store from the rest, returning whether that was possible.
+    pub fn partition_store(&mut self, member: usize) -> bool {
+        self.states.partition_store(member)
+    }
+
+    /// Heal the partition of the store, returning whether there was one.
+    pub fn heal_store(&mut self) -> bool {
+        self.states.heal_store()
+    }
+
+    /// Compact the history, keeping the latest `keep` revisions along with the referenced ones and
+    /// those that sessions, watch caches and undelivered messages refer to.
+    pub fn compact(&mut self, keep: usize, referenced: &[Revision]) {
+        let oldest = referenced
+            .iter()
+            .chain(self.last_writes.values())
+            .chain(self.watch_caches.values())
+            .chain(self.messages.iter().map(|m| &m.revision))
+            .filter_map(|r| r.components().first().copied())
+            .min();
+        self.states.compact(keep, oldest);
+    }
+
     /// Get all the possible revisions under the given consistency level.
     pub fn revisions(&self, min_revision: Option<&Revision>) -> Vec<Revision> {
         self.states.valid_revisions(min_revision)
     }
 
+    /// Get all the revisions still in the history, from the oldest kept by compaction up to the
+    /// latest.
+    pub fn retained_revisions(&self) -> Vec<Revision> {
+        self.states.retained_revisions()
+    }
+
+    /// Get the revisions that the controller can work off, given the last one it read and the
+    /// guarantees of its session.
+    pub fn session_revisions(
+        &self,
+        controller: usize,
+        min_revision: Option<&Revision>,
+        guarantee: SessionGuarantee,
+    ) -> Vec<Revision> {
+        let mut revisions = self.revisions(min_revision);
+        if guarantee == SessionGuarantee::ReadYourWrites {
+            if let Some(last_write) = self.last_writes.get(&controller) {
+                // THEMELIOS: revisions are compared by their latest component, which is exact for
+                // the linear histories that sessions are modelled with
+                revisions.retain(|r| r.components().last() >= last_write.components().last());
+            }
+        }
+        revisions
+    }
+
+    /// Record that the write of the controller was applied at the revision.
+    pub fn record_write(&mut self, controller: usize, revision: Revision) {
+        self.last_writes.insert(controller, revision);
+    }
+
+    /// Forget the writes of the controller, as when it restarts with a new session.
+    pub fn reset_session(&mut self, controller: usize) {
+        self.last_writes.remove(&controller);
+    }
+
+    /// The revision that the watch cache of the controller is at, none if it has yet to list.
+    pub fn watch_cache(&self, controller: usize) -> Option<&Revision> {
+        self.watch_caches.get(&controller)
+    }
+
+    /// Bring the watch cache of the controller up to the revision.
+    pub fn resync_watch_cache(&mut self, controller: usize, revision: Revision) {
+        self.watch_caches.insert(controller, revision);
+    }
+
+    /// Drop the watch cache of the controller, as when it restarts and has to list again.
+    pub fn clear_watch_cache(&mut self, controller: usize) {
+        self.watch_caches.remove(&controller);
+    }
+
+    /// The work queue of the controller, none if it has nothing queued.
+    pub fn workqueue(&self, controller: usize) -> Option<&WorkQueue> {
+        self.workqueues.get(&controller)
+    }
+
+    /// Change the work queue of the controller, dropping it once it is empty.
+    pub fn update_workqueue(&mut self, controller: usize, f: impl FnOnce(&mut WorkQueue)) {
+        let queue = self.workqueues.entry(controller).or_default();
+        f(queue);
+        if queue.is_empty() {
+            self.workqueues.remove(&controller);
+        }
+    }
+
+    /// Drop the work queue of the controller, as when it restarts with nothing queued.
+    pub fn clear_workqueue(&mut self, controller: usize) {
+        self.workqueues.remove(&controller);
+    }
+
     pub fn add_controller(&mut self, controller_state: ControllerStates) {
         self.controller_states.push(controller_state);
     }
@@ -69,6 +268,199 @@ impl State {
         &self.controller_states[controller]
     }
 
+    /// Mark the controller as upgraded, replacing its state with that of the new implementation.
+    pub fn upgrade_controller(&mut self, controller: usize, controller_state: ControllerStates) {
+        self.upgraded_controllers.insert(controller);
+        self.controller_states[controller] = controller_state;
+    }
+
+    pub fn controller_upgraded(&self, controller: usize) -> bool {
+        self.upgraded_controllers.contains(&controller)
+    }
+
+    /// Whether any controller has been upgraded so far.
+    pub fn any_controller_upgraded(&self) -> bool {
+        !self.upgraded_controllers.is_empty()
+    }
+
+    pub fn update_shadow(&mut self, controller: usize, shadow_state: ControllerStates) {
+        self.shadow_states.insert(controller, shadow_state);
+    }
+
+    pub fn get_shadow(&self, controller: usize) -> &ControllerStates {
+        &self.shadow_states[&controller]
+    }
+
+    /// Record that the shadow of the given controller took a different action to it.
+    pub fn mark_shadow_diverged(&mut self, controller: usize) {
+        self.diverged_shadows.insert(controller);
+    }
+
+    /// The indices of controllers whose shadows have diverged from them.
+    pub fn diverged_shadows(&self) -> &BTreeSet<usize> {
+        &self.diverged_shadows
+    }
+
+    /// Record that the controller deleted a resource that it would not have with a full view.
+    pub fn record_destructive_relist(&mut self, controller: usize) {
+        self.destructive_relists.insert(controller);
+    }
+
+    /// The indices of controllers that have deleted resources based on an incomplete relist.
+    pub fn destructive_relists(&self) -> &BTreeSet<usize> {
+        &self.destructive_relists
+    }
+
+    /// Record that the controller attempted an action that its role does not allow.
+    pub fn record_unauthorized(&mut self, controller: usize) {
+        self.unauthorized_controllers.insert(controller);
+    }
+
+    /// The indices of controllers that have attempted unauthorized actions.
+    pub fn unauthorized_controllers(&self) -> &BTreeSet<usize> {
+        &self.unauthorized_controllers
+    }
+
+    /// Record that the controller acted when it no longer held its lease.
+    pub fn record_deposed_leader(&mut self, controller: usize) {
+        self.deposed_leaders.insert(controller);
+    }
+
+    /// The indices of controllers that have acted after losing their lease.
+    pub fn deposed_leaders(&self) -> &BTreeSet<usize> {
+        &self.deposed_leaders
+    }
+
+    /// Record that the controller tried to change the spec of a resource through its status
+    /// subresource.
+    pub fn record_spec_through_status(&mut self, controller: usize) {
+        self.spec_through_status.insert(controller);
+    }
+
+    /// The indices of controllers that have tried to change specs through status updates.
+    pub fn spec_through_status(&self) -> &BTreeSet<usize> {
+        &self.spec_through_status
+    }
+
+    /// Record that a write of the controller lowered the observed generation of a resource.
+    pub fn record_regressed_observed_generation(&mut self, controller: usize) {
+        self.regressed_observed_generations.insert(controller);
+    }
+
+    /// The indices of controllers whose writes have lowered the observed generation of a resource.
+    pub fn regressed_observed_generations(&self) -> &BTreeSet<usize> {
+        &self.regressed_observed_generations
+    }
+
+    /// Cut the node controller off from the control plane.
+    pub fn partition_node(&mut self, controller: usize) {
+        self.partitioned_nodes.insert(controller);
+    }
+
+    pub fn node_partitioned(&self, controller: usize) -> bool {
+        self.partitioned_nodes.contains(&controller)
+    }
+
+    pub fn crash_node(&mut self, controller: usize) {
+        self.crashed_nodes.insert(controller);
+    }
+
+    /// The node is back up, after having crashed or not.
+    pub fn recover_node(&mut self, controller: usize) {
+        self.crashed_nodes.remove(&controller);
+    }
+
+    pub fn node_crashed(&self, controller: usize) -> bool {
+        self.crashed_nodes.contains(&controller)
+    }
+
+    pub fn crash_controller(&mut self, controller: usize) {
+        self.crashed_controllers.insert(controller);
+    }
+
+    /// The controller is running again, after having crashed or not.
+    pub fn recover_controller(&mut self, controller: usize) {
+        self.crashed_controllers.remove(&controller);
+    }
+
+    pub fn controller_crashed(&self, controller: usize) -> bool {
+        self.crashed_controllers.contains(&controller)
+    }
+
+    /// The indices of node controllers that are partitioned from the control plane.
+    pub fn partitioned_nodes(&self) -> &BTreeSet<usize> {
+        &self.partitioned_nodes
+    }
+
+    pub fn starved_steps(&self, controller: usize) -> usize {
+        self.starved_steps
+            .get(&controller)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_starved_steps(&mut self, controller: usize, steps: usize) {
+        if steps == 0 {
+            self.starved_steps.remove(&controller);
+        } else {
+            self.starved_steps.insert(controller, steps);
+        }
+    }
+
+    /// Start recording events afresh.
+    pub fn set_event_recording(&mut self, recording: EventRecording) {
+        self.events = EventLog::new(recording);
+    }
+
+    pub fn record_events(&mut self, events: impl IntoIterator<Item = Event>) {
+        for event in events {
+            self.events.record(event);
+        }
+    }
+
+    /// The events recorded so far, oldest first.
+    pub fn events(&self) -> &[Event] {
+        self.events.events()
+    }
+
+    /// The number of recorded client operations taken so far, the next being at this index.
+    pub fn client_operations(&self) -> usize {
+        self.client_operations
+    }
+
+    pub fn take_client_operation(&mut self) {
+        self.client_operations += 1;
+    }
+
+    /// The messages yet to be delivered to the API server, oldest first.
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// Whether the controller is waiting on a message that it sent to be delivered.
+    pub fn awaiting_message(&self, controller: usize) -> bool {
+        self.messages.iter().any(|m| m.controller == controller)
+    }
+
+    pub fn send_message(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// Take the message at the index out of those in flight.
+    pub fn take_message(&mut self, index: usize) -> Message {
+        self.messages.remove(index)
+    }
+
+    /// Use up one of the retries of the message at the index, returning whether it had any left.
+    pub fn retry_message(&mut self, index: usize) -> bool {
+        let message = &mut self.messages[index];
+        if message.retries == 0 {
+            return false;
+        }
+        message.retries -= 1;
+        true
+    }
+
     pub fn latest(&self) -> Cow<StateView> {
         self.states.state_at(&self.max_revision())
     }
@@ -93,16 +485,125 @@ impl From<RawState> for StateView {
     }
 }
 
+/// The kinds of resources held in the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Nodes,
+    Pods,
+    ReplicaSets,
+    ReplicationControllers,
+    Deployments,
+    StatefulSets,
+    ControllerRevisions,
+    PersistentVolumeClaims,
+    PersistentVolumes,
+    StorageClasses,
+    PriorityClasses,
+    Leases,
+    Services,
+    Endpoints,
+    ConfigMaps,
+    Secrets,
+    Jobs,
+    CronJobs,
+    HorizontalPodAutoscalers,
+    Namespaces,
+    Roles,
+    RoleBindings,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 22] = [
+        ResourceKind::Nodes,
+        ResourceKind::Pods,
+        ResourceKind::ReplicaSets,
+        ResourceKind::ReplicationControllers,
+        ResourceKind::Deployments,
+        ResourceKind::StatefulSets,
+        ResourceKind::ControllerRevisions,
+        ResourceKind::PersistentVolumeClaims,
+        ResourceKind::PersistentVolumes,
+        ResourceKind::StorageClasses,
+        ResourceKind::PriorityClasses,
+        ResourceKind::Leases,
+        ResourceKind::Services,
+        ResourceKind::Endpoints,
+        ResourceKind::ConfigMaps,
+        ResourceKind::Secrets,
+        ResourceKind::Jobs,
+        ResourceKind::CronJobs,
+        ResourceKind::HorizontalPodAutoscalers,
+        ResourceKind::Namespaces,
+        ResourceKind::Roles,
+        ResourceKind::RoleBindings,
+    ];
+}
+
+impl FromStr for ResourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "nodes" => ResourceKind::Nodes,
+            "pods" => ResourceKind::Pods,
+            "replicasets" => ResourceKind::ReplicaSets,
+            "replication-controllers" => ResourceKind::ReplicationControllers,
+            "deployments" => ResourceKind::Deployments,
+            "statefulsets" => ResourceKind::StatefulSets,
+            "controller-revisions" => ResourceKind::ControllerRevisions,
+            "persistent-volume-claims" => ResourceKind::PersistentVolumeClaims,
+            "persistent-volumes" => ResourceKind::PersistentVolumes,
+            "storage-classes" => ResourceKind::StorageClasses,
+            "priority-classes" => ResourceKind::PriorityClasses,
+            "leases" => ResourceKind::Leases,
+            "services" => ResourceKind::Services,
+            "endpoints" => ResourceKind::Endpoints,
+            "config-maps" => ResourceKind::ConfigMaps,
+            "secrets" => ResourceKind::Secrets,
+            "jobs" => ResourceKind::Jobs,
+            "cronjobs" => ResourceKind::CronJobs,
+            "horizontal-pod-autoscalers" => ResourceKind::HorizontalPodAutoscalers,
+            "namespaces" => ResourceKind::Namespaces,
+            "roles" => ResourceKind::Roles,
+            "role-bindings" => ResourceKind::RoleBindings,
+            _ => return Err(format!("unknown resource kind {s:?}")),
+        })
+    }
+}
+
 #[derive(Default, Clone, Debug, Eq, PartialOrd, Ord, PartialEq, Hash)]
 pub struct RawState {
     pub nodes: Resources<Node>,
     pub pods: Resources<Pod>,
     pub replicasets: Resources<ReplicaSet>,
+    pub replication_controllers: Resources<ReplicationController>,
     pub deployments: Resources<Deployment>,
     pub statefulsets: Resources<StatefulSet>,
     pub controller_revisions: Resources<ControllerRevision>,
     pub persistent_volume_claims: Resources<PersistentVolumeClaim>,
+    pub persistent_volumes: Resources<PersistentVolume>,
+    pub storage_classes: Resources<StorageClass>,
+    pub priority_classes: Resources<PriorityClass>,
+    pub leases: Resources<Lease>,
+    pub services: Resources<Service>,
+    pub endpoints: Resources<Endpoints>,
+    pub config_maps: Resources<ConfigMap>,
+    pub secrets: Resources<Secret>,
     pub jobs: Resources<Job>,
+    pub cronjobs: Resources<CronJob>,
+    pub horizontal_pod_autoscalers: Resources<HorizontalPodAutoscaler>,
+    pub namespaces: Resources<Namespace>,
+    pub roles: Resources<Role>,
+    pub role_bindings: Resources<RoleBinding>,
+    /// THEMELIOS: The current time of the cluster, in seconds since the unix epoch.
+    /// This only moves forward when explicitly advanced, letting the checker explore different
+    /// interleavings of time passing and controllers running.
+    pub clock: u64,
+    /// THEMELIOS: An abstract metrics source, giving the current average cpu utilization (as a
+    /// percentage of requests) of the pods targeted by each horizontal pod autoscaler, keyed by
+    /// the namespace and name of the autoscaler.
+    /// Readings are chosen non-deterministically by the model rather than derived from the pods.
+    pub metrics: BTreeMap<(String, String), u32>,
 }
 
 impl RawState {
@@ -135,6 +636,27 @@ impl RawState {
         self
     }
 
+    pub fn with_replication_controllers(
+        mut self,
+        replication_controllers: impl IntoIterator<Item = ReplicationController>,
+    ) -> Self {
+        self.set_replication_controllers(replication_controllers);
+        self
+    }
+
+    pub fn set_replication_controllers(
+        &mut self,
+        replication_controllers: impl IntoIterator<Item = ReplicationController>,
+    ) -> &mut Self {
+        for replication_controller in replication_controllers {
+            let revision = replication_controller.metadata.resource_version.clone();
+            self.replication_controllers
+                .create(replication_controller, revision)
+                .unwrap();
+        }
+        self
+    }
+
     pub fn with_deployments(mut self, deployments: impl IntoIterator<Item = Deployment>) -> Self {
         self.set_deployments(deployments);
         self
@@ -183,6 +705,240 @@ impl RawState {
         self
     }
 
+    pub fn with_cronjobs(mut self, cronjobs: impl IntoIterator<Item = CronJob>) -> Self {
+        self.set_cronjobs(cronjobs);
+        self
+    }
+
+    pub fn set_cronjobs(&mut self, cronjobs: impl IntoIterator<Item = CronJob>) -> &mut Self {
+        for cronjob in cronjobs {
+            let revision = cronjob.metadata.resource_version.clone();
+            self.cronjobs.create(cronjob, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_horizontal_pod_autoscalers(
+        mut self,
+        hpas: impl IntoIterator<Item = HorizontalPodAutoscaler>,
+    ) -> Self {
+        self.set_horizontal_pod_autoscalers(hpas);
+        self
+    }
+
+    pub fn set_horizontal_pod_autoscalers(
+        &mut self,
+        hpas: impl IntoIterator<Item = HorizontalPodAutoscaler>,
+    ) -> &mut Self {
+        for hpa in hpas {
+            let revision = hpa.metadata.resource_version.clone();
+            self.horizontal_pod_autoscalers
+                .create(hpa, revision)
+                .unwrap();
+        }
+        self
+    }
+
+    pub fn with_namespaces(mut self, namespaces: impl IntoIterator<Item = Namespace>) -> Self {
+        self.set_namespaces(namespaces);
+        self
+    }
+
+    pub fn set_namespaces(&mut self, namespaces: impl IntoIterator<Item = Namespace>) -> &mut Self {
+        for mut namespace in namespaces {
+            // the API server adds its finalizer so that contents get removed before the namespace
+            if namespace.spec.finalizers.is_empty() {
+                namespace
+                    .spec
+                    .finalizers
+                    .push(Namespace::FINALIZER.to_owned());
+            }
+            let revision = namespace.metadata.resource_version.clone();
+            self.namespaces.create(namespace, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_persistent_volume_claims(
+        mut self,
+        pvcs: impl IntoIterator<Item = PersistentVolumeClaim>,
+    ) -> Self {
+        self.set_persistent_volume_claims(pvcs);
+        self
+    }
+
+    pub fn set_persistent_volume_claims(
+        &mut self,
+        pvcs: impl IntoIterator<Item = PersistentVolumeClaim>,
+    ) -> &mut Self {
+        for pvc in pvcs {
+            let revision = pvc.metadata.resource_version.clone();
+            self.persistent_volume_claims.create(pvc, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_persistent_volumes(
+        mut self,
+        pvs: impl IntoIterator<Item = PersistentVolume>,
+    ) -> Self {
+        self.set_persistent_volumes(pvs);
+        self
+    }
+
+    pub fn set_persistent_volumes(
+        &mut self,
+        pvs: impl IntoIterator<Item = PersistentVolume>,
+    ) -> &mut Self {
+        for pv in pvs {
+            let revision = pv.metadata.resource_version.clone();
+            self.persistent_volumes.create(pv, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_storage_classes(
+        mut self,
+        storage_classes: impl IntoIterator<Item = StorageClass>,
+    ) -> Self {
+        self.set_storage_classes(storage_classes);
+        self
+    }
+
+    pub fn set_storage_classes(
+        &mut self,
+        storage_classes: impl IntoIterator<Item = StorageClass>,
+    ) -> &mut Self {
+        for storage_class in storage_classes {
+            let revision = storage_class.metadata.resource_version.clone();
+            self.storage_classes
+                .create(storage_class, revision)
+                .unwrap();
+        }
+        self
+    }
+
+    pub fn with_priority_classes(
+        mut self,
+        priority_classes: impl IntoIterator<Item = PriorityClass>,
+    ) -> Self {
+        self.set_priority_classes(priority_classes);
+        self
+    }
+
+    pub fn set_priority_classes(
+        &mut self,
+        priority_classes: impl IntoIterator<Item = PriorityClass>,
+    ) -> &mut Self {
+        for priority_class in priority_classes {
+            let revision = priority_class.metadata.resource_version.clone();
+            self.priority_classes
+                .create(priority_class, revision)
+                .unwrap();
+        }
+        self
+    }
+
+    pub fn with_leases(mut self, leases: impl IntoIterator<Item = Lease>) -> Self {
+        self.set_leases(leases);
+        self
+    }
+
+    pub fn set_leases(&mut self, leases: impl IntoIterator<Item = Lease>) -> &mut Self {
+        for lease in leases {
+            let revision = lease.metadata.resource_version.clone();
+            self.leases.create(lease, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_services(mut self, services: impl IntoIterator<Item = Service>) -> Self {
+        self.set_services(services);
+        self
+    }
+
+    pub fn set_services(&mut self, services: impl IntoIterator<Item = Service>) -> &mut Self {
+        for service in services {
+            let revision = service.metadata.resource_version.clone();
+            self.services.create(service, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_endpoints(mut self, endpoints: impl IntoIterator<Item = Endpoints>) -> Self {
+        self.set_endpoints(endpoints);
+        self
+    }
+
+    pub fn set_endpoints(&mut self, endpoints: impl IntoIterator<Item = Endpoints>) -> &mut Self {
+        for endpoints in endpoints {
+            let revision = endpoints.metadata.resource_version.clone();
+            self.endpoints.create(endpoints, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_config_maps(mut self, config_maps: impl IntoIterator<Item = ConfigMap>) -> Self {
+        self.set_config_maps(config_maps);
+        self
+    }
+
+    pub fn set_config_maps(
+        &mut self,
+        config_maps: impl IntoIterator<Item = ConfigMap>,
+    ) -> &mut Self {
+        for config_map in config_maps {
+            let revision = config_map.metadata.resource_version.clone();
+            self.config_maps.create(config_map, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_secrets(mut self, secrets: impl IntoIterator<Item = Secret>) -> Self {
+        self.set_secrets(secrets);
+        self
+    }
+
+    pub fn set_secrets(&mut self, secrets: impl IntoIterator<Item = Secret>) -> &mut Self {
+        for secret in secrets {
+            let revision = secret.metadata.resource_version.clone();
+            self.secrets.create(secret, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_roles(mut self, roles: impl IntoIterator<Item = Role>) -> Self {
+        self.set_roles(roles);
+        self
+    }
+
+    pub fn set_roles(&mut self, roles: impl IntoIterator<Item = Role>) -> &mut Self {
+        for role in roles {
+            let revision = role.metadata.resource_version.clone();
+            self.roles.create(role, revision).unwrap();
+        }
+        self
+    }
+
+    pub fn with_role_bindings(
+        mut self,
+        role_bindings: impl IntoIterator<Item = RoleBinding>,
+    ) -> Self {
+        self.set_role_bindings(role_bindings);
+        self
+    }
+
+    pub fn set_role_bindings(
+        &mut self,
+        role_bindings: impl IntoIterator<Item = RoleBinding>,
+    ) -> &mut Self {
+        for role_binding in role_bindings {
+            let revision = role_binding.metadata.resource_version.clone();
+            self.role_bindings.create(role_binding, revision).unwrap();
+        }
+        self
+    }
+
     pub fn with_nodes(mut self, nodes: impl IntoIterator<Item = Node>) -> Self {
         self.set_nodes(nodes);
         self
@@ -196,6 +952,90 @@ impl RawState {
         self
     }
 
+    /// Remove all resources of the given kind.
+    pub fn clear_kind(&mut self, kind: ResourceKind) {
+        match kind {
+            ResourceKind::Nodes => self.nodes = Resources::default(),
+            ResourceKind::Pods => self.pods = Resources::default(),
+            ResourceKind::ReplicaSets => self.replicasets = Resources::default(),
+            ResourceKind::ReplicationControllers => {
+                self.replication_controllers = Resources::default()
+            }
+            ResourceKind::Deployments => self.deployments = Resources::default(),
+            ResourceKind::StatefulSets => self.statefulsets = Resources::default(),
+            ResourceKind::ControllerRevisions => self.controller_revisions = Resources::default(),
+            ResourceKind::PersistentVolumeClaims => {
+                self.persistent_volume_claims = Resources::default()
+            }
+            ResourceKind::PersistentVolumes => self.persistent_volumes = Resources::default(),
+            ResourceKind::StorageClasses => self.storage_classes = Resources::default(),
+            ResourceKind::PriorityClasses => self.priority_classes = Resources::default(),
+            ResourceKind::Leases => self.leases = Resources::default(),
+            ResourceKind::Services => self.services = Resources::default(),
+            ResourceKind::Endpoints => self.endpoints = Resources::default(),
+            ResourceKind::ConfigMaps => self.config_maps = Resources::default(),
+            ResourceKind::Secrets => self.secrets = Resources::default(),
+            ResourceKind::Jobs => self.jobs = Resources::default(),
+            ResourceKind::CronJobs => self.cronjobs = Resources::default(),
+            ResourceKind::HorizontalPodAutoscalers => {
+                self.horizontal_pod_autoscalers = Resources::default()
+            }
+            ResourceKind::Namespaces => self.namespaces = Resources::default(),
+            ResourceKind::Roles => self.roles = Resources::default(),
+            ResourceKind::RoleBindings => self.role_bindings = Resources::default(),
+        }
+    }
+
+    /// Keep only the resources of the given kind whose names satisfy the predicate.
+    pub fn retain_names(&mut self, kind: ResourceKind, f: impl Fn(&str) -> bool) {
+        macro_rules! retain {
+            ($field:ident) => {
+                self.$field.retain(|r| f(&r.metadata.name))
+            };
+        }
+        match kind {
+            ResourceKind::Nodes => retain!(nodes),
+            ResourceKind::Pods => retain!(pods),
+            ResourceKind::ReplicaSets => retain!(replicasets),
+            ResourceKind::ReplicationControllers => retain!(replication_controllers),
+            ResourceKind::Deployments => retain!(deployments),
+            ResourceKind::StatefulSets => retain!(statefulsets),
+            ResourceKind::ControllerRevisions => retain!(controller_revisions),
+            ResourceKind::PersistentVolumeClaims => retain!(persistent_volume_claims),
+            ResourceKind::PersistentVolumes => retain!(persistent_volumes),
+            ResourceKind::StorageClasses => retain!(storage_classes),
+            ResourceKind::PriorityClasses => retain!(priority_classes),
+            ResourceKind::Leases => retain!(leases),
+            ResourceKind::Services => retain!(services),
+            ResourceKind::Endpoints => retain!(endpoints),
+            ResourceKind::ConfigMaps => retain!(config_maps),
+            ResourceKind::Secrets => retain!(secrets),
+            ResourceKind::Jobs => retain!(jobs),
+            ResourceKind::CronJobs => retain!(cronjobs),
+            ResourceKind::HorizontalPodAutoscalers => retain!(horizontal_pod_autoscalers),
+            ResourceKind::Namespaces => retain!(namespaces),
+            ResourceKind::Roles => retain!(roles),
+            ResourceKind::RoleBindings => retain!(role_bindings),
+        }
+    }
+
+    /// Whether new resources can be created in the namespace, it must exist and not be terminating.
+    ///
+    /// THEMELIOS: States without any namespaces don't model them so allow creation anywhere.
+    pub fn namespace_accepts_creates(&self, namespace: &str) -> bool {
+        if self.namespaces.is_empty() {
+            return true;
+        }
+        self.namespaces
+            .get(namespace)
+            .map_or(false, |ns| ns.status.phase == NamespacePhase::Active)
+    }
+
+    /// The current time according to the cluster clock.
+    pub fn now(&self) -> Time {
+        Time(OffsetDateTime::UNIX_EPOCH + Duration::from_secs(self.clock))
+    }
+
     pub fn pods_for_node(&self, node: &str) -> Vec<&Pod> {
         self.pods
             .iter()
@@ -203,16 +1043,54 @@ impl RawState {
             .collect()
     }
 
+    /// Merge the other state into this one, keeping the latest version of each resource.
     pub fn merge(&mut self, other: &Self) {
-        self.nodes.merge(&other.nodes);
-        self.pods.merge(&other.pods);
-        self.replicasets.merge(&other.replicasets);
-        self.deployments.merge(&other.deployments);
-        self.statefulsets.merge(&other.statefulsets);
-        self.controller_revisions.merge(&other.controller_revisions);
-        self.persistent_volume_claims
-            .merge(&other.persistent_volume_claims);
-        self.jobs.merge(&other.jobs);
+        ReplicaMerge::default().merge(self, other)
+    }
+
+    /// Whether any resource has a lower observed generation in the later state than in this one.
+    pub fn observed_generation_regressed(&self, later: &Self) -> bool {
+        fn regressed<T: Meta + Spec + Clone + ObservedGeneration>(
+            before: &Resources<T>,
+            after: &Resources<T>,
+        ) -> bool {
+            after.iter().any(|res| {
+                let metadata = res.metadata();
+                before
+                    .get_in(&metadata.namespace, &metadata.name)
+                    .map_or(false, |old| {
+                        old.metadata().uid == res.metadata().uid
+                            && res.observed_generation() < old.observed_generation()
+                    })
+            })
+        }
+        regressed(&self.deployments, &later.deployments)
+            || regressed(&self.replicasets, &later.replicasets)
+            || regressed(
+                &self.replication_controllers,
+                &later.replication_controllers,
+            )
+            || regressed(&self.statefulsets, &later.statefulsets)
+            || regressed(&self.jobs, &later.jobs)
+            || regressed(
+                &self.horizontal_pod_autoscalers,
+                &later.horizontal_pod_autoscalers,
+            )
+    }
+
+    /// Whether every resource has observed at most its current generation.
+    pub fn observed_generations_bounded(&self) -> bool {
+        fn bounded<T: Meta + ObservedGeneration>(resources: &Resources<T>) -> bool {
+            resources
+                .iter()
+                .all(|res| res.observed_generation() <= res.metadata().generation)
+        }
+        bounded(&self.deployments)
+            && bounded(&self.replicasets)
+            && bounded(&self.replication_controllers)
+            && bounded(&self.statefulsets)
+            && bounded(&self.jobs)
+            && bounded(&self.horizontal_pod_autoscalers)
     }
 }
 
@@ -244,7 +1122,7 @@ impl StateView {
                 *self = s;
                 true
             }
-            Err(()) => {
+            Err(ApplyError) => {
                 // don't update our self, basically abort the transaction so no changes
                 false
             }
@@ -255,131 +1133,185 @@ impl StateView {
         &mut self,
         operation: ControllerAction,
         new_revision: Revision,
-    ) -> Result<(), ()> {
+    ) -> ApplyResult {
         match operation {
             ControllerAction::NodeJoin(name, capacity) => {
-                self.nodes
-                    .create(
-                        Node {
-                            metadata: utils::metadata(name.clone()),
-                            spec: crate::resources::NodeSpec {
-                                taints: Vec::new(),
-                                unschedulable: false,
-                            },
-                            status: crate::resources::NodeStatus {
-                                capacity: capacity.clone(),
-                                allocatable: Some(capacity.clone()),
-                                conditions: vec![NodeCondition {
-                                    r#type: NodeConditionType::Ready,
-                                    status: ConditionStatus::True,
-                                    ..Default::default()
-                                }],
-                            },
-                        },
-                        new_revision,
-                    )
-                    .map_err(|_| ())?;
-            }
-            ControllerAction::DeleteNode(name) => {
-                self.nodes.remove(&name);
-            }
-            ControllerAction::CreatePod(mut pod) => {
-                pod.metadata.uid = self.revision.to_string();
-                self.fill_name(&mut pod);
-                self.pods.create(pod, new_revision).map_err(|_| ())?;
-            }
-            ControllerAction::UpdatePod(pod) => {
-                self.pods.update(pod, new_revision).map_err(|_| ())?;
-            }
-            ControllerAction::SoftDeletePod(mut pod) => {
-                // marked for deletion
-                pod.metadata.deletion_timestamp = Some(now());
-                self.pods.update(pod, new_revision).map_err(|_| ())?;
+                apply::nodes::join(self, name, capacity, new_revision)
+            }
+            ControllerAction::DeleteNode(node) => apply::nodes::delete(self, node),
+            ControllerAction::UpdateNode(node) => apply::nodes::update(self, node, new_revision),
+            ControllerAction::CreatePod(pod) => apply::pods::create(self, pod, new_revision),
+            ControllerAction::UpdatePod(pod) => apply::pods::update(self, pod, new_revision),
+            ControllerAction::PatchPod(namespace, name, patch) => {
+                apply::pods::patch(self, &namespace, &name, &patch, new_revision)
+            }
+            ControllerAction::SoftDeletePod(pod) => {
+                apply::pods::soft_delete(self, pod, new_revision)
             }
             ControllerAction::HardDeletePod(pod) => {
-                self.pods.remove(&pod);
+                apply::pods::hard_delete(self, pod, new_revision)
+            }
+            ControllerAction::CreateDeployment(dep) => {
+                apply::deployments::create(self, dep, new_revision)
             }
             ControllerAction::UpdateDeployment(dep) => {
-                self.deployments.update(dep, new_revision).map_err(|_| ())?;
+                apply::deployments::update(self, dep, new_revision)
+            }
+            ControllerAction::PatchDeployment(namespace, name, patch) => {
+                apply::deployments::patch(self, &namespace, &name, &patch, new_revision)
             }
-            ControllerAction::RequeueDeployment(_dep) => {
-                // skip
+            ControllerAction::DeleteDeployment(dep) => {
+                apply::deployments::delete(self, dep, new_revision)
             }
+            // requeues only go to the controller's work queue, leaving the state as is
+            ControllerAction::RequeueDeployment(_, _) => Ok(()),
             ControllerAction::UpdateDeploymentStatus(dep) => {
-                self.deployments.update(dep, new_revision).map_err(|_| ())?;
+                apply::deployments::update_status(self, dep, new_revision)
             }
-            ControllerAction::CreateReplicaSet(mut rs) => {
-                rs.metadata.uid = self.revision.to_string();
-                self.fill_name(&mut rs);
-                self.replicasets.create(rs, new_revision).map_err(|_| ())?;
+            ControllerAction::CreateReplicaSet(rs) => {
+                apply::replicasets::create(self, rs, new_revision)
             }
             ControllerAction::UpdateReplicaSet(rs) => {
-                self.replicasets.update(rs, new_revision).map_err(|_| ())?;
+                apply::replicasets::update(self, rs, new_revision)
             }
             ControllerAction::UpdateReplicaSetStatus(rs) => {
-                self.replicasets.update(rs, new_revision).map_err(|_| ())?;
+                apply::replicasets::update_status(self, rs, new_revision)
             }
-            ControllerAction::UpdateReplicaSets(rss) => {
-                for rs in rss {
-                    self.replicasets
-                        .update(rs, new_revision.clone())
-                        .map_err(|_| ())?;
-                }
+            ControllerAction::PatchReplicaSet(namespace, name, patch) => {
+                apply::replicasets::patch(self, &namespace, &name, &patch, new_revision)
+            }
+            ControllerAction::DeleteReplicaSet(rs) => apply::replicasets::delete(self, rs),
+            ControllerAction::CreateStatefulSet(sts) => {
+                apply::statefulsets::create(self, sts, new_revision)
             }
             ControllerAction::UpdateStatefulSet(sts) => {
-                self.statefulsets
-                    .update(sts, new_revision)
-                    .map_err(|_| ())?;
+                apply::statefulsets::update(self, sts, new_revision)
             }
             ControllerAction::UpdateStatefulSetStatus(sts) => {
-                self.statefulsets
-                    .update(sts, new_revision)
-                    .map_err(|_| ())?;
+                apply::statefulsets::update_status(self, sts, new_revision)
+            }
+            ControllerAction::PatchStatefulSet(namespace, name, patch) => {
+                apply::statefulsets::patch(self, &namespace, &name, &patch, new_revision)
             }
-            ControllerAction::CreateControllerRevision(mut cr) => {
-                cr.metadata.uid = self.revision.to_string();
-                self.fill_name(&mut cr);
-                self.controller_revisions
-                    .create(cr, new_revision)
-                    .map_err(|_| ())?;
+            ControllerAction::DeleteStatefulSet(sts) => {
+                apply::statefulsets::delete(self, sts, new_revision)
+            }
+            ControllerAction::CreateControllerRevision(cr) => {
+                apply::controller_revisions::create(self, cr, new_revision)
             }
             ControllerAction::UpdateControllerRevision(cr) => {
-                self.controller_revisions
-                    .update(cr, new_revision)
-                    .map_err(|_| ())?;
+                apply::controller_revisions::update(self, cr, new_revision)
             }
             ControllerAction::DeleteControllerRevision(cr) => {
-                self.controller_revisions.remove(&cr);
-            }
-            ControllerAction::DeleteReplicaSet(rs) => {
-                self.replicasets.remove(&rs);
+                apply::controller_revisions::delete(self, cr)
             }
-            ControllerAction::CreatePersistentVolumeClaim(mut pvc) => {
-                pvc.metadata.uid = self.revision.to_string();
-                self.fill_name(&mut pvc);
-                self.persistent_volume_claims
-                    .create(pvc, new_revision)
-                    .map_err(|_| ())?;
+            ControllerAction::CreatePersistentVolumeClaim(pvc) => {
+                apply::persistent_volume_claims::create(self, pvc, new_revision)
             }
             ControllerAction::UpdatePersistentVolumeClaim(pvc) => {
-                self.persistent_volume_claims
-                    .update(pvc, new_revision)
-                    .map_err(|_| ())?;
+                apply::persistent_volume_claims::update(self, pvc, new_revision)
+            }
+            ControllerAction::DeletePersistentVolumeClaim(pvc) => {
+                apply::persistent_volume_claims::delete(self, pvc)
+            }
+            ControllerAction::UpdatePersistentVolumeClaimStatus(pvc) => {
+                apply::persistent_volume_claims::update_status(self, pvc, new_revision)
+            }
+            ControllerAction::CreatePersistentVolume(pv) => {
+                apply::persistent_volumes::create(self, pv, new_revision)
+            }
+            ControllerAction::UpdatePersistentVolume(pv) => {
+                apply::persistent_volumes::update(self, pv, new_revision)
+            }
+            ControllerAction::UpdatePersistentVolumeStatus(pv) => {
+                apply::persistent_volumes::update_status(self, pv, new_revision)
+            }
+            ControllerAction::CreateLease(lease) => {
+                apply::leases::create(self, lease, new_revision)
+            }
+            ControllerAction::UpdateLease(lease) => {
+                apply::leases::update(self, lease, new_revision)
+            }
+            ControllerAction::DeleteService(service) => apply::services::delete(self, service),
+            ControllerAction::CreateEndpoints(endpoints) => {
+                apply::endpoints::create(self, endpoints, new_revision)
+            }
+            ControllerAction::UpdateEndpoints(endpoints) => {
+                apply::endpoints::update(self, endpoints, new_revision)
+            }
+            ControllerAction::DeleteEndpoints(endpoints) => {
+                apply::endpoints::delete(self, endpoints)
+            }
+            ControllerAction::CreateConfigMap(config_map) => {
+                apply::config_maps::create(self, config_map, new_revision)
             }
+            ControllerAction::UpdateConfigMap(config_map) => {
+                apply::config_maps::update(self, config_map, new_revision)
+            }
+            ControllerAction::DeleteConfigMap(config_map) => {
+                apply::config_maps::delete(self, config_map)
+            }
+            ControllerAction::CreateSecret(secret) => {
+                apply::secrets::create(self, secret, new_revision)
+            }
+            ControllerAction::UpdateSecret(secret) => {
+                apply::secrets::update(self, secret, new_revision)
+            }
+            ControllerAction::DeleteSecret(secret) => apply::secrets::delete(self, secret),
             ControllerAction::UpdateJobStatus(job) => {
-                self.jobs.update(job, new_revision).map_err(|_| ())?;
+                apply::jobs::update_status(self, job, new_revision)
             }
-            ControllerAction::UpdateJob(job) => {
-                self.jobs.update(job, new_revision).map_err(|_| ())?;
+            ControllerAction::UpdateJob(job) => apply::jobs::update(self, job, new_revision),
+            ControllerAction::PatchJob(namespace, name, patch) => {
+                apply::jobs::patch(self, &namespace, &name, &patch, new_revision)
+            }
+            ControllerAction::CreateJob(job) => apply::jobs::create(self, job, new_revision),
+            ControllerAction::DeleteJob(job) => apply::jobs::delete(self, job, new_revision),
+            ControllerAction::UpdateCronJobStatus(cronjob) => {
+                apply::cronjobs::update_status(self, cronjob, new_revision)
+            }
+            ControllerAction::DeleteCronJob(cronjob) => apply::cronjobs::delete(self, cronjob),
+            ControllerAction::ScaleDeployment(scale) => {
+                apply::deployments::scale(self, scale, new_revision)
+            }
+            ControllerAction::RollbackDeployment(namespace, rollback) => {
+                apply::deployments::rollback(self, &namespace, rollback, new_revision)
+            }
+            ControllerAction::UpdateReplicationControllerStatus(rc) => {
+                apply::replication_controllers::update_status(self, rc, new_revision)
+            }
+            ControllerAction::DeleteReplicationController(rc) => {
+                apply::replication_controllers::delete(self, rc)
+            }
+            ControllerAction::ScaleReplicaSet(scale) => {
+                apply::replicasets::scale(self, scale, new_revision)
+            }
+            ControllerAction::ScaleStatefulSet(scale) => {
+                apply::statefulsets::scale(self, scale, new_revision)
+            }
+            ControllerAction::UpdateHorizontalPodAutoscalerStatus(hpa) => {
+                apply::horizontal_pod_autoscalers::update_status(self, hpa, new_revision)
+            }
+            ControllerAction::DeleteHorizontalPodAutoscaler(hpa) => {
+                apply::horizontal_pod_autoscalers::delete(self, hpa)
+            }
+            ControllerAction::SoftDeleteNamespace(namespace) => {
+                apply::namespaces::soft_delete(self, namespace, new_revision)
+            }
+            ControllerAction::FinalizeNamespace(namespace) => {
+                apply::namespaces::finalize(self, namespace, new_revision)
+            }
+            ControllerAction::Transaction(writes) => {
+                // stopping at the first write that fails, the state being discarded by the caller
+                for write in writes {
+                    self.apply_operation_inner(write, new_revision.clone())?;
+                }
+                Ok(())
+            }
+            ControllerAction::AdvanceClock(seconds) => apply::clock::advance(self, seconds),
+            ControllerAction::UpdateMetric(namespace, name, utilization) => {
+                apply::metrics::update(self, namespace, name, utilization)
             }
-        }
-        Ok(())
-    }
-
-    fn fill_name<T: Meta>(&self, res: &mut T) {
-        if res.metadata().name.is_empty() && !res.metadata().generate_name.is_empty() {
-            let rev = &self.revision;
-            res.metadata_mut().name = format!("{}{}", res.metadata().generate_name, rev);
         }
     }
 
@@ -0,0 +1,147 @@
+//! Application of [`ControllerAction`](crate::abstract_model::ControllerAction)s to a
+//! [`StateView`], split by the resource kind that they target.
+//!
+//! Each applier either fully applies its change or returns an error, in which case the caller is
+//! expected to discard the partially modified state.
+
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::warn;
+
+use crate::api::patch::Patch;
+use crate::resources::{Meta, Spec, Time};
+
+use super::{resources::Resources, revision::Revision, StateView};
+
+pub mod clock;
+pub mod config_maps;
+pub mod controller_revisions;
+pub mod cronjobs;
+pub mod deployments;
+pub mod endpoints;
+pub mod horizontal_pod_autoscalers;
+pub mod jobs;
+pub mod leases;
+pub mod metrics;
+pub mod namespaces;
+pub mod nodes;
+pub mod persistent_volume_claims;
+pub mod persistent_volumes;
+pub mod pods;
+pub mod replicasets;
+pub mod replication_controllers;
+pub mod secrets;
+pub mod services;
+pub mod statefulsets;
+
+/// An operation could not be applied to the state, such as updating a resource that does not exist
+/// or using an outdated resource version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyError;
+
+/// The result of applying a single operation.
+pub type ApplyResult = Result<(), ApplyError>;
+
+/// Prepare a resource for creation: set the uid from the current revision, the creation timestamp
+/// from the cluster clock and generate a name if only a `generate_name` prefix was given.
+///
+/// A creation timestamp that is already set is kept, being one that the model stamped from the
+/// skewed clock of the api server that the write went through.
+///
+/// Fails if the resource would be created in a namespace that is missing or being deleted.
+fn prepare_create<T: Meta>(state: &StateView, res: &mut T) -> ApplyResult {
+    let namespace = match res.metadata().namespace.as_str() {
+        "" => "default",
+        namespace => namespace,
+    };
+    if T::NAMESPACED && !state.namespace_accepts_creates(namespace) {
+        warn!(
+            namespace,
+            "Tried to create resource in an inactive namespace"
+        );
+        return Err(ApplyError);
+    }
+    res.metadata_mut().uid = state.revision.to_string();
+    res.metadata_mut()
+        .creation_timestamp
+        .get_or_insert_with(|| state.now());
+    fill_name(&state.revision, res);
+    Ok(())
+}
+
+fn fill_name<T: Meta>(revision: &Revision, res: &mut T) {
+    if res.metadata().name.is_empty() && !res.metadata().generate_name.is_empty() {
+        res.metadata_mut().name = format!("{}{}", res.metadata().generate_name, revision);
+    }
+}
+
+/// Delete the resource, or only mark it for deletion while it still has finalizers, leaving it to
+/// be removed once the last of them is.
+fn delete_or_terminate<T: Meta + Spec + Clone + PartialEq>(
+    resources: &mut Resources<T>,
+    res: &T,
+    now: Time,
+    new_revision: Revision,
+) -> ApplyResult {
+    let (namespace, name) = (&res.metadata().namespace, &res.metadata().name);
+    let Some(existing) = resources
+        .get_in(namespace, name)
+        .filter(|r| r.metadata().uid == res.metadata().uid)
+    else {
+        return Ok(());
+    };
+    if existing.metadata().finalizers.is_empty() {
+        resources.remove(res);
+        return Ok(());
+    }
+    if existing.metadata().deletion_timestamp.is_some() {
+        return Ok(());
+    }
+    let mut terminating = existing.clone();
+    terminating.metadata_mut().deletion_timestamp = Some(now);
+    resources
+        .update(terminating, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+/// Remove the resource if it is being deleted and has no finalizers left.
+fn remove_if_finalized<T: Meta + Spec + Clone>(
+    resources: &mut Resources<T>,
+    namespace: &str,
+    name: &str,
+) {
+    let Some(res) = resources.get_in(namespace, name) else {
+        return;
+    };
+    if res.metadata().deletion_timestamp.is_some() && res.metadata().finalizers.is_empty() {
+        let res = res.clone();
+        resources.remove(&res);
+    }
+}
+
+/// The resource with the patch applied to its current version.
+///
+/// Fails if the patch is invalid, changes the identity of the resource or sets a resource version
+/// other than the current one, the only way a patch can conflict with other writes.
+pub(crate) fn patched<T: Meta + Serialize + DeserializeOwned>(
+    current: Option<&T>,
+    patch: &Patch,
+) -> Result<T, ApplyError> {
+    let Some(current) = current else {
+        warn!("Tried to patch a resource that doesn't exist");
+        return Err(ApplyError);
+    };
+    let res = patch.apply(current).map_err(|error| {
+        warn!(?error, "Invalid patch");
+        ApplyError
+    })?;
+    let (new, old) = (res.metadata(), current.metadata());
+    if new.name != old.name || new.namespace != old.namespace || new.uid != old.uid {
+        warn!("Tried to change the identity of a resource with a patch");
+        return Err(ApplyError);
+    }
+    if new.resource_version != old.resource_version {
+        warn!("Tried to patch a resource with an outdated resource version");
+        return Err(ApplyError);
+    }
+    Ok(res)
+}
@@ -0,0 +1,9 @@
+use crate::state::StateView;
+
+use super::ApplyResult;
+
+/// Move the cluster clock forward by the given number of seconds.
+pub fn advance(state: &mut StateView, seconds: u64) -> ApplyResult {
+    state.clock += seconds;
+    Ok(())
+}
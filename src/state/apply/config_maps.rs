@@ -0,0 +1,30 @@
+use crate::{
+    resources::ConfigMap,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn create(
+    state: &mut StateView,
+    mut config_map: ConfigMap,
+    new_revision: Revision,
+) -> ApplyResult {
+    prepare_create(state, &mut config_map)?;
+    state
+        .config_maps
+        .create(config_map, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, config_map: ConfigMap, new_revision: Revision) -> ApplyResult {
+    state
+        .config_maps
+        .update(config_map, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, config_map: ConfigMap) -> ApplyResult {
+    state.config_maps.remove(&config_map);
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::{
+    resources::ControllerRevision,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn create(
+    state: &mut StateView,
+    mut cr: ControllerRevision,
+    new_revision: Revision,
+) -> ApplyResult {
+    prepare_create(state, &mut cr)?;
+    state
+        .controller_revisions
+        .create(cr, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(
+    state: &mut StateView,
+    cr: ControllerRevision,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .controller_revisions
+        .update(cr, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, cr: ControllerRevision) -> ApplyResult {
+    state.controller_revisions.remove(&cr);
+    Ok(())
+}
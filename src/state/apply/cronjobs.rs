@@ -0,0 +1,22 @@
+use crate::{
+    resources::CronJob,
+    state::{revision::Revision, StateView},
+};
+
+use super::{ApplyError, ApplyResult};
+
+pub fn update_status(
+    state: &mut StateView,
+    cronjob: CronJob,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .cronjobs
+        .update_status(cronjob, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, cronjob: CronJob) -> ApplyResult {
+    state.cronjobs.remove(&cronjob);
+    Ok(())
+}
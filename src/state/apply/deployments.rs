@@ -0,0 +1,111 @@
+use crate::{
+    api::patch::Patch,
+    controller::deployment::DEPRECATED_ROLLBACK_TO,
+    resources::{Deployment, DeploymentRollback, Scale},
+    state::{revision::Revision, StateView},
+};
+
+use super::{
+    delete_or_terminate, patched, prepare_create, remove_if_finalized, ApplyError, ApplyResult,
+};
+
+pub fn create(
+    state: &mut StateView,
+    mut deployment: Deployment,
+    new_revision: Revision,
+) -> ApplyResult {
+    prepare_create(state, &mut deployment)?;
+    state
+        .deployments
+        .create(deployment, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(
+    state: &mut StateView,
+    deployment: Deployment,
+    new_revision: Revision,
+) -> ApplyResult {
+    let (namespace, name) = (
+        deployment.metadata.namespace.clone(),
+        deployment.metadata.name.clone(),
+    );
+    state
+        .deployments
+        .update(deployment, new_revision)
+        .map_err(|_| ApplyError)?;
+    remove_if_finalized(&mut state.deployments, &namespace, &name);
+    Ok(())
+}
+
+pub fn update_status(
+    state: &mut StateView,
+    deployment: Deployment,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .deployments
+        .update_status(deployment, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+/// Update just the replicas of the deployment, as through its scale subresource.
+pub fn scale(state: &mut StateView, scale: Scale, new_revision: Revision) -> ApplyResult {
+    let mut deployment = state
+        .deployments
+        .get_in(&scale.metadata.namespace, &scale.metadata.name)
+        .ok_or(ApplyError)?
+        .clone();
+    // the scale was read at some resource version so conflicts with any newer writes
+    deployment.metadata.resource_version = scale.metadata.resource_version;
+    deployment.spec.replicas = scale.spec.replicas;
+    update(state, deployment, new_revision)
+}
+
+/// Ask the controller to roll the deployment back, as through its rollback subresource.
+///
+/// As in the API server this only records the revision to roll back to in the deprecated
+/// annotation, the controller does the rollback itself.
+pub fn rollback(
+    state: &mut StateView,
+    namespace: &str,
+    rollback: DeploymentRollback,
+    new_revision: Revision,
+) -> ApplyResult {
+    let mut deployment = state
+        .deployments
+        .get_in(namespace, &rollback.name)
+        .ok_or(ApplyError)?
+        .clone();
+    deployment
+        .metadata
+        .annotations
+        .extend(rollback.updated_annotations);
+    deployment.metadata.annotations.insert(
+        DEPRECATED_ROLLBACK_TO.to_owned(),
+        rollback.rollback_to.revision.to_string(),
+    );
+    update(state, deployment, new_revision)
+}
+
+/// Delete the deployment, leaving it terminating until its finalizers are removed.
+pub fn delete(
+    state: &mut StateView,
+    deployment: Deployment,
+    new_revision: Revision,
+) -> ApplyResult {
+    let now = state.now();
+    delete_or_terminate(&mut state.deployments, &deployment, now, new_revision)
+}
+
+/// Patch the current version of the deployment.
+pub fn patch(
+    state: &mut StateView,
+    namespace: &str,
+    name: &str,
+    patch: &Patch,
+    new_revision: Revision,
+) -> ApplyResult {
+    let deployment = patched(state.deployments.get_in(namespace, name), patch)?;
+    update(state, deployment, new_revision)
+}
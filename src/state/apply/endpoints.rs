@@ -0,0 +1,30 @@
+use crate::{
+    resources::Endpoints,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn create(
+    state: &mut StateView,
+    mut endpoints: Endpoints,
+    new_revision: Revision,
+) -> ApplyResult {
+    prepare_create(state, &mut endpoints)?;
+    state
+        .endpoints
+        .create(endpoints, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, endpoints: Endpoints, new_revision: Revision) -> ApplyResult {
+    state
+        .endpoints
+        .update(endpoints, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, endpoints: Endpoints) -> ApplyResult {
+    state.endpoints.remove(&endpoints);
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::{
+    resources::HorizontalPodAutoscaler,
+    state::{revision::Revision, StateView},
+};
+
+use super::{ApplyError, ApplyResult};
+
+pub fn update_status(
+    state: &mut StateView,
+    hpa: HorizontalPodAutoscaler,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .horizontal_pod_autoscalers
+        .update_status(hpa, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, hpa: HorizontalPodAutoscaler) -> ApplyResult {
+    state.horizontal_pod_autoscalers.remove(&hpa);
+    Ok(())
+}
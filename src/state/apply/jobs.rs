@@ -0,0 +1,49 @@
+use crate::{
+    api::patch::Patch,
+    resources::Job,
+    state::{revision::Revision, StateView},
+};
+
+use super::{
+    delete_or_terminate, patched, prepare_create, remove_if_finalized, ApplyError, ApplyResult,
+};
+
+pub fn create(state: &mut StateView, mut job: Job, new_revision: Revision) -> ApplyResult {
+    prepare_create(state, &mut job)?;
+    state.jobs.create(job, new_revision).map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, job: Job, new_revision: Revision) -> ApplyResult {
+    let (namespace, name) = (job.metadata.namespace.clone(), job.metadata.name.clone());
+    state
+        .jobs
+        .update(job, new_revision)
+        .map_err(|_| ApplyError)?;
+    remove_if_finalized(&mut state.jobs, &namespace, &name);
+    Ok(())
+}
+
+pub fn update_status(state: &mut StateView, job: Job, new_revision: Revision) -> ApplyResult {
+    state
+        .jobs
+        .update_status(job, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+/// Delete the job, leaving it terminating until its finalizers are removed.
+pub fn delete(state: &mut StateView, job: Job, new_revision: Revision) -> ApplyResult {
+    let now = state.now();
+    delete_or_terminate(&mut state.jobs, &job, now, new_revision)
+}
+
+/// Patch the current version of the job.
+pub fn patch(
+    state: &mut StateView,
+    namespace: &str,
+    name: &str,
+    patch: &Patch,
+    new_revision: Revision,
+) -> ApplyResult {
+    let job = patched(state.jobs.get_in(namespace, name), patch)?;
+    update(state, job, new_revision)
+}
@@ -0,0 +1,21 @@
+use crate::{
+    resources::Lease,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn create(state: &mut StateView, mut lease: Lease, new_revision: Revision) -> ApplyResult {
+    prepare_create(state, &mut lease)?;
+    state
+        .leases
+        .create(lease, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, lease: Lease, new_revision: Revision) -> ApplyResult {
+    state
+        .leases
+        .update(lease, new_revision)
+        .map_err(|_| ApplyError)
+}
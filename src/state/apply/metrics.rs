@@ -0,0 +1,15 @@
+use crate::state::StateView;
+
+use super::ApplyResult;
+
+/// Record a new utilization reading from the metrics source for the autoscaler with the given
+/// namespace and name.
+pub fn update(
+    state: &mut StateView,
+    namespace: String,
+    name: String,
+    utilization: u32,
+) -> ApplyResult {
+    state.metrics.insert((namespace, name), utilization);
+    Ok(())
+}
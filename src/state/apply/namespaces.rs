@@ -0,0 +1,60 @@
+use tracing::warn;
+
+use crate::{
+    resources::{Namespace, NamespacePhase},
+    state::{revision::Revision, StateView},
+};
+
+use super::{ApplyError, ApplyResult};
+
+/// Mark the namespace for deletion, leaving it to the namespace controller to remove its contents
+/// and finalize it.
+pub fn soft_delete(
+    state: &mut StateView,
+    mut namespace: Namespace,
+    new_revision: Revision,
+) -> ApplyResult {
+    if namespace.metadata.name == Namespace::DEFAULT {
+        warn!("Tried to delete the default namespace");
+        return Err(ApplyError);
+    }
+    namespace.metadata.deletion_timestamp = Some(state.now());
+    namespace.status.phase = NamespacePhase::Terminating;
+    state
+        .namespaces
+        .update(namespace, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+/// Set the finalizers in the spec of the namespace, through the finalize subresource.
+///
+/// Unlike other updates this is allowed on terminating namespaces, once no finalizers remain
+/// the namespace gets removed.
+pub fn finalize(
+    state: &mut StateView,
+    namespace: Namespace,
+    new_revision: Revision,
+) -> ApplyResult {
+    let existing = state
+        .namespaces
+        .get(&namespace.metadata.name)
+        .ok_or(ApplyError)?;
+    if existing.metadata.uid != namespace.metadata.uid
+        || existing.metadata.resource_version != namespace.metadata.resource_version
+    {
+        return Err(ApplyError);
+    }
+    let mut finalized = existing.clone();
+    finalized.spec.finalizers = namespace.spec.finalizers;
+    state.namespaces.remove(&finalized);
+    if finalized.metadata.deletion_timestamp.is_some()
+        && finalized.spec.finalizers.is_empty()
+        && finalized.metadata.finalizers.is_empty()
+    {
+        return Ok(());
+    }
+    state
+        .namespaces
+        .create(finalized, new_revision)
+        .map_err(|_| ApplyError)
+}
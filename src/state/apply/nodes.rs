@@ -0,0 +1,54 @@
+use crate::{
+    resources::{
+        ConditionStatus, Node, NodeCondition, NodeConditionType, NodeSpec, NodeStatus,
+        ResourceQuantities,
+    },
+    state::{revision::Revision, StateView},
+    utils,
+};
+
+use super::{ApplyError, ApplyResult};
+
+/// A node joins the cluster, registering itself as ready with the given capacity.
+pub fn join(
+    state: &mut StateView,
+    name: String,
+    capacity: ResourceQuantities,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .nodes
+        .create(
+            Node {
+                metadata: utils::metadata(name),
+                spec: NodeSpec {
+                    taints: Vec::new(),
+                    unschedulable: false,
+                },
+                status: NodeStatus {
+                    capacity: capacity.clone(),
+                    allocatable: Some(capacity),
+                    conditions: vec![NodeCondition {
+                        r#type: NodeConditionType::Ready,
+                        status: ConditionStatus::True,
+                        ..Default::default()
+                    }],
+                },
+            },
+            new_revision,
+        )
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, node: Node, new_revision: Revision) -> ApplyResult {
+    state
+        .nodes
+        .update(node, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+/// Remove a node from the cluster.
+pub fn delete(state: &mut StateView, node: Node) -> ApplyResult {
+    state.nodes.remove(&node);
+    Ok(())
+}
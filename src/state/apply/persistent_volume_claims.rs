@@ -0,0 +1,45 @@
+use crate::{
+    resources::PersistentVolumeClaim,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn create(
+    state: &mut StateView,
+    mut pvc: PersistentVolumeClaim,
+    new_revision: Revision,
+) -> ApplyResult {
+    prepare_create(state, &mut pvc)?;
+    state
+        .persistent_volume_claims
+        .create(pvc, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(
+    state: &mut StateView,
+    pvc: PersistentVolumeClaim,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .persistent_volume_claims
+        .update(pvc, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, pvc: PersistentVolumeClaim) -> ApplyResult {
+    state.persistent_volume_claims.remove(&pvc);
+    Ok(())
+}
+
+pub fn update_status(
+    state: &mut StateView,
+    pvc: PersistentVolumeClaim,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .persistent_volume_claims
+        .update_status(pvc, new_revision)
+        .map_err(|_| ApplyError)
+}
@@ -0,0 +1,36 @@
+use crate::{
+    resources::PersistentVolume,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn update(state: &mut StateView, pv: PersistentVolume, new_revision: Revision) -> ApplyResult {
+    state
+        .persistent_volumes
+        .update(pv, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update_status(
+    state: &mut StateView,
+    pv: PersistentVolume,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .persistent_volumes
+        .update_status(pv, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn create(
+    state: &mut StateView,
+    mut pv: PersistentVolume,
+    new_revision: Revision,
+) -> ApplyResult {
+    prepare_create(state, &mut pv)?;
+    state
+        .persistent_volumes
+        .create(pv, new_revision)
+        .map_err(|_| ApplyError)
+}
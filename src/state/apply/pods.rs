@@ -0,0 +1,118 @@
+use tracing::warn;
+
+use crate::{
+    api::patch::Patch,
+    resources::Pod,
+    state::{revision::Revision, StateView},
+};
+
+use super::{patched, prepare_create, ApplyError, ApplyResult};
+
+/// The grace period for pods that don't set their own.
+pub const DEFAULT_TERMINATION_GRACE_PERIOD_SECONDS: u64 = 30;
+
+pub fn create(state: &mut StateView, mut pod: Pod, new_revision: Revision) -> ApplyResult {
+    prepare_create(state, &mut pod)?;
+    resolve_priority(state, &mut pod)?;
+    pod.status.qos_class = Some(pod.qos_class());
+    state.pods.create(pod, new_revision).map_err(|_| ApplyError)
+}
+
+/// Fill in the priority of the pod from its priority class, or the global default one, as the
+/// priority admission plugin does.
+///
+/// Fails if the pod names a priority class that doesn't exist.
+fn resolve_priority(state: &StateView, pod: &mut Pod) -> ApplyResult {
+    let class = match &pod.spec.priority_class_name {
+        Some(name) => match state.priority_classes.get(name) {
+            Some(class) => Some(class),
+            None => {
+                warn!(
+                    class = name,
+                    "Tried to create pod with a missing priority class"
+                );
+                return Err(ApplyError);
+            }
+        },
+        None => state.priority_classes.iter().find(|c| c.global_default),
+    };
+    if let Some(class) = class {
+        pod.spec.priority_class_name = Some(class.metadata.name.clone());
+        pod.spec.priority = Some(class.value);
+        pod.spec.preemption_policy = Some(class.preemption_policy);
+    }
+    Ok(())
+}
+
+pub fn update(state: &mut StateView, pod: Pod, new_revision: Revision) -> ApplyResult {
+    let (namespace, name) = (pod.metadata.namespace.clone(), pod.metadata.name.clone());
+    state
+        .pods
+        .update(pod, new_revision)
+        .map_err(|_| ApplyError)?;
+    remove_if_finalized(state, &namespace, &name);
+    Ok(())
+}
+
+/// Mark the pod for deletion with its grace period, leaving it to the node (or podgc) to stop its
+/// containers and then remove it for good.
+pub fn soft_delete(state: &mut StateView, mut pod: Pod, new_revision: Revision) -> ApplyResult {
+    pod.metadata.deletion_timestamp = Some(state.now());
+    pod.metadata.deletion_grace_period_seconds = Some(
+        pod.spec
+            .termination_grace_period_seconds
+            .unwrap_or(DEFAULT_TERMINATION_GRACE_PERIOD_SECONDS),
+    );
+    state.pods.update(pod, new_revision).map_err(|_| ApplyError)
+}
+
+/// Delete the pod without any grace period, removing it from the state entirely.
+///
+/// Pods that still have finalizers are instead left terminating with no grace period and are
+/// removed once their last finalizer is.
+pub fn hard_delete(state: &mut StateView, pod: Pod, new_revision: Revision) -> ApplyResult {
+    let Some(existing) = state
+        .pods
+        .get_in(&pod.metadata.namespace, &pod.metadata.name)
+        .filter(|p| p.metadata.uid == pod.metadata.uid)
+    else {
+        return Ok(());
+    };
+    if existing.metadata.finalizers.is_empty() {
+        state.pods.remove(&pod);
+        return Ok(());
+    }
+    if existing.metadata.deletion_grace_period_seconds == Some(0) {
+        return Ok(());
+    }
+    let mut pod = existing.clone();
+    pod.metadata.deletion_timestamp.get_or_insert_with(now);
+    pod.metadata.deletion_grace_period_seconds = Some(0);
+    state.pods.update(pod, new_revision).map_err(|_| ApplyError)
+}
+
+/// Remove the pod if it has been deleted without a grace period and has no finalizers left.
+fn remove_if_finalized(state: &mut StateView, namespace: &str, name: &str) {
+    let Some(pod) = state.pods.get_in(namespace, name) else {
+        return;
+    };
+    if pod.metadata.deletion_timestamp.is_some()
+        && pod.metadata.deletion_grace_period_seconds == Some(0)
+        && pod.metadata.finalizers.is_empty()
+    {
+        let pod = pod.clone();
+        state.pods.remove(&pod);
+    }
+}
+
+/// Patch the current version of the pod.
+pub fn patch(
+    state: &mut StateView,
+    namespace: &str,
+    name: &str,
+    patch: &Patch,
+    new_revision: Revision,
+) -> ApplyResult {
+    let pod = patched(state.pods.get_in(namespace, name), patch)?;
+    update(state, pod, new_revision)
+}
@@ -0,0 +1,59 @@
+use crate::{
+    api::patch::Patch,
+    resources::{ReplicaSet, Scale},
+    state::{revision::Revision, StateView},
+};
+
+use super::{patched, prepare_create, ApplyError, ApplyResult};
+
+pub fn create(state: &mut StateView, mut rs: ReplicaSet, new_revision: Revision) -> ApplyResult {
+    prepare_create(state, &mut rs)?;
+    state
+        .replicasets
+        .create(rs, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, rs: ReplicaSet, new_revision: Revision) -> ApplyResult {
+    state
+        .replicasets
+        .update(rs, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update_status(state: &mut StateView, rs: ReplicaSet, new_revision: Revision) -> ApplyResult {
+    state
+        .replicasets
+        .update_status(rs, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, rs: ReplicaSet) -> ApplyResult {
+    state.replicasets.remove(&rs);
+    Ok(())
+}
+
+/// Update just the replicas of the replicaset, as through its scale subresource.
+pub fn scale(state: &mut StateView, scale: Scale, new_revision: Revision) -> ApplyResult {
+    let mut rs = state
+        .replicasets
+        .get_in(&scale.metadata.namespace, &scale.metadata.name)
+        .ok_or(ApplyError)?
+        .clone();
+    // the scale was read at some resource version so conflicts with any newer writes
+    rs.metadata.resource_version = scale.metadata.resource_version;
+    rs.spec.replicas = Some(scale.spec.replicas);
+    update(state, rs, new_revision)
+}
+
+/// Patch the current version of the rs.
+pub fn patch(
+    state: &mut StateView,
+    namespace: &str,
+    name: &str,
+    patch: &Patch,
+    new_revision: Revision,
+) -> ApplyResult {
+    let rs = patched(state.replicasets.get_in(namespace, name), patch)?;
+    update(state, rs, new_revision)
+}
@@ -0,0 +1,22 @@
+use crate::{
+    resources::ReplicationController,
+    state::{revision::Revision, StateView},
+};
+
+use super::{ApplyError, ApplyResult};
+
+pub fn update_status(
+    state: &mut StateView,
+    rc: ReplicationController,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .replication_controllers
+        .update_status(rc, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, rc: ReplicationController) -> ApplyResult {
+    state.replication_controllers.remove(&rc);
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use crate::{
+    resources::Secret,
+    state::{revision::Revision, StateView},
+};
+
+use super::{prepare_create, ApplyError, ApplyResult};
+
+pub fn create(state: &mut StateView, mut secret: Secret, new_revision: Revision) -> ApplyResult {
+    prepare_create(state, &mut secret)?;
+    state
+        .secrets
+        .create(secret, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, secret: Secret, new_revision: Revision) -> ApplyResult {
+    state
+        .secrets
+        .update(secret, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn delete(state: &mut StateView, secret: Secret) -> ApplyResult {
+    state.secrets.remove(&secret);
+    Ok(())
+}
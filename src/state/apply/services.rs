@@ -0,0 +1,8 @@
+use crate::{resources::Service, state::StateView};
+
+use super::ApplyResult;
+
+pub fn delete(state: &mut StateView, service: Service) -> ApplyResult {
+    state.services.remove(&service);
+    Ok(())
+}
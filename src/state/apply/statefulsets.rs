@@ -0,0 +1,69 @@
+use crate::{
+    api::patch::Patch,
+    resources::{Scale, StatefulSet},
+    state::{revision::Revision, StateView},
+};
+
+use super::{
+    delete_or_terminate, patched, prepare_create, remove_if_finalized, ApplyError, ApplyResult,
+};
+
+pub fn create(state: &mut StateView, mut sts: StatefulSet, new_revision: Revision) -> ApplyResult {
+    prepare_create(state, &mut sts)?;
+    state
+        .statefulsets
+        .create(sts, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+pub fn update(state: &mut StateView, sts: StatefulSet, new_revision: Revision) -> ApplyResult {
+    let (namespace, name) = (sts.metadata.namespace.clone(), sts.metadata.name.clone());
+    state
+        .statefulsets
+        .update(sts, new_revision)
+        .map_err(|_| ApplyError)?;
+    remove_if_finalized(&mut state.statefulsets, &namespace, &name);
+    Ok(())
+}
+
+pub fn update_status(
+    state: &mut StateView,
+    sts: StatefulSet,
+    new_revision: Revision,
+) -> ApplyResult {
+    state
+        .statefulsets
+        .update_status(sts, new_revision)
+        .map_err(|_| ApplyError)
+}
+
+/// Update just the replicas of the statefulset, as through its scale subresource.
+pub fn scale(state: &mut StateView, scale: Scale, new_revision: Revision) -> ApplyResult {
+    let mut sts = state
+        .statefulsets
+        .get_in(&scale.metadata.namespace, &scale.metadata.name)
+        .ok_or(ApplyError)?
+        .clone();
+    // the scale was read at some resource version so conflicts with any newer writes
+    sts.metadata.resource_version = scale.metadata.resource_version;
+    sts.spec.replicas = Some(scale.spec.replicas);
+    update(state, sts, new_revision)
+}
+
+/// Delete the statefulset, leaving it terminating until its finalizers are removed.
+pub fn delete(state: &mut StateView, sts: StatefulSet, new_revision: Revision) -> ApplyResult {
+    let now = state.now();
+    delete_or_terminate(&mut state.statefulsets, &sts, now, new_revision)
+}
+
+/// Patch the current version of the sts.
+pub fn patch(
+    state: &mut StateView,
+    namespace: &str,
+    name: &str,
+    patch: &Patch,
+    new_revision: Revision,
+) -> ApplyResult {
+    let sts = patched(state.statefulsets.get_in(namespace, name), patch)?;
+    update(state, sts, new_revision)
+}
@@ -8,7 +8,11 @@ use std::{
 use crate::abstract_model::Change;
 
 use self::{
-    causal::CausalHistory, synchronous::SynchronousHistory,
+    causal::CausalHistory,
+    eventual::{EventualHistory, ReplicaMerge},
+    partitioned::PartitionedHistory,
+    read_your_writes::ReadYourWritesHistory,
+    synchronous::SynchronousHistory,
     monotonic_session::MonotonicSessionHistory, optimistic::OptimisticLinearHistory,
     resettable_session::ResettableSessionHistory,
 };
@@ -16,6 +20,9 @@ use self::{
 use super::{revision::Revision, RawState, StateView};
 
 pub mod causal;
+pub mod eventual;
+pub mod partitioned;
+pub mod read_your_writes;
 pub mod synchronous;
 pub mod monotonic_session;
 pub mod optimistic;
@@ -39,12 +46,27 @@ pub enum ConsistencySetup {
     /// Session consistency on reads.
     /// Linearizable writes.
     ResettableSession,
+    /// Work off any state that includes the last successful write of the controller, as with a
+    /// sticky connection to a single API server.
+    /// Read-your-writes on reads, without them being monotonic.
+    /// Linearizable writes.
+    ReadYourWrites,
     /// Optimistically apply changes without guarantee that they are committed.
     /// Optimistic reads.
     /// Optimistic writes.
     OptimisticLinear,
     /// Apply changes to a causal graph.
     Causal,
+    /// Apply changes to one of the given number of replicas, which only converge through explicit
+    /// anti-entropy.
+    /// Eventually consistent reads.
+    /// Eventually consistent writes.
+    Eventual(usize),
+    /// Apply changes to a store replicated over the given number of members with a leader, where
+    /// a minority of them can be partitioned from the rest.
+    /// Linearizable reads, stale through the partitioned member.
+    /// Linearizable writes, failing through the partitioned member.
+    Partitioned(usize),
 }
 
 impl Display for ConsistencySetup {
@@ -56,13 +78,59 @@ impl Display for ConsistencySetup {
                 ConsistencySetup::Synchronous => "synchronous",
                 ConsistencySetup::MonotonicSession => "monotonic-session",
                 ConsistencySetup::ResettableSession => "resettable-session",
+                ConsistencySetup::ReadYourWrites => "read-your-writes",
                 ConsistencySetup::OptimisticLinear => "optimistic-linear",
                 ConsistencySetup::Causal => "causal",
+                ConsistencySetup::Eventual(_) => "eventual",
+                ConsistencySetup::Partitioned(_) => "partitioned",
+            }
+        )
+    }
+}
+
+/// The guarantees a session gives a controller over the revisions it can work off, on top of the
+/// consistency level.
+///
+/// Only the session consistency levels let controllers read older revisions, so this makes no
+/// difference to the others.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SessionGuarantee {
+    /// Reads never go back to a revision before the last one read, but may not include the
+    /// controller's own writes.
+    #[default]
+    MonotonicReads,
+    /// Reads are monotonic and always include the controller's last successful write.
+    ReadYourWrites,
+}
+
+impl Display for SessionGuarantee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SessionGuarantee::MonotonicReads => "monotonic-reads",
+                SessionGuarantee::ReadYourWrites => "read-your-writes",
             }
         )
     }
 }
 
+/// How much of the history of past states to keep.
+///
+/// Revisions that a controller can still read, or that its session or watch cache refers to, are
+/// always kept so that compaction never changes what controllers observe.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compaction {
+    /// Keep every revision.
+    #[default]
+    Disabled,
+    /// Keep the given number of latest revisions, along with any still referenced.
+    KeepLast(usize),
+    /// Only keep the latest revision and those still referenced.
+    Referenced,
+}
+
 pub trait History {
     fn add_change(&mut self, change: Change);
 
@@ -71,6 +139,11 @@ pub trait History {
     fn state_at(&self, revision: &Revision) -> Cow<'_, StateView>;
 
     fn valid_revisions(&self, min_revision: Option<&Revision>) -> Vec<Revision>;
+
+    /// All the revisions still stored, oldest first, whether or not a client could read them.
+    fn retained_revisions(&self) -> Vec<Revision> {
+        self.valid_revisions(None)
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -84,10 +157,19 @@ pub enum StateHistory {
     /// Session consistency on reads.
     /// Linearizable writes.
     ResettableSession(ResettableSessionHistory),
+    /// Read-your-writes on reads.
+    /// Linearizable writes.
+    ReadYourWrites(ReadYourWritesHistory),
     /// Optimistic reads.
     /// Optimistic writes.
     OptimisticLinear(OptimisticLinearHistory),
     Causal(CausalHistory),
+    /// Eventually consistent reads.
+    /// Eventually consistent writes.
+    Eventual(EventualHistory),
+    /// Linearizable reads, stale through the partitioned member.
+    /// Linearizable writes, failing through the partitioned member.
+    Partitioned(PartitionedHistory),
 }
 
 impl Default for StateHistory {
@@ -108,10 +190,89 @@ impl StateHistory {
             ConsistencySetup::ResettableSession => {
                 Self::ResettableSession(ResettableSessionHistory::new(initial_state))
             }
+            ConsistencySetup::ReadYourWrites => {
+                Self::ReadYourWrites(ReadYourWritesHistory::new(initial_state))
+            }
             ConsistencySetup::OptimisticLinear => {
                 Self::OptimisticLinear(OptimisticLinearHistory::new(initial_state))
             }
             ConsistencySetup::Causal => Self::Causal(CausalHistory::new(initial_state)),
+            ConsistencySetup::Eventual(replicas) => {
+                Self::Eventual(EventualHistory::new(initial_state, replicas))
+            }
+            ConsistencySetup::Partitioned(members) => {
+                Self::Partitioned(PartitionedHistory::new(initial_state, members))
+            }
+        }
+    }
+
+    /// The number of replicas that the state is spread over.
+    pub fn replicas(&self) -> usize {
+        match self {
+            StateHistory::Eventual(s) => s.replicas(),
+            _ => 1,
+        }
+    }
+
+    /// Merge the latest state of one replica into another, returning whether it changed anything.
+    ///
+    /// Only eventually consistent histories have replicas to merge.
+    pub fn merge_replicas(&mut self, from: usize, to: usize, merge: &ReplicaMerge) -> bool {
+        match self {
+            StateHistory::Eventual(s) => s.merge_replicas(from, to, merge),
+            _ => false,
+        }
+    }
+
+    /// The number of members of the store that can be partitioned from each other.
+    pub fn store_members(&self) -> usize {
+        match self {
+            StateHistory::Partitioned(s) => s.members(),
+            _ => 1,
+        }
+    }
+
+    /// The member of the store that is partitioned from the rest, if any.
+    pub fn partitioned_store_member(&self) -> Option<usize> {
+        match self {
+            StateHistory::Partitioned(s) => s.partitioned_member(),
+            _ => None,
+        }
+    }
+
+    /// Partition the member of the store from the rest, returning whether that was possible.
+    ///
+    /// Only partitioned histories have members to partition.
+    pub fn partition_store(&mut self, member: usize) -> bool {
+        match self {
+            StateHistory::Partitioned(s) => s.partition(member),
+            _ => false,
+        }
+    }
+
+    /// Heal the partition of the store, returning whether there was one.
+    pub fn heal_store(&mut self) -> bool {
+        match self {
+            StateHistory::Partitioned(s) => s.heal(),
+            _ => false,
+        }
+    }
+
+    /// Drop the states from before both the latest `keep` revisions and the oldest referenced
+    /// index.
+    ///
+    /// Causal, eventual and optimistic histories build on older states, and resettable sessions and
+    /// read-your-writes can read any of them, so those are left whole.
+    pub fn compact(&mut self, keep: usize, oldest_referenced: Option<usize>) {
+        match self {
+            StateHistory::Synchronous(s) => s.compact(keep, oldest_referenced),
+            StateHistory::MonotonicSession(s) => s.compact(keep, oldest_referenced),
+            StateHistory::Partitioned(s) => s.compact(keep, oldest_referenced),
+            StateHistory::ResettableSession(_)
+            | StateHistory::ReadYourWrites(_)
+            | StateHistory::OptimisticLinear(_)
+            | StateHistory::Causal(_)
+            | StateHistory::Eventual(_) => {}
         }
     }
 }
@@ -122,8 +283,11 @@ impl History for StateHistory {
             StateHistory::Synchronous(s) => s.add_change(change),
             StateHistory::MonotonicSession(s) => s.add_change(change),
             StateHistory::ResettableSession(s) => s.add_change(change),
+            StateHistory::ReadYourWrites(s) => s.add_change(change),
             StateHistory::OptimisticLinear(s) => s.add_change(change),
             StateHistory::Causal(s) => s.add_change(change),
+            StateHistory::Eventual(s) => s.add_change(change),
+            StateHistory::Partitioned(s) => s.add_change(change),
         }
     }
 
@@ -132,8 +296,11 @@ impl History for StateHistory {
             StateHistory::Synchronous(s) => s.max_revision(),
             StateHistory::MonotonicSession(s) => s.max_revision(),
             StateHistory::ResettableSession(s) => s.max_revision(),
+            StateHistory::ReadYourWrites(s) => s.max_revision(),
             StateHistory::OptimisticLinear(s) => s.max_revision(),
             StateHistory::Causal(s) => s.max_revision(),
+            StateHistory::Eventual(s) => s.max_revision(),
+            StateHistory::Partitioned(s) => s.max_revision(),
         }
     }
 
@@ -142,8 +309,11 @@ impl History for StateHistory {
             StateHistory::Synchronous(s) => s.state_at(revision),
             StateHistory::MonotonicSession(s) => s.state_at(revision),
             StateHistory::ResettableSession(s) => s.state_at(revision),
+            StateHistory::ReadYourWrites(s) => s.state_at(revision),
             StateHistory::OptimisticLinear(s) => s.state_at(revision),
             StateHistory::Causal(s) => s.state_at(revision),
+            StateHistory::Eventual(s) => s.state_at(revision),
+            StateHistory::Partitioned(s) => s.state_at(revision),
         }
     }
 
@@ -152,8 +322,24 @@ impl History for StateHistory {
             StateHistory::Synchronous(s) => s.valid_revisions(min_revision),
             StateHistory::MonotonicSession(s) => s.valid_revisions(min_revision),
             StateHistory::ResettableSession(s) => s.valid_revisions(min_revision),
+            StateHistory::ReadYourWrites(s) => s.valid_revisions(min_revision),
             StateHistory::OptimisticLinear(s) => s.valid_revisions(min_revision),
             StateHistory::Causal(s) => s.valid_revisions(min_revision),
+            StateHistory::Eventual(s) => s.valid_revisions(min_revision),
+            StateHistory::Partitioned(s) => s.valid_revisions(min_revision),
+        }
+    }
+
+    fn retained_revisions(&self) -> Vec<Revision> {
+        match self {
+            StateHistory::Synchronous(s) => s.retained_revisions(),
+            StateHistory::MonotonicSession(s) => s.retained_revisions(),
+            StateHistory::ResettableSession(s) => s.retained_revisions(),
+            StateHistory::ReadYourWrites(s) => s.retained_revisions(),
+            StateHistory::OptimisticLinear(s) => s.retained_revisions(),
+            StateHistory::Causal(s) => s.retained_revisions(),
+            StateHistory::Eventual(s) => s.retained_revisions(),
+            StateHistory::Partitioned(s) => s.retained_revisions(),
         }
     }
 }
@@ -175,6 +361,41 @@ impl<T> DerefMut for StatesVec<T> {
     }
 }
 
+impl<T: Default + PartialEq> StatesVec<T> {
+    /// Replace the states before both the latest `keep` and the oldest referenced index with
+    /// placeholders, so that later states keep their index but the compacted ones no longer take up
+    /// space or tell apart states that only differ in their past.
+    pub fn compact(&mut self, keep: usize, oldest_referenced: Option<usize>) {
+        let before = self
+            .len()
+            .saturating_sub(keep.max(1))
+            .min(oldest_referenced.unwrap_or(usize::MAX));
+        let placeholder = Arc::new(T::default());
+        for i in 0..before {
+            // leave those compacted before alone to save copying them
+            if self.0[i] != placeholder {
+                self.0[i] = Arc::clone(&placeholder);
+            }
+        }
+    }
+}
+
+impl StatesVec {
+    /// The revisions of the states, oldest first, skipping the placeholders left by compaction.
+    ///
+    /// THEMELIOS: an initial state with nothing in it looks like a placeholder so is skipped too,
+    /// unless it is the latest.
+    pub fn retained_revisions(&self) -> Vec<Revision> {
+        let placeholder = StateView::default();
+        let latest = self.len() - 1;
+        self.iter()
+            .enumerate()
+            .filter(|(i, s)| *i == latest || ***s != placeholder)
+            .map(|(_, s)| s.revision.clone())
+            .collect()
+    }
+}
+
 impl<T> std::fmt::Debug for StatesVec<T>
 where
     T: std::fmt::Debug,
@@ -0,0 +1,218 @@
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{
+    abstract_model::Change,
+    resources::{
+        ConfigMap, ControllerRevision, CronJob, Deployment, Endpoints, HorizontalPodAutoscaler,
+        Job, Lease, Namespace, Node, PersistentVolume, PersistentVolumeClaim, Pod, PriorityClass,
+        ReplicaSet, ReplicationController, Role, RoleBinding, Secret, Service, StatefulSet,
+        StorageClass,
+    },
+    state::{resources::Resources, revision::Revision, RawState, StateView},
+};
+
+use super::{History, StatesVec};
+
+/// Replicas of the state that each accept writes on their own and only converge when one merges
+/// in the state of another.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct EventualHistory {
+    /// Every state that any of the replicas has been at, with their revision being their index.
+    states: StatesVec<ReplicaState>,
+    /// The index of the latest state of each replica.
+    heads: Vec<usize>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+struct ReplicaState {
+    state: StateView,
+    replica: usize,
+}
+
+impl EventualHistory {
+    /// Start each replica from its own copy of the initial state, so that the revision a client
+    /// reads also picks the replica that it talks to.
+    pub fn new(initial_state: RawState, replicas: usize) -> Self {
+        let state = StateView::from(initial_state);
+        let states = (0..replicas.max(1))
+            .map(|replica| {
+                let mut state = state.clone();
+                state.revision = Revision::from(vec![replica]);
+                Arc::new(ReplicaState { state, replica })
+            })
+            .collect();
+        Self {
+            heads: (0..replicas.max(1)).collect(),
+            states: StatesVec(states),
+        }
+    }
+
+    pub fn replicas(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// Merge the latest state of one replica into another, returning whether it changed anything.
+    pub fn merge_replicas(&mut self, from: usize, to: usize, merge: &ReplicaMerge) -> bool {
+        let mut merged = self.states[self.heads[to]].state.clone();
+        merge.merge(
+            &mut merged.state,
+            &self.states[self.heads[from]].state.state,
+        );
+        if merged == self.states[self.heads[to]].state {
+            // already had everything the other replica did
+            return false;
+        }
+        self.push(merged, to);
+        true
+    }
+
+    fn push(&mut self, mut state: StateView, replica: usize) {
+        let index = self.states.len();
+        state.revision = Revision::from(vec![index]);
+        self.states
+            .push_back(Arc::new(ReplicaState { state, replica }));
+        self.heads[replica] = index;
+    }
+}
+
+impl History for EventualHistory {
+    fn add_change(&mut self, change: Change) {
+        // the write goes to the replica that the client read from, which may have moved on since
+        let index = change.revision.components().first().unwrap();
+        let replica = self.states[*index].replica;
+        let mut new_state = self.states[self.heads[replica]].state.clone();
+        let new_revision = Revision::from(vec![self.states.len()]);
+        if new_state.apply_operation(change.operation, new_revision) {
+            self.push(new_state, replica);
+        }
+    }
+
+    fn max_revision(&self) -> Revision {
+        self.states.last().unwrap().state.revision.clone()
+    }
+
+    fn state_at(&self, revision: &Revision) -> Cow<StateView> {
+        let index = revision.components().first().unwrap();
+        Cow::Borrowed(&self.states[*index].state)
+    }
+
+    fn valid_revisions(&self, min_revision: Option<&Revision>) -> Vec<Revision> {
+        // clients can talk to any replica, so there is nothing to keep their reads monotonic
+        let mut heads = self.heads.clone();
+        heads.sort();
+        heads.dedup();
+        heads
+            .into_iter()
+            .map(|i| self.states[i].state.revision.clone())
+            .filter(|r| Some(r) != min_revision)
+            .collect()
+    }
+}
+
+/// How the resources of a kind on another replica are merged into those of this one.
+pub type MergeFn<T> = fn(&mut Resources<T>, &Resources<T>);
+
+/// The merge function used for each kind of resource when replicas reconcile.
+///
+/// By default each resource is a last-writer-wins register, keeping the version with the latest
+/// resource version.
+///
+/// THEMELIOS: Deletions leave no tombstones so the default merge brings back resources that only
+/// one of the replicas has deleted.
+#[derive(Clone, Copy, Debug)]
+pub struct ReplicaMerge {
+    pub nodes: MergeFn<Node>,
+    pub pods: MergeFn<Pod>,
+    pub replicasets: MergeFn<ReplicaSet>,
+    pub replication_controllers: MergeFn<ReplicationController>,
+    pub deployments: MergeFn<Deployment>,
+    pub statefulsets: MergeFn<StatefulSet>,
+    pub controller_revisions: MergeFn<ControllerRevision>,
+    pub persistent_volume_claims: MergeFn<PersistentVolumeClaim>,
+    pub persistent_volumes: MergeFn<PersistentVolume>,
+    pub storage_classes: MergeFn<StorageClass>,
+    pub priority_classes: MergeFn<PriorityClass>,
+    pub leases: MergeFn<Lease>,
+    pub services: MergeFn<Service>,
+    pub endpoints: MergeFn<Endpoints>,
+    pub config_maps: MergeFn<ConfigMap>,
+    pub secrets: MergeFn<Secret>,
+    pub jobs: MergeFn<Job>,
+    pub cronjobs: MergeFn<CronJob>,
+    pub horizontal_pod_autoscalers: MergeFn<HorizontalPodAutoscaler>,
+    pub namespaces: MergeFn<Namespace>,
+    pub roles: MergeFn<Role>,
+    pub role_bindings: MergeFn<RoleBinding>,
+}
+
+impl Default for ReplicaMerge {
+    fn default() -> Self {
+        Self {
+            nodes: Resources::merge,
+            pods: Resources::merge,
+            replicasets: Resources::merge,
+            replication_controllers: Resources::merge,
+            deployments: Resources::merge,
+            statefulsets: Resources::merge,
+            controller_revisions: Resources::merge,
+            persistent_volume_claims: Resources::merge,
+            persistent_volumes: Resources::merge,
+            storage_classes: Resources::merge,
+            priority_classes: Resources::merge,
+            leases: Resources::merge,
+            services: Resources::merge,
+            endpoints: Resources::merge,
+            config_maps: Resources::merge,
+            secrets: Resources::merge,
+            jobs: Resources::merge,
+            cronjobs: Resources::merge,
+            horizontal_pod_autoscalers: Resources::merge,
+            namespaces: Resources::merge,
+            roles: Resources::merge,
+            role_bindings: Resources::merge,
+        }
+    }
+}
+
+impl ReplicaMerge {
+    /// Merge the other state into this one, kind by kind.
+    pub fn merge(&self, state: &mut RawState, other: &RawState) {
+        (self.nodes)(&mut state.nodes, &other.nodes);
+        (self.pods)(&mut state.pods, &other.pods);
+        (self.replicasets)(&mut state.replicasets, &other.replicasets);
+        (self.replication_controllers)(
+            &mut state.replication_controllers,
+            &other.replication_controllers,
+        );
+        (self.deployments)(&mut state.deployments, &other.deployments);
+        (self.statefulsets)(&mut state.statefulsets, &other.statefulsets);
+        (self.controller_revisions)(&mut state.controller_revisions, &other.controller_revisions);
+        (self.persistent_volume_claims)(
+            &mut state.persistent_volume_claims,
+            &other.persistent_volume_claims,
+        );
+        (self.persistent_volumes)(&mut state.persistent_volumes, &other.persistent_volumes);
+        (self.storage_classes)(&mut state.storage_classes, &other.storage_classes);
+        (self.priority_classes)(&mut state.priority_classes, &other.priority_classes);
+        (self.leases)(&mut state.leases, &other.leases);
+        (self.services)(&mut state.services, &other.services);
+        (self.endpoints)(&mut state.endpoints, &other.endpoints);
+        (self.config_maps)(&mut state.config_maps, &other.config_maps);
+        (self.secrets)(&mut state.secrets, &other.secrets);
+        (self.jobs)(&mut state.jobs, &other.jobs);
+        (self.cronjobs)(&mut state.cronjobs, &other.cronjobs);
+        (self.horizontal_pod_autoscalers)(
+            &mut state.horizontal_pod_autoscalers,
+            &other.horizontal_pod_autoscalers,
+        );
+        (self.namespaces)(&mut state.namespaces, &other.namespaces);
+        (self.roles)(&mut state.roles, &other.roles);
+        (self.role_bindings)(&mut state.role_bindings, &other.role_bindings);
+        state.clock = std::cmp::max(state.clock, other.clock);
+        for (key, utilization) in &other.metrics {
+            // readings carry no version to order them by, so keep the highest
+            let current = state.metrics.entry(key.clone()).or_insert(*utilization);
+            *current = std::cmp::max(*current, *utilization);
+        }
+    }
+}
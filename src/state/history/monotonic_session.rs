@@ -18,6 +18,10 @@ impl MonotonicSessionHistory {
             states: StatesVec(imbl::vector![Arc::new(initial_state.into())]),
         }
     }
+
+    pub fn compact(&mut self, keep: usize, oldest_referenced: Option<usize>) {
+        self.states.compact(keep, oldest_referenced);
+    }
 }
 
 impl History for MonotonicSessionHistory {
@@ -56,4 +60,8 @@ impl History for MonotonicSessionHistory {
             vec![self.max_revision()]
         }
     }
+
+    fn retained_revisions(&self) -> Vec<Revision> {
+        self.states.retained_revisions()
+    }
 }
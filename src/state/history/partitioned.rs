@@ -0,0 +1,122 @@
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{
+    abstract_model::Change,
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::{History, StatesVec};
+
+/// A linear history kept by a store replicated over some members with a leader, where one member
+/// can be partitioned from the rest.
+///
+/// The isolated member stops applying the log, so reads through it are stale, and as it can't
+/// reach a quorum the writes made through it fail. The rest keep a majority, electing a new
+/// leader if they have to, so reads and writes through them stay linearizable.
+///
+/// THEMELIOS: Clients are taken to have gone through the isolated member when they write from the
+/// revision it is stuck at, as reads and writes are made in the same step.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PartitionedHistory {
+    states: StatesVec,
+    members: usize,
+    leader: usize,
+    /// The isolated member along with the index of the last state that it applied.
+    partitioned: Option<(usize, usize)>,
+}
+
+impl PartitionedHistory {
+    pub fn new(initial_state: RawState, members: usize) -> Self {
+        Self {
+            states: StatesVec(imbl::vector![Arc::new(initial_state.into())]),
+            members: members.max(1),
+            leader: 0,
+            partitioned: None,
+        }
+    }
+
+    pub fn members(&self) -> usize {
+        self.members
+    }
+
+    pub fn leader(&self) -> usize {
+        self.leader
+    }
+
+    pub fn partitioned_member(&self) -> Option<usize> {
+        self.partitioned.map(|(member, _)| member)
+    }
+
+    /// Isolate the member from the rest, returning whether that was possible.
+    ///
+    /// Only one member is partitioned at a time, and only when the rest still make a majority.
+    pub fn partition(&mut self, member: usize) -> bool {
+        if self.partitioned.is_some() || member >= self.members || self.members < 3 {
+            return false;
+        }
+        self.partitioned = Some((member, self.states.len() - 1));
+        if member == self.leader {
+            // the majority elects a new leader between them
+            self.leader = (member + 1) % self.members;
+        }
+        true
+    }
+
+    /// Reconnect the isolated member, which catches up on the log it missed, returning whether
+    /// there was one.
+    pub fn heal(&mut self) -> bool {
+        self.partitioned.take().is_some()
+    }
+
+    pub fn compact(&mut self, keep: usize, oldest_referenced: Option<usize>) {
+        // the isolated member still serves the state it is stuck at
+        let stale = self.partitioned.map(|(_, applied)| applied);
+        let oldest = oldest_referenced.into_iter().chain(stale).min();
+        self.states.compact(keep, oldest);
+    }
+
+    /// The index of the state that the isolated member is stuck at, if it is behind the rest.
+    fn stale_index(&self) -> Option<usize> {
+        self.partitioned
+            .map(|(_, applied)| applied)
+            .filter(|applied| *applied != self.states.len() - 1)
+    }
+}
+
+impl History for PartitionedHistory {
+    fn add_change(&mut self, change: Change) {
+        let index = change.revision.components().first().unwrap();
+        if self.stale_index() == Some(*index) {
+            // made through the isolated member, which can't commit it
+            return;
+        }
+        let mut new_state = (**self.states.last().unwrap()).clone();
+        let new_revision = self.max_revision().increment();
+        if new_state.apply_operation(change.operation, new_revision) {
+            self.states.push_back(Arc::new(new_state));
+        }
+    }
+
+    fn max_revision(&self) -> Revision {
+        self.states.last().unwrap().revision.clone()
+    }
+
+    fn state_at(&self, revision: &Revision) -> Cow<StateView> {
+        let index = revision.components().first().unwrap();
+        Cow::Borrowed(&self.states[*index])
+    }
+
+    fn valid_revisions(&self, min_revision: Option<&Revision>) -> Vec<Revision> {
+        // the majority serves the latest state, the isolated member whatever it last applied
+        self.stale_index()
+            .map(|i| self.states[i].revision.clone())
+            .into_iter()
+            .chain([self.max_revision()])
+            .filter(|r| min_revision.map_or(true, |min| r > min))
+            .collect()
+    }
+
+    fn retained_revisions(&self) -> Vec<Revision> {
+        self.states.retained_revisions()
+    }
+}
@@ -0,0 +1,50 @@
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{
+    abstract_model::Change,
+    state::{revision::Revision, RawState, StateView},
+};
+
+use super::{History, StatesVec};
+
+/// A linear history where clients can read any state, not necessarily later than the last one
+/// they read, as long as it includes their own last write.
+///
+/// The history itself has no notion of clients so leaves keeping to their writes to
+/// [`State::session_revisions`](crate::state::State::session_revisions).
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ReadYourWritesHistory {
+    states: StatesVec,
+}
+
+impl ReadYourWritesHistory {
+    pub fn new(initial_state: RawState) -> Self {
+        Self {
+            states: StatesVec(imbl::vector![Arc::new(initial_state.into())]),
+        }
+    }
+}
+
+impl History for ReadYourWritesHistory {
+    fn add_change(&mut self, change: Change) {
+        let mut new_state = (**self.states.last().unwrap()).clone();
+        let new_revision = self.max_revision().increment();
+        if new_state.apply_operation(change.operation, new_revision) {
+            self.states.push_back(Arc::new(new_state));
+        }
+    }
+
+    fn max_revision(&self) -> Revision {
+        self.states.last().unwrap().revision.clone()
+    }
+
+    fn state_at(&self, revision: &Revision) -> Cow<StateView> {
+        let index = revision.components().first().unwrap();
+        Cow::Borrowed(&self.states[*index])
+    }
+
+    fn valid_revisions(&self, _min_revision: Option<&Revision>) -> Vec<Revision> {
+        // reads aren't monotonic so can go back to before the last one
+        self.states.iter().map(|s| s.revision.clone()).collect()
+    }
+}
@@ -18,6 +18,10 @@ impl SynchronousHistory {
             states: StatesVec(imbl::vector![Arc::new(initial_state.into())]),
         }
     }
+
+    pub fn compact(&mut self, keep: usize, oldest_referenced: Option<usize>) {
+        self.states.compact(keep, oldest_referenced);
+    }
 }
 
 impl History for SynchronousHistory {
@@ -53,4 +57,8 @@ impl History for SynchronousHistory {
             vec![max]
         }
     }
+
+    fn retained_revisions(&self) -> Vec<Revision> {
+        self.states.retained_revisions()
+    }
 }
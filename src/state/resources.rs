@@ -1,16 +1,16 @@
 use std::sync::Arc;
 
+use time::OffsetDateTime;
 use tracing::warn;
 
-use crate::{
-    resources::{LabelSelector, Meta, Spec},
-    utils::now,
+use crate::resources::{
+    ClusterScoped, LabelSelector, Meta, Metadata, Spec, StatusSubresource, Time,
 };
 
 use super::revision::Revision;
 
-/// A data structure that ensures the resources are unique by name, and kept in sorted order for
-/// efficient lookup and deterministic ordering.
+/// A data structure that ensures the resources are unique by name within their namespace, and kept
+/// in sorted order for efficient lookup and deterministic ordering.
 #[derive(derivative::Derivative)]
 #[derivative(PartialEq, Hash)]
 #[derive(Clone, Debug, Eq, PartialOrd, Ord)]
@@ -27,16 +27,26 @@ impl<T: Meta + Spec + Clone> Resources<T> {
     where
         T: PartialEq,
     {
-        match self.create(res.clone(), revision.clone()) {
+        match self.create(res, revision.clone()) {
             Ok(_) => {}
-            Err(_) => {
+            Err(mut res) => {
+                // upserts replace whatever version is there
+                let pos = self.get_pos(res.metadata()).unwrap();
+                let existing = &self.0[pos];
+                res.metadata_mut().resource_version = existing.metadata().resource_version.clone();
                 self.update(res, revision).map_err(|_| ()).unwrap();
             }
         }
     }
 
     pub fn create(&mut self, mut res: T, revision: Revision) -> Result<(), T> {
-        if self.has(&res.metadata().name) {
+        // set the namespace, leaving cluster-scoped resources without one
+        if !T::NAMESPACED {
+            res.metadata_mut().namespace.clear();
+        } else if res.metadata().namespace.is_empty() {
+            res.metadata_mut().namespace = "default".to_owned();
+        }
+        if self.has_in(&res.metadata().namespace, &res.metadata().name) {
             return Err(res);
         }
         // set the uid if not set already
@@ -47,34 +57,42 @@ impl<T: Meta + Spec + Clone> Resources<T> {
         if res.metadata().generation == 0 {
             res.metadata_mut().generation = 1;
         }
-        // set the creation timestamp
+        // set the creation timestamp, to the start of the clock for resources that weren't
+        // created through the api
         if res.metadata().creation_timestamp.is_none() {
-            res.metadata_mut().creation_timestamp = Some(now());
-        }
-        // set the namespace
-        if res.metadata().namespace.is_empty() {
-            res.metadata_mut().namespace = "default".to_owned();
+            res.metadata_mut().creation_timestamp = Some(Time(OffsetDateTime::UNIX_EPOCH));
         }
         // set resource version to mod revision as per https://github.com/kubernetes/community/blob/master/contributors/devel/sig-architecture/api-conventions.md#concurrency-control-and-consistency
         res.metadata_mut().resource_version = revision;
-        let pos = self.get_insertion_pos(&res.metadata().name);
+        let pos = self.get_insertion_pos(res.metadata());
         self.0.insert(pos, Arc::new(res));
         Ok(())
     }
 
+    /// Replace the existing version of the resource, as long as it is the version that the new
+    /// one was read from or the new one doesn't say which version it was read from.
     pub fn update(&mut self, mut res: T, revision: Revision) -> Result<(), T>
     where
         T: PartialEq,
     {
-        if let Some(existing_pos) = self.get_pos(&res.metadata().name) {
+        if let Some(existing_pos) = self.get_pos(res.metadata()) {
             let existing = &self.0[existing_pos];
             if existing.metadata().deletion_timestamp.is_some() {
-                // can only remove finalizers on terminating resources
-                let mut ex = (**existing).clone();
-                ex.metadata_mut().finalizers.clear();
-                let mut r = res.clone();
-                r.metadata_mut().finalizers.clear();
-                if r != ex {
+                // can only remove finalizers, shorten the grace period or update the status of
+                // terminating resources
+                let mut ex = existing.metadata().clone();
+                ex.finalizers.clear();
+                let mut r = res.metadata().clone();
+                r.finalizers.clear();
+                if let (Some(new), Some(old)) = (
+                    r.deletion_grace_period_seconds,
+                    ex.deletion_grace_period_seconds,
+                ) {
+                    if new <= old {
+                        ex.deletion_grace_period_seconds = Some(new);
+                    }
+                }
+                if r != ex || res.spec() != existing.spec() {
                     warn!("Tried to update resource that is terminating, only removing finalizers is allowed");
                     return Err(res);
                 }
@@ -87,30 +105,24 @@ impl<T: Meta + Spec + Clone> Resources<T> {
                     res.metadata().uid
                 );
                 Err(res)
-            } else if existing.metadata().resource_version > res.metadata().resource_version {
-                // ignore changes to resources when resource version is specified but the resource
-                // being inserted is old
+            } else if !res.metadata().resource_version.is_empty()
+                && existing.metadata().resource_version != res.metadata().resource_version
+            {
+                // the update must be made against the version being replaced, anything else was
+                // read before a write that it would clobber, or from a state the write isn't
+                // going to, while updates without a resource version are unconditional
                 let existing = &existing.metadata().resource_version;
                 let new = &res.metadata().resource_version;
-                warn!(?existing, ?new, "Old resource");
+                warn!(?existing, ?new, "Conflicting resource version");
                 Err(res)
             } else {
-                // set resource version to mod revision as per https://github.com/kubernetes/community/blob/master/contributors/devel/sig-architecture/api-conventions.md#concurrency-control-and-consistency
-                // Update the generation of the resource if the spec (desired state) has changed.
-                let mut new_meta_without_generation = res.metadata().clone();
-                new_meta_without_generation.generation = 0;
-                let mut existing_meta_without_generation = existing.metadata().clone();
-                existing_meta_without_generation.generation = 0;
-                if res.spec() != existing.spec() ||
-                // TODO: this should be able to be removed now that we have the
-                // observed_revision field
-                // THEMELIOS: changing metadata does not change generation normally, but this
-                // eliminates the way to check for stability (that a controller has observed the
-                // updates)
-                    new_meta_without_generation != existing_meta_without_generation
-                {
+                // Update the generation of the resource only if the spec (desired state) has
+                // changed, metadata and status changes leave it as is.
+                res.metadata_mut().generation = existing.metadata().generation;
+                if res.spec() != existing.spec() {
                     res.metadata_mut().generation += 1;
                 }
+                // set resource version to mod revision as per https://github.com/kubernetes/community/blob/master/contributors/devel/sig-architecture/api-conventions.md#concurrency-control-and-consistency
                 res.metadata_mut().resource_version = revision;
                 self.0[existing_pos] = Arc::new(res);
                 Ok(())
@@ -120,23 +132,52 @@ impl<T: Meta + Spec + Clone> Resources<T> {
         }
     }
 
-    fn get_insertion_pos(&self, k: &str) -> usize {
-        match self.0.binary_search_by(|v| k.cmp(&v.metadata().name)) {
+    /// Update the resource through its status subresource, keeping the spec of the existing
+    /// version so that only the status and metadata are written.
+    pub fn update_status(&mut self, mut res: T, revision: Revision) -> Result<(), T>
+    where
+        T: PartialEq + StatusSubresource,
+    {
+        if let Some(pos) = self.get_pos(res.metadata()) {
+            res.reset_spec(&self.0[pos]);
+        }
+        self.update(res, revision)
+    }
+
+    fn get_insertion_pos(&self, k: &Metadata) -> usize {
+        match self.search(&k.namespace, &k.name) {
             Ok(p) => p,
             Err(p) => p,
         }
     }
 
-    fn get_pos(&self, k: &str) -> Option<usize> {
-        self.0.binary_search_by(|v| k.cmp(&v.metadata().name)).ok()
+    fn get_pos(&self, k: &Metadata) -> Option<usize> {
+        self.search(&k.namespace, &k.name).ok()
     }
 
-    pub fn has(&self, name: &str) -> bool {
-        self.get(name).is_some()
+    /// Resources are ordered by name and then namespace, which is defaulted as on creation.
+    fn search(&self, namespace: &str, name: &str) -> Result<usize, usize> {
+        let namespace = if !T::NAMESPACED {
+            ""
+        } else if namespace.is_empty() {
+            "default"
+        } else {
+            namespace
+        };
+        self.0.binary_search_by(|v| {
+            let meta = v.metadata();
+            (name, namespace).cmp(&(meta.name.as_str(), meta.namespace.as_str()))
+        })
     }
 
-    pub fn get(&self, name: &str) -> Option<&T> {
-        self.get_pos(name)
+    pub fn has_in(&self, namespace: &str, name: &str) -> bool {
+        self.get_in(namespace, name).is_some()
+    }
+
+    /// Get the resource with the name in the namespace, the default one if empty.
+    pub fn get_in(&self, namespace: &str, name: &str) -> Option<&T> {
+        self.search(namespace, name)
+            .ok()
             .and_then(|p| self.0.get(p).map(|r| r.as_ref()))
     }
 
@@ -148,7 +189,7 @@ impl<T: Meta + Spec + Clone> Resources<T> {
 
     pub fn remove(&mut self, res: &T) -> Option<T> {
         // in order to remove a resource it must have the same uid.
-        if let Some(existing_pos) = self.get_pos(&res.metadata().name) {
+        if let Some(existing_pos) = self.get_pos(res.metadata()) {
             let existing = &self.0[existing_pos];
             if existing.metadata().uid == res.metadata().uid {
                 return Some((*self.0.remove(existing_pos)).clone());
@@ -183,13 +224,20 @@ impl<T: Meta + Spec + Clone> Resources<T> {
             .map(|r| r.as_ref())
     }
 
+    pub fn in_namespace<'a>(&'a self, namespace: &'a str) -> impl Iterator<Item = &T> + 'a {
+        self.0
+            .iter()
+            .filter(move |t| t.metadata().namespace == namespace)
+            .map(|r| r.as_ref())
+    }
+
     pub fn to_vec(&self) -> Vec<&T> {
         self.iter().collect()
     }
 
     pub fn merge(&mut self, other: &Self) {
         for resource in &other.0 {
-            if let Some(existing_pos) = self.get_pos(&resource.metadata().name) {
+            if let Some(existing_pos) = self.get_pos(resource.metadata()) {
                 let existing = &self.0[existing_pos];
                 let new_revision = &resource.metadata().resource_version;
                 let existing_revision = &existing.metadata().resource_version;
@@ -197,13 +245,25 @@ impl<T: Meta + Spec + Clone> Resources<T> {
                     self.0[existing_pos] = Arc::clone(resource);
                 }
             } else {
-                let pos = self.get_insertion_pos(&resource.metadata().name);
+                let pos = self.get_insertion_pos(resource.metadata());
                 self.0.insert(pos, Arc::clone(resource));
             }
         }
     }
 }
 
+impl<T: ClusterScoped + Spec + Clone> Resources<T> {
+    pub fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Get the resource with the name, only for kinds that aren't namespaced, others needing
+    /// [`Resources::get_in`].
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.get_in("", name)
+    }
+}
+
 impl<T: Meta + Spec + Clone> From<Vec<T>> for Resources<T> {
     fn from(value: Vec<T>) -> Self {
         let mut rv = Resources::default();
@@ -19,6 +19,9 @@ impl<'de> Deserialize<'de> for Revision {
         D: serde::Deserializer<'de>,
     {
         let s = <&str>::deserialize(deserializer)?;
+        if s.is_empty() {
+            return Ok(Self::empty());
+        }
         Ok(Self::try_from(s).unwrap())
     }
 }
@@ -80,6 +83,16 @@ impl Revision {
         self
     }
 
+    /// The resource version of a write that doesn't say which version it was read from, as
+    /// clients send with an empty or missing `resourceVersion`.
+    pub fn empty() -> Self {
+        Self(SmallVec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn components(&self) -> &[usize] {
         &self.0
     }
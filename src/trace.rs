@@ -0,0 +1,285 @@
+//! Reconstruction of traces through the model from the fingerprint paths printed for discoveries,
+//! for stepping through them outside of the web explorer.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use stateright::Model;
+use tracing::metadata::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::abstract_model::{AbstractModel, Action};
+use crate::resources::Meta;
+use crate::state::{RawState, State};
+
+/// A single step of a trace.
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    /// Description of the action taken to reach this step, none for the initial state.
+    pub action: Option<String>,
+    pub state: State,
+    /// Logs emitted while taking the action.
+    pub logs: String,
+}
+
+/// A resource in the state at some step, rendered for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceResource {
+    pub kind: &'static str,
+    pub name: String,
+    pub yaml: String,
+}
+
+impl TraceResource {
+    /// Whether the resource matches the filter, on either its kind or name.
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        self.kind.to_lowercase().contains(&filter) || self.name.to_lowercase().contains(&filter)
+    }
+}
+
+impl TraceStep {
+    /// All resources in the latest view of the state at this step, followed by the events
+    /// recorded up to it.
+    pub fn resources(&self) -> Vec<TraceResource> {
+        let mut out = resources(&self.state.latest());
+        out.extend(self.state.events().iter().map(|e| render("Event", e)));
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The path contained something that was not a fingerprint.
+    InvalidFingerprint(String),
+    /// No state reachable from the previous step matched the fingerprint at the given index.
+    NoMatchingState(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+}
+
+impl Trace {
+    /// Replay the model along an encoded path of fingerprints, as printed for discoveries.
+    pub fn replay(model: &AbstractModel, encoded_path: &str) -> Result<Self, ReplayError> {
+        let fingerprints = parse_path(encoded_path)?;
+
+        let mut state = initial_state(model, fingerprints.first().copied())?;
+        let mut steps = vec![TraceStep {
+            action: None,
+            state: state.clone(),
+            logs: String::new(),
+        }];
+
+        for (i, fp) in fingerprints.iter().enumerate().skip(1) {
+            let (action, _) =
+                next_step(model, &state, *fp).ok_or(ReplayError::NoMatchingState(i))?;
+
+            // take the step again, now that we know which it is, to collect its logs
+            let logs = LogBuffer::default();
+            let subscriber = tracing_subscriber::fmt()
+                .with_writer(logs.clone())
+                .with_ansi(false)
+                .with_max_level(LevelFilter::DEBUG)
+                .finish();
+            let next = tracing::subscriber::with_default(subscriber, || {
+                model.next_state(&state, action.clone())
+            })
+            .ok_or(ReplayError::NoMatchingState(i))?;
+
+            steps.push(TraceStep {
+                action: Some(model.format_action(&state, &action)),
+                state: next.clone(),
+                logs: logs.contents(),
+            });
+            state = next;
+        }
+
+        Ok(Self { steps })
+    }
+
+    /// A unified diff of the resources between the previous step and the given one, all of them
+    /// being added for the initial step.
+    pub fn diff(&self, step: usize) -> String {
+        let render = |step: &TraceStep| {
+            step.resources()
+                .into_iter()
+                .map(|r| format!("# {}/{}\n{}", r.kind, r.name, r.yaml))
+                .collect::<String>()
+        };
+        let before = match step.checked_sub(1) {
+            Some(previous) => render(&self.steps[previous]),
+            None => String::new(),
+        };
+        let after = render(&self.steps[step]);
+        let textdiff = similar::TextDiff::from_lines(&before, &after);
+        similar::udiff::UnifiedDiff::from_text_diff(&textdiff).to_string()
+    }
+}
+
+/// The fingerprints in an encoded path, as printed for discoveries.
+pub fn parse_path(encoded_path: &str) -> Result<Vec<u64>, ReplayError> {
+    encoded_path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<u64>()
+                .map_err(|_| ReplayError::InvalidFingerprint(s.to_owned()))
+        })
+        .collect()
+}
+
+/// The states along a path of fingerprints, without collecting anything else about the steps.
+pub fn replay_states(
+    model: &AbstractModel,
+    fingerprints: &[u64],
+) -> Result<Vec<State>, ReplayError> {
+    let mut states = vec![initial_state(model, fingerprints.first().copied())?];
+    for (i, fp) in fingerprints.iter().enumerate().skip(1) {
+        let (_, next) =
+            next_step(model, states.last().unwrap(), *fp).ok_or(ReplayError::NoMatchingState(i))?;
+        states.push(next);
+    }
+    Ok(states)
+}
+
+/// The initial state and the actions taken from it along a path of fingerprints.
+pub fn replay_actions(
+    model: &AbstractModel,
+    fingerprints: &[u64],
+) -> Result<(State, Vec<Action>), ReplayError> {
+    let initial = initial_state(model, fingerprints.first().copied())?;
+    let mut state = initial.clone();
+    let mut actions = Vec::new();
+    for (i, fp) in fingerprints.iter().enumerate().skip(1) {
+        let (action, next) =
+            next_step(model, &state, *fp).ok_or(ReplayError::NoMatchingState(i))?;
+        actions.push(action);
+        state = next;
+    }
+    Ok((initial, actions))
+}
+
+/// The initial state with the given fingerprint, or the first one if there is none.
+fn initial_state(model: &AbstractModel, fingerprint: Option<u64>) -> Result<State, ReplayError> {
+    let mut states = model.init_states().into_iter();
+    match fingerprint {
+        Some(fp) => states.find(|s| stateright::fingerprint(s).get() == fp),
+        None => states.next(),
+    }
+    .ok_or(ReplayError::NoMatchingState(0))
+}
+
+/// The action from the state that leads to the state with the given fingerprint.
+fn next_step(model: &AbstractModel, state: &State, fingerprint: u64) -> Option<(Action, State)> {
+    let mut actions = Vec::new();
+    model.actions(state, &mut actions);
+    actions.into_iter().find_map(|a| {
+        model
+            .next_state(state, a.clone())
+            .filter(|next| stateright::fingerprint(next).get() == fingerprint)
+            .map(|next| (a, next))
+    })
+}
+
+fn render<T: Meta + Serialize>(kind: &'static str, resource: &T) -> TraceResource {
+    TraceResource {
+        kind,
+        name: resource.metadata().name.clone(),
+        yaml: serde_yaml::to_string(resource).unwrap_or_default(),
+    }
+}
+
+fn resources(state: &RawState) -> Vec<TraceResource> {
+    let mut out = Vec::new();
+    out.extend(state.namespaces.iter().map(|r| render("Namespace", r)));
+    out.extend(state.nodes.iter().map(|r| render("Node", r)));
+    out.extend(state.pods.iter().map(|r| render("Pod", r)));
+    out.extend(state.replicasets.iter().map(|r| render("ReplicaSet", r)));
+    out.extend(
+        state
+            .replication_controllers
+            .iter()
+            .map(|r| render("ReplicationController", r)),
+    );
+    out.extend(state.deployments.iter().map(|r| render("Deployment", r)));
+    out.extend(state.statefulsets.iter().map(|r| render("StatefulSet", r)));
+    out.extend(
+        state
+            .controller_revisions
+            .iter()
+            .map(|r| render("ControllerRevision", r)),
+    );
+    out.extend(
+        state
+            .persistent_volume_claims
+            .iter()
+            .map(|r| render("PersistentVolumeClaim", r)),
+    );
+    out.extend(
+        state
+            .persistent_volumes
+            .iter()
+            .map(|r| render("PersistentVolume", r)),
+    );
+    out.extend(
+        state
+            .storage_classes
+            .iter()
+            .map(|r| render("StorageClass", r)),
+    );
+    out.extend(
+        state
+            .priority_classes
+            .iter()
+            .map(|r| render("PriorityClass", r)),
+    );
+    out.extend(state.leases.iter().map(|r| render("Lease", r)));
+    out.extend(state.services.iter().map(|r| render("Service", r)));
+    out.extend(state.endpoints.iter().map(|r| render("Endpoints", r)));
+    out.extend(state.config_maps.iter().map(|r| render("ConfigMap", r)));
+    out.extend(state.secrets.iter().map(|r| render("Secret", r)));
+    out.extend(state.jobs.iter().map(|r| render("Job", r)));
+    out.extend(state.cronjobs.iter().map(|r| render("CronJob", r)));
+    out.extend(
+        state
+            .horizontal_pod_autoscalers
+            .iter()
+            .map(|r| render("HorizontalPodAutoscaler", r)),
+    );
+    out.extend(state.roles.iter().map(|r| render("Role", r)));
+    out.extend(state.role_bindings.iter().map(|r| render("RoleBinding", r)));
+    out
+}
+
+/// Collects formatted logs in memory.
+#[derive(Debug, Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl LogBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogBuffer {
+    type Writer = LogBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
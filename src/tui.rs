@@ -0,0 +1,254 @@
+//! A terminal UI for stepping through a [`Trace`].
+
+use std::io;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+
+use crate::trace::{Trace, TraceResource};
+
+/// What to show in the detail pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    /// Changes to the filtered resources made by this step.
+    Diff,
+    /// The selected resource.
+    Resource,
+    /// The logs emitted while taking this step.
+    Logs,
+}
+
+struct App {
+    trace: Trace,
+    step: usize,
+    pane: Pane,
+    filter: String,
+    editing_filter: bool,
+    selected: ListState,
+    scroll: u16,
+}
+
+/// Run the UI on the current terminal until the user quits.
+pub fn run(trace: Trace) -> io::Result<()> {
+    enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let result = App::new(trace).run(&mut terminal);
+    disable_raw_mode()?;
+    io::stdout().execute(LeaveAlternateScreen)?;
+    result
+}
+
+impl App {
+    fn new(trace: Trace) -> Self {
+        Self {
+            trace,
+            step: 0,
+            pane: Pane::Diff,
+            filter: String::new(),
+            editing_filter: false,
+            selected: ListState::default().with_selected(Some(0)),
+            scroll: 0,
+        }
+    }
+
+    fn run<B: Backend>(mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            if self.editing_filter {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.editing_filter = false,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                self.selected.select(Some(0));
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Right | KeyCode::Char('n') => self.goto(self.step + 1),
+                KeyCode::Left | KeyCode::Char('p') => self.goto(self.step.saturating_sub(1)),
+                KeyCode::Home => self.goto(0),
+                KeyCode::End => self.goto(self.trace.steps.len() - 1),
+                KeyCode::Down | KeyCode::Char('j') => self.select(1),
+                KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::Char('/') => self.editing_filter = true,
+                KeyCode::Char('d') => self.show(Pane::Diff),
+                KeyCode::Char('r') => self.show(Pane::Resource),
+                KeyCode::Char('l') => self.show(Pane::Logs),
+                _ => {}
+            }
+        }
+    }
+
+    fn goto(&mut self, step: usize) {
+        self.step = step.min(self.trace.steps.len() - 1);
+        self.scroll = 0;
+    }
+
+    fn select(&mut self, offset: isize) {
+        let count = self.resources(self.step).len();
+        if count == 0 {
+            return;
+        }
+        let current = self.selected.selected().unwrap_or(0) as isize;
+        let next = (current + offset).clamp(0, count as isize - 1);
+        self.selected.select(Some(next as usize));
+        self.scroll = 0;
+    }
+
+    fn show(&mut self, pane: Pane) {
+        self.pane = pane;
+        self.scroll = 0;
+    }
+
+    /// The resources at the given step that match the current filter.
+    fn resources(&self, step: usize) -> Vec<TraceResource> {
+        self.trace.steps[step]
+            .resources()
+            .into_iter()
+            .filter(|r| r.matches(&self.filter))
+            .collect()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(frame.size());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(rows[1]);
+
+        let current = &self.trace.steps[self.step];
+        let latest = current.state.latest();
+        let header = vec![
+            Line::from(format!(
+                "Step {}/{}  revision {}  clock {}s",
+                self.step,
+                self.trace.steps.len() - 1,
+                latest.revision,
+                latest.clock,
+            )),
+            Line::from(
+                current
+                    .action
+                    .clone()
+                    .unwrap_or_else(|| "Initial state".to_owned()),
+            ),
+        ];
+        frame.render_widget(
+            Paragraph::new(header)
+                .block(Block::default().borders(Borders::ALL).title("Trace"))
+                .wrap(Wrap { trim: true }),
+            rows[0],
+        );
+
+        let resources = self.resources(self.step);
+        let items = resources
+            .iter()
+            .map(|r| ListItem::new(format!("{}/{}", r.kind, r.name)))
+            .collect::<Vec<_>>();
+        let title = if self.editing_filter || !self.filter.is_empty() {
+            format!("Resources [/{}]", self.filter)
+        } else {
+            "Resources".to_owned()
+        };
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            columns[0],
+            &mut self.selected,
+        );
+
+        let (title, lines) = match self.pane {
+            Pane::Diff => ("Diff", self.diff_lines()),
+            Pane::Resource => {
+                let lines = self
+                    .selected
+                    .selected()
+                    .and_then(|i| resources.get(i))
+                    .map(|r| r.yaml.lines().map(|l| Line::from(l.to_owned())).collect())
+                    .unwrap_or_default();
+                ("Resource", lines)
+            }
+            Pane::Logs => (
+                "Logs",
+                current
+                    .logs
+                    .lines()
+                    .map(|l| Line::from(l.to_owned()))
+                    .collect(),
+            ),
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .scroll((self.scroll, 0)),
+            columns[1],
+        );
+
+        let help = if self.editing_filter {
+            "type to filter by kind or name, enter/esc: done"
+        } else {
+            "n/p: step  j/k: select  /: filter  d: diff  r: resource  l: logs  pgup/pgdn: scroll  q: quit"
+        };
+        frame.render_widget(Paragraph::new(help), rows[2]);
+    }
+
+    /// A diff of the filtered resources between the previous step and this one.
+    fn diff_lines(&self) -> Vec<Line<'static>> {
+        let render = |step: usize| {
+            self.resources(step)
+                .into_iter()
+                .map(|r| format!("# {}/{}\n{}", r.kind, r.name, r.yaml))
+                .collect::<String>()
+        };
+        let before = if self.step == 0 {
+            String::new()
+        } else {
+            render(self.step - 1)
+        };
+        let after = render(self.step);
+        let textdiff = similar::TextDiff::from_lines(&before, &after);
+        let diff = similar::udiff::UnifiedDiff::from_text_diff(&textdiff).to_string();
+        diff.lines()
+            .map(|l| {
+                let style = if l.starts_with('+') {
+                    Style::default().fg(Color::Green)
+                } else if l.starts_with('-') {
+                    Style::default().fg(Color::Red)
+                } else if l.starts_with("@@") {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                Line::from(Span::styled(l.to_owned(), style))
+            })
+            .collect()
+    }
+}
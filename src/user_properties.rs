@@ -0,0 +1,629 @@
+//! Properties written in a small expression language and loaded at runtime, so that checking a new
+//! property doesn't need recompiling.
+//!
+//! A file holds one property per line, as `<always|sometimes|eventually> "<name>": <expression>`,
+//! ignoring blank lines and those starting with `#`:
+//!
+//! ```text
+//! # no more pods than the nodes can hold
+//! always "few pods": count(pods) <= 4
+//! eventually "web scaled": get(deployments, "web").status.replicas == 3
+//! always "web pods run": all(pods, metadata.labels["app"] != "web" || status.phase == "Running")
+//! ```
+//!
+//! Expressions are evaluated on the latest view of the state, with resources in their JSON form:
+//! - `count(kind)` is the number of resources of a kind, and `count(kind, predicate)`,
+//!   `all(kind, predicate)` and `any(kind, predicate)` evaluate the predicate on each of them,
+//!   with the fields of the resource in scope.
+//! - `get(kind, "name")` is the named resource and `len(value)` is the length of a list, map or
+//!   string.
+//! - `.field` and `["key"]` get the entries of maps, `[0]` those of lists, and missing ones are
+//!   `null`.
+//! - `==` and `!=` compare any values, `<`, `<=`, `>` and `>=` numbers and strings, `+` and `-`
+//!   work on integers and `&&`, `||` and `!` on booleans.
+//!
+//! Kinds are named as for `--controller-scope`, with `_` in place of `-`, such as
+//! `replication_controllers`.
+//! A property holds only where its expression is `true`, operators given values of the wrong type
+//! evaluating to `null`.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::Value;
+use stateright::Expectation;
+
+use crate::abstract_model::AbstractModel;
+use crate::controller_properties::Properties;
+use crate::resources::{Meta, Spec};
+use crate::state::resources::Resources;
+use crate::state::{RawState, ResourceKind, State};
+
+/// The most properties that can be loaded for a model.
+pub const MAX_USER_PROPERTIES: usize = 32;
+
+/// A property loaded at runtime.
+#[derive(Debug, Clone)]
+pub struct UserProperty {
+    pub expectation: Expectation,
+    pub name: &'static str,
+    pub expr: Expr,
+}
+
+impl UserProperty {
+    /// Whether the property's expression is true in the latest view of the state.
+    pub fn holds(&self, state: &State) -> bool {
+        self.expr.eval(&state.latest().state, None) == Value::Bool(true)
+    }
+}
+
+/// Load the properties from the file.
+pub fn load(path: &Path) -> Result<Vec<UserProperty>, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse(&source).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Parse the properties, one per line.
+///
+/// THEMELIOS: Property names are leaked, as stateright needs them to live forever, so this should
+/// only be done once for each file.
+pub fn parse(source: &str) -> Result<Vec<UserProperty>, String> {
+    let mut properties = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let property = parse_property(line).map_err(|e| format!("line {}: {e}", i + 1))?;
+        properties.push(property);
+    }
+    if properties.len() > MAX_USER_PROPERTIES {
+        return Err(format!(
+            "{} properties given but at most {MAX_USER_PROPERTIES} are supported",
+            properties.len()
+        ));
+    }
+    Ok(properties)
+}
+
+fn parse_property(line: &str) -> Result<UserProperty, String> {
+    let mut parser = Parser {
+        tokens: tokenize(line)?,
+        pos: 0,
+    };
+    let expectation = match parser.advance() {
+        Some(Token::Ident(e)) if e == "always" => Expectation::Always,
+        Some(Token::Ident(e)) if e == "sometimes" => Expectation::Sometimes,
+        Some(Token::Ident(e)) if e == "eventually" => Expectation::Eventually,
+        t => {
+            return Err(format!(
+                "expected always, sometimes or eventually but found {t:?}"
+            ))
+        }
+    };
+    let name = match parser.advance() {
+        Some(Token::Str(name)) => name,
+        t => {
+            return Err(format!(
+                "expected the property name as a string but found {t:?}"
+            ))
+        }
+    };
+    parser.expect(&Token::Colon)?;
+    let expr = parser.expr()?;
+    if let Some(t) = parser.peek() {
+        return Err(format!("unexpected {t:?} after the expression"));
+    }
+    Ok(UserProperty {
+        expectation,
+        name: Box::leak(name.into_boxed_str()),
+        expr,
+    })
+}
+
+/// The properties checking that each of the user properties hold.
+///
+/// Properties are plain functions that can't capture what they check, so each one looks its
+/// expression up in the model by position.
+pub fn properties(user_properties: &[UserProperty]) -> Properties {
+    assert!(
+        user_properties.len() <= MAX_USER_PROPERTIES,
+        "at most {MAX_USER_PROPERTIES} user properties are supported"
+    );
+    let mut properties = Properties::default();
+    for (property, condition) in user_properties.iter().zip(CONDITIONS) {
+        properties.add(property.expectation, property.name, condition);
+    }
+    properties
+}
+
+fn holds<const I: usize>(model: &AbstractModel, state: &State) -> bool {
+    model.user_properties[I].holds(state)
+}
+
+const CONDITIONS: [fn(&AbstractModel, &State) -> bool; MAX_USER_PROPERTIES] = [
+    holds::<0>,
+    holds::<1>,
+    holds::<2>,
+    holds::<3>,
+    holds::<4>,
+    holds::<5>,
+    holds::<6>,
+    holds::<7>,
+    holds::<8>,
+    holds::<9>,
+    holds::<10>,
+    holds::<11>,
+    holds::<12>,
+    holds::<13>,
+    holds::<14>,
+    holds::<15>,
+    holds::<16>,
+    holds::<17>,
+    holds::<18>,
+    holds::<19>,
+    holds::<20>,
+    holds::<21>,
+    holds::<22>,
+    holds::<23>,
+    holds::<24>,
+    holds::<25>,
+    holds::<26>,
+    holds::<27>,
+    holds::<28>,
+    holds::<29>,
+    holds::<30>,
+    holds::<31>,
+];
+
+/// An expression over the state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    /// A field of the resource in scope.
+    Field(String),
+    /// An entry of a map or list.
+    Member(Box<Expr>, Key),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    /// The number of resources of the kind, satisfying the predicate if given.
+    Count(ResourceKind, Option<Box<Expr>>),
+    All(ResourceKind, Box<Expr>),
+    Any(ResourceKind, Box<Expr>),
+    /// The resource of the kind with the name.
+    Get(ResourceKind, String),
+    Len(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    Name(String),
+    Position(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+}
+
+impl Expr {
+    /// Evaluate the expression on the state, with the fields of the given resource in scope.
+    pub fn eval(&self, state: &RawState, scope: Option<&Value>) -> Value {
+        match self {
+            Expr::Literal(v) => v.clone(),
+            Expr::Field(name) => scope
+                .and_then(|r| r.get(name))
+                .cloned()
+                .unwrap_or(Value::Null),
+            Expr::Member(e, key) => {
+                let v = e.eval(state, scope);
+                let member = match key {
+                    Key::Name(name) => v.get(name),
+                    Key::Position(i) => v.get(i),
+                };
+                member.cloned().unwrap_or(Value::Null)
+            }
+            Expr::Not(e) => match e.eval(state, scope) {
+                Value::Bool(b) => Value::Bool(!b),
+                _ => Value::Null,
+            },
+            Expr::Binary(op, l, r) => binary(*op, state, scope, l, r),
+            Expr::Count(kind, predicate) => {
+                let count = resources(state, *kind)
+                    .iter()
+                    .filter(|r| predicate.as_ref().map_or(true, |p| is_true(p, state, r)))
+                    .count();
+                Value::from(count)
+            }
+            Expr::All(kind, predicate) => Value::Bool(
+                resources(state, *kind)
+                    .iter()
+                    .all(|r| is_true(predicate, state, r)),
+            ),
+            Expr::Any(kind, predicate) => Value::Bool(
+                resources(state, *kind)
+                    .iter()
+                    .any(|r| is_true(predicate, state, r)),
+            ),
+            Expr::Get(kind, name) => resources(state, *kind)
+                .into_iter()
+                .find(|r| r["metadata"]["name"] == name.as_str())
+                .unwrap_or(Value::Null),
+            Expr::Len(e) => match e.eval(state, scope) {
+                Value::Array(a) => Value::from(a.len()),
+                Value::Object(o) => Value::from(o.len()),
+                Value::String(s) => Value::from(s.chars().count()),
+                _ => Value::Null,
+            },
+        }
+    }
+}
+
+fn is_true(predicate: &Expr, state: &RawState, resource: &Value) -> bool {
+    predicate.eval(state, Some(resource)) == Value::Bool(true)
+}
+
+fn binary(op: BinOp, state: &RawState, scope: Option<&Value>, l: &Expr, r: &Expr) -> Value {
+    let l = l.eval(state, scope);
+    // only evaluate the right when it is needed, as with rust
+    match (op, &l) {
+        (BinOp::And, Value::Bool(false)) => return Value::Bool(false),
+        (BinOp::Or, Value::Bool(true)) => return Value::Bool(true),
+        _ => {}
+    }
+    let r = r.eval(state, scope);
+    match op {
+        BinOp::And | BinOp::Or => match (l, r) {
+            (Value::Bool(_), Value::Bool(r)) => Value::Bool(r),
+            _ => Value::Null,
+        },
+        BinOp::Eq => Value::Bool(equal(&l, &r)),
+        BinOp::Ne => Value::Bool(!equal(&l, &r)),
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+            let ordering = match (&l, &r) {
+                (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            match ordering {
+                Some(o) => Value::Bool(match op {
+                    BinOp::Lt => o.is_lt(),
+                    BinOp::Le => o.is_le(),
+                    BinOp::Gt => o.is_gt(),
+                    _ => o.is_ge(),
+                }),
+                None => Value::Null,
+            }
+        }
+        BinOp::Add | BinOp::Sub => match (l.as_i64(), r.as_i64()) {
+            (Some(a), Some(b)) if op == BinOp::Add => Value::from(a + b),
+            (Some(a), Some(b)) => Value::from(a - b),
+            _ => Value::Null,
+        },
+    }
+}
+
+/// Whether the values are equal, numbers being compared by value rather than representation.
+fn equal(l: &Value, r: &Value) -> bool {
+    match (l, r) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => l == r,
+    }
+}
+
+/// The resources of the kind in their JSON form.
+fn resources(state: &RawState, kind: ResourceKind) -> Vec<Value> {
+    fn to_values<T: Meta + Spec + Clone + Serialize>(resources: &Resources<T>) -> Vec<Value> {
+        resources
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap_or(Value::Null))
+            .collect()
+    }
+    match kind {
+        ResourceKind::Nodes => to_values(&state.nodes),
+        ResourceKind::Pods => to_values(&state.pods),
+        ResourceKind::ReplicaSets => to_values(&state.replicasets),
+        ResourceKind::ReplicationControllers => to_values(&state.replication_controllers),
+        ResourceKind::Deployments => to_values(&state.deployments),
+        ResourceKind::StatefulSets => to_values(&state.statefulsets),
+        ResourceKind::ControllerRevisions => to_values(&state.controller_revisions),
+        ResourceKind::PersistentVolumeClaims => to_values(&state.persistent_volume_claims),
+        ResourceKind::PersistentVolumes => to_values(&state.persistent_volumes),
+        ResourceKind::StorageClasses => to_values(&state.storage_classes),
+        ResourceKind::PriorityClasses => to_values(&state.priority_classes),
+        ResourceKind::Leases => to_values(&state.leases),
+        ResourceKind::Services => to_values(&state.services),
+        ResourceKind::Endpoints => to_values(&state.endpoints),
+        ResourceKind::ConfigMaps => to_values(&state.config_maps),
+        ResourceKind::Secrets => to_values(&state.secrets),
+        ResourceKind::Jobs => to_values(&state.jobs),
+        ResourceKind::CronJobs => to_values(&state.cronjobs),
+        ResourceKind::HorizontalPodAutoscalers => to_values(&state.horizontal_pod_autoscalers),
+        ResourceKind::Namespaces => to_values(&state.namespaces),
+        ResourceKind::Roles => to_values(&state.roles),
+        ResourceKind::RoleBindings => to_values(&state.role_bindings),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Comma,
+    Colon,
+    Bang,
+    Op(BinOp),
+    Plus,
+    Minus,
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '[' => (Token::LBracket, 1),
+            ']' => (Token::RBracket, 1),
+            '.' => (Token::Dot, 1),
+            ',' => (Token::Comma, 1),
+            ':' => (Token::Colon, 1),
+            '+' => (Token::Plus, 1),
+            '-' => (Token::Minus, 1),
+            '=' if next == Some('=') => (Token::Op(BinOp::Eq), 2),
+            '!' if next == Some('=') => (Token::Op(BinOp::Ne), 2),
+            '!' => (Token::Bang, 1),
+            '<' if next == Some('=') => (Token::Op(BinOp::Le), 2),
+            '<' => (Token::Op(BinOp::Lt), 1),
+            '>' if next == Some('=') => (Token::Op(BinOp::Ge), 2),
+            '>' => (Token::Op(BinOp::Gt), 1),
+            '&' if next == Some('&') => (Token::Op(BinOp::And), 2),
+            '|' if next == Some('|') => (Token::Op(BinOp::Or), 2),
+            '"' => {
+                let mut s = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        Some('"') => break,
+                        Some('\\') if j + 1 < chars.len() => {
+                            s.push(chars[j + 1]);
+                            j += 2;
+                        }
+                        Some(c) => {
+                            s.push(*c);
+                            j += 1;
+                        }
+                        None => return Err("unterminated string".to_owned()),
+                    }
+                }
+                (Token::Str(s), j + 1 - i)
+            }
+            c if c.is_ascii_digit() => {
+                let digits = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>();
+                let n = digits
+                    .parse()
+                    .map_err(|e| format!("bad number {digits}: {e}"))?;
+                (Token::Int(n), digits.len())
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let ident = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .collect::<String>();
+                let len = ident.chars().count();
+                (Token::Ident(ident), len)
+            }
+            c => return Err(format!("unexpected character {c:?}")),
+        };
+        tokens.push(token);
+        i += len;
+    }
+    Ok(tokens)
+}
+
+/// The binary operators other than addition and subtraction, from the loosest binding to the
+/// tightest.
+const PRECEDENCE: &[&[BinOp]] = &[
+    &[BinOp::Or],
+    &[BinOp::And],
+    &[
+        BinOp::Eq,
+        BinOp::Ne,
+        BinOp::Lt,
+        BinOp::Le,
+        BinOp::Gt,
+        BinOp::Ge,
+    ],
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is the given one.
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), String> {
+        match self.advance() {
+            Some(t) if &t == token => Ok(()),
+            t => Err(format!("expected {token:?} but found {t:?}")),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.binary(PRECEDENCE)
+    }
+
+    /// Parse operators of the first precedence level, with those of the rest binding tighter.
+    fn binary(&mut self, levels: &[&[BinOp]]) -> Result<Expr, String> {
+        let Some((ops, tighter)) = levels.split_first() else {
+            return self.sum();
+        };
+        let mut left = self.binary(tighter)?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if !ops.contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            let right = self.binary(tighter)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinOp::Add,
+                Some(Token::Minus) => BinOp::Sub,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Bang) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Minus) {
+            let e = self.unary()?;
+            return Ok(Expr::Binary(
+                BinOp::Sub,
+                Box::new(Expr::Literal(Value::from(0))),
+                Box::new(e),
+            ));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut e = self.primary()?;
+        loop {
+            if self.eat(&Token::Dot) {
+                match self.advance() {
+                    Some(Token::Ident(name)) => e = Expr::Member(Box::new(e), Key::Name(name)),
+                    t => return Err(format!("expected a field name but found {t:?}")),
+                }
+            } else if self.eat(&Token::LBracket) {
+                let key = match self.advance() {
+                    Some(Token::Str(name)) => Key::Name(name),
+                    Some(Token::Int(i)) if i >= 0 => Key::Position(i as usize),
+                    t => return Err(format!("expected a key or position but found {t:?}")),
+                };
+                self.expect(&Token::RBracket)?;
+                e = Expr::Member(Box::new(e), key);
+            } else {
+                return Ok(e);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.advance() {
+            Some(Token::Int(n)) => Ok(Expr::Literal(Value::from(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::LParen) => {
+                let e = self.expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(e)
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "count" | "all" | "any" | "get" | "len" if self.eat(&Token::LParen) => {
+                    let e = self.call(&ident)?;
+                    self.expect(&Token::RParen)?;
+                    Ok(e)
+                }
+                _ => Ok(Expr::Field(ident)),
+            },
+            t => Err(format!("expected an expression but found {t:?}")),
+        }
+    }
+
+    /// The arguments of the function, after its opening parenthesis.
+    fn call(&mut self, function: &str) -> Result<Expr, String> {
+        if function == "len" {
+            return Ok(Expr::Len(Box::new(self.expr()?)));
+        }
+        let kind = match self.advance() {
+            Some(Token::Ident(kind)) => ResourceKind::from_str(&kind.replace('_', "-"))?,
+            t => return Err(format!("expected a kind of resource but found {t:?}")),
+        };
+        match function {
+            "count" if self.eat(&Token::Comma) => {
+                Ok(Expr::Count(kind, Some(Box::new(self.expr()?))))
+            }
+            "count" => Ok(Expr::Count(kind, None)),
+            "get" => {
+                self.expect(&Token::Comma)?;
+                match self.advance() {
+                    Some(Token::Str(name)) => Ok(Expr::Get(kind, name)),
+                    t => Err(format!("expected a resource name but found {t:?}")),
+                }
+            }
+            _ => {
+                self.expect(&Token::Comma)?;
+                let predicate = Box::new(self.expr()?);
+                if function == "all" {
+                    Ok(Expr::All(kind, predicate))
+                } else {
+                    Ok(Expr::Any(kind, predicate))
+                }
+            }
+        }
+    }
+}
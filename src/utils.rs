@@ -1,4 +1,4 @@
-use crate::{resources::Time, state::revision::Revision};
+use crate::state::revision::Revision;
 use time::OffsetDateTime;
 
 use crate::resources::Metadata;
@@ -13,14 +13,10 @@ pub fn new_uid(name: &str) -> String {
     name.to_owned()
 }
 
-#[cfg(feature = "serve")]
-pub fn now() -> Time {
-    Time(OffsetDateTime::now_utc())
-}
-
-#[cfg(not(feature = "serve"))]
-pub fn now() -> Time {
-    Time(OffsetDateTime::UNIX_EPOCH)
+/// The seconds since the unix epoch by the wall clock, for serving against a real cluster rather
+/// than checking the model, whose clock only advances through its actions.
+pub fn wall_clock() -> u64 {
+    OffsetDateTime::now_utc().unix_timestamp() as u64
 }
 
 pub fn metadata(name: String) -> Metadata {
@@ -0,0 +1,106 @@
+//! The work queues of controllers, holding the resources that they have asked to look at again,
+//! after a delay or with their retries rate limited.
+//!
+//! Controllers reconcile everything they see each time they step, so what is queued only matters
+//! for when they step again: a controller with work due can step without anything having changed.
+
+use std::collections::BTreeMap;
+
+/// The delay before retrying a resource the first time, doubling with each failure after that.
+pub const BASE_RETRY_DELAY_SECONDS: u64 = 5;
+
+/// The longest delay before retrying a resource, however many times it has failed.
+pub const MAX_RETRY_DELAY_SECONDS: u64 = 1000;
+
+/// The key of a resource in a work queue, `namespace/name` or just the name for cluster scoped
+/// resources, as in upstream's `MetaNamespaceKeyFunc`.
+pub fn key(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{namespace}/{name}")
+    }
+}
+
+/// Resources waiting to be looked at again, keyed by [`key`], along with how often acting on each
+/// has failed.
+///
+/// Times are seconds on the clock of the cluster.
+///
+/// THEMELIOS: the queue runs on the cluster's clock rather than that of its controller, so
+/// controllers with skewed clocks still agree with the cluster on when their work is due.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct WorkQueue {
+    /// The time from which each queued resource is due.
+    due: BTreeMap<String, u64>,
+    /// The number of times in a row that acting on each resource has failed.
+    failures: BTreeMap<String, u32>,
+}
+
+impl WorkQueue {
+    /// Queue the resource to be looked at again straight away.
+    pub fn add(&mut self, key: &str, now: u64) {
+        self.add_after(key, now, 0);
+    }
+
+    /// Queue the resource to be looked at again after the given number of seconds, unless it is
+    /// already queued for sooner.
+    pub fn add_after(&mut self, key: &str, now: u64, seconds: u64) {
+        let due = now + seconds;
+        self.due
+            .entry(key.to_owned())
+            .and_modify(|d| *d = (*d).min(due))
+            .or_insert(due);
+    }
+
+    /// Queue the resource to be retried after a delay that grows with each failure to act on it.
+    pub fn add_rate_limited(&mut self, key: &str, now: u64) {
+        let failures = self.failures.get(key).copied().unwrap_or_default();
+        let delay = BASE_RETRY_DELAY_SECONDS
+            .saturating_mul(2u64.saturating_pow(failures))
+            .min(MAX_RETRY_DELAY_SECONDS);
+        if delay < MAX_RETRY_DELAY_SECONDS {
+            // stop counting once at the longest delay to keep the failures bounded
+            self.failures.insert(key.to_owned(), failures + 1);
+        }
+        self.add_after(key, now, delay);
+    }
+
+    /// Stop rate limiting the resource, as once acting on it has succeeded.
+    pub fn forget(&mut self, key: &str) {
+        self.failures.remove(key);
+    }
+
+    /// The number of times in a row that acting on the resource has failed.
+    pub fn failures(&self, key: &str) -> u32 {
+        self.failures.get(key).copied().unwrap_or_default()
+    }
+
+    /// Whether any of the queued resources are due.
+    pub fn has_due(&self, now: u64) -> bool {
+        self.due.values().any(|d| *d <= now)
+    }
+
+    /// Whether any of the queued resources are waiting for time to pass before they are due.
+    pub fn has_waiting(&self, now: u64) -> bool {
+        self.due.values().any(|d| *d > now)
+    }
+
+    /// Take the resources that are due out of the queue, for the controller to look at.
+    pub fn take_due(&mut self, now: u64) -> Vec<String> {
+        let due = self
+            .due
+            .iter()
+            .filter(|(_, d)| **d <= now)
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+        for key in &due {
+            self.due.remove(key);
+        }
+        due
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.due.is_empty() && self.failures.is_empty()
+    }
+}
@@ -0,0 +1,720 @@
+use serde_json::json;
+use themelios::abstract_model::ControllerAction;
+use themelios::api::patch::Patch;
+use themelios::resources::{
+    Container, Deployment, Job, Namespace, NamespacePhase, Node, PersistentVolume,
+    PersistentVolumeClaim, PersistentVolumePhase, Pod, PodPhase, PodQosClass, PodSpec,
+    PreemptionPolicy, PriorityClass, ReplicaSet, ReplicaSetStatus, ResourceQuantities, Scale,
+    ScaleSpec, StatusSubresource,
+};
+use themelios::state::apply::{self, ApplyError};
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, StateView};
+use themelios::utils;
+
+fn rev(i: usize) -> Revision {
+    Revision::from(vec![i])
+}
+
+fn new_pod(name: &str) -> Pod {
+    Pod {
+        metadata: utils::metadata(name.to_owned()),
+        spec: PodSpec {
+            containers: vec![Container {
+                name: "fake".to_owned(),
+                image: "fake".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn new_replicaset(name: &str) -> ReplicaSet {
+    ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn node_join_registers_ready_node() {
+    let mut state = StateView::default();
+    apply::nodes::join(
+        &mut state,
+        "node-0".to_owned(),
+        ResourceQuantities::default(),
+        rev(1),
+    )
+    .unwrap();
+    let node = state.nodes.get("node-0").unwrap();
+    assert_eq!(node.metadata.resource_version, rev(1));
+    assert_eq!(node.status.allocatable, Some(node.status.capacity.clone()));
+    assert_eq!(node.status.conditions.len(), 1);
+
+    // joining twice fails
+    assert_eq!(
+        apply::nodes::join(
+            &mut state,
+            "node-0".to_owned(),
+            ResourceQuantities::default(),
+            rev(2),
+        ),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn node_delete_removes_node() {
+    let node = Node {
+        metadata: utils::metadata("node-0".to_owned()),
+        ..Default::default()
+    };
+    let mut state = StateView::from(RawState::default().with_nodes([node.clone()]));
+    apply::nodes::delete(&mut state, node).unwrap();
+    assert!(state.nodes.is_empty());
+}
+
+#[test]
+fn pod_create_sets_defaults() {
+    let mut state = StateView {
+        revision: rev(1),
+        ..Default::default()
+    };
+    let mut pod = new_pod("");
+    pod.metadata.generate_name = "pod-".to_owned();
+    apply::pods::create(&mut state, pod, rev(2)).unwrap();
+
+    let pod = state.pods.get_in("default", "pod-1").unwrap();
+    assert_eq!(pod.metadata.uid, "1");
+    assert_eq!(pod.metadata.generation, 1);
+    assert_eq!(pod.metadata.resource_version, rev(2));
+    assert!(pod.metadata.creation_timestamp.is_some());
+    assert_eq!(pod.metadata.namespace, "default");
+    assert_eq!(pod.status.qos_class, Some(PodQosClass::BestEffort));
+}
+
+#[test]
+fn pod_create_resolves_priority_class() {
+    let mut state = StateView::default();
+    state.set_priority_classes([
+        PriorityClass {
+            metadata: utils::metadata("default".to_owned()),
+            value: 10,
+            global_default: true,
+            ..Default::default()
+        },
+        PriorityClass {
+            metadata: utils::metadata("batch".to_owned()),
+            value: 1,
+            preemption_policy: PreemptionPolicy::Never,
+            ..Default::default()
+        },
+    ]);
+    apply::pods::create(&mut state, new_pod("defaulted"), rev(1)).unwrap();
+    let mut pod = new_pod("batch");
+    pod.spec.priority_class_name = Some("batch".to_owned());
+    apply::pods::create(&mut state, pod, rev(2)).unwrap();
+
+    let pod = state.pods.get_in("default", "defaulted").unwrap();
+    assert_eq!(pod.spec.priority_class_name.as_deref(), Some("default"));
+    assert_eq!(pod.spec.priority, Some(10));
+    let pod = state.pods.get_in("default", "batch").unwrap();
+    assert_eq!(pod.spec.priority, Some(1));
+    assert_eq!(pod.spec.preemption_policy, Some(PreemptionPolicy::Never));
+}
+
+#[test]
+fn pod_create_with_missing_priority_class_fails() {
+    let mut state = StateView::default();
+    let mut pod = new_pod("pod");
+    pod.spec.priority_class_name = Some("missing".to_owned());
+    assert_eq!(
+        apply::pods::create(&mut state, pod, rev(1)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn pod_create_duplicate_name_fails() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    assert_eq!(
+        apply::pods::create(&mut state, new_pod("pod"), rev(2)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn pod_create_same_name_in_other_namespace() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    let mut other = new_pod("pod");
+    other.metadata.namespace = "other".to_owned();
+    apply::pods::create(&mut state, other, rev(2)).unwrap();
+
+    assert_eq!(state.pods.len(), 2);
+    let pod = state.pods.get_in("", "pod").unwrap();
+    assert_eq!(pod.metadata.namespace, "default");
+    assert_eq!(pod.metadata.resource_version, rev(1));
+    let pod = state.pods.get_in("other", "pod").unwrap();
+    assert_eq!(pod.metadata.resource_version, rev(2));
+    assert!(!state.pods.has_in("missing", "pod"));
+}
+
+#[test]
+fn pod_update_bumps_generation_on_spec_change() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+
+    let mut pod = state.pods.get_in("default", "pod").unwrap().clone();
+    pod.spec.node_name = Some("node-0".to_owned());
+    apply::pods::update(&mut state, pod, rev(2)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap();
+    assert_eq!(pod.metadata.generation, 2);
+    assert_eq!(pod.metadata.resource_version, rev(2));
+}
+
+#[test]
+fn pod_update_keeps_generation_on_metadata_change() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+
+    let mut pod = state.pods.get_in("default", "pod").unwrap().clone();
+    pod.metadata
+        .labels
+        .insert("app".to_owned(), "web".to_owned());
+    pod.metadata.generation = 5;
+    apply::pods::update(&mut state, pod, rev(2)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap();
+    assert_eq!(pod.metadata.labels["app"], "web");
+    // the generation is managed by the api server
+    assert_eq!(pod.metadata.generation, 1);
+}
+
+#[test]
+fn pod_update_with_old_resource_version_fails() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    let stale = state.pods.get_in("default", "pod").unwrap().clone();
+
+    let mut pod = stale.clone();
+    pod.spec.node_name = Some("node-0".to_owned());
+    apply::pods::update(&mut state, pod, rev(2)).unwrap();
+
+    let mut pod = stale;
+    pod.spec.node_name = Some("node-1".to_owned());
+    assert_eq!(
+        apply::pods::update(&mut state, pod, rev(3)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn pod_update_requires_the_current_resource_version() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+
+    // a version the pod was never at, such as one read from another replica
+    let mut pod = state.pods.get_in("default", "pod").unwrap().clone();
+    pod.metadata.resource_version = rev(5);
+    pod.spec.node_name = Some("node-0".to_owned());
+    assert_eq!(
+        apply::pods::update(&mut state, pod.clone(), rev(2)),
+        Err(ApplyError)
+    );
+
+    pod.metadata.resource_version = rev(1);
+    apply::pods::update(&mut state, pod, rev(2)).unwrap();
+    assert_eq!(
+        state
+            .pods
+            .get_in("default", "pod")
+            .unwrap()
+            .metadata
+            .resource_version,
+        rev(2)
+    );
+}
+
+#[test]
+fn pod_update_without_resource_version_is_unconditional() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    let mut pod = state.pods.get_in("default", "pod").unwrap().clone();
+    pod.metadata.labels.insert("app".to_owned(), "a".to_owned());
+    apply::pods::update(&mut state, pod.clone(), rev(2)).unwrap();
+
+    // the pod has moved on since it was read, but the update doesn't ask for that version
+    pod.metadata.resource_version = Revision::empty();
+    pod.metadata.labels.insert("app".to_owned(), "b".to_owned());
+    apply::pods::update(&mut state, pod, rev(3)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap();
+    assert_eq!(pod.metadata.labels["app"], "b");
+    assert_eq!(pod.metadata.resource_version, rev(3));
+}
+
+#[test]
+fn pod_update_with_different_uid_fails() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    let mut pod = state.pods.get_in("default", "pod").unwrap().clone();
+    pod.metadata.uid = "other".to_owned();
+    assert_eq!(
+        apply::pods::update(&mut state, pod, rev(2)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn pod_patch_applies_to_the_latest_version() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+
+    let patch = Patch::merge(json!({ "spec": { "nodeName": "node-0" } }));
+    apply::pods::patch(&mut state, "default", "pod", &patch, rev(2)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap();
+    assert_eq!(pod.spec.node_name.as_deref(), Some("node-0"));
+    assert_eq!(pod.metadata.resource_version, rev(2));
+
+    // patches carrying an outdated resource version conflict
+    let stale = Patch::merge(json!({
+        "metadata": { "resourceVersion": rev(1).to_string() },
+        "spec": { "nodeName": "node-1" },
+    }));
+    assert_eq!(
+        apply::pods::patch(&mut state, "default", "pod", &stale, rev(3)),
+        Err(ApplyError)
+    );
+
+    // patches can't change the identity of the pod
+    let renamed = Patch::merge(json!({ "metadata": { "name": "other" } }));
+    assert_eq!(
+        apply::pods::patch(&mut state, "default", "pod", &renamed, rev(3)),
+        Err(ApplyError)
+    );
+    let uid = Patch::json(json!([{ "op": "replace", "path": "/metadata/uid", "value": "other" }]));
+    assert_eq!(
+        apply::pods::patch(&mut state, "default", "pod", &uid, rev(3)),
+        Err(ApplyError)
+    );
+
+    // nor patch pods that don't exist
+    assert_eq!(
+        apply::pods::patch(&mut state, "default", "missing", &patch, rev(3)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn pod_soft_delete_only_allows_finalizer_removal() {
+    let mut state = StateView::default();
+    let mut pod = new_pod("pod");
+    pod.metadata.finalizers.push("test".to_owned());
+    apply::pods::create(&mut state, pod, rev(1)).unwrap();
+
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    apply::pods::soft_delete(&mut state, pod, rev(2)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    assert!(pod.metadata.deletion_timestamp.is_some());
+
+    // changing the spec of a terminating pod is not allowed
+    let mut changed = pod.clone();
+    changed.spec.node_name = Some("node-0".to_owned());
+    assert_eq!(
+        apply::pods::update(&mut state, changed, rev(3)),
+        Err(ApplyError)
+    );
+
+    // but removing finalizers is
+    let mut finalized = pod;
+    finalized.metadata.finalizers.clear();
+    apply::pods::update(&mut state, finalized, rev(3)).unwrap();
+    assert!(state
+        .pods
+        .get_in("default", "pod")
+        .unwrap()
+        .metadata
+        .finalizers
+        .is_empty());
+}
+
+#[test]
+fn pod_soft_delete_sets_grace_period() {
+    let mut state = StateView::default();
+    let mut custom = new_pod("custom");
+    custom.spec.termination_grace_period_seconds = Some(5);
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    apply::pods::create(&mut state, custom, rev(2)).unwrap();
+
+    for (name, grace) in [
+        ("pod", apply::pods::DEFAULT_TERMINATION_GRACE_PERIOD_SECONDS),
+        ("custom", 5),
+    ] {
+        let pod = state.pods.get_in("default", name).unwrap().clone();
+        apply::pods::soft_delete(&mut state, pod, rev(3)).unwrap();
+        let pod = state.pods.get_in("default", name).unwrap();
+        assert_eq!(pod.metadata.deletion_grace_period_seconds, Some(grace));
+    }
+}
+
+#[test]
+fn pod_hard_delete_waits_for_finalizers() {
+    let mut state = StateView::default();
+    let mut pod = new_pod("pod");
+    pod.metadata.finalizers.push("test".to_owned());
+    apply::pods::create(&mut state, pod, rev(1)).unwrap();
+
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    apply::pods::soft_delete(&mut state, pod, rev(2)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    apply::pods::hard_delete(&mut state, pod, rev(3)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    assert_eq!(pod.metadata.deletion_grace_period_seconds, Some(0));
+
+    // the terminating pod's status can still be updated
+    let mut updated = pod.clone();
+    updated.status.phase = PodPhase::Failed;
+    apply::pods::update(&mut state, updated, rev(4)).unwrap();
+    assert!(state.pods.has_in("default", "pod"));
+
+    // and it is removed along with its last finalizer
+    let mut finalized = state.pods.get_in("default", "pod").unwrap().clone();
+    finalized.metadata.finalizers.clear();
+    apply::pods::update(&mut state, finalized, rev(5)).unwrap();
+    assert!(!state.pods.has_in("default", "pod"));
+}
+
+#[test]
+fn pod_hard_delete_requires_matching_uid() {
+    let mut state = StateView::default();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+
+    let mut other = state.pods.get_in("default", "pod").unwrap().clone();
+    other.metadata.uid = "other".to_owned();
+    apply::pods::hard_delete(&mut state, other, rev(2)).unwrap();
+    assert!(state.pods.has_in("default", "pod"));
+
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    apply::pods::hard_delete(&mut state, pod, rev(2)).unwrap();
+    assert!(!state.pods.has_in("default", "pod"));
+}
+
+#[test]
+fn deployment_status_update_keeps_generation() {
+    let deployment = Deployment {
+        metadata: utils::metadata("dep".to_owned()),
+        ..Default::default()
+    };
+    let mut state = StateView::from(RawState::default().with_deployments([deployment]));
+    let mut dep = state.deployments.get_in("default", "dep").unwrap().clone();
+    let generation = dep.metadata.generation;
+    dep.status.observed_generation = generation;
+    apply::deployments::update_status(&mut state, dep, rev(1)).unwrap();
+    let dep = state.deployments.get_in("default", "dep").unwrap();
+    assert_eq!(dep.metadata.generation, generation);
+    assert_eq!(dep.status.observed_generation, generation);
+
+    let mut dep = dep.clone();
+    dep.spec.replicas += 1;
+    apply::deployments::update(&mut state, dep, rev(2)).unwrap();
+    assert_eq!(
+        state
+            .deployments
+            .get_in("default", "dep")
+            .unwrap()
+            .metadata
+            .generation,
+        generation + 1
+    );
+}
+
+#[test]
+fn deployment_status_update_ignores_spec() {
+    let deployment = Deployment {
+        metadata: utils::metadata("dep".to_owned()),
+        ..Default::default()
+    };
+    let mut state = StateView::from(RawState::default().with_deployments([deployment]));
+    let mut dep = state.deployments.get_in("default", "dep").unwrap().clone();
+    let generation = dep.metadata.generation;
+    assert!(
+        !ControllerAction::UpdateDeploymentStatus(dep.clone()).changes_spec_through_status(&state)
+    );
+
+    dep.spec.replicas += 1;
+    dep.spec.paused = true;
+    dep.status.replicas = 2;
+    let action = ControllerAction::UpdateDeploymentStatus(dep.clone());
+    assert!(action.changes_spec_through_status(&state));
+    apply::deployments::update_status(&mut state, dep, rev(1)).unwrap();
+
+    let dep = state.deployments.get_in("default", "dep").unwrap();
+    assert_eq!(dep.status.replicas, 2);
+    assert!(!dep.spec.paused);
+    assert_eq!(dep.metadata.generation, generation);
+    assert_eq!(dep.metadata.resource_version, rev(1));
+}
+
+#[test]
+fn deployment_reset_status_keeps_existing_status() {
+    let existing = Deployment {
+        metadata: utils::metadata("dep".to_owned()),
+        ..Default::default()
+    };
+    let mut dep = existing.clone();
+    dep.spec.replicas += 1;
+    dep.status.replicas = 2;
+    dep.reset_status(&existing);
+    assert_eq!(dep.spec.replicas, existing.spec.replicas + 1);
+    assert_eq!(dep.status, existing.status);
+}
+
+#[test]
+fn deployment_requeue_leaves_state_unchanged() {
+    let deployment = Deployment {
+        metadata: utils::metadata("dep".to_owned()),
+        ..Default::default()
+    };
+    let mut state = StateView::from(RawState::default().with_deployments([deployment]));
+    let before = state.clone();
+    let dep = state.deployments.get_in("default", "dep").unwrap().clone();
+    apply::deployments::requeue(&mut state, dep).unwrap();
+    assert_eq!(state.state, before.state);
+}
+
+#[test]
+fn replicaset_create_and_delete() {
+    let mut state = StateView::default();
+    apply::replicasets::create(&mut state, new_replicaset("rs"), rev(1)).unwrap();
+    let rs = state.replicasets.get_in("default", "rs").unwrap().clone();
+    apply::replicasets::delete(&mut state, rs).unwrap();
+    assert!(state.replicasets.is_empty());
+}
+
+#[test]
+fn replicaset_status_update() {
+    let mut state = StateView::default();
+    apply::replicasets::create(&mut state, new_replicaset("rs"), rev(1)).unwrap();
+    let mut rs = state.replicasets.get_in("default", "rs").unwrap().clone();
+    rs.status = ReplicaSetStatus {
+        replicas: 2,
+        ..Default::default()
+    };
+    apply::replicasets::update_status(&mut state, rs, rev(2)).unwrap();
+    let rs = state.replicasets.get_in("default", "rs").unwrap();
+    assert_eq!(rs.status.replicas, 2);
+    assert_eq!(rs.metadata.generation, 1);
+}
+
+#[test]
+fn transaction_applies_its_writes_at_one_revision() {
+    let mut state = StateView::default();
+    apply::replicasets::create(&mut state, new_replicaset("rs-1"), rev(1)).unwrap();
+    apply::replicasets::create(&mut state, new_replicaset("rs-2"), rev(2)).unwrap();
+    let mut rs1 = state.replicasets.get_in("default", "rs-1").unwrap().clone();
+    rs1.spec.replicas = Some(3);
+    let mut rs2 = state.replicasets.get_in("default", "rs-2").unwrap().clone();
+    rs2.spec.replicas = Some(0);
+    assert!(state.apply_operation(
+        ControllerAction::Transaction(vec![
+            ControllerAction::UpdateReplicaSet(rs1),
+            ControllerAction::UpdateReplicaSet(rs2),
+        ]),
+        rev(3),
+    ));
+    let rs1 = state.replicasets.get_in("default", "rs-1").unwrap();
+    let rs2 = state.replicasets.get_in("default", "rs-2").unwrap();
+    assert_eq!(rs1.spec.replicas, Some(3));
+    assert_eq!(rs2.spec.replicas, Some(0));
+    assert_eq!(rs1.metadata.resource_version, rs2.metadata.resource_version);
+}
+
+#[test]
+fn failed_operation_leaves_state_untouched() {
+    let mut state = StateView::default();
+    apply::replicasets::create(&mut state, new_replicaset("rs-1"), rev(1)).unwrap();
+    let before = state.clone();
+    let mut rs1 = state.replicasets.get_in("default", "rs-1").unwrap().clone();
+    rs1.spec.replicas = Some(3);
+    let missing = new_replicaset("rs-2");
+    let applied = state.apply_operation(
+        ControllerAction::Transaction(vec![
+            ControllerAction::UpdateReplicaSet(rs1),
+            ControllerAction::UpdateReplicaSet(missing),
+        ]),
+        rev(2),
+    );
+    assert!(!applied);
+    assert_eq!(state.state, before.state);
+    assert_eq!(state.revision, before.revision);
+}
+
+#[test]
+fn persistent_volume_claim_create_and_update() {
+    let mut state = StateView::default();
+    let pvc = PersistentVolumeClaim {
+        metadata: utils::metadata("pvc".to_owned()),
+        ..Default::default()
+    };
+    apply::persistent_volume_claims::create(&mut state, pvc, rev(1)).unwrap();
+    let mut pvc = state
+        .persistent_volume_claims
+        .get_in("default", "pvc")
+        .unwrap()
+        .clone();
+    pvc.spec.volume_name = Some("vol".to_owned());
+    apply::persistent_volume_claims::update(&mut state, pvc, rev(2)).unwrap();
+    let pvc = state
+        .persistent_volume_claims
+        .get_in("default", "pvc")
+        .unwrap();
+    assert_eq!(pvc.metadata.generation, 2);
+}
+
+#[test]
+fn persistent_volume_status_update_keeps_generation() {
+    let mut state = StateView::default();
+    state.set_persistent_volumes([PersistentVolume {
+        metadata: utils::metadata("pv".to_owned()),
+        ..Default::default()
+    }]);
+    let mut pv = state.persistent_volumes.get("pv").unwrap().clone();
+    assert!(pv.metadata.namespace.is_empty());
+    let generation = pv.metadata.generation;
+    pv.status.phase = PersistentVolumePhase::Bound;
+    apply::persistent_volumes::update_status(&mut state, pv, rev(1)).unwrap();
+    let pv = state.persistent_volumes.get("pv").unwrap();
+    assert_eq!(pv.status.phase, PersistentVolumePhase::Bound);
+    assert_eq!(pv.metadata.generation, generation);
+}
+
+#[test]
+fn job_update_suspend_bumps_generation() {
+    let job = Job {
+        metadata: utils::metadata("job".to_owned()),
+        ..Default::default()
+    };
+    let mut state = StateView::from(RawState::default().with_jobs([job]));
+    let mut job = state.jobs.get_in("default", "job").unwrap().clone();
+    job.spec.suspend = true;
+    apply::jobs::update(&mut state, job, rev(1)).unwrap();
+    let job = state.jobs.get_in("default", "job").unwrap();
+    assert_eq!(job.metadata.generation, 2);
+    assert!(job.spec.suspend);
+}
+
+#[test]
+fn deployment_scale_only_changes_replicas() {
+    let deployment = Deployment {
+        metadata: utils::metadata("dep".to_owned()),
+        ..Default::default()
+    };
+    let mut state = StateView::from(RawState::default().with_deployments([deployment]));
+    let dep = state.deployments.get_in("default", "dep").unwrap().clone();
+    let scale = Scale {
+        metadata: dep.metadata.clone(),
+        spec: ScaleSpec { replicas: 3 },
+        ..Default::default()
+    };
+    apply::deployments::scale(&mut state, scale.clone(), rev(1)).unwrap();
+    let scaled = state.deployments.get_in("default", "dep").unwrap();
+    assert_eq!(scaled.spec.replicas, 3);
+    assert_eq!(scaled.spec.template, dep.spec.template);
+    assert_eq!(scaled.metadata.resource_version, rev(1));
+
+    // scaling from an old read conflicts with the newer write
+    let mut stale = scale;
+    stale.metadata.resource_version = Revision::default();
+    assert_eq!(
+        apply::deployments::scale(&mut state, stale, rev(2)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn metric_update_records_reading() {
+    let mut state = StateView::default();
+    let key = ("default".to_owned(), "hpa".to_owned());
+    apply::metrics::update(&mut state, key.0.clone(), key.1.clone(), 50).unwrap();
+    apply::metrics::update(&mut state, key.0.clone(), key.1.clone(), 100).unwrap();
+    assert_eq!(state.metrics.get(&key), Some(&100));
+}
+
+fn namespaced_state() -> StateView {
+    let mut state = StateView::default();
+    state.set_namespaces(["default", "test"].map(|name| Namespace {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    }));
+    state
+}
+
+#[test]
+fn namespace_soft_delete_blocks_creates() {
+    let mut state = namespaced_state();
+    let ns = state.namespaces.get("test").unwrap().clone();
+    apply::namespaces::soft_delete(&mut state, ns, rev(1)).unwrap();
+    let ns = state.namespaces.get("test").unwrap();
+    assert!(ns.metadata.deletion_timestamp.is_some());
+    assert_eq!(ns.status.phase, NamespacePhase::Terminating);
+
+    let mut pod = new_pod("pod");
+    pod.metadata.namespace = "test".to_owned();
+    assert_eq!(
+        apply::pods::create(&mut state, pod, rev(2)),
+        Err(ApplyError)
+    );
+    // other namespaces are unaffected
+    apply::pods::create(&mut state, new_pod("pod"), rev(2)).unwrap();
+}
+
+#[test]
+fn namespace_create_requires_existing_namespace() {
+    let mut state = namespaced_state();
+    let mut pod = new_pod("pod");
+    pod.metadata.namespace = "missing".to_owned();
+    assert_eq!(
+        apply::pods::create(&mut state, pod, rev(1)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn namespace_default_cannot_be_deleted() {
+    let mut state = namespaced_state();
+    let ns = state.namespaces.get("default").unwrap().clone();
+    assert_eq!(
+        apply::namespaces::soft_delete(&mut state, ns, rev(1)),
+        Err(ApplyError)
+    );
+}
+
+#[test]
+fn namespace_finalize_removes_once_finalizers_are_gone() {
+    let mut state = namespaced_state();
+    let ns = state.namespaces.get("test").unwrap().clone();
+    assert_eq!(ns.spec.finalizers, vec![Namespace::FINALIZER.to_owned()]);
+    apply::namespaces::soft_delete(&mut state, ns, rev(1)).unwrap();
+
+    let mut ns = state.namespaces.get("test").unwrap().clone();
+    ns.spec.finalizers.clear();
+    apply::namespaces::finalize(&mut state, ns, rev(2)).unwrap();
+    assert!(!state.namespaces.has("test"));
+}
+
+#[test]
+fn creation_and_deletion_follow_the_clock() {
+    let mut state = StateView::default();
+    apply::clock::advance(&mut state, 120).unwrap();
+    apply::pods::create(&mut state, new_pod("pod"), rev(1)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    assert_eq!(pod.metadata.creation_timestamp, Some(state.now()));
+
+    apply::clock::advance(&mut state, 60).unwrap();
+    apply::pods::soft_delete(&mut state, pod, rev(2)).unwrap();
+    let pod = state.pods.get_in("default", "pod").unwrap();
+    assert_ne!(pod.metadata.creation_timestamp, Some(state.now()));
+    assert_eq!(pod.metadata.deletion_timestamp, Some(state.now()));
+}
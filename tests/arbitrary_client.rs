@@ -0,0 +1,154 @@
+use std::collections::BTreeSet;
+
+use themelios::arbitrary_client::{
+    ArbitraryClient, ArbitraryClientAction, ArbitraryClientCfg, Mutation, CLIENT_FINALIZER,
+};
+use themelios::resources::{Container, Deployment, Job};
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, ResourceKind, StateView};
+use themelios::utils;
+
+fn initial() -> RawState {
+    let mut deployment = Deployment {
+        metadata: utils::metadata("web".to_owned()),
+        ..Default::default()
+    };
+    deployment.spec.replicas = 2;
+    deployment.spec.template.spec.containers.push(Container {
+        name: "web".to_owned(),
+        image: "nginx:1.24".to_owned(),
+        ..Default::default()
+    });
+    let job = Job {
+        metadata: utils::metadata("batch".to_owned()),
+        ..Default::default()
+    };
+    RawState::default()
+        .with_deployments([deployment])
+        .with_jobs([job])
+}
+
+#[test]
+fn default_client_makes_every_change() {
+    let actions = ArbitraryClient::actions(
+        &StateView::from(initial()),
+        &initial(),
+        &ArbitraryClientCfg::default(),
+    );
+    assert!(actions.contains(&ArbitraryClientAction::ScaleDeployment(
+        "default".to_owned(),
+        "web".to_owned(),
+        1
+    )));
+    assert!(actions.contains(&ArbitraryClientAction::ScaleDeployment(
+        "default".to_owned(),
+        "web".to_owned(),
+        -1
+    )));
+    assert!(
+        actions.contains(&ArbitraryClientAction::ChangeImageDeployment(
+            "default".to_owned(),
+            "web".to_owned(),
+            "nginx:1.241".to_owned()
+        ))
+    );
+    assert!(
+        actions.contains(&ArbitraryClientAction::TogglePauseDeployment(
+            "default".to_owned(),
+            "web".to_owned()
+        ))
+    );
+    assert!(actions.contains(&ArbitraryClientAction::ToggleSuspendJob(
+        "default".to_owned(),
+        "batch".to_owned()
+    )));
+    // removing workloads has to be asked for
+    assert!(!actions.contains(&ArbitraryClientAction::DeleteJob(
+        "default".to_owned(),
+        "batch".to_owned()
+    )));
+}
+
+#[test]
+fn configured_client_keeps_to_its_kinds_mutations_and_ranges() {
+    let cfg = ArbitraryClientCfg {
+        kinds: Some(BTreeSet::from([ResourceKind::Deployments])),
+        mutations: Some(BTreeSet::from([Mutation::Scale, Mutation::Image])),
+        min_replicas: 2,
+        max_replicas: Some(3),
+        images: vec!["nginx:1.24".to_owned(), "nginx:1.25".to_owned()],
+    };
+    let actions = ArbitraryClient::actions(&StateView::from(initial()), &initial(), &cfg);
+    assert_eq!(
+        actions,
+        vec![
+            ArbitraryClientAction::ScaleDeployment("default".to_owned(), "web".to_owned(), 1),
+            ArbitraryClientAction::ChangeImageDeployment(
+                "default".to_owned(),
+                "web".to_owned(),
+                "nginx:1.25".to_owned()
+            ),
+        ]
+    );
+
+    assert_eq!("delete-namespace".parse(), Ok(Mutation::DeleteNamespace));
+    assert!("replace".parse::<Mutation>().is_err());
+}
+
+#[test]
+fn deleted_workloads_linger_on_finalizers_and_come_back() {
+    let cfg = ArbitraryClientCfg {
+        kinds: Some(BTreeSet::from([ResourceKind::Jobs])),
+        mutations: Some(BTreeSet::from([
+            Mutation::Delete,
+            Mutation::Finalizer,
+            Mutation::Recreate,
+        ])),
+        ..Default::default()
+    };
+    let initial = initial();
+    let mut view = StateView::from(initial.clone());
+    let mut revision = 1;
+    let mut take = |view: &mut StateView, action: ArbitraryClientAction| {
+        let actions = ArbitraryClient::actions(view, &initial, &cfg);
+        assert!(actions.contains(&action), "{action:?} not in {actions:?}");
+        let operation = ArbitraryClient::controller_action(view, action);
+        revision += 1;
+        assert!(view.apply_operation(operation, Revision::from(vec![revision])));
+    };
+
+    take(
+        &mut view,
+        ArbitraryClientAction::ToggleFinalizerJob("default".to_owned(), "batch".to_owned()),
+    );
+    take(
+        &mut view,
+        ArbitraryClientAction::DeleteJob("default".to_owned(), "batch".to_owned()),
+    );
+    let job = view.jobs.get_in("default", "batch").unwrap();
+    assert!(job.metadata.deletion_timestamp.is_some());
+    assert_eq!(job.metadata.finalizers, vec![CLIENT_FINALIZER]);
+
+    take(
+        &mut view,
+        ArbitraryClientAction::ToggleFinalizerJob("default".to_owned(), "batch".to_owned()),
+    );
+    assert!(view.jobs.get_in("default", "batch").is_none());
+
+    let actions = ArbitraryClient::actions(&view, &initial, &cfg);
+    let [ArbitraryClientAction::RecreateJob(job)] = actions.as_slice() else {
+        panic!("expected the job to be recreated, got {actions:?}");
+    };
+    take(&mut view, ArbitraryClientAction::RecreateJob(job.clone()));
+    let job = view.jobs.get_in("default", "batch").unwrap();
+    assert!(job.metadata.deletion_timestamp.is_none());
+    assert_ne!(
+        job.metadata.uid,
+        initial
+            .jobs
+            .get_in("default", "batch")
+            .unwrap()
+            .metadata
+            .uid
+    );
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use themelios::audit::{self, AuditLog, Writer};
+use themelios::model::OrchestrationModelCfg;
+use themelios::rbac::Verb;
+use themelios::resources::{
+    LabelSelector, Metadata, PodSpec, PodTemplateSpec, ReplicaSet, ReplicaSetSpec,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, ResourceKind};
+use themelios::utils;
+
+const CONTROLLER: &str = "system:serviceaccount:kube-system:replicaset-controller";
+
+fn event(user: &str, verb: &str, uri: &str, resource: &str, name: &str, object: &str) -> String {
+    format!(
+        r#"{{"kind":"Event","apiVersion":"audit.k8s.io/v1","stage":"ResponseComplete","verb":"{verb}","requestURI":"{uri}","user":{{"username":"{user}"}},"objectRef":{{"resource":"{resource}","namespace":"default","name":"{name}"}},"responseStatus":{{"code":200}},"responseObject":{object}}}"#
+    )
+}
+
+fn log() -> String {
+    [
+        event(
+            CONTROLLER,
+            "create",
+            "/api/v1/namespaces/default/pods",
+            "pods",
+            "",
+            r#"{"metadata":{"name":"web-x7k2p","generateName":"web-"}}"#,
+        ),
+        // not yet complete, so left out
+        event(
+            CONTROLLER,
+            "create",
+            "/api/v1/namespaces/default/pods",
+            "pods",
+            "",
+            "{}",
+        )
+        .replace("ResponseComplete", "RequestReceived"),
+        event(
+            "admin",
+            "patch",
+            "/apis/apps/v1/namespaces/default/replicasets/web/scale",
+            "replicasets",
+            "web",
+            r#"{"metadata":{"name":"web"},"spec":{"replicas":2}}"#,
+        ),
+        event(
+            "admin",
+            "create",
+            "/api/v1/namespaces/default/configmaps",
+            "configmaps",
+            "settings",
+            r#"{"metadata":{"name":"settings"}}"#,
+        ),
+        event(
+            "system:node:kind-worker",
+            "patch",
+            "/api/v1/namespaces/default/pods/web-x7k2p/status",
+            "pods",
+            "web-x7k2p",
+            r#"{"metadata":{"name":"web-x7k2p"}}"#,
+        ),
+        event(
+            CONTROLLER,
+            "create",
+            "/api/v1/namespaces/default/pods",
+            "pods",
+            "",
+            r#"{"metadata":{"name":"web-q9d4z","generateName":"web-"}}"#,
+        ),
+        event(
+            CONTROLLER,
+            "delete",
+            "/apis/apps/v1/namespaces/default/deployments/api",
+            "deployments",
+            "api",
+            r#"{"kind":"Status"}"#,
+        ),
+    ]
+    .join("\n")
+}
+
+fn replicaset() -> ReplicaSet {
+    let labels = BTreeMap::from([("app".to_owned(), "web".to_owned())]);
+    ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            template: PodTemplateSpec {
+                metadata: Metadata {
+                    labels: labels.clone(),
+                    ..Default::default()
+                },
+                spec: PodSpec::default(),
+            },
+            min_ready_seconds: 0,
+            selector: LabelSelector {
+                match_labels: labels,
+            },
+        },
+        status: Default::default(),
+    }
+}
+
+#[test]
+fn audit_logs_become_writes() {
+    let log = AuditLog::parse(&log()).unwrap();
+    assert_eq!(log.writes.len(), 5);
+    assert_eq!(log.skipped, 1);
+    assert_eq!(log.operations.len(), 1);
+
+    let create = &log.writes[0];
+    assert_eq!(create.writer, Writer::Controller(CONTROLLER.to_owned()));
+    assert_eq!(create.permission.verb, Verb::Create);
+    assert_eq!(create.permission.kind, ResourceKind::Pods);
+    assert_eq!(create.name, "web-");
+    assert_eq!(log.writes[1].writer, Writer::Client(0));
+    assert_eq!(
+        log.writes[2].writer,
+        Writer::Controller("system:node:kind-worker".to_owned())
+    );
+
+    assert!(matches!(
+        AuditLog::parse("{\"verb\": 3}"),
+        Err(audit::AuditError::InvalidLine(1, _))
+    ));
+}
+
+#[test]
+fn controller_writes_the_model_cannot_make_diverge() {
+    let log = AuditLog::parse(&log()).unwrap();
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset()]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.client_operations = log.operations.clone();
+    let model = cfg.into_abstract_model();
+
+    let conformance = audit::check(&model, &log);
+    assert_eq!(conformance.checked, 4);
+    assert_eq!(conformance.unattributed, 1);
+    assert_eq!(conformance.divergences.len(), 1);
+    let divergence = &conformance.divergences[0];
+    assert_eq!(divergence.index, 4);
+    assert_eq!(divergence.write.name, "api");
+    assert!(!conformance.conforms());
+}
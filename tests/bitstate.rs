@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::{Checker, Model, Property};
+use themelios::abstract_model::AbstractModel;
+use themelios::bitstate::{check_bitstate, BloomFilter};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::trace::Trace;
+use themelios::utils;
+
+fn model() -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model.properties.push(Property::sometimes(
+        "two pods are created",
+        |_model, state: &<AbstractModel as Model>::State| state.latest().pods.len() == 2,
+    ));
+    model.into_abstract_model()
+}
+
+struct NoReporter;
+
+impl Reporter<AbstractModel> for NoReporter {
+    fn report_checking(&mut self, _data: ReportData) {}
+
+    fn report_discoveries(
+        &mut self,
+        _discoveries: BTreeMap<&'static str, ReportDiscovery<AbstractModel>>,
+    ) {
+    }
+}
+
+#[test]
+fn bloom_filter_fills_up() {
+    let mut filter = BloomFilter::new(1024, 3);
+    assert_eq!(filter.false_positive_rate(), 0.0);
+    let fingerprint = |i: u64| stateright::fingerprint(&i);
+    assert!(filter.insert(fingerprint(1)));
+    assert!(!filter.insert(fingerprint(1)));
+    let rate = filter.false_positive_rate();
+    assert!(rate > 0.0);
+    for i in 2..100 {
+        filter.insert(fingerprint(i));
+    }
+    assert!(filter.false_positive_rate() > rate);
+}
+
+#[test]
+fn large_filters_explore_the_whole_state_space() {
+    let mut filter = BloomFilter::new(1 << 20, 3);
+    let check = check_bitstate(&model(), &mut filter, 0, &mut NoReporter);
+    let checker = model().checker().spawn_bfs().join();
+    assert_eq!(check.unique_states, checker.unique_state_count());
+    assert!(check.false_positive_rate < 1e-6);
+
+    // discoveries can be replayed
+    let path = check.encoded_path("two pods are created").unwrap();
+    let trace = Trace::replay(&model(), &path).unwrap();
+    assert_eq!(trace.steps.last().unwrap().state.latest().pods.len(), 2);
+}
+
+#[test]
+fn small_filters_skip_states() {
+    let mut full = BloomFilter::new(1 << 20, 3);
+    let complete = check_bitstate(&model(), &mut full, 0, &mut NoReporter);
+    let mut tiny = BloomFilter::new(8, 3);
+    let partial = check_bitstate(&model(), &mut tiny, 0, &mut NoReporter);
+    assert!(partial.unique_states < complete.unique_states);
+    assert!(partial.false_positive_rate > complete.false_positive_rate);
+}
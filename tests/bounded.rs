@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::{Checker, Model, Property};
+use themelios::abstract_model::AbstractModel;
+use themelios::bounded::{check_bounded, VisitedCache};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::trace::Trace;
+use themelios::utils;
+
+fn model() -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model.properties.push(Property::sometimes(
+        "two pods are created",
+        |_model, state: &<AbstractModel as Model>::State| state.latest().pods.len() == 2,
+    ));
+    model.into_abstract_model()
+}
+
+struct NoReporter;
+
+impl Reporter<AbstractModel> for NoReporter {
+    fn report_checking(&mut self, _data: ReportData) {}
+
+    fn report_discoveries(
+        &mut self,
+        _discoveries: BTreeMap<&'static str, ReportDiscovery<AbstractModel>>,
+    ) {
+    }
+}
+
+#[test]
+fn visited_cache_evicts_least_recently_seen() {
+    let fingerprint = |i: u64| stateright::fingerprint(&i);
+    let mut visited = VisitedCache::new(2);
+    assert!(visited.insert(fingerprint(1)));
+    assert!(visited.insert(fingerprint(2)));
+    // seeing 1 again leaves 2 as the least recently seen
+    assert!(!visited.insert(fingerprint(1)));
+    assert!(visited.insert(fingerprint(3)));
+    assert_eq!(visited.len(), 2);
+    assert_eq!(visited.evictions(), 1);
+    assert!(!visited.insert(fingerprint(1)));
+    assert!(visited.insert(fingerprint(2)));
+}
+
+#[test]
+fn large_caches_explore_the_whole_state_space() {
+    let mut visited = VisitedCache::new(1 << 20);
+    let check = check_bounded(&model(), &mut visited, 0, &mut NoReporter);
+    let checker = model().checker().spawn_bfs().join();
+    assert_eq!(check.unique_states, checker.unique_state_count());
+    assert_eq!(check.evictions, 0);
+
+    // discoveries can be replayed
+    let path = check.encoded_path("two pods are created").unwrap();
+    let trace = Trace::replay(&model(), &path).unwrap();
+    assert_eq!(trace.steps.last().unwrap().state.latest().pods.len(), 2);
+}
+
+#[test]
+fn small_caches_explore_states_again() {
+    // bound the depth as evicting so much explores every path to each state
+    let mut full = VisitedCache::new(1 << 20);
+    let complete = check_bounded(&model(), &mut full, 6, &mut NoReporter);
+    let mut small = VisitedCache::new(4);
+    let bounded = check_bounded(&model(), &mut small, 6, &mut NoReporter);
+    assert!(bounded.evictions > 0);
+    assert!(small.len() <= 4);
+    assert!(bounded.unique_states >= complete.unique_states);
+    assert!(bounded.discoveries.contains_key("two pods are created"));
+}
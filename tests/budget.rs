@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use stateright::report::{ReportData, Reporter};
+use themelios::abstract_model::AbstractModel;
+use themelios::budget::{Budget, BudgetReporter, Exhausted};
+
+fn data(total_states: usize, duration: Duration) -> ReportData {
+    ReportData {
+        total_states,
+        unique_states: total_states,
+        max_depth: 1,
+        duration,
+        done: false,
+    }
+}
+
+#[test]
+fn budgets_run_out_on_the_first_limit_reached() {
+    let budget = Budget {
+        max_states: Some(100),
+        timeout: Some(Duration::from_secs(10)),
+        max_memory: Some(1 << 30),
+    };
+    assert_eq!(budget.exhausted(&data(10, Duration::from_secs(1)), 0), None);
+    assert_eq!(
+        budget.exhausted(&data(100, Duration::from_secs(1)), 0),
+        Some(Exhausted::States)
+    );
+    assert_eq!(
+        budget.exhausted(&data(10, Duration::from_secs(10)), 0),
+        Some(Exhausted::Duration)
+    );
+    assert_eq!(
+        budget.exhausted(&data(10, Duration::from_secs(1)), 1 << 30),
+        Some(Exhausted::Memory)
+    );
+    assert!(!Budget::default().is_limited());
+    assert_eq!(
+        Budget::default().exhausted(&data(usize::MAX, Duration::MAX), u64::MAX),
+        None
+    );
+}
+
+#[test]
+fn running_out_stops_the_check() {
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut reporter = BudgetReporter::new(
+        Budget {
+            timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        },
+        stop.clone(),
+    );
+    Reporter::<AbstractModel>::report_checking(&mut reporter, data(1, Duration::from_secs(1)));
+    assert!(!stop.load(Ordering::Relaxed));
+    Reporter::<AbstractModel>::report_checking(&mut reporter, data(2, Duration::from_secs(6)));
+    assert!(stop.load(Ordering::Relaxed));
+    assert_eq!(reporter.exhausted(), Some(Exhausted::Duration));
+}
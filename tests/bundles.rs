@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use stateright::Model;
+use themelios::abstract_model::PropertySelection;
+use themelios::controller_properties::{self, BUNDLES};
+use themelios::model::OrchestrationModelCfg;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+
+fn names(bundle: &str) -> BTreeSet<&'static str> {
+    controller_properties::for_controller(bundle)
+        .unwrap()
+        .into_iter()
+        .map(|p| p.name)
+        .collect()
+}
+
+#[test]
+fn every_controller_has_a_bundle() {
+    for bundle in BUNDLES {
+        assert!(
+            controller_properties::for_controller(bundle).is_some(),
+            "{bundle}"
+        );
+    }
+    assert!(controller_properties::for_controller("Widget").is_none());
+}
+
+#[test]
+fn deployment_bundle_includes_availability() {
+    assert!(names("Deployment").contains(
+        "dep: available replicas stay above replicas - maxUnavailable during rolling updates"
+    ));
+}
+
+#[test]
+fn running_controllers_get_their_bundles_unless_others_are_chosen() {
+    let model = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 1)
+        .into_abstract_model();
+    let properties = model
+        .properties
+        .iter()
+        .map(|p| p.name)
+        .collect::<BTreeSet<_>>();
+    assert!(properties.is_superset(&names("Deployment")));
+    assert!(properties.is_superset(&names("ReplicaSet")));
+
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 1);
+    cfg.property_bundles = Some(BTreeSet::from(["ReplicaSet".to_owned()]));
+    let properties = cfg
+        .into_abstract_model()
+        .properties
+        .iter()
+        .map(|p| p.name)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(properties, names("ReplicaSet"));
+}
+
+#[test]
+fn properties_can_be_chosen_skipped_and_made_warnings() {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 1);
+    cfg.status_writes = true;
+    cfg.property_selection = PropertySelection {
+        only: Some(BTreeSet::from([
+            "all resources have unique names".to_owned(),
+            "controllers never change specs through status updates".to_owned(),
+        ])),
+        skip: BTreeSet::from(["controllers never change specs through status updates".to_owned()]),
+        warnings: BTreeSet::from(["all resources have unique names".to_owned()]),
+    };
+    let model = cfg.into_abstract_model();
+    let properties = model
+        .properties()
+        .iter()
+        .map(|p| p.name)
+        .collect::<Vec<_>>();
+    assert_eq!(properties, ["all resources have unique names"]);
+    assert!(model
+        .property_selection
+        .is_warning("all resources have unique names"));
+    assert!(model.all_properties().len() > properties.len());
+}
+
+#[test]
+#[should_panic(expected = "No property named \"widgets are frobnicated\"")]
+fn unknown_properties_cannot_be_chosen() {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 1);
+    cfg.property_selection.skip = BTreeSet::from(["widgets are frobnicated".to_owned()]);
+    cfg.into_abstract_model();
+}
@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+
+use stateright::{Checker, Model};
+use themelios::abstract_model::AbstractModel;
+use themelios::checkpoint::Checkpoint;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+
+fn model(replicas: u32) -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(replicas),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model.into_abstract_model()
+}
+
+fn checkpoint_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("themelios-{name}-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Check the model through the checkpoint, returning how many states were visited.
+fn check(mut model: AbstractModel, checkpoint: &Checkpoint) -> usize {
+    model.explored = checkpoint.explored();
+    let checker = model
+        .checker()
+        .visitor(checkpoint.clone())
+        .spawn_bfs()
+        .join();
+    checkpoint.save().unwrap();
+    checker.unique_state_count()
+}
+
+#[test]
+fn resuming_skips_explored_states() {
+    let path = checkpoint_path("resume");
+    let checkpoint = Checkpoint::load(path.clone(), &model(1)).unwrap();
+    assert!(checkpoint.explored().is_empty());
+    assert!(check(model(1), &checkpoint) > 0);
+
+    let model = model(1);
+    let resumed = Checkpoint::load(path.clone(), &model).unwrap();
+    let initial = stateright::fingerprint(&model.init_states()[0]);
+    assert!(resumed.explored().contains(&initial));
+    assert_eq!(check(model, &resumed), 0);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn states_are_only_explored_once_all_that_follows_is() {
+    let path = checkpoint_path("depth");
+    let mut limited = model(2);
+    let initial = stateright::fingerprint(&limited.init_states()[0]);
+    let checkpoint = Checkpoint::load(path.clone(), &limited).unwrap();
+    limited.explored = checkpoint.explored();
+    limited
+        .checker()
+        .visitor(checkpoint.clone())
+        .target_max_depth(2)
+        .spawn_bfs()
+        .join();
+    checkpoint.save().unwrap();
+
+    // the rest of the state space is still to be explored
+    let resumed = Checkpoint::load(path.clone(), &model(2)).unwrap();
+    assert!(!resumed.explored().contains(&initial));
+    assert!(check(model(2), &resumed) > 0);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn checkpoints_of_other_models_are_ignored() {
+    let path = checkpoint_path("other");
+    let checkpoint = Checkpoint::load(path.clone(), &model(1)).unwrap();
+    check(model(1), &checkpoint);
+
+    let other = Checkpoint::load(path.clone(), &model(2)).unwrap();
+    assert!(other.explored().is_empty());
+    std::fs::remove_file(path).unwrap();
+}
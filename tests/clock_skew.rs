@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use stateright::Model;
+use themelios::abstract_model::Action;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{
+    LabelSelector, Metadata, PodSpec, PodTemplateSpec, ReplicaSet, ReplicaSetSpec, Time,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+
+fn replicaset() -> ReplicaSet {
+    let labels = BTreeMap::from([("app".to_owned(), "web".to_owned())]);
+    ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            template: PodTemplateSpec {
+                metadata: Metadata {
+                    labels: labels.clone(),
+                    ..Default::default()
+                },
+                spec: PodSpec::default(),
+            },
+            min_ready_seconds: 0,
+            selector: LabelSelector {
+                match_labels: labels,
+            },
+        },
+        status: Default::default(),
+    }
+}
+
+#[test]
+fn skewed_controllers_see_and_stamp_their_own_time() {
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset()]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 2;
+    cfg.clock_skew = 30;
+    let model = cfg.into_abstract_model();
+    assert_eq!(model.clock_skews, BTreeMap::from([(1, 30)]));
+
+    let state = model.init_states().remove(0);
+    let revision = state.max_revision();
+    let cluster_now = state.latest().now();
+    assert_eq!(model.view_for(&state, &revision, 0).now(), cluster_now);
+    let skewed_now = model.view_for(&state, &revision, 1).now();
+    assert_eq!(skewed_now, Time(cluster_now.0 + Duration::from_secs(30)));
+
+    // the pod is created through the skewed controller's api server
+    let state = model
+        .next_state(&state, Action::ControllerStep(revision, 1))
+        .unwrap();
+    let latest = state.latest();
+    let pod = latest.pods.iter().next().unwrap();
+    assert_eq!(pod.metadata.creation_timestamp, Some(skewed_now));
+}
@@ -3,16 +3,19 @@ use stateright::HasDiscoveries;
 use stateright::Model;
 use stateright::UniformChooser;
 use std::collections::BTreeMap;
-use std::fs::create_dir;
+use std::fs::create_dir_all;
 use std::num::NonZeroU64;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::time::Duration;
+use themelios::abstract_model::AbstractModel;
 use themelios::model::OrchestrationModelCfg;
 use themelios::report::CSVReporter;
 use themelios::report::JointReporter;
+use themelios::report::RolloutTracker;
+use themelios::report::SqliteReporter;
 use themelios::report::StdoutReporter;
 use themelios::state::history::ConsistencySetup;
 use tracing::info;
@@ -81,32 +84,51 @@ fn check(model: OrchestrationModelCfg, test_name: &str, should_succeed: bool, ma
     let consistency = model.consistency_level.clone();
     let controllers = model.nodes;
     let am = model.into_abstract_model();
-    let report_dir =
-        PathBuf::from(std::env::var("MCO_REPORT_PATH").unwrap_or_else(|_| "testout".to_owned()));
+    // keep the reports out of the working tree unless asked for them, as the scripts do
+    let report_dir = std::env::var("MCO_REPORT_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir().join("themelios-testout"));
     if !report_dir.exists() {
-        create_dir(&report_dir).unwrap();
+        create_dir_all(&report_dir).unwrap();
     } else if !report_dir.is_dir() {
         panic!("Report dir {report_dir:?} should be a directory!");
     }
     let report_file = format!("{test_name}.csv");
     let report_path = report_dir.join(report_file);
+    let rollouts_path = report_dir.join(format!("{test_name}-rollouts.csv"));
+    let rollouts = RolloutTracker::default();
     let depths = DepthTracker::new(
         max_depth,
         consistency.clone(),
         controllers,
         test_name.to_owned(),
+        rollouts.clone(),
     );
     let depths2 = depths.clone();
+    let sqlite = SqliteReporter::new(
+        &am,
+        &report_dir.join("reports.sqlite"),
+        consistency.clone(),
+        max_depth,
+        controllers,
+        test_name.to_owned(),
+    )
+    .unwrap()
+    .with_rollouts(rollouts.clone());
     let mut reporter = JointReporter {
         reporters: vec![
-            Box::new(StdoutReporter::new(&am)),
-            Box::new(CSVReporter::new(
-                &report_path,
-                consistency,
-                max_depth,
-                controllers,
-                test_name.to_owned(),
-            )),
+            Box::new(StdoutReporter::new(&am).with_rollouts(rollouts.clone())),
+            Box::new(sqlite.clone()),
+            Box::new(
+                CSVReporter::new(
+                    &report_path,
+                    consistency,
+                    max_depth,
+                    controllers,
+                    test_name.to_owned(),
+                )
+                .with_rollouts(rollouts, &rollouts_path),
+            ),
         ],
     };
     let checker = am
@@ -137,6 +159,7 @@ fn check(model: OrchestrationModelCfg, test_name: &str, should_succeed: bool, ma
     };
     let depth_file = format!("{test_name}-depths.csv");
     depths2.to_csv(&report_dir.join(depth_file));
+    sqlite.record_depths(depths2.counts());
     if check_result.iter().all(|(_, ok)| *ok) != should_succeed && !cfg!(tarpaulin) {
         // don't panic during coverage runs, that breaks the llvm engine
         panic!("Some properties failed");
@@ -164,6 +187,7 @@ struct DepthTracker {
     max_depth: usize,
     controllers: usize,
     function: String,
+    rollouts: RolloutTracker,
 }
 
 impl DepthTracker {
@@ -172,6 +196,7 @@ impl DepthTracker {
         consistency: ConsistencySetup,
         controllers: usize,
         function: String,
+        rollouts: RolloutTracker,
     ) -> Self {
         let mut depths = BTreeMap::new();
         for i in 0..=max_depth {
@@ -183,9 +208,17 @@ impl DepthTracker {
             max_depth,
             controllers,
             function,
+            rollouts,
         }
     }
 
+    fn counts(&self) -> Vec<(usize, u64)> {
+        self.depths
+            .iter()
+            .map(|(d, c)| (*d, c.load(std::sync::atomic::Ordering::Relaxed)))
+            .collect()
+    }
+
     fn to_csv(&self, path: &Path) {
         let mut writer = csv::Writer::from_path(path).unwrap();
         writer
@@ -214,15 +247,13 @@ impl DepthTracker {
     }
 }
 
-impl<M> stateright::CheckerTerminalVisitor<M> for DepthTracker
-where
-    M: Model,
-{
-    fn visit(&self, _model: &M, path: &[NonZeroU64]) {
+impl stateright::CheckerTerminalVisitor<AbstractModel> for DepthTracker {
+    fn visit(&self, model: &AbstractModel, path: &[NonZeroU64]) {
         let len = path.len();
         self.depths
             .get(&len)
             .unwrap()
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        stateright::CheckerTerminalVisitor::visit(&self.rollouts, model, path);
     }
 }
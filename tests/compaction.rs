@@ -0,0 +1,125 @@
+use stateright::Model;
+use themelios::abstract_model::{AbstractModel, Change, ControllerAction};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{Pod, ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::{Compaction, ConsistencySetup};
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, State};
+use themelios::utils;
+
+fn rev(i: usize) -> Revision {
+    Revision::from(vec![i])
+}
+
+fn state_with_pods(pods: usize) -> State {
+    let mut state = State::new(RawState::default(), ConsistencySetup::MonotonicSession);
+    for i in 0..pods {
+        state.push_change(Change {
+            revision: state.max_revision(),
+            operation: ControllerAction::CreatePod(Pod {
+                metadata: utils::metadata(format!("pod-{i}")),
+                ..Default::default()
+            }),
+        });
+    }
+    state
+}
+
+fn model(compaction: Compaction) -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::MonotonicSession,
+        0,
+    );
+    model.replicaset_controllers = 2;
+    model.compaction = compaction;
+    model.into_abstract_model()
+}
+
+#[test]
+fn compaction_keeps_referenced_revisions() {
+    let mut state = state_with_pods(4);
+    let before = state.revisions(Some(&rev(2)));
+    let referenced = state.view_at(&rev(2)).into_owned();
+    assert_eq!(state.view_at(&rev(1)).pods.len(), 1);
+    state.compact(1, &[rev(2)]);
+
+    // the unreferenced revision held a pod, so is only empty once dropped
+    assert!(state.view_at(&rev(1)).pods.is_empty());
+    assert_eq!(state.view_at(&rev(2)).pods.len(), 2);
+    assert_eq!(*state.view_at(&rev(2)), referenced);
+    assert_eq!(state.revisions(Some(&rev(2))), before);
+
+    // keeping more than are referenced
+    let mut state = state_with_pods(5);
+    assert_eq!(state.view_at(&rev(1)).pods.len(), 1);
+    state.compact(4, &[]);
+    assert!(state.view_at(&rev(1)).pods.is_empty());
+    assert_eq!(state.view_at(&rev(2)).pods.len(), 2);
+}
+
+#[test]
+fn compaction_merges_states_with_different_pasts() {
+    let metrics = |names: [&str; 2]| {
+        let mut state = State::new(RawState::default(), ConsistencySetup::Synchronous);
+        for name in names {
+            state.push_change(Change {
+                revision: state.max_revision(),
+                operation: ControllerAction::UpdateMetric(
+                    "default".to_owned(),
+                    name.to_owned(),
+                    50,
+                ),
+            });
+        }
+        state
+    };
+    let mut a = metrics(["a", "b"]);
+    let mut b = metrics(["b", "a"]);
+    assert_ne!(a, b);
+    a.compact(1, &[]);
+    b.compact(1, &[]);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn compaction_never_changes_controller_behaviour() {
+    let full = model(Compaction::Disabled);
+    let compacted = model(Compaction::Referenced);
+    let mut frontier = vec![(
+        full.init_states().pop().unwrap(),
+        compacted.init_states().pop().unwrap(),
+    )];
+    for _ in 0..4 {
+        let mut next = Vec::new();
+        for (full_state, compacted_state) in frontier {
+            let mut full_actions = Vec::new();
+            full.actions(&full_state, &mut full_actions);
+            let mut compacted_actions = Vec::new();
+            compacted.actions(&compacted_state, &mut compacted_actions);
+            assert_eq!(full_actions, compacted_actions);
+
+            for action in full_actions {
+                let full_next = full.next_state(&full_state, action.clone());
+                let compacted_next = compacted.next_state(&compacted_state, action);
+                match (full_next, compacted_next) {
+                    (Some(f), Some(c)) => {
+                        assert_eq!(f.latest().state, c.latest().state);
+                        next.push((f, c));
+                    }
+                    (None, None) => {}
+                    _ => panic!("compaction changed whether an action was taken"),
+                }
+            }
+        }
+        frontier = next;
+    }
+}
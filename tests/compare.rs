@@ -0,0 +1,56 @@
+use themelios::compare;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::user_properties;
+use themelios::utils;
+
+#[test]
+fn properties_are_compared_across_levels() {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.user_properties =
+        user_properties::parse(r#"always "at most one pod": count(pods) <= 1"#).unwrap();
+
+    let levels = [ConsistencySetup::Synchronous, ConsistencySetup::Causal];
+    let comparison = compare::compare(&cfg, &levels, 1, 4);
+    assert_eq!(comparison.levels.len(), 2);
+    assert_eq!(
+        comparison.levels[1].consistency_level,
+        ConsistencySetup::Causal
+    );
+    assert!(!comparison.holds("at most one pod", 0));
+    assert!(!comparison.holds("at most one pod", 1));
+
+    let failures = comparison
+        .failures()
+        .into_iter()
+        .filter(|(name, _, _)| *name == "at most one pod")
+        .map(|(_, level, _)| level)
+        .collect::<Vec<_>>();
+    assert_eq!(failures, [0, 1]);
+
+    let table = comparison.table();
+    let mut lines = table.lines();
+    let header = lines.next().unwrap();
+    assert!(header.starts_with("Property"));
+    assert!(header.ends_with("Synchronous  Causal"));
+    let row = table
+        .lines()
+        .find(|l| l.contains("\"at most one pod\""))
+        .unwrap();
+    assert!(row.ends_with("FAILED       FAILED"), "{row}");
+}
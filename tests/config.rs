@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+use clap::{Arg, Command};
+use themelios::config::layer_args;
+
+fn command() -> Command<'static> {
+    Command::new("themelios")
+        .arg(Arg::new("nodes").long("nodes").takes_value(true))
+        .arg(Arg::new("fairness").long("fairness"))
+        .arg(Arg::new("verbose").long("verbose").short('v'))
+}
+
+fn layer(cli: &[&str], file: &[(&str, &str)], env: &[(&str, &str)]) -> Vec<OsString> {
+    let args = std::iter::once("themelios")
+        .chain(cli.iter().copied())
+        .map(OsString::from)
+        .collect();
+    let file = file
+        .iter()
+        .map(|(k, v)| (k.to_string(), vec![v.to_string()]))
+        .collect();
+    let env = env
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<BTreeMap<_, _>>();
+    layer_args(&command(), args, file, &env)
+}
+
+fn nodes(args: Vec<OsString>) -> Option<String> {
+    command()
+        .try_get_matches_from(args)
+        .unwrap()
+        .value_of("nodes")
+        .map(|v| v.to_owned())
+}
+
+#[test]
+fn cli_overrides_env_overrides_file() {
+    let file = [("nodes", "1")];
+    let env = [("THEMELIOS_NODES", "2")];
+    assert_eq!(nodes(layer(&[], &file, &[])).as_deref(), Some("1"));
+    assert_eq!(nodes(layer(&[], &file, &env)).as_deref(), Some("2"));
+    assert_eq!(
+        nodes(layer(&["--nodes", "3"], &file, &env)).as_deref(),
+        Some("3")
+    );
+    assert_eq!(
+        nodes(layer(&["--nodes=3"], &file, &env)).as_deref(),
+        Some("3")
+    );
+}
+
+#[test]
+fn env_flags_accept_common_booleans() {
+    for (value, set) in [
+        ("true", true),
+        ("1", true),
+        ("yes", true),
+        ("false", false),
+        ("0", false),
+        ("no", false),
+    ] {
+        let args = layer(&[], &[], &[("THEMELIOS_FAIRNESS", value)]);
+        let matches = command().try_get_matches_from(args).unwrap();
+        assert_eq!(matches.is_present("fairness"), set, "{value}");
+    }
+    let args = layer(&[], &[], &[("THEMELIOS_FAIRNESS", "maybe")]);
+    assert!(command().try_get_matches_from(args).is_err());
+}
+
+#[test]
+fn short_flags_on_the_cli_override_the_env() {
+    let args = layer(&["-v"], &[], &[("THEMELIOS_VERBOSE", "0")]);
+    let matches = command().try_get_matches_from(args).unwrap();
+    assert!(matches.is_present("verbose"));
+}
+
+#[test]
+fn other_prefixed_variables_are_ignored() {
+    let args = layer(
+        &[],
+        &[],
+        &[("THEMELIOS_UNKNOWN", "1"), ("THEMELIOS_CONFIG", "x")],
+    );
+    assert_eq!(args, vec![OsString::from("themelios")]);
+}
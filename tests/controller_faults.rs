@@ -0,0 +1,45 @@
+use stateright::Model;
+use themelios::abstract_model::{AbstractModel, Action};
+use themelios::model::OrchestrationModelCfg;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+
+fn actions(model: &AbstractModel, state: &State) -> Vec<Action> {
+    let mut actions = Vec::new();
+    model.actions(state, &mut actions);
+    actions
+}
+
+#[test]
+fn crashed_controllers_lose_their_state_until_restarted() {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.schedulers = 1;
+    cfg.controller_crashes = true;
+    let model = cfg.into_abstract_model();
+    let fresh = model.controllers[0].new_state();
+
+    let state = model.init_states().remove(0);
+    let state = model
+        .next_state(&state, Action::ControllerStep(state.max_revision(), 0))
+        .unwrap();
+    assert_ne!(state.get_controller(0), &fresh);
+    assert!(actions(&model, &state).contains(&Action::ControllerCrash(0)));
+
+    let crashed = model
+        .next_state(&state, Action::ControllerCrash(0))
+        .unwrap();
+    assert_eq!(crashed.get_controller(0), &fresh);
+    let actions_while_crashed = actions(&model, &crashed);
+    assert!(!actions_while_crashed
+        .iter()
+        .any(|a| a.controller() == Some(0)));
+    assert!(!actions_while_crashed.contains(&Action::ControllerCrash(0)));
+    assert!(actions_while_crashed.contains(&Action::ControllerRestart(0)));
+
+    let restarted = model
+        .next_state(&crashed, Action::ControllerRestart(0))
+        .unwrap();
+    assert!(actions(&model, &restarted)
+        .iter()
+        .any(|a| matches!(a, Action::ControllerStep(_, 0))));
+}
@@ -0,0 +1,62 @@
+use stateright::{Checker, Model};
+use themelios::abstract_model::{ActionKind, ControllerAction};
+use themelios::coverage::{ActionCoverage, BRANCHES};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{Deployment, ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, StateView};
+use themelios::utils;
+
+#[test]
+fn coverage_records_the_actions_taken_during_a_check() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([rs]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    let mut model = cfg.into_abstract_model();
+    let coverage = ActionCoverage::default();
+    model.coverage = Some(coverage.clone());
+    model.checker().spawn_bfs().join();
+
+    let data = coverage.data();
+    assert!(data.actions["CreatePod"] > 0);
+    assert!(data.missed_actions().contains(&"UpdateDeploymentStatus"));
+    assert_eq!(data.missed_branches(), BRANCHES.to_vec());
+    let summary = coverage.summary();
+    assert!(summary.starts_with("Controller action coverage: "));
+    assert!(summary.contains("  deployment: rollback: never\n"));
+}
+
+#[test]
+fn coverage_tells_branches_apart_from_the_view() {
+    let deployment = Deployment {
+        metadata: utils::metadata("web".to_owned()),
+        ..Default::default()
+    };
+    let view = StateView::from(RawState::default().with_deployments([deployment.clone()]));
+    let coverage = ActionCoverage::default();
+
+    coverage.record(
+        &view,
+        &ControllerAction::UpdateDeploymentStatus(deployment.clone()),
+    );
+    assert!(coverage.data().branches.is_empty());
+
+    let mut collided = deployment;
+    collided.status.collision_count += 1;
+    coverage.record(&view, &ControllerAction::UpdateDeploymentStatus(collided));
+    let data = coverage.data();
+    assert_eq!(data.actions["UpdateDeploymentStatus"], 2);
+    assert_eq!(data.branches["deployment: hash collision"], 1);
+}
@@ -0,0 +1,236 @@
+use common::run;
+use common::test_table;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use stdext::function_name;
+use themelios::controller::cronjob::Schedule;
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
+use themelios::resources::ConcurrencyPolicy;
+use themelios::resources::Container;
+use themelios::resources::CronJob;
+use themelios::resources::CronJobSpec;
+use themelios::resources::JobSpec;
+use themelios::resources::JobTemplateSpec;
+use themelios::resources::Metadata;
+use themelios::resources::PodSpec;
+use themelios::resources::PodTemplateSpec;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+use time::Date;
+use time::Month;
+use time::OffsetDateTime;
+
+mod common;
+
+fn model(
+    cronjobs: impl IntoIterator<Item = CronJob>,
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    let initial_state = RawState::default().with_cronjobs(cronjobs);
+    OrchestrationModelCfg {
+        initial_state,
+        consistency_level: consistency,
+        schedulers: controllers,
+        scheduler_profiles: Vec::new(),
+        nodes: controllers,
+        container_restarts: 0,
+        image_pulls: false,
+        replicaset_controllers: 0,
+        replicationcontroller_controllers: 0,
+        deployment_controllers: 0,
+        statefulset_controllers: 0,
+        job_controllers: controllers,
+        cronjob_controllers: controllers,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
+        podgc_controllers: controllers,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
+        properties: Vec::new(),
+    }
+}
+
+fn new_cronjob(name: &str, schedule: &str) -> CronJob {
+    let mut test_labels = BTreeMap::new();
+    test_labels.insert("name".to_owned(), "test".to_owned());
+    let mut job_spec = JobSpec::default();
+    job_spec.selector.match_labels = test_labels.clone();
+    job_spec.template = PodTemplateSpec {
+        metadata: Metadata {
+            labels: test_labels,
+            ..Default::default()
+        },
+        spec: PodSpec {
+            containers: vec![Container {
+                name: "fake".to_owned(),
+                image: "fake".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    };
+    CronJob {
+        metadata: utils::metadata(name.to_owned()),
+        spec: CronJobSpec {
+            schedule: schedule.to_owned(),
+            job_template: JobTemplateSpec {
+                metadata: Metadata::default(),
+                spec: job_spec,
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn test_every_minute(consistency: ConsistencySetup, controllers: usize) -> OrchestrationModelCfg {
+    // initial state: cronjob scheduled every minute
+    // eventually: a job is created once the clock has advanced
+    let cronjob = new_cronjob("every-minute", "* * * * *");
+    model([cronjob], consistency, controllers)
+}
+
+test_table! {
+    test_every_minute,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_forbid_concurrent(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: cronjob scheduled every minute that forbids concurrent runs
+    // always: at most one of its jobs is running
+    let mut cronjob = new_cronjob("forbid", "* * * * *");
+    cronjob.spec.concurrency_policy = ConcurrencyPolicy::Forbid;
+    model([cronjob], consistency, controllers)
+}
+
+test_table! {
+    test_forbid_concurrent,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn at(day: u8, hour: u8, minute: u8, second: u8) -> OffsetDateTime {
+    Date::from_calendar_date(2024, Month::January, day)
+        .unwrap()
+        .with_hms(hour, minute, second)
+        .unwrap()
+        .assume_utc()
+}
+
+#[test]
+fn schedule_parses_standard_fields() {
+    let schedule = Schedule::parse("*/15 2-4 * * 1,3").unwrap();
+    // wednesday
+    assert!(schedule.matches(at(3, 3, 30, 0)));
+    assert!(!schedule.matches(at(3, 3, 31, 0)));
+    assert!(!schedule.matches(at(3, 5, 30, 0)));
+    // thursday
+    assert!(!schedule.matches(at(4, 3, 30, 0)));
+}
+
+#[test]
+fn schedule_parses_macros() {
+    let schedule = Schedule::parse("@hourly").unwrap();
+    assert!(schedule.matches(at(3, 3, 0, 0)));
+    assert!(!schedule.matches(at(3, 3, 1, 0)));
+}
+
+#[test]
+fn schedule_rejects_invalid() {
+    assert!(Schedule::parse("* * *").is_err());
+    assert!(Schedule::parse("60 * * * *").is_err());
+    assert!(Schedule::parse("*/0 * * * *").is_err());
+}
+
+#[test]
+fn schedule_finds_most_recent_time() {
+    let schedule = Schedule::parse("*/10 * * * *").unwrap();
+    let earliest = at(3, 3, 0, 0);
+    assert_eq!(
+        schedule.most_recent_time(earliest, at(3, 3, 25, 30)),
+        Some(at(3, 3, 20, 0))
+    );
+    // nothing new since the earliest time
+    assert_eq!(schedule.most_recent_time(earliest, at(3, 3, 5, 0)), None);
+}
+
+#[test]
+fn schedule_most_recent_time_matches_checking_each_minute() {
+    let earliest = at(1, 0, 0, 0);
+    let now = at(10, 13, 47, 12);
+    for schedule in [
+        "*/7 * * * *",
+        "30 9-17 * * 1-5",
+        "5 0 * * 0",
+        "0 12 8 * 3",
+        "15 */5 2,9 * *",
+    ] {
+        let schedule = Schedule::parse(schedule).unwrap();
+        let mut t = at(10, 13, 47, 0);
+        while t > earliest && !schedule.matches(t) {
+            t -= Duration::from_secs(60);
+        }
+        let expected = (t > earliest).then_some(t);
+        assert_eq!(schedule.most_recent_time(earliest, now), expected);
+    }
+}
+
+#[test]
+fn schedule_skips_back_over_whole_years() {
+    // leap days only come every four years, so there are millions of minutes between them
+    let schedule = Schedule::parse("0 0 29 2 *").unwrap();
+    let leap_day = Date::from_calendar_date(2024, Month::February, 29)
+        .unwrap()
+        .midnight()
+        .assume_utc();
+    let earliest = leap_day - Duration::from_secs(60);
+    let now = Date::from_calendar_date(2028, Month::February, 28)
+        .unwrap()
+        .midnight()
+        .assume_utc();
+    assert_eq!(schedule.most_recent_time(earliest, now), Some(leap_day));
+}
@@ -1,21 +1,41 @@
 use common::run;
 use common::test_table;
 use common::test_table_panic;
+use stateright::Model;
 use std::collections::BTreeMap;
 use stdext::function_name;
+use themelios::abstract_model::{Action, ControllerAction};
+use themelios::controller::deployment;
 use themelios::controller::deployment::LAST_APPLIED_CONFIG_ANNOTATION;
-use themelios::model::OrchestrationModelCfg;
+use themelios::controller::deployment::{DEPRECATED_ROLLBACK_TO, REVISION_ANNOTATION};
+use themelios::controller::Controllers;
+use themelios::controller::DeploymentController;
+use themelios::controller::{Controller, DeploymentControllerState};
+use themelios::controller_properties::deployment::min_available;
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
+use themelios::rbac;
+use themelios::rbac::Role;
+use themelios::rbac::Verb;
 use themelios::resources::Container;
 use themelios::resources::Deployment;
+use themelios::resources::DeploymentConditionType;
+use themelios::resources::DeploymentRollback;
 use themelios::resources::DeploymentSpec;
 use themelios::resources::DeploymentStrategy;
 use themelios::resources::IntOrString;
 use themelios::resources::Metadata;
 use themelios::resources::PodSpec;
 use themelios::resources::PodTemplateSpec;
+use themelios::resources::RollbackConfig;
 use themelios::resources::RollingUpdate;
+use themelios::resources::Scale;
 use themelios::state::history::ConsistencySetup;
+use themelios::state::revision::Revision;
 use themelios::state::RawState;
+use themelios::state::ResourceKind;
+use themelios::state::State;
+use themelios::state::StateView;
 use themelios::utils;
 
 mod common;
@@ -30,12 +50,58 @@ fn model(
         initial_state,
         consistency_level: consistency,
         schedulers: controllers,
+        scheduler_profiles: Vec::new(),
         nodes: controllers,
+        container_restarts: 0,
+        image_pulls: false,
         replicaset_controllers: controllers,
+        replicationcontroller_controllers: 0,
         deployment_controllers: controllers,
         statefulset_controllers: 0,
         job_controllers: 0,
+        cronjob_controllers: 0,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
         podgc_controllers: controllers,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
         properties: Vec::new(),
     }
 }
@@ -163,6 +229,117 @@ test_table_panic! {
     causal_2(ConsistencySetup::Causal, 2),
 }
 
+fn test_deployment_rolling_update_controller_upgrade(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: rolling update deployment, with the deployment controller being swapped for
+    // a new instance during the rollout
+    // eventually: deployment completes after the upgrade
+    fn upgrade(controller: &Controllers) -> Option<Controllers> {
+        match controller {
+            Controllers::Deployment(_) => Some(Controllers::Deployment(DeploymentController)),
+            _ => None,
+        }
+    }
+    let mut model = test_deployment_rolling_update(consistency, controllers);
+    model.controller_upgrade = Some(upgrade);
+    model
+}
+
+test_table! {
+    test_deployment_rolling_update_controller_upgrade,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_deployment_rolling_update_shadow_controller(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: rolling update deployment, with the deployment controller shadowed by
+    // another instance of itself
+    // always: the shadow takes the same actions as the primary
+    fn shadow(controller: &Controllers) -> Option<Controllers> {
+        match controller {
+            Controllers::Deployment(_) => Some(Controllers::Deployment(DeploymentController)),
+            _ => None,
+        }
+    }
+    let mut model = test_deployment_rolling_update(consistency, controllers);
+    model.controller_shadow = Some(shadow);
+    model
+}
+
+test_table! {
+    test_deployment_rolling_update_shadow_controller,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_deployment_rolling_update_default_roles(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: rolling update deployment, with every controller restricted to its default
+    // role
+    // always: controllers only take actions allowed by their role
+    let mut model = test_deployment_rolling_update(consistency, controllers);
+    model.controller_roles = Some(rbac::default_role);
+    model
+}
+
+test_table! {
+    test_deployment_rolling_update_default_roles,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_new_deployment_leader_election(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: new deployment, with replicas of each controller electing a leader through a
+    // lease
+    // always: controllers only act while holding their lease
+    // eventually: every replicated controller has a leader
+    let mut model = test_new_deployment(consistency, controllers);
+    model.leader_election = true;
+    model
+}
+
+test_table! {
+    test_new_deployment_leader_election,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    synchronous_2(ConsistencySetup::Synchronous, 2),
+}
+
+fn test_new_deployment_missing_grant(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: new deployment, with the deployment controller not allowed to create
+    // replicasets
+    // always: controllers only take actions allowed by their role (violated)
+    fn role(controller: &Controllers) -> Option<Role> {
+        let role = rbac::default_role(controller)?;
+        match controller {
+            Controllers::Deployment(_) => {
+                Some(role.without(Verb::Create, ResourceKind::ReplicaSets))
+            }
+            _ => Some(role),
+        }
+    }
+    let mut model = test_new_deployment(consistency, controllers);
+    model.controller_roles = Some(role);
+    model
+}
+
+test_table_panic! {
+    test_new_deployment_missing_grant,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+}
+
 // TestPausedDeployment
 fn test_paused_deployment(
     consistency: ConsistencySetup,
@@ -212,3 +389,363 @@ test_table_panic! {
 // TestGeneralReplicaSetAdoption
 // TestDeploymentScaleSubresource
 // TestReplicaSetOrphaningAndAdoptionWhenLabelsChange
+
+/// Run the deployment controller, applying its actions, until it has nothing more to do.
+fn settle(state: &mut StateView, revision: &mut usize) {
+    for _ in 0..50 {
+        let Some(op) = DeploymentController.step(state, &mut DeploymentControllerState::default())
+        else {
+            return;
+        };
+        if let deployment::DeploymentControllerAction::RequeueDeployment(_, _) = op {
+            // only waiting to check on its progress again
+            return;
+        }
+        apply(state, op.into(), revision);
+    }
+    panic!("deployment controller did not settle");
+}
+
+fn apply(state: &mut StateView, action: ControllerAction, revision: &mut usize) {
+    *revision += 1;
+    assert!(state.apply_operation(action, Revision::from(vec![*revision])));
+}
+
+fn image(deployment: &Deployment) -> &str {
+    &deployment.spec.template.spec.containers[0].image
+}
+
+fn rollback_to_last(name: &str) -> ControllerAction {
+    ControllerAction::RollbackDeployment(
+        "default".to_owned(),
+        DeploymentRollback {
+            name: name.to_owned(),
+            updated_annotations: BTreeMap::new(),
+            rollback_to: RollbackConfig { revision: 0 },
+        },
+    )
+}
+
+#[test]
+fn deployment_rollback_restores_previous_template() {
+    let mut state =
+        StateView::from(RawState::default().with_deployments([new_deployment("web", "", 1)]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+
+    let mut updated = state.deployments.get_in("default", "web").unwrap().clone();
+    updated.spec.template.spec.containers[0].image = "fake1".to_owned();
+    apply(
+        &mut state,
+        ControllerAction::UpdateDeployment(updated),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    assert_eq!(state.replicasets.len(), 2);
+
+    apply(&mut state, rollback_to_last("web"), &mut revision);
+    settle(&mut state, &mut revision);
+    let deployment = state.deployments.get_in("default", "web").unwrap();
+    assert_eq!(image(deployment), "fake");
+    assert!(!deployment
+        .metadata
+        .annotations
+        .contains_key(DEPRECATED_ROLLBACK_TO));
+    // the replicaset being rolled back to becomes the newest revision
+    let rolled_back = state
+        .replicasets
+        .iter()
+        .find(|rs| rs.spec.template.spec.containers[0].image == "fake")
+        .unwrap();
+    assert_eq!(
+        rolled_back.metadata.annotations.get(REVISION_ANNOTATION),
+        Some(&"3".to_owned())
+    );
+}
+
+#[test]
+fn deployment_rollback_without_history_is_abandoned() {
+    let mut state =
+        StateView::from(RawState::default().with_deployments([new_deployment("web", "", 1)]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+
+    apply(&mut state, rollback_to_last("web"), &mut revision);
+    settle(&mut state, &mut revision);
+    let deployment = state.deployments.get_in("default", "web").unwrap();
+    assert_eq!(image(deployment), "fake");
+    assert!(!deployment
+        .metadata
+        .annotations
+        .contains_key(DEPRECATED_ROLLBACK_TO));
+}
+
+#[test]
+fn deployment_scaled_mid_rollout_scales_replicasets_proportionally() {
+    let mut state =
+        StateView::from(RawState::default().with_deployments([new_deployment("web", "", 10)]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+
+    // without any pods becoming available the rollout stalls with both replicasets scaled up
+    let mut updated = state.deployments.get_in("default", "web").unwrap().clone();
+    updated.spec.template.spec.containers[0].image = "fake1".to_owned();
+    apply(
+        &mut state,
+        ControllerAction::UpdateDeployment(updated),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    let replicas = |state: &StateView| {
+        let mut replicas = state
+            .replicasets
+            .iter()
+            .map(|rs| {
+                let image = rs.spec.template.spec.containers[0].image.clone();
+                (image, rs.spec.replicas.unwrap())
+            })
+            .collect::<Vec<_>>();
+        replicas.sort();
+        replicas
+    };
+    let before = replicas(&state);
+    assert_eq!(before.len(), 2);
+    assert!(before.iter().all(|(_, r)| *r > 0));
+
+    let deployment = state.deployments.get_in("default", "web").unwrap();
+    let scale = Scale::new(&deployment.metadata, 20, deployment.status.replicas);
+    apply(
+        &mut state,
+        ControllerAction::ScaleDeployment(scale),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    let after = replicas(&state);
+    // the new replicas are shared between the replicasets rather than going to the new one
+    for ((_, before), (_, after)) in before.iter().zip(&after) {
+        assert!(after > before);
+    }
+    // up to the new size plus the surge
+    assert_eq!(after.iter().map(|(_, r)| r).sum::<u32>(), 25);
+}
+
+#[test]
+fn deployment_without_progress_times_out_once_the_clock_passes_its_deadline() {
+    let mut deployment = new_deployment("web", "", 1);
+    deployment.spec.progress_deadline_seconds = Some(600);
+    let mut state = StateView::from(RawState::default().with_deployments([deployment]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+    assert!(deployment::progress_deadlines_pending(&state));
+
+    // no pods become available so the rollout makes no progress while time passes
+    apply(
+        &mut state,
+        ControllerAction::AdvanceClock(660),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    let progressing = state
+        .deployments
+        .get_in("default", "web")
+        .unwrap()
+        .status
+        .conditions
+        .iter()
+        .find(|c| c.r#type == DeploymentConditionType::Progressing)
+        .unwrap();
+    assert_eq!(
+        progressing.reason.as_deref(),
+        Some("ProgressDeadlineExceeded")
+    );
+    assert!(!deployment::progress_deadlines_pending(&state));
+}
+
+#[test]
+fn deployment_without_progress_is_requeued_for_its_deadline() {
+    let mut deployment = new_deployment("web", "", 1);
+    deployment.spec.progress_deadline_seconds = Some(600);
+    let mut state = StateView::from(RawState::default().with_deployments([deployment]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+    apply(
+        &mut state,
+        ControllerAction::AdvanceClock(60),
+        &mut revision,
+    );
+
+    // checking again once the rest of the deadline has passed
+    let requeue = DeploymentController.step(&state, &mut DeploymentControllerState::default());
+    assert!(matches!(
+        requeue,
+        Some(deployment::DeploymentControllerAction::RequeueDeployment(
+            _,
+            Some(541)
+        ))
+    ));
+}
+
+#[test]
+fn stalled_deployment_times_out_through_its_queued_requeue() {
+    let mut deployment = new_deployment("web", "", 1);
+    deployment.spec.progress_deadline_seconds = Some(600);
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_deployments([deployment]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.deployment_controllers = 1;
+    let model = cfg.into_abstract_model();
+    let reason = |state: &State| {
+        let latest = state.latest();
+        let deployment = latest.deployments.get_in("default", "web").unwrap();
+        deployment
+            .status
+            .conditions
+            .iter()
+            .find(|c| c.r#type == DeploymentConditionType::Progressing)
+            .and_then(|c| c.reason.clone())
+    };
+    let controller_step = |state: &State| {
+        let mut actions = Vec::new();
+        model.actions(state, &mut actions);
+        actions
+            .into_iter()
+            .find(|a| matches!(a, Action::ControllerStep(_, _)))
+    };
+
+    // the controller settles by queueing the deployment for its deadline
+    let mut state = model.init_states().remove(0);
+    while let Some(step) = controller_step(&state) {
+        state = model.next_state(&state, step).unwrap();
+    }
+    let now = state.latest().clock;
+    assert!(state.workqueue(0).map_or(false, |q| q.has_waiting(now)));
+
+    // no pods ever become available, so time passes until the queued check finds it timed out
+    for _ in 0..50 {
+        if reason(&state).as_deref() == Some("ProgressDeadlineExceeded") {
+            break;
+        }
+        let action = controller_step(&state).unwrap_or(Action::AdvanceClock);
+        state = model.next_state(&state, action).unwrap();
+    }
+    assert_eq!(reason(&state).as_deref(), Some("ProgressDeadlineExceeded"));
+}
+
+#[test]
+fn deployment_min_available_resolves_max_unavailable() {
+    let mut deployment = new_deployment("web", "", 4);
+    // both default to 25%
+    assert_eq!(min_available(&deployment), 3);
+    deployment.spec.strategy = Some(DeploymentStrategy {
+        r#type: themelios::resources::DeploymentStrategyType::RollingUpdate,
+        rolling_update: Some(RollingUpdate {
+            max_surge: Some(IntOrString::Int(0)),
+            max_unavailable: Some(IntOrString::Int(0)),
+        }),
+    });
+    // the rollout couldn't progress without letting one be unavailable
+    assert_eq!(min_available(&deployment), 3);
+    deployment.spec.strategy = Some(DeploymentStrategy {
+        r#type: themelios::resources::DeploymentStrategyType::RollingUpdate,
+        rolling_update: Some(RollingUpdate {
+            max_surge: Some(IntOrString::Int(1)),
+            max_unavailable: Some(IntOrString::Str("50%".to_owned())),
+        }),
+    });
+    assert_eq!(min_available(&deployment), 2);
+}
+
+#[test]
+fn deployment_rollout_without_available_pods_breaks_availability() {
+    let property =
+        "dep: available replicas stay above replicas - maxUnavailable during rolling updates";
+    let mut state =
+        StateView::from(RawState::default().with_deployments([new_deployment("web", "", 2)]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.rollout_availability.insert("web".to_owned());
+    let model = cfg.into_abstract_model();
+    let holds = |view: &StateView| {
+        let state = State::new(view.state.clone(), ConsistencySetup::Synchronous);
+        let property = model
+            .properties
+            .iter()
+            .find(|p| p.name == property)
+            .unwrap();
+        (property.condition)(&model, &state)
+    };
+    // nothing is rolling out yet
+    assert!(holds(&state));
+
+    let mut updated = state.deployments.get_in("default", "web").unwrap().clone();
+    updated.spec.template.spec.containers[0].image = "fake1".to_owned();
+    apply(
+        &mut state,
+        ControllerAction::UpdateDeployment(updated),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    // no pods ever become available, but the old replicaset still has replicas to scale down
+    assert!(!holds(&state));
+}
+
+#[test]
+fn deployment_replicas_stay_within_their_fenceposts_while_rolling_out() {
+    let property = "dep: replicas stay within replicas + maxSurge and above replicas - maxUnavailable during rolling updates";
+    let mut state =
+        StateView::from(RawState::default().with_deployments([new_deployment("web", "", 10)]));
+    let mut revision = 0;
+    settle(&mut state, &mut revision);
+
+    let model = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0)
+        .into_abstract_model();
+    let holds = |view: &StateView| {
+        let state = State::new(view.state.clone(), ConsistencySetup::Synchronous);
+        let property = model
+            .properties
+            .iter()
+            .find(|p| p.name == property)
+            .unwrap();
+        (property.condition)(&model, &state)
+    };
+
+    let mut updated = state.deployments.get_in("default", "web").unwrap().clone();
+    updated.spec.template.spec.containers[0].image = "fake1".to_owned();
+    apply(
+        &mut state,
+        ControllerAction::UpdateDeployment(updated),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    assert!(holds(&state));
+
+    // scaling mid rollout moves both bounds, which the replicasets are scaled proportionally to
+    let deployment = state.deployments.get_in("default", "web").unwrap();
+    let scale = Scale::new(&deployment.metadata, 20, deployment.status.replicas);
+    apply(
+        &mut state,
+        ControllerAction::ScaleDeployment(scale),
+        &mut revision,
+    );
+    settle(&mut state, &mut revision);
+    assert!(holds(&state));
+
+    // one more replica than the surge allows
+    let mut rs = state
+        .replicasets
+        .iter()
+        .find(|rs| rs.spec.template.spec.containers[0].image == "fake1")
+        .unwrap()
+        .clone();
+    rs.spec.replicas = rs.spec.replicas.map(|r| r + 1);
+    apply(
+        &mut state,
+        ControllerAction::UpdateReplicaSet(rs),
+        &mut revision,
+    );
+    assert!(!holds(&state));
+}
@@ -0,0 +1,146 @@
+use themelios::differential::{self, DifferentialError, Scenario, ScenarioStep, Summary};
+use themelios::model::OrchestrationModelCfg;
+use themelios::recording::{ClientOperation, RecordedResource};
+use themelios::resources::{Metadata, Node, OwnerReference, Pod, PodPhase, ReplicaSet};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, ResourceKind};
+use themelios::utils;
+
+fn nodes() -> Vec<String> {
+    vec!["node-0".to_owned(), "node-1".to_owned()]
+}
+
+fn owned_by(mut metadata: Metadata, owner: &str) -> Metadata {
+    metadata.owner_references.push(OwnerReference {
+        api_version: String::new(),
+        kind: String::new(),
+        name: owner.to_owned(),
+        uid: owner.to_owned(),
+        block_owner_deletion: true,
+        controller: true,
+    });
+    metadata
+}
+
+#[test]
+fn scenario_steps_become_client_operations() {
+    let scenario: Scenario = serde_yaml::from_str(
+        "steps:
+  - createDeployment: {name: web, image: nginx:1.25, replicas: 2}
+  - scale: {name: web, replicas: 4}
+  - updateImage: {name: web, image: nginx:1.26}
+  - killNode: {node: 1}
+",
+    )
+    .unwrap();
+    assert_eq!(scenario.namespace, "default");
+    assert_eq!(scenario.steps[3], ScenarioStep::KillNode { node: 1 });
+
+    let operations = scenario.recording(&nodes()).unwrap().operations;
+    assert_eq!(operations.len(), 4);
+    let ClientOperation::Update(RecordedResource::Deployment(updated)) = &operations[2] else {
+        panic!("expected the image update to update the deployment");
+    };
+    assert_eq!(updated.spec.template.spec.containers[0].image, "nginx:1.26");
+    assert_eq!(updated.spec.replicas, 2);
+    assert_eq!(
+        operations[3],
+        ClientOperation::Delete {
+            kind: ResourceKind::Nodes,
+            namespace: String::new(),
+            name: "node-1".to_owned(),
+        }
+    );
+
+    assert!(matches!(
+        scenario.recording(&nodes()[..1]),
+        Err(DifferentialError::Scenario(_))
+    ));
+    let scenario = Scenario {
+        steps: vec![ScenarioStep::UpdateImage {
+            name: "missing".to_owned(),
+            image: "nginx".to_owned(),
+        }],
+        ..Default::default()
+    };
+    assert!(matches!(
+        scenario.recording(&nodes()),
+        Err(DifferentialError::Scenario(_))
+    ));
+}
+
+#[test]
+fn summaries_only_count_what_deployments_own() {
+    let scenario = Scenario::default();
+    let ClientOperation::Create(RecordedResource::Deployment(deployment)) =
+        scenario.recording(&nodes()).unwrap().operations.remove(0)
+    else {
+        panic!("expected the scenario to start by creating a deployment");
+    };
+    let mut rs = ReplicaSet {
+        metadata: owned_by(utils::metadata("web-1".to_owned()), "web"),
+        ..Default::default()
+    };
+    rs.spec.template = deployment.spec.template.clone();
+    rs.spec.replicas = Some(2);
+    let mut pod = Pod {
+        metadata: owned_by(utils::metadata("web-1-a".to_owned()), "web-1"),
+        ..Default::default()
+    };
+    pod.spec = deployment.spec.template.spec.clone();
+    pod.status.phase = PodPhase::Running;
+    let stray = Pod {
+        metadata: utils::metadata("stray".to_owned()),
+        ..Default::default()
+    };
+    let state = RawState::default()
+        .with_deployments([deployment])
+        .with_replicasets([rs])
+        .with_pods([pod, stray]);
+
+    let summary = differential::summarize(&state, "default");
+    let web = &summary.deployments["web"];
+    assert_eq!(web.replicas, 2);
+    assert_eq!(web.replicasets.get_in("default", "nginx:1.25"), Some(&2));
+    assert_eq!(web.pods.get_in("default", "nginx:1.25 Running"), Some(&1));
+    assert_eq!(web.pods.values().sum::<usize>(), 1);
+    assert!(differential::summarize(&state, "other")
+        .deployments
+        .is_empty());
+
+    let differences = differential::differences(
+        &[summary.clone(), summary.clone()],
+        &[summary, Summary::default()],
+    );
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].0, 1);
+    assert!(differences[0].1.contains("-  web:"));
+}
+
+#[test]
+fn model_converges_between_scenario_steps() {
+    let replay = Scenario::default()
+        .recording(&nodes())
+        .unwrap()
+        .into_replay()
+        .unwrap();
+    let initial_state = replay
+        .initial_state
+        .with_nodes(nodes().into_iter().map(|name| Node {
+            metadata: utils::metadata(name),
+            ..Default::default()
+        }));
+    let mut cfg = OrchestrationModelCfg::new(initial_state, ConsistencySetup::Synchronous, 0);
+    cfg.deployment_controllers = 1;
+    cfg.replicaset_controllers = 1;
+    cfg.client_operations = replay.operations;
+    let model = cfg.into_abstract_model();
+
+    let summaries = differential::converge_model(&model, "default").unwrap();
+    assert_eq!(summaries.len(), 4);
+    assert_eq!(summaries[0].deployments["web"].replicas, 2);
+    assert_eq!(summaries[1].deployments["web"].replicas, 4);
+    assert!(summaries[2].deployments["web"]
+        .replicasets
+        .contains_key("nginx:1.26"));
+}
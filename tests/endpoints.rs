@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use themelios::controller::endpoints::EndpointsControllerAction;
+use themelios::controller::{Controller, EndpointsController, EndpointsControllerState};
+use themelios::resources::{
+    ConditionStatus, EndpointAddress, Node, Pod, PodCondition, PodConditionType, Service,
+    ServiceSpec,
+};
+use themelios::routing::{routable_backends, routing_table};
+use themelios::state::apply;
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, StateView};
+use themelios::utils;
+
+fn labels() -> BTreeMap<String, String> {
+    BTreeMap::from([("app".to_owned(), "web".to_owned())])
+}
+
+fn new_pod(name: &str, ready: bool) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    pod.metadata.labels = labels();
+    pod.spec.node_name = Some("node".to_owned());
+    pod.status.conditions.push(PodCondition {
+        status: if ready {
+            ConditionStatus::True
+        } else {
+            ConditionStatus::False
+        },
+        r#type: PodConditionType::Ready,
+        last_probe_time: None,
+        last_transition_time: None,
+        message: None,
+        reason: None,
+    });
+    pod
+}
+
+fn new_state(pods: impl IntoIterator<Item = Pod>) -> StateView {
+    let service = Service {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ServiceSpec { selector: labels() },
+    };
+    StateView::from(
+        RawState::default()
+            .with_nodes([Node {
+                metadata: utils::metadata("node".to_owned()),
+                ..Default::default()
+            }])
+            .with_pods(pods)
+            .with_services([service]),
+    )
+}
+
+/// Run the endpoints controller until it has nothing more to do.
+fn reconcile(state: &mut StateView) {
+    for i in 1.. {
+        let Some(action) =
+            EndpointsController.step(state, &mut EndpointsControllerState::default())
+        else {
+            return;
+        };
+        let revision = Revision::from(vec![i]);
+        match action {
+            EndpointsControllerAction::CreateEndpoints(e) => {
+                apply::endpoints::create(state, e, revision).unwrap()
+            }
+            EndpointsControllerAction::UpdateEndpoints(e) => {
+                apply::endpoints::update(state, e, revision).unwrap()
+            }
+            EndpointsControllerAction::DeleteEndpoints(e) => {
+                apply::endpoints::delete(state, e).unwrap()
+            }
+        }
+    }
+}
+
+#[test]
+fn endpoints_split_ready_and_not_ready_pods() {
+    let mut state = new_state([new_pod("ready", true), new_pod("starting", false)]);
+    reconcile(&mut state);
+    let endpoints = state.endpoints.get_in("default", "web").unwrap();
+    assert_eq!(endpoints.subsets.len(), 1);
+    let name = |a: &EndpointAddress| a.target_ref.clone().unwrap().name;
+    let subset = &endpoints.subsets[0];
+    assert_eq!(
+        subset.addresses.iter().map(name).collect::<Vec<_>>(),
+        ["ready"]
+    );
+    assert_eq!(
+        subset
+            .not_ready_addresses
+            .iter()
+            .map(name)
+            .collect::<Vec<_>>(),
+        ["starting"]
+    );
+    assert_eq!(routing_table(&state)["web"], ["ready"]);
+}
+
+#[test]
+fn routes_to_removed_pods_are_not_routable() {
+    let mut state = new_state([new_pod("ready", true)]);
+    reconcile(&mut state);
+    assert_eq!(routable_backends(&state, "default", "web").len(), 1);
+
+    let pod = state.pods.get_in("default", "ready").unwrap().clone();
+    apply::pods::hard_delete(&mut state, pod, Revision::from(vec![100])).unwrap();
+    // kube-proxy still routes to the pod until the endpoints catch up
+    assert_eq!(routing_table(&state)["web"], ["ready"]);
+    assert!(routable_backends(&state, "default", "web").is_empty());
+
+    reconcile(&mut state);
+    assert!(state
+        .endpoints
+        .get_in("default", "web")
+        .unwrap()
+        .subsets
+        .is_empty());
+}
+
+#[test]
+fn endpoints_are_removed_with_their_service() {
+    let mut state = new_state([new_pod("ready", true)]);
+    reconcile(&mut state);
+    let service = state.services.get_in("default", "web").unwrap().clone();
+    apply::services::delete(&mut state, service).unwrap();
+    reconcile(&mut state);
+    assert!(state.endpoints.is_empty());
+    assert!(routing_table(&state).is_empty());
+}
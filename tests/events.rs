@@ -0,0 +1,150 @@
+use themelios::abstract_model::ControllerAction;
+use themelios::events::{self, EventLog, EventRecording};
+use themelios::resources::{
+    Event, EventType, Metadata, OwnerReference, Pod, ReplicaSet, ReplicaSetSpec,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State, StateView};
+use themelios::utils;
+
+fn owned_by(mut metadata: Metadata, kind: &str, owner: &str) -> Metadata {
+    metadata.owner_references.push(OwnerReference {
+        kind: kind.to_owned(),
+        name: owner.to_owned(),
+        uid: utils::new_uid(owner),
+        controller: true,
+        ..Default::default()
+    });
+    metadata
+}
+
+fn new_pod(owner: &str) -> Pod {
+    let mut metadata = owned_by(utils::metadata(String::new()), "ReplicaSet", owner);
+    metadata.generate_name = format!("{owner}-");
+    Pod {
+        metadata,
+        ..Default::default()
+    }
+}
+
+fn new_replicaset(name: &str, replicas: u32) -> ReplicaSet {
+    ReplicaSet {
+        metadata: owned_by(utils::metadata(name.to_owned()), "Deployment", "web"),
+        spec: ReplicaSetSpec {
+            replicas: Some(replicas),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn reasons(events: &[Event]) -> Vec<(&str, &str)> {
+    events
+        .iter()
+        .map(|e| (e.reason.as_str(), e.message.as_str()))
+        .collect()
+}
+
+#[test]
+fn created_pods_are_reported_against_their_owner() {
+    let view = StateView::default();
+    let action = ControllerAction::CreatePod(new_pod("web"));
+
+    let created = events::for_action("ReplicaSetController", &view, &action, true);
+    assert_eq!(
+        reasons(&created),
+        vec![("SuccessfulCreate", "Created pod: web-")]
+    );
+    assert_eq!(created[0].involved_object.kind, "ReplicaSet");
+    assert_eq!(created[0].involved_object.name, "web");
+    assert_eq!(created[0].r#type, EventType::Normal);
+    assert_eq!(created[0].reporting_controller, "ReplicaSetController");
+
+    let failed = events::for_action("ReplicaSetController", &view, &action, false);
+    assert_eq!(
+        reasons(&failed),
+        vec![("FailedCreate", "Error creating: pod web-")]
+    );
+    assert_eq!(failed[0].r#type, EventType::Warning);
+}
+
+#[test]
+fn scaling_replicasets_reports_the_direction() {
+    let view = StateView::from(
+        RawState::default()
+            .with_replicasets([new_replicaset("web-1", 3), new_replicaset("web-2", 1)]),
+    );
+    let action = ControllerAction::Transaction(vec![
+        ControllerAction::UpdateReplicaSet(new_replicaset("web-1", 2)),
+        ControllerAction::UpdateReplicaSet(new_replicaset("web-2", 1)),
+    ]);
+    let scaled = events::for_action("DeploymentController", &view, &action, true);
+    assert_eq!(
+        reasons(&scaled),
+        vec![(
+            "ScalingReplicaSet",
+            "Scaled down replica set web-1 from 3 to 2"
+        )]
+    );
+    assert_eq!(scaled[0].involved_object.name, "web");
+
+    let action = ControllerAction::UpdateReplicaSet(new_replicaset("web-2", 4));
+    let scaled = events::for_action("DeploymentController", &view, &action, true);
+    assert_eq!(
+        reasons(&scaled),
+        vec![(
+            "ScalingReplicaSet",
+            "Scaled up replica set web-2 from 1 to 4"
+        )]
+    );
+
+    // nothing happened if the update was rejected
+    assert!(events::for_action("DeploymentController", &view, &action, false).is_empty());
+}
+
+#[test]
+fn repeated_events_are_counted() {
+    let view = StateView::default();
+    let action = ControllerAction::CreatePod(new_pod("web"));
+    let mut log = EventLog::new(EventRecording::Recorded);
+    for _ in 0..3 {
+        for event in events::for_action("ReplicaSetController", &view, &action, true) {
+            log.record(event);
+        }
+    }
+    assert_eq!(log.events().len(), 1);
+    assert_eq!(log.events()[0].count, 3);
+
+    let mut disabled = EventLog::new(EventRecording::Disabled);
+    for event in events::for_action("ReplicaSetController", &view, &action, true) {
+        disabled.record(event);
+    }
+    assert!(disabled.events().is_empty());
+}
+
+#[test]
+fn events_are_only_fingerprinted_when_configured() {
+    let view = StateView::default();
+    let action = ControllerAction::CreatePod(new_pod("web"));
+    let fingerprint = |recording| {
+        let mut state = State::new(RawState::default(), ConsistencySetup::Synchronous);
+        state.set_event_recording(recording);
+        let before = state.clone();
+        state.record_events(events::for_action(
+            "ReplicaSetController",
+            &view,
+            &action,
+            true,
+        ));
+        assert_eq!(state.events().len(), 1);
+        (
+            stateright::fingerprint(&before),
+            stateright::fingerprint(&state),
+        )
+    };
+
+    let (before, after) = fingerprint(EventRecording::Recorded);
+    assert_eq!(before, after);
+    let (before, after) = fingerprint(EventRecording::Fingerprinted);
+    assert_ne!(before, after);
+}
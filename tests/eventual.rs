@@ -0,0 +1,88 @@
+use stateright::Model;
+use themelios::abstract_model::{Action, Change, ControllerAction};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::Pod;
+use themelios::state::history::eventual::ReplicaMerge;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, State};
+use themelios::utils;
+
+fn rev(i: usize) -> Revision {
+    Revision::from(vec![i])
+}
+
+fn create_pod(state: &mut State, revision: Revision, name: &str) {
+    state.push_change(Change {
+        revision,
+        operation: ControllerAction::CreatePod(Pod {
+            metadata: utils::metadata(name.to_owned()),
+            ..Default::default()
+        }),
+    });
+}
+
+#[test]
+fn replicas_diverge_until_anti_entropy() {
+    let mut state = State::new(RawState::default(), ConsistencySetup::Eventual(2));
+    assert_eq!(state.replicas(), 2);
+    assert_eq!(state.revisions(None), vec![rev(0), rev(1)]);
+
+    // a write through the second replica
+    create_pod(&mut state, rev(1), "pod");
+    assert_eq!(state.revisions(None), vec![rev(0), rev(2)]);
+    assert!(state.view_at(&rev(0)).pods.is_empty());
+    assert!(state.view_at(&rev(2)).pods.has_in("default", "pod"));
+    // clients can go back to the other replica
+    assert_eq!(state.revisions(Some(&rev(2))), vec![rev(0)]);
+
+    let merge = ReplicaMerge::default();
+    assert!(state.merge_replicas(1, 0, &merge));
+    assert_eq!(state.revisions(None), vec![rev(2), rev(3)]);
+    assert!(state.view_at(&rev(3)).pods.has_in("default", "pod"));
+
+    // the replicas have converged so further merges change nothing
+    assert!(!state.merge_replicas(1, 0, &merge));
+    assert!(!state.merge_replicas(0, 1, &merge));
+}
+
+#[test]
+fn concurrent_writes_are_merged_by_kind() {
+    let mut state = State::new(RawState::default(), ConsistencySetup::Eventual(2));
+    create_pod(&mut state, rev(0), "pod-a");
+    create_pod(&mut state, rev(1), "pod-b");
+
+    // a merge that never takes pods from other replicas
+    let local_pods = ReplicaMerge {
+        pods: |_local, _remote| {},
+        ..Default::default()
+    };
+    assert!(!state.merge_replicas(1, 0, &local_pods));
+
+    assert!(state.merge_replicas(1, 0, &ReplicaMerge::default()));
+    let merged = state.latest();
+    assert!(merged.pods.has_in("default", "pod-a"));
+    assert!(merged.pods.has_in("default", "pod-b"));
+}
+
+#[test]
+fn anti_entropy_is_only_taken_when_it_changes_a_replica() {
+    let model = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Eventual(2), 0)
+        .into_abstract_model();
+    let state = model.init_states().pop().unwrap();
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&Action::AntiEntropy(0, 1)));
+    assert!(actions.contains(&Action::AntiEntropy(1, 0)));
+    assert!(model
+        .next_state(&state, Action::AntiEntropy(0, 1))
+        .is_none());
+
+    let mut state = state;
+    create_pod(&mut state, rev(0), "pod");
+    let merged = model.next_state(&state, Action::AntiEntropy(0, 1)).unwrap();
+    assert!(merged
+        .view_at(&merged.max_revision())
+        .pods
+        .has_in("default", "pod"));
+}
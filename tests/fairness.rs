@@ -0,0 +1,96 @@
+use stateright::Model;
+use themelios::abstract_model::{AbstractModel, Action};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+use themelios::utils;
+
+fn model(fair: bool, fairness_bound: usize) -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    if fair {
+        model.fair_controllers.insert("ReplicaSet".to_owned());
+    }
+    model.fairness_bound = fairness_bound;
+    model.into_abstract_model()
+}
+
+fn actions(model: &AbstractModel, state: &State) -> Vec<Action> {
+    let mut actions = Vec::new();
+    model.actions(state, &mut actions);
+    actions
+}
+
+/// Take an action that isn't a step of a controller, leaving the replicaset controller with work
+/// to do.
+fn step_others(model: &AbstractModel, state: &State) -> State {
+    let action = actions(model, state)
+        .into_iter()
+        .find(|a| matches!(a, Action::ArbitraryStep(_)))
+        .unwrap();
+    model.next_state(state, action).unwrap()
+}
+
+#[test]
+fn unfair_controllers_can_be_left_waiting() {
+    let model = model(false, 0);
+    let mut state = model.init_states().remove(0);
+    for _ in 0..5 {
+        state = step_others(&model, &state);
+    }
+    assert_eq!(state.starved_steps(0), 0);
+    assert!(actions(&model, &state)
+        .iter()
+        .any(|a| matches!(a, Action::ArbitraryStep(_))));
+}
+
+#[test]
+fn fair_controllers_step_once_the_bound_is_reached() {
+    let model = model(true, 2);
+    let mut state = model.init_states().remove(0);
+    assert!(model.controller_enabled(&state, 0));
+    for steps in 1..=2 {
+        state = step_others(&model, &state);
+        assert_eq!(state.starved_steps(0), steps);
+    }
+    let forced = actions(&model, &state);
+    assert!(!forced.is_empty());
+    assert!(forced.iter().all(|a| a.controller() == Some(0)));
+
+    // stepping resets the count
+    let state = model
+        .next_state(&state, forced.into_iter().next().unwrap())
+        .unwrap();
+    assert_eq!(state.starved_steps(0), 0);
+}
+
+#[test]
+fn fair_controllers_without_work_are_not_forced() {
+    let model = model(true, 0);
+    let mut state = model.init_states().remove(0);
+    // let the controller create the pod so it has nothing left to do
+    for _ in 0..10 {
+        if !model.controller_enabled(&state, 0) {
+            break;
+        }
+        let action = actions(&model, &state).into_iter().next().unwrap();
+        state = model.next_state(&state, action).unwrap();
+    }
+    assert!(!model.controller_enabled(&state, 0));
+    assert!(actions(&model, &state)
+        .iter()
+        .any(|a| a.controller().is_none()));
+}
@@ -0,0 +1,62 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use themelios::serve_cluster::faults::{FaultConfig, Faults};
+use themelios::serve_cluster::watch::Watches;
+use themelios::state::StateView;
+
+#[test]
+fn faults_only_apply_to_registered_controllers_and_nodes() {
+    let faults = Faults::default();
+    faults.register_controller("Deployment".to_owned());
+    faults.register_node("node1".to_owned());
+
+    assert!(!faults.pause("Missing"));
+    assert!(!faults.kill("node2"));
+    assert!(faults.pause("Deployment"));
+    assert!(faults.kill("node1"));
+    assert!(faults.paused("Deployment"));
+    assert!(faults.killed("node1"));
+
+    faults.resume("Deployment");
+    faults.restore("node1");
+    assert!(!faults.paused("Deployment"));
+    assert!(!faults.killed("node1"));
+}
+
+#[test]
+fn dropped_writes_are_spread_evenly() {
+    let faults = Faults::default();
+    assert!(!(0..100).any(|_| faults.drop_write()));
+
+    faults.set_drop_writes(25);
+    let dropped = (0..8).map(|_| faults.drop_write()).collect::<Vec<_>>();
+    assert_eq!(
+        dropped,
+        vec![false, false, false, true, false, false, false, true]
+    );
+
+    faults.set_drop_writes(200);
+    assert_eq!(faults.config().drop_writes_percent, 100);
+    assert!((0..10).all(|_| faults.drop_write()));
+
+    faults.set_read_staleness(3);
+    faults.clear();
+    assert_eq!(faults.config(), FaultConfig::default());
+}
+
+#[test]
+fn stale_reads_come_from_the_watch_history() {
+    let watches = Watches::new(Arc::new(AtomicBool::new(false)));
+    assert!(watches.behind(1).is_none());
+    let mut view = StateView::default();
+    let mut revisions = Vec::new();
+    for _ in 0..5 {
+        view.revision = view.revision.clone().increment();
+        watches.record(&view);
+        revisions.push(view.revision.clone());
+    }
+    assert_eq!(watches.behind(0).unwrap().revision, revisions[4]);
+    assert_eq!(watches.behind(2).unwrap().revision, revisions[2]);
+    assert_eq!(watches.behind(10).unwrap().revision, revisions[0]);
+}
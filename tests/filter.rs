@@ -0,0 +1,112 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use stateright::{Checker, Model, Property};
+use themelios::abstract_model::{AbstractModel, ActionKind, ControllerScope};
+use themelios::controller::Controllers;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, ResourceKind, State};
+use themelios::utils;
+
+fn new_replicaset(name: &str) -> ReplicaSet {
+    ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn model_cfg() -> OrchestrationModelCfg {
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([new_replicaset("rs-1"), new_replicaset("rs-2")]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model
+}
+
+/// The kinds of actions taken from the states within the given depth.
+fn action_kinds(model: &AbstractModel, depth: usize) -> BTreeSet<ActionKind> {
+    let mut kinds = BTreeSet::new();
+    let mut seen = BTreeSet::new();
+    let mut queue = model
+        .init_states()
+        .into_iter()
+        .map(|s| (s, 0))
+        .collect::<VecDeque<_>>();
+    while let Some((state, d)) = queue.pop_front() {
+        if d >= depth || !seen.insert(stateright::fingerprint(&state)) {
+            continue;
+        }
+        let mut actions = Vec::new();
+        model.actions(&state, &mut actions);
+        for action in actions {
+            kinds.insert(action.kind());
+            if let Some(next) = model.next_state(&state, action) {
+                queue.push_back((next, d + 1));
+            }
+        }
+    }
+    kinds
+}
+
+#[test]
+fn action_kinds_parse_from_their_names() {
+    assert_eq!("node-restart".parse(), Ok(ActionKind::NodeRestart));
+    assert_eq!("advance-clock".parse(), Ok(ActionKind::AdvanceClock));
+    assert!("node-crash".parse::<ActionKind>().is_err());
+}
+
+#[test]
+fn disabled_actions_are_not_explored() {
+    let kinds = action_kinds(&model_cfg().into_abstract_model(), 3);
+    assert!(kinds.contains(&ActionKind::ControllerRestart));
+
+    let mut model = model_cfg();
+    model.disabled_actions.insert(ActionKind::ControllerRestart);
+    let kinds = action_kinds(&model.into_abstract_model(), 3);
+    assert!(!kinds.contains(&ActionKind::ControllerRestart));
+    assert!(kinds.contains(&ActionKind::ControllerStep));
+}
+
+#[test]
+fn disabled_controllers_are_not_run() {
+    let mut model = model_cfg();
+    model.deployment_controllers = 1;
+    model.disabled_controllers.insert("Deployment".to_owned());
+    let model = model.into_abstract_model();
+    assert_eq!(model.controllers.len(), 1);
+    assert!(matches!(model.controllers[0], Controllers::ReplicaSet(_)));
+}
+
+#[test]
+fn scoped_controllers_only_reconcile_their_resources() {
+    let mut model = model_cfg();
+    model.controller_scopes.insert(
+        "ReplicaSet".to_owned(),
+        ControllerScope::default().with(ResourceKind::ReplicaSets, ["rs-1".to_owned()]),
+    );
+    // keep the replicas of the replicasets fixed
+    model.disabled_actions.insert(ActionKind::ArbitraryStep);
+    model.properties.push(Property::always(
+        "only the scoped replicaset gets a pod",
+        |_model, state: &State| state.latest().pods.len() <= 1,
+    ));
+    model.properties.push(Property::sometimes(
+        "the scoped replicaset gets a pod",
+        |_model, state: &State| state.latest().pods.len() == 1,
+    ));
+    let model = model.into_abstract_model();
+    let checker = model.checker().spawn_bfs().join();
+    assert!(checker
+        .discovery("only the scoped replicaset gets a pod")
+        .is_none());
+    assert!(checker
+        .discovery("the scoped replicaset gets a pod")
+        .is_some());
+}
@@ -0,0 +1,71 @@
+use stateright::{Checker, Model};
+use themelios::abstract_model::ActionKind;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{Deployment, ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+
+fn new_deployment(name: &str, generation: u64, observed_generation: u64) -> Deployment {
+    let mut deployment = Deployment {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    deployment.metadata.generation = generation;
+    deployment.status.observed_generation = observed_generation;
+    deployment
+}
+
+#[test]
+fn observed_generations_are_bounded_by_the_generation() {
+    let state = RawState::default().with_deployments([new_deployment("web", 2, 2)]);
+    assert!(state.observed_generations_bounded());
+    let state = RawState::default().with_deployments([new_deployment("web", 2, 3)]);
+    assert!(!state.observed_generations_bounded());
+}
+
+#[test]
+fn observed_generations_regress_when_they_go_backwards() {
+    let before = RawState::default().with_deployments([new_deployment("web", 2, 2)]);
+    let after = RawState::default().with_deployments([new_deployment("web", 2, 1)]);
+    assert!(before.observed_generation_regressed(&after));
+    assert!(!after.observed_generation_regressed(&before));
+
+    // a recreated resource starts its generations afresh
+    let mut recreated = new_deployment("web", 1, 1);
+    recreated.metadata.uid = "other".to_owned();
+    let after = RawState::default().with_deployments([recreated]);
+    assert!(!before.observed_generation_regressed(&after));
+}
+
+#[test]
+fn replicaset_controller_keeps_observed_generations_moving_forward() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for consistency in [
+        ConsistencySetup::Synchronous,
+        ConsistencySetup::MonotonicSession,
+    ] {
+        let mut cfg = OrchestrationModelCfg::new(
+            RawState::default().with_replicasets([rs.clone()]),
+            consistency,
+            0,
+        );
+        cfg.replicaset_controllers = 1;
+        cfg.generations = true;
+        cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+        let checker = cfg.into_abstract_model().checker().spawn_bfs().join();
+        assert!(checker
+            .discovery("generation: observed generations never decrease")
+            .is_none());
+        assert!(checker
+            .discovery("generation: observed generations never exceed the generation")
+            .is_none());
+    }
+}
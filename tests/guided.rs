@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use stateright::report::{ReportData, ReportDiscovery, Reporter};
+use stateright::{Checker, Model, Property};
+use themelios::abstract_model::AbstractModel;
+use themelios::guided::{changed_resources, check_guided, resource_changes};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+use themelios::trace::Trace;
+use themelios::utils;
+
+fn model() -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model.properties.push(Property::sometimes(
+        "two pods are created",
+        |_model, state: &<AbstractModel as Model>::State| state.latest().pods.len() == 2,
+    ));
+    model.into_abstract_model()
+}
+
+struct NoReporter;
+
+impl Reporter<AbstractModel> for NoReporter {
+    fn report_checking(&mut self, _data: ReportData) {}
+
+    fn report_discoveries(
+        &mut self,
+        _discoveries: BTreeMap<&'static str, ReportDiscovery<AbstractModel>>,
+    ) {
+    }
+}
+
+/// Explore in the order the states were found, the heuristic being no guide at all.
+fn unguided(_model: &AbstractModel, _last: &State, _next: &State) -> u64 {
+    0
+}
+
+#[test]
+fn changed_resources_counts_writes() {
+    let state = model().init_states().remove(0);
+    let before = state.latest().state.clone();
+    let mut after = before.clone();
+    assert_eq!(changed_resources(&before, &after), 0);
+    after.replicasets = Default::default();
+    assert_eq!(changed_resources(&before, &after), 1);
+    assert_eq!(changed_resources(&after, &before), 1);
+}
+
+#[test]
+fn guided_checks_explore_the_whole_state_space() {
+    let check = check_guided(&model(), resource_changes, 0, 0, &mut NoReporter);
+    assert!(check.discoveries.contains_key("two pods are created"));
+
+    // discoveries can be replayed
+    let path = check.encoded_path("two pods are created").unwrap();
+    let trace = Trace::replay(&model(), &path).unwrap();
+    assert_eq!(trace.steps.last().unwrap().state.latest().pods.len(), 2);
+
+    // without any properties left to find, every state is explored
+    let mut model = model();
+    model.properties.clear();
+    let check = check_guided(&model, resource_changes, 0, 0, &mut NoReporter);
+    let checker = model.checker().spawn_bfs().join();
+    assert_eq!(check.unique_states, checker.unique_state_count());
+}
+
+#[test]
+fn heuristics_find_discoveries_sooner() {
+    let guided = check_guided(&model(), resource_changes, 0, 0, &mut NoReporter);
+    let unguided = check_guided(&model(), unguided, 0, 0, &mut NoReporter);
+    assert!(
+        guided.states_to_discovery["two pods are created"]
+            <= unguided.states_to_discovery["two pods are created"]
+    );
+}
+
+#[test]
+fn max_states_limits_the_check() {
+    let mut model = model();
+    model.properties.clear();
+    let check = check_guided(&model, resource_changes, 3, 0, &mut NoReporter);
+    assert_eq!(check.total_states, 3);
+}
@@ -0,0 +1,202 @@
+use common::run;
+use common::test_table;
+use std::collections::BTreeMap;
+use stdext::function_name;
+use themelios::controller::hpa::{replicas_for_utilization, HPAControllerAction};
+use themelios::controller::{Controller, HPAController, HPAControllerState};
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
+use themelios::resources::Container;
+use themelios::resources::CrossVersionObjectReference;
+use themelios::resources::Deployment;
+use themelios::resources::DeploymentSpec;
+use themelios::resources::HorizontalPodAutoscaler;
+use themelios::resources::HorizontalPodAutoscalerSpec;
+use themelios::resources::Metadata;
+use themelios::resources::PodSpec;
+use themelios::resources::PodTemplateSpec;
+use themelios::state::apply;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::state::StateView;
+use themelios::utils;
+
+mod common;
+
+fn model(
+    deployments: impl IntoIterator<Item = Deployment>,
+    hpas: impl IntoIterator<Item = HorizontalPodAutoscaler>,
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    let initial_state = RawState::default()
+        .with_deployments(deployments)
+        .with_horizontal_pod_autoscalers(hpas);
+    OrchestrationModelCfg {
+        initial_state,
+        consistency_level: consistency,
+        schedulers: controllers,
+        scheduler_profiles: Vec::new(),
+        nodes: controllers,
+        container_restarts: 0,
+        image_pulls: false,
+        replicaset_controllers: controllers,
+        replicationcontroller_controllers: 0,
+        deployment_controllers: controllers,
+        statefulset_controllers: 0,
+        job_controllers: 0,
+        cronjob_controllers: 0,
+        hpa_controllers: controllers,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
+        podgc_controllers: controllers,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
+        properties: Vec::new(),
+    }
+}
+
+fn new_deployment(name: &str, replicas: u32) -> Deployment {
+    let mut test_labels = BTreeMap::new();
+    test_labels.insert("name".to_owned(), "test".to_owned());
+    let mut d = Deployment {
+        metadata: utils::metadata(name.to_owned()),
+        spec: DeploymentSpec {
+            replicas,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    d.spec.selector.match_labels = test_labels.clone();
+    d.spec.template = PodTemplateSpec {
+        metadata: Metadata {
+            labels: test_labels,
+            ..Default::default()
+        },
+        spec: PodSpec {
+            containers: vec![Container {
+                name: "fake".to_owned(),
+                image: "fake".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    };
+    d
+}
+
+fn new_hpa(name: &str, target: &Deployment, min: u32, max: u32) -> HorizontalPodAutoscaler {
+    HorizontalPodAutoscaler {
+        metadata: utils::metadata(name.to_owned()),
+        spec: HorizontalPodAutoscalerSpec {
+            scale_target_ref: CrossVersionObjectReference {
+                kind: "Deployment".to_owned(),
+                name: target.metadata.name.clone(),
+                api_version: "apps/v1".to_owned(),
+            },
+            min_replicas: Some(min),
+            max_replicas: max,
+            target_cpu_utilization_percentage: Some(50),
+        },
+        ..Default::default()
+    }
+}
+
+fn test_scale_deployment(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: deployment with 1 replica, autoscaled between 1 and 3 replicas as the
+    // utilization varies
+    // always: stable autoscalers have their target at the desired replicas
+    // always: desired replicas are within the bounds
+    let deployment = new_deployment("test-hpa", 1);
+    let hpa = new_hpa("test-hpa", &deployment, 1, 3);
+    model([deployment], [hpa], consistency, controllers)
+}
+
+test_table! {
+    test_scale_deployment,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+#[test]
+fn utilization_within_tolerance_keeps_replicas() {
+    assert_eq!(replicas_for_utilization(3, 50, 50), 3);
+    assert_eq!(replicas_for_utilization(3, 54, 50), 3);
+    assert_eq!(replicas_for_utilization(3, 46, 50), 3);
+}
+
+#[test]
+fn utilization_scales_proportionally() {
+    assert_eq!(replicas_for_utilization(2, 100, 50), 4);
+    assert_eq!(replicas_for_utilization(4, 25, 50), 2);
+    // rounds up to stay at or below the target
+    assert_eq!(replicas_for_utilization(3, 60, 50), 4);
+}
+
+#[test]
+fn same_named_autoscalers_in_different_namespaces_read_their_own_metrics() {
+    let in_namespace = |namespace: &str| {
+        let mut deployment = new_deployment("web", 1);
+        deployment.metadata.namespace = namespace.to_owned();
+        let mut hpa = new_hpa("web", &deployment, 1, 3);
+        hpa.metadata.namespace = namespace.to_owned();
+        (deployment, hpa)
+    };
+    let (dep_a, hpa_a) = in_namespace("a");
+    let (dep_b, hpa_b) = in_namespace("b");
+    let mut state = StateView::from(
+        RawState::default()
+            .with_deployments([dep_a, dep_b])
+            .with_horizontal_pod_autoscalers([hpa_a, hpa_b]),
+    );
+    apply::metrics::update(&mut state, "a".to_owned(), "web".to_owned(), 100).unwrap();
+    apply::metrics::update(&mut state, "b".to_owned(), "web".to_owned(), 25).unwrap();
+    assert_eq!(state.metrics.len(), 2);
+
+    // the busy autoscaler scales up rather than reading the quiet one's metric
+    let op = HPAController.step(&state, &mut HPAControllerState::default());
+    let Some(HPAControllerAction::ScaleDeployment(scale)) = op else {
+        panic!("expected a scale, got {op:?}");
+    };
+    assert_eq!(scale.metadata.namespace, "a");
+    assert_eq!(scale.spec.replicas, 2);
+}
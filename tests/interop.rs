@@ -0,0 +1,61 @@
+#![cfg(feature = "openapi")]
+
+use k8s_openapi::api::apps::v1 as apps;
+use k8s_openapi::api::core::v1 as core;
+use themelios::resources::{Deployment, Pod, PodSpec};
+use themelios::state::revision::Revision;
+use themelios::utils;
+
+#[test]
+fn resources_convert_both_ways() {
+    let mut deployment = Deployment {
+        metadata: utils::metadata("web".to_owned()),
+        ..Default::default()
+    };
+    deployment.spec.replicas = 3;
+    deployment.metadata.resource_version = Revision::from(vec![4]);
+
+    let theirs = apps::Deployment::try_from(&deployment).unwrap();
+    assert_eq!(theirs.metadata.name.as_deref(), Some("web"));
+    assert_eq!(theirs.spec.as_ref().unwrap().replicas, Some(3));
+    let ours = Deployment::try_from(theirs).unwrap();
+    assert_eq!(ours.spec.replicas, 3);
+    assert_eq!(ours.metadata, deployment.metadata);
+
+    let pod = core::Pod {
+        metadata: k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta {
+            name: Some("fixture".to_owned()),
+            namespace: Some("default".to_owned()),
+            ..Default::default()
+        },
+        spec: Some(core::PodSpec {
+            node_name: Some("node-0".to_owned()),
+            ..Default::default()
+        }),
+        status: Some(core::PodStatus::default()),
+    };
+    let ours = Pod::try_from(&pod).unwrap();
+    assert_eq!(ours.metadata.name, "fixture");
+    assert_eq!(
+        ours.spec,
+        PodSpec {
+            node_name: Some("node-0".to_owned()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn unknown_values_fail_to_convert() {
+    let mut pod = core::Pod {
+        spec: Some(core::PodSpec::default()),
+        status: Some(core::PodStatus::default()),
+        ..Default::default()
+    };
+    assert!(Pod::try_from(&pod).is_ok());
+    pod.status = Some(core::PodStatus {
+        phase: Some("Hibernating".to_owned()),
+        ..Default::default()
+    });
+    assert!(Pod::try_from(pod).is_err());
+}
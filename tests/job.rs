@@ -3,15 +3,20 @@ use common::test_table;
 use common::test_table_panic;
 use std::collections::BTreeMap;
 use stdext::function_name;
-use themelios::model::OrchestrationModelCfg;
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
+use themelios::resources::ConditionStatus;
 use themelios::resources::Container;
 use themelios::resources::Job;
+use themelios::resources::JobCondition;
+use themelios::resources::JobConditionType;
 use themelios::resources::JobSpec;
 use themelios::resources::Metadata;
 use themelios::resources::PodSpec;
 use themelios::resources::PodTemplateSpec;
 use themelios::state::history::ConsistencySetup;
 use themelios::state::RawState;
+use themelios::state::State;
 use themelios::utils;
 
 mod common;
@@ -26,12 +31,58 @@ fn model(
         initial_state,
         consistency_level: consistency,
         schedulers: controllers,
+        scheduler_profiles: Vec::new(),
         nodes: controllers,
+        container_restarts: 0,
+        image_pulls: false,
         replicaset_controllers: 0,
+        replicationcontroller_controllers: 0,
         deployment_controllers: 0,
         statefulset_controllers: 0,
         job_controllers: controllers,
+        cronjob_controllers: 0,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
         podgc_controllers: controllers,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
         properties: Vec::new(),
     }
 }
@@ -116,6 +167,57 @@ test_table_panic! {
     causal_2(ConsistencySetup::Causal, 2),
 }
 
+#[test]
+fn finished_jobs_satisfy_completion_liveness() {
+    let property = "job: jobs with finite completions eventually complete or fail";
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.job_controllers = 1;
+    let model = cfg.into_abstract_model();
+    let holds = |job: &Job| {
+        let state = State::new(
+            RawState::default().with_jobs([job.clone()]),
+            ConsistencySetup::Synchronous,
+        );
+        let property = model
+            .properties
+            .iter()
+            .find(|p| p.name == property)
+            .unwrap();
+        (property.condition)(&model, &state)
+    };
+
+    let mut job = new_job("job", "");
+    job.spec.completions = Some(1);
+    // nothing has finished yet so there is nothing to wait for
+    assert!(holds(&job));
+
+    job.status.succeeded = 1;
+    assert!(!holds(&job));
+    job.status.conditions.push(JobCondition {
+        status: ConditionStatus::True,
+        r#type: JobConditionType::Complete,
+        last_probe_time: None,
+        last_transition_time: None,
+        message: String::new(),
+        reason: String::new(),
+    });
+    assert!(holds(&job));
+
+    // running past the deadline has to fail the job even though its pods never finish
+    let mut job = new_job("job", "");
+    job.spec.completions = Some(1);
+    job.spec.active_deadline_seconds = Some(0);
+    job.status.start_time = Some(RawState::default().now());
+    assert!(!holds(&job));
+    job.spec.suspend = true;
+    assert!(holds(&job));
+
+    // jobs without a number of completions to reach are left alone
+    let mut job = new_job("job", "");
+    job.status.failed = 1;
+    assert!(holds(&job));
+}
+
 // TESTS TO DO
 // func TestJobPodFailurePolicyWithFailedPodDeletedDuringControllerRestart(t *testing.T) {
 // func TestJobPodFailurePolicy(t *testing.T) {
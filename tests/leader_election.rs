@@ -0,0 +1,99 @@
+use themelios::abstract_model::{ControllerAction, CLOCK_STEP_SECONDS};
+use themelios::leader_election::{self, Election};
+use themelios::resources::Lease;
+use themelios::state::apply::{self, ApplyError};
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, StateView};
+
+fn rev(i: usize) -> Revision {
+    Revision::from(vec![i])
+}
+
+/// A state where the lease was acquired by the identity at the current time.
+fn held_by(identity: &str) -> RawState {
+    let mut state = StateView::default();
+    let Election::Lease(ControllerAction::CreateLease(lease)) =
+        leader_election::elect(&state, "deployment", identity)
+    else {
+        panic!("expected a lease to be created");
+    };
+    apply::leases::create(&mut state, lease, rev(1)).unwrap();
+    (*state).clone()
+}
+
+fn updated(election: Election) -> Lease {
+    match election {
+        Election::Lease(ControllerAction::UpdateLease(lease)) => lease,
+        other => panic!("expected a lease update, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_lease_is_created() {
+    let state = held_by("deployment-0");
+    let lease = state
+        .leases
+        .get_in(leader_election::LEASE_NAMESPACE, "deployment")
+        .unwrap();
+    assert_eq!(lease.spec.holder_identity.as_deref(), Some("deployment-0"));
+    assert_eq!(lease.spec.renew_time, Some(state.now()));
+    assert!(leader_election::holds_lease(
+        &state,
+        "deployment",
+        "deployment-0"
+    ));
+}
+
+#[test]
+fn holder_leads_until_the_clock_moves() {
+    let mut state = held_by("deployment-0");
+    assert_eq!(
+        leader_election::elect(&state, "deployment", "deployment-0"),
+        Election::Lead
+    );
+    assert_eq!(
+        leader_election::elect(&state, "deployment", "deployment-1"),
+        Election::Follow
+    );
+
+    state.clock += CLOCK_STEP_SECONDS;
+    let lease = updated(leader_election::elect(&state, "deployment", "deployment-0"));
+    assert_eq!(lease.spec.holder_identity.as_deref(), Some("deployment-0"));
+    assert_eq!(lease.spec.renew_time, Some(state.now()));
+    assert_eq!(lease.spec.lease_transitions, 0);
+}
+
+#[test]
+fn expired_lease_is_taken_over() {
+    let mut state = held_by("deployment-0");
+    state.clock += CLOCK_STEP_SECONDS;
+    assert!(!leader_election::holds_lease(
+        &state,
+        "deployment",
+        "deployment-0"
+    ));
+    let lease = updated(leader_election::elect(&state, "deployment", "deployment-1"));
+    assert_eq!(lease.spec.holder_identity.as_deref(), Some("deployment-1"));
+    assert_eq!(lease.spec.acquire_time, Some(state.now()));
+    assert_eq!(lease.spec.lease_transitions, 1);
+}
+
+#[test]
+fn racing_renewal_and_takeover_only_one_applies() {
+    let mut raw = held_by("deployment-0");
+    raw.clock += CLOCK_STEP_SECONDS;
+    let renewal = updated(leader_election::elect(&raw, "deployment", "deployment-0"));
+    let takeover = updated(leader_election::elect(&raw, "deployment", "deployment-1"));
+
+    let mut state = StateView::from(raw);
+    apply::leases::update(&mut state, takeover, rev(2)).unwrap();
+    assert_eq!(
+        apply::leases::update(&mut state, renewal, rev(3)),
+        Err(ApplyError)
+    );
+    assert!(leader_election::holds_lease(
+        &state,
+        "deployment",
+        "deployment-1"
+    ));
+}
@@ -0,0 +1,76 @@
+use themelios::manifests;
+use themelios::resources::{Deployment, Node, OwnerReference, Pod, PodSpec, ReplicaSet};
+use themelios::state::RawState;
+use themelios::utils;
+
+fn state() -> RawState {
+    let mut owned = ReplicaSet {
+        metadata: utils::metadata("web-abc".to_owned()),
+        ..Default::default()
+    };
+    owned.metadata.owner_references.push(OwnerReference {
+        api_version: "apps/v1".to_owned(),
+        kind: "Deployment".to_owned(),
+        name: "web".to_owned(),
+        uid: "web".to_owned(),
+        block_owner_deletion: true,
+        controller: true,
+    });
+    RawState::default()
+        .with_nodes([Node {
+            metadata: utils::metadata("node-0".to_owned()),
+            ..Default::default()
+        }])
+        .with_pods([Pod {
+            metadata: utils::metadata("standalone".to_owned()),
+            spec: PodSpec {
+                node_name: Some("node-0".to_owned()),
+                ..Default::default()
+            },
+            status: Default::default(),
+        }])
+        .with_replicasets([owned])
+        .with_deployments([Deployment {
+            metadata: utils::metadata("web".to_owned()),
+            ..Default::default()
+        }])
+}
+
+#[test]
+fn manifests_leave_out_what_the_cluster_makes() {
+    let manifests = manifests::manifests(&state());
+    let kinds = manifests.iter().map(|m| m.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec!["Deployment", "Pod"]);
+
+    let deployment = &manifests[0].yaml;
+    assert!(deployment.starts_with("apiVersion: apps/v1\nkind: Deployment\n"));
+    assert!(deployment.contains("namespace: default"));
+    assert!(!deployment.contains("uid:"));
+    assert!(!deployment.contains("resourceVersion:"));
+    assert!(!deployment.contains("status:"));
+
+    let pod = &manifests[1].yaml;
+    assert!(pod.starts_with("apiVersion: v1\nkind: Pod\n"));
+    assert!(!pod.contains("nodeName"));
+}
+
+#[test]
+fn export_writes_a_file_per_manifest_in_order() {
+    let dir = std::env::temp_dir().join(format!("themelios-manifests-{}", std::process::id()));
+    let paths = manifests::export(&state(), &dir).unwrap();
+    let names = paths
+        .iter()
+        .map(|p| p.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "000-deployment-default-web.yaml",
+            "001-pod-default-standalone.yaml"
+        ]
+    );
+    let deployment: serde_yaml::Value =
+        serde_yaml::from_str(&std::fs::read_to_string(&paths[0]).unwrap()).unwrap();
+    assert_eq!(deployment["metadata"]["name"].as_str(), Some("web"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
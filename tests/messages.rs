@@ -0,0 +1,80 @@
+use stateright::Model;
+use themelios::abstract_model::{AbstractModel, Action};
+use themelios::model::OrchestrationModelCfg;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+
+/// A model with a single node that sends its actions as messages, resending dropped ones the
+/// given number of times.
+fn model(retries: u32) -> AbstractModel {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.nodes = 1;
+    cfg.message_retries = Some(retries);
+    cfg.into_abstract_model()
+}
+
+fn actions(model: &AbstractModel, state: &State) -> Vec<Action> {
+    let mut actions = Vec::new();
+    model.actions(state, &mut actions);
+    actions
+}
+
+/// The state after the node has sent its request to join the cluster.
+fn sent(model: &AbstractModel) -> State {
+    let state = model.init_states().remove(0);
+    model
+        .next_state(&state, Action::ControllerStep(state.max_revision(), 0))
+        .unwrap()
+}
+
+#[test]
+fn actions_wait_in_flight_until_delivered() {
+    let model = model(0);
+    let sent = sent(&model);
+    assert_eq!(sent.messages().len(), 1);
+    assert!(sent.latest().nodes.is_empty());
+    // the node waits on its request rather than stepping again
+    let actions = actions(&model, &sent);
+    assert!(!actions
+        .iter()
+        .any(|a| matches!(a, Action::ControllerStep(_, _))));
+    assert!(actions.contains(&Action::DeliverMessage(0)));
+    assert!(actions.contains(&Action::DropMessage(0)));
+    assert!(!actions.contains(&Action::DuplicateMessage(0)));
+
+    let delivered = model.next_state(&sent, Action::DeliverMessage(0)).unwrap();
+    assert!(delivered.messages().is_empty());
+    assert!(delivered.latest().nodes.has("node-0"));
+}
+
+#[test]
+fn dropped_messages_are_resent_until_out_of_retries() {
+    let model = model(1);
+    let sent = sent(&model);
+    let dropped = model.next_state(&sent, Action::DropMessage(0)).unwrap();
+    assert_eq!(dropped.messages()[0].retries, 0);
+
+    let lost = model.next_state(&dropped, Action::DropMessage(0)).unwrap();
+    assert!(lost.messages().is_empty());
+    assert!(lost.latest().nodes.is_empty());
+    // having given up on it the node is free to try again
+    assert!(actions(&model, &lost).contains(&Action::ControllerStep(lost.max_revision(), 0)));
+}
+
+#[test]
+fn duplicated_messages_are_delivered_again() {
+    let model = model(1);
+    let sent = sent(&model);
+    let duplicated = model
+        .next_state(&sent, Action::DuplicateMessage(0))
+        .unwrap();
+    assert!(duplicated.latest().nodes.has("node-0"));
+    assert_eq!(duplicated.messages().len(), 1);
+    assert!(!actions(&model, &duplicated).contains(&Action::DuplicateMessage(0)));
+
+    let delivered = model
+        .next_state(&duplicated, Action::DeliverMessage(0))
+        .unwrap();
+    assert!(delivered.messages().is_empty());
+    assert_eq!(delivered.latest().nodes.iter().count(), 1);
+}
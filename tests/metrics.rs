@@ -0,0 +1,49 @@
+use stateright::{Checker, Model};
+use themelios::abstract_model::ActionKind;
+use themelios::metrics::{Metrics, MetricsReporter};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::user_properties;
+use themelios::utils;
+
+#[test]
+fn metrics_follow_the_progress_of_the_check() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([rs]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    cfg.user_properties =
+        user_properties::parse(r#"always "never any \"pods\"": count(pods) == 0"#).unwrap();
+    let model = cfg.into_abstract_model();
+    let metrics = Metrics::new(&model);
+
+    let before = metrics.render();
+    assert!(before.contains("themelios_states 0\n"));
+    assert!(before.contains("themelios_done 0\n"));
+    assert!(before.contains(
+        "themelios_property_holds{property=\"never any \\\"pods\\\"\",expectation=\"Always\"} 1\n"
+    ));
+
+    let mut reporter = MetricsReporter::new(metrics.clone());
+    model.checker().spawn_bfs().report(&mut reporter).join();
+    let after = metrics.render();
+    assert!(after.contains("themelios_done 1\n"));
+    assert!(after.contains("themelios_discoveries 1\n"));
+    assert!(after.contains(
+        "themelios_property_holds{property=\"never any \\\"pods\\\"\",expectation=\"Always\"} 0\n"
+    ));
+    assert!(!after.contains("themelios_unique_states 0\n"));
+}
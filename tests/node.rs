@@ -0,0 +1,557 @@
+use std::collections::BTreeMap;
+use themelios::controller::node::{self, ImagePull, NodeControllerAction, ProbeResults};
+use themelios::controller::{Controller, NodeController, NodeControllerState};
+
+use themelios::resources::{
+    ConditionStatus, ConfigMap, ConfigMapKeySelector, Container, ContainerState,
+    ContainerStateTerminated, EnvVar, EnvVarSource, Node, ObjectFieldSelector, Pod,
+    PodConditionType, PodPhase, PodRestartPolicy, PodSpec, Probe, SecretVolumeSource, Volume,
+};
+use themelios::state::apply;
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, StateView};
+use themelios::utils;
+
+fn node_controller() -> NodeController {
+    NodeController {
+        name: "node-0".to_owned(),
+        max_restarts: 1,
+        lease_duration_seconds: None,
+        image_pulls: false,
+    }
+}
+
+/// A pod on the node with a single container, with the given probes.
+fn probed_pod(readiness: bool, liveness: bool, startup: bool) -> Pod {
+    let probe = |enabled: bool| enabled.then(Probe::default);
+    Pod {
+        metadata: utils::metadata("pod".to_owned()),
+        spec: PodSpec {
+            node_name: Some("node-0".to_owned()),
+            containers: vec![Container {
+                name: "app".to_owned(),
+                image: "app".to_owned(),
+                readiness_probe: probe(readiness),
+                liveness_probe: probe(liveness),
+                startup_probe: probe(startup),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn new_state(pod: Pod) -> StateView {
+    StateView::from(
+        RawState::default()
+            .with_nodes([Node {
+                metadata: utils::metadata("node-0".to_owned()),
+                ..Default::default()
+            }])
+            .with_pods([pod]),
+    )
+}
+
+/// Take a step of the kubelet, applying the pod update it makes.
+fn step(state: &mut StateView, local: &mut NodeControllerState, revision: usize) -> Pod {
+    step_with(&node_controller(), state, local, revision)
+}
+
+fn step_with(
+    controller: &NodeController,
+    state: &mut StateView,
+    local: &mut NodeControllerState,
+    revision: usize,
+) -> Pod {
+    match controller.step(state, local) {
+        Some(NodeControllerAction::UpdatePod(pod)) => {
+            apply::pods::update(state, pod.clone(), Revision::from(vec![revision])).unwrap();
+            pod
+        }
+        op => panic!("expected a pod update, got {op:?}"),
+    }
+}
+
+fn pod_ready(pod: &Pod) -> Option<ConditionStatus> {
+    pod.status
+        .conditions
+        .iter()
+        .find(|c| c.r#type == PodConditionType::Ready)
+        .map(|c| c.status.clone())
+}
+
+fn set_probes(local: &mut NodeControllerState, results: ProbeResults) {
+    local
+        .probes
+        .insert(("pod".to_owned(), "app".to_owned()), results);
+}
+
+#[test]
+fn pods_without_probes_become_ready() {
+    let mut state = new_state(probed_pod(false, false, false));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert!(pod.status.container_statuses[0].ready);
+    let pod = step(&mut state, &mut local, 2);
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::True));
+    assert!(node_controller().step(&state, &mut local).is_none());
+}
+
+#[test]
+fn readiness_probe_drives_the_ready_condition() {
+    let mut state = new_state(probed_pod(true, false, false));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert!(!pod.status.container_statuses[0].ready);
+    let pod = step(&mut state, &mut local, 2);
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::False));
+
+    // the model can flip the readiness probe, but only for running containers
+    assert_eq!(node_controller().arbitrary_steps(&local).len(), 3);
+    set_probes(
+        &mut local,
+        ProbeResults {
+            readiness: Some(true),
+            ..Default::default()
+        },
+    );
+    let pod = step(&mut state, &mut local, 3);
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::True));
+    let condition = &pod.status.conditions[0];
+    assert_eq!(condition.last_transition_time, Some(state.now()));
+}
+
+#[test]
+fn failed_liveness_probe_restarts_the_container() {
+    let mut state = new_state(probed_pod(false, true, false));
+    let mut local = NodeControllerState::default();
+    step(&mut state, &mut local, 1);
+    step(&mut state, &mut local, 2);
+
+    set_probes(
+        &mut local,
+        ProbeResults {
+            liveness: Some(false),
+            ..Default::default()
+        },
+    );
+    let pod = step(&mut state, &mut local, 3);
+    let status = &pod.status.container_statuses[0];
+    assert_eq!(status.restart_count, 1);
+    assert!(status.ready);
+    assert_eq!(
+        local.probes.get(&("pod".to_owned(), "app".to_owned())),
+        Some(&ProbeResults {
+            liveness: Some(true),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn startup_probe_holds_back_readiness() {
+    let mut state = new_state(probed_pod(false, false, true));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert!(!pod.status.container_statuses[0].started);
+    assert!(!pod.status.container_statuses[0].ready);
+
+    set_probes(
+        &mut local,
+        ProbeResults {
+            startup: Some(true),
+            ..Default::default()
+        },
+    );
+    let pod = step(&mut state, &mut local, 2);
+    assert!(pod.status.container_statuses[0].started);
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::True));
+}
+
+/// A pod on the node with two init containers before its container.
+fn initializing_pod(restart_policy: PodRestartPolicy) -> Pod {
+    let mut pod = probed_pod(false, false, false);
+    pod.spec.restart_policy = Some(restart_policy);
+    pod.spec.init_containers = ["init-0", "init-1"]
+        .into_iter()
+        .map(|name| Container {
+            name: name.to_owned(),
+            image: name.to_owned(),
+            ..Default::default()
+        })
+        .collect();
+    pod
+}
+
+fn exit_init_container(local: &mut NodeControllerState, exit_code: u32) {
+    local.initializing.insert(
+        "pod".to_owned(),
+        ContainerState::Terminated(ContainerStateTerminated {
+            exit_code,
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn init_containers_run_in_order_before_the_containers() {
+    let mut state = new_state(initializing_pod(PodRestartPolicy::Always));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert_eq!(pod.status.phase, PodPhase::Pending);
+    assert!(matches!(
+        pod.status.init_container_statuses[0].state,
+        ContainerState::Running(_)
+    ));
+    assert!(matches!(
+        pod.status.init_container_statuses[1].state,
+        ContainerState::Waiting(_)
+    ));
+    // nothing happens until the init container exits
+    assert!(node_controller().step(&state, &mut local).is_none());
+
+    exit_init_container(&mut local, 0);
+    let pod = step(&mut state, &mut local, 2);
+    assert!(matches!(
+        pod.status.init_container_statuses[1].state,
+        ContainerState::Running(_)
+    ));
+    assert!(!node::is_initialized(&pod));
+
+    exit_init_container(&mut local, 0);
+    let pod = step(&mut state, &mut local, 3);
+    assert!(node::is_initialized(&pod));
+    assert!(local.initializing.is_empty());
+
+    let pod = step(&mut state, &mut local, 4);
+    assert_eq!(pod.status.phase, PodPhase::Running);
+}
+
+#[test]
+fn failed_init_container_is_restarted() {
+    let mut state = new_state(initializing_pod(PodRestartPolicy::OnFailure));
+    let mut local = NodeControllerState::default();
+    step(&mut state, &mut local, 1);
+    exit_init_container(&mut local, 1);
+    let pod = step(&mut state, &mut local, 2);
+    let status = &pod.status.init_container_statuses[0];
+    assert_eq!(status.restart_count, 1);
+    assert!(matches!(status.state, ContainerState::Running(_)));
+    assert_eq!(pod.status.phase, PodPhase::Pending);
+}
+
+#[test]
+fn failed_init_container_fails_the_pod_without_restarts() {
+    let mut state = new_state(initializing_pod(PodRestartPolicy::Never));
+    let mut local = NodeControllerState::default();
+    step(&mut state, &mut local, 1);
+    exit_init_container(&mut local, 1);
+    let pod = step(&mut state, &mut local, 2);
+    assert_eq!(pod.status.phase, PodPhase::Failed);
+    assert!(pod.status.container_statuses.iter().all(|cs| !cs.started));
+    assert!(local.initializing.is_empty());
+}
+
+fn exit_container(local: &mut NodeControllerState, exit_code: u32) {
+    local.running.insert(
+        "pod".to_owned(),
+        ContainerState::Terminated(ContainerStateTerminated {
+            exit_code,
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn exited_containers_back_off_before_restarting() {
+    let mut pod = probed_pod(false, false, false);
+    pod.spec.restart_policy = Some(PodRestartPolicy::Always);
+    let mut state = new_state(pod);
+    let mut local = NodeControllerState::default();
+    step(&mut state, &mut local, 1);
+    step(&mut state, &mut local, 2);
+
+    exit_container(&mut local, 1);
+    let pod = step(&mut state, &mut local, 3);
+    assert_eq!(waiting_reason(&pod), Some(node::CRASH_LOOP_BACK_OFF));
+    assert!(matches!(
+        pod.status.container_statuses[0].last_state,
+        ContainerState::Terminated(ContainerStateTerminated { exit_code: 1, .. })
+    ));
+    assert_eq!(pod.status.container_statuses[0].restart_count, 0);
+    assert_eq!(pod.status.phase, PodPhase::Running);
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::False));
+
+    // the backoff ending is the only thing that can happen
+    let mut next = node_controller().arbitrary_steps(&local);
+    assert_eq!(next.len(), 1);
+    local = next.remove(0);
+    let pod = step(&mut state, &mut local, 4);
+    assert!(matches!(
+        pod.status.container_statuses[0].state,
+        ContainerState::Running(_)
+    ));
+    assert_eq!(pod.status.container_statuses[0].restart_count, 1);
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::True));
+
+    // out of restarts, the containers are left backing off
+    exit_container(&mut local, 0);
+    let pod = step(&mut state, &mut local, 5);
+    assert_eq!(waiting_reason(&pod), Some(node::CRASH_LOOP_BACK_OFF));
+    assert!(node_controller().arbitrary_steps(&local).is_empty());
+}
+
+#[test]
+fn exited_containers_finish_the_pod_without_restarts() {
+    for (restart_policy, exit_code, phase) in [
+        (PodRestartPolicy::OnFailure, 0, PodPhase::Succeeded),
+        (PodRestartPolicy::Never, 1, PodPhase::Failed),
+    ] {
+        let mut pod = probed_pod(false, false, false);
+        pod.spec.restart_policy = Some(restart_policy);
+        let mut state = new_state(pod);
+        let mut local = NodeControllerState::default();
+        step(&mut state, &mut local, 1);
+
+        exit_container(&mut local, exit_code);
+        let pod = step(&mut state, &mut local, 2);
+        assert_eq!(pod.status.phase, phase);
+        assert!(matches!(
+            pod.status.container_statuses[0].state,
+            ContainerState::Terminated(_)
+        ));
+        assert!(local.running.is_empty());
+    }
+}
+
+#[test]
+fn terminating_pods_have_their_containers_stopped_before_deletion() {
+    let mut state = new_state(probed_pod(false, false, false));
+    let mut local = NodeControllerState::default();
+    step(&mut state, &mut local, 1);
+    step(&mut state, &mut local, 2);
+
+    let pod = state.pods.get_in("default", "pod").unwrap().clone();
+    apply::pods::soft_delete(&mut state, pod, Revision::from(vec![4])).unwrap();
+    let pod = step(&mut state, &mut local, 5);
+    assert!(matches!(
+        pod.status.container_statuses[0].state,
+        ContainerState::Terminated(_)
+    ));
+    assert_eq!(pod_ready(&pod), Some(ConditionStatus::False));
+    assert!(local.running.is_empty());
+
+    match node_controller().step(&state, &mut local) {
+        Some(NodeControllerAction::DeletePod(pod)) => assert_eq!(pod.metadata.name, "pod"),
+        op => panic!("expected the pod to be deleted, got {op:?}"),
+    }
+}
+
+/// A pod with its container's environment taken from a key of the `config` config map.
+fn config_pod(optional: Option<bool>) -> Pod {
+    let mut pod = probed_pod(false, false, false);
+    pod.spec.containers[0].env.push(EnvVar {
+        name: "SETTING".to_owned(),
+        value: None,
+        value_from: Some(EnvVarSource {
+            config_map_key_ref: Some(ConfigMapKeySelector {
+                name: "config".to_owned(),
+                key: "setting".to_owned(),
+                optional,
+            }),
+            ..Default::default()
+        }),
+    });
+    pod
+}
+
+fn config_map(data: &[&str]) -> ConfigMap {
+    ConfigMap {
+        metadata: utils::metadata("config".to_owned()),
+        data: data
+            .iter()
+            .map(|k| (k.to_string(), "value".to_owned()))
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
+
+fn waiting_reason(pod: &Pod) -> Option<&str> {
+    match &pod.status.container_statuses[0].state {
+        ContainerState::Waiting(waiting) => Some(&waiting.reason),
+        _ => None,
+    }
+}
+
+#[test]
+fn missing_config_map_holds_back_the_pod_until_created() {
+    let mut state = new_state(config_pod(None));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert_eq!(waiting_reason(&pod), Some("CreateContainerConfigError"));
+    assert_eq!(pod.status.phase, PodPhase::Pending);
+    assert!(node_controller().step(&state, &mut local).is_none());
+
+    // a config map without the key still leaves the pod waiting
+    apply::config_maps::create(&mut state, config_map(&["other"]), Revision::from(vec![2]))
+        .unwrap();
+    let pod = step(&mut state, &mut local, 3);
+    let ContainerState::Waiting(waiting) = &pod.status.container_statuses[0].state else {
+        panic!("expected the container to be waiting");
+    };
+    assert_eq!(
+        waiting.message,
+        "couldn't find key setting in ConfigMap default/config"
+    );
+
+    let mut config = state
+        .config_maps
+        .get_in("default", "config")
+        .unwrap()
+        .clone();
+    config.data.insert("setting".to_owned(), "value".to_owned());
+    apply::config_maps::update(&mut state, config, Revision::from(vec![4])).unwrap();
+    let pod = step(&mut state, &mut local, 5);
+    assert_eq!(pod.status.phase, PodPhase::Running);
+}
+
+#[test]
+fn optional_config_references_do_not_hold_back_the_pod() {
+    let mut state = new_state(config_pod(Some(true)));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert_eq!(pod.status.phase, PodPhase::Running);
+}
+
+#[test]
+fn missing_secret_volume_holds_back_the_pod() {
+    let mut pod = probed_pod(false, false, false);
+    pod.spec.volumes.push(Volume {
+        name: "creds".to_owned(),
+        secret: Some(SecretVolumeSource {
+            secret_name: "creds".to_owned(),
+            optional: None,
+        }),
+        ..Default::default()
+    });
+    let mut state = new_state(pod);
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert_eq!(waiting_reason(&pod), Some("ContainerCreating"));
+    assert!(local.running.is_empty());
+    assert_eq!(
+        pod.secret_references().keys().collect::<Vec<_>>(),
+        vec!["creds"]
+    );
+}
+
+fn pulling_node_controller() -> NodeController {
+    NodeController {
+        image_pulls: true,
+        ..node_controller()
+    }
+}
+
+fn set_pull(local: &mut NodeControllerState, pull: ImagePull) {
+    local.image_pulls.insert("pod".to_owned(), pull);
+}
+
+#[test]
+fn failed_image_pulls_back_off_before_retrying() {
+    let controller = pulling_node_controller();
+    let mut state = new_state(probed_pod(false, false, false));
+    let mut local = NodeControllerState::default();
+    let pod = step_with(&controller, &mut state, &mut local, 1);
+    assert_eq!(waiting_reason(&pod), Some("ContainerCreating"));
+    assert!(controller.step(&state, &mut local).is_none());
+    // the pull could succeed or fail, for a while or for good
+    assert_eq!(controller.arbitrary_steps(&local).len(), 3);
+
+    set_pull(&mut local, ImagePull::Failed { permanent: false });
+    let pod = step_with(&controller, &mut state, &mut local, 2);
+    assert_eq!(waiting_reason(&pod), Some(node::ERR_IMAGE_PULL));
+    let pod = step_with(&controller, &mut state, &mut local, 3);
+    assert_eq!(waiting_reason(&pod), Some(node::IMAGE_PULL_BACK_OFF));
+    assert_eq!(pod.status.phase, PodPhase::Pending);
+    assert!(controller.step(&state, &mut local).is_none());
+
+    // retrying the pull
+    let mut next = controller.arbitrary_steps(&local);
+    assert_eq!(next.len(), 1);
+    local = next.remove(0);
+    assert!(controller.step(&state, &mut local).is_none());
+    set_pull(&mut local, ImagePull::Pulled);
+    let pod = step_with(&controller, &mut state, &mut local, 4);
+    assert_eq!(pod.status.phase, PodPhase::Running);
+}
+
+#[test]
+fn permanently_failed_image_pulls_are_never_retried() {
+    let controller = pulling_node_controller();
+    let mut state = new_state(probed_pod(false, false, false));
+    let mut local = NodeControllerState::default();
+    step_with(&controller, &mut state, &mut local, 1);
+    set_pull(&mut local, ImagePull::Failed { permanent: true });
+    step_with(&controller, &mut state, &mut local, 2);
+    let pod = step_with(&controller, &mut state, &mut local, 3);
+    assert_eq!(waiting_reason(&pod), Some(node::IMAGE_PULL_BACK_OFF));
+    assert!(controller.arbitrary_steps(&local).is_empty());
+    assert!(controller.step(&state, &mut local).is_none());
+}
+
+/// A pod with its container's environment taken from the field of the pod at the path.
+fn field_pod(field_path: &str) -> Pod {
+    let mut pod = probed_pod(false, false, false);
+    pod.metadata
+        .annotations
+        .insert("index".to_owned(), "2".to_owned());
+    pod.spec.containers[0].env.extend([
+        EnvVar {
+            name: "MODE".to_owned(),
+            value: Some("fast".to_owned()),
+            value_from: None,
+        },
+        EnvVar {
+            name: "FIELD".to_owned(),
+            value: None,
+            value_from: Some(EnvVarSource {
+                field_ref: Some(ObjectFieldSelector {
+                    field_path: field_path.to_owned(),
+                    api_version: None,
+                }),
+                ..Default::default()
+            }),
+        },
+    ]);
+    pod
+}
+
+#[test]
+fn downward_api_fields_are_resolved_when_containers_start() {
+    let mut state = new_state(field_pod("metadata.annotations['index']"));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert_eq!(pod.status.phase, PodPhase::Running);
+    let environment = &local.environments[&("pod".to_owned(), "app".to_owned())];
+    assert_eq!(
+        environment,
+        &BTreeMap::from([
+            ("FIELD".to_owned(), "2".to_owned()),
+            ("MODE".to_owned(), "fast".to_owned()),
+        ])
+    );
+    assert_eq!(pod.field_value("spec.nodeName").as_deref(), Some("node-0"));
+    assert_eq!(
+        pod.field_value("metadata.labels['missing']").as_deref(),
+        Some("")
+    );
+}
+
+#[test]
+fn unsupported_fields_hold_back_the_pod() {
+    let mut state = new_state(field_pod("spec.containers"));
+    let mut local = NodeControllerState::default();
+    let pod = step(&mut state, &mut local, 1);
+    assert_eq!(waiting_reason(&pod), Some("CreateContainerConfigError"));
+    assert!(local.environments.is_empty());
+}
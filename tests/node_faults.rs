@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+
+use stateright::Model;
+use themelios::abstract_model::{Action, ControllerAction};
+use themelios::controller::node::NODE_LEASE_NAMESPACE;
+use themelios::controller::nodelifecycle;
+use themelios::model::OrchestrationModelCfg;
+use themelios::node_faults;
+use themelios::resources::{
+    ConditionStatus, Container, Node, NodeConditionType, Pod, PodConditionType, PodPhase,
+    PodQosClass, PodRestartPolicy, PodSpec, Quantity, ResourceQuantities, ResourceRequirements,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State, StateView};
+use themelios::utils;
+
+fn pod(name: &str, restart_policy: PodRestartPolicy) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata(name.to_owned()),
+        spec: PodSpec {
+            node_name: Some("node-0".to_owned()),
+            restart_policy: Some(restart_policy),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    pod.status.phase = PodPhase::Running;
+    pod
+}
+
+fn view() -> StateView {
+    let node = Node {
+        metadata: utils::metadata("node-0".to_owned()),
+        ..Default::default()
+    };
+    StateView::from(RawState::default().with_nodes([node]).with_pods([
+        pod("always", PodRestartPolicy::Always),
+        pod("never", PodRestartPolicy::Never),
+    ]))
+}
+
+#[test]
+fn crashed_nodes_lose_their_pods() {
+    let actions = node_faults::crash(&view(), "node-0");
+    assert_eq!(actions.len(), 3);
+    let phases = actions
+        .iter()
+        .filter_map(|a| match a {
+            ControllerAction::UpdatePod(pod) => {
+                Some((pod.metadata.name.as_str(), pod.status.phase))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        phases,
+        vec![("always", PodPhase::Unknown), ("never", PodPhase::Failed)]
+    );
+}
+
+#[test]
+fn drained_nodes_are_cordoned_and_evicted() {
+    let view = view();
+    let actions = node_faults::drain(&view, "node-0");
+    let ControllerAction::UpdateNode(node) = &actions[0] else {
+        panic!(
+            "expected the node to be cordoned first, got {:?}",
+            actions[0]
+        );
+    };
+    assert!(node.spec.unschedulable);
+    assert_eq!(
+        actions[1..]
+            .iter()
+            .filter(|a| matches!(a, ControllerAction::SoftDeletePod(_)))
+            .count(),
+        2
+    );
+
+    let Some(ControllerAction::UpdateNode(node)) = node_faults::uncordon(&view, "node-0") else {
+        panic!("expected the node to be uncordoned");
+    };
+    assert!(!node.spec.unschedulable);
+}
+
+#[test]
+fn crashed_nodes_only_come_back_by_restarting() {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.nodes = 1;
+    cfg.node_crashes = true;
+    cfg.node_drains = true;
+    let model = cfg.into_abstract_model();
+
+    // let the node join
+    let state = model.init_states().remove(0);
+    let state = model
+        .next_state(&state, Action::ControllerStep(state.max_revision(), 0))
+        .unwrap();
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&Action::NodeCrash(0)));
+    assert!(actions.contains(&Action::NodeDrain(0)));
+
+    let crashed = model.next_state(&state, Action::NodeCrash(0)).unwrap();
+    let mut actions = Vec::new();
+    model.actions(&crashed, &mut actions);
+    assert!(!actions.contains(&Action::NodeCrash(0)));
+    assert!(!actions
+        .iter()
+        .any(|a| matches!(a, Action::ControllerStep(_, 0))));
+    assert!(actions.contains(&Action::NodeRestart(0)));
+}
+
+/// A pod on the node with a container having the given cpu and memory requests and limits.
+fn resourced_pod(name: &str, requests: &[(&str, &str)], limits: &[(&str, &str)]) -> Pod {
+    let quantities = |qs: &[(&str, &str)]| {
+        (!qs.is_empty()).then(|| ResourceQuantities {
+            others: qs
+                .iter()
+                .map(|(r, q)| (r.to_string(), Quantity::Str(q.to_string())))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    };
+    let mut pod = pod(name, PodRestartPolicy::Always);
+    pod.spec.containers.push(Container {
+        name: "app".to_owned(),
+        resources: ResourceRequirements {
+            requests: quantities(requests),
+            limits: quantities(limits),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    pod
+}
+
+fn pressured_view() -> StateView {
+    let node = Node {
+        metadata: utils::metadata("node-0".to_owned()),
+        ..Default::default()
+    };
+    let limits = [("cpu", "1"), ("memory", "1Gi")];
+    StateView::from(RawState::default().with_nodes([node]).with_pods([
+        resourced_pod("guaranteed", &[], &limits),
+        resourced_pod("burstable", &[("memory", "1Gi")], &[]),
+        resourced_pod("best-effort", &[], &[]),
+    ]))
+}
+
+#[test]
+fn pods_are_evicted_by_their_qos_class() {
+    let view = pressured_view();
+    let order = node_faults::eviction_order(&view, "node-0")
+        .into_iter()
+        .map(|p| (p.metadata.name.as_str(), p.qos_class()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        order,
+        vec![
+            ("best-effort", PodQosClass::BestEffort),
+            ("burstable", PodQosClass::Burstable),
+            ("guaranteed", PodQosClass::Guaranteed),
+        ]
+    );
+
+    let actions = node_faults::pressure(&view, "node-0", &NodeConditionType::MemoryPressure);
+    let ControllerAction::UpdateNode(node) = &actions[0] else {
+        panic!("expected the node to be marked first, got {:?}", actions[0]);
+    };
+    assert!(node_faults::under_pressure(
+        node,
+        &NodeConditionType::MemoryPressure
+    ));
+    assert_eq!(
+        node.spec.taints[0].key,
+        "node.kubernetes.io/memory-pressure"
+    );
+    let ControllerAction::UpdatePod(pod) = &actions[1] else {
+        panic!("expected a pod to be evicted, got {:?}", actions[1]);
+    };
+    assert_eq!(pod.metadata.name, "best-effort");
+    assert_eq!(pod.status.phase, PodPhase::Failed);
+    assert!(pod.status.conditions.iter().any(
+        |c| c.r#type == PodConditionType::DisruptionTarget && c.status == ConditionStatus::True
+    ));
+}
+
+#[test]
+fn pressure_evicts_pods_until_relieved() {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.nodes = 1;
+    cfg.node_pressure = true;
+    let model = cfg.into_abstract_model();
+
+    // let the node join
+    let state = model.init_states().remove(0);
+    let state = model
+        .next_state(&state, Action::ControllerStep(state.max_revision(), 0))
+        .unwrap();
+    let pressure = Action::NodePressure(0, NodeConditionType::DiskPressure);
+    let relieved = Action::NodePressureRelieved(0, NodeConditionType::DiskPressure);
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&pressure));
+    assert!(!actions.contains(&relieved));
+
+    // without pods to evict the pressure can only be relieved
+    let pressured = model.next_state(&state, pressure.clone()).unwrap();
+    let mut actions = Vec::new();
+    model.actions(&pressured, &mut actions);
+    assert!(!actions.contains(&pressure));
+    assert!(actions.contains(&relieved));
+
+    let relieved = model.next_state(&pressured, relieved).unwrap();
+    let node = relieved.latest().nodes.get("node-0").unwrap().clone();
+    assert!(!node_faults::under_pressure(
+        &node,
+        &NodeConditionType::DiskPressure
+    ));
+    assert!(node.spec.taints.is_empty());
+}
+
+#[test]
+fn partitioned_nodes_are_noticed_once_they_miss_heartbeats() {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.nodes = 1;
+    cfg.nodelifecycle_controllers = 1;
+    cfg.node_partitions = true;
+    cfg.node_monitor_grace_period = Some(60);
+    let model = cfg.into_abstract_model();
+    let step = |state: &State, action: Action| model.next_state(state, action).unwrap();
+    let controller_step =
+        |state: &State, i: usize| step(state, Action::ControllerStep(state.max_revision(), i));
+
+    // the node joins and renews its lease
+    let state = model.init_states().remove(0);
+    let state = controller_step(&state, 0);
+    let state = controller_step(&state, 0);
+    assert!(state.latest().leases.has_in(NODE_LEASE_NAMESPACE, "node-0"));
+
+    // partitioning it leaves it ready until it misses a heartbeat
+    let state = step(&state, Action::NodePartition(0));
+    let ready = |state: &State| {
+        let node = state.latest().nodes.get("node-0").unwrap().clone();
+        node.status.conditions[0].status.clone()
+    };
+    assert_eq!(ready(&state), ConditionStatus::True);
+    let state = step(&state, Action::AdvanceClock);
+    let state = controller_step(&state, 1);
+    assert_eq!(ready(&state), ConditionStatus::Unknown);
+    let state = controller_step(&state, 1);
+    let node = state.latest().nodes.get("node-0").unwrap().clone();
+    assert_eq!(
+        node.spec.taints[0].key,
+        nodelifecycle::TAINT_NODE_UNREACHABLE
+    );
+}
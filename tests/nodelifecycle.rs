@@ -0,0 +1,166 @@
+use themelios::controller::node::NODE_LEASE_NAMESPACE;
+use themelios::controller::nodelifecycle::{self, NodeLifecycleControllerAction};
+use themelios::controller::{Controller, NodeLifecycleController, NodeLifecycleControllerState};
+use themelios::resources::{
+    ConditionStatus, Lease, LeaseSpec, Metadata, Node, NodeCondition, NodeConditionType, Operator,
+    Pod, PodCondition, PodConditionType, Taint, TaintEffect, Toleration,
+};
+use themelios::state::revision::Revision;
+use themelios::state::{apply, RawState, StateView};
+use themelios::utils;
+
+/// A ready node with a NoExecute taint added at the start of the clock.
+fn tainted_node() -> Node {
+    let mut node = Node {
+        metadata: utils::metadata("node".to_owned()),
+        ..Default::default()
+    };
+    node.spec.taints.push(Taint {
+        effect: TaintEffect::NoExecute,
+        key: "example.com/maintenance".to_owned(),
+        time_added: Some(RawState::default().now()),
+        value: String::new(),
+    });
+    node.status.conditions.push(NodeCondition {
+        r#type: NodeConditionType::Ready,
+        status: ConditionStatus::True,
+        reason: String::new(),
+        message: String::new(),
+        last_heartbeat_time: None,
+        last_transition_time: None,
+    });
+    node
+}
+
+/// A pod on the tainted node, tolerating the taint for the given number of seconds if given.
+fn pod_tolerating(toleration_seconds: Option<Option<u64>>) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata("pod".to_owned()),
+        ..Default::default()
+    };
+    pod.spec.node_name = Some("node".to_owned());
+    if let Some(toleration_seconds) = toleration_seconds {
+        pod.spec.tolerations.push(Toleration {
+            key: "example.com/maintenance".to_owned(),
+            operator: Some(Operator::Exists),
+            value: None,
+            effect: Some(TaintEffect::NoExecute),
+            toleration_seconds,
+        });
+    }
+    pod
+}
+
+fn state_at(pod: Pod, clock: u64) -> StateView {
+    let mut state = RawState::default()
+        .with_nodes([tainted_node()])
+        .with_pods([pod]);
+    state.clock = clock;
+    StateView::from(state)
+}
+
+fn evicts(state: &StateView) -> bool {
+    match NodeLifecycleController::default()
+        .step(state, &mut NodeLifecycleControllerState::default())
+    {
+        Some(NodeLifecycleControllerAction::EvictPod(pod)) => {
+            assert_eq!(pod.metadata.name, "pod");
+            true
+        }
+        None => false,
+        op => panic!("expected an eviction, got {op:?}"),
+    }
+}
+
+#[test]
+fn intolerant_pods_are_evicted() {
+    let state = state_at(pod_tolerating(None), 0);
+    assert!(evicts(&state));
+    assert!(!nodelifecycle::evictions_pending(&state));
+}
+
+#[test]
+fn tolerated_pods_are_evicted_once_their_toleration_runs_out() {
+    let state = state_at(pod_tolerating(Some(Some(120))), 60);
+    assert!(!evicts(&state));
+    assert!(nodelifecycle::evictions_pending(&state));
+
+    let state = state_at(pod_tolerating(Some(Some(120))), 120);
+    assert!(evicts(&state));
+    assert!(!nodelifecycle::evictions_pending(&state));
+}
+
+#[test]
+fn pods_tolerating_forever_are_never_evicted() {
+    let state = state_at(pod_tolerating(Some(None)), 1_000_000);
+    assert!(!evicts(&state));
+    assert!(!nodelifecycle::evictions_pending(&state));
+}
+
+/// A ready node with a ready pod, the node having last renewed its lease at the start of the
+/// clock.
+fn heartbeat_state(clock: u64) -> StateView {
+    let mut node = tainted_node();
+    node.spec.taints.clear();
+    let lease = Lease {
+        metadata: Metadata {
+            name: "node".to_owned(),
+            namespace: NODE_LEASE_NAMESPACE.to_owned(),
+            ..Default::default()
+        },
+        spec: LeaseSpec {
+            holder_identity: Some("node".to_owned()),
+            renew_time: Some(RawState::default().now()),
+            ..Default::default()
+        },
+    };
+    let mut pod = pod_tolerating(None);
+    pod.status.conditions.push(PodCondition {
+        status: ConditionStatus::True,
+        r#type: PodConditionType::Ready,
+        last_probe_time: None,
+        last_transition_time: None,
+        message: None,
+        reason: None,
+    });
+    let mut state = RawState::default()
+        .with_nodes([node])
+        .with_pods([pod])
+        .with_leases([lease]);
+    state.clock = clock;
+    StateView::from(state)
+}
+
+#[test]
+fn nodes_that_stop_heartbeating_are_marked_unknown() {
+    let controller = NodeLifecycleController {
+        node_monitor_grace_period: Some(120),
+    };
+    let step =
+        |state: &StateView| controller.step(state, &mut NodeLifecycleControllerState::default());
+    assert!(step(&heartbeat_state(60)).is_none());
+
+    let mut state = heartbeat_state(120);
+    let Some(NodeLifecycleControllerAction::UpdateNode(node)) = step(&state) else {
+        panic!("expected the node to be marked");
+    };
+    let ready = &node.status.conditions[0];
+    assert_eq!(ready.status, ConditionStatus::Unknown);
+    apply::nodes::update(&mut state, node, Revision::from(vec![1])).unwrap();
+
+    // then its pods are marked as not ready
+    let Some(NodeLifecycleControllerAction::UpdatePod(pod)) = step(&state) else {
+        panic!("expected the pod to be marked");
+    };
+    assert_eq!(pod.status.conditions[0].status, ConditionStatus::False);
+    apply::pods::update(&mut state, pod, Revision::from(vec![2])).unwrap();
+
+    // and the node gets tainted so that its pods are evicted
+    let Some(NodeLifecycleControllerAction::UpdateNode(node)) = step(&state) else {
+        panic!("expected the node to be tainted");
+    };
+    assert_eq!(
+        node.spec.taints[0].key,
+        nodelifecycle::TAINT_NODE_UNREACHABLE
+    );
+}
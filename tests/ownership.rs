@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use stateright::Model;
+use themelios::controller::util::new_controller_ref;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{Pod, PodPhase, ReplicaSet};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+use themelios::utils;
+
+fn new_replicaset(name: &str) -> ReplicaSet {
+    let mut rs = ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    rs.spec.selector.match_labels = BTreeMap::from([("app".to_owned(), name.to_owned())]);
+    rs
+}
+
+fn new_pod(name: &str, owner: &ReplicaSet) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    pod.metadata.generate_name = format!("{}-", owner.metadata.name);
+    pod.metadata.labels = owner.spec.selector.match_labels.clone();
+    pod.metadata
+        .owner_references
+        .push(new_controller_ref(&owner.metadata, &ReplicaSet::GVK));
+    pod
+}
+
+/// Whether the built-in property holds in the state.
+fn holds(property: &str, raw: RawState) -> bool {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.ownership = true;
+    let model = cfg.into_abstract_model();
+    let state = State::new(raw, ConsistencySetup::Synchronous);
+    let property = model
+        .properties()
+        .into_iter()
+        .find(|p| p.name == property)
+        .unwrap();
+    (property.condition)(&model, &state)
+}
+
+#[test]
+fn pods_of_deleted_owners_are_orphaned() {
+    let property = "ownership: pods with a controller point at an existing owner";
+    let rs = new_replicaset("web");
+    let pod = new_pod("web-1", &rs);
+    assert!(holds(
+        property,
+        RawState::default()
+            .with_replicasets([rs.clone()])
+            .with_pods([pod.clone()])
+    ));
+    assert!(!holds(
+        property,
+        RawState::default().with_pods([pod.clone()])
+    ));
+
+    // a replicaset with the same name is a different owner
+    let mut recreated = rs.clone();
+    recreated.metadata.uid = "other".to_owned();
+    assert!(!holds(
+        property,
+        RawState::default()
+            .with_replicasets([recreated])
+            .with_pods([pod.clone()])
+    ));
+
+    // without a garbage collector finished pods are left behind
+    let mut finished = pod;
+    finished.status.phase = PodPhase::Succeeded;
+    assert!(holds(property, RawState::default().with_pods([finished])));
+}
+
+#[test]
+fn pods_only_lose_their_owner_once_released() {
+    let property = "ownership: replicaset and job pods keep their owner while they match it";
+    let rs = new_replicaset("web");
+    let mut pod = new_pod("web-1", &rs);
+    pod.metadata.owner_references.clear();
+    assert!(!holds(
+        property,
+        RawState::default()
+            .with_replicasets([rs.clone()])
+            .with_pods([pod.clone()])
+    ));
+
+    // released by changing its labels
+    pod.metadata.labels.clear();
+    assert!(holds(
+        property,
+        RawState::default().with_replicasets([rs]).with_pods([pod])
+    ));
+}
@@ -0,0 +1,87 @@
+use stateright::Model;
+use themelios::abstract_model::{Action, Change, ControllerAction};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::Pod;
+use themelios::state::history::partitioned::PartitionedHistory;
+use themelios::state::history::{ConsistencySetup, History};
+use themelios::state::revision::Revision;
+use themelios::state::RawState;
+use themelios::utils;
+
+fn rev(i: usize) -> Revision {
+    Revision::from(vec![i])
+}
+
+fn create_pod(revision: Revision, name: &str) -> Change {
+    Change {
+        revision,
+        operation: ControllerAction::CreatePod(Pod {
+            metadata: utils::metadata(name.to_owned()),
+            ..Default::default()
+        }),
+    }
+}
+
+#[test]
+fn partitioned_member_serves_stale_reads_and_fails_writes() {
+    let mut history = PartitionedHistory::new(RawState::default(), 3);
+    assert!(history.partition(2));
+    // only one member is partitioned at a time
+    assert!(!history.partition(1));
+
+    history.add_change(create_pod(rev(0), "pod-a"));
+    assert_eq!(history.max_revision(), rev(1));
+    assert_eq!(history.valid_revisions(None), vec![rev(0), rev(1)]);
+    // sessions never go back to the stale member
+    assert_eq!(history.valid_revisions(Some(&rev(0))), vec![rev(1)]);
+
+    // writes through the stale member can't be committed
+    history.add_change(create_pod(rev(0), "pod-b"));
+    assert_eq!(history.max_revision(), rev(1));
+    history.add_change(create_pod(rev(1), "pod-b"));
+    assert_eq!(history.max_revision(), rev(2));
+
+    assert!(history.heal());
+    assert!(!history.heal());
+    assert_eq!(history.valid_revisions(None), vec![rev(2)]);
+}
+
+#[test]
+fn partitioning_the_leader_elects_another() {
+    let mut history = PartitionedHistory::new(RawState::default(), 3);
+    assert_eq!(history.leader(), 0);
+    assert!(history.partition(0));
+    assert_eq!(history.partitioned_member(), Some(0));
+    assert_eq!(history.leader(), 1);
+    // the new leader keeps leading once the old one is back
+    assert!(history.heal());
+    assert_eq!(history.leader(), 1);
+
+    // two members can't lose one and keep a majority
+    let mut history = PartitionedHistory::new(RawState::default(), 2);
+    assert!(!history.partition(1));
+}
+
+#[test]
+fn model_partitions_and_heals_the_store() {
+    let model =
+        OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Partitioned(3), 0)
+            .into_abstract_model();
+    let state = model.init_states().pop().unwrap();
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    for member in 0..3 {
+        assert!(actions.contains(&Action::StorePartition(member)));
+    }
+    assert!(!actions.contains(&Action::StoreHeal));
+
+    let state = model.next_state(&state, Action::StorePartition(1)).unwrap();
+    assert_eq!(state.partitioned_store_member(), Some(1));
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&Action::StoreHeal));
+    assert!(!actions.contains(&Action::StorePartition(0)));
+
+    let state = model.next_state(&state, Action::StoreHeal).unwrap();
+    assert_eq!(state.partitioned_store_member(), None);
+}
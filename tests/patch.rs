@@ -0,0 +1,150 @@
+use serde_json::json;
+use themelios::api::patch::{Patch, PatchError};
+use themelios::resources::{Container, EnvVar, Pod, PodSpec};
+use themelios::utils;
+
+fn container(name: &str, image: &str) -> Container {
+    Container {
+        name: name.to_owned(),
+        image: image.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn new_pod() -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata("pod".to_owned()),
+        spec: PodSpec {
+            containers: vec![container("app", "app:1"), container("sidecar", "proxy:1")],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    pod.metadata.finalizers = vec!["a".to_owned(), "b".to_owned()];
+    pod
+}
+
+fn images(pod: &Pod) -> Vec<(&str, &str)> {
+    pod.spec
+        .containers
+        .iter()
+        .map(|c| (c.name.as_str(), c.image.as_str()))
+        .collect()
+}
+
+#[test]
+fn json_patch_operations() {
+    let patch = Patch::json(json!([
+        { "op": "test", "path": "/spec/containers/0/image", "value": "app:1" },
+        { "op": "replace", "path": "/spec/containers/0/image", "value": "app:2" },
+        { "op": "remove", "path": "/spec/containers/1" },
+        { "op": "add", "path": "/metadata/finalizers/-", "value": "c" },
+        { "op": "move", "from": "/metadata/finalizers/0", "path": "/metadata/finalizers/1" },
+        { "op": "add", "path": "/metadata/labels", "value": {} },
+        { "op": "copy", "from": "/spec/containers/0/image", "path": "/metadata/labels/image" },
+    ]));
+    let pod = patch.apply(&new_pod()).unwrap();
+    assert_eq!(images(&pod), vec![("app", "app:2")]);
+    assert_eq!(pod.metadata.finalizers, vec!["b", "a", "c"]);
+    assert_eq!(pod.metadata.labels["image"], "app:2");
+}
+
+#[test]
+fn json_patch_failures() {
+    let failed = Patch::json(json!([
+        { "op": "test", "path": "/spec/containers/0/image", "value": "app:0" },
+    ]));
+    assert_eq!(
+        failed.apply(&new_pod()),
+        Err(PatchError::TestFailed(
+            "/spec/containers/0/image".to_owned()
+        ))
+    );
+    let missing = Patch::json(json!([{ "op": "remove", "path": "/spec/containers/5" }]));
+    assert_eq!(
+        missing.apply(&new_pod()),
+        Err(PatchError::PathNotFound("/spec/containers/5".to_owned()))
+    );
+    let invalid = Patch::Json("{".to_owned());
+    assert!(matches!(
+        invalid.apply(&new_pod()),
+        Err(PatchError::Invalid(_))
+    ));
+}
+
+#[test]
+fn merge_patch_replaces_lists() {
+    let patch = Patch::merge(json!({
+        "metadata": { "labels": { "app": "web" }, "finalizers": ["c"] },
+        "spec": { "containers": [{ "name": "app", "image": "app:2" }] },
+    }));
+    let pod = patch.apply(&new_pod()).unwrap();
+    assert_eq!(pod.metadata.labels["app"], "web");
+    assert_eq!(pod.metadata.finalizers, vec!["c"]);
+    assert_eq!(images(&pod), vec![("app", "app:2")]);
+
+    // null removes fields
+    let patch = Patch::merge(json!({ "metadata": { "labels": { "app": null } } }));
+    assert!(patch.apply(&pod).unwrap().metadata.labels.is_empty());
+}
+
+#[test]
+fn strategic_merge_patch_merges_lists_by_key() {
+    let mut pod = new_pod();
+    pod.spec.containers[0].env = vec![EnvVar {
+        name: "A".to_owned(),
+        value: Some("1".to_owned()),
+        ..Default::default()
+    }];
+    let patch = Patch::strategic_merge(json!({
+        "metadata": { "finalizers": ["b", "c"] },
+        "spec": {
+            "containers": [
+                { "name": "app", "image": "app:2", "env": [{ "name": "B", "value": "2" }] },
+                { "name": "sidecar", "$patch": "delete" },
+                { "name": "debug", "image": "busybox" },
+            ],
+        },
+    }));
+    let pod = patch.apply(&pod).unwrap();
+    assert_eq!(images(&pod), vec![("app", "app:2"), ("debug", "busybox")]);
+    let env = pod.spec.containers[0]
+        .env
+        .iter()
+        .map(|e| e.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(env, vec!["A", "B"]);
+    // finalizers are merged as a set
+    assert_eq!(pod.metadata.finalizers, vec!["a", "b", "c"]);
+}
+
+#[test]
+fn strategic_merge_patch_deletes_from_primitive_lists() {
+    let patch = Patch::strategic_merge(json!({
+        "metadata": { "$deleteFromPrimitiveList/finalizers": ["a"] },
+    }));
+    let pod = patch.apply(&new_pod()).unwrap();
+    assert_eq!(pod.metadata.finalizers, vec!["b"]);
+    assert_eq!(pod.spec, new_pod().spec);
+
+    let unsupported = Patch::strategic_merge(json!({ "metadata": { "$retainKeys": ["name"] } }));
+    assert!(matches!(
+        unsupported.apply(&new_pod()),
+        Err(PatchError::Invalid(_))
+    ));
+}
+
+#[test]
+fn patches_are_chosen_by_content_type() {
+    let body = "{}".to_owned();
+    assert_eq!(
+        Patch::from_content_type("application/merge-patch+json; charset=utf-8", body.clone()),
+        Some(Patch::Merge(body.clone()))
+    );
+    assert_eq!(
+        Patch::from_content_type("application/strategic-merge-patch+json", body.clone())
+            .map(|p| p.content_type()),
+        Some("application/strategic-merge-patch+json")
+    );
+    assert_eq!(Patch::from_content_type("application/json", body), None);
+}
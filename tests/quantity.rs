@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use themelios::resources::{Quantity, QuantityError, ResourceQuantities};
+
+fn q(s: &str) -> Quantity {
+    Quantity::Str(s.to_owned())
+}
+
+fn quantities(entries: &[(&str, &str)]) -> ResourceQuantities {
+    ResourceQuantities {
+        others: entries
+            .iter()
+            .map(|(res, s)| ((*res).to_owned(), q(s)))
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
+
+#[test]
+fn parses_suffixes() {
+    assert_eq!(q("3").value(), Ok(3));
+    assert_eq!(q("2k").value(), Ok(2_000));
+    assert_eq!(q("129M").value(), Ok(129_000_000));
+    assert_eq!(q("1Ki").value(), Ok(1024));
+    assert_eq!(q("123Mi").value(), Ok(128_974_848));
+    assert_eq!(q("4Gi").value(), Ok(4 << 30));
+    assert_eq!(q("1.5Gi").value(), Ok(3 << 29));
+    assert_eq!(q("129e6").value(), Ok(129_000_000));
+    assert_eq!(q("1E3").value(), Ok(1_000));
+    assert_eq!(q("+2").value(), Ok(2));
+}
+
+#[test]
+fn keeps_fractions_of_units() {
+    assert_eq!(q("500m").milli_value(), Ok(500));
+    assert_eq!(q("0.25").milli_value(), Ok(250));
+    // finer precision than milli is rounded up
+    assert_eq!(q("100n").milli_value(), Ok(1));
+    assert_eq!(q("1e-3").milli_value(), Ok(1));
+    // as are whole values
+    assert_eq!(q("500m").value(), Ok(1));
+    assert_eq!(Quantity::Num(2).milli_value(), Ok(2_000));
+}
+
+#[test]
+fn rejects_invalid_quantities() {
+    for s in ["", ".", "abc", "1.2.3", "1Zi", "1ki", "1e"] {
+        assert_eq!(
+            q(s).milli_value(),
+            Err(QuantityError::Invalid(s.to_owned()))
+        );
+    }
+    assert_eq!(q("-1").milli_value(), Err(QuantityError::Underflow));
+    assert_eq!(q("16Ei").value(), Err(QuantityError::Overflow));
+}
+
+#[test]
+fn checked_arithmetic() {
+    assert_eq!(q("1500m").checked_add(&q("1")), Ok(q("2500m")));
+    assert_eq!(
+        q("512Mi").checked_add(&q("512Mi")),
+        Ok(Quantity::Num(1 << 30))
+    );
+    assert_eq!(q("1").checked_sub(&q("250m")), Ok(q("750m")));
+    assert_eq!(q("1").checked_sub(&q("2")), Err(QuantityError::Underflow));
+    assert_eq!(
+        Quantity::Num(u64::MAX).checked_add(&q("1")),
+        Err(QuantityError::Overflow)
+    );
+    assert_eq!(
+        q("1").checked_add(&q("x")),
+        Err(QuantityError::Invalid("x".to_owned()))
+    );
+}
+
+#[test]
+fn saturating_arithmetic() {
+    assert_eq!(q("1").saturating_sub(&q("2")), Ok(Quantity::Num(0)));
+    assert_eq!(
+        Quantity::Num(u64::MAX).saturating_add(&q("1")),
+        Ok(Quantity::Num(u64::MAX))
+    );
+    assert_eq!(
+        q("1").saturating_sub(&q("x")),
+        Err(QuantityError::Invalid("x".to_owned()))
+    );
+}
+
+#[test]
+fn resource_quantities_compare_across_suffixes() {
+    let node = quantities(&[("cpu", "2"), ("memory", "1Gi")]);
+    let pod = quantities(&[("cpu", "500m"), ("memory", "512Mi")]);
+    assert!(pod.fits_within(&node));
+    assert!((pod.clone() + pod.clone()).fits_within(&node));
+    assert!(!(pod.clone() + pod.clone() + pod.clone()).fits_within(&node));
+    assert_eq!(
+        (node.clone() - pod.clone() - pod.clone() - pod.clone()).others["memory"],
+        Quantity::Num(0)
+    );
+
+    // invalid quantities never fit, even once added to
+    let invalid = quantities(&[("memory", "lots")]);
+    assert!(!invalid.fits_within(&node));
+    assert!(!(invalid + pod).fits_within(&node));
+}
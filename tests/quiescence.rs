@@ -0,0 +1,58 @@
+use stateright::{Checker, Model};
+use themelios::abstract_model::AbstractModel;
+use themelios::controller_properties::quiescence::{can_quiesce, quiescent};
+use themelios::coverage::{ActionCoverage, CoverageData};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+
+const PROPERTY: &str = "quiescence: controllers can settle once clients stop acting";
+
+fn model(consistency: ConsistencySetup) -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        consistency,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model.quiescence = true;
+    model.into_abstract_model()
+}
+
+#[test]
+fn controllers_with_work_left_are_not_quiescent() {
+    let model = model(ConsistencySetup::Synchronous);
+    let state = model.init_states().remove(0);
+    assert!(!quiescent(&model, &state));
+    assert!(can_quiesce(&model, &state));
+    assert!(model.properties().iter().any(|p| p.name == PROPERTY));
+}
+
+#[test]
+fn settling_records_no_coverage() {
+    let mut model = model(ConsistencySetup::Synchronous);
+    let coverage = ActionCoverage::default();
+    model.coverage = Some(coverage.clone());
+    let state = model.init_states().remove(0);
+    assert!(can_quiesce(&model, &state));
+    assert_eq!(coverage.data(), CoverageData::default());
+}
+
+#[test]
+fn replicaset_controller_settles() {
+    let checker = model(ConsistencySetup::Synchronous)
+        .checker()
+        .spawn_bfs()
+        .join();
+    assert!(checker.discovery(PROPERTY).is_none());
+}
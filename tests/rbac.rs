@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use stateright::Model;
+use themelios::abstract_model::ControllerAction;
+use themelios::controller::{Controllers, NodeController, PodGCController};
+use themelios::model::OrchestrationModelCfg;
+use themelios::rbac::{self, Authorizer, Permission, Role, Verb};
+use themelios::resources::{Pod, PolicyRule, ReplicaSet};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, ResourceKind, State};
+
+#[test]
+fn status_updates_need_update_on_parent_kind() {
+    assert_eq!(
+        ControllerAction::UpdateReplicaSetStatus(ReplicaSet::default()).required_permission(),
+        Some(Permission::new(Verb::Update, ResourceKind::ReplicaSets))
+    );
+    assert_eq!(
+        ControllerAction::HardDeletePod(Pod::default()).required_permission(),
+        Some(Permission::new(Verb::Delete, ResourceKind::Pods))
+    );
+    assert_eq!(
+        ControllerAction::AdvanceClock(60).required_permission(),
+        None
+    );
+}
+
+#[test]
+fn authorizer_rejects_actions_outside_role() {
+    let role = rbac::default_role(&Controllers::PodGC(PodGCController)).unwrap();
+    let mut roles = BTreeMap::new();
+    roles.insert(0, ("PodGC".to_owned(), role));
+    let authorizer = Authorizer::new(roles);
+
+    assert!(authorizer.authorize(0, &ControllerAction::SoftDeletePod(Pod::default())));
+    assert!(!authorizer.authorize(0, &ControllerAction::CreatePod(Pod::default())));
+    // unrestricted controllers can do anything
+    assert!(authorizer.authorize(1, &ControllerAction::CreatePod(Pod::default())));
+}
+
+#[test]
+fn authorizer_reports_unexercised_grants() {
+    let role = Role::default().with(ResourceKind::Pods, [Verb::Create, Verb::Delete]);
+    let mut roles = BTreeMap::new();
+    roles.insert(0, ("test".to_owned(), role));
+    let authorizer = Authorizer::new(roles);
+    assert_eq!(authorizer.unexercised_grants().len(), 2);
+
+    // usage is shared between clones
+    let clone = authorizer.clone();
+    assert!(clone.authorize(0, &ControllerAction::CreatePod(Pod::default())));
+    assert_eq!(
+        authorizer.unexercised_grants(),
+        vec![(
+            "test".to_owned(),
+            Permission::new(Verb::Delete, ResourceKind::Pods)
+        )]
+    );
+}
+
+#[test]
+fn bindings_authorize_by_identity() {
+    let podgc = rbac::identity(&Controllers::PodGC(PodGCController));
+    let node = rbac::identity(&Controllers::Node(NodeController {
+        name: "node-0".to_owned(),
+        max_restarts: 0,
+        lease_duration_seconds: None,
+        image_pulls: false,
+    }));
+    assert_eq!(
+        podgc.to_string(),
+        "system:serviceaccount:kube-system:pod-garbage-collector"
+    );
+    assert_eq!(node.to_string(), "system:node:node-0");
+
+    let role = rbac::default_role(&Controllers::PodGC(PodGCController)).unwrap();
+    let (role, binding) = rbac::bind(&podgc, &role);
+    let state = RawState::default()
+        .with_roles([role])
+        .with_role_bindings([binding]);
+    let delete = Permission::new(Verb::Delete, ResourceKind::Pods);
+    assert!(rbac::bindings_allow(&state, &podgc, &delete));
+    assert!(!rbac::bindings_allow(
+        &state,
+        &podgc,
+        &Permission::new(Verb::Create, ResourceKind::Pods)
+    ));
+    // the role isn't bound to the node
+    assert!(!rbac::bindings_allow(&state, &node, &delete));
+
+    let wildcard = PolicyRule {
+        api_groups: vec!["*".to_owned()],
+        resources: vec!["*".to_owned()],
+        verbs: vec!["delete".to_owned()],
+    };
+    assert!(rbac::rule_allows(&wildcard, &delete));
+    assert!(!rbac::rule_allows(
+        &wildcard,
+        &Permission::new(Verb::Update, ResourceKind::Pods)
+    ));
+}
+
+#[test]
+fn narrower_bound_roles_reject_actions() {
+    let podgc = rbac::identity(&Controllers::PodGC(PodGCController));
+    let narrower = Role::default().with(ResourceKind::Pods, [Verb::Update]);
+    let (role, binding) = rbac::bind(&podgc, &narrower);
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_roles([role.clone()]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.podgc_controllers = 1;
+    cfg.rbac_bindings = true;
+    let model = cfg.into_abstract_model();
+    let state = model.init_states().pop().unwrap();
+    let latest = state.latest();
+    // the existing role is kept, only the binding being added
+    assert_eq!(latest.roles.iter().collect::<Vec<_>>(), vec![&role]);
+    assert_eq!(
+        latest.role_bindings.iter().collect::<Vec<_>>(),
+        vec![&binding]
+    );
+
+    let mut identities = BTreeMap::new();
+    identities.insert(0, podgc);
+    let authorizer = Authorizer::new(BTreeMap::new()).with_identities(identities);
+    let state = State::new(latest.state.clone(), ConsistencySetup::Synchronous);
+    assert!(!authorizer.authorize_in(&state, 0, &ControllerAction::HardDeletePod(Pod::default())));
+    assert!(authorizer.authorize_in(&state, 0, &ControllerAction::UpdatePod(Pod::default())));
+    // controllers without identities are only checked against their roles
+    assert!(authorizer.authorize_in(&state, 1, &ControllerAction::HardDeletePod(Pod::default())));
+}
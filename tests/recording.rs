@@ -0,0 +1,162 @@
+use axum::http::Method;
+use stateright::Model;
+use themelios::abstract_model::Action;
+use themelios::model::OrchestrationModelCfg;
+use themelios::recording::{ClientOperation, RecordedResource, Recording, RecordingError};
+use themelios::resources::{Deployment, ReplicaSet};
+use themelios::serve_cluster::record::Target;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::revision::Revision;
+use themelios::state::ResourceKind;
+use themelios::utils;
+
+fn deployment(name: &str) -> Deployment {
+    let mut deployment = Deployment {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    deployment.metadata.resource_version = Revision::from(vec![7]);
+    deployment
+}
+
+fn scale(name: &str, replicas: u32) -> ClientOperation {
+    ClientOperation::Scale {
+        kind: ResourceKind::Deployments,
+        namespace: "default".to_owned(),
+        name: name.to_owned(),
+        replicas,
+    }
+}
+
+fn lines(operations: &[ClientOperation]) -> String {
+    operations
+        .iter()
+        .map(|o| serde_json::to_string(o).unwrap() + "\n")
+        .collect()
+}
+
+#[test]
+fn leading_creates_become_the_initial_state() {
+    let recording = lines(&[
+        ClientOperation::Create(RecordedResource::Deployment(deployment("web"))),
+        ClientOperation::Create(RecordedResource::ReplicaSet(ReplicaSet {
+            metadata: utils::metadata("rs".to_owned()),
+            ..Default::default()
+        })),
+        scale("web", 3),
+        ClientOperation::Create(RecordedResource::ReplicaSet(ReplicaSet {
+            metadata: utils::metadata("later".to_owned()),
+            ..Default::default()
+        })),
+    ]);
+    let replay = Recording::parse(&recording).unwrap().into_replay().unwrap();
+    let web = replay
+        .initial_state
+        .deployments
+        .get_in("default", "web")
+        .unwrap();
+    assert_eq!(web.metadata.resource_version, Revision::default());
+    assert!(replay
+        .initial_state
+        .replicasets
+        .get_in("default", "rs")
+        .is_some());
+    assert!(replay
+        .initial_state
+        .replicasets
+        .get_in("default", "later")
+        .is_none());
+    assert_eq!(replay.operations.len(), 2);
+}
+
+#[test]
+fn recordings_without_model_actions_are_rejected() {
+    let recording = lines(&[
+        scale("web", 3),
+        ClientOperation::Create(RecordedResource::Deployment(deployment("api"))),
+    ]);
+    assert_eq!(
+        Recording::parse(&recording)
+            .unwrap()
+            .into_replay()
+            .unwrap_err(),
+        RecordingError::Unsupported(1)
+    );
+    assert!(matches!(
+        Recording::parse(&format!("{recording}\nnot json")),
+        Err(RecordingError::InvalidLine(4, _))
+    ));
+}
+
+#[test]
+fn model_takes_recorded_operations_in_order() {
+    let replay = Recording {
+        operations: vec![
+            ClientOperation::Create(RecordedResource::Deployment(deployment("web"))),
+            scale("web", 3),
+            ClientOperation::Delete {
+                kind: ResourceKind::Deployments,
+                namespace: "default".to_owned(),
+                name: "web".to_owned(),
+            },
+        ],
+    }
+    .into_replay()
+    .unwrap();
+    let mut cfg =
+        OrchestrationModelCfg::new(replay.initial_state, ConsistencySetup::Synchronous, 0);
+    cfg.client_operations = replay.operations;
+    let model = cfg.into_abstract_model();
+
+    let state = model.init_states().pop().unwrap();
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&Action::ClientOperation(0)));
+    assert!(!actions.contains(&Action::ClientOperation(1)));
+
+    let state = model
+        .next_state(&state, Action::ClientOperation(0))
+        .unwrap();
+    assert_eq!(state.client_operations(), 1);
+    assert_eq!(
+        state
+            .latest()
+            .deployments
+            .get_in("default", "web")
+            .unwrap()
+            .spec
+            .replicas,
+        3
+    );
+
+    let state = model
+        .next_state(&state, Action::ClientOperation(1))
+        .unwrap();
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(!actions
+        .iter()
+        .any(|a| matches!(a, Action::ClientOperation(_))));
+}
+
+#[test]
+fn writes_are_recorded_from_their_responses() {
+    let target = Target::parse("/apis/apps/v1/namespaces/default/deployments/web/scale").unwrap();
+    assert_eq!(target.kind, ResourceKind::Deployments);
+    let body = br#"{"metadata":{"name":"web"},"spec":{"replicas":2}}"#;
+    assert_eq!(
+        target.operation(&Method::PATCH, body).unwrap(),
+        Some(scale("web", 2))
+    );
+    assert_eq!(target.operation(&Method::GET, body).unwrap(), None);
+
+    let status = Target::parse("/apis/apps/v1/namespaces/default/deployments/web/status").unwrap();
+    assert_eq!(status.operation(&Method::PUT, b"{}").unwrap(), None);
+
+    let pods = Target::parse("/api/v1/namespaces/default/pods").unwrap();
+    assert_eq!(pods.name, None);
+    assert!(pods.operation(&Method::POST, b"not json").is_err());
+
+    assert!(Target::parse("/api/v1/nodes/node1").is_none());
+    assert!(Target::parse("/api/v1/namespaces/default/secrets/s").is_none());
+}
@@ -0,0 +1,53 @@
+use stateright::Model;
+use themelios::abstract_model::Action;
+use themelios::model::OrchestrationModelCfg;
+use themelios::recording::ClientOperation;
+use themelios::resources::{Node, Pod, PodSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, ResourceKind};
+use themelios::utils;
+
+#[test]
+fn relist_at_an_older_revision_is_found_destructive_under_synchronous() {
+    // a pod on a node that gets deleted by a client, with the podgc controller relisting at the
+    // revision from before without the nodes
+    let state = RawState::default()
+        .with_nodes([Node {
+            metadata: utils::metadata("node-0".to_owned()),
+            ..Default::default()
+        }])
+        .with_pods([Pod {
+            metadata: utils::metadata("p".to_owned()),
+            spec: PodSpec {
+                node_name: Some("node-0".to_owned()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }]);
+    let mut cfg = OrchestrationModelCfg::new(state, ConsistencySetup::Synchronous, 0);
+    cfg.podgc_controllers = 1;
+    cfg.relist_faults = true;
+    cfg.client_operations = vec![ClientOperation::Delete {
+        kind: ResourceKind::Pods,
+        namespace: "default".to_owned(),
+        name: "p".to_owned(),
+    }];
+    let model = cfg.into_abstract_model();
+
+    let initial = model.init_states().pop().unwrap();
+    let before_delete = initial.max_revision();
+    let state = model
+        .next_state(&initial, Action::ClientOperation(0))
+        .unwrap();
+    assert!(state.max_revision() > before_delete);
+
+    // a fresh read under synchronous only sees the latest revision, but relists can still land
+    // on the older one
+    let relist = Action::ControllerRelist(before_delete, 0, ResourceKind::Nodes);
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&relist));
+
+    let state = model.next_state(&state, relist).unwrap();
+    assert!(state.destructive_relists().contains(&0));
+}
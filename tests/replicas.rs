@@ -0,0 +1,43 @@
+use themelios::serve_cluster::replicas::{ReplicaReads, Replicas};
+use themelios::state::history::{ConsistencySetup, SessionGuarantee};
+use themelios::state::revision::Revision;
+
+#[test]
+fn replica_reads_follow_the_consistency_level() {
+    assert_eq!(
+        ReplicaReads::new(&ConsistencySetup::Synchronous),
+        ReplicaReads::Linearizable
+    );
+    assert_eq!(
+        ReplicaReads::new(&ConsistencySetup::ResettableSession),
+        ReplicaReads::Cached(SessionGuarantee::MonotonicReads)
+    );
+    assert_eq!(
+        ReplicaReads::new(&ConsistencySetup::ReadYourWrites),
+        ReplicaReads::Cached(SessionGuarantee::ReadYourWrites)
+    );
+    assert_eq!(
+        ReplicaReads::new(&ConsistencySetup::Eventual(3)),
+        ReplicaReads::Cached(SessionGuarantee::MonotonicReads)
+    );
+}
+
+#[test]
+fn replica_caches_never_go_back() {
+    let replicas = Replicas::new(2, ReplicaReads::Cached(SessionGuarantee::MonotonicReads));
+    let first = Revision::default().increment();
+    let second = first.clone().increment();
+
+    replicas.sync(0, second.clone());
+    replicas.sync(0, first.clone());
+    assert_eq!(replicas.revision(0), second);
+    assert_eq!(replicas.revision(1), Revision::default());
+
+    // writes only move the cache along with read-your-writes
+    replicas.record_write(1, first.clone());
+    assert_eq!(replicas.revision(1), Revision::default());
+    let replicas = Replicas::new(2, ReplicaReads::Cached(SessionGuarantee::ReadYourWrites));
+    replicas.record_write(1, first.clone());
+    assert_eq!(replicas.revision(1), first);
+    assert_eq!(replicas.revision(0), Revision::default());
+}
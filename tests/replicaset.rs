@@ -3,15 +3,24 @@ use common::test_table;
 use common::test_table_panic;
 use std::collections::BTreeMap;
 use stdext::function_name;
-use themelios::model::OrchestrationModelCfg;
+use themelios::controller::replication::ReplicationManagerAction;
+use themelios::controller::{Controller, ReplicationManager, ReplicationManagerState};
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
 use themelios::resources::Container;
 use themelios::resources::Metadata;
+use themelios::resources::Namespace;
 use themelios::resources::PodSpec;
 use themelios::resources::PodTemplateSpec;
 use themelios::resources::ReplicaSet;
 use themelios::resources::ReplicaSetSpec;
+use themelios::resources::ReplicationController;
+use themelios::resources::ReplicationControllerSpec;
+use themelios::resources::Service;
+use themelios::resources::ServiceSpec;
 use themelios::state::history::ConsistencySetup;
 use themelios::state::RawState;
+use themelios::state::StateView;
 use themelios::utils;
 
 mod common;
@@ -26,19 +35,76 @@ fn model(
         initial_state,
         consistency_level: consistency,
         schedulers: controllers,
+        scheduler_profiles: Vec::new(),
         nodes: controllers,
+        container_restarts: 0,
+        image_pulls: false,
         replicaset_controllers: controllers,
+        replicationcontroller_controllers: 0,
         deployment_controllers: 0,
         statefulset_controllers: 0,
         job_controllers: 0,
+        cronjob_controllers: 0,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
         podgc_controllers: controllers,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
         properties: Vec::new(),
     }
 }
 
-fn new_replicaset(name: &str, _namespace: &str, replicas: u32) -> ReplicaSet {
-    let mut d = ReplicaSet {
+fn new_namespace(name: &str) -> Namespace {
+    Namespace {
         metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    }
+}
+
+fn new_replicaset(name: &str, namespace: &str, replicas: u32) -> ReplicaSet {
+    let mut metadata = utils::metadata(name.to_owned());
+    if !namespace.is_empty() {
+        metadata.namespace = namespace.to_owned();
+    }
+    let mut d = ReplicaSet {
+        metadata,
         spec: ReplicaSetSpec {
             replicas: Some(replicas),
             ..Default::default()
@@ -98,6 +164,103 @@ test_table_panic! {
     causal_2(ConsistencySetup::Causal, 2),
 }
 
+fn test_spec_replicas_change_relist_faults(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: replicaset whose pods get scheduled, with controllers seeing relists missing
+    // a kind of resource
+    // never: the podgc controller removes pods for nodes missing from the relist
+    let mut model = test_spec_replicas_change(consistency, controllers);
+    model.relist_faults = true;
+    model
+}
+
+test_table_panic! {
+    test_spec_replicas_change_relist_faults,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+}
+
+fn test_spec_replicas_change_watch_caches(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: replicaset whose controller reads through a watch cache that lags behind its
+    // own creates
+    // never: more pods than replicas are created from the stale cache
+    let mut model = test_spec_replicas_change(consistency, controllers);
+    model.watch_caches = true;
+    model
+}
+
+test_table_panic! {
+    test_spec_replicas_change_watch_caches,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+}
+
+fn test_node_partition(consistency: ConsistencySetup, controllers: usize) -> OrchestrationModelCfg {
+    // initial state: replicaset whose pods get scheduled across two nodes, either of which can be
+    // partitioned from the control plane
+    // eventually: pods on the partitioned node are evicted and replaced on the other
+    let mut model = test_spec_replicas_change(consistency, controllers);
+    model.nodes = 2;
+    model.nodelifecycle_controllers = controllers;
+    model.node_partitions = true;
+    model
+}
+
+test_table! {
+    test_node_partition,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_namespace_deletion(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: replicaset in its own namespace, which can get deleted
+    // always: once the namespace is gone none of its replicasets or pods are left
+    let replicaset = new_replicaset("test-namespace-deletion", "test", 1);
+    let mut model = model([replicaset], consistency, controllers);
+    model
+        .initial_state
+        .set_namespaces([new_namespace("default"), new_namespace("test")]);
+    model.namespace_controllers = controllers;
+    model
+}
+
+test_table! {
+    test_namespace_deletion,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_service_availability(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: replicaset with a service selecting its pods
+    // always: when the endpoints are current, ready pods are routable through the service
+    let replicaset = new_replicaset("test-service-availability", "", 2);
+    let service = Service {
+        metadata: utils::metadata("test-service".to_owned()),
+        spec: ServiceSpec {
+            selector: replicaset.spec.selector.match_labels.clone(),
+        },
+    };
+    let mut model = model([replicaset], consistency, controllers);
+    model.initial_state.set_services([service]);
+    model.endpoints_controllers = controllers;
+    model
+}
+
+test_table! {
+    test_service_availability,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    synchronous_2(ConsistencySetup::Synchronous, 2),
+}
+
 // TestOverlappingRSs
 fn test_overlapping_rss(
     consistency: ConsistencySetup,
@@ -127,6 +290,61 @@ test_table_panic! {
     causal_2(ConsistencySetup::Causal, 2),
 }
 
+/// A replication controller with the same pods as [`new_replicaset`], leaving the selector to be
+/// defaulted from the template.
+fn new_replication_controller(name: &str, replicas: u32) -> ReplicationController {
+    let replicaset = new_replicaset(name, "", replicas);
+    ReplicationController {
+        metadata: replicaset.metadata,
+        spec: ReplicationControllerSpec {
+            replicas: Some(replicas),
+            template: Some(replicaset.spec.template),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn test_replication_controller(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: replication controller with 2 replicas
+    // always: when stable, the status matches the spec and the active pods
+    let rc = new_replication_controller("test-replication-controller", 2);
+    let mut model = model([], consistency, controllers);
+    model.initial_state.set_replication_controllers([rc]);
+    model.replicaset_controllers = 0;
+    model.replicationcontroller_controllers = controllers;
+    model
+}
+
+test_table! {
+    test_replication_controller,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    synchronous_2(ConsistencySetup::Synchronous, 2),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+#[test]
+fn replication_controller_pods_are_owned_by_it() {
+    let rc = new_replication_controller("test-rc", 1);
+    let state = StateView::from(RawState::default().with_replication_controllers([rc.clone()]));
+    let action = ReplicationManager.step(&state, &mut ReplicationManagerState::default());
+    let Some(ReplicationManagerAction::CreatePod(pod)) = action else {
+        panic!("expected a pod to be created, got {:?}", action);
+    };
+    let owner = &pod.metadata.owner_references[0];
+    assert_eq!(owner.kind, "ReplicationController");
+    assert_eq!(owner.api_version, "v1");
+    assert_eq!(owner.uid, rc.metadata.uid);
+    // the defaulted selector matches the pods from the template
+    assert_eq!(
+        pod.metadata.labels,
+        rc.spec.template.unwrap().metadata.labels
+    );
+}
+
 // TESTS TO DO
 // TestAdoption
 // TestDeletingAndFailedPods
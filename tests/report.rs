@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroU64;
+use std::sync::atomic::Ordering;
+
+use stateright::{Checker, CheckerTerminalVisitor, Model};
+use themelios::abstract_model::ActionKind;
+use themelios::controller::deployment::REVISION_ANNOTATION;
+use themelios::controller::util::new_controller_ref;
+use themelios::model::OrchestrationModelCfg;
+use themelios::report::{
+    rollout_stats, DeepestPath, DepthHistogram, HtmlReporter, JointTerminalVisitor, JsonReporter,
+    RolloutStats, RolloutSummary, SqliteReporter,
+};
+use themelios::resources::{Deployment, Pod, ReplicaSet, ReplicaSetSpec};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+use themelios::user_properties;
+use themelios::utils;
+
+fn new_replicaset(name: &str, owner: &Deployment, revision: &str) -> ReplicaSet {
+    let mut rs = ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    rs.metadata
+        .owner_references
+        .push(new_controller_ref(&owner.metadata, &Deployment::GVK));
+    rs.metadata
+        .annotations
+        .insert(REVISION_ANNOTATION.to_owned(), revision.to_owned());
+    rs
+}
+
+fn new_pod(name: &str, owner: &ReplicaSet) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    pod.metadata
+        .owner_references
+        .push(new_controller_ref(&owner.metadata, &ReplicaSet::GVK));
+    pod
+}
+
+fn state(raw: RawState) -> State {
+    State::new(raw, ConsistencySetup::Synchronous)
+}
+
+#[test]
+fn rollout_stats_track_replicasets_and_pods_over_a_path() {
+    let deployment = Deployment {
+        metadata: utils::metadata("dep".to_owned()),
+        ..Default::default()
+    };
+    let rs1 = new_replicaset("rs-1", &deployment, "1");
+    let rs2 = new_replicaset("rs-2", &deployment, "2");
+
+    let states = [
+        // the old replicaset and its pods
+        state(
+            RawState::default()
+                .with_deployments([deployment.clone()])
+                .with_replicasets([rs1.clone()])
+                .with_pods([new_pod("pod-1", &rs1), new_pod("pod-2", &rs1)]),
+        ),
+        // surging with both replicasets
+        state(
+            RawState::default()
+                .with_deployments([deployment.clone()])
+                .with_replicasets([rs1.clone(), rs2.clone()])
+                .with_pods([
+                    new_pod("pod-1", &rs1),
+                    new_pod("pod-2", &rs1),
+                    new_pod("pod-3", &rs2),
+                ]),
+        ),
+        // only the new replicaset remains
+        state(
+            RawState::default()
+                .with_deployments([deployment])
+                .with_replicasets([rs2.clone()])
+                .with_pods([new_pod("pod-3", &rs2), new_pod("pod-4", &rs2)]),
+        ),
+    ];
+
+    let stats = rollout_stats(&states);
+    assert_eq!(
+        stats.get("dep"),
+        Some(&RolloutStats {
+            replicasets_created: 2,
+            max_concurrent_replicasets: 2,
+            peak_pods: 3,
+            max_revision: 2,
+        })
+    );
+}
+
+#[test]
+fn rollout_summary_aggregates_paths() {
+    let mut summary = RolloutSummary::default();
+    assert_eq!(summary.mean_replicasets_created(), 0.0);
+    summary.add(&RolloutStats {
+        replicasets_created: 1,
+        max_concurrent_replicasets: 1,
+        peak_pods: 2,
+        max_revision: 1,
+    });
+    summary.add(&RolloutStats {
+        replicasets_created: 2,
+        max_concurrent_replicasets: 2,
+        peak_pods: 3,
+        max_revision: 2,
+    });
+    assert_eq!(summary.paths, 2);
+    assert_eq!(summary.mean_replicasets_created(), 1.5);
+    assert_eq!(summary.max_replicasets_created, 2);
+    assert_eq!(summary.peak_pods, 3);
+    assert_eq!(summary.max_revision, 2);
+}
+
+#[test]
+fn json_reporter_writes_the_results_of_the_check() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([rs]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    let model = cfg.into_abstract_model();
+    let path = std::env::temp_dir().join("themelios-json-reporter.json");
+    let mut reporter = JsonReporter::new(
+        &model,
+        &path,
+        ConsistencySetup::Synchronous,
+        0,
+        model.controllers.len(),
+        "json_reporter".to_owned(),
+    );
+    model.checker().spawn_bfs().report(&mut reporter).join();
+
+    let report: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(report["done"], true);
+    assert_eq!(report["config"]["function"], "json_reporter");
+    assert!(report["unique_states"].as_u64().unwrap() > 0);
+    let properties = report["properties"].as_array().unwrap();
+    let unique = properties
+        .iter()
+        .find(|p| p["name"] == "all resources have unique names")
+        .unwrap();
+    assert_eq!(unique["expectation"], "Always");
+    assert_eq!(unique["holds"], true);
+    assert!(unique["discovery"].is_null());
+}
+
+#[test]
+fn html_reporter_renders_the_steps_to_each_discovery() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([rs]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    cfg.user_properties =
+        user_properties::parse(r#"always "never any <pods>": count(pods) == 0"#).unwrap();
+    let model = cfg.into_abstract_model();
+    let path = std::env::temp_dir().join("themelios-html-reporter.html");
+    let mut reporter = HtmlReporter::new(&model, &path);
+    model.checker().spawn_bfs().report(&mut reporter).join();
+
+    let html = std::fs::read_to_string(&path).unwrap();
+    assert!(html.contains("never any &lt;pods&gt;"));
+    // the replicaset controller creating the pod
+    assert!(html.contains("CreatePod"));
+    assert!(html.contains("<span class=\"add\">+# Pod/"));
+}
+
+#[test]
+fn sqlite_reporter_records_runs_to_query_across() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([rs]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    cfg.user_properties =
+        user_properties::parse(r#"always "never any pods": count(pods) == 0"#).unwrap();
+    let model = cfg.into_abstract_model();
+    let path = std::env::temp_dir().join("themelios-sqlite-reporter.sqlite");
+    let _ = std::fs::remove_file(&path);
+
+    let mut run_ids = Vec::new();
+    for function in ["first", "second"] {
+        let mut reporter = SqliteReporter::new(
+            &model,
+            &path,
+            ConsistencySetup::Synchronous,
+            0,
+            model.controllers.len(),
+            function.to_owned(),
+        )
+        .unwrap();
+        model.checker().spawn_bfs().report(&mut reporter).join();
+        reporter.record_depths([(0, 1), (1, 2)]);
+        run_ids.push(reporter.run_id());
+    }
+    assert_ne!(run_ids[0], run_ids[1]);
+
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    let runs: i64 = connection
+        .query_row("SELECT COUNT(*) FROM runs", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(runs, 2);
+    let (holds, trace): (bool, Option<String>) = connection
+        .query_row(
+            "SELECT holds, trace FROM properties JOIN runs ON runs.id = properties.run_id WHERE runs.function = 'second' AND properties.name = 'never any pods'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap();
+    assert!(!holds);
+    assert!(trace.is_some());
+    let done: bool = connection
+        .query_row(
+            "SELECT done FROM progress WHERE run_id = ?1 ORDER BY rowid DESC LIMIT 1",
+            [run_ids[0]],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert!(done);
+    let depths: i64 = connection
+        .query_row("SELECT SUM(count) FROM depths", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(depths, 6);
+}
+
+#[test]
+fn stopping_the_model_still_reports_the_histogram_of_depths() {
+    let rs = ReplicaSet {
+        metadata: utils::metadata("web".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([rs]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    let model = cfg.into_abstract_model();
+
+    let depths = DepthHistogram::default();
+    model
+        .clone()
+        .checker()
+        .terminal_visitor(JointTerminalVisitor {
+            visitors: vec![Box::new(depths.clone())],
+        })
+        .spawn_bfs()
+        .join();
+    let counts = depths.counts();
+    assert!(!counts.is_empty());
+    assert!(counts.keys().all(|depth| *depth > 0));
+
+    model.stop.store(true, Ordering::Relaxed);
+    let stopped = DepthHistogram::default();
+    let checker = model
+        .clone()
+        .checker()
+        .terminal_visitor(stopped.clone())
+        .spawn_bfs()
+        .join();
+    assert!(checker.is_done());
+    // nothing more is explored once stopped
+    assert_eq!(stopped.counts(), BTreeMap::new());
+}
+
+#[test]
+fn the_deepest_path_is_kept() {
+    let model = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0)
+        .into_abstract_model();
+    let deepest = DeepestPath::default();
+    assert_eq!(deepest.encoded_path(), None);
+    let fps = |fps: &[u64]| {
+        fps.iter()
+            .map(|fp| NonZeroU64::new(*fp).unwrap())
+            .collect::<Vec<_>>()
+    };
+    deepest.visit(&model, &fps(&[1, 2]));
+    deepest.visit(&model, &fps(&[1, 3, 4]));
+    deepest.visit(&model, &fps(&[5]));
+    assert_eq!(deepest.depth(), 3);
+    assert_eq!(deepest.encoded_path().as_deref(), Some("1/3/4"));
+}
@@ -0,0 +1,604 @@
+use common::run;
+use common::test_table;
+use common::test_table_panic;
+use stateright::{Checker, Model};
+use stdext::function_name;
+use themelios::abstract_model::ActionKind;
+use themelios::controller::scheduler::{
+    self, SchedulerControllerAction, SchedulerProfile, ScoringStrategy,
+};
+use themelios::controller::{
+    Controller, Controllers, SchedulerController, SchedulerControllerState,
+};
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
+use themelios::resources::Affinity;
+use themelios::resources::Container;
+use themelios::resources::Node;
+use themelios::resources::NodeAffinity;
+use themelios::resources::NodeSelector;
+use themelios::resources::NodeSelectorOperator;
+use themelios::resources::NodeSelectorRequirement;
+use themelios::resources::NodeSelectorTerm;
+use themelios::resources::NodeStatus;
+use themelios::resources::Pod;
+use themelios::resources::PodPhase;
+use themelios::resources::PodSpec;
+use themelios::resources::PreemptionPolicy;
+use themelios::resources::PreferredSchedulingTerm;
+use themelios::resources::PriorityClass;
+use themelios::resources::ResourceQuantities;
+use themelios::resources::ResourceRequirements;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::state::StateView;
+use themelios::utils;
+
+mod common;
+
+fn model(initial_state: RawState, consistency: ConsistencySetup) -> OrchestrationModelCfg {
+    OrchestrationModelCfg {
+        initial_state,
+        consistency_level: consistency,
+        schedulers: 1,
+        scheduler_profiles: Vec::new(),
+        nodes: 1,
+        container_restarts: 0,
+        image_pulls: false,
+        replicaset_controllers: 0,
+        replicationcontroller_controllers: 0,
+        deployment_controllers: 0,
+        statefulset_controllers: 0,
+        job_controllers: 0,
+        cronjob_controllers: 0,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
+        podgc_controllers: 0,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
+        properties: Vec::new(),
+    }
+}
+
+fn cpu(amount: u32) -> ResourceQuantities {
+    let mut quantities = ResourceQuantities::default();
+    quantities.others.insert("cpu".to_owned(), amount.into());
+    quantities
+}
+
+fn new_node(name: &str, capacity: u32) -> Node {
+    Node {
+        metadata: utils::metadata(name.to_owned()),
+        status: NodeStatus {
+            capacity: cpu(capacity),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn new_pod(name: &str, requests: u32, priority_class: Option<&str>) -> Pod {
+    Pod {
+        metadata: utils::metadata(name.to_owned()),
+        spec: PodSpec {
+            containers: vec![Container {
+                name: "fake".to_owned(),
+                image: "fake".to_owned(),
+                resources: ResourceRequirements {
+                    requests: Some(cpu(requests)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            priority_class_name: priority_class.map(ToOwned::to_owned),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn new_priority_class(name: &str, value: i32) -> PriorityClass {
+    PriorityClass {
+        metadata: utils::metadata(name.to_owned()),
+        value,
+        ..Default::default()
+    }
+}
+
+fn bound(mut pod: Pod, node: &str, priority: i32) -> Pod {
+    pod.spec.node_name = Some(node.to_owned());
+    pod.spec.priority = Some(priority);
+    pod
+}
+
+test_table! {
+    test_preemption,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_preemption(consistency: ConsistencySetup, _controllers: usize) -> OrchestrationModelCfg {
+    // initial state: a full node running low priority pods and a high priority pod that doesn't
+    // fit
+    // eventually: the high priority pod gets scheduled by preempting a low priority one
+    // pods in the initial state skip admission so get their priority filled in here
+    let mut high = new_pod("high", 1, Some("high"));
+    high.spec.priority = Some(1000);
+    let initial_state = RawState::default()
+        .with_priority_classes([new_priority_class("high", 1000)])
+        .with_nodes([new_node("node-0", 2)])
+        .with_pods([
+            bound(new_pod("low-1", 1, None), "node-0", 0),
+            bound(new_pod("low-2", 1, None), "node-0", 0),
+            high,
+        ]);
+    model(initial_state, consistency)
+}
+
+#[test]
+fn scheduler_preempts_lowest_priority_pod() {
+    let mut high = new_pod("high", 1, None);
+    high.spec.priority = Some(100);
+    let state = StateView::from(
+        RawState::default()
+            .with_nodes([new_node("node-0", 2)])
+            .with_pods([
+                bound(new_pod("mid", 1, None), "node-0", 10),
+                bound(new_pod("low", 1, None), "node-0", 1),
+                high,
+            ]),
+    );
+    let op = SchedulerController::default().step(&state, &mut SchedulerControllerState::default());
+    match op {
+        Some(SchedulerControllerAction::PreemptPod(victim)) => {
+            assert_eq!(victim.metadata.name, "low")
+        }
+        op => panic!("expected a preemption, got {op:?}"),
+    }
+}
+
+#[test]
+fn scheduler_does_not_preempt_equal_priority_pods() {
+    let mut pod = new_pod("pending", 1, None);
+    pod.spec.priority = Some(10);
+    let state = StateView::from(
+        RawState::default()
+            .with_nodes([new_node("node-0", 1)])
+            .with_pods([bound(new_pod("running", 1, None), "node-0", 10), pod]),
+    );
+    let op = SchedulerController::default().step(&state, &mut SchedulerControllerState::default());
+    assert!(op.is_none());
+}
+
+#[test]
+fn scheduler_respects_never_preemption_policy() {
+    let mut pod = new_pod("pending", 1, None);
+    pod.spec.priority = Some(100);
+    pod.spec.preemption_policy = Some(PreemptionPolicy::Never);
+    let state = StateView::from(
+        RawState::default()
+            .with_nodes([new_node("node-0", 1)])
+            .with_pods([bound(new_pod("running", 1, None), "node-0", 0), pod]),
+    );
+    let op = SchedulerController::default().step(&state, &mut SchedulerControllerState::default());
+    assert!(op.is_none());
+}
+
+fn labelled_node(name: &str, labels: &[(&str, &str)]) -> Node {
+    let mut node = new_node(name, 1);
+    node.metadata.labels = labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    node
+}
+
+fn term(key: &str, operator: NodeSelectorOperator, values: &[&str]) -> NodeSelectorTerm {
+    NodeSelectorTerm {
+        match_expressions: vec![NodeSelectorRequirement {
+            key: key.to_owned(),
+            operator,
+            values: values.iter().map(|v| v.to_string()).collect(),
+        }],
+        ..Default::default()
+    }
+}
+
+fn with_affinity(mut pod: Pod, affinity: NodeAffinity) -> Pod {
+    pod.spec.affinity = Some(Affinity {
+        node_affinity: Some(affinity),
+    });
+    pod
+}
+
+fn required(terms: Vec<NodeSelectorTerm>) -> NodeAffinity {
+    NodeAffinity {
+        required_during_scheduling_ignored_during_execution: Some(NodeSelector {
+            node_selector_terms: terms,
+        }),
+        ..Default::default()
+    }
+}
+
+/// The node that the scheduler binds the only pending pod to, if any.
+fn scheduled_node(nodes: Vec<Node>, pod: Pod) -> Option<String> {
+    let state = StateView::from(RawState::default().with_nodes(nodes).with_pods([pod]));
+    match SchedulerController::default().step(&state, &mut SchedulerControllerState::default()) {
+        Some(SchedulerControllerAction::UpdatePod(pod)) => pod.spec.node_name,
+        None => None,
+        op => panic!("expected the pod to be bound, got {op:?}"),
+    }
+}
+
+#[test]
+fn scheduler_honours_node_selector() {
+    let mut pod = new_pod("pending", 0, None);
+    pod.spec
+        .node_selector
+        .insert("disk".to_owned(), "ssd".to_owned());
+    let nodes = vec![
+        labelled_node("node-0", &[("disk", "hdd")]),
+        labelled_node("node-1", &[("disk", "ssd")]),
+    ];
+    assert_eq!(scheduled_node(nodes, pod), Some("node-1".to_owned()));
+}
+
+#[test]
+fn scheduler_matches_affinity_expressions() {
+    let nodes = || {
+        vec![
+            labelled_node("node-0", &[("zone", "a"), ("cores", "2")]),
+            labelled_node("node-1", &[("zone", "b"), ("cores", "8")]),
+        ]
+    };
+    let pod = |terms| with_affinity(new_pod("pending", 0, None), required(terms));
+
+    let in_b = pod(vec![term("zone", NodeSelectorOperator::In, &["b", "c"])]);
+    assert_eq!(scheduled_node(nodes(), in_b), Some("node-1".to_owned()));
+
+    let not_in_b = pod(vec![term("zone", NodeSelectorOperator::NotIn, &["b"])]);
+    assert_eq!(scheduled_node(nodes(), not_in_b), Some("node-0".to_owned()));
+
+    let gt = pod(vec![term("cores", NodeSelectorOperator::Gt, &["4"])]);
+    assert_eq!(scheduled_node(nodes(), gt), Some("node-1".to_owned()));
+
+    let lt = pod(vec![term("cores", NodeSelectorOperator::Lt, &["4"])]);
+    assert_eq!(scheduled_node(nodes(), lt), Some("node-0".to_owned()));
+
+    // terms are ORed, the requirements within one are ANDed
+    let mut both = term("zone", NodeSelectorOperator::In, &["a"]);
+    both.match_expressions
+        .extend(term("cores", NodeSelectorOperator::Gt, &["4"]).match_expressions);
+    let either = pod(vec![both, term("gpu", NodeSelectorOperator::Exists, &[])]);
+    assert_eq!(scheduled_node(nodes(), either), None);
+}
+
+#[test]
+fn scheduler_prefers_nodes_by_affinity_weight() {
+    let nodes = vec![
+        labelled_node("node-0", &[("zone", "a")]),
+        labelled_node("node-1", &[("zone", "b")]),
+    ];
+    let pod = with_affinity(
+        new_pod("pending", 0, None),
+        NodeAffinity {
+            preferred_during_scheduling_ignored_during_execution: vec![
+                PreferredSchedulingTerm {
+                    weight: 10,
+                    preference: term("zone", NodeSelectorOperator::In, &["a"]),
+                },
+                PreferredSchedulingTerm {
+                    weight: 50,
+                    preference: term("zone", NodeSelectorOperator::In, &["b"]),
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    assert_eq!(scheduled_node(nodes, pod), Some("node-1".to_owned()));
+}
+
+#[test]
+fn scheduler_counts_init_container_requests() {
+    let mut pod = new_pod("pending", 1, None);
+    pod.spec.init_containers = pod.spec.containers.clone();
+    pod.spec.init_containers[0].resources.requests = Some(cpu(2));
+    assert_eq!(scheduler::pod_requests(&pod), cpu(2));
+    assert_eq!(scheduled_node(vec![new_node("node-0", 1)], pod), None);
+}
+
+#[test]
+fn scheduler_ignores_requests_of_finished_pods() {
+    let mut finished = bound(new_pod("finished", 1, None), "node-0", 0);
+    finished.status.phase = PodPhase::Succeeded;
+    let state = StateView::from(
+        RawState::default()
+            .with_nodes([new_node("node-0", 1)])
+            .with_pods([finished, new_pod("pending", 1, None)]),
+    );
+    let op = SchedulerController::default().step(&state, &mut SchedulerControllerState::default());
+    match op {
+        Some(SchedulerControllerAction::UpdatePod(pod)) => {
+            assert_eq!(pod.spec.node_name.as_deref(), Some("node-0"))
+        }
+        op => panic!("expected the pod to be bound, got {op:?}"),
+    }
+}
+
+/// The node that a scheduler with the given profile binds the only pending pod to, if any.
+fn scheduled_node_by(profile: &str, nodes: Vec<Node>, pods: Vec<Pod>) -> Option<String> {
+    let scheduler = SchedulerController::new(profile.parse().unwrap());
+    let state = StateView::from(RawState::default().with_nodes(nodes).with_pods(pods));
+    match scheduler.step(&state, &mut SchedulerControllerState::default()) {
+        Some(SchedulerControllerAction::UpdatePod(pod)) => pod.spec.node_name,
+        None => None,
+        op => panic!("expected the pod to be bound, got {op:?}"),
+    }
+}
+
+#[test]
+fn scheduler_only_schedules_pods_naming_it() {
+    let mut pod = new_pod("pending", 0, None);
+    pod.spec.scheduler_name = Some("batch".to_owned());
+    let nodes = || vec![new_node("node-0", 1)];
+    assert_eq!(scheduled_node(nodes(), pod.clone()), None);
+    assert_eq!(
+        scheduled_node_by("batch", nodes(), vec![pod.clone()]),
+        Some("node-0".to_owned())
+    );
+
+    // the default profile can also be named explicitly
+    pod.spec.scheduler_name = Some(scheduler::DEFAULT_SCHEDULER_NAME.to_owned());
+    assert_eq!(
+        scheduled_node(nodes(), pod.clone()),
+        Some("node-0".to_owned())
+    );
+    assert_eq!(scheduled_node_by("batch", nodes(), vec![pod]), None);
+}
+
+#[test]
+fn scheduler_profiles_score_nodes() {
+    let nodes = || vec![new_node("node-0", 4), new_node("node-1", 4)];
+    let pods = || {
+        vec![
+            bound(new_pod("running", 1, None), "node-1", 0),
+            new_pod("pending", 1, None),
+        ]
+    };
+    assert_eq!(
+        scheduled_node_by("default-scheduler", nodes(), pods()),
+        Some("node-0".to_owned())
+    );
+    assert_eq!(
+        scheduled_node_by("default-scheduler:most-allocated", nodes(), pods()),
+        Some("node-1".to_owned())
+    );
+}
+
+#[test]
+fn scheduler_profile_without_preemption() {
+    let mut high = new_pod("high", 1, None);
+    high.spec.priority = Some(100);
+    let state = StateView::from(
+        RawState::default()
+            .with_nodes([new_node("node-0", 1)])
+            .with_pods([bound(new_pod("low", 1, None), "node-0", 0), high]),
+    );
+    let scheduler = SchedulerController::new("default-scheduler:no-preemption".parse().unwrap());
+    let op = scheduler.step(&state, &mut SchedulerControllerState::default());
+    assert!(op.is_none());
+}
+
+#[test]
+fn scheduler_profile_parsing() {
+    assert_eq!(
+        "batch:most-allocated:no-preemption".parse::<SchedulerProfile>(),
+        Ok(SchedulerProfile {
+            scheduler_name: "batch".to_owned(),
+            scoring: ScoringStrategy::MostAllocated,
+            preemption: false,
+            gang_scheduling: false,
+        })
+    );
+    assert!("".parse::<SchedulerProfile>().is_err());
+    assert!("batch:spread".parse::<SchedulerProfile>().is_err());
+}
+
+fn gang_pod(name: &str, group: &str) -> Pod {
+    let mut pod = new_pod(name, 1, None);
+    pod.metadata
+        .annotations
+        .insert(scheduler::ANNOTATION_POD_GROUP.to_owned(), group.to_owned());
+    pod
+}
+
+#[test]
+fn gang_scheduler_waits_for_the_whole_group_to_fit() {
+    let gang = || {
+        vec![
+            gang_pod("a-0", "a"),
+            gang_pod("a-1", "a"),
+            gang_pod("a-2", "a"),
+        ]
+    };
+    assert_eq!(
+        scheduled_node_by(
+            "default-scheduler:gang",
+            vec![new_node("node-0", 2)],
+            gang()
+        ),
+        None
+    );
+    assert_eq!(
+        scheduled_node_by(
+            "default-scheduler:gang",
+            vec![new_node("node-0", 2), new_node("node-1", 1)],
+            gang()
+        ),
+        Some("node-0".to_owned())
+    );
+    // without gang scheduling the pods are placed one at a time
+    assert_eq!(
+        scheduled_node_by("default-scheduler", vec![new_node("node-0", 2)], gang()),
+        Some("node-0".to_owned())
+    );
+}
+
+#[test]
+fn gang_scheduler_honours_min_member() {
+    let mut gang = vec![
+        gang_pod("a-0", "a"),
+        gang_pod("a-1", "a"),
+        gang_pod("a-2", "a"),
+    ];
+    for pod in &mut gang {
+        pod.metadata.annotations.insert(
+            scheduler::ANNOTATION_POD_GROUP_MIN_MEMBER.to_owned(),
+            "2".to_owned(),
+        );
+    }
+    assert_eq!(
+        scheduled_node_by(
+            "default-scheduler:gang",
+            vec![new_node("node-0", 2)],
+            gang.clone()
+        ),
+        Some("node-0".to_owned())
+    );
+    assert_eq!(
+        scheduled_node_by("default-scheduler:gang", vec![new_node("node-0", 1)], gang),
+        None
+    );
+}
+
+fn gang_model(consistency: ConsistencySetup, schedulers: usize) -> OrchestrationModelCfg {
+    let mut model = model(
+        RawState::default()
+            .with_nodes([new_node("node-0", 2)])
+            .with_pods([
+                gang_pod("a-0", "a"),
+                gang_pod("a-1", "a"),
+                gang_pod("b-0", "b"),
+                gang_pod("b-1", "b"),
+            ]),
+        consistency,
+    );
+    model.schedulers = 0;
+    model.scheduler_profiles = vec!["default-scheduler:gang".parse().unwrap(); schedulers];
+    model
+}
+
+test_table! {
+    test_gang_scheduling,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_gang_scheduling(consistency: ConsistencySetup, schedulers: usize) -> OrchestrationModelCfg {
+    // initial state: two pod groups that only fit on the node one at a time
+    // eventually: one group gets scheduled and the other is left pending as a whole
+    gang_model(consistency, schedulers)
+}
+
+test_table_panic! {
+    test_gang_scheduling_stale_views,
+    causal_2(ConsistencySetup::Causal, 2),
+}
+
+fn test_gang_scheduling_stale_views(
+    consistency: ConsistencySetup,
+    schedulers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: two pod groups that only fit on the node one at a time
+    // eventually: schedulers working off stale views each bind part of a different group, leaving
+    // both groups stuck partially scheduled
+    gang_model(consistency, schedulers)
+}
+
+const SHADOW_PROPERTY: &str = "shadow: shadow controllers take the same actions as their primary";
+
+fn shadowed_scheduler(shadow: fn(&Controllers) -> Option<Controllers>) -> OrchestrationModelCfg {
+    let state = RawState::default()
+        .with_nodes([new_node("node-0", 4), new_node("node-1", 4)])
+        .with_pods([
+            bound(new_pod("running", 1, None), "node-1", 0),
+            new_pod("pending", 1, None),
+        ]);
+    let mut model = model(state, ConsistencySetup::Synchronous);
+    model.nodes = 0;
+    model.disabled_actions.insert(ActionKind::ArbitraryStep);
+    model.controller_shadow = Some(shadow);
+    model
+}
+
+#[test]
+fn shadow_scheduler_scoring_differently_diverges() {
+    // the primary spreads the pending pod onto the emptier node, the shadow packs it
+    fn most_allocated(controller: &Controllers) -> Option<Controllers> {
+        match controller {
+            Controllers::Scheduler(_) => Some(Controllers::Scheduler(SchedulerController::new(
+                "default-scheduler:most-allocated".parse().unwrap(),
+            ))),
+            _ => None,
+        }
+    }
+    let checker = shadowed_scheduler(most_allocated)
+        .into_abstract_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    assert!(checker.discovery(SHADOW_PROPERTY).is_some());
+}
+
+#[test]
+fn shadow_scheduler_scoring_the_same_never_diverges() {
+    fn same(controller: &Controllers) -> Option<Controllers> {
+        match controller {
+            Controllers::Scheduler(s) => Some(Controllers::Scheduler(s.clone())),
+            _ => None,
+        }
+    }
+    let checker = shadowed_scheduler(same)
+        .into_abstract_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    assert!(checker.discovery(SHADOW_PROPERTY).is_none());
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use themelios::api::selector::{ListQuery, Operator, Selector};
+use themelios::resources::{Node, Pod, PodPhase};
+use themelios::utils;
+
+fn labels(labels: &[(&str, &str)]) -> BTreeMap<String, String> {
+    labels
+        .iter()
+        .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+        .collect()
+}
+
+#[test]
+fn label_selectors_parse_equality_and_set_based_requirements() {
+    let selector =
+        Selector::parse_labels("tier=web, env != prod,app==a,track in (stable, canary),!old,new")
+            .unwrap();
+    let operators = selector
+        .0
+        .iter()
+        .map(|r| (r.key.as_str(), r.operator, r.values.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        operators,
+        vec![
+            ("tier", Operator::Equals, 1),
+            ("env", Operator::NotEquals, 1),
+            ("app", Operator::Equals, 1),
+            ("track", Operator::In, 2),
+            ("old", Operator::DoesNotExist, 0),
+            ("new", Operator::Exists, 0),
+        ]
+    );
+    assert_eq!(Selector::parse_labels("").unwrap(), Selector::default());
+
+    assert!(Selector::parse_labels("a in (b").is_err());
+    assert!(Selector::parse_labels("a within (b)").is_err());
+    assert!(Selector::parse_labels("a in ()").is_err());
+    assert!(Selector::parse_labels("a b=c").is_err());
+    assert!(Selector::parse_fields("a in (b)").is_err());
+}
+
+#[test]
+fn label_selectors_match_labels() {
+    let selector = Selector::parse_labels("tier=web,env notin (prod),!old").unwrap();
+    assert!(selector.matches(&labels(&[("tier", "web")])));
+    assert!(selector.matches(&labels(&[("tier", "web"), ("env", "dev")])));
+    assert!(!selector.matches(&labels(&[("tier", "web"), ("env", "prod")])));
+    assert!(!selector.matches(&labels(&[("tier", "web"), ("old", "")])));
+    assert!(!selector.matches(&labels(&[])));
+}
+
+#[test]
+fn list_queries_select_pods_by_labels_and_fields() {
+    let mut pod = Pod {
+        metadata: utils::metadata("web".to_owned()),
+        ..Default::default()
+    };
+    pod.metadata.labels = labels(&[("app", "web")]);
+    pod.spec.node_name = Some("node-1".to_owned());
+    pod.status.phase = PodPhase::Running;
+
+    let query = ListQuery::new::<Pod>(
+        Some("app in (web,api)"),
+        Some("spec.nodeName=node-1,status.phase!=Failed,metadata.namespace=default"),
+    )
+    .unwrap();
+    assert!(query.matches(&pod));
+    let query = ListQuery::new::<Pod>(None, Some("status.phase=Pending")).unwrap();
+    assert!(!query.matches(&pod));
+    assert!(ListQuery::default().matches(&pod));
+
+    assert!(ListQuery::new::<Pod>(None, Some("spec.image=nginx")).is_err());
+    assert!(ListQuery::new::<Node>(None, Some("spec.nodeName=node-1")).is_err());
+    assert!(ListQuery::new::<Node>(None, Some("metadata.name=node-1")).is_ok());
+}
@@ -0,0 +1,171 @@
+use stateright::Model;
+use themelios::abstract_model::{Action, Change, ControllerAction};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::Pod;
+use themelios::state::history::{ConsistencySetup, SessionGuarantee};
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, State};
+use themelios::utils;
+
+fn rev(i: usize) -> Revision {
+    Revision::from(vec![i])
+}
+
+fn state_with_pods(pods: usize) -> State {
+    let mut state = State::new(RawState::default(), ConsistencySetup::MonotonicSession);
+    for i in 0..pods {
+        state.push_change(Change {
+            revision: state.max_revision(),
+            operation: ControllerAction::CreatePod(Pod {
+                metadata: utils::metadata(format!("pod-{i}")),
+                ..Default::default()
+            }),
+        });
+    }
+    assert_eq!(state.max_revision(), rev(pods));
+    state
+}
+
+#[test]
+fn monotonic_reads_can_miss_own_writes() {
+    let mut state = state_with_pods(3);
+    state.record_write(0, rev(2));
+    assert_eq!(
+        state.session_revisions(0, Some(&rev(0)), SessionGuarantee::MonotonicReads),
+        vec![rev(1), rev(2), rev(3)]
+    );
+}
+
+#[test]
+fn read_your_writes_includes_own_writes() {
+    let mut state = state_with_pods(3);
+    assert_eq!(
+        state.session_revisions(0, Some(&rev(0)), SessionGuarantee::ReadYourWrites),
+        vec![rev(1), rev(2), rev(3)]
+    );
+
+    state.record_write(0, rev(2));
+    assert_eq!(
+        state.session_revisions(0, Some(&rev(0)), SessionGuarantee::ReadYourWrites),
+        vec![rev(2), rev(3)]
+    );
+    // other controllers' sessions are unaffected
+    assert_eq!(
+        state.session_revisions(1, Some(&rev(0)), SessionGuarantee::ReadYourWrites),
+        vec![rev(1), rev(2), rev(3)]
+    );
+
+    // restarting starts a new session
+    state.reset_session(0);
+    assert_eq!(
+        state.session_revisions(0, Some(&rev(0)), SessionGuarantee::ReadYourWrites),
+        vec![rev(1), rev(2), rev(3)]
+    );
+}
+
+#[test]
+fn writes_from_stale_reads_conflict() {
+    let mut state = state_with_pods(1);
+    let stale = state
+        .view_at(&rev(1))
+        .pods
+        .get_in("default", "pod-0")
+        .unwrap()
+        .clone();
+
+    let mut pod = stale.clone();
+    pod.metadata.labels.insert("app".to_owned(), "a".to_owned());
+    state.push_change(Change {
+        revision: rev(1),
+        operation: ControllerAction::UpdatePod(pod),
+    });
+    assert_eq!(state.max_revision(), rev(2));
+
+    // the second writer read before the first write so has to read again
+    let mut pod = stale;
+    pod.metadata.labels.insert("app".to_owned(), "b".to_owned());
+    state.push_change(Change {
+        revision: rev(1),
+        operation: ControllerAction::UpdatePod(pod),
+    });
+    assert_eq!(state.max_revision(), rev(2));
+    assert_eq!(
+        state
+            .latest()
+            .pods
+            .get_in("default", "pod-0")
+            .unwrap()
+            .metadata
+            .labels["app"],
+        "a"
+    );
+}
+
+#[test]
+fn watch_caches_only_step_on_what_they_have_synced() {
+    let mut model =
+        OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    model.replicaset_controllers = 1;
+    model.watch_caches = true;
+    let model = model.into_abstract_model();
+    let state = model.init_states().pop().unwrap();
+
+    // the cache has to list before the controller can do anything
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&Action::ControllerResync(rev(0), 0)));
+    assert!(!actions
+        .iter()
+        .any(|a| matches!(a, Action::ControllerStep(_, _))));
+
+    let mut state = model
+        .next_state(&state, Action::ControllerResync(rev(0), 0))
+        .unwrap();
+    assert_eq!(state.watch_cache(0), Some(&rev(0)));
+
+    // writes by others aren't seen until another resync
+    state.push_change(Change {
+        revision: rev(0),
+        operation: ControllerAction::CreatePod(Pod {
+            metadata: utils::metadata("pod".to_owned()),
+            ..Default::default()
+        }),
+    });
+    let mut actions = Vec::new();
+    model.actions(&state, &mut actions);
+    assert!(actions.contains(&Action::ControllerStep(rev(0), 0)));
+    assert!(!actions.contains(&Action::ControllerStep(rev(1), 0)));
+    assert!(actions.contains(&Action::ControllerResync(rev(1), 0)));
+}
+
+#[test]
+fn read_your_writes_consistency_can_read_backwards() {
+    let mut model =
+        OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::ReadYourWrites, 0);
+    model.replicaset_controllers = 1;
+    let model = model.into_abstract_model();
+    let guarantee = model.session_guarantee(0);
+    assert_eq!(guarantee, SessionGuarantee::ReadYourWrites);
+
+    let mut state = model.init_states().pop().unwrap();
+    for i in 0..2 {
+        state.push_change(Change {
+            revision: state.max_revision(),
+            operation: ControllerAction::CreatePod(Pod {
+                metadata: utils::metadata(format!("pod-{i}")),
+                ..Default::default()
+            }),
+        });
+    }
+    // reads aren't monotonic
+    assert_eq!(
+        state.session_revisions(0, Some(&rev(2)), guarantee),
+        vec![rev(0), rev(1), rev(2)]
+    );
+    // but never miss the controller's own write
+    state.record_write(0, rev(1));
+    assert_eq!(
+        state.session_revisions(0, Some(&rev(2)), guarantee),
+        vec![rev(1), rev(2)]
+    );
+}
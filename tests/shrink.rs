@@ -0,0 +1,125 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use stateright::Model;
+use themelios::abstract_model::AbstractModel;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::shrink::{shrink, ShrinkError};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+use themelios::trace::Trace;
+use themelios::user_properties;
+use themelios::utils;
+
+fn new_replicaset(name: &str) -> ReplicaSet {
+    let mut rs = ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let labels = BTreeMap::from([("app".to_owned(), name.to_owned())]);
+    rs.spec.selector.match_labels = labels.clone();
+    rs.spec.template.metadata.labels = labels;
+    rs
+}
+
+const PROPERTY: &str = "no web pods";
+
+fn model() -> AbstractModel {
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([new_replicaset("db"), new_replicaset("web")]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    cfg.user_properties = user_properties::parse(
+        r#"always "no web pods": count(pods, metadata.labels["app"] == "web") == 0"#,
+    )
+    .unwrap();
+    cfg.into_abstract_model()
+}
+
+/// A path to the discovery that wanders around before heading for it.
+fn noisy_path(model: &AbstractModel) -> String {
+    let property = model
+        .properties()
+        .into_iter()
+        .find(|p| p.name == PROPERTY)
+        .unwrap();
+    let discovered = |state: &State| !(property.condition)(model, state);
+    let nexts = |state: &State| {
+        let mut actions = Vec::new();
+        model.actions(state, &mut actions);
+        actions
+            .into_iter()
+            .filter_map(|a| model.next_state(state, a))
+            .collect::<Vec<_>>()
+    };
+
+    let mut path = vec![model.init_states().remove(0)];
+    for _ in 0..6 {
+        // keep the web replicaset wanting a pod
+        let next = nexts(path.last().unwrap())
+            .into_iter()
+            .rev()
+            .find(|s| {
+                !discovered(s)
+                    && s.latest()
+                        .replicasets
+                        .get_in("default", "web")
+                        .map_or(false, |rs| rs.spec.replicas >= Some(1))
+            })
+            .unwrap();
+        path.push(next);
+    }
+
+    // then the shortest way to the discovery from there
+    let mut queue = VecDeque::from([path]);
+    while let Some(path) = queue.pop_front() {
+        if discovered(path.last().unwrap()) {
+            return path
+                .iter()
+                .map(|s| stateright::fingerprint(s).to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+        }
+        for next in nexts(path.last().unwrap()) {
+            let mut path = path.clone();
+            path.push(next);
+            queue.push_back(path);
+        }
+    }
+    panic!("no discovery from the path");
+}
+
+#[test]
+fn shrink_finds_a_shorter_path_to_the_discovery() {
+    let model = model();
+    let path = noisy_path(&model);
+    let shrunk = shrink(&model, PROPERTY, &path).unwrap();
+    assert!(shrunk.original_len >= 7);
+    assert!(shrunk.actions.len() < shrunk.original_len);
+    // creating the pod for each replicaset is all it takes
+    assert!(shrunk.actions.len() <= 2, "{:?}", shrunk.actions);
+
+    // the shrunk path can be stepped through
+    let trace = Trace::replay(&model, &shrunk.encoded_path()).unwrap();
+    assert_eq!(trace.steps.len(), shrunk.actions.len() + 1);
+}
+
+#[test]
+fn shrink_needs_a_discovery() {
+    let model = model();
+    let initial = stateright::fingerprint(&model.init_states()[0]).get();
+    assert_eq!(
+        shrink(&model, PROPERTY, &initial.to_string()).unwrap_err(),
+        ShrinkError::NotDiscovered
+    );
+    assert_eq!(
+        shrink(&model, "missing", &initial.to_string()).unwrap_err(),
+        ShrinkError::UnknownProperty("missing".to_owned())
+    );
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use stateright::report::{DiscoveryClassification, ReportData, ReportDiscovery, Reporter};
+use stateright::{Chooser, Model, Property};
+use themelios::abstract_model::{AbstractModel, Action, ActionClass, ActionKind};
+use themelios::model::OrchestrationModelCfg;
+use themelios::report::SeedTracker;
+use themelios::resources::{ReplicaSet, ReplicaSetSpec};
+use themelios::simulation::{
+    simulate_seeds, simulate_swarm, swarm_member, SeedLimits, WeightedChooser,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+
+fn model() -> AbstractModel {
+    let replicaset = ReplicaSet {
+        metadata: utils::metadata("rs".to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut model = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([replicaset]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    model.replicaset_controllers = 1;
+    model.properties.push(Property::sometimes(
+        "a pod is created",
+        |_model, state: &<AbstractModel as Model>::State| !state.latest().pods.is_empty(),
+    ));
+    model.into_abstract_model()
+}
+
+#[derive(Default)]
+struct RecordingReporter {
+    checking: Vec<ReportData>,
+    discoveries: Vec<&'static str>,
+}
+
+impl Reporter<AbstractModel> for RecordingReporter {
+    fn report_checking(&mut self, data: ReportData) {
+        self.checking.push(data);
+    }
+
+    fn report_discoveries(
+        &mut self,
+        discoveries: BTreeMap<&'static str, ReportDiscovery<AbstractModel>>,
+    ) {
+        self.discoveries = discoveries.into_keys().collect();
+    }
+}
+
+#[test]
+fn every_seed_is_simulated_and_reported() {
+    let tracker = SeedTracker::default();
+    let mut reporter = RecordingReporter::default();
+    let limits = SeedLimits {
+        states: 100,
+        max_depth: 10,
+    };
+    let discoveries = simulate_seeds(&model(), 3..7, 2, limits, &tracker, &mut reporter);
+
+    let seeds = tracker.seeds();
+    assert_eq!(
+        seeds.iter().map(|s| s.seed).collect::<Vec<_>>(),
+        [3, 4, 5, 6]
+    );
+    assert!(seeds.iter().all(|s| s.total_states > 0 && s.max_depth > 0));
+
+    // once for each seed and again when they are all done
+    assert_eq!(reporter.checking.len(), 5);
+    let last = reporter.checking.last().unwrap();
+    assert!(last.done);
+    assert_eq!(
+        last.total_states,
+        seeds.iter().map(|s| s.total_states).sum::<usize>()
+    );
+
+    let found = &discoveries["a pod is created"];
+    assert!(matches!(
+        found.classification,
+        DiscoveryClassification::Example
+    ));
+    assert!(reporter.discoveries.contains(&"a pod is created"));
+    assert!(seeds
+        .iter()
+        .any(|s| s.discoveries.contains(&"a pod is created")));
+}
+
+#[test]
+fn swarm_members_vary_the_model() {
+    let model = model();
+    let limits = SeedLimits {
+        states: 100,
+        max_depth: 10,
+    };
+    let members = (0..16)
+        .map(|seed| swarm_member(&model, seed, limits))
+        .collect::<Vec<_>>();
+    for (member, member_limits) in &members {
+        assert!(!member
+            .disabled_actions
+            .contains(&ActionKind::ControllerStep));
+        assert!((5..=10).contains(&member_limits.max_depth));
+        assert_eq!(member_limits.states, 100);
+    }
+    assert!(members
+        .iter()
+        .any(|(a, _)| a.disabled_actions != members[0].0.disabled_actions));
+    // the same seed always gives the same member
+    assert_eq!(
+        swarm_member(&model, 3, limits).0.disabled_actions,
+        members[3].0.disabled_actions
+    );
+}
+
+#[test]
+fn swarm_discoveries_are_merged() {
+    let tracker = SeedTracker::default();
+    let mut reporter = RecordingReporter::default();
+    let limits = SeedLimits {
+        states: 100,
+        max_depth: 10,
+    };
+    let discoveries = simulate_swarm(&model(), 0..8, 2, limits, &tracker, &mut reporter);
+    assert_eq!(tracker.seeds().len(), 8);
+    assert!(discoveries.contains_key("a pod is created"));
+    assert!(reporter.discoveries.contains(&"a pod is created"));
+}
+
+#[test]
+fn weighted_chooser_follows_the_weights() {
+    let model = model();
+    let state = model.init_states().remove(0);
+    let actions = [
+        Action::ControllerStep(state.max_revision(), 0),
+        Action::ControllerRestart(0),
+        Action::AdvanceClock,
+    ];
+    let chooser = WeightedChooser::new(
+        [(ActionClass::Controller, 0), (ActionClass::Fault, 3)]
+            .into_iter()
+            .collect(),
+    );
+    let mut rng = chooser.new_state(7);
+    let chosen = (0..100)
+        .map(|_| chooser.choose_action(&mut rng, &state, &actions))
+        .collect::<Vec<_>>();
+    assert!(chosen.iter().all(|&i| i == 1));
+
+    // with nothing left to weight the choice is uniform again
+    let chooser = WeightedChooser::new([(ActionClass::Controller, 0)].into_iter().collect());
+    let mut rng = chooser.new_state(7);
+    let mut chosen = (0..100)
+        .map(|_| chooser.choose_action(&mut rng, &state, &actions[..1]))
+        .collect::<Vec<_>>();
+    chosen.dedup();
+    assert_eq!(chosen, [0]);
+    assert_eq!("fault".parse(), Ok(ActionClass::Fault));
+    assert_eq!(ActionKind::NodeCrash.class(), ActionClass::Fault);
+}
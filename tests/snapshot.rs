@@ -0,0 +1,158 @@
+use themelios::resources::DeploymentStrategyType;
+use themelios::snapshot::{Snapshot, SnapshotError};
+use themelios::state::revision::Revision;
+
+const DEPLOYMENT: &str = r#"{
+    "apiVersion": "apps/v1",
+    "kind": "Deployment",
+    "metadata": {
+        "name": "web",
+        "namespace": "prod",
+        "resourceVersion": "4821",
+        "creationTimestamp": "2024-01-02T03:04:05Z",
+        "managedFields": [{"manager": "kubectl"}]
+    },
+    "spec": {
+        "replicas": 3,
+        "selector": {"matchLabels": {"app": "web"}},
+        "template": {
+            "metadata": {"labels": {"app": "web"}},
+            "spec": {"containers": [{"name": "web", "image": "nginx"}]}
+        }
+    },
+    "status": {
+        "replicas": 3,
+        "conditions": [{"type": "Frobnicated", "status": "True"}]
+    }
+}"#;
+
+#[test]
+fn lists_of_resources_become_the_state() {
+    let list = format!(
+        r#"{{"apiVersion": "v1", "kind": "List", "items": [
+            {DEPLOYMENT},
+            {{"apiVersion": "networking.k8s.io/v1", "kind": "Ingress", "metadata": {{"name": "web"}}}},
+            {{"apiVersion": "v1", "kind": "ConfigMap", "metadata": {{"name": "config", "creationTimestamp": "2024-01-01T00:00:00Z"}}}}
+        ]}}
+        {{"apiVersion": "v1", "kind": "ConfigMap", "metadata": {{"name": "config"}}}}"#
+    );
+    let snapshot = Snapshot::parse(&list).unwrap();
+
+    let web = snapshot.state.deployments.get_in("prod", "web").unwrap();
+    assert_eq!(web.spec.replicas, 3);
+    assert_eq!(web.metadata.resource_version, Revision::default());
+    // the status had a condition the model doesn't know of
+    assert_eq!(web.status.replicas, 0);
+    assert!(snapshot
+        .state
+        .config_maps
+        .get_in("default", "config")
+        .is_some());
+    assert_eq!(snapshot.state.clock, 1704164645);
+
+    assert_eq!(snapshot.skipped.get("Ingress"), Some(&1));
+    assert_eq!(snapshot.warnings.len(), 2, "{:?}", snapshot.warnings);
+    assert!(snapshot.warnings[0].contains("status of Deployment prod/web"));
+    assert!(snapshot.warnings[1].contains("ConfigMap"));
+}
+
+#[test]
+fn snapshots_must_be_json() {
+    assert!(matches!(
+        Snapshot::parse("items: []"),
+        Err(SnapshotError::Json(_))
+    ));
+    let snapshot = Snapshot::parse("").unwrap();
+    assert_eq!(snapshot.state.deployments.iter().count(), 0);
+    assert!(snapshot.warnings.is_empty());
+}
+
+#[test]
+fn manifests_from_a_config_file_become_the_state() {
+    let config = r#"
+deployment_controllers: 1
+resources:
+  - apiVersion: apps/v1
+    kind: Deployment
+    metadata:
+      name: web
+      labels: {app: web}
+    spec:
+      replicas: 2
+      selector: {matchLabels: {app: web}}
+      strategy: {type: Recreate}
+      template:
+        metadata: {labels: {app: web}}
+        spec: {containers: [{name: web, image: nginx}]}
+"#;
+    let config: serde_yaml::Value = serde_yaml::from_str(config).unwrap();
+    let resources = config["resources"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|r| serde_json::to_value(r).unwrap());
+    let snapshot = Snapshot::from_values(resources);
+
+    let web = snapshot.state.deployments.get_in("default", "web").unwrap();
+    assert_eq!(web.spec.replicas, 2);
+    assert_eq!(web.spec.selector.match_labels["app"], "web");
+    assert_eq!(
+        web.spec.strategy.as_ref().map(|s| s.r#type),
+        Some(DeploymentStrategyType::Recreate)
+    );
+    assert!(snapshot.warnings.is_empty(), "{:?}", snapshot.warnings);
+}
+
+#[test]
+fn directories_of_manifests_become_the_state() {
+    let dir = std::env::temp_dir().join(format!("themelios-manifests-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("web.yaml"),
+        r#"---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 2
+  selector: {matchLabels: {app: web}}
+  template:
+    metadata: {labels: {app: web}}
+    spec: {containers: [{name: web, image: nginx}]}
+---
+apiVersion: batch/v1
+kind: Job
+metadata:
+  name: migrate
+spec:
+  template:
+    spec:
+      restartPolicy: Never
+      containers: [{name: migrate, image: migrate}]
+---
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("node.json"),
+        r#"{"apiVersion": "v1", "kind": "Node", "metadata": {"name": "worker"}}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("README.md"), "not a manifest").unwrap();
+    let snapshot = Snapshot::load_manifests(&dir);
+    std::fs::write(dir.join("broken.yaml"), "kind: [").unwrap();
+    let broken = Snapshot::load_manifests(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let snapshot = snapshot.unwrap();
+    assert!(snapshot.warnings.is_empty(), "{:?}", snapshot.warnings);
+    let web = snapshot.state.deployments.get_in("default", "web").unwrap();
+    assert_eq!(web.spec.selector.match_labels["app"], "web");
+    // the selector is defaulted as the api server would
+    let migrate = snapshot.state.jobs.get_in("default", "migrate").unwrap();
+    assert_eq!(migrate.spec.selector.match_labels["job-name"], "migrate");
+    assert_eq!(migrate.spec.template.metadata.labels["job-name"], "migrate");
+    assert!(snapshot.state.nodes.get("worker").is_some());
+    assert!(matches!(broken, Err(SnapshotError::Yaml(_))));
+}
@@ -3,16 +3,31 @@ use common::test_table;
 use common::test_table_panic;
 use std::collections::BTreeMap;
 use stdext::function_name;
-use themelios::model::OrchestrationModelCfg;
+use themelios::abstract_model::{Change, ControllerAction};
+use themelios::controller::pvbinder::find_matching_volume;
+use themelios::controller::util::new_controller_ref;
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
 use themelios::resources::Container;
 use themelios::resources::Metadata;
+use themelios::resources::PersistentVolume;
+use themelios::resources::PersistentVolumeClaim;
+use themelios::resources::PersistentVolumeClaimSpec;
+use themelios::resources::PersistentVolumeSpec;
 use themelios::resources::Pod;
+use themelios::resources::PodManagementPolicyType;
+use themelios::resources::PodPhase;
 use themelios::resources::PodSpec;
 use themelios::resources::PodTemplateSpec;
+use themelios::resources::ResourceQuantities;
+use themelios::resources::ResourceRequirements;
 use themelios::resources::StatefulSet;
 use themelios::resources::StatefulSetSpec;
+use themelios::resources::StorageClass;
+use themelios::resources::VolumeBindingMode;
 use themelios::state::history::ConsistencySetup;
 use themelios::state::RawState;
+use themelios::state::State;
 use themelios::utils;
 
 mod common;
@@ -28,12 +43,58 @@ fn model(
         initial_state,
         consistency_level: consistency,
         schedulers: controllers,
+        scheduler_profiles: Vec::new(),
         nodes,
+        container_restarts: 0,
+        image_pulls: false,
         replicaset_controllers: 0,
+        replicationcontroller_controllers: 0,
         deployment_controllers: 0,
         statefulset_controllers: controllers,
         job_controllers: 0,
+        cronjob_controllers: 0,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
         podgc_controllers: controllers,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
         properties: Vec::new(),
     }
 }
@@ -112,6 +173,24 @@ test_table! {
 }
 
 // TestStatefulSetAvailable
+fn test_node_partition(consistency: ConsistencySetup, controllers: usize) -> OrchestrationModelCfg {
+    // initial state: statefulset whose pods get scheduled across two nodes, either of which can be
+    // partitioned from the control plane
+    // always: pods on the partitioned node are evicted but not recreated while they may still be
+    // running
+    let statefulset = new_statefulset("test-node-partition", "", 2);
+    let mut model = model([statefulset], 2, consistency, controllers);
+    model.nodelifecycle_controllers = controllers;
+    model.node_partitions = true;
+    model
+}
+
+test_table! {
+    test_node_partition,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
 fn test_statefulset_available(
     consistency: ConsistencySetup,
     controllers: usize,
@@ -164,3 +243,254 @@ fn test_stale_reads(consistency: ConsistencySetup, controllers: usize) -> Orches
 // TestStatefulSetStatusWithPodFail
 // TestAutodeleteOwnerRefs
 // TestStatefulSetStartOrdinal
+
+fn storage(amount: u32) -> ResourceQuantities {
+    let mut quantities = ResourceQuantities::default();
+    quantities
+        .others
+        .insert("storage".to_owned(), amount.into());
+    quantities
+}
+
+fn new_claim_template(name: &str, requested: u32) -> PersistentVolumeClaim {
+    PersistentVolumeClaim {
+        metadata: utils::metadata(name.to_owned()),
+        spec: PersistentVolumeClaimSpec {
+            access_modes: vec!["ReadWriteOnce".to_owned()],
+            resources: ResourceRequirements {
+                requests: Some(storage(requested)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn new_volume(name: &str, capacity: u32) -> PersistentVolume {
+    PersistentVolume {
+        metadata: utils::metadata(name.to_owned()),
+        spec: PersistentVolumeSpec {
+            capacity: storage(capacity),
+            access_modes: vec!["ReadWriteOnce".to_owned()],
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+test_table! {
+    test_volume_binding,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+    resettable_session_1(ConsistencySetup::ResettableSession, 1),
+}
+
+fn test_volume_binding(consistency: ConsistencySetup, controllers: usize) -> OrchestrationModelCfg {
+    // initial state: statefulset with a claim template and volumes, only some of which are big
+    // enough for the claims
+    // always: claims are bound to a single volume that satisfies them
+    // eventually: every claim is bound
+    let mut statefulset = new_statefulset("test-volume-binding", "", 2);
+    statefulset.spec.volume_claim_templates = vec![new_claim_template("data", 2)];
+    let mut m = model([statefulset], 1, consistency, controllers);
+    m.initial_state.set_persistent_volumes([
+        new_volume("small", 1),
+        new_volume("large-1", 2),
+        new_volume("large-2", 3),
+    ]);
+    m.pvbinder_controllers = controllers;
+    m
+}
+
+fn new_storage_class(name: &str, volume_binding_mode: VolumeBindingMode) -> StorageClass {
+    StorageClass {
+        metadata: utils::metadata(name.to_owned()),
+        provisioner: "fake".to_owned(),
+        volume_binding_mode,
+    }
+}
+
+test_table! {
+    test_dynamic_provisioning,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_dynamic_provisioning(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: statefulset with a claim template whose class provisions volumes
+    // eventually: every claim is bound to a provisioned volume
+    let mut statefulset = new_statefulset("test-dynamic-provisioning", "", 1);
+    let mut template = new_claim_template("data", 1);
+    template.spec.storage_class_name = Some("standard".to_owned());
+    statefulset.spec.volume_claim_templates = vec![template];
+    let mut m = model([statefulset], 1, consistency, controllers);
+    m.initial_state
+        .set_storage_classes([new_storage_class("standard", VolumeBindingMode::Immediate)]);
+    m.pvbinder_controllers = controllers;
+    m.provisioner_controllers = controllers;
+    m
+}
+
+test_table! {
+    test_wait_for_first_consumer,
+    synchronous_1(ConsistencySetup::Synchronous, 1),
+    monotonic_session_1(ConsistencySetup::MonotonicSession, 1),
+}
+
+fn test_wait_for_first_consumer(
+    consistency: ConsistencySetup,
+    controllers: usize,
+) -> OrchestrationModelCfg {
+    // initial state: statefulset with a claim template whose class waits for a consumer, and
+    // two nodes to choose from
+    // always: pods run on the node that their volume was provisioned for
+    let mut statefulset = new_statefulset("test-wait-for-first-consumer", "", 1);
+    let mut template = new_claim_template("data", 1);
+    template.spec.storage_class_name = Some("local".to_owned());
+    statefulset.spec.volume_claim_templates = vec![template];
+    let mut m = model([statefulset], 2, consistency, controllers);
+    m.initial_state.set_storage_classes([new_storage_class(
+        "local",
+        VolumeBindingMode::WaitForFirstConsumer,
+    )]);
+    m.pvbinder_controllers = controllers;
+    m.provisioner_controllers = controllers;
+    m
+}
+
+#[test]
+fn volume_matching_picks_smallest_satisfying_volume() {
+    let mut claim = new_claim_template("data", 2);
+    let mut wrong_class = new_volume("wrong-class", 2);
+    wrong_class.spec.storage_class_name = Some("fast".to_owned());
+    let mut read_only = new_volume("read-only", 2);
+    read_only.spec.access_modes = vec!["ReadOnlyMany".to_owned()];
+    let state = RawState::default().with_persistent_volumes([
+        new_volume("small", 1),
+        new_volume("large", 3),
+        new_volume("medium", 2),
+        wrong_class,
+        read_only,
+    ]);
+    let pv = find_matching_volume(&claim, &state).unwrap();
+    assert_eq!(pv.metadata.name, "medium");
+
+    claim.spec.storage_class_name = Some("fast".to_owned());
+    let pv = find_matching_volume(&claim, &state).unwrap();
+    assert_eq!(pv.metadata.name, "wrong-class");
+}
+
+fn new_pod(name: &str, owner: &StatefulSet, phase: PodPhase) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    pod.metadata
+        .owner_references
+        .push(new_controller_ref(&owner.metadata, &StatefulSet::GVK));
+    pod.status.phase = phase;
+    pod
+}
+
+/// Whether the statefulset property holds in the state.
+fn holds(property: &str, state: &State) -> bool {
+    let mut cfg = OrchestrationModelCfg::new(RawState::default(), ConsistencySetup::Synchronous, 0);
+    cfg.statefulset_controllers = 1;
+    let model = cfg.into_abstract_model();
+    let property = model
+        .properties
+        .iter()
+        .find(|p| p.name == property)
+        .unwrap();
+    (property.condition)(&model, state)
+}
+
+#[test]
+fn statefulset_pods_share_no_ordinal() {
+    let property = "sts: at most one pod per ordinal";
+    let sts = new_statefulset("web", "", 2);
+    let pods = [
+        new_pod("web-0", &sts, PodPhase::Running),
+        new_pod("web-1", &sts, PodPhase::Running),
+    ];
+    let state = State::new(
+        RawState::default()
+            .with_statefulsets([sts.clone()])
+            .with_pods(pods.clone()),
+        ConsistencySetup::Synchronous,
+    );
+    assert!(holds(property, &state));
+
+    let duplicate = new_pod("old-web-1", &sts, PodPhase::Running);
+    let state = State::new(
+        RawState::default()
+            .with_statefulsets([sts])
+            .with_pods(pods.into_iter().chain([duplicate])),
+        ConsistencySetup::Synchronous,
+    );
+    assert!(!holds(property, &state));
+}
+
+#[test]
+fn statefulset_pods_are_created_after_their_predecessors() {
+    let property = "sts: pods are created in order under OrderedReady";
+    let sts = new_statefulset("web", "", 2);
+    let mut state = State::new(
+        RawState::default().with_statefulsets([sts.clone()]),
+        ConsistencySetup::Synchronous,
+    );
+    state.push_change(Change {
+        revision: state.max_revision(),
+        operation: ControllerAction::CreatePod(new_pod("web-1", &sts, PodPhase::Pending)),
+    });
+    assert!(!holds(property, &state));
+
+    // the same pods are fine when created in order
+    let mut state = State::new(
+        RawState::default().with_statefulsets([sts.clone()]),
+        ConsistencySetup::Synchronous,
+    );
+    for name in ["web-0", "web-1"] {
+        state.push_change(Change {
+            revision: state.max_revision(),
+            operation: ControllerAction::CreatePod(new_pod(name, &sts, PodPhase::Pending)),
+        });
+    }
+    assert!(holds(property, &state));
+}
+
+#[test]
+fn statefulset_pods_only_run_once_their_predecessor_is_ready() {
+    let property = "sts: pods only run once their predecessor is ready under OrderedReady";
+    let sts = new_statefulset("web", "", 2);
+    let mut state = State::new(
+        RawState::default()
+            .with_statefulsets([sts.clone()])
+            .with_pods([new_pod("web-0", &sts, PodPhase::Pending)]),
+        ConsistencySetup::Synchronous,
+    );
+    state.push_change(Change {
+        revision: state.max_revision(),
+        operation: ControllerAction::CreatePod(new_pod("web-1", &sts, PodPhase::Running)),
+    });
+    // web-0 was never ready when web-1 was created
+    assert!(!holds(property, &state));
+
+    let mut sts = sts;
+    sts.spec.pod_management_policy = PodManagementPolicyType::Parallel;
+    let mut state = State::new(
+        RawState::default()
+            .with_statefulsets([sts.clone()])
+            .with_pods([new_pod("web-0", &sts, PodPhase::Pending)]),
+        ConsistencySetup::Synchronous,
+    );
+    state.push_change(Change {
+        revision: state.max_revision(),
+        operation: ControllerAction::CreatePod(new_pod("web-1", &sts, PodPhase::Running)),
+    });
+    assert!(holds(property, &state));
+}
@@ -0,0 +1,92 @@
+use themelios::events::EventRecording;
+use themelios::model::{OrchestrationModelCfg, DEFAULT_FAIRNESS_BOUND};
+use themelios::resources::Pod;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::trace::{ReplayError, Trace};
+use themelios::utils;
+
+fn model() -> OrchestrationModelCfg {
+    OrchestrationModelCfg {
+        initial_state: RawState::default().with_pods([Pod {
+            metadata: utils::metadata("pod".to_owned()),
+            ..Default::default()
+        }]),
+        consistency_level: ConsistencySetup::Synchronous,
+        schedulers: 1,
+        scheduler_profiles: Vec::new(),
+        nodes: 1,
+        container_restarts: 0,
+        image_pulls: false,
+        replicaset_controllers: 0,
+        replicationcontroller_controllers: 0,
+        deployment_controllers: 0,
+        statefulset_controllers: 0,
+        job_controllers: 0,
+        cronjob_controllers: 0,
+        hpa_controllers: 0,
+        nodelifecycle_controllers: 0,
+        namespace_controllers: 0,
+        endpoints_controllers: 0,
+        pvbinder_controllers: 0,
+        provisioner_controllers: 0,
+        podgc_controllers: 0,
+        controller_upgrade: None,
+        controller_shadow: None,
+        controller_roles: None,
+        rbac_bindings: false,
+        controller_sessions: None,
+        disabled_controllers: Default::default(),
+        controller_scopes: Default::default(),
+        disabled_actions: Default::default(),
+        fair_controllers: Default::default(),
+        fairness_bound: DEFAULT_FAIRNESS_BOUND,
+        replica_merge: Default::default(),
+        compaction: Default::default(),
+        relist_faults: false,
+        watch_caches: false,
+        message_retries: None,
+        rate_limited_retries: false,
+        node_monitor_grace_period: None,
+        node_partitions: false,
+        node_crashes: false,
+        controller_crashes: false,
+        clock_skew: 0,
+        node_drains: false,
+        node_pressure: false,
+        leader_election: false,
+        events: EventRecording::Disabled,
+        quiescence: false,
+        status_writes: false,
+        ownership: false,
+        generations: false,
+        rollout_availability: Default::default(),
+        client_operations: Vec::new(),
+        arbitrary_client: Default::default(),
+        user_properties: Default::default(),
+        property_bundles: None,
+        property_selection: Default::default(),
+        properties: Vec::new(),
+    }
+}
+
+#[test]
+fn empty_path_replays_initial_state() {
+    let model = model().into_abstract_model();
+    let trace = Trace::replay(&model, "").unwrap();
+    assert_eq!(trace.steps.len(), 1);
+    assert_eq!(trace.steps[0].action, None);
+    assert!(trace.steps[0]
+        .resources()
+        .iter()
+        .any(|r| r.kind == "Pod" && r.name == "pod"));
+}
+
+#[test]
+fn invalid_fingerprint_is_rejected() {
+    let model = model().into_abstract_model();
+    assert_eq!(
+        Trace::replay(&model, "123/abc").unwrap_err(),
+        ReplayError::InvalidFingerprint("abc".to_owned())
+    );
+}
@@ -0,0 +1,94 @@
+use themelios::abstract_model::{Change, ControllerAction};
+use themelios::resources::ReplicaSet;
+use themelios::state::history::ConsistencySetup;
+use themelios::state::revision::Revision;
+use themelios::state::{RawState, State};
+use themelios::utils;
+
+fn consistency_levels() -> Vec<ConsistencySetup> {
+    vec![
+        ConsistencySetup::Synchronous,
+        ConsistencySetup::MonotonicSession,
+        ConsistencySetup::ResettableSession,
+        ConsistencySetup::ReadYourWrites,
+        ConsistencySetup::OptimisticLinear,
+        ConsistencySetup::Causal,
+        ConsistencySetup::Eventual(2),
+        ConsistencySetup::Partitioned(3),
+    ]
+}
+
+fn new_replicaset(name: &str) -> ReplicaSet {
+    ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    }
+}
+
+fn state(consistency: ConsistencySetup) -> State {
+    State::new(
+        RawState::default().with_replicasets([new_replicaset("rs-1"), new_replicaset("rs-2")]),
+        consistency,
+    )
+}
+
+/// The replicaset in the latest state, scaled to the given number of replicas.
+fn scaled(state: &State, name: &str, replicas: u32) -> ReplicaSet {
+    let mut rs = state
+        .latest()
+        .replicasets
+        .get_in("default", name)
+        .unwrap()
+        .clone();
+    rs.spec.replicas = Some(replicas);
+    rs
+}
+
+fn replicas(state: &State, name: &str) -> Option<u32> {
+    state
+        .latest()
+        .replicasets
+        .get_in("default", name)
+        .unwrap()
+        .spec
+        .replicas
+}
+
+#[test]
+fn transactions_apply_all_of_their_writes() {
+    for consistency in consistency_levels() {
+        let mut state = state(consistency.clone());
+        let before = state.max_revision();
+        let transaction = ControllerAction::Transaction(vec![
+            ControllerAction::UpdateReplicaSet(scaled(&state, "rs-1", 3)),
+            ControllerAction::UpdateReplicaSet(scaled(&state, "rs-2", 0)),
+        ]);
+        state.push_change(Change {
+            revision: before.clone(),
+            operation: transaction,
+        });
+        assert_ne!(state.max_revision(), before, "{consistency}");
+        assert_eq!(replicas(&state, "rs-1"), Some(3), "{consistency}");
+        assert_eq!(replicas(&state, "rs-2"), Some(0), "{consistency}");
+    }
+}
+
+#[test]
+fn transactions_with_a_failing_write_apply_none_of_them() {
+    for consistency in consistency_levels() {
+        let mut state = state(consistency.clone());
+        let before = state.max_revision();
+        let mut stale = scaled(&state, "rs-2", 0);
+        stale.metadata.resource_version = Revision::from(vec![99]);
+        let transaction = ControllerAction::Transaction(vec![
+            ControllerAction::UpdateReplicaSet(scaled(&state, "rs-1", 3)),
+            ControllerAction::UpdateReplicaSet(stale),
+        ]);
+        state.push_change(Change {
+            revision: before.clone(),
+            operation: transaction,
+        });
+        assert_eq!(state.max_revision(), before, "{consistency}");
+        assert_eq!(replicas(&state, "rs-1"), None, "{consistency}");
+    }
+}
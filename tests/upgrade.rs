@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use stateright::{Checker, Model};
+use themelios::abstract_model::{AbstractModel, ActionKind};
+use themelios::controller::{Controllers, ReplicationManager, SchedulerController};
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{
+    Container, Metadata, Node, NodeStatus, PodSpec, PodTemplateSpec, ReplicaSet, ReplicaSetSpec,
+    ResourceQuantities, ResourceRequirements,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::RawState;
+use themelios::utils;
+
+const REPLICASETS_STABLE: &str = "upgrade: replicasets are stable after a controller upgrade";
+const DEPLOYMENTS_COMPLETE: &str = "upgrade: deployments complete after a controller upgrade";
+
+fn cpu(amount: u32) -> ResourceQuantities {
+    let mut quantities = ResourceQuantities::default();
+    quantities.others.insert("cpu".to_owned(), amount.into());
+    quantities
+}
+
+fn new_node(name: &str) -> Node {
+    Node {
+        metadata: utils::metadata(name.to_owned()),
+        status: NodeStatus {
+            capacity: cpu(4),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn new_replicaset(name: &str, replicas: u32) -> ReplicaSet {
+    let labels = BTreeMap::from([("app".to_owned(), name.to_owned())]);
+    let mut rs = ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(replicas),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    rs.spec.selector.match_labels = labels.clone();
+    rs.spec.template = PodTemplateSpec {
+        metadata: Metadata {
+            labels,
+            ..Default::default()
+        },
+        spec: PodSpec {
+            containers: vec![Container {
+                name: "fake".to_owned(),
+                image: "fake".to_owned(),
+                resources: ResourceRequirements {
+                    requests: Some(cpu(1)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    };
+    rs
+}
+
+/// A replicaset placed by a scheduler across two nodes that run its pods, with the controllers
+/// upgraded by the given hook.
+///
+/// The nodes make the pods ready, which the replicaset controller records, so that it is the last
+/// to write once things settle.
+fn model(upgrade: fn(&Controllers) -> Option<Controllers>) -> AbstractModel {
+    let state = RawState::default()
+        .with_nodes([new_node("node-0"), new_node("node-1")])
+        .with_replicasets([new_replicaset("web", 2)]);
+    let mut cfg = OrchestrationModelCfg::new(state, ConsistencySetup::Synchronous, 0);
+    cfg.replicaset_controllers = 1;
+    cfg.schedulers = 1;
+    cfg.nodes = 2;
+    cfg.container_restarts = 0;
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    cfg.controller_upgrade = Some(upgrade);
+    cfg.into_abstract_model()
+}
+
+#[test]
+fn upgrade_to_a_scheduler_that_packs_pods_still_converges() {
+    // the original spreads the pods over both nodes, the upgrade packs them onto one
+    fn most_allocated(controller: &Controllers) -> Option<Controllers> {
+        match controller {
+            Controllers::Scheduler(_) => Some(Controllers::Scheduler(SchedulerController::new(
+                "default-scheduler:most-allocated".parse().unwrap(),
+            ))),
+            _ => None,
+        }
+    }
+    let checker = model(most_allocated).checker().spawn_bfs().join();
+    assert!(checker.discovery(REPLICASETS_STABLE).is_none());
+    assert!(checker.discovery(DEPLOYMENTS_COMPLETE).is_none());
+}
+
+#[test]
+fn upgrade_to_a_controller_that_stops_managing_replicasets_never_converges() {
+    // the upgrade only manages replication controllers, leaving the replicaset without pods when
+    // taken before it has them
+    fn replication_manager(controller: &Controllers) -> Option<Controllers> {
+        match controller {
+            Controllers::ReplicaSet(_) => {
+                Some(Controllers::ReplicationController(ReplicationManager))
+            }
+            _ => None,
+        }
+    }
+    let checker = model(replication_manager).checker().spawn_bfs().join();
+    assert!(checker.discovery(REPLICASETS_STABLE).is_some());
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use stateright::{Checker, Model};
+use themelios::abstract_model::ActionKind;
+use themelios::model::OrchestrationModelCfg;
+use themelios::resources::{
+    Container, Metadata, PodSpec, PodTemplateSpec, ReplicaSet, ReplicaSetSpec,
+};
+use themelios::state::history::ConsistencySetup;
+use themelios::state::{RawState, State};
+use themelios::user_properties::{self, UserProperty};
+use themelios::utils;
+
+fn new_replicaset(name: &str, replicas: u32) -> ReplicaSet {
+    let labels = BTreeMap::from([("app".to_owned(), name.to_owned())]);
+    let mut rs = ReplicaSet {
+        metadata: utils::metadata(name.to_owned()),
+        spec: ReplicaSetSpec {
+            replicas: Some(replicas),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    rs.spec.selector.match_labels = labels.clone();
+    rs.spec.template = PodTemplateSpec {
+        metadata: Metadata {
+            labels,
+            ..Default::default()
+        },
+        spec: PodSpec {
+            containers: vec![Container {
+                name: "fake".to_owned(),
+                image: "fake".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    };
+    rs
+}
+
+fn property(line: &str) -> UserProperty {
+    user_properties::parse(line).unwrap().remove(0)
+}
+
+fn state() -> State {
+    State::new(
+        RawState::default().with_replicasets([new_replicaset("web", 2), new_replicaset("db", 1)]),
+        ConsistencySetup::Synchronous,
+    )
+}
+
+#[test]
+fn user_properties_parse_one_per_line() {
+    let properties = user_properties::parse(
+        r#"
+        # comments and blank lines are skipped
+
+        always "few replicasets": count(replicasets) <= 2
+        eventually "some pods": count(pods) > 0
+        "#,
+    )
+    .unwrap();
+    assert_eq!(properties.len(), 2);
+    assert_eq!(properties[0].name, "few replicasets");
+    assert_eq!(properties[1].name, "some pods");
+}
+
+#[test]
+fn user_properties_report_where_they_fail_to_parse() {
+    let err = user_properties::parse("always \"ok\": true\nnever \"bad\": true").unwrap_err();
+    assert!(err.starts_with("line 2:"), "{err}");
+    assert!(user_properties::parse("always \"bad\": count(widgets) == 0").is_err());
+    assert!(user_properties::parse("always \"bad\": (1 == 1").is_err());
+    assert!(user_properties::parse("always \"bad\": 1 == 1 1").is_err());
+}
+
+#[test]
+fn user_properties_evaluate_on_the_latest_state() {
+    let state = state();
+    assert!(property(r#"always "p": count(replicasets) == 2"#).holds(&state));
+    assert!(property(r#"always "p": count(replicasets, spec.replicas > 1) == 1"#).holds(&state));
+    assert!(property(r#"always "p": get(replicasets, "web").spec.replicas == 2"#).holds(&state));
+    assert!(
+        property(r#"always "p": all(replicasets, metadata.labels["app"] == metadata.name)"#)
+            .holds(&state)
+    );
+    assert!(property(r#"always "p": any(replicasets, metadata.name == "db")"#).holds(&state));
+    assert!(property(
+        r#"always "p": len(get(replicasets, "web").spec.template.spec.containers) == 1"#
+    )
+    .holds(&state));
+    assert!(property(r#"always "p": 1 + 2 - 3 == 0 && !(1 >= 2) || false"#).holds(&state));
+    // missing fields are null, which is never true
+    assert!(!property(r#"always "p": get(replicasets, "cache").spec.replicas"#).holds(&state));
+    assert!(!property(r#"always "p": get(replicasets, "cache").spec.replicas < 1"#).holds(&state));
+    assert!(property(r#"always "p": get(replicasets, "cache") == null"#).holds(&state));
+}
+
+#[test]
+fn user_properties_are_checked_by_the_model() {
+    let mut cfg = OrchestrationModelCfg::new(
+        RawState::default().with_replicasets([new_replicaset("web", 2)]),
+        ConsistencySetup::Synchronous,
+        0,
+    );
+    cfg.replicaset_controllers = 1;
+    // keep the replicas of the replicaset fixed
+    cfg.disabled_actions.insert(ActionKind::ArbitraryStep);
+    cfg.user_properties = user_properties::parse(
+        r#"
+        always "no more pods than replicas": count(pods) <= 2
+        sometimes "web gets its pods": count(pods, metadata.labels["app"] == "web") == 2
+        always "never any pods": count(pods) == 0
+        "#,
+    )
+    .unwrap();
+    let checker = cfg.into_abstract_model().checker().spawn_bfs().join();
+    assert!(checker.discovery("no more pods than replicas").is_none());
+    assert!(checker.discovery("web gets its pods").is_some());
+    assert!(checker.discovery("never any pods").is_some());
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use themelios::resources::Pod;
+use themelios::serve_cluster::watch::{events, EventType, Watches, WATCH_HISTORY};
+use themelios::state::revision::Revision;
+use themelios::state::StateView;
+use themelios::utils;
+
+fn new_pod(name: &str, resource_version: &str) -> Pod {
+    let mut pod = Pod {
+        metadata: utils::metadata(name.to_owned()),
+        ..Default::default()
+    };
+    pod.metadata.resource_version = Revision::try_from(resource_version).unwrap();
+    pod
+}
+
+fn names(events: Vec<(EventType, Pod)>) -> Vec<(EventType, String)> {
+    events
+        .into_iter()
+        .map(|(t, pod)| (t, pod.metadata.name))
+        .collect()
+}
+
+#[test]
+fn watch_events_take_the_resources_from_before_to_after() {
+    let before = vec![new_pod("a", "1"), new_pod("b", "1"), new_pod("c", "1")];
+    let mut replaced = new_pod("c", "3");
+    replaced.metadata.uid = "other".to_owned();
+    let after = vec![
+        new_pod("a", "1"),
+        new_pod("b", "2"),
+        replaced,
+        new_pod("d", "3"),
+    ];
+    assert_eq!(
+        names(events(&before, &after)),
+        vec![
+            (EventType::Deleted, "c".to_owned()),
+            (EventType::Modified, "b".to_owned()),
+            (EventType::Added, "c".to_owned()),
+            (EventType::Added, "d".to_owned()),
+        ]
+    );
+    assert!(events(&after, &after).is_empty());
+}
+
+#[test]
+fn watches_start_from_the_history_until_it_is_compacted() {
+    let watches = Watches::new(Arc::new(AtomicBool::new(false)));
+    let mut view = StateView::default();
+    for _ in 0..WATCH_HISTORY + 10 {
+        view.revision = view.revision.clone().increment();
+        watches.record(&view);
+        // unchanged states are only kept once
+        watches.record(&view);
+    }
+    let latest = view.revision.clone();
+    assert_eq!(watches.at(&latest).unwrap().revision, latest);
+    assert_eq!(
+        watches
+            .at(&Revision::try_from("50").unwrap())
+            .unwrap()
+            .revision,
+        Revision::try_from("50").unwrap()
+    );
+    assert!(watches.at(&Revision::try_from("10").unwrap()).is_none());
+}
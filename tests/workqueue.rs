@@ -0,0 +1,57 @@
+use themelios::workqueue::{key, WorkQueue, BASE_RETRY_DELAY_SECONDS, MAX_RETRY_DELAY_SECONDS};
+
+#[test]
+fn rate_limited_retries_back_off_until_forgotten() {
+    let mut queue = WorkQueue::default();
+    queue.add_rate_limited("web", 0);
+    assert!(!queue.has_due(BASE_RETRY_DELAY_SECONDS - 1));
+    assert_eq!(queue.take_due(BASE_RETRY_DELAY_SECONDS), vec!["web"]);
+
+    // each failure doubles the delay
+    queue.add_rate_limited("web", 0);
+    assert!(!queue.has_due(2 * BASE_RETRY_DELAY_SECONDS - 1));
+    assert!(queue.has_due(2 * BASE_RETRY_DELAY_SECONDS));
+    assert_eq!(queue.failures("web"), 2);
+
+    queue.take_due(2 * BASE_RETRY_DELAY_SECONDS);
+    queue.forget("web");
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn rate_limited_delays_are_capped() {
+    let mut queue = WorkQueue::default();
+    for _ in 0..20 {
+        queue.add_rate_limited("web", 0);
+        queue.take_due(u64::MAX);
+    }
+    let failures = queue.failures("web");
+    queue.add_rate_limited("web", 0);
+    assert!(!queue.has_due(MAX_RETRY_DELAY_SECONDS - 1));
+    assert!(queue.has_due(MAX_RETRY_DELAY_SECONDS));
+    assert_eq!(queue.failures("web"), failures);
+}
+
+#[test]
+fn requeues_keep_the_earliest_due_time() {
+    let mut queue = WorkQueue::default();
+    queue.add_after("web", 0, 60);
+    queue.add_after("web", 0, 600);
+    queue.add_after("db", 0, 120);
+    assert!(queue.has_waiting(0));
+    assert_eq!(queue.take_due(60), vec!["web"]);
+    assert!(queue.has_waiting(60));
+    assert_eq!(queue.take_due(120), vec!["db"]);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn same_names_in_different_namespaces_back_off_apart() {
+    let mut queue = WorkQueue::default();
+    queue.add_rate_limited(&key("default", "web"), 0);
+    queue.add_rate_limited(&key("default", "web"), 0);
+    queue.add_rate_limited(&key("other", "web"), 0);
+    assert_eq!(queue.failures(&key("default", "web")), 2);
+    assert_eq!(queue.failures(&key("other", "web")), 1);
+    assert_eq!(key("", "node-1"), "node-1");
+}